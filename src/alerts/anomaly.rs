@@ -0,0 +1,279 @@
+//! Behavioral anomaly detection
+//!
+//! Compares what a device does now with its stored history: a port it has
+//! never had open, or activity at an hour of the day it has never been seen
+//! at. Unlike [`super::detect_alerts`], which compares with the last
+//! sighting, anything the device has done before counts as normal. Hours
+//! are only judged once a device has [`ANOMALY_MIN_SIGHTINGS`] sightings,
+//! so new devices are not flagged while their routine is still unknown.
+
+use std::collections::{BTreeSet, HashMap};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, Timelike, Utc};
+use rusqlite::Connection;
+
+use super::types::{Alert, AlertType};
+use crate::config::ANOMALY_MIN_SIGHTINGS;
+use crate::database::DeviceSettings;
+use crate::HostInfo;
+
+/// What a device has done before, by MAC
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeviceBehavior {
+    /// Every port ever seen open
+    pub ports: BTreeSet<u16>,
+    /// Local hours of the day (0-23) the device was seen in
+    pub active_hours: BTreeSet<u32>,
+    /// Stored sightings the hours come from
+    pub sightings: u32,
+}
+
+/// Load the behavior history of every stored device, keyed by MAC
+///
+/// Only scans still in history count; pruned days are forgotten.
+pub fn load_device_behavior(conn: &Connection) -> Result<HashMap<String, DeviceBehavior>> {
+    let mut behavior: HashMap<String, DeviceBehavior> = HashMap::new();
+
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT d.mac, dp.port
+        FROM device_ports dp
+        JOIN devices d ON d.id = dp.device_id
+        "#,
+    )?;
+    let ports = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, u16>(1)?))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("Failed to load device port history")?;
+    for (mac, port) in ports {
+        behavior.entry(mac).or_default().ports.insert(port);
+    }
+
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT d.mac, CAST(strftime('%H', s.scan_time, 'localtime') AS INTEGER) AS hour,
+               COUNT(*)
+        FROM device_history dh
+        JOIN devices d ON d.id = dh.device_id
+        JOIN scans s ON s.id = dh.scan_id
+        WHERE dh.is_online = 1
+        GROUP BY d.mac, hour
+        "#,
+    )?;
+    let hours = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, u32>(1)?,
+                row.get::<_, u32>(2)?,
+            ))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("Failed to load device activity history")?;
+    for (mac, hour, sightings) in hours {
+        let device = behavior.entry(mac).or_default();
+        device.active_hours.insert(hour);
+        device.sightings += sightings;
+    }
+
+    Ok(behavior)
+}
+
+/// Raise an alert for each way a host departs from its history
+///
+/// Hosts with no history at all are new devices, reported elsewhere, and
+/// muted devices are skipped.
+pub fn detect_behavior_anomalies(
+    current_hosts: &[HostInfo],
+    history: &HashMap<String, DeviceBehavior>,
+    settings: &[DeviceSettings],
+    now: DateTime<Local>,
+) -> Vec<Alert> {
+    let utc_now = now.with_timezone(&Utc);
+    let hour = now.hour();
+    let mut alerts = Vec::new();
+
+    for host in current_hosts {
+        let Some(behavior) = history.get(&host.mac) else {
+            continue;
+        };
+        if settings
+            .iter()
+            .any(|s| s.device_mac == host.mac && s.is_muted(utc_now))
+        {
+            continue;
+        }
+        let hostname_str = host.hostname.as_deref().unwrap_or("Unknown");
+
+        let mut new_ports: Vec<u16> = host
+            .open_ports
+            .iter()
+            .filter(|port| !behavior.ports.contains(port))
+            .copied()
+            .collect();
+        new_ports.sort_unstable();
+        new_ports.dedup();
+        for port in new_ports {
+            alerts.push(
+                Alert::new(
+                    AlertType::BehaviorAnomaly,
+                    format!(
+                        "Port {} open on {} ({}) for the first time; it has had {}",
+                        port,
+                        host.ip,
+                        hostname_str,
+                        describe_ports(&behavior.ports)
+                    ),
+                )
+                .with_device(&host.mac, &host.ip)
+                .with_evidence(serde_json::json!({
+                    "anomaly": "new_port",
+                    "port": port,
+                    "known_ports": behavior.ports,
+                })),
+            );
+        }
+
+        if behavior.sightings >= ANOMALY_MIN_SIGHTINGS && !behavior.active_hours.contains(&hour) {
+            alerts.push(
+                Alert::new(
+                    AlertType::BehaviorAnomaly,
+                    format!(
+                        "{} ({}) active at {:02}:00-{:02}:59, never seen at that hour in {} \
+                         sightings (usually {})",
+                        host.ip,
+                        hostname_str,
+                        hour,
+                        hour,
+                        behavior.sightings,
+                        describe_hours(&behavior.active_hours)
+                    ),
+                )
+                .with_device(&host.mac, &host.ip)
+                .with_evidence(serde_json::json!({
+                    "anomaly": "unusual_hour",
+                    "hour": hour,
+                    "active_hours": behavior.active_hours,
+                    "sightings": behavior.sightings,
+                })),
+            );
+        }
+    }
+
+    alerts
+}
+
+fn describe_ports(ports: &BTreeSet<u16>) -> String {
+    if ports.is_empty() {
+        return "no open ports before".to_string();
+    }
+    let list = ports
+        .iter()
+        .map(|p| p.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!("only {} before", list)
+}
+
+/// Active hours as ranges, e.g. "08:00-12:59, 18:00-22:59"
+fn describe_hours(hours: &BTreeSet<u32>) -> String {
+    let mut ranges: Vec<(u32, u32)> = Vec::new();
+    for &hour in hours {
+        match ranges.last_mut() {
+            Some((_, end)) if *end + 1 == hour => *end = hour,
+            _ => ranges.push((hour, hour)),
+        }
+    }
+    ranges
+        .iter()
+        .map(|(start, end)| format!("{:02}:00-{:02}:59", start, end))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{queries, Database};
+    use crate::ScanResult;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_behavior_anomalies() {
+        let mut camera = HostInfo::new(
+            "192.168.1.30".to_string(),
+            "aa:00:00:00:00:30".to_string(),
+            "CAMERA".to_string(),
+            "ARP".to_string(),
+        );
+        camera.open_ports = vec![80, 554];
+
+        let db = Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
+        let result = ScanResult {
+            interface_name: "eth0".to_string(),
+            local_ip: "192.168.1.2".to_string(),
+            local_mac: "AA:BB:CC:DD:EE:FF".to_string(),
+            subnet: "192.168.1.0/24".to_string(),
+            scan_method: "arp".to_string(),
+            arp_discovered: 1,
+            icmp_discovered: 0,
+            total_hosts: 1,
+            scan_duration_ms: 100,
+            active_hosts: vec![camera.clone()],
+            wan: None,
+            ssid: None,
+        };
+        queries::insert_scan(&conn, &result).unwrap();
+        let loaded = load_device_behavior(&conn).unwrap();
+        let stored = &loaded[&camera.mac];
+        assert_eq!(stored.ports, BTreeSet::from([80, 554]));
+        assert_eq!(stored.sightings, 1);
+        assert_eq!(stored.active_hours.len(), 1);
+
+        let history = HashMap::from([(
+            camera.mac.clone(),
+            DeviceBehavior {
+                ports: BTreeSet::from([80, 554]),
+                active_hours: (8..13).chain(18..23).collect(),
+                sightings: 40,
+            },
+        )]);
+        let evening = Local.with_ymd_and_hms(2024, 5, 6, 19, 30, 0).unwrap();
+        let night = Local.with_ymd_and_hms(2024, 5, 7, 3, 10, 0).unwrap();
+
+        // Usual ports at a usual hour
+        assert!(detect_behavior_anomalies(&[camera.clone()], &history, &[], evening).is_empty());
+
+        // A port never seen before, then an unusual hour
+        camera.open_ports.push(23);
+        let alerts = detect_behavior_anomalies(&[camera.clone()], &history, &[], night);
+        assert_eq!(alerts.len(), 2);
+        assert!(alerts
+            .iter()
+            .all(|a| a.alert_type == AlertType::BehaviorAnomaly));
+        assert_eq!(
+            alerts[0].message,
+            "Port 23 open on 192.168.1.30 (Unknown) for the first time; it has had only 80, 554 before"
+        );
+        assert_eq!(
+            alerts[1].message,
+            "192.168.1.30 (Unknown) active at 03:00-03:59, never seen at that hour in 40 \
+             sightings (usually 08:00-12:59, 18:00-22:59)"
+        );
+
+        // Too little history to judge hours; muted devices are skipped
+        let mut young = history.clone();
+        young.get_mut(&camera.mac).unwrap().sightings = ANOMALY_MIN_SIGHTINGS - 1;
+        assert_eq!(
+            detect_behavior_anomalies(&[camera.clone()], &young, &[], night).len(),
+            1
+        );
+        let mut muted = DeviceSettings::new(&camera.mac);
+        muted.muted_until = Some(Utc::now() + chrono::Duration::hours(1));
+        assert!(detect_behavior_anomalies(&[camera], &history, &[muted], night).is_empty());
+    }
+}
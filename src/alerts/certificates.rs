@@ -0,0 +1,231 @@
+//! TLS certificate expiry tracking
+//!
+//! Keeps the certificate each device serves per port, as fetched by
+//! [`crate::scanner::inspect_certificates`], and warns
+//! [`CERT_EXPIRY_WARNING_DAYS`] days before it runs out: NAS, router and
+//! hypervisor admin pages are rarely on anyone's renewal calendar. Each step
+//! raises its own alert, so the 7-day warning is not merged into an unread
+//! 30-day one; a renewed certificate stops them.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+use super::types::{Alert, AlertSeverity, AlertType};
+use crate::config::CERT_EXPIRY_WARNING_DAYS;
+use crate::database::queries::parse_datetime_column;
+use crate::database::DeviceSettings;
+use crate::scanner::TlsCertificate;
+
+/// A certificate as last seen on a device's port
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoredCertificate {
+    #[serde(flatten)]
+    pub certificate: TlsCertificate,
+    /// When this certificate (by fingerprint) was first served on the port
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+}
+
+fn format_time(time: DateTime<Utc>) -> String {
+    time.format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+/// Store the certificates found by a scan at `now`
+///
+/// A different certificate on the same port (a renewal) replaces the old one.
+pub fn record_certificates(
+    conn: &Connection,
+    certificates: &[TlsCertificate],
+    now: DateTime<Utc>,
+) -> Result<()> {
+    let now = format_time(now);
+    for cert in certificates {
+        conn.execute(
+            r#"
+            INSERT INTO device_certificates
+                (mac, port, ip, subject, issuer, not_before, not_after, fingerprint,
+                 first_seen, last_seen)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?9)
+            ON CONFLICT(mac, port) DO UPDATE SET
+                ip = excluded.ip,
+                subject = excluded.subject,
+                issuer = excluded.issuer,
+                not_before = excluded.not_before,
+                not_after = excluded.not_after,
+                first_seen = CASE WHEN fingerprint = excluded.fingerprint
+                    THEN first_seen ELSE excluded.first_seen END,
+                fingerprint = excluded.fingerprint,
+                last_seen = excluded.last_seen
+            "#,
+            params![
+                cert.mac.to_uppercase(),
+                cert.port,
+                cert.ip,
+                cert.subject,
+                cert.issuer,
+                format_time(cert.not_before),
+                format_time(cert.not_after),
+                cert.fingerprint,
+                now,
+            ],
+        )
+        .with_context(|| format!("Failed to save certificate of {}:{}", cert.ip, cert.port))?;
+    }
+    Ok(())
+}
+
+/// Every stored certificate, soonest to expire first
+pub fn load_certificates(conn: &Connection) -> Result<Vec<StoredCertificate>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT mac, ip, port, subject, issuer, not_before, not_after, fingerprint,
+               first_seen, last_seen
+        FROM device_certificates
+        ORDER BY not_after, mac, port
+        "#,
+    )?;
+    let certificates = stmt
+        .query_map([], |row| {
+            Ok(StoredCertificate {
+                certificate: TlsCertificate {
+                    mac: row.get(0)?,
+                    ip: row.get(1)?,
+                    port: row.get(2)?,
+                    subject: row.get(3)?,
+                    issuer: row.get(4)?,
+                    not_before: parse_datetime_column(row.get(5)?, 5)?,
+                    not_after: parse_datetime_column(row.get(6)?, 6)?,
+                    fingerprint: row.get(7)?,
+                },
+                first_seen: parse_datetime_column(row.get(8)?, 8)?,
+                last_seen: parse_datetime_column(row.get(9)?, 9)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("Failed to load certificates")?;
+    Ok(certificates)
+}
+
+/// Raise an alert for each certificate within a warning step of expiry
+///
+/// Expired certificates raise a high alert. Muted devices are skipped.
+pub fn detect_expiring_certificates(
+    certificates: &[TlsCertificate],
+    settings: &[DeviceSettings],
+    now: DateTime<Utc>,
+) -> Vec<Alert> {
+    let mut alerts = Vec::new();
+    for cert in certificates {
+        if settings
+            .iter()
+            .any(|s| s.device_mac.eq_ignore_ascii_case(&cert.mac) && s.is_muted(now))
+        {
+            continue;
+        }
+        let days_left = (cert.not_after - now).num_days();
+        let Some(&step) = CERT_EXPIRY_WARNING_DAYS
+            .iter()
+            .filter(|&&days| days_left < days)
+            .min()
+        else {
+            continue;
+        };
+        let expired = cert.not_after <= now;
+        let when = if expired {
+            format!("expired on {}", cert.not_after.format("%Y-%m-%d"))
+        } else {
+            format!(
+                "expires in {} day{} ({})",
+                days_left,
+                if days_left == 1 { "" } else { "s" },
+                cert.not_after.format("%Y-%m-%d")
+            )
+        };
+        alerts.push(
+            Alert::new(
+                AlertType::CertificateExpiring,
+                format!(
+                    "Certificate {} on {}:{} {}",
+                    cert.subject, cert.ip, cert.port, when
+                ),
+            )
+            .with_device(&cert.mac, &cert.ip)
+            .with_severity(if expired {
+                AlertSeverity::High
+            } else {
+                AlertSeverity::Medium
+            })
+            .with_evidence(serde_json::json!({
+                "port": cert.port,
+                "step_days": if expired { 0 } else { step },
+                "subject": cert.subject,
+                "issuer": cert.issuer,
+                "not_after": cert.not_after,
+                "fingerprint": cert.fingerprint,
+            })),
+        );
+    }
+    alerts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Database;
+    use chrono::Duration;
+
+    fn cert(port: u16, not_after: DateTime<Utc>, fingerprint: &str) -> TlsCertificate {
+        TlsCertificate {
+            mac: "AA:BB:CC:00:00:10".to_string(),
+            ip: "192.168.1.10".to_string(),
+            port,
+            subject: "CN=nas.local".to_string(),
+            issuer: "CN=nas.local".to_string(),
+            not_before: not_after - Duration::days(365),
+            not_after,
+            fingerprint: fingerprint.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_certificate_expiry_alerts() {
+        let now = "2026-10-17T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let at = |days: i64| now + Duration::days(days) + Duration::hours(1);
+        let certs = vec![
+            cert(443, at(90), "a"),
+            cert(5001, at(20), "b"),
+            cert(8006, at(6), "c"),
+            cert(8443, now - Duration::days(2), "d"),
+        ];
+        let alerts = detect_expiring_certificates(&certs, &[], now);
+        assert_eq!(alerts.len(), 3);
+        assert_eq!(
+            alerts[0].message,
+            "Certificate CN=nas.local on 192.168.1.10:5001 expires in 20 days (2026-11-06)"
+        );
+        assert_eq!(alerts[1].evidence.as_ref().unwrap()["step_days"], 7);
+        assert_eq!(alerts[2].severity, AlertSeverity::High);
+        // Each warning step is its own alert
+        assert_ne!(alerts[0].dedupe_key(), alerts[1].dedupe_key());
+
+        let mut muted = DeviceSettings::new("aa:bb:cc:00:00:10");
+        muted.muted_until = Some(now + Duration::days(1));
+        assert!(detect_expiring_certificates(&certs, &[muted], now).is_empty());
+
+        // A renewal replaces the stored certificate and restarts first_seen
+        let db = Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
+        record_certificates(&conn, &certs[..2], now).unwrap();
+        let renewed = cert(5001, at(400), "e");
+        let later = now + Duration::days(3);
+        record_certificates(&conn, std::slice::from_ref(&renewed), later).unwrap();
+        let stored = load_certificates(&conn).unwrap();
+        assert_eq!(stored.len(), 2);
+        assert_eq!(stored[0].certificate.port, 443);
+        assert_eq!(stored[1].certificate.fingerprint, "e");
+        assert_eq!(stored[1].first_seen, later);
+        assert_eq!(stored[0].first_seen, now);
+    }
+}
@@ -2,16 +2,50 @@
 //!
 //! Compares scan results to detect changes and generate alerts
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
+use chrono::Utc;
+
+use super::spoofing::detect_mac_spoofing;
 use super::types::{Alert, AlertSeverity, AlertType, HIGH_RISK_THRESHOLD, SUSPICIOUS_PORTS};
-use crate::database::DeviceRecord;
+use crate::database::{DeviceRecord, DeviceSettings, PortDeviation};
 use crate::HostInfo;
 
-fn append_security_alerts(current_hosts: &[HostInfo], alerts: &mut Vec<Alert>) {
+fn settings_by_mac(settings: &[DeviceSettings]) -> HashMap<&str, &DeviceSettings> {
+    settings
+        .iter()
+        .map(|s| (s.device_mac.as_str(), s))
+        .collect()
+}
+
+/// Drop alerts about devices whose alerts are muted
+fn drop_muted(alerts: &mut Vec<Alert>, settings: &HashMap<&str, &DeviceSettings>) {
+    let now = Utc::now();
+    alerts.retain(|alert| {
+        !alert
+            .device_mac
+            .as_deref()
+            .and_then(|mac| settings.get(mac))
+            .is_some_and(|s| s.is_muted(now))
+    });
+}
+
+fn append_security_alerts(
+    current_hosts: &[HostInfo],
+    settings: &HashMap<&str, &DeviceSettings>,
+    alerts: &mut Vec<Alert>,
+) {
     // Check for high risk devices
     for host in current_hosts {
-        if host.risk_score >= HIGH_RISK_THRESHOLD as u8 {
+        let device_settings = settings.get(host.mac.as_str());
+        if device_settings.is_some_and(|s| !s.risk_alerts_enabled) {
+            continue;
+        }
+        let threshold = device_settings
+            .and_then(|s| s.risk_threshold)
+            .unwrap_or(HIGH_RISK_THRESHOLD as u8);
+
+        if host.risk_score >= threshold {
             let hostname_str = host.hostname.as_deref().unwrap_or("Unknown");
             alerts.push(
                 Alert::new(
@@ -46,18 +80,185 @@ fn append_security_alerts(current_hosts: &[HostInfo], alerts: &mut Vec<Alert>) {
             }
         }
     }
+
+    // Check per-device expected port baselines
+    for host in current_hosts {
+        let Some(expected) = settings
+            .get(host.mac.as_str())
+            .and_then(|s| s.expected_ports.as_deref())
+        else {
+            continue;
+        };
+        if let Some(deviation) = PortDeviation::between(expected, &host.open_ports) {
+            let hostname_str = host.hostname.as_deref().unwrap_or("Unknown");
+            let list = |ports: &[u16]| {
+                ports
+                    .iter()
+                    .map(|p| p.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            let mut changes = Vec::new();
+            if !deviation.unexpected.is_empty() {
+                changes.push(format!("unexpected open {}", list(&deviation.unexpected)));
+            }
+            if !deviation.missing.is_empty() {
+                changes.push(format!("expected but closed {}", list(&deviation.missing)));
+            }
+
+            // Newly opened ports matter more than a service going away
+            let severity = if deviation.unexpected.is_empty() {
+                AlertSeverity::Medium
+            } else {
+                AlertSeverity::High
+            };
+            alerts.push(
+                Alert::new(
+                    AlertType::PortBaselineDeviation,
+                    format!(
+                        "Port baseline deviation on {} ({}): {}",
+                        host.ip,
+                        hostname_str,
+                        changes.join("; ")
+                    ),
+                )
+                .with_device(&host.mac, &host.ip)
+                .with_severity(severity),
+            );
+        }
+    }
+
+    // Check for address conflicts (one alert per contested IP)
+    let mut contested = HashSet::new();
+    for host in current_hosts {
+        if host.conflicting_macs.is_empty() || !contested.insert(host.ip.as_str()) {
+            continue;
+        }
+        let mut macs = vec![host.mac.as_str()];
+        macs.extend(host.conflicting_macs.iter().map(String::as_str));
+        alerts.push(
+            Alert::new(
+                AlertType::IpConflict,
+                format!(
+                    "IP address conflict on {}: claimed by {}",
+                    host.ip,
+                    macs.join(", ")
+                ),
+            )
+            .with_device(&host.mac, &host.ip),
+        );
+    }
+
+    // Check for cloned MACs: one MAC answering on several addresses at once
+    let mut addresses: HashMap<String, Vec<&HostInfo>> = HashMap::new();
+    for host in current_hosts {
+        let claims = addresses.entry(host.mac.to_lowercase()).or_default();
+        if claims.iter().all(|h| h.ip != host.ip) {
+            claims.push(host);
+        }
+    }
+    let mut cloned: Vec<(String, Vec<&HostInfo>)> = addresses
+        .into_iter()
+        .filter(|(_, claims)| claims.len() > 1)
+        .collect();
+    cloned.sort_by(|a, b| a.0.cmp(&b.0));
+    for (mac, claims) in cloned {
+        let ips: Vec<&str> = claims.iter().map(|h| h.ip.as_str()).collect();
+        // Routers answering proxy ARP legitimately do this
+        let router = claims
+            .iter()
+            .any(|h| h.device_type.eq_ignore_ascii_case("router"));
+        let evidence = serde_json::json!({
+            "source": "scan",
+            "mac": mac,
+            "ips": ips,
+            "hosts": claims.iter().map(|h| serde_json::json!({
+                "ip": h.ip,
+                "hostname": h.hostname,
+                "vendor": h.vendor,
+                "device_type": h.device_type,
+                "open_ports": h.open_ports,
+                "ttl": h.ttl,
+            })).collect::<Vec<_>>(),
+        });
+        alerts.push(
+            Alert::new(
+                AlertType::MacCloned,
+                format!(
+                    "MAC {} answers on {} addresses ({}); possible cloned or spoofed MAC",
+                    claims[0].mac,
+                    ips.len(),
+                    ips.join(", ")
+                ),
+            )
+            .with_device(&claims[0].mac, &claims[0].ip)
+            .with_severity(if router {
+                AlertSeverity::Medium
+            } else {
+                AlertSeverity::High
+            })
+            .with_evidence(evidence),
+        );
+    }
+
+    // Check per-device latency thresholds (only devices with an override)
+    for host in current_hosts {
+        let threshold = settings
+            .get(host.mac.as_str())
+            .and_then(|s| s.latency_threshold_ms);
+        if let (Some(threshold), Some(latency)) = (threshold, host.response_time_ms) {
+            if latency > threshold {
+                let hostname_str = host.hostname.as_deref().unwrap_or("Unknown");
+                alerts.push(
+                    Alert::new(
+                        AlertType::HighLatency,
+                        format!(
+                            "High latency on {} ({}): {}ms exceeds {}ms",
+                            host.ip, hostname_str, latency, threshold
+                        ),
+                    )
+                    .with_device(&host.mac, &host.ip),
+                );
+            }
+        }
+    }
 }
 
 /// Detect baseline-independent security alerts when known-device baseline is unavailable.
 pub fn detect_alerts_without_baseline(current_hosts: &[HostInfo]) -> Vec<Alert> {
+    detect_alerts_without_baseline_with_settings(current_hosts, &[])
+}
+
+/// Like [`detect_alerts_without_baseline`], honoring per-device overrides.
+pub fn detect_alerts_without_baseline_with_settings(
+    current_hosts: &[HostInfo],
+    settings: &[DeviceSettings],
+) -> Vec<Alert> {
     let mut alerts = Vec::new();
-    append_security_alerts(current_hosts, &mut alerts);
+    let settings = settings_by_mac(settings);
+    append_security_alerts(current_hosts, &settings, &mut alerts);
+    drop_muted(&mut alerts, &settings);
     alerts
 }
 
 /// Detect alerts by comparing current scan with known devices
 pub fn detect_alerts(known_devices: &[DeviceRecord], current_hosts: &[HostInfo]) -> Vec<Alert> {
+    detect_alerts_with_settings(known_devices, current_hosts, &[])
+}
+
+/// Detect alerts honoring per-device thresholds
+///
+/// Offline alerts are skipped for devices with offline alerting disabled, or
+/// whose last sighting is still within their offline grace period. Muted
+/// devices raise no alerts at all until their mute ends.
+pub fn detect_alerts_with_settings(
+    known_devices: &[DeviceRecord],
+    current_hosts: &[HostInfo],
+    settings: &[DeviceSettings],
+) -> Vec<Alert> {
     let mut alerts = Vec::new();
+    let settings = settings_by_mac(settings);
+    let now = Utc::now();
 
     // Build lookup maps
     let known_macs: HashMap<&str, &DeviceRecord> =
@@ -83,6 +284,14 @@ pub fn detect_alerts(known_devices: &[DeviceRecord], current_hosts: &[HostInfo])
     // Check for offline devices (was online, now not in scan)
     for device in known_devices {
         if !current_macs.contains_key(device.mac.as_str()) {
+            if let Some(device_settings) = settings.get(device.mac.as_str()) {
+                let grace = device_settings.offline_grace_secs.unwrap_or(0) as i64;
+                let missing_for = (now - device.last_seen).num_seconds();
+                if !device_settings.offline_alerts_enabled || missing_for < grace {
+                    continue;
+                }
+            }
+
             let last_ip = device.last_ip.as_deref().unwrap_or("Unknown");
             let hostname = device.hostname.as_deref().unwrap_or("Unknown");
             alerts.push(
@@ -95,7 +304,65 @@ pub fn detect_alerts(known_devices: &[DeviceRecord], current_hosts: &[HostInfo])
         }
     }
 
-    append_security_alerts(current_hosts, &mut alerts);
+    append_security_alerts(current_hosts, &settings, &mut alerts);
+
+    // Check for gateways answered by a different MAC than before (possible ARP spoofing)
+    for host in current_hosts {
+        if !host.device_type.eq_ignore_ascii_case("router") {
+            continue;
+        }
+        let previous = known_devices.iter().find(|d| {
+            d.last_ip.as_deref() == Some(host.ip.as_str())
+                && d.device_type
+                    .as_deref()
+                    .is_some_and(|t| t.eq_ignore_ascii_case("router"))
+                && !current_macs.contains_key(d.mac.as_str())
+        });
+        if let Some(previous) = previous {
+            alerts.push(
+                Alert::new(
+                    AlertType::GatewayMacChanged,
+                    format!(
+                        "Gateway {} changed MAC: {} → {}",
+                        host.ip, previous.mac, host.mac
+                    ),
+                )
+                .with_device(&host.mac, &host.ip)
+                .with_evidence(serde_json::json!({
+                    "ip": host.ip,
+                    "previous_mac": previous.mac,
+                    "mac": host.mac,
+                    "vendor": host.vendor,
+                    "previous_vendor": previous.vendor,
+                })),
+            );
+        }
+    }
+
+    // Check for ports opened since the device's last sighting
+    for host in current_hosts {
+        let Some(known) = known_macs.get(host.mac.as_str()) else {
+            continue;
+        };
+        let mut opened: Vec<u16> = host
+            .open_ports
+            .iter()
+            .filter(|port| !known.open_ports.contains(port))
+            .copied()
+            .collect();
+        opened.sort_unstable();
+        opened.dedup();
+        for port in opened {
+            let hostname_str = host.hostname.as_deref().unwrap_or("Unknown");
+            alerts.push(
+                Alert::new(
+                    AlertType::PortOpened,
+                    format!("Port {} opened on {} ({})", port, host.ip, hostname_str),
+                )
+                .with_device(&host.mac, &host.ip),
+            );
+        }
+    }
 
     // Check for IP changes
     for host in current_hosts {
@@ -118,6 +385,10 @@ pub fn detect_alerts(known_devices: &[DeviceRecord], current_hosts: &[HostInfo])
         }
     }
 
+    // Check for known MACs presenting as a different device
+    alerts.extend(detect_mac_spoofing(known_devices, current_hosts));
+
+    drop_muted(&mut alerts, &settings);
     alerts
 }
 
@@ -138,3 +409,271 @@ pub fn count_alerts_by_type(alerts: &[Alert]) -> HashMap<String, usize> {
     }
     counts
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn known(mac: &str, last_seen_secs_ago: i64) -> DeviceRecord {
+        let last_seen = Utc::now() - Duration::seconds(last_seen_secs_ago);
+        DeviceRecord {
+            id: 1,
+            mac: mac.to_string(),
+            first_seen: last_seen,
+            last_seen,
+            last_ip: Some("192.168.1.20".to_string()),
+            vendor: None,
+            device_type: None,
+            hostname: None,
+            os_guess: None,
+            custom_name: None,
+            notes: None,
+            security_grade: None,
+            tags: Vec::new(),
+            attributes: Default::default(),
+            availability: Default::default(),
+            open_ports: Vec::new(),
+            network_id: None,
+        }
+    }
+
+    fn count(alerts: &[Alert], alert_type: AlertType) -> usize {
+        alerts.iter().filter(|a| a.alert_type == alert_type).count()
+    }
+
+    #[test]
+    fn test_offline_overrides() {
+        let server = known("aa:00:00:00:00:01", 60);
+        let phone = known("aa:00:00:00:00:02", 3600);
+
+        let mut server_settings = DeviceSettings::new(&server.mac);
+        server_settings.offline_grace_secs = Some(120);
+        let mut phone_settings = DeviceSettings::new(&phone.mac);
+        phone_settings.offline_alerts_enabled = false;
+        let settings = vec![server_settings, phone_settings];
+
+        let known_devices = vec![server.clone(), phone];
+        let alerts = detect_alerts_with_settings(&known_devices, &[], &settings);
+        assert_eq!(count(&alerts, AlertType::DeviceWentOffline), 0);
+
+        // Server missing for longer than its grace period is reported
+        let stale_server = known(&server.mac, 300);
+        let alerts =
+            detect_alerts_with_settings(std::slice::from_ref(&stale_server), &[], &settings);
+        assert_eq!(count(&alerts, AlertType::DeviceWentOffline), 1);
+
+        // Unless it is muted, until the mute ends
+        let mut muted = settings.clone();
+        muted[0].muted_until = Some(Utc::now() + Duration::hours(1));
+        let alerts = detect_alerts_with_settings(std::slice::from_ref(&stale_server), &[], &muted);
+        assert_eq!(count(&alerts, AlertType::DeviceWentOffline), 0);
+        muted[0].muted_until = Some(Utc::now() - Duration::minutes(1));
+        let alerts = detect_alerts_with_settings(&[stale_server], &[], &muted);
+        assert_eq!(count(&alerts, AlertType::DeviceWentOffline), 1);
+
+        // No overrides keeps the default immediate behaviour
+        assert_eq!(
+            count(
+                &detect_alerts(&known_devices, &[]),
+                AlertType::DeviceWentOffline
+            ),
+            2
+        );
+    }
+
+    #[test]
+    fn test_risk_and_latency_overrides() {
+        let mut host = HostInfo::new(
+            "192.168.1.30".to_string(),
+            "aa:00:00:00:00:03".to_string(),
+            "SERVER".to_string(),
+            "ARP".to_string(),
+        );
+        host.risk_score = 60;
+        host.response_time_ms = Some(250);
+        let hosts = vec![host.clone()];
+
+        assert_eq!(
+            count(
+                &detect_alerts_without_baseline(&hosts),
+                AlertType::HighRiskDetected
+            ),
+            1
+        );
+        assert_eq!(
+            count(
+                &detect_alerts_without_baseline(&hosts),
+                AlertType::HighLatency
+            ),
+            0
+        );
+
+        let mut settings = DeviceSettings::new(&host.mac);
+        settings.risk_threshold = Some(80);
+        settings.latency_threshold_ms = Some(100);
+        let alerts = detect_alerts_without_baseline_with_settings(&hosts, &[settings.clone()]);
+        assert_eq!(count(&alerts, AlertType::HighRiskDetected), 0);
+        assert_eq!(count(&alerts, AlertType::HighLatency), 1);
+
+        settings.risk_threshold = None;
+        settings.risk_alerts_enabled = false;
+        let alerts = detect_alerts_without_baseline_with_settings(&hosts, &[settings]);
+        assert_eq!(count(&alerts, AlertType::HighRiskDetected), 0);
+    }
+
+    #[test]
+    fn test_expected_ports_baseline() {
+        let mut host = HostInfo::new(
+            "192.168.1.40".to_string(),
+            "aa:00:00:00:00:04".to_string(),
+            "NAS".to_string(),
+            "ARP".to_string(),
+        );
+        host.open_ports = vec![443, 22];
+        let mut settings = DeviceSettings::new(&host.mac);
+
+        // No baseline, no alert
+        let alerts =
+            detect_alerts_without_baseline_with_settings(&[host.clone()], &[settings.clone()]);
+        assert_eq!(count(&alerts, AlertType::PortBaselineDeviation), 0);
+
+        settings.expected_ports = Some(vec![22, 443]);
+        let alerts =
+            detect_alerts_without_baseline_with_settings(&[host.clone()], &[settings.clone()]);
+        assert_eq!(count(&alerts, AlertType::PortBaselineDeviation), 0);
+
+        host.open_ports = vec![22, 445];
+        let alerts =
+            detect_alerts_without_baseline_with_settings(&[host.clone()], &[settings.clone()]);
+        let deviation: Vec<_> = alerts
+            .iter()
+            .filter(|a| a.alert_type == AlertType::PortBaselineDeviation)
+            .collect();
+        assert_eq!(deviation.len(), 1);
+        assert_eq!(deviation[0].severity, AlertSeverity::High);
+        assert!(deviation[0].message.contains("unexpected open 445"));
+        assert!(deviation[0].message.contains("expected but closed 443"));
+
+        host.open_ports = vec![22];
+        let alerts = detect_alerts_without_baseline_with_settings(&[host], &[settings]);
+        let deviation = alerts
+            .iter()
+            .find(|a| a.alert_type == AlertType::PortBaselineDeviation)
+            .unwrap();
+        assert_eq!(deviation.severity, AlertSeverity::Medium);
+    }
+
+    #[test]
+    fn test_gateway_mac_change_and_opened_ports() {
+        let mut router = known("aa:00:00:00:00:10", 60);
+        router.last_ip = Some("192.168.1.1".to_string());
+        router.device_type = Some("ROUTER".to_string());
+        let mut nas = known("aa:00:00:00:00:11", 60);
+        nas.open_ports = vec![22, 445];
+
+        let mut spoofed = HostInfo::new(
+            "192.168.1.1".to_string(),
+            "bb:00:00:00:00:10".to_string(),
+            "ROUTER".to_string(),
+            "ARP".to_string(),
+        );
+        spoofed.vendor = Some("Unknown Vendor".to_string());
+        let mut nas_now = HostInfo::new(
+            "192.168.1.20".to_string(),
+            nas.mac.clone(),
+            "NAS".to_string(),
+            "ARP".to_string(),
+        );
+        nas_now.open_ports = vec![22, 445, 8080];
+
+        let alerts = detect_alerts(&[router.clone(), nas.clone()], &[spoofed, nas_now]);
+        let gateway: Vec<_> = alerts
+            .iter()
+            .filter(|a| a.alert_type == AlertType::GatewayMacChanged)
+            .collect();
+        assert_eq!(gateway.len(), 1);
+        assert_eq!(gateway[0].severity, AlertSeverity::Critical);
+        assert!(gateway[0].message.contains(&router.mac));
+        let opened: Vec<_> = alerts
+            .iter()
+            .filter(|a| a.alert_type == AlertType::PortOpened)
+            .collect();
+        assert_eq!(opened.len(), 1);
+        assert!(opened[0].message.contains("Port 8080"));
+
+        // The known gateway answering as before raises nothing
+        let router_now = HostInfo::new(
+            "192.168.1.1".to_string(),
+            router.mac.clone(),
+            "ROUTER".to_string(),
+            "ARP".to_string(),
+        );
+        let alerts = detect_alerts(&[router], &[router_now]);
+        assert_eq!(count(&alerts, AlertType::GatewayMacChanged), 0);
+        assert_eq!(count(&alerts, AlertType::PortOpened), 0);
+    }
+
+    #[test]
+    fn test_ip_conflict_alerted_once_per_address() {
+        let mut hosts: Vec<HostInfo> = ["aa:00:00:00:00:05", "bb:00:00:00:00:06"]
+            .iter()
+            .map(|mac| {
+                HostInfo::new(
+                    "192.168.1.50".to_string(),
+                    mac.to_string(),
+                    "PC".to_string(),
+                    "ARP".to_string(),
+                )
+            })
+            .collect();
+        crate::network::annotate_ip_conflicts(&mut hosts);
+
+        let alerts = detect_alerts_without_baseline(&hosts);
+        assert_eq!(count(&alerts, AlertType::IpConflict), 1);
+        let conflict = alerts
+            .iter()
+            .find(|a| a.alert_type == AlertType::IpConflict)
+            .unwrap();
+        assert!(conflict
+            .message
+            .contains("aa:00:00:00:00:05, bb:00:00:00:00:06"));
+    }
+
+    #[test]
+    fn test_cloned_mac_alert_carries_evidence() {
+        let hosts: Vec<HostInfo> = ["192.168.1.30", "192.168.1.31"]
+            .iter()
+            .map(|ip| {
+                HostInfo::new(
+                    ip.to_string(),
+                    "AA:00:00:00:00:07".to_string(),
+                    "PC".to_string(),
+                    "ARP".to_string(),
+                )
+            })
+            .collect();
+
+        let alerts = detect_alerts_without_baseline(&hosts);
+        let cloned: Vec<_> = alerts
+            .iter()
+            .filter(|a| a.alert_type == AlertType::MacCloned)
+            .collect();
+        assert_eq!(cloned.len(), 1);
+        assert_eq!(cloned[0].severity, AlertSeverity::High);
+        let evidence = cloned[0].evidence.as_ref().unwrap();
+        assert_eq!(
+            evidence["ips"],
+            serde_json::json!(["192.168.1.30", "192.168.1.31"])
+        );
+
+        // One address per MAC is normal
+        assert_eq!(
+            count(
+                &detect_alerts_without_baseline(&hosts[..1]),
+                AlertType::MacCloned
+            ),
+            0
+        );
+    }
+}
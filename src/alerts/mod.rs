@@ -2,8 +2,20 @@
 //!
 //! Provides change detection and alert generation
 
+pub mod anomaly;
+pub mod certificates;
 pub mod detector;
+pub mod rules;
+pub mod spoofing;
+pub mod ssh_keys;
+pub mod trust;
 pub mod types;
 
+pub use anomaly::*;
+pub use certificates::*;
 pub use detector::*;
+pub use rules::*;
+pub use spoofing::*;
+pub use ssh_keys::*;
+pub use trust::*;
 pub use types::*;
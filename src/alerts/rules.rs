@@ -0,0 +1,610 @@
+//! User-defined alert rules
+//!
+//! Besides the built-in heuristics of [`super::detect_alerts`], users can
+//! store rules that raise an alert of their chosen severity when an event
+//! matches. A rule names the events it reacts to, optionally narrows them
+//! to devices matching a filter expression (the smart group language, e.g.
+//! `tag = camera`) and to events whose measure reaches a threshold, and
+//! lists the channels to notify. Rules are evaluated for every monitor
+//! event and for every completed scan; matches are stored as custom alerts.
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::types::{Alert, AlertSeverity};
+use crate::config::ALERT_COOLDOWN_MINUTES;
+use crate::database::queries::{self, parse_datetime_column, AlertInsert};
+use crate::database::{AlertSeverity as StoredSeverity, AlertType as StoredAlertType};
+use crate::groups::{DeviceFilter, FilterSubject};
+use crate::monitor::NetworkEvent;
+use crate::notify::{notify_alerts, Notification};
+use crate::HostInfo;
+
+/// Event type of each host in a completed scan
+pub const SCAN_HOST_EVENT: &str = "ScanHost";
+
+/// What an alert rule reacts to and what it does, as created or edited
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlertRuleSpec {
+    pub name: String,
+    /// Monitor event kinds (`DeviceWentOffline`), scan alert types
+    /// (`HIGH_RISK`) or `ScanHost`; empty matches every event
+    #[serde(default)]
+    pub event_types: Vec<String>,
+    /// Device filter expression the event's device must match
+    #[serde(default)]
+    pub device_filter: Option<String>,
+    /// Minimum measure of the event (see [`RuleInput::measure`])
+    #[serde(default)]
+    pub threshold: Option<f64>,
+    pub severity: AlertSeverity,
+    /// Notification channels told about a match, by name
+    #[serde(default)]
+    pub notify: Vec<String>,
+    /// Minutes a repeat of an unread match is counted on it instead of
+    /// raising another alert (`ALERT_COOLDOWN_MINUTES` if unset)
+    #[serde(default)]
+    pub cooldown_minutes: Option<u32>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl AlertRuleSpec {
+    pub fn validate(&self) -> Result<()> {
+        if self.name.trim().is_empty() {
+            bail!("Rule name cannot be empty");
+        }
+        if let Some(filter) = &self.device_filter {
+            DeviceFilter::parse(filter)?;
+        }
+        if self.threshold.is_some_and(|t| !t.is_finite()) {
+            bail!("Rule threshold must be a number");
+        }
+        Ok(())
+    }
+}
+
+/// A stored alert rule
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlertRule {
+    pub id: i64,
+    #[serde(flatten)]
+    pub spec: AlertRuleSpec,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Something a rule is checked against: a monitor event or a scan finding
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RuleInput {
+    /// Event kind, scan alert type or [`SCAN_HOST_EVENT`]
+    pub kind: String,
+    pub mac: Option<String>,
+    pub ip: Option<String>,
+    pub hostname: Option<String>,
+    pub vendor: Option<String>,
+    pub device_type: Option<String>,
+    pub risk_score: Option<u8>,
+    /// What a threshold is compared with: the risk score of new devices,
+    /// scanned hosts and scan alerts, the current latency, request count,
+    /// flap count, packet loss, port or hosts found
+    pub measure: Option<f64>,
+}
+
+impl RuleInput {
+    /// Rule input for a monitor event (`None` for scan progress)
+    pub fn from_event(event: &NetworkEvent) -> Option<Self> {
+        let measure = match event {
+            NetworkEvent::ScanProgress { .. } => return None,
+            NetworkEvent::NewDeviceDiscovered { risk_score, .. } => Some(*risk_score as f64),
+            NetworkEvent::LatencyDegraded { current_ms, .. } => Some(*current_ms as f64),
+            NetworkEvent::ArpStormDetected { request_count, .. } => Some(*request_count as f64),
+            NetworkEvent::DeviceFlapping { changes, .. } => Some(*changes as f64),
+            NetworkEvent::InternetDegraded { loss_percent, .. } => Some(*loss_percent),
+            NetworkEvent::UnexpectedPortOpen { port, .. } => Some(*port as f64),
+            NetworkEvent::ScanCompleted { hosts_found, .. } => Some(*hosts_found as f64),
+            _ => None,
+        };
+        let (ip, hostname) = match event {
+            NetworkEvent::NewDeviceDiscovered { ip, hostname, .. }
+            | NetworkEvent::DeviceCameOnline { ip, hostname, .. }
+            | NetworkEvent::UnknownDevicePresent { ip, hostname, .. } => {
+                (Some(ip.clone()), hostname.clone())
+            }
+            NetworkEvent::DeviceWentOffline {
+                last_ip, hostname, ..
+            } => (Some(last_ip.clone()), hostname.clone()),
+            NetworkEvent::ExpectedDeviceMissing { ip, hostname, .. } => {
+                (ip.clone(), hostname.clone())
+            }
+            NetworkEvent::DeviceFlapping { ip, .. }
+            | NetworkEvent::ArpStormDetected { ip, .. }
+            | NetworkEvent::ArpAddressTakeover { ip, .. }
+            | NetworkEvent::IpConflict { ip, .. }
+            | NetworkEvent::UnexpectedPortOpen { ip, .. } => (Some(ip.clone()), None),
+            NetworkEvent::DeviceIpChanged { new_ip, .. } => (Some(new_ip.clone()), None),
+            _ => (None, None),
+        };
+        let (device_type, vendor, risk_score) = match event {
+            NetworkEvent::NewDeviceDiscovered {
+                device_type,
+                vendor,
+                risk_score,
+                ..
+            } => (Some(device_type.clone()), vendor.clone(), Some(*risk_score)),
+            _ => (None, None, None),
+        };
+
+        Some(Self {
+            kind: event.kind().to_string(),
+            mac: event.device_macs().first().map(|mac| mac.to_string()),
+            ip,
+            hostname,
+            vendor,
+            device_type,
+            risk_score,
+            measure,
+        })
+    }
+
+    /// Rule input for a host found by a scan ([`SCAN_HOST_EVENT`])
+    pub fn from_host(host: &HostInfo) -> Self {
+        Self {
+            kind: SCAN_HOST_EVENT.to_string(),
+            mac: Some(host.mac.clone()),
+            ip: Some(host.ip.clone()),
+            hostname: host.hostname.clone(),
+            vendor: host.vendor.clone(),
+            device_type: Some(host.device_type.clone()),
+            risk_score: Some(host.risk_score),
+            measure: Some(host.risk_score as f64),
+        }
+    }
+
+    /// Where the event happened, for alert messages
+    fn location(&self) -> String {
+        match (&self.ip, &self.mac) {
+            (Some(ip), Some(mac)) => format!(" on {} ({})", ip, mac),
+            (Some(ip), None) => format!(" on {}", ip),
+            (None, Some(mac)) => format!(" on {}", mac),
+            (None, None) => String::new(),
+        }
+    }
+}
+
+/// Rule inputs for a completed scan: every host, and every built-in alert
+/// raised for it (typed by its alert type, e.g. `HIGH_RISK`)
+pub fn scan_rule_inputs(hosts: &[HostInfo], alerts: &[Alert]) -> Vec<RuleInput> {
+    let by_mac: HashMap<&str, &HostInfo> = hosts.iter().map(|h| (h.mac.as_str(), h)).collect();
+    let mut inputs: Vec<RuleInput> = hosts.iter().map(RuleInput::from_host).collect();
+    inputs.extend(alerts.iter().map(|alert| {
+        let host = alert
+            .device_mac
+            .as_deref()
+            .and_then(|mac| by_mac.get(mac).copied());
+        RuleInput {
+            kind: alert.alert_type.as_str().to_string(),
+            mac: alert.device_mac.clone(),
+            ip: alert.device_ip.clone(),
+            ..host.map(RuleInput::from_host).unwrap_or_default()
+        }
+    }));
+    inputs
+}
+
+/// A rule that matched, with what to raise and whom to tell
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RuleMatch {
+    pub rule_id: i64,
+    pub rule_name: String,
+    pub severity: AlertSeverity,
+    pub notify: Vec<String>,
+    pub cooldown_minutes: i64,
+    pub event_type: String,
+    pub device_mac: Option<String>,
+    pub device_ip: Option<String>,
+    pub message: String,
+    /// ID of the alert stored for the match
+    #[serde(default)]
+    pub alert_id: Option<i64>,
+}
+
+impl AlertRule {
+    /// Check an input; `subject` describes its device for the device filter
+    pub fn matches(&self, input: &RuleInput, subject: Option<&FilterSubject>) -> bool {
+        let spec = &self.spec;
+        if !spec.enabled {
+            return false;
+        }
+        if !spec.event_types.is_empty()
+            && !spec
+                .event_types
+                .iter()
+                .any(|kind| kind.eq_ignore_ascii_case(&input.kind))
+        {
+            return false;
+        }
+        if let Some(threshold) = spec.threshold {
+            if input.measure.is_none_or(|measure| measure < threshold) {
+                return false;
+            }
+        }
+        match (&spec.device_filter, subject) {
+            (None, _) => true,
+            (Some(_), None) => false,
+            (Some(filter), Some(subject)) => {
+                DeviceFilter::parse(filter).is_ok_and(|filter| filter.matches(subject))
+            }
+        }
+    }
+}
+
+/// Check inputs against every enabled rule
+///
+/// Devices are described for filters by their stored record when there is
+/// one, else by what the input knows about them.
+pub fn evaluate_alert_rules(conn: &Connection, inputs: &[RuleInput]) -> Result<Vec<RuleMatch>> {
+    let rules: Vec<AlertRule> = get_alert_rules(conn)?
+        .into_iter()
+        .filter(|rule| rule.spec.enabled)
+        .collect();
+    if rules.is_empty() || inputs.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let filtered = rules.iter().any(|rule| rule.spec.device_filter.is_some());
+    let risk_scores = if filtered {
+        queries::get_latest_risk_scores(conn)?
+    } else {
+        HashMap::new()
+    };
+
+    let mut matches = Vec::new();
+    for input in inputs {
+        let subject = match (&input.mac, filtered) {
+            (Some(mac), true) => Some(match queries::get_device_by_mac(conn, mac)? {
+                Some(device) => {
+                    FilterSubject::from_device(&device, risk_scores.get(&device.id).copied())
+                }
+                None => FilterSubject {
+                    mac: mac.clone(),
+                    ip: input.ip.clone(),
+                    vendor: input.vendor.clone(),
+                    device_type: input.device_type.clone(),
+                    hostname: input.hostname.clone(),
+                    risk_score: input.risk_score,
+                    ..FilterSubject::default()
+                },
+            }),
+            _ => None,
+        };
+        for rule in &rules {
+            if rule.matches(input, subject.as_ref()) {
+                matches.push(RuleMatch {
+                    rule_id: rule.id,
+                    rule_name: rule.spec.name.clone(),
+                    severity: rule.spec.severity,
+                    notify: rule.spec.notify.clone(),
+                    cooldown_minutes: rule
+                        .spec
+                        .cooldown_minutes
+                        .map_or(ALERT_COOLDOWN_MINUTES, i64::from),
+                    event_type: input.kind.clone(),
+                    device_mac: input.mac.clone(),
+                    device_ip: input.ip.clone(),
+                    message: format!("{}: {}{}", rule.spec.name, input.kind, input.location()),
+                    alert_id: None,
+                });
+            }
+        }
+    }
+    Ok(matches)
+}
+
+pub(super) fn stored_severity(severity: AlertSeverity) -> StoredSeverity {
+    match severity {
+        AlertSeverity::Low => StoredSeverity::Info,
+        AlertSeverity::Medium => StoredSeverity::Warning,
+        AlertSeverity::High => StoredSeverity::Error,
+        AlertSeverity::Critical => StoredSeverity::Critical,
+    }
+}
+
+/// Store matches as custom alerts, returning those stored
+///
+/// A match repeating one still unread and raised within the rule's cooldown
+/// is counted on that alert instead.
+pub fn record_rule_matches(conn: &Connection, matches: Vec<RuleMatch>) -> Result<Vec<RuleMatch>> {
+    let mut stored = Vec::with_capacity(matches.len());
+    for mut rule_match in matches {
+        let evidence = serde_json::json!({
+            "rule_id": rule_match.rule_id,
+            "rule": rule_match.rule_name,
+            "event_type": rule_match.event_type,
+            "notify": rule_match.notify,
+        });
+        let dedupe_key = format!(
+            "rule:{}:{}:{}",
+            rule_match.rule_id,
+            rule_match.event_type,
+            rule_match.device_mac.as_deref().unwrap_or("-")
+        );
+        let alert = AlertInsert {
+            alert_type: StoredAlertType::Custom,
+            device_id: None,
+            device_mac: rule_match.device_mac.as_deref(),
+            device_ip: rule_match.device_ip.as_deref(),
+            dedupe_key: None,
+            message: &rule_match.message,
+            severity: stored_severity(rule_match.severity),
+            evidence: Some(&evidence),
+        };
+        let alert_id = queries::insert_alert_if_not_exists(
+            conn,
+            &alert,
+            &dedupe_key,
+            rule_match.cooldown_minutes,
+        )?;
+        if alert_id.is_some() {
+            rule_match.alert_id = alert_id;
+            stored.push(rule_match);
+        }
+    }
+    Ok(stored)
+}
+
+/// Evaluate the rules, store the alerts they raise and send them to the
+/// notification channels, returning the new ones
+pub fn apply_alert_rules(conn: &Connection, inputs: &[RuleInput]) -> Result<Vec<RuleMatch>> {
+    let matches = evaluate_alert_rules(conn, inputs)?;
+    let stored = record_rule_matches(conn, matches)?;
+    let notifications = stored.iter().map(Notification::from_rule_match).collect();
+    if let Err(e) = notify_alerts(conn, notifications) {
+        tracing::warn!("[ALERTS] Failed to send rule alert notifications: {:#}", e);
+    }
+    Ok(stored)
+}
+
+const RULE_COLUMNS: &str = "id, name, event_types, device_filter, threshold, severity, notify, \
+                            cooldown_minutes, enabled, created_at, updated_at";
+
+fn parse_json_column<T: serde::de::DeserializeOwned>(
+    s: String,
+    column: usize,
+) -> rusqlite::Result<T> {
+    serde_json::from_str(&s).map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(column, rusqlite::types::Type::Text, Box::new(e))
+    })
+}
+
+fn map_rule_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<AlertRule> {
+    Ok(AlertRule {
+        id: row.get(0)?,
+        spec: AlertRuleSpec {
+            name: row.get(1)?,
+            event_types: parse_json_column(row.get(2)?, 2)?,
+            device_filter: row.get(3)?,
+            threshold: row.get(4)?,
+            severity: row.get::<_, String>(5)?.parse().map_err(|e: String| {
+                rusqlite::Error::FromSqlConversionFailure(5, rusqlite::types::Type::Text, e.into())
+            })?,
+            notify: parse_json_column(row.get(6)?, 6)?,
+            cooldown_minutes: row.get(7)?,
+            enabled: row.get(8)?,
+        },
+        created_at: parse_datetime_column(row.get(9)?, 9)?,
+        updated_at: parse_datetime_column(row.get(10)?, 10)?,
+    })
+}
+
+fn rule_params(spec: &AlertRuleSpec) -> Result<(String, Option<String>, String, String)> {
+    let device_filter = spec
+        .device_filter
+        .as_deref()
+        .map(str::trim)
+        .filter(|f| !f.is_empty())
+        .map(String::from);
+    Ok((
+        serde_json::to_string(&spec.event_types)?,
+        device_filter,
+        spec.severity.as_str().to_string(),
+        serde_json::to_string(&spec.notify)?,
+    ))
+}
+
+/// Create an alert rule and return its ID
+pub fn create_alert_rule(conn: &Connection, spec: &AlertRuleSpec) -> Result<i64> {
+    spec.validate()?;
+    let (event_types, device_filter, severity, notify) = rule_params(spec)?;
+    conn.execute(
+        r#"
+        INSERT INTO alert_rules (
+            name, event_types, device_filter, threshold, severity, notify, cooldown_minutes,
+            enabled
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+        "#,
+        params![
+            spec.name.trim(),
+            event_types,
+            device_filter,
+            spec.threshold,
+            severity,
+            notify,
+            spec.cooldown_minutes,
+            spec.enabled
+        ],
+    )
+    .context("Failed to create alert rule")?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Replace an alert rule, returning whether it exists
+pub fn update_alert_rule(conn: &Connection, id: i64, spec: &AlertRuleSpec) -> Result<bool> {
+    spec.validate()?;
+    let (event_types, device_filter, severity, notify) = rule_params(spec)?;
+    let affected = conn
+        .execute(
+            r#"
+            UPDATE alert_rules
+            SET name = ?2, event_types = ?3, device_filter = ?4, threshold = ?5, severity = ?6,
+                notify = ?7, cooldown_minutes = ?8, enabled = ?9, updated_at = datetime('now')
+            WHERE id = ?1
+            "#,
+            params![
+                id,
+                spec.name.trim(),
+                event_types,
+                device_filter,
+                spec.threshold,
+                severity,
+                notify,
+                spec.cooldown_minutes,
+                spec.enabled
+            ],
+        )
+        .context("Failed to update alert rule")?;
+    Ok(affected > 0)
+}
+
+/// Delete an alert rule, returning whether it existed
+pub fn delete_alert_rule(conn: &Connection, id: i64) -> Result<bool> {
+    let affected = conn
+        .execute("DELETE FROM alert_rules WHERE id = ?1", params![id])
+        .context("Failed to delete alert rule")?;
+    Ok(affected > 0)
+}
+
+/// Get an alert rule by ID
+pub fn get_alert_rule(conn: &Connection, id: i64) -> Result<Option<AlertRule>> {
+    conn.query_row(
+        &format!("SELECT {} FROM alert_rules WHERE id = ?1", RULE_COLUMNS),
+        params![id],
+        map_rule_row,
+    )
+    .optional()
+    .context("Failed to get alert rule")
+}
+
+/// Get all alert rules, ordered by name
+pub fn get_alert_rules(conn: &Connection) -> Result<Vec<AlertRule>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM alert_rules ORDER BY name COLLATE NOCASE",
+        RULE_COLUMNS
+    ))?;
+    let rules = stmt
+        .query_map([], map_rule_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("Failed to get alert rules")?;
+    Ok(rules)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alerts::AlertType;
+    use crate::database::Database;
+
+    fn rule(name: &str, event_types: &[&str]) -> AlertRuleSpec {
+        AlertRuleSpec {
+            name: name.to_string(),
+            event_types: event_types.iter().map(|s| s.to_string()).collect(),
+            device_filter: None,
+            threshold: None,
+            severity: AlertSeverity::High,
+            notify: vec!["desktop".to_string()],
+            cooldown_minutes: None,
+            enabled: true,
+        }
+    }
+
+    #[test]
+    fn test_alert_rules() {
+        let db = Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
+
+        let offline = create_alert_rule(
+            &conn,
+            &AlertRuleSpec {
+                device_filter: Some("type = Camera".to_string()),
+                severity: AlertSeverity::Critical,
+                ..rule("Camera offline", &["DeviceWentOffline"])
+            },
+        )
+        .unwrap();
+        create_alert_rule(
+            &conn,
+            &AlertRuleSpec {
+                threshold: Some(70.0),
+                ..rule("Risky host", &["ScanHost", "HIGH_RISK"])
+            },
+        )
+        .unwrap();
+        let mut invalid = rule("Broken", &[]);
+        invalid.device_filter = Some("type =".to_string());
+        assert!(create_alert_rule(&conn, &invalid).is_err());
+        assert_eq!(
+            get_alert_rule(&conn, offline).unwrap().unwrap().spec.notify,
+            vec!["desktop"]
+        );
+
+        // The device filter sees what the event knows about an unstored device
+        let mut camera = HostInfo::new(
+            "192.168.1.30".to_string(),
+            "aa:00:00:00:00:30".to_string(),
+            "CAMERA".to_string(),
+            "ARP".to_string(),
+        );
+        camera.risk_score = 80;
+        let went_offline = NetworkEvent::DeviceWentOffline {
+            mac: camera.mac.clone(),
+            last_ip: camera.ip.clone(),
+            hostname: None,
+            network: None,
+        };
+        let input = RuleInput {
+            device_type: Some("CAMERA".to_string()),
+            ..RuleInput::from_event(&went_offline).unwrap()
+        };
+        let matches = apply_alert_rules(&conn, std::slice::from_ref(&input)).unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].severity, AlertSeverity::Critical);
+        assert_eq!(
+            matches[0].message,
+            "Camera offline: DeviceWentOffline on 192.168.1.30 (aa:00:00:00:00:30)"
+        );
+        // Repeats are not stored again while unread
+        assert!(apply_alert_rules(&conn, std::slice::from_ref(&input))
+            .unwrap()
+            .is_empty());
+
+        // Scans: the host and its built-in alert reach the threshold
+        let alert = Alert::new(AlertType::HighRiskDetected, "High risk")
+            .with_device(camera.mac.clone(), camera.ip.clone());
+        let inputs = scan_rule_inputs(&[camera.clone()], &[alert]);
+        let matches = evaluate_alert_rules(&conn, &inputs).unwrap();
+        let kinds: Vec<&str> = matches.iter().map(|m| m.event_type.as_str()).collect();
+        assert_eq!(kinds, ["ScanHost", "HIGH_RISK"]);
+        camera.risk_score = 40;
+        assert!(
+            evaluate_alert_rules(&conn, &[RuleInput::from_host(&camera)])
+                .unwrap()
+                .is_empty()
+        );
+
+        // Disabled rules are skipped
+        let mut spec = get_alert_rule(&conn, offline).unwrap().unwrap().spec;
+        spec.enabled = false;
+        assert!(update_alert_rule(&conn, offline, &spec).unwrap());
+        assert!(evaluate_alert_rules(&conn, &[input]).unwrap().is_empty());
+        assert_eq!(get_alert_rules(&conn).unwrap().len(), 2);
+        assert!(delete_alert_rule(&conn, offline).unwrap());
+        assert!(!delete_alert_rule(&conn, offline).unwrap());
+    }
+}
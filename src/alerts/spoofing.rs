@@ -0,0 +1,230 @@
+//! MAC spoofing detection
+//!
+//! A MAC address is trivial to copy, but the copy rarely looks like the
+//! original. Two things give it away: a known MAC that suddenly presents as
+//! a different device (another vendor, device type, OS or name than its scan
+//! history), and two hosts answering for the same MAC at once, which passive
+//! ARP sees as the MAC alternating between addresses. Both raise
+//! [`AlertType::MacSpoofing`] with the evidence that triggered it.
+
+use super::types::{Alert, AlertSeverity, AlertType};
+use crate::config::MAC_SPOOFING_MIN_SIGNALS;
+use crate::database::DeviceRecord;
+use crate::HostInfo;
+
+/// Whether a fingerprint value says anything (not empty or "unknown")
+fn known_value(value: Option<&str>) -> Option<&str> {
+    value
+        .map(str::trim)
+        .filter(|v| !v.is_empty() && !v.eq_ignore_ascii_case("unknown"))
+}
+
+/// Fingerprint fields that differ between the stored device and the host
+///
+/// A field only counts when both sides know a value.
+fn changed_signals(known: &DeviceRecord, host: &HostInfo) -> Vec<serde_json::Value> {
+    let fields = [
+        ("vendor", known.vendor.as_deref(), host.vendor.as_deref()),
+        (
+            "device_type",
+            known.device_type.as_deref(),
+            Some(host.device_type.as_str()),
+        ),
+        (
+            "os_guess",
+            known.os_guess.as_deref(),
+            host.os_guess.as_deref(),
+        ),
+        (
+            "hostname",
+            known.hostname.as_deref(),
+            host.hostname.as_deref(),
+        ),
+    ];
+    fields
+        .into_iter()
+        .filter_map(|(field, previous, current)| {
+            let (previous, current) = (known_value(previous)?, known_value(current)?);
+            (!previous.eq_ignore_ascii_case(current)).then(|| {
+                serde_json::json!({
+                    "field": field,
+                    "previous": previous,
+                    "current": current,
+                })
+            })
+        })
+        .collect()
+}
+
+/// Raise an alert for each known MAC now presenting a different fingerprint
+///
+/// One changed field is routine (a renamed host, a refined OS guess), so
+/// at least [`MAC_SPOOFING_MIN_SIGNALS`] must change together.
+pub fn detect_mac_spoofing(
+    known_devices: &[DeviceRecord],
+    current_hosts: &[HostInfo],
+) -> Vec<Alert> {
+    let mut alerts = Vec::new();
+    for host in current_hosts {
+        let Some(known) = known_devices
+            .iter()
+            .find(|d| d.mac.eq_ignore_ascii_case(&host.mac))
+        else {
+            continue;
+        };
+        let signals = changed_signals(known, host);
+        if signals.len() < MAC_SPOOFING_MIN_SIGNALS {
+            continue;
+        }
+        let fields: Vec<&str> = signals.iter().filter_map(|s| s["field"].as_str()).collect();
+        alerts.push(
+            Alert::new(
+                AlertType::MacSpoofing,
+                format!(
+                    "MAC {} on {} no longer matches its history ({} changed); possible spoofed MAC",
+                    host.mac,
+                    host.ip,
+                    fields.join(", ")
+                ),
+            )
+            .with_device(&host.mac, &host.ip)
+            .with_evidence(serde_json::json!({
+                "source": "scan",
+                "mac": host.mac,
+                "ip": host.ip,
+                "previous_ip": known.last_ip,
+                "first_seen": known.first_seen,
+                "last_seen": known.last_seen,
+                "signals": signals,
+            })),
+        );
+    }
+    alerts
+}
+
+/// Alert for a MAC passive ARP saw answering from several addresses at once
+///
+/// `known` is the stored device for the MAC, if any; the address it was
+/// last scanned at is the expected one, and the alert names the other.
+/// Routers answering proxy ARP legitimately do this, so a known router only
+/// raises a medium alert.
+pub fn shared_mac_alert(mac: &str, ips: &[String], known: Option<&DeviceRecord>) -> Alert {
+    let scanned_ip = known.and_then(|d| d.last_ip.as_deref());
+    let intruder_ip = ips
+        .iter()
+        .find(|ip| Some(ip.as_str()) != scanned_ip)
+        .or_else(|| ips.first())
+        .map_or("", String::as_str);
+    let message = match known {
+        Some(device) => format!(
+            "MAC {} of {} answers from {} at once; another host may be using its MAC",
+            mac,
+            device
+                .custom_name
+                .as_deref()
+                .or(device.hostname.as_deref())
+                .or(scanned_ip)
+                .unwrap_or("a known device"),
+            ips.join(" and ")
+        ),
+        None => format!(
+            "MAC {} answers from {} at once; two hosts may share it",
+            mac,
+            ips.join(" and ")
+        ),
+    };
+    let router = known
+        .and_then(|d| d.device_type.as_deref())
+        .is_some_and(|t| t.eq_ignore_ascii_case("router"));
+    Alert::new(AlertType::MacSpoofing, message)
+        .with_device(mac, intruder_ip)
+        .with_severity(if router {
+            AlertSeverity::Medium
+        } else {
+            AlertSeverity::High
+        })
+        .with_evidence(serde_json::json!({
+            "source": "arp",
+            "mac": mac,
+            "ips": ips,
+            "known": known.map(|d| serde_json::json!({
+                "last_ip": d.last_ip,
+                "vendor": d.vendor,
+                "device_type": d.device_type,
+                "hostname": d.hostname,
+                "first_seen": d.first_seen,
+                "last_seen": d.last_seen,
+            })),
+        }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn record(mac: &str) -> DeviceRecord {
+        DeviceRecord {
+            id: 1,
+            mac: mac.to_string(),
+            first_seen: Utc::now(),
+            last_seen: Utc::now(),
+            last_ip: Some("192.168.1.20".to_string()),
+            vendor: Some("Apple".to_string()),
+            device_type: Some("MOBILE".to_string()),
+            hostname: Some("alice-iphone".to_string()),
+            os_guess: Some("iOS".to_string()),
+            custom_name: None,
+            notes: None,
+            security_grade: None,
+            tags: Vec::new(),
+            attributes: Default::default(),
+            availability: Default::default(),
+            open_ports: Vec::new(),
+            network_id: None,
+        }
+    }
+
+    #[test]
+    fn test_mac_spoofing_detection() {
+        let known = vec![record("AA:BB:CC:00:00:20")];
+        let mut host = HostInfo::new(
+            "192.168.1.20".to_string(),
+            "aa:bb:cc:00:00:20".to_string(),
+            "MOBILE".to_string(),
+            "ARP".to_string(),
+        );
+        host.vendor = Some("apple".to_string());
+        host.hostname = Some("alice-iphone-2".to_string());
+        host.os_guess = Some("iOS".to_string());
+
+        // A renamed host alone is routine
+        assert!(detect_mac_spoofing(&known, &[host.clone()]).is_empty());
+
+        // Now a Linux PC with the phone's MAC
+        host.device_type = "PC".to_string();
+        host.os_guess = Some("Linux".to_string());
+        host.hostname = Some("kali".to_string());
+        let alerts = detect_mac_spoofing(&known, &[host]);
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].alert_type, AlertType::MacSpoofing);
+        let evidence = alerts[0].evidence.as_ref().unwrap();
+        assert_eq!(evidence["signals"].as_array().unwrap().len(), 3);
+        assert_eq!(evidence["signals"][0]["field"], "device_type");
+        assert_eq!(evidence["signals"][0]["previous"], "MOBILE");
+
+        // Passive ARP: the stored address is expected, the other is named
+        let ips = vec!["192.168.1.20".to_string(), "192.168.1.77".to_string()];
+        let alert = shared_mac_alert("aa:bb:cc:00:00:20", &ips, Some(&known[0]));
+        assert_eq!(alert.device_ip.as_deref(), Some("192.168.1.77"));
+        assert_eq!(alert.severity, AlertSeverity::High);
+        assert_eq!(
+            alert.message,
+            "MAC aa:bb:cc:00:00:20 of alice-iphone answers from 192.168.1.20 and \
+             192.168.1.77 at once; another host may be using its MAC"
+        );
+        let alert = shared_mac_alert("aa:bb:cc:00:00:21", &ips, None);
+        assert_eq!(alert.device_ip.as_deref(), Some("192.168.1.20"));
+        assert!(alert.evidence.unwrap()["known"].is_null());
+    }
+}
@@ -0,0 +1,222 @@
+//! SSH host key change detection
+//!
+//! Remembers the host key each device's SSH server presents, as collected by
+//! [`crate::scanner::collect_ssh_host_keys`]. A server keeps its key for
+//! life, so a different one on the same device and port means the device
+//! was replaced or reinstalled, or something in between is answering for
+//! it. The new key is remembered once reported, so a legitimate change
+//! alerts only once.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+use super::types::{Alert, AlertType};
+use crate::database::queries::parse_datetime_column;
+use crate::database::DeviceSettings;
+use crate::scanner::SshHostKey;
+
+/// A host key as last seen on a device's port
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoredSshHostKey {
+    #[serde(flatten)]
+    pub key: SshHostKey,
+    /// When this key was first presented on the port
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+}
+
+fn format_time(time: DateTime<Utc>) -> String {
+    time.format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+/// Store the host keys collected by a scan at `now`
+///
+/// A different key on the same port replaces the old one.
+pub fn record_ssh_host_keys(
+    conn: &Connection,
+    keys: &[SshHostKey],
+    now: DateTime<Utc>,
+) -> Result<()> {
+    let now = format_time(now);
+    for key in keys {
+        conn.execute(
+            r#"
+            INSERT INTO device_ssh_keys
+                (mac, port, ip, banner, key_type, fingerprint, first_seen, last_seen)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?7)
+            ON CONFLICT(mac, port) DO UPDATE SET
+                ip = excluded.ip,
+                banner = excluded.banner,
+                key_type = excluded.key_type,
+                first_seen = CASE WHEN fingerprint = excluded.fingerprint
+                    THEN first_seen ELSE excluded.first_seen END,
+                fingerprint = excluded.fingerprint,
+                last_seen = excluded.last_seen
+            "#,
+            params![
+                key.mac.to_uppercase(),
+                key.port,
+                key.ip,
+                key.banner,
+                key.key_type,
+                key.fingerprint,
+                now,
+            ],
+        )
+        .with_context(|| format!("Failed to save SSH host key of {}:{}", key.ip, key.port))?;
+    }
+    Ok(())
+}
+
+/// Every stored host key, by device and port
+pub fn load_ssh_host_keys(conn: &Connection) -> Result<Vec<StoredSshHostKey>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT mac, ip, port, banner, key_type, fingerprint, first_seen, last_seen
+        FROM device_ssh_keys
+        ORDER BY mac, port
+        "#,
+    )?;
+    let keys = stmt
+        .query_map([], |row| {
+            Ok(StoredSshHostKey {
+                key: SshHostKey {
+                    mac: row.get(0)?,
+                    ip: row.get(1)?,
+                    port: row.get(2)?,
+                    banner: row.get(3)?,
+                    key_type: row.get(4)?,
+                    fingerprint: row.get(5)?,
+                },
+                first_seen: parse_datetime_column(row.get(6)?, 6)?,
+                last_seen: parse_datetime_column(row.get(7)?, 7)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("Failed to load SSH host keys")?;
+    Ok(keys)
+}
+
+/// Raise an alert for each port now presenting a different host key
+///
+/// Ports seen for the first time have nothing to compare with and are
+/// only recorded. Muted devices are skipped.
+pub fn detect_ssh_host_key_changes(
+    stored: &[StoredSshHostKey],
+    current: &[SshHostKey],
+    settings: &[DeviceSettings],
+    now: DateTime<Utc>,
+) -> Vec<Alert> {
+    let mut alerts = Vec::new();
+    for key in current {
+        let Some(previous) = stored
+            .iter()
+            .find(|s| s.key.port == key.port && s.key.mac.eq_ignore_ascii_case(&key.mac))
+        else {
+            continue;
+        };
+        if previous.key.fingerprint == key.fingerprint
+            || settings
+                .iter()
+                .any(|s| s.device_mac.eq_ignore_ascii_case(&key.mac) && s.is_muted(now))
+        {
+            continue;
+        }
+        alerts.push(
+            Alert::new(
+                AlertType::SshHostKeyChanged,
+                format!(
+                    "SSH host key of {}:{} changed: {} {} (since {}) is now {} {}; device \
+                     replaced or possible man-in-the-middle",
+                    key.ip,
+                    key.port,
+                    previous.key.key_type,
+                    previous.key.fingerprint,
+                    previous.first_seen.format("%Y-%m-%d"),
+                    key.key_type,
+                    key.fingerprint
+                ),
+            )
+            .with_device(&key.mac, &key.ip)
+            .with_evidence(serde_json::json!({
+                "port": key.port,
+                "previous_key_type": previous.key.key_type,
+                "previous_fingerprint": previous.key.fingerprint,
+                "previous_banner": previous.key.banner,
+                "known_since": previous.first_seen,
+                "key_type": key.key_type,
+                "fingerprint": key.fingerprint,
+                "banner": key.banner,
+            })),
+        );
+    }
+    alerts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Database;
+    use chrono::Duration;
+
+    fn key(fingerprint: &str) -> SshHostKey {
+        SshHostKey {
+            mac: "aa:bb:cc:00:00:22".to_string(),
+            ip: "192.168.1.22".to_string(),
+            port: 22,
+            banner: Some("SSH-2.0-OpenSSH_9.6".to_string()),
+            key_type: "ssh-ed25519".to_string(),
+            fingerprint: fingerprint.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_ssh_host_key_change() {
+        let db = Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
+        let now = "2026-10-17T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+
+        // First sighting: nothing to compare with
+        let original = key("SHA256:original");
+        assert!(
+            detect_ssh_host_key_changes(&[], std::slice::from_ref(&original), &[], now).is_empty()
+        );
+        record_ssh_host_keys(&conn, std::slice::from_ref(&original), now).unwrap();
+
+        let stored = load_ssh_host_keys(&conn).unwrap();
+        assert_eq!(stored[0].key.mac, "AA:BB:CC:00:00:22");
+        assert!(detect_ssh_host_key_changes(&stored, &[original], &[], now).is_empty());
+
+        let mut replaced = key("SHA256:replaced");
+        replaced.key_type = "ssh-rsa".to_string();
+        let alerts = detect_ssh_host_key_changes(&stored, &[replaced.clone()], &[], now);
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].alert_type, AlertType::SshHostKeyChanged);
+        assert_eq!(
+            alerts[0].message,
+            "SSH host key of 192.168.1.22:22 changed: ssh-ed25519 SHA256:original (since \
+             2026-10-17) is now ssh-rsa SHA256:replaced; device replaced or possible \
+             man-in-the-middle"
+        );
+        assert_eq!(
+            alerts[0].dedupe_key(),
+            "ssh-host-key:aa:bb:cc:00:00:22:22:SHA256:replaced"
+        );
+
+        let mut muted = DeviceSettings::new("AA:BB:CC:00:00:22");
+        muted.muted_until = Some(now + Duration::hours(1));
+        assert!(
+            detect_ssh_host_key_changes(&stored, &[replaced.clone()], &[muted], now).is_empty()
+        );
+
+        // Once recorded, the new key is the known one
+        let later = now + Duration::days(1);
+        record_ssh_host_keys(&conn, &[replaced.clone()], later).unwrap();
+        let stored = load_ssh_host_keys(&conn).unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].first_seen, later);
+        assert!(detect_ssh_host_key_changes(&stored, &[replaced], &[], later).is_empty());
+    }
+}
@@ -0,0 +1,270 @@
+//! Known-device allowlist
+//!
+//! With untrusted-device alerts on, every device on the network that the
+//! user has not marked trusted raises a high (or critical) alert instead of
+//! the usual medium "new device" one. Trusting every current device once
+//! turns the app into an "is someone on my Wi-Fi" alarm. Both scans and the
+//! background monitor raise them; repeats while the device stays merge into
+//! the unread alert like any other.
+
+use std::collections::HashSet;
+
+use anyhow::{bail, Result};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+use super::rules::stored_severity;
+use super::types::{Alert, AlertSeverity, AlertType};
+use crate::database::queries::{self, AlertInsert, SETTING_UNTRUSTED_DEVICE_ALERTS};
+use crate::database::{AlertType as StoredAlertType, DeviceSettings};
+use crate::notify::{notify_alerts, Notification};
+use crate::HostInfo;
+
+/// How devices missing from the allowlist are reported
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UntrustedDeviceAlerts {
+    #[serde(default)]
+    pub enabled: bool,
+    /// High or Critical
+    #[serde(default = "default_severity")]
+    pub severity: AlertSeverity,
+    /// Send the alerts to notification channels too
+    #[serde(default = "default_notify")]
+    pub notify: bool,
+}
+
+fn default_severity() -> AlertSeverity {
+    AlertSeverity::High
+}
+
+fn default_notify() -> bool {
+    true
+}
+
+impl Default for UntrustedDeviceAlerts {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            severity: default_severity(),
+            notify: default_notify(),
+        }
+    }
+}
+
+impl UntrustedDeviceAlerts {
+    fn validate(&self) -> Result<()> {
+        if self.severity < AlertSeverity::High {
+            bail!("Untrusted device alerts must be High or Critical");
+        }
+        Ok(())
+    }
+}
+
+/// Load the untrusted-device alert mode (off if never saved)
+pub fn load_untrusted_device_alerts(conn: &Connection) -> Result<UntrustedDeviceAlerts> {
+    Ok(queries::get_json_setting(conn, SETTING_UNTRUSTED_DEVICE_ALERTS)?.unwrap_or_default())
+}
+
+/// Validate and persist the untrusted-device alert mode
+pub fn save_untrusted_device_alerts(conn: &Connection, mode: &UntrustedDeviceAlerts) -> Result<()> {
+    mode.validate()?;
+    queries::set_json_setting(conn, SETTING_UNTRUSTED_DEVICE_ALERTS, mode)
+}
+
+/// Raise an alert for every host not marked trusted
+///
+/// The untrusted alert replaces a "new device" alert for the same MAC.
+/// Returns `alerts` unchanged when the mode is off.
+pub fn escalate_untrusted_devices(
+    mut alerts: Vec<Alert>,
+    current_hosts: &[HostInfo],
+    settings: &[DeviceSettings],
+    mode: &UntrustedDeviceAlerts,
+) -> Vec<Alert> {
+    if !mode.enabled {
+        return alerts;
+    }
+
+    let trusted: HashSet<String> = settings
+        .iter()
+        .filter(|s| s.trusted)
+        .map(|s| s.device_mac.to_lowercase())
+        .collect();
+    let mut escalated = HashSet::new();
+    for host in current_hosts {
+        let mac = host.mac.to_lowercase();
+        if trusted.contains(&mac) || !escalated.insert(mac) {
+            continue;
+        }
+        let hostname_str = host.hostname.as_deref().unwrap_or("Unknown");
+        let vendor_str = host.vendor.as_deref().unwrap_or("unknown vendor");
+        alerts.push(
+            Alert::new(
+                AlertType::UntrustedDevice,
+                format!(
+                    "Untrusted device on the network: {} ({}, {})",
+                    host.ip, hostname_str, vendor_str
+                ),
+            )
+            .with_device(&host.mac, &host.ip)
+            .with_severity(mode.severity),
+        );
+    }
+
+    alerts.retain(|alert| {
+        alert.alert_type != AlertType::NewDeviceDiscovered
+            || !alert
+                .device_mac
+                .as_ref()
+                .is_some_and(|mac| escalated.contains(&mac.to_lowercase()))
+    });
+    alerts
+}
+
+/// Store an alert for a newly discovered host that is not trusted
+///
+/// For the monitor, which sees new devices between scans. Goes through the
+/// same dedupe as scan alerts, so a host showing up again while its alert is
+/// unread is counted on it instead. Returns the id of a newly stored alert.
+pub fn record_untrusted_device(conn: &Connection, host: &HostInfo) -> Result<Option<i64>> {
+    let mode = load_untrusted_device_alerts(conn)?;
+    if !mode.enabled {
+        return Ok(None);
+    }
+    let settings = queries::get_all_device_settings(conn)?;
+    let hosts = std::slice::from_ref(host);
+    let Some(alert) = escalate_untrusted_devices(Vec::new(), hosts, &settings, &mode).pop() else {
+        return Ok(None);
+    };
+
+    let insert = AlertInsert {
+        alert_type: StoredAlertType::UntrustedDevice,
+        device_id: None,
+        device_mac: alert.device_mac.as_deref(),
+        device_ip: alert.device_ip.as_deref(),
+        dedupe_key: None,
+        message: &alert.message,
+        severity: stored_severity(alert.severity),
+        evidence: alert.evidence.as_ref(),
+    };
+    let alert_id = queries::insert_alert_if_not_exists(
+        conn,
+        &insert,
+        &alert.dedupe_key(),
+        alert.alert_type.cooldown_minutes(),
+    )?;
+    if let (Some(id), true) = (alert_id, mode.notify) {
+        if let Err(e) = notify_alerts(conn, vec![Notification::from_alert(&alert, Some(id))]) {
+            tracing::warn!("[ALERTS] Failed to send untrusted device notification: {:#}", e);
+        }
+    }
+    Ok(alert_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alerts::detect_alerts_with_settings;
+    use crate::database::Database;
+
+    fn host(ip: &str, mac: &str) -> HostInfo {
+        HostInfo::new(
+            ip.to_string(),
+            mac.to_string(),
+            "UNKNOWN".to_string(),
+            "ARP".to_string(),
+        )
+    }
+
+    #[test]
+    fn test_untrusted_device_escalation() {
+        let hosts = vec![
+            host("192.168.1.10", "AA:BB:CC:00:00:10"),
+            host("192.168.1.66", "AA:BB:CC:00:00:66"),
+        ];
+        let mut laptop = DeviceSettings::new("aa:bb:cc:00:00:10");
+        laptop.trusted = true;
+        let settings = vec![laptop];
+        let detected = detect_alerts_with_settings(&[], &hosts, &settings);
+
+        // Off by default: only the usual new-device alerts
+        let off = escalate_untrusted_devices(
+            detected.clone(),
+            &hosts,
+            &settings,
+            &UntrustedDeviceAlerts::default(),
+        );
+        assert_eq!(off.len(), 2);
+
+        let mode = UntrustedDeviceAlerts {
+            enabled: true,
+            severity: AlertSeverity::Critical,
+            notify: false,
+        };
+        let alerts = escalate_untrusted_devices(detected, &hosts, &settings, &mode);
+        assert_eq!(alerts.len(), 2);
+        let untrusted: Vec<_> = alerts
+            .iter()
+            .filter(|a| a.alert_type == AlertType::UntrustedDevice)
+            .collect();
+        assert_eq!(untrusted.len(), 1);
+        assert_eq!(
+            untrusted[0].device_mac.as_deref(),
+            Some("AA:BB:CC:00:00:66")
+        );
+        assert_eq!(untrusted[0].severity, AlertSeverity::Critical);
+        // The trusted laptop keeps its plain new-device alert
+        assert!(alerts.iter().any(|a| {
+            a.alert_type == AlertType::NewDeviceDiscovered
+                && a.device_mac.as_deref() == Some("AA:BB:CC:00:00:10")
+        }));
+
+        let db = Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
+        assert_eq!(
+            load_untrusted_device_alerts(&conn).unwrap(),
+            UntrustedDeviceAlerts::default()
+        );
+        save_untrusted_device_alerts(&conn, &mode).unwrap();
+        assert_eq!(load_untrusted_device_alerts(&conn).unwrap(), mode);
+        let medium = UntrustedDeviceAlerts {
+            severity: AlertSeverity::Medium,
+            ..mode
+        };
+        assert!(save_untrusted_device_alerts(&conn, &medium).is_err());
+    }
+
+    #[test]
+    fn test_record_untrusted_device_dedupes() {
+        let db = Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
+        let stranger = host("192.168.1.66", "AA:BB:CC:00:00:66");
+        let laptop = host("192.168.1.10", "AA:BB:CC:00:00:10");
+        queries::set_device_trusted(&conn, "aa:bb:cc:00:00:10", true).unwrap();
+
+        // Off by default
+        assert_eq!(record_untrusted_device(&conn, &stranger).unwrap(), None);
+
+        let mode = UntrustedDeviceAlerts {
+            enabled: true,
+            severity: AlertSeverity::High,
+            notify: false,
+        };
+        save_untrusted_device_alerts(&conn, &mode).unwrap();
+        assert_eq!(record_untrusted_device(&conn, &laptop).unwrap(), None);
+        let id = record_untrusted_device(&conn, &stranger).unwrap();
+        assert!(id.is_some());
+
+        // Seen again by a later scan: counted on the unread alert
+        assert_eq!(record_untrusted_device(&conn, &stranger).unwrap(), None);
+        let (count, occurrences): (i64, i64) = conn
+            .query_row(
+                "SELECT COUNT(*), MAX(occurrences) FROM alerts \
+                 WHERE alert_type = 'untrusted_device'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((count, occurrences), (1, 2));
+    }
+}
@@ -4,8 +4,10 @@
 
 use serde::{Deserialize, Serialize};
 
-/// Alert severity levels
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+use crate::config::{ALERT_COOLDOWN_MINUTES, FINDING_ALERT_COOLDOWN_MINUTES};
+
+/// Alert severity levels, least severe first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum AlertSeverity {
     Low,
     Medium,
@@ -24,6 +26,21 @@ impl AlertSeverity {
     }
 }
 
+impl std::str::FromStr for AlertSeverity {
+    type Err = String;
+
+    /// Parse the identifier returned by [`AlertSeverity::as_str`]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "LOW" => Ok(AlertSeverity::Low),
+            "MEDIUM" => Ok(AlertSeverity::Medium),
+            "HIGH" => Ok(AlertSeverity::High),
+            "CRITICAL" => Ok(AlertSeverity::Critical),
+            _ => Err(format!("Unknown alert severity: {}", s)),
+        }
+    }
+}
+
 /// Types of alerts that can be generated
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum AlertType {
@@ -39,6 +56,34 @@ pub enum AlertType {
     UnusualPort,
     /// Device IP address changed
     IpChanged,
+    /// Device response time exceeded its configured threshold
+    HighLatency,
+    /// Device's open ports differ from its expected baseline
+    PortBaselineDeviation,
+    /// Several MACs claim the same IP address
+    IpConflict,
+    /// One MAC answers on several IP addresses (cloned or spoofed)
+    MacCloned,
+    /// The gateway's address is now answered by a different MAC
+    GatewayMacChanged,
+    /// A DHCP server other than the known ones answered
+    RogueDhcp,
+    /// A known device opened a port it did not have in its last sighting
+    PortOpened,
+    /// A TLS certificate is close to (or past) its expiry date
+    CertificateExpiring,
+    /// Internet connectivity was lost
+    InternetDown,
+    /// A device keeps going offline and back online
+    Flapping,
+    /// A device not marked trusted is on the network
+    UntrustedDevice,
+    /// A device departs from its history (a port it never had, an unusual hour)
+    BehaviorAnomaly,
+    /// A known MAC now looks like a different device, or two hosts share it
+    MacSpoofing,
+    /// A device's SSH server presents a different host key than before
+    SshHostKeyChanged,
 }
 
 impl AlertType {
@@ -50,6 +95,20 @@ impl AlertType {
             AlertType::HighRiskDetected => "HIGH_RISK",
             AlertType::UnusualPort => "UNUSUAL_PORT",
             AlertType::IpChanged => "IP_CHANGED",
+            AlertType::HighLatency => "HIGH_LATENCY",
+            AlertType::PortBaselineDeviation => "PORT_DEVIATION",
+            AlertType::IpConflict => "IP_CONFLICT",
+            AlertType::MacCloned => "MAC_CLONED",
+            AlertType::GatewayMacChanged => "GATEWAY_MAC_CHANGED",
+            AlertType::RogueDhcp => "ROGUE_DHCP",
+            AlertType::PortOpened => "PORT_OPENED",
+            AlertType::CertificateExpiring => "CERT_EXPIRING",
+            AlertType::InternetDown => "INTERNET_DOWN",
+            AlertType::Flapping => "FLAPPING",
+            AlertType::UntrustedDevice => "UNTRUSTED_DEVICE",
+            AlertType::BehaviorAnomaly => "BEHAVIOR_ANOMALY",
+            AlertType::MacSpoofing => "MAC_SPOOFING",
+            AlertType::SshHostKeyChanged => "SSH_HOST_KEY_CHANGED",
         }
     }
 
@@ -61,6 +120,66 @@ impl AlertType {
             AlertType::HighRiskDetected => AlertSeverity::High,
             AlertType::UnusualPort => AlertSeverity::High,
             AlertType::IpChanged => AlertSeverity::Low,
+            AlertType::HighLatency => AlertSeverity::Medium,
+            AlertType::PortBaselineDeviation => AlertSeverity::High,
+            AlertType::IpConflict => AlertSeverity::High,
+            AlertType::MacCloned => AlertSeverity::High,
+            AlertType::GatewayMacChanged => AlertSeverity::Critical,
+            AlertType::RogueDhcp => AlertSeverity::Critical,
+            AlertType::PortOpened => AlertSeverity::Medium,
+            AlertType::CertificateExpiring => AlertSeverity::Medium,
+            AlertType::InternetDown => AlertSeverity::High,
+            AlertType::Flapping => AlertSeverity::Medium,
+            AlertType::UntrustedDevice => AlertSeverity::High,
+            AlertType::BehaviorAnomaly => AlertSeverity::Medium,
+            AlertType::MacSpoofing => AlertSeverity::High,
+            AlertType::SshHostKeyChanged => AlertSeverity::High,
+        }
+    }
+
+    /// Minutes a repeat of this alert is merged into the unread one
+    pub fn cooldown_minutes(&self) -> i64 {
+        match self {
+            AlertType::NewDeviceDiscovered
+            | AlertType::HighRiskDetected
+            | AlertType::UnusualPort
+            | AlertType::PortBaselineDeviation
+            | AlertType::PortOpened
+            | AlertType::CertificateExpiring
+            | AlertType::UntrustedDevice
+            | AlertType::BehaviorAnomaly => FINDING_ALERT_COOLDOWN_MINUTES,
+            _ => ALERT_COOLDOWN_MINUTES,
+        }
+    }
+}
+
+impl std::str::FromStr for AlertType {
+    type Err = String;
+
+    /// Parse the identifier returned by [`AlertType::as_str`]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "NEW_DEVICE" => Ok(AlertType::NewDeviceDiscovered),
+            "DEVICE_OFFLINE" => Ok(AlertType::DeviceWentOffline),
+            "DEVICE_ONLINE" => Ok(AlertType::DeviceCameOnline),
+            "HIGH_RISK" => Ok(AlertType::HighRiskDetected),
+            "UNUSUAL_PORT" => Ok(AlertType::UnusualPort),
+            "IP_CHANGED" => Ok(AlertType::IpChanged),
+            "HIGH_LATENCY" => Ok(AlertType::HighLatency),
+            "PORT_DEVIATION" => Ok(AlertType::PortBaselineDeviation),
+            "IP_CONFLICT" => Ok(AlertType::IpConflict),
+            "MAC_CLONED" => Ok(AlertType::MacCloned),
+            "GATEWAY_MAC_CHANGED" => Ok(AlertType::GatewayMacChanged),
+            "ROGUE_DHCP" => Ok(AlertType::RogueDhcp),
+            "PORT_OPENED" => Ok(AlertType::PortOpened),
+            "CERT_EXPIRING" => Ok(AlertType::CertificateExpiring),
+            "INTERNET_DOWN" => Ok(AlertType::InternetDown),
+            "FLAPPING" => Ok(AlertType::Flapping),
+            "UNTRUSTED_DEVICE" => Ok(AlertType::UntrustedDevice),
+            "BEHAVIOR_ANOMALY" => Ok(AlertType::BehaviorAnomaly),
+            "MAC_SPOOFING" => Ok(AlertType::MacSpoofing),
+            "SSH_HOST_KEY_CHANGED" => Ok(AlertType::SshHostKeyChanged),
+            _ => Err(format!("Unknown alert type: {}", s)),
         }
     }
 }
@@ -73,6 +192,9 @@ pub struct Alert {
     pub device_ip: Option<String>,
     pub message: String,
     pub severity: AlertSeverity,
+    /// Supporting data stored with the alert record
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub evidence: Option<serde_json::Value>,
 }
 
 impl Alert {
@@ -84,6 +206,7 @@ impl Alert {
             device_ip: None,
             message: message.into(),
             severity,
+            evidence: None,
         }
     }
 
@@ -97,6 +220,68 @@ impl Alert {
         self.severity = severity;
         self
     }
+
+    pub fn with_evidence(mut self, evidence: serde_json::Value) -> Self {
+        self.evidence = Some(evidence);
+        self
+    }
+
+    /// What identifies a repeat of this alert, besides its type and device
+    pub fn dedupe_key(&self) -> String {
+        let mac = self.device_mac.as_deref().unwrap_or("unknown-mac");
+        let ip = self.device_ip.as_deref().unwrap_or("unknown-ip");
+        let port = || {
+            self.message
+                .split(|c: char| !c.is_ascii_digit())
+                .find_map(|chunk| chunk.parse::<u16>().ok())
+                .map_or_else(|| "unknown".to_string(), |port| port.to_string())
+        };
+
+        match self.alert_type {
+            AlertType::NewDeviceDiscovered => format!("new-device:{mac}"),
+            AlertType::DeviceWentOffline => format!("device-offline:{mac}"),
+            AlertType::DeviceCameOnline => format!("device-online:{mac}"),
+            AlertType::HighRiskDetected => format!("high-risk:{mac}"),
+            AlertType::UnusualPort => format!("unusual-port:{mac}:{}", port()),
+            AlertType::IpChanged => format!("ip-changed:{mac}:{ip}"),
+            AlertType::HighLatency => format!("high-latency:{mac}"),
+            AlertType::PortBaselineDeviation => format!("port-deviation:{mac}:{}", self.message),
+            AlertType::IpConflict => format!("ip-conflict:{ip}"),
+            AlertType::MacCloned => format!("mac-cloned:{mac}"),
+            AlertType::GatewayMacChanged => format!("gateway-mac-changed:{ip}:{mac}"),
+            AlertType::RogueDhcp => format!("rogue-dhcp:{mac}"),
+            AlertType::PortOpened => format!("port-opened:{mac}:{}", port()),
+            AlertType::CertificateExpiring => match self.evidence.as_ref() {
+                Some(evidence) => format!(
+                    "cert-expiring:{mac}:{}:{}",
+                    evidence["port"], evidence["step_days"]
+                ),
+                None => format!("cert-expiring:{ip}:{}", self.message),
+            },
+            AlertType::InternetDown => "internet-down".to_string(),
+            AlertType::Flapping => format!("flapping:{mac}"),
+            AlertType::UntrustedDevice => format!("untrusted-device:{mac}"),
+            AlertType::BehaviorAnomaly => {
+                let evidence = self.evidence.as_ref();
+                let kind = evidence
+                    .and_then(|e| e["anomaly"].as_str())
+                    .unwrap_or("unknown");
+                let detail = evidence
+                    .and_then(|e| e.get("port").or_else(|| e.get("hour")))
+                    .map_or_else(|| "unknown".to_string(), |v| v.to_string());
+                format!("behavior-anomaly:{mac}:{kind}:{detail}")
+            }
+            AlertType::MacSpoofing => format!("mac-spoofing:{mac}"),
+            AlertType::SshHostKeyChanged => {
+                let evidence = self.evidence.as_ref();
+                let port = evidence.map_or_else(port, |e| e["port"].to_string());
+                let fingerprint = evidence
+                    .and_then(|e| e["fingerprint"].as_str())
+                    .unwrap_or("unknown");
+                format!("ssh-host-key:{mac}:{port}:{fingerprint}")
+            }
+        }
+    }
 }
 
 /// Suspicious ports that should trigger alerts
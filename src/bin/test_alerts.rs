@@ -23,6 +23,11 @@ fn main() {
             custom_name: None,
             notes: None,
             security_grade: None,
+            tags: Vec::new(),
+            attributes: Default::default(),
+            availability: Default::default(),
+            open_ports: Vec::new(),
+            network_id: None,
         },
         DeviceRecord {
             id: 2,
@@ -37,6 +42,11 @@ fn main() {
             custom_name: None,
             notes: None,
             security_grade: None,
+            tags: Vec::new(),
+            attributes: Default::default(),
+            availability: Default::default(),
+            open_ports: Vec::new(),
+            network_id: None,
         },
     ];
 
@@ -62,6 +72,12 @@ fn main() {
             vulnerabilities: vec![],
             port_warnings: vec![],
             security_grade: String::new(),
+            filtered_ports: Vec::new(),
+            host_state: None,
+            conflicting_macs: Vec::new(),
+            ipv6_addresses: Vec::new(),
+            device_id: None,
+            last_seen: None,
         },
         // NEW device (new device alert)
         HostInfo {
@@ -83,6 +99,12 @@ fn main() {
             vulnerabilities: vec![],
             port_warnings: vec![],
             security_grade: String::new(),
+            filtered_ports: Vec::new(),
+            host_state: None,
+            conflicting_macs: Vec::new(),
+            ipv6_addresses: Vec::new(),
+            device_id: None,
+            last_seen: None,
         },
     ];
     // Note: Device 2 (galaxy) is NOT in current scan - it went offline
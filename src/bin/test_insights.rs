@@ -28,6 +28,12 @@ fn main() {
             vulnerabilities: vec![],
             port_warnings: vec![],
             security_grade: String::new(),
+            filtered_ports: Vec::new(),
+            host_state: None,
+            conflicting_macs: Vec::new(),
+            ipv6_addresses: Vec::new(),
+            device_id: None,
+            last_seen: None,
         },
         HostInfo {
             ip: "192.168.1.100".to_string(),
@@ -48,6 +54,12 @@ fn main() {
             vulnerabilities: vec![],
             port_warnings: vec![],
             security_grade: String::new(),
+            filtered_ports: Vec::new(),
+            host_state: None,
+            conflicting_macs: Vec::new(),
+            ipv6_addresses: Vec::new(),
+            device_id: None,
+            last_seen: None,
         },
         HostInfo {
             ip: "192.168.1.101".to_string(),
@@ -68,6 +80,12 @@ fn main() {
             vulnerabilities: vec![],
             port_warnings: vec![],
             security_grade: String::new(),
+            filtered_ports: Vec::new(),
+            host_state: None,
+            conflicting_macs: Vec::new(),
+            ipv6_addresses: Vec::new(),
+            device_id: None,
+            last_seen: None,
         },
         HostInfo {
             ip: "192.168.1.102".to_string(),
@@ -88,6 +106,12 @@ fn main() {
             vulnerabilities: vec![],
             port_warnings: vec![],
             security_grade: String::new(),
+            filtered_ports: Vec::new(),
+            host_state: None,
+            conflicting_macs: Vec::new(),
+            ipv6_addresses: Vec::new(),
+            device_id: None,
+            last_seen: None,
         },
     ];
 
@@ -14,9 +14,12 @@ pub const PING_RETRIES: u8 = 1;
 /// Default subnet prefix length when interface doesn't provide one
 pub const DEFAULT_PREFIX_LEN: u8 = 24;
 
-/// Maximum hosts to scan (prevent scanning huge /20 subnets)
-/// Set to 254 for typical /24 subnet, or 512 for /23
-pub const MAX_SCAN_HOSTS: usize = 254;
+/// Maximum hosts to scan (a /16)
+/// Larger prefixes, e.g. a misconfigured /8, are scanned in the window around the local IP
+pub const MAX_SCAN_HOSTS: usize = 65_534;
+
+/// Large subnets are ARP-scanned in chunks of this prefix length, one after another
+pub const SUBNET_CHUNK_PREFIX: u8 = 24;
 
 // ====== ARP Adaptive Scan Configuration ======
 
@@ -32,6 +35,11 @@ pub const ARP_IDLE_TIMEOUT_MS: u64 = 300;
 /// Number of ARP scan rounds (reduced to 1 for speed)
 pub const ARP_ROUNDS: u8 = 1;
 
+// ====== IPv6 Neighbor Discovery ======
+
+/// How long to collect replies to the all-nodes multicast ping (ms)
+pub const NDP_LISTEN_MS: u64 = 1500;
+
 /// TCP probe timeout (reduced from 500ms)
 pub const TCP_PROBE_TIMEOUT: Duration = Duration::from_millis(300);
 
@@ -39,6 +47,194 @@ pub const TCP_PROBE_TIMEOUT: Duration = Duration::from_millis(300);
 /// Full list: [22, 80, 443, 445, 8080, 3389, 5353, 62078]
 pub const TCP_PROBE_PORTS: &[u16] = &[22, 80, 443, 445, 3389];
 
+// ====== TLS Certificate Inspection ======
+
+/// Open ports whose TLS certificate is fetched after a scan (HTTPS, router
+/// and NAS admin UIs, Proxmox)
+pub const TLS_CERT_PORTS: &[u16] = &[443, 5001, 8006, 8443, 9443];
+
+/// Connect and handshake timeout when fetching a certificate
+pub const TLS_CERT_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Days before expiry at which a certificate raises an alert, latest last
+pub const CERT_EXPIRY_WARNING_DAYS: &[i64] = &[30, 7, 1];
+
+// ====== SSH Host Keys ======
+
+/// Open ports whose SSH banner and host key are collected after a scan
+pub const SSH_PORTS: &[u16] = &[22];
+
+/// Connect and key exchange timeout when collecting a host key
+pub const SSH_PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Consecutive scans with the same results within this window are duplicates (seconds)
+pub const DUPLICATE_SCAN_WINDOW_SECS: u64 = 120;
+
+// ====== ARP Anomaly Detection ======
+
+/// ARP requests from a single MAC within the window that count as a storm
+pub const ARP_STORM_REQUEST_THRESHOLD: u32 = 100;
+
+/// Sliding window for ARP request rate tracking (seconds)
+pub const ARP_STORM_WINDOW_SECS: u64 = 10;
+
+/// How long an observed IP → MAC binding is trusted for takeover detection (seconds)
+pub const ARP_BINDING_TTL_SECS: u64 = 3600;
+
+/// Longest accepted ARP storm window or binding TTL (one week, in seconds)
+pub const ARP_MAX_WINDOW_SECS: u64 = 7 * 24 * 3600;
+
+// ====== Vendor Lookup Configuration ======
+
+/// IEEE MA-L (OUI) registry in CSV form
+pub const OUI_REGISTRY_URL: &str = "https://standards-oui.ieee.org/oui/oui.csv";
+
+/// Timeout for downloading the OUI registry
+pub const OUI_DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(60);
+
+// ====== WAN Lookup Configuration ======
+
+/// Service returning the caller's public IP as plain text
+pub const WAN_IP_ENDPOINT: &str = "https://api.ipify.org";
+
+/// Timeout for public IP and ISP lookups
+pub const WAN_LOOKUP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Largest lookup response read
+pub const WAN_LOOKUP_MAX_BYTES: u64 = 64 * 1024;
+
+// ====== Internet Health Checks ======
+
+/// External addresses pinged to judge internet connectivity
+pub const DEFAULT_INTERNET_ANCHORS: [&str; 2] = ["1.1.1.1", "8.8.8.8"];
+
+/// Name resolved to time DNS
+pub const DEFAULT_INTERNET_DNS_NAME: &str = "example.com";
+
+/// Echo requests sent to the gateway and each anchor per check
+pub const INTERNET_CHECK_PINGS: u32 = 4;
+
+/// Longest a DNS resolution may take before it counts as failed
+pub const INTERNET_DNS_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Most external anchors a health check pings
+pub const MAX_INTERNET_ANCHORS: usize = 5;
+
+// ====== Forward DNS Sweep ======
+
+/// Timeout for each query to the local DNS server
+pub const DNS_SWEEP_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Upper bound on records accepted from a zone transfer
+pub const DNS_SWEEP_MAX_RECORDS: usize = 10_000;
+
+/// Host names tried in the search domain when a zone transfer is refused
+pub const DNS_SWEEP_NAMES: &[&str] = &[
+    "router", "gateway", "gw", "firewall", "fw", "modem", "ap", "wifi", "switch", "nas",
+    "storage", "backup", "files", "fileserver", "server", "srv", "dc", "dc1", "dc01", "ad",
+    "dns", "ns", "ns1", "ns2", "dhcp", "mail", "smtp", "vpn", "proxy", "printer", "print",
+    "scanner", "camera", "nvr", "pihole", "homeassistant", "hass", "plex", "media", "www",
+    "web", "intranet", "git", "db", "monitor", "ntp", "voip", "pbx",
+];
+
+// ====== MAC Randomization Correlation ======
+
+/// Minimum fingerprint score for linking a new randomized MAC to a known device
+pub const MAC_CORRELATION_MIN_SCORE: u32 = 50;
+
+/// Host names too common to identify a device on their own
+pub const GENERIC_HOSTNAMES: &[&str] = &[
+    "localhost",
+    "android",
+    "iphone",
+    "ipad",
+    "galaxy",
+    "unknown",
+    "espressif",
+    "esp32",
+];
+
+// ====== Query Cache ======
+
+/// Longest a cached UI query result is served without re-reading SQLite.
+/// Writes invalidate it sooner; this bounds time-based fields like "new in 24h".
+pub const QUERY_CACHE_TTL: Duration = Duration::from_secs(30);
+
+// ====== Insights ======
+
+/// Ports covered by the port-exposure report (Telnet, SMB, RDP, VNC)
+pub const SENSITIVE_PORTS: &[u16] = &[23, 445, 3389, 5900];
+
+/// Consecutive ARP-only scans before a device is reported as a stealth host
+pub const STEALTH_MIN_SCANS: u32 = 3;
+
+// ====== Alerts ======
+
+/// Minutes an unread alert absorbs repeats of itself (counted on the record
+/// instead of stored again), for events that come and go
+pub const ALERT_COOLDOWN_MINUTES: i64 = 60;
+
+/// The same for findings that persist from scan to scan (new and risky
+/// devices, open ports, expiring certificates)
+pub const FINDING_ALERT_COOLDOWN_MINUTES: i64 = 24 * 60;
+
+/// Stored sightings a device needs before being active at a new hour of the
+/// day counts as unusual
+pub const ANOMALY_MIN_SIGHTINGS: u32 = 20;
+
+/// Fingerprint fields (vendor, device type, OS, hostname) that must change
+/// together before a known MAC counts as possibly spoofed
+pub const MAC_SPOOFING_MIN_SIGNALS: usize = 2;
+
+// ====== Notifications ======
+
+/// Timeout for delivering an alert to a webhook
+pub const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Retries after a failed webhook delivery, unless configured otherwise
+pub const WEBHOOK_RETRIES: u32 = 3;
+
+/// Most retries a webhook may be configured with
+pub const MAX_WEBHOOK_RETRIES: u32 = 10;
+
+/// Wait before the first webhook retry, doubled for each one after it
+pub const WEBHOOK_RETRY_BACKOFF: Duration = Duration::from_secs(2);
+
+/// SMTP submission port (STARTTLS)
+pub const DEFAULT_SMTP_PORT: u16 = 587;
+
+/// Timeout for talking to the SMTP server
+pub const SMTP_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Local hour the daily alert digest is emailed, unless configured otherwise
+pub const DEFAULT_DIGEST_HOUR: u32 = 8;
+
+/// MQTT broker port (plain TCP)
+pub const DEFAULT_MQTT_PORT: u16 = 1883;
+
+/// MQTT keep-alive interval
+pub const MQTT_KEEP_ALIVE: Duration = Duration::from_secs(30);
+
+/// How long to wait for the broker to acknowledge an alert
+pub const MQTT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Wait before reconnecting to an unreachable broker
+pub const MQTT_RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Syslog facility, local0
+pub const DEFAULT_SYSLOG_FACILITY: u8 = 16;
+
+/// Longest syslog message sent over UDP; longer ones are truncated (RFC 5426)
+pub const SYSLOG_MAX_UDP_BYTES: usize = 2048;
+
+/// Timeout for connecting and writing to a syslog server
+pub const SYSLOG_TIMEOUT: Duration = Duration::from_secs(10);
+
+// ====== Exports ======
+
+/// Most recent alerts listed on the public HTML dashboard
+pub const DASHBOARD_RECENT_ALERTS: usize = 20;
+
 // ====== SNMP Configuration (Optional Feature) ======
 
 /// Enable SNMP enrichment for discovered hosts (disabled by default)
@@ -53,6 +249,14 @@ pub const SNMP_TIMEOUT: Duration = Duration::from_secs(1);
 /// SNMP port
 pub const SNMP_PORT: u16 = 161;
 
+// ====== LDAP Enrichment (Optional Feature) ======
+
+/// Timeout for connecting to and each request against the directory server
+pub const LDAP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Hostnames per LDAP search (keeps filters well under server limits)
+pub const LDAP_BATCH_SIZE: usize = 50;
+
 // ====== Monitoring Configuration ======
 
 /// Default monitoring interval in seconds
@@ -63,3 +267,94 @@ pub const MIN_MONITOR_INTERVAL: u64 = 10;
 
 /// Maximum monitoring interval in seconds
 pub const MAX_MONITOR_INTERVAL: u64 = 3600;
+
+/// Most monitor cycles per full scan; the cycles in between only ping known devices
+pub const MAX_FULL_SCAN_EVERY: u32 = 60;
+
+/// Monitor events that can wait for a slow consumer before new ones are dropped
+pub const MONITOR_EVENT_QUEUE_SIZE: usize = 1024;
+
+/// How often the monitor watchdog checks on the scan loop
+pub const MONITOR_WATCHDOG_INTERVAL: Duration = Duration::from_secs(5);
+
+/// A scan loop silent for this many seconds counts as hung and is restarted
+/// (longer than the slowest full scan)
+pub const MONITOR_STALL_TIMEOUT_SECS: u64 = 600;
+
+/// Failed scans in a row after which the monitor looks up its interface again
+pub const MONITOR_RECOVERY_FAILURES: u32 = 3;
+
+/// Pause before a crashed or hung scan loop is restarted
+pub const MONITOR_RESTART_DELAY: Duration = Duration::from_secs(5);
+
+/// Seconds between checks of watched devices while waiting for the next scan
+pub const WATCH_CHECK_INTERVAL_SECS: u64 = 5;
+
+/// Consecutive monitor scans a device must miss before it counts as offline
+pub const MONITOR_MISSED_SCANS: u32 = 2;
+
+/// Consecutive monitor scans an unknown device must be seen in before it is new
+pub const MONITOR_CONFIRM_SCANS: u32 = 2;
+
+/// Appearances plus disappearances within `FLAP_WINDOW_SECS` that make a
+/// device flapping
+pub const FLAP_MIN_CHANGES: usize = 4;
+
+/// Window for counting a device's presence changes, in seconds
+pub const FLAP_WINDOW_SECS: u64 = 1800;
+
+/// Ping replies kept per device for its latency baseline (their median)
+pub const LATENCY_BASELINE_SAMPLES: usize = 10;
+
+/// A reply this many times slower than the baseline counts as degraded...
+pub const LATENCY_DEGRADED_FACTOR: u64 = 3;
+
+/// ...if it is also at least this many milliseconds slower, so LAN jitter
+/// (1 ms to 4 ms) is not reported
+pub const LATENCY_DEGRADED_MIN_MS: u64 = 20;
+
+// ====== Passive Capture Configuration ======
+
+/// Default size of the rolling packet capture buffer (16 MiB)
+pub const DEFAULT_CAPTURE_BUFFER_BYTES: usize = 16 * 1024 * 1024;
+
+/// Smallest allowed capture buffer (1 MiB)
+pub const MIN_CAPTURE_BUFFER_BYTES: usize = 1024 * 1024;
+
+/// Largest allowed capture buffer (256 MiB)
+pub const MAX_CAPTURE_BUFFER_BYTES: usize = 256 * 1024 * 1024;
+
+/// Maximum bytes kept per captured frame
+pub const CAPTURE_SNAPLEN: usize = 65535;
+
+// ====== Peer Discovery ======
+
+/// DNS-SD service type instances advertise themselves under
+pub const PEER_SERVICE_TYPE: &str = "_netmapper._tcp.local.";
+
+/// Port advertised for peer coordination
+pub const PEER_DEFAULT_PORT: u16 = 47_820;
+
+/// Timeout for fetching a scan share from a peer
+pub const PEER_REQUEST_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Allowed clock difference for signed share requests (seconds)
+pub const PEER_REQUEST_MAX_SKEW_SECS: i64 = 120;
+
+/// Peer scan results older than this are scanned again locally (seconds)
+pub const PEER_SHARE_MAX_AGE_SECS: i64 = 600;
+
+/// Largest scan share accepted from a peer
+pub const PEER_SHARE_MAX_BYTES: usize = 8 * 1024 * 1024;
+
+// ====== Scheduled Scans ======
+
+/// How often the scan scheduler checks for due schedules
+pub const SCHEDULER_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Folder next to the database that scheduled scan reports are written to
+pub const SCHEDULED_REPORT_DIR: &str = "reports";
+
+/// How far ahead to look for the next run of a cron expression, in days
+/// (covers a run on 29 February)
+pub const CRON_SEARCH_DAYS: u32 = 366 * 8;
@@ -0,0 +1,163 @@
+//! In-memory cache for frequently polled queries
+//!
+//! The UI polls the device list and network stats every few seconds, which
+//! is noticeable on low-power hosts like a Raspberry Pi. Results are kept
+//! until the database changes: every write through `Database` bumps its
+//! write generation, and `PRAGMA data_version` on the pooled connection
+//! catches writes from other processes (e.g. the CLI). Entries also expire
+//! after `QUERY_CACHE_TTL` since some stats are relative to the current time.
+
+use anyhow::Result;
+use rusqlite::Connection;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use super::connection::{ConnectionGuard, Database};
+use super::models::{DeviceRecord, NetworkStats};
+use super::queries;
+use crate::config::QUERY_CACHE_TTL;
+
+/// Database state a cached value was read at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Version {
+    write_generation: u64,
+    /// `data_version` is only comparable on the same connection
+    connection: usize,
+    data_version: i64,
+}
+
+impl Version {
+    fn current(db: &Database, conn: &ConnectionGuard<'_>) -> Result<Self> {
+        Ok(Self {
+            write_generation: db.write_generation(),
+            connection: conn.id(),
+            data_version: conn.query_row("PRAGMA data_version", [], |row| row.get(0))?,
+        })
+    }
+}
+
+struct Entry<T> {
+    version: Version,
+    /// Network profile the value was loaded for (`None` for all networks)
+    network_id: Option<i64>,
+    loaded_at: Instant,
+    value: T,
+}
+
+type Slot<T> = Mutex<Option<Entry<T>>>;
+
+/// Cached results of the queries the UI polls most
+#[derive(Default)]
+pub struct QueryCache {
+    devices: Slot<Vec<DeviceRecord>>,
+    stats: Slot<NetworkStats>,
+}
+
+impl QueryCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn get_or_load<T: Clone>(
+        slot: &Slot<T>,
+        db: &Database,
+        network_id: Option<i64>,
+        load: impl FnOnce(&Connection) -> Result<T>,
+    ) -> Result<T> {
+        let conn = db.reader()?;
+        let version = Version::current(db, &conn)?;
+        // A poisoned slot only costs a cache miss
+        let mut slot = slot.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(entry) = slot.as_ref() {
+            if entry.version == version
+                && entry.network_id == network_id
+                && entry.loaded_at.elapsed() < QUERY_CACHE_TTL
+            {
+                return Ok(entry.value.clone());
+            }
+        }
+
+        let value = load(&conn)?;
+        *slot = Some(Entry {
+            version,
+            network_id,
+            loaded_at: Instant::now(),
+            value: value.clone(),
+        });
+        Ok(value)
+    }
+
+    /// Known devices on a network profile, or all (`queries::get_devices_in_network`)
+    pub fn devices(&self, db: &Database, network_id: Option<i64>) -> Result<Vec<DeviceRecord>> {
+        Self::get_or_load(&self.devices, db, network_id, |conn| {
+            queries::get_devices_in_network(conn, network_id)
+        })
+    }
+
+    /// Dashboard statistics (`queries::get_network_stats_for`)
+    pub fn network_stats(&self, db: &Database, network_id: Option<i64>) -> Result<NetworkStats> {
+        Self::get_or_load(&self.stats, db, network_id, |conn| {
+            queries::get_network_stats_for(conn, network_id)
+        })
+    }
+
+    /// Drop all cached results, e.g. after a scan is saved or a device edited
+    pub fn invalidate(&self) {
+        clear(&self.devices);
+        clear(&self.stats);
+    }
+}
+
+fn clear<T>(slot: &Slot<T>) {
+    *slot.lock().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    #[test]
+    fn test_query_cache_invalidated_by_writes() {
+        let db = Database::in_memory().unwrap();
+        let cache = QueryCache::new();
+        let loads = Cell::new(0);
+        let count_devices = |conn: &Connection| {
+            loads.set(loads.get() + 1);
+            queries::get_all_devices(conn).map(|devices| devices.len())
+        };
+        let slot: Slot<usize> = Mutex::default();
+
+        assert_eq!(
+            QueryCache::get_or_load(&slot, &db, None, count_devices).unwrap(),
+            0
+        );
+        assert_eq!(
+            QueryCache::get_or_load(&slot, &db, None, count_devices).unwrap(),
+            0
+        );
+        assert_eq!(loads.get(), 1);
+
+        // Any write through the database makes the next read hit SQLite
+        db.writer()
+            .unwrap()
+            .execute("INSERT INTO devices (mac) VALUES ('AA:BB:CC:DD:EE:01')", [])
+            .unwrap();
+        assert_eq!(
+            QueryCache::get_or_load(&slot, &db, None, count_devices).unwrap(),
+            1
+        );
+        assert_eq!(loads.get(), 2);
+
+        clear(&slot);
+        QueryCache::get_or_load(&slot, &db, None, count_devices).unwrap();
+        assert_eq!(loads.get(), 3);
+
+        assert_eq!(cache.devices(&db, None).unwrap().len(), 1);
+        assert_eq!(cache.network_stats(&db, None).unwrap().total_devices, 1);
+        // Another network profile is a miss, not the cached list
+        assert!(cache.devices(&db, Some(1)).unwrap().is_empty());
+        cache.invalidate();
+        assert!(cache.devices.lock().unwrap().is_none());
+    }
+}
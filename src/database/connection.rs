@@ -1,18 +1,213 @@
 //! Database connection and initialization
 //!
-//! Handles SQLite connection pooling and database setup
+//! Handles SQLite connection pooling and database setup. File databases run
+//! in WAL mode with one writer connection and a small pool of read-only
+//! connections, so reads (the UI polling devices and stats) are not blocked
+//! behind a long write such as saving a scan.
+//!
+//! Backups use SQLite's online backup API, so they can be taken (and
+//! restored) while the app keeps running. [`Database::maintenance`] checks
+//! integrity, refreshes planner statistics and compacts the file.
+//!
+//! Builds with the `sqlcipher` feature can keep the live database encrypted
+//! on disk (see [`AtRestEncryption`]); backups of an encrypted database are
+//! encrypted with the same key.
 
-use anyhow::{anyhow, Context, Result};
-use rusqlite::Connection;
-use std::path::PathBuf;
-use std::sync::{Arc, Mutex};
+use anyhow::{anyhow, bail, Context, Result};
+use rusqlite::backup::Backup;
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
 
 use super::schema;
 
-/// Database wrapper with thread-safe connection
+/// Number of read-only connections kept open for file databases
+const READ_CONNECTIONS: usize = 4;
+
+/// How long a connection waits on a lock held by another connection
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Pages copied per backup step; other connections can work between steps
+const BACKUP_PAGES_PER_STEP: i32 = 256;
+
+/// Pause between backup steps
+const BACKUP_STEP_PAUSE: Duration = Duration::from_millis(10);
+
+/// Environment variable holding a passphrase for the encrypted database
+pub const DB_PASSPHRASE_ENV: &str = "NTM_DB_PASSPHRASE";
+
+/// Environment variable selecting at-rest encryption ("machine" for a key
+/// bound to this machine)
+pub const DB_ENCRYPTION_ENV: &str = "NTM_DB_ENCRYPTION";
+
+/// First bytes of every unencrypted SQLite database file
+const SQLITE_HEADER: &[u8; 16] = b"SQLite format 3\0";
+
+/// How the live database file is protected on disk
+#[derive(Clone, Default, PartialEq, Eq)]
+pub enum AtRestEncryption {
+    /// Plain SQLite file
+    #[default]
+    None,
+    /// SQLCipher with the machine-bound key also used for encrypted exports;
+    /// the file only opens on this machine
+    MachineKey,
+    /// SQLCipher with a user passphrase
+    Passphrase(String),
+}
+
+impl std::fmt::Debug for AtRestEncryption {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::None => write!(f, "None"),
+            Self::MachineKey => write!(f, "MachineKey"),
+            Self::Passphrase(_) => write!(f, "Passphrase(..)"),
+        }
+    }
+}
+
+impl AtRestEncryption {
+    /// Mode chosen by `NTM_DB_PASSPHRASE` or `NTM_DB_ENCRYPTION=machine`
+    pub fn from_env() -> Self {
+        match std::env::var(DB_PASSPHRASE_ENV) {
+            Ok(passphrase) if !passphrase.is_empty() => Self::Passphrase(passphrase),
+            _ => match std::env::var(DB_ENCRYPTION_ENV).as_deref() {
+                Ok("machine") => Self::MachineKey,
+                _ => Self::None,
+            },
+        }
+    }
+
+    /// Value for SQLCipher's `PRAGMA key`, or `None` for a plain database
+    fn sqlcipher_key(&self) -> Result<Option<String>> {
+        if *self == Self::None {
+            return Ok(None);
+        }
+        if !cfg!(feature = "sqlcipher") {
+            bail!("At-rest encryption needs a build with the `sqlcipher` feature");
+        }
+
+        match self {
+            Self::None => Ok(None),
+            Self::MachineKey => {
+                let key = super::encryption::get_encryption_key()
+                    .map_err(|e| anyhow!("Failed to derive database key: {}", e))?;
+                // Raw 256-bit key, skipping SQLCipher's own key derivation
+                Ok(Some(format!("x'{}'", hex::encode(key))))
+            }
+            Self::Passphrase(passphrase) if passphrase.is_empty() => {
+                bail!("Database passphrase must not be empty")
+            }
+            Self::Passphrase(passphrase) => Ok(Some(passphrase.clone())),
+        }
+    }
+}
+
+/// Outcome of [`Database::maintenance`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MaintenanceReport {
+    /// Problems reported by `PRAGMA integrity_check`; empty when healthy
+    pub integrity_errors: Vec<String>,
+    /// Whether the file was compacted (skipped when the check fails)
+    pub vacuumed: bool,
+    pub size_before_bytes: u64,
+    pub size_after_bytes: u64,
+    pub duration_ms: u64,
+}
+
+impl MaintenanceReport {
+    /// Whether the integrity check passed
+    pub fn is_healthy(&self) -> bool {
+        self.integrity_errors.is_empty()
+    }
+}
+
+/// Problems reported by the integrity check, at most this many
+const MAX_INTEGRITY_ERRORS: u32 = 100;
+
+/// Read-only connections handed out one caller at a time
+struct ReaderPool {
+    /// Idle connections with their pool slot (1-based)
+    idle: Mutex<Vec<(usize, Connection)>>,
+    available: Condvar,
+}
+
+/// Database wrapper with a writer connection and pooled readers
 pub struct Database {
-    conn: Arc<Mutex<Connection>>,
+    writer: Arc<Mutex<Connection>>,
+    /// Empty for in-memory databases, whose reads go through the writer
+    readers: Arc<ReaderPool>,
+    /// Bumped whenever a write connection is released
+    write_generation: Arc<AtomicU64>,
     path: PathBuf,
+    /// SQLCipher key, when the file is encrypted at rest
+    key: Option<Arc<str>>,
+}
+
+/// A connection checked out from a `Database`, returned when dropped
+pub struct ConnectionGuard<'a> {
+    inner: GuardInner<'a>,
+}
+
+enum GuardInner<'a> {
+    Writer {
+        conn: MutexGuard<'a, Connection>,
+        /// `None` when the writer stands in for a reader
+        generation: Option<&'a AtomicU64>,
+    },
+    Reader {
+        slot: usize,
+        conn: Option<Connection>,
+        pool: &'a ReaderPool,
+    },
+}
+
+impl ConnectionGuard<'_> {
+    /// Which connection this is: 0 for the writer, 1.. for pooled readers
+    pub fn id(&self) -> usize {
+        match &self.inner {
+            GuardInner::Writer { .. } => 0,
+            GuardInner::Reader { slot, .. } => *slot,
+        }
+    }
+}
+
+impl Deref for ConnectionGuard<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        match &self.inner {
+            GuardInner::Writer { conn, .. } => conn,
+            GuardInner::Reader { conn, .. } => conn
+                .as_ref()
+                .expect("pooled connection is present until dropped"),
+        }
+    }
+}
+
+impl Drop for ConnectionGuard<'_> {
+    fn drop(&mut self) {
+        match &mut self.inner {
+            GuardInner::Writer { generation, .. } => {
+                if let Some(generation) = generation {
+                    generation.fetch_add(1, Ordering::Release);
+                }
+            }
+            GuardInner::Reader { slot, conn, pool } => {
+                if let Some(conn) = conn.take() {
+                    // A poisoned pool still gets its connection back
+                    let mut idle = pool.idle.lock().unwrap_or_else(|e| e.into_inner());
+                    idle.push((*slot, conn));
+                    pool.available.notify_one();
+                }
+            }
+        }
+    }
 }
 
 impl Database {
@@ -21,21 +216,62 @@ impl Database {
     /// # Arguments
     /// * `path` - Path to the SQLite database file (created if not exists)
     pub fn new(path: PathBuf) -> Result<Self> {
+        Self::open(path, &AtRestEncryption::None)
+    }
+
+    /// Opens a database, encrypted on disk unless `encryption` is `None`
+    ///
+    /// An existing unencrypted file is encrypted in place the first time it
+    /// is opened with encryption.
+    pub fn open(path: PathBuf, encryption: &AtRestEncryption) -> Result<Self> {
+        let key = encryption.sqlcipher_key()?;
+
         // Create parent directories if needed
         if let Some(parent) = path.parent() {
             std::fs::create_dir_all(parent).context("Failed to create database directory")?;
         }
+        if let Some(key) = &key {
+            encrypt_plain_file(&path, key)?;
+        }
 
         let conn = Connection::open(&path).context("Failed to open database")?;
+        apply_key(&conn, key.as_deref())?;
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        let journal_mode: String = conn
+            .pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get(0))
+            .context("Failed to enable WAL journal mode")?;
+        if !journal_mode.eq_ignore_ascii_case("wal") {
+            // e.g. some network filesystems; reads then wait on writes
+            tracing::warn!(
+                "Database is in {} journal mode, not WAL; reads may be blocked by writes",
+                journal_mode
+            );
+        }
+        conn.pragma_update(None, "synchronous", "NORMAL")?;
 
-        let db = Self {
-            conn: Arc::new(Mutex::new(conn)),
-            path,
-        };
+        let mut db = Self::with_writer(conn, path);
+        db.key = key.map(Arc::from);
 
         // Initialize schema
         db.initialize()?;
 
+        // Readers are opened after the schema exists
+        let mut readers = Vec::with_capacity(READ_CONNECTIONS);
+        for slot in 1..=READ_CONNECTIONS {
+            let reader = Connection::open_with_flags(
+                &db.path,
+                OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+            )
+            .context("Failed to open read connection")?;
+            apply_key(&reader, db.key.as_deref())?;
+            reader.busy_timeout(BUSY_TIMEOUT)?;
+            readers.push((slot, reader));
+        }
+        *db.readers
+            .idle
+            .lock()
+            .map_err(|_| anyhow!("Database reader pool lock poisoned"))? = readers;
+
         Ok(db)
     }
 
@@ -43,23 +279,40 @@ impl Database {
     pub fn in_memory() -> Result<Self> {
         let conn = Connection::open_in_memory().context("Failed to open in-memory database")?;
 
-        let db = Self {
-            conn: Arc::new(Mutex::new(conn)),
-            path: PathBuf::from(":memory:"),
-        };
+        let db = Self::with_writer(conn, PathBuf::from(":memory:"));
 
         db.initialize()?;
 
         Ok(db)
     }
 
+    fn with_writer(conn: Connection, path: PathBuf) -> Self {
+        Self {
+            writer: Arc::new(Mutex::new(conn)),
+            readers: Arc::new(ReaderPool {
+                idle: Mutex::new(Vec::new()),
+                available: Condvar::new(),
+            }),
+            write_generation: Arc::new(AtomicU64::new(0)),
+            path,
+            key: None,
+        }
+    }
+
+    /// Whether the database file is encrypted at rest
+    pub fn is_encrypted(&self) -> bool {
+        self.key.is_some()
+    }
+
     /// Initialize database schema
     fn initialize(&self) -> Result<()> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|_| anyhow!("Database connection lock poisoned during initialization"))?;
-        schema::create_tables(&conn)?;
+        let conn = self.writer()?;
+        Self::initialize_connection(&conn)
+    }
+
+    /// Bring the schema up to date and seed reference data if missing
+    fn initialize_connection(conn: &Connection) -> Result<()> {
+        schema::create_tables(conn)?;
 
         // Seed vulnerability database if empty
         let cve_count: i64 = conn
@@ -68,16 +321,204 @@ impl Database {
 
         if cve_count == 0 {
             use super::seed_cves::{seed_port_warnings, seed_vulnerabilities};
-            seed_vulnerabilities(&conn)?;
-            seed_port_warnings(&conn)?;
+            seed_vulnerabilities(conn)?;
+            seed_port_warnings(conn)?;
         }
 
         Ok(())
     }
 
-    /// Get a reference to the connection
-    pub fn connection(&self) -> Arc<Mutex<Connection>> {
-        Arc::clone(&self.conn)
+    /// Copy the whole database to `path` (overwritten if it exists)
+    ///
+    /// Reads from a pooled connection a few pages at a time, so scans and the
+    /// UI keep working while the backup runs.
+    pub fn backup_to(&self, path: &Path) -> Result<()> {
+        if path == self.path {
+            bail!("Backup path is the live database");
+        }
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent).context("Failed to create backup directory")?;
+        }
+
+        let source = self.reader()?;
+        let mut target = Connection::open(path)
+            .with_context(|| format!("Failed to create backup file {}", path.display()))?;
+        apply_key(&target, self.key.as_deref())?;
+        Backup::new(&source, &mut target)
+            .context("Failed to start backup")?
+            .run_to_completion(BACKUP_PAGES_PER_STEP, BACKUP_STEP_PAUSE, None)
+            .context("Backup failed")?;
+
+        tracing::info!("Backed up database to {}", path.display());
+        Ok(())
+    }
+
+    /// Replace all data with a backup made by [`Database::backup_to`]
+    ///
+    /// Backups from an older schema are upgraded after the restore; backups
+    /// from a newer build are refused. Writes wait until the restore is done.
+    pub fn restore_from(&self, path: &Path) -> Result<()> {
+        if path == self.path {
+            bail!("Backup path is the live database");
+        }
+        if !path.is_file() {
+            bail!("Backup file {} not found", path.display());
+        }
+
+        let source = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .with_context(|| format!("Failed to open backup file {}", path.display()))?;
+        apply_key(&source, self.key.as_deref())?;
+        let version = backup_schema_version(&source)
+            .with_context(|| format!("{} is not a database backup", path.display()))?;
+        if version > schema::SCHEMA_VERSION {
+            bail!(
+                "Backup schema version {} is newer than this build supports ({})",
+                version,
+                schema::SCHEMA_VERSION
+            );
+        }
+
+        let mut conn = self
+            .writer
+            .lock()
+            .map_err(|_| anyhow!("Database connection lock poisoned"))?;
+        Backup::new(&source, &mut conn)
+            .context("Failed to start restore")?
+            .run_to_completion(BACKUP_PAGES_PER_STEP, BACKUP_STEP_PAUSE, None)
+            .context("Restore failed")?;
+        let result = Self::initialize_connection(&conn);
+        self.write_generation.fetch_add(1, Ordering::Release);
+        result?;
+
+        tracing::info!(
+            "Restored database from {} (schema version {})",
+            path.display(),
+            version
+        );
+        Ok(())
+    }
+
+    /// Check integrity, refresh query planner statistics and compact the file
+    ///
+    /// Holds the writer for the duration, so scans and monitor writes wait;
+    /// readers keep working. VACUUM is skipped when the integrity check
+    /// finds problems, leaving the file as it was for recovery.
+    pub fn maintenance(&self) -> Result<MaintenanceReport> {
+        let started = Instant::now();
+        let conn = self.writer()?;
+        let size = |conn: &Connection| -> Result<u64> {
+            let pages: i64 = conn.query_row("PRAGMA page_count", [], |row| row.get(0))?;
+            let page_size: i64 = conn.query_row("PRAGMA page_size", [], |row| row.get(0))?;
+            Ok(u64::try_from(pages * page_size)?)
+        };
+        let size_before_bytes = size(&conn)?;
+
+        let mut stmt =
+            conn.prepare(&format!("PRAGMA integrity_check({})", MAX_INTEGRITY_ERRORS))?;
+        let integrity_errors = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .context("Integrity check failed to run")?
+            .into_iter()
+            .filter(|line| line != "ok")
+            .collect::<Vec<_>>();
+        drop(stmt);
+
+        conn.execute_batch("ANALYZE").context("ANALYZE failed")?;
+
+        let vacuumed = integrity_errors.is_empty();
+        if vacuumed {
+            conn.execute_batch("VACUUM").context("VACUUM failed")?;
+            if self.path.as_os_str() != ":memory:" {
+                // Also shrink the WAL file back down
+                conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))
+                    .context("WAL checkpoint failed")?;
+            }
+        } else {
+            tracing::warn!(
+                "Database integrity check found {} problem(s); skipping VACUUM",
+                integrity_errors.len()
+            );
+        }
+
+        let report = MaintenanceReport {
+            integrity_errors,
+            vacuumed,
+            size_before_bytes,
+            size_after_bytes: size(&conn)?,
+            duration_ms: started.elapsed().as_millis() as u64,
+        };
+        tracing::info!(
+            "Database maintenance done in {} ms ({} -> {} bytes)",
+            report.duration_ms,
+            report.size_before_bytes,
+            report.size_after_bytes
+        );
+        Ok(report)
+    }
+
+    /// Lock the writer connection, for anything that modifies the database
+    pub fn writer(&self) -> Result<ConnectionGuard<'_>> {
+        self.lock_writer(Some(&self.write_generation))
+    }
+
+    fn lock_writer<'a>(&'a self, generation: Option<&'a AtomicU64>) -> Result<ConnectionGuard<'a>> {
+        let conn = self
+            .writer
+            .lock()
+            .map_err(|_| anyhow!("Database connection lock poisoned"))?;
+        Ok(ConnectionGuard {
+            inner: GuardInner::Writer { conn, generation },
+        })
+    }
+
+    /// Check out a read-only connection, waiting for one to be free
+    ///
+    /// In-memory databases have no separate readers and lock the writer.
+    pub fn reader(&self) -> Result<ConnectionGuard<'_>> {
+        if self.path.as_os_str() == ":memory:" {
+            return self.lock_writer(None);
+        }
+
+        let mut idle = self
+            .readers
+            .idle
+            .lock()
+            .map_err(|_| anyhow!("Database reader pool lock poisoned"))?;
+        loop {
+            if let Some((slot, conn)) = idle.pop() {
+                return Ok(ConnectionGuard {
+                    inner: GuardInner::Reader {
+                        slot,
+                        conn: Some(conn),
+                        pool: &self.readers,
+                    },
+                });
+            }
+            idle = self
+                .readers
+                .available
+                .wait(idle)
+                .map_err(|_| anyhow!("Database reader pool lock poisoned"))?;
+        }
+    }
+
+    /// Run a read-only query on a pooled connection
+    pub fn read<T>(&self, f: impl FnOnce(&Connection) -> Result<T>) -> Result<T> {
+        f(&*self.reader()?)
+    }
+
+    /// Run a write on the writer connection
+    pub fn write<T>(&self, f: impl FnOnce(&Connection) -> Result<T>) -> Result<T> {
+        f(&*self.writer()?)
+    }
+
+    /// Number of times a write connection has been released
+    ///
+    /// Changes after every write made through this database (and its
+    /// clones), for invalidating cached reads.
+    pub fn write_generation(&self) -> u64 {
+        self.write_generation.load(Ordering::Acquire)
     }
 
     /// Get database path
@@ -104,11 +545,90 @@ impl Database {
     }
 }
 
+/// Set the SQLCipher key on a freshly opened connection and check it
+fn apply_key(conn: &Connection, key: Option<&str>) -> Result<()> {
+    let Some(key) = key else {
+        return Ok(());
+    };
+    conn.pragma_update(None, "key", key)
+        .context("Failed to set database key")?;
+    // SQLCipher only checks the key on first access
+    conn.query_row("SELECT COUNT(*) FROM sqlite_master", [], |row| {
+        row.get::<_, i64>(0)
+    })
+    .context("Wrong database key, or the file is not an encrypted database")?;
+    Ok(())
+}
+
+/// Encrypt an existing unencrypted database file in place
+///
+/// Does nothing for a missing, empty or already encrypted file.
+fn encrypt_plain_file(path: &Path, key: &str) -> Result<()> {
+    let mut header = [0u8; 16];
+    let is_plain = match std::fs::File::open(path) {
+        Ok(mut file) => file.read_exact(&mut header).is_ok() && &header == SQLITE_HEADER,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => false,
+        Err(e) => return Err(e).context("Failed to read database file"),
+    };
+    if !is_plain {
+        return Ok(());
+    }
+
+    let mut encrypted = path.as_os_str().to_owned();
+    encrypted.push(".encrypting");
+    let encrypted = PathBuf::from(encrypted);
+    let _ = std::fs::remove_file(&encrypted);
+
+    let conn = Connection::open(path).context("Failed to open database for encryption")?;
+    conn.execute(
+        "ATTACH DATABASE ?1 AS encrypted KEY ?2",
+        params![encrypted.to_string_lossy(), key],
+    )
+    .context("Failed to create encrypted database")?;
+    conn.query_row("SELECT sqlcipher_export('encrypted')", [], |_| Ok(()))
+        .context("Failed to encrypt database")?;
+    conn.execute_batch("DETACH DATABASE encrypted")?;
+    // Closing the last connection checkpoints and removes the WAL
+    conn.close()
+        .map_err(|(_, e)| e)
+        .context("Failed to close database")?;
+
+    std::fs::rename(&encrypted, path).context("Failed to replace database with encrypted copy")?;
+    tracing::info!("Encrypted database {} at rest", path.display());
+    Ok(())
+}
+
+/// Schema version recorded in a backup, without writing to it
+fn backup_schema_version(conn: &Connection) -> Result<u32> {
+    let has_versions: Option<i64> = conn
+        .query_row(
+            "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'schema_version'",
+            [],
+            |row| row.get(0),
+        )
+        .optional()?;
+    if has_versions.is_none() {
+        bail!("No schema version table");
+    }
+
+    let version: Option<u32> =
+        conn.query_row("SELECT MAX(version) FROM schema_version", [], |row| {
+            row.get(0)
+        })?;
+    match version {
+        Some(version) if version > 0 => Ok(version),
+        _ => bail!("No schema version recorded"),
+    }
+}
+
 impl Clone for Database {
     fn clone(&self) -> Self {
         Self {
-            conn: Arc::clone(&self.conn),
+            writer: Arc::clone(&self.writer),
+            readers: Arc::clone(&self.readers),
+            write_generation: Arc::clone(&self.write_generation),
             path: self.path.clone(),
+            key: self.key.clone(),
         }
     }
 }
@@ -128,4 +648,123 @@ mod tests {
         let path = Database::default_path();
         assert!(path.to_str().unwrap().contains("NetworkTopologyMapper"));
     }
+
+    #[test]
+    fn test_readers_see_writes_while_writer_is_busy() {
+        let dir = std::env::temp_dir().join(format!("db-pool-test-{}", std::process::id()));
+        let db = Database::new(dir.join("data.db")).unwrap();
+
+        let before = db.write_generation();
+        db.write(|conn| {
+            conn.execute("INSERT INTO devices (mac) VALUES ('AA:BB:CC:DD:EE:01')", [])?;
+            Ok(())
+        })
+        .unwrap();
+        assert!(db.write_generation() > before);
+
+        // A held writer does not block reads
+        let writer = db.writer().unwrap();
+        let clone = db.clone();
+        let count = std::thread::spawn(move || {
+            let reader = clone.reader().unwrap();
+            assert_ne!(reader.id(), 0);
+            reader
+                .query_row("SELECT COUNT(*) FROM devices", [], |row| {
+                    row.get::<_, i64>(0)
+                })
+                .unwrap()
+        })
+        .join()
+        .unwrap();
+        assert_eq!(count, 1);
+        drop(writer);
+
+        let reader = db.reader().unwrap();
+        assert!(reader.execute("DELETE FROM devices", []).is_err());
+        drop(reader);
+
+        drop(db);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_maintenance() {
+        let dir = std::env::temp_dir().join(format!("db-maintenance-test-{}", std::process::id()));
+        let db = Database::new(dir.join("data.db")).unwrap();
+        db.write(|conn| {
+            for i in 0..200 {
+                conn.execute(
+                    "INSERT INTO devices (mac, notes) VALUES (?1, ?2)",
+                    params![
+                        format!("AA:BB:CC:DD:{:02X}:{:02X}", i / 256, i % 256),
+                        "x".repeat(2000)
+                    ],
+                )?;
+            }
+            conn.execute("DELETE FROM devices", [])?;
+            Ok(())
+        })
+        .unwrap();
+
+        let report = db.maintenance().unwrap();
+        assert!(report.is_healthy());
+        assert!(report.vacuumed);
+        assert!(report.size_after_bytes < report.size_before_bytes);
+
+        // In-memory databases (no WAL) are maintained too
+        let memory = Database::in_memory().unwrap();
+        assert!(memory.maintenance().unwrap().vacuumed);
+
+        drop(db);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_backup_and_restore() {
+        let dir = std::env::temp_dir().join(format!("db-backup-test-{}", std::process::id()));
+        let db = Database::new(dir.join("data.db")).unwrap();
+        let backup = dir.join("backups").join("data-backup.db");
+        let count = |db: &Database| {
+            db.read(|conn| {
+                Ok(conn.query_row("SELECT COUNT(*) FROM devices", [], |row| {
+                    row.get::<_, i64>(0)
+                })?)
+            })
+            .unwrap()
+        };
+        let insert = |db: &Database, mac: &str| {
+            db.write(|conn| {
+                conn.execute("INSERT INTO devices (mac) VALUES (?1)", [mac])?;
+                Ok(())
+            })
+            .unwrap()
+        };
+
+        insert(&db, "AA:BB:CC:DD:EE:01");
+        db.backup_to(&backup).unwrap();
+        insert(&db, "AA:BB:CC:DD:EE:02");
+        assert_eq!(count(&db), 2);
+
+        let before = db.write_generation();
+        db.restore_from(&backup).unwrap();
+        assert_eq!(count(&db), 1);
+        assert!(db.write_generation() > before);
+
+        // Backups from a newer build are refused
+        let newer = Connection::open(&backup).unwrap();
+        newer
+            .execute(
+                "INSERT INTO schema_version (version, description) VALUES (?1, 'future')",
+                [schema::SCHEMA_VERSION + 1],
+            )
+            .unwrap();
+        drop(newer);
+        assert!(db.restore_from(&backup).is_err());
+        assert!(db.restore_from(&dir.join("missing.db")).is_err());
+        assert!(db.backup_to(db.path()).is_err());
+        assert_eq!(count(&db), 1);
+
+        drop(db);
+        let _ = std::fs::remove_dir_all(dir);
+    }
 }
@@ -0,0 +1,315 @@
+//! Whole-database JSON export and import
+//!
+//! The dump holds the rows of the history tables (scans, devices and their
+//! per-scan history, alerts, monitor events, internet health checks and
+//! presence sessions, plus what hangs off them) as plain JSON objects keyed by
+//! column name, so it can be inspected outside SQLite and carried to another
+//! install. It records the schema version it was taken at; a dump
+//! from an older build imports into a newer one (columns added since then
+//! take their defaults), one from a newer build is refused.
+
+use anyhow::{bail, Context, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use chrono::{DateTime, Utc};
+use rusqlite::types::{Value as SqlValue, ValueRef};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+use super::schema::{self, SCHEMA_VERSION};
+
+/// Identifies a database dump document
+pub const DUMP_FORMAT: &str = "ntm-database-dump";
+
+/// Version of the dump document layout (not the database schema)
+pub const DUMP_FORMAT_VERSION: u32 = 1;
+
+/// Tables in a dump, parents before the tables referring to them
+const DUMP_TABLES: [&str; 15] = [
+    "networks",
+    "scans",
+    "devices",
+    "device_history",
+    "device_port_events",
+    "device_history_daily",
+    "scan_daily",
+    "device_tags",
+    "device_attributes",
+    "alerts",
+    "network_events",
+    "internet_health",
+    "presence_sessions",
+    "device_certificates",
+    "device_ssh_keys",
+];
+
+/// A whole-database export
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatabaseDump {
+    pub format: String,
+    pub format_version: u32,
+    /// Database schema version the rows were read from
+    pub schema_version: u32,
+    pub exported_at: DateTime<Utc>,
+    /// Rows of each table, as column name to value
+    pub tables: BTreeMap<String, Vec<Map<String, Value>>>,
+}
+
+/// Rows written per table by [`import_database_json`]
+pub type ImportSummary = BTreeMap<String, usize>;
+
+/// Columns of a table in the live schema
+fn table_columns(conn: &Connection, table: &str) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let columns = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(columns)
+}
+
+fn to_json(value: ValueRef<'_>) -> Value {
+    match value {
+        ValueRef::Null => Value::Null,
+        ValueRef::Integer(i) => Value::from(i),
+        ValueRef::Real(f) => Value::from(f),
+        ValueRef::Text(text) => Value::String(String::from_utf8_lossy(text).into_owned()),
+        ValueRef::Blob(bytes) => serde_json::json!({ "base64": BASE64.encode(bytes) }),
+    }
+}
+
+fn from_json(table: &str, column: &str, value: &Value) -> Result<SqlValue> {
+    Ok(match value {
+        Value::Null => SqlValue::Null,
+        Value::Bool(b) => SqlValue::Integer(i64::from(*b)),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => SqlValue::Integer(i),
+            None => SqlValue::Real(n.as_f64().unwrap_or_default()),
+        },
+        Value::String(s) => SqlValue::Text(s.clone()),
+        Value::Object(object) => match object.get("base64").and_then(Value::as_str) {
+            Some(encoded) => SqlValue::Blob(
+                BASE64
+                    .decode(encoded)
+                    .with_context(|| format!("Invalid blob in {}.{}", table, column))?,
+            ),
+            None => bail!("Unexpected object in {}.{}", table, column),
+        },
+        Value::Array(_) => bail!("Unexpected array in {}.{}", table, column),
+    })
+}
+
+/// Read the history tables into a dump document
+pub fn dump_database(conn: &Connection) -> Result<DatabaseDump> {
+    let mut tables = BTreeMap::new();
+    for table in DUMP_TABLES {
+        let mut stmt = conn.prepare(&format!("SELECT * FROM {} ORDER BY rowid", table))?;
+        let columns: Vec<String> = stmt.column_names().into_iter().map(String::from).collect();
+        let mut rows = stmt.query([])?;
+        let mut dumped = Vec::new();
+        while let Some(row) = rows.next()? {
+            let mut object = Map::new();
+            for (index, column) in columns.iter().enumerate() {
+                object.insert(column.clone(), to_json(row.get_ref(index)?));
+            }
+            dumped.push(object);
+        }
+        tables.insert(table.to_string(), dumped);
+    }
+
+    Ok(DatabaseDump {
+        format: DUMP_FORMAT.to_string(),
+        format_version: DUMP_FORMAT_VERSION,
+        schema_version: schema::schema_version(conn)?,
+        exported_at: Utc::now(),
+        tables,
+    })
+}
+
+/// Export devices, history, scans and alerts as a pretty-printed JSON document
+pub fn export_database_json(conn: &Connection) -> Result<String> {
+    serde_json::to_string_pretty(&dump_database(conn)?).context("Failed to encode database dump")
+}
+
+/// Load a document made by [`export_database_json`]
+///
+/// Meant for a fresh install: the database must not have any scans or
+/// devices yet. Everything is written in one transaction, so a failed
+/// import leaves the database empty.
+pub fn import_database_json(conn: &Connection, json: &str) -> Result<ImportSummary> {
+    let dump: DatabaseDump = serde_json::from_str(json).context("Not a database dump")?;
+    if dump.format != DUMP_FORMAT {
+        bail!("Not a database dump (format {:?})", dump.format);
+    }
+    if dump.format_version > DUMP_FORMAT_VERSION {
+        bail!(
+            "Dump format version {} is newer than this build supports ({})",
+            dump.format_version,
+            DUMP_FORMAT_VERSION
+        );
+    }
+    if dump.schema_version > SCHEMA_VERSION {
+        bail!(
+            "Dump schema version {} is newer than this build supports ({})",
+            dump.schema_version,
+            SCHEMA_VERSION
+        );
+    }
+    if let Some(table) = dump
+        .tables
+        .keys()
+        .find(|t| !DUMP_TABLES.contains(&t.as_str()))
+    {
+        bail!("Unexpected table {} in database dump", table);
+    }
+
+    let existing: i64 = conn.query_row(
+        "SELECT (SELECT COUNT(*) FROM scans) + (SELECT COUNT(*) FROM devices)",
+        [],
+        |row| row.get(0),
+    )?;
+    if existing > 0 {
+        bail!("Database already has scans or devices; import into a fresh database");
+    }
+
+    conn.execute_batch("SAVEPOINT import_database")
+        .context("Failed to start import_database transaction")?;
+
+    let result = (|| -> Result<ImportSummary> {
+        let mut summary = ImportSummary::new();
+        for table in DUMP_TABLES {
+            let Some(rows) = dump.tables.get(table) else {
+                continue;
+            };
+            let columns = table_columns(conn, table)?;
+            for row in rows {
+                if let Some(column) = row.keys().find(|c| !columns.contains(c)) {
+                    bail!("Unknown column {}.{} in database dump", table, column);
+                }
+                let names: Vec<&str> = row.keys().map(String::as_str).collect();
+                let values = row
+                    .iter()
+                    .map(|(column, value)| from_json(table, column, value))
+                    .collect::<Result<Vec<_>>>()?;
+                let placeholders = (1..=names.len())
+                    .map(|i| format!("?{}", i))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                conn.execute(
+                    &format!(
+                        "INSERT INTO {} ({}) VALUES ({})",
+                        table,
+                        names.join(", "),
+                        placeholders
+                    ),
+                    rusqlite::params_from_iter(values),
+                )
+                .with_context(|| format!("Failed to import a row of {}", table))?;
+            }
+            summary.insert(table.to_string(), rows.len());
+        }
+        Ok(summary)
+    })();
+
+    match result {
+        Ok(summary) => {
+            conn.execute_batch("RELEASE SAVEPOINT import_database")
+                .context("Failed to commit import_database transaction")?;
+            tracing::info!(
+                "Imported database dump (schema version {}): {:?}",
+                dump.schema_version,
+                summary
+            );
+            Ok(summary)
+        }
+        Err(e) => {
+            let _ = conn.execute_batch(
+                "ROLLBACK TO SAVEPOINT import_database; RELEASE SAVEPOINT import_database",
+            );
+            Err(e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::queries::{
+        add_tag, get_all_devices, get_recent_scans, get_unread_alerts, insert_alert, insert_scan,
+        set_device_attribute,
+    };
+    use crate::database::{AlertSeverity, AlertType, Database};
+    use crate::models::{HostInfo, ScanResult};
+
+    #[test]
+    fn test_export_import_roundtrip() {
+        let source = Database::in_memory().unwrap();
+        let conn = source.writer().unwrap();
+        let host = HostInfo::new(
+            "192.168.1.40".to_string(),
+            "AA:BB:CC:DD:EE:40".to_string(),
+            "PC".to_string(),
+            "ARP".to_string(),
+        );
+        let result = ScanResult {
+            interface_name: "eth0".to_string(),
+            local_ip: "192.168.1.1".to_string(),
+            local_mac: "AA:BB:CC:DD:EE:FF".to_string(),
+            subnet: "192.168.1.0/24".to_string(),
+            scan_method: "arp".to_string(),
+            arp_discovered: 1,
+            icmp_discovered: 0,
+            total_hosts: 1,
+            scan_duration_ms: 100,
+            active_hosts: vec![host],
+            wan: None,
+            ssid: None,
+        };
+        insert_scan(&conn, &result).unwrap();
+        add_tag(&conn, "AA:BB:CC:DD:EE:40", "work").unwrap();
+        set_device_attribute(&conn, "AA:BB:CC:DD:EE:40", "Owner", "Dana").unwrap();
+        insert_alert(
+            &conn,
+            AlertType::NewDevice,
+            None,
+            Some("AA:BB:CC:DD:EE:40"),
+            Some("192.168.1.40"),
+            "New device",
+            AlertSeverity::Info,
+        )
+        .unwrap();
+
+        let json = export_database_json(&conn).unwrap();
+        let dump: DatabaseDump = serde_json::from_str(&json).unwrap();
+        assert_eq!(dump.schema_version, SCHEMA_VERSION);
+        assert_eq!(dump.tables["devices"].len(), 1);
+
+        let target = Database::in_memory().unwrap();
+        let imported = target.writer().unwrap();
+        let summary = import_database_json(&imported, &json).unwrap();
+        assert_eq!(summary["scans"], 1);
+        assert_eq!(summary["alerts"], 1);
+
+        let devices = get_all_devices(&imported).unwrap();
+        assert_eq!(devices.len(), 1);
+        assert_eq!(devices[0].tags, vec!["work".to_string()]);
+        assert_eq!(devices[0].attributes["Owner"], "Dana");
+        assert_eq!(get_recent_scans(&imported, 10).unwrap().len(), 1);
+        assert_eq!(get_unread_alerts(&imported).unwrap().len(), 1);
+        assert_eq!(
+            crate::database::search_devices(&imported, "dana")
+                .unwrap()
+                .len(),
+            1
+        );
+
+        // Only into an empty database, and never from a newer schema
+        assert!(import_database_json(&imported, &json).is_err());
+        let mut newer = dump.clone();
+        newer.schema_version = SCHEMA_VERSION + 1;
+        let empty = Database::in_memory().unwrap();
+        let empty = empty.writer().unwrap();
+        assert!(import_database_json(&empty, &serde_json::to_string(&newer).unwrap()).is_err());
+        assert!(import_database_json(&empty, "{}").is_err());
+    }
+}
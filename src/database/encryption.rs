@@ -2,12 +2,18 @@
 //!
 //! Provides encryption/decryption for database exports without requiring SQLCipher.
 //! This works on all platforms and avoids Windows build issues.
+//!
+//! Exports are either machine-bound (key derived from this machine's ID, so
+//! they only open here) or passphrase-protected (key derived with Argon2id
+//! from a passphrase and a random salt, so they open anywhere). A short
+//! header records which; files without it are older machine-bound exports.
 
 use aes_gcm::{
-    aead::{Aead, KeyInit, OsRng},
+    aead::{Aead, KeyInit, OsRng, Payload},
     Aes256Gcm, Key, Nonce,
 };
 use argon2::{Algorithm, Argon2, Params, Version};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
 use sha2::{Digest, Sha256};
 use std::error::Error;
 use std::fs;
@@ -19,6 +25,24 @@ const ARGON2_MEMORY_KIB: u32 = 64 * 1024;
 const ARGON2_ITERATIONS: u32 = 3;
 const ARGON2_PARALLELISM: u32 = 1;
 
+/// Marks an export written with a format header
+const EXPORT_MAGIC: &[u8; 6] = b"NTMENC";
+const EXPORT_FORMAT_VERSION: u8 = 1;
+const MODE_MACHINE_BOUND: u8 = 1;
+const MODE_PASSPHRASE: u8 = 2;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const MIN_PASSPHRASE_LEN: usize = 8;
+
+/// How an encrypted export is protected
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportProtection {
+    /// Only opens on the machine that wrote it
+    MachineBound,
+    /// Opens anywhere with the passphrase
+    Passphrase,
+}
+
 /// Generate encryption key from machine ID
 ///
 /// Same as before but now used for AES encryption
@@ -67,6 +91,11 @@ fn derive_legacy_key_from_machine_material(
 
 /// Derive 256-bit key from any string using Argon2id.
 fn derive_key_from_string_argon2(input: &str) -> Result<[u8; 32], Box<dyn Error>> {
+    derive_key_argon2(input, ARGON2_SALT)
+}
+
+/// Derive 256-bit key from a string and salt using Argon2id.
+fn derive_key_argon2(input: &str, salt: &[u8]) -> Result<[u8; 32], Box<dyn Error>> {
     let params = Params::new(
         ARGON2_MEMORY_KIB,
         ARGON2_ITERATIONS,
@@ -77,7 +106,7 @@ fn derive_key_from_string_argon2(input: &str) -> Result<[u8; 32], Box<dyn Error>
     let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
     let mut key = [0u8; 32];
     argon2
-        .hash_password_into(input.as_bytes(), ARGON2_SALT, &mut key)
+        .hash_password_into(input.as_bytes(), salt, &mut key)
         .map_err(|e| format!("Argon2 key derivation failed: {}", e))?;
     Ok(key)
 }
@@ -96,9 +125,80 @@ fn derive_key_from_string_legacy_sha256(input: &str) -> Result<[u8; 32], Box<dyn
 
 /// Encrypt database file using AES-256-GCM
 ///
-/// Creates an encrypted copy of the database with .encrypted extension
+/// Creates an encrypted copy of the database with .encrypted extension,
+/// bound to this machine
 pub fn encrypt_database_file<P: AsRef<Path>>(db_path: P) -> Result<String, Box<dyn Error>> {
-    let db_path = db_path.as_ref();
+    let key_bytes = get_encryption_key()?;
+    let header = export_header(MODE_MACHINE_BOUND, &[]);
+    write_encrypted_export(db_path.as_ref(), &key_bytes, &header)
+}
+
+/// Encrypt database file with a key derived from `passphrase`
+///
+/// Unlike [`encrypt_database_file`], the export can be opened on any machine
+/// with the passphrase.
+pub fn encrypt_database_file_with_passphrase<P: AsRef<Path>>(
+    db_path: P,
+    passphrase: &str,
+) -> Result<String, Box<dyn Error>> {
+    if passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+        return Err(format!(
+            "Passphrase must be at least {} characters",
+            MIN_PASSPHRASE_LEN
+        )
+        .into());
+    }
+
+    let mut salt = [0u8; SALT_LEN];
+    {
+        use aes_gcm::aead::rand_core::RngCore;
+        OsRng.fill_bytes(&mut salt);
+    }
+    let key_bytes = derive_key_argon2(passphrase, &salt)?;
+    let header = export_header(MODE_PASSPHRASE, &salt);
+    write_encrypted_export(db_path.as_ref(), &key_bytes, &header)
+}
+
+/// How an encrypted export is protected, read from its header
+pub fn export_protection<P: AsRef<Path>>(
+    encrypted_path: P,
+) -> Result<ExportProtection, Box<dyn Error>> {
+    let data = fs::read(encrypted_path)?;
+    Ok(parse_export(&data)?.protection)
+}
+
+/// Decrypt database file using AES-256-GCM
+///
+/// Decrypts a machine-bound .encrypted file back to .db
+pub fn decrypt_database_file<P: AsRef<Path>>(encrypted_path: P) -> Result<String, Box<dyn Error>> {
+    decrypt_export(encrypted_path.as_ref(), None)
+}
+
+/// Decrypt a passphrase-protected .encrypted file back to .db
+///
+/// Machine-bound exports are accepted too; the passphrase is then unused.
+pub fn decrypt_database_file_with_passphrase<P: AsRef<Path>>(
+    encrypted_path: P,
+    passphrase: &str,
+) -> Result<String, Box<dyn Error>> {
+    decrypt_export(encrypted_path.as_ref(), Some(passphrase))
+}
+
+/// Header for the given mode: magic, format version, mode, then mode data
+fn export_header(mode: u8, extra: &[u8]) -> Vec<u8> {
+    let mut header = Vec::with_capacity(EXPORT_MAGIC.len() + 2 + extra.len());
+    header.extend_from_slice(EXPORT_MAGIC);
+    header.push(EXPORT_FORMAT_VERSION);
+    header.push(mode);
+    header.extend_from_slice(extra);
+    header
+}
+
+fn write_encrypted_export(
+    db_path: &Path,
+    key_bytes: &[u8; 32],
+    header: &[u8],
+) -> Result<String, Box<dyn Error>> {
     let encrypted_path = db_path.with_extension("db.encrypted");
 
     tracing::info!("Encrypting database: {:?} -> {:?}", db_path, encrypted_path);
@@ -106,22 +206,27 @@ pub fn encrypt_database_file<P: AsRef<Path>>(db_path: P) -> Result<String, Box<d
     // Read database file
     let plaintext = fs::read(db_path)?;
 
-    // Generate encryption key
-    let key_bytes = get_encryption_key()?;
-    let key = Key::<Aes256Gcm>::from_slice(&key_bytes);
+    let key = Key::<Aes256Gcm>::from_slice(key_bytes);
     let cipher = Aes256Gcm::new(key);
 
     // Generate random nonce (96 bits for GCM)
     let nonce_bytes = generate_nonce();
     let nonce = Nonce::from_slice(&nonce_bytes);
 
-    // Encrypt
+    // Encrypt, authenticating the header so the mode can't be swapped
     let ciphertext = cipher
-        .encrypt(nonce, plaintext.as_ref())
+        .encrypt(
+            nonce,
+            Payload {
+                msg: &plaintext,
+                aad: header,
+            },
+        )
         .map_err(|e| format!("Encryption failed: {}", e))?;
 
-    // Prepend nonce to ciphertext (needed for decryption)
-    let mut output = Vec::new();
+    // Header, then nonce (needed for decryption), then ciphertext
+    let mut output = Vec::with_capacity(header.len() + NONCE_LEN + ciphertext.len());
+    output.extend_from_slice(header);
     output.extend_from_slice(&nonce_bytes);
     output.extend_from_slice(&ciphertext);
 
@@ -136,42 +241,101 @@ pub fn encrypt_database_file<P: AsRef<Path>>(db_path: P) -> Result<String, Box<d
     Ok(encrypted_path.to_string_lossy().to_string())
 }
 
-/// Decrypt database file using AES-256-GCM
-///
-/// Decrypts a .encrypted file back to .db
-pub fn decrypt_database_file<P: AsRef<Path>>(encrypted_path: P) -> Result<String, Box<dyn Error>> {
-    let encrypted_path = encrypted_path.as_ref();
-    let db_path = encrypted_path.with_extension("db");
-
-    tracing::info!("Decrypting database: {:?} -> {:?}", encrypted_path, db_path);
+/// Parts of an encrypted export
+struct ParsedExport<'a> {
+    protection: ExportProtection,
+    /// Empty for headerless (older) exports
+    header: &'a [u8],
+    salt: &'a [u8],
+    nonce: &'a [u8],
+    ciphertext: &'a [u8],
+}
 
-    // Read encrypted file
-    let data = fs::read(encrypted_path)?;
+fn parse_export(data: &[u8]) -> Result<ParsedExport<'_>, Box<dyn Error>> {
+    if !data.starts_with(EXPORT_MAGIC) {
+        // Older exports: nonce followed by ciphertext, always machine-bound
+        if data.len() < NONCE_LEN {
+            return Err("Invalid encrypted file: too short".into());
+        }
+        return Ok(ParsedExport {
+            protection: ExportProtection::MachineBound,
+            header: &[],
+            salt: &[],
+            nonce: &data[..NONCE_LEN],
+            ciphertext: &data[NONCE_LEN..],
+        });
+    }
 
-    if data.len() < 12 {
+    let fixed = EXPORT_MAGIC.len() + 2;
+    if data.len() < fixed {
+        return Err("Invalid encrypted file: too short".into());
+    }
+    if data[EXPORT_MAGIC.len()] != EXPORT_FORMAT_VERSION {
+        return Err(format!(
+            "Unsupported encrypted file version {}",
+            data[EXPORT_MAGIC.len()]
+        )
+        .into());
+    }
+    let (protection, salt_len) = match data[EXPORT_MAGIC.len() + 1] {
+        MODE_MACHINE_BOUND => (ExportProtection::MachineBound, 0),
+        MODE_PASSPHRASE => (ExportProtection::Passphrase, SALT_LEN),
+        mode => return Err(format!("Unknown encryption mode {}", mode).into()),
+    };
+
+    let header_len = fixed + salt_len;
+    if data.len() < header_len + NONCE_LEN {
         return Err("Invalid encrypted file: too short".into());
     }
+    Ok(ParsedExport {
+        protection,
+        header: &data[..header_len],
+        salt: &data[fixed..header_len],
+        nonce: &data[header_len..header_len + NONCE_LEN],
+        ciphertext: &data[header_len + NONCE_LEN..],
+    })
+}
 
-    // Extract nonce (first 12 bytes)
-    let nonce_bytes = &data[..12];
-    let nonce = Nonce::from_slice(nonce_bytes);
+fn decrypt_export(
+    encrypted_path: &Path,
+    passphrase: Option<&str>,
+) -> Result<String, Box<dyn Error>> {
+    let db_path = encrypted_path.with_extension("db");
 
-    // Extract ciphertext (rest of file)
-    let ciphertext = &data[12..];
+    tracing::info!("Decrypting database: {:?} -> {:?}", encrypted_path, db_path);
 
-    // Try decrypting with the current Argon2 key first, then legacy SHA-256 key.
-    let mut candidates = vec![get_encryption_key()?];
-    let legacy = get_legacy_encryption_key()?;
-    if !candidates.iter().any(|k| k == &legacy) {
-        candidates.push(legacy);
-    }
+    // Read encrypted file
+    let data = fs::read(encrypted_path)?;
+    let export = parse_export(&data)?;
+    let nonce = Nonce::from_slice(export.nonce);
+
+    let candidates = match export.protection {
+        ExportProtection::Passphrase => {
+            let passphrase = passphrase
+                .ok_or("This export is protected by a passphrase; enter it to decrypt")?;
+            vec![derive_key_argon2(passphrase, export.salt)?]
+        }
+        ExportProtection::MachineBound => {
+            // Try decrypting with the current Argon2 key first, then legacy SHA-256 key.
+            let mut candidates = vec![get_encryption_key()?];
+            let legacy = get_legacy_encryption_key()?;
+            if !candidates.iter().any(|k| k == &legacy) {
+                candidates.push(legacy);
+            }
+            candidates
+        }
+    };
 
     let mut plaintext: Option<Vec<u8>> = None;
     let mut last_error = String::new();
     for key_bytes in candidates {
         let key = Key::<Aes256Gcm>::from_slice(&key_bytes);
         let cipher = Aes256Gcm::new(key);
-        match cipher.decrypt(nonce, ciphertext) {
+        let payload = Payload {
+            msg: export.ciphertext,
+            aad: export.header,
+        };
+        match cipher.decrypt(nonce, payload) {
             Ok(bytes) => {
                 plaintext = Some(bytes);
                 break;
@@ -182,15 +346,16 @@ pub fn decrypt_database_file<P: AsRef<Path>>(encrypted_path: P) -> Result<String
         }
     }
 
-    let plaintext = plaintext.ok_or_else(|| {
-        format!(
+    let plaintext = plaintext.ok_or_else(|| match export.protection {
+        ExportProtection::Passphrase => "Decryption failed: wrong passphrase".to_string(),
+        ExportProtection::MachineBound => format!(
             "Decryption failed with all supported key derivation strategies. Last error: {}",
             if last_error.is_empty() {
                 "unknown"
             } else {
                 &last_error
             }
-        )
+        ),
     })?;
 
     // Write decrypted database
@@ -201,10 +366,41 @@ pub fn decrypt_database_file<P: AsRef<Path>>(encrypted_path: P) -> Result<String
     Ok(db_path.to_string_lossy().to_string())
 }
 
+/// Encrypt a short secret (e.g. an SMTP password) for storing in settings
+///
+/// Machine-bound like [`encrypt_database_file`]; returns base64 of the nonce
+/// followed by the ciphertext.
+pub fn encrypt_secret(secret: &str) -> Result<String, Box<dyn Error>> {
+    let key_bytes = get_encryption_key()?;
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key_bytes));
+    let nonce_bytes = generate_nonce();
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce_bytes), secret.as_bytes())
+        .map_err(|e| format!("Encryption failed: {}", e))?;
+
+    let mut output = nonce_bytes.to_vec();
+    output.extend_from_slice(&ciphertext);
+    Ok(BASE64.encode(output))
+}
+
+/// Decrypt a secret written by [`encrypt_secret`] on this machine
+pub fn decrypt_secret(encoded: &str) -> Result<String, Box<dyn Error>> {
+    let data = BASE64.decode(encoded.trim())?;
+    if data.len() < NONCE_LEN {
+        return Err("Invalid encrypted secret: too short".into());
+    }
+    let key_bytes = get_encryption_key()?;
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key_bytes));
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(&data[..NONCE_LEN]), &data[NONCE_LEN..])
+        .map_err(|e| format!("Decryption failed: {}", e))?;
+    Ok(String::from_utf8(plaintext)?)
+}
+
 /// Generate a random 96-bit nonce for AES-GCM
-fn generate_nonce() -> [u8; 12] {
+fn generate_nonce() -> [u8; NONCE_LEN] {
     use aes_gcm::aead::rand_core::RngCore;
-    let mut nonce = [0u8; 12];
+    let mut nonce = [0u8; NONCE_LEN];
     OsRng.fill_bytes(&mut nonce);
     nonce
 }
@@ -255,6 +451,50 @@ mod tests {
         let _ = fs::remove_file(&decrypted_path);
     }
 
+    #[test]
+    fn test_secret_roundtrip() {
+        let encrypted = encrypt_secret("smtp password").unwrap();
+        assert!(!encrypted.contains("smtp"));
+        assert_eq!(decrypt_secret(&encrypted).unwrap(), "smtp password");
+        assert!(decrypt_secret("AAAA").is_err());
+    }
+
+    #[test]
+    fn test_passphrase_export_roundtrip() {
+        let test_db = "test_passphrase_encryption.db";
+        let test_data = b"portable export";
+        fs::write(test_db, test_data).unwrap();
+
+        assert!(encrypt_database_file_with_passphrase(test_db, "short").is_err());
+        let encrypted_path =
+            encrypt_database_file_with_passphrase(test_db, "correct horse battery").unwrap();
+        assert_eq!(
+            export_protection(&encrypted_path).unwrap(),
+            ExportProtection::Passphrase
+        );
+
+        // Needs the passphrase, and the right one
+        assert!(decrypt_database_file(&encrypted_path).is_err());
+        assert!(
+            decrypt_database_file_with_passphrase(&encrypted_path, "wrong passphrase").is_err()
+        );
+
+        let decrypted_path =
+            decrypt_database_file_with_passphrase(&encrypted_path, "correct horse battery")
+                .unwrap();
+        assert_eq!(fs::read(&decrypted_path).unwrap(), test_data);
+
+        // Flipping the mode byte breaks authentication
+        let mut data = fs::read(&encrypted_path).unwrap();
+        data[EXPORT_MAGIC.len() + 1] = MODE_MACHINE_BOUND;
+        fs::write(&encrypted_path, data).unwrap();
+        assert!(decrypt_database_file(&encrypted_path).is_err());
+
+        let _ = fs::remove_file(test_db);
+        let _ = fs::remove_file(&encrypted_path);
+        let _ = fs::remove_file(&decrypted_path);
+    }
+
     #[test]
     fn test_decrypt_legacy_sha256_encrypted_file() {
         let test_db = "test_legacy_encryption.db";
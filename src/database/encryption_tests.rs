@@ -38,4 +38,41 @@ mod tests {
         println!("✅ Encryption key is consistent!");
         println!("🔑 Key (hex, first 16 bytes): {}", hex::encode(&key1[..16]));
     }
+
+    #[test]
+    fn test_at_rest_encryption_mode() {
+        use crate::AtRestEncryption;
+
+        let test_path = std::env::temp_dir().join(format!("ntm-at-rest-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&test_path);
+
+        let db = Database::open(test_path.clone(), &AtRestEncryption::None)
+            .expect("Plain database should open");
+        assert!(!db.is_encrypted());
+        drop(db);
+
+        let passphrase = AtRestEncryption::Passphrase("correct horse".to_string());
+        let result = Database::open(test_path.clone(), &passphrase);
+        if cfg!(feature = "sqlcipher") {
+            // The plain file from above is encrypted in place
+            let db = result.expect("Should encrypt existing database");
+            assert!(db.is_encrypted());
+            drop(db);
+
+            let header = std::fs::read(&test_path).unwrap();
+            assert_ne!(&header[..16], b"SQLite format 3\0");
+
+            let wrong = AtRestEncryption::Passphrase("wrong".to_string());
+            assert!(Database::open(test_path.clone(), &wrong).is_err());
+            assert!(Database::open(test_path.clone(), &passphrase).is_ok());
+        } else {
+            assert!(result.is_err(), "Encryption needs the sqlcipher feature");
+        }
+
+        for suffix in ["", "-wal", "-shm"] {
+            let mut path = test_path.clone().into_os_string();
+            path.push(suffix);
+            let _ = std::fs::remove_file(path);
+        }
+    }
 }
@@ -0,0 +1,461 @@
+//! Latency history per device
+//!
+//! Every scan stores a device's response time in `device_history`. Charting
+//! months of that means reading thousands of rows, so a downsampling job
+//! rolls the raw samples up into hourly and daily buckets
+//! (`latency_rollups`) with min/max/average and percentiles. Short ranges
+//! are read from the raw rows, longer ones from the rollups.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use super::queries::{self, find_device_id, parse_datetime_column, SETTING_LATENCY_ROLLUP_SCAN};
+
+/// Granularity of a latency series
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LatencyResolution {
+    /// One point per scan
+    Raw,
+    Hourly,
+    Daily,
+}
+
+impl LatencyResolution {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LatencyResolution::Raw => "raw",
+            LatencyResolution::Hourly => "hourly",
+            LatencyResolution::Daily => "daily",
+        }
+    }
+
+    /// Resolution that keeps a chart of the range to a few hundred points
+    pub fn for_range(since: Option<DateTime<Utc>>, until: Option<DateTime<Utc>>) -> Self {
+        let Some(since) = since else {
+            return LatencyResolution::Daily;
+        };
+        let span = until.unwrap_or_else(Utc::now) - since;
+        if span <= Duration::days(2) {
+            LatencyResolution::Raw
+        } else if span <= Duration::days(60) {
+            LatencyResolution::Hourly
+        } else {
+            LatencyResolution::Daily
+        }
+    }
+
+    /// SQL expression for the start of the bucket holding `column`
+    fn bucket_sql(&self, column: &str) -> String {
+        match self {
+            LatencyResolution::Raw => column.to_string(),
+            LatencyResolution::Hourly => format!("strftime('%Y-%m-%d %H:00:00', {})", column),
+            LatencyResolution::Daily => format!("strftime('%Y-%m-%d 00:00:00', {})", column),
+        }
+    }
+}
+
+/// Response times of one device within one bucket (or one scan when raw)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LatencyPoint {
+    /// Start of the bucket, or the scan time for raw points
+    pub time: DateTime<Utc>,
+    pub samples: u32,
+    pub avg_ms: f64,
+    pub min_ms: u64,
+    pub max_ms: u64,
+    pub p50_ms: u64,
+    pub p95_ms: u64,
+}
+
+/// Distribution of a device's response times over a range
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LatencyPercentiles {
+    pub samples: u32,
+    pub avg_ms: f64,
+    pub min_ms: u64,
+    pub p50_ms: u64,
+    pub p90_ms: u64,
+    pub p95_ms: u64,
+    pub p99_ms: u64,
+    pub max_ms: u64,
+}
+
+/// Roll response times from scans since the last run into hourly and daily buckets
+///
+/// Buckets touched by new scans are rebuilt from all their raw samples, so
+/// running it again (or after a partial hour) is safe. Returns the number of
+/// bucket rows written.
+pub fn downsample_latency(conn: &Connection) -> Result<usize> {
+    let watermark: i64 = queries::get_setting(conn, SETTING_LATENCY_ROLLUP_SCAN)?
+        .and_then(|id| id.parse().ok())
+        .unwrap_or(0);
+    let latest: Option<i64> = conn.query_row("SELECT MAX(id) FROM scans", [], |row| row.get(0))?;
+    let Some(latest) = latest.filter(|&latest| latest > watermark) else {
+        return Ok(0);
+    };
+
+    conn.execute_batch("SAVEPOINT downsample_latency")
+        .context("Failed to start downsample_latency transaction")?;
+
+    let result = (|| -> Result<usize> {
+        let mut written = 0;
+        for resolution in [LatencyResolution::Hourly, LatencyResolution::Daily] {
+            written += rebuild_buckets(conn, resolution, watermark)?;
+        }
+        queries::set_setting(conn, SETTING_LATENCY_ROLLUP_SCAN, &latest.to_string())?;
+        Ok(written)
+    })();
+
+    match result {
+        Ok(written) => {
+            conn.execute_batch("RELEASE SAVEPOINT downsample_latency")
+                .context("Failed to commit downsample_latency transaction")?;
+            Ok(written)
+        }
+        Err(e) => {
+            let _ = conn.execute_batch(
+                "ROLLBACK TO SAVEPOINT downsample_latency; RELEASE SAVEPOINT downsample_latency",
+            );
+            Err(e)
+        }
+    }
+}
+
+fn rebuild_buckets(
+    conn: &Connection,
+    resolution: LatencyResolution,
+    after_scan_id: i64,
+) -> Result<usize> {
+    let mut stmt = conn.prepare(&format!(
+        r#"
+        SELECT dh.device_id, {bucket}, dh.response_time_ms
+        FROM device_history dh
+        JOIN scans s ON s.id = dh.scan_id
+        WHERE dh.response_time_ms IS NOT NULL
+          AND (dh.device_id, {bucket}) IN (
+            SELECT nh.device_id, {new_bucket}
+            FROM device_history nh
+            JOIN scans ns ON ns.id = nh.scan_id
+            WHERE nh.scan_id > ?1 AND nh.response_time_ms IS NOT NULL
+          )
+        ORDER BY dh.device_id, 2, dh.response_time_ms
+        "#,
+        bucket = resolution.bucket_sql("s.scan_time"),
+        new_bucket = resolution.bucket_sql("ns.scan_time"),
+    ))?;
+
+    let mut buckets: BTreeMap<(i64, String), Vec<u64>> = BTreeMap::new();
+    let mut rows = stmt.query(params![after_scan_id])?;
+    while let Some(row) = rows.next()? {
+        let response_ms: i64 = row.get(2)?;
+        buckets
+            .entry((row.get(0)?, row.get(1)?))
+            .or_default()
+            .push(response_ms.max(0) as u64);
+    }
+
+    for ((device_id, bucket), samples) in &buckets {
+        let total: u64 = samples.iter().sum();
+        conn.execute(
+            r#"
+            INSERT OR REPLACE INTO latency_rollups (
+                device_id, resolution, bucket, samples, total_ms,
+                min_ms, max_ms, p50_ms, p95_ms
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+            "#,
+            params![
+                device_id,
+                resolution.as_str(),
+                bucket,
+                samples.len() as i64,
+                total as i64,
+                samples[0] as i64,
+                samples[samples.len() - 1] as i64,
+                percentile(samples, 50.0) as i64,
+                percentile(samples, 95.0) as i64,
+            ],
+        )
+        .context("Failed to write latency rollup")?;
+    }
+    Ok(buckets.len())
+}
+
+/// Nearest-rank percentile of an ascending, non-empty slice
+fn percentile(sorted: &[u64], p: f64) -> u64 {
+    let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+fn format_time(time: DateTime<Utc>) -> String {
+    time.format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+/// A device's response times between `since` and `until`, oldest first
+///
+/// Hourly and daily points come from the rollups, so they only cover scans
+/// up to the last [`downsample_latency`] run.
+pub fn get_latency_series(
+    conn: &Connection,
+    mac: &str,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+    resolution: LatencyResolution,
+) -> Result<Vec<LatencyPoint>> {
+    let Some(device_id) = find_device_id(conn, mac)? else {
+        return Ok(Vec::new());
+    };
+
+    if resolution == LatencyResolution::Raw {
+        return Ok(raw_samples(conn, device_id, since, until)?
+            .into_iter()
+            .map(|(time, ms)| LatencyPoint {
+                time,
+                samples: 1,
+                avg_ms: ms as f64,
+                min_ms: ms,
+                max_ms: ms,
+                p50_ms: ms,
+                p95_ms: ms,
+            })
+            .collect());
+    }
+
+    let mut stmt = conn.prepare(&format!(
+        r#"
+        SELECT bucket, samples, total_ms, min_ms, max_ms, p50_ms, p95_ms
+        FROM latency_rollups
+        WHERE device_id = ?1 AND resolution = ?2
+          AND (?3 IS NULL OR bucket >= {since_bucket})
+          AND (?4 IS NULL OR bucket <= ?4)
+        ORDER BY bucket
+        "#,
+        since_bucket = resolution.bucket_sql("?3"),
+    ))?;
+    let points = stmt
+        .query_map(
+            params![
+                device_id,
+                resolution.as_str(),
+                since.map(format_time),
+                until.map(format_time)
+            ],
+            |row| {
+                let samples: u32 = row.get(1)?;
+                let total_ms: i64 = row.get(2)?;
+                let ms = |index| row.get::<_, i64>(index).map(|ms| ms.max(0) as u64);
+                Ok(LatencyPoint {
+                    time: parse_datetime_column(row.get(0)?, 0)?,
+                    samples,
+                    avg_ms: total_ms as f64 / f64::from(samples.max(1)),
+                    min_ms: ms(3)?,
+                    max_ms: ms(4)?,
+                    p50_ms: ms(5)?,
+                    p95_ms: ms(6)?,
+                })
+            },
+        )?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("Failed to get latency series")?;
+    Ok(points)
+}
+
+/// Percentiles of a device's response times between `since` and `until`
+///
+/// Computed from per-scan history, so the range is limited to what the
+/// retention policy keeps. `None` when there are no samples.
+pub fn get_latency_percentiles(
+    conn: &Connection,
+    mac: &str,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+) -> Result<Option<LatencyPercentiles>> {
+    let Some(device_id) = find_device_id(conn, mac)? else {
+        return Ok(None);
+    };
+
+    let mut samples: Vec<u64> = raw_samples(conn, device_id, since, until)?
+        .into_iter()
+        .map(|(_, ms)| ms)
+        .collect();
+    if samples.is_empty() {
+        return Ok(None);
+    }
+    samples.sort_unstable();
+
+    Ok(Some(LatencyPercentiles {
+        samples: samples.len() as u32,
+        avg_ms: samples.iter().sum::<u64>() as f64 / samples.len() as f64,
+        min_ms: samples[0],
+        p50_ms: percentile(&samples, 50.0),
+        p90_ms: percentile(&samples, 90.0),
+        p95_ms: percentile(&samples, 95.0),
+        p99_ms: percentile(&samples, 99.0),
+        max_ms: samples[samples.len() - 1],
+    }))
+}
+
+fn raw_samples(
+    conn: &Connection,
+    device_id: i64,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+) -> Result<Vec<(DateTime<Utc>, u64)>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT s.scan_time, dh.response_time_ms
+        FROM device_history dh
+        JOIN scans s ON s.id = dh.scan_id
+        WHERE dh.device_id = ?1 AND dh.response_time_ms IS NOT NULL
+          AND (?2 IS NULL OR s.scan_time >= ?2)
+          AND (?3 IS NULL OR s.scan_time <= ?3)
+        ORDER BY s.scan_time, dh.id
+        "#,
+    )?;
+    let samples = stmt
+        .query_map(
+            params![device_id, since.map(format_time), until.map(format_time)],
+            |row| {
+                let response_ms: i64 = row.get(1)?;
+                Ok((
+                    parse_datetime_column(row.get(0)?, 0)?,
+                    response_ms.max(0) as u64,
+                ))
+            },
+        )?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("Failed to get latency samples")?;
+    Ok(samples)
+}
+
+/// Move a merged device's rollups to the canonical device
+///
+/// Buckets both devices have keep the canonical device's figures.
+pub(super) fn merge_latency_rollups(
+    conn: &Connection,
+    canonical: i64,
+    duplicate: i64,
+) -> Result<()> {
+    conn.execute(
+        "UPDATE OR IGNORE latency_rollups SET device_id = ?1 WHERE device_id = ?2",
+        params![canonical, duplicate],
+    )?;
+    conn.execute(
+        "DELETE FROM latency_rollups WHERE device_id = ?1",
+        params![duplicate],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{queries::insert_scan, Database};
+    use crate::models::{HostInfo, ScanResult};
+
+    fn scan(response_ms: u64) -> ScanResult {
+        let mut host = HostInfo::new(
+            "192.168.1.90".to_string(),
+            "AA:BB:CC:DD:EE:10".to_string(),
+            "PC".to_string(),
+            "ARP+ICMP".to_string(),
+        );
+        host.response_time_ms = Some(response_ms);
+        ScanResult {
+            interface_name: "eth0".to_string(),
+            local_ip: "192.168.1.1".to_string(),
+            local_mac: "AA:BB:CC:DD:EE:FF".to_string(),
+            subnet: "192.168.1.0/24".to_string(),
+            scan_method: "arp".to_string(),
+            arp_discovered: 1,
+            icmp_discovered: 1,
+            total_hosts: 1,
+            scan_duration_ms: 100,
+            active_hosts: vec![host],
+            wan: None,
+            ssid: None,
+        }
+    }
+
+    #[test]
+    fn test_percentile_nearest_rank() {
+        let samples: Vec<u64> = (1..=100).collect();
+        assert_eq!(percentile(&samples, 50.0), 50);
+        assert_eq!(percentile(&samples, 95.0), 95);
+        assert_eq!(percentile(&samples, 0.0), 1);
+        assert_eq!(percentile(&[7], 99.0), 7);
+    }
+
+    #[test]
+    fn test_downsample_rolls_up_buckets_incrementally() {
+        let db = Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
+        let mac = "AA:BB:CC:DD:EE:10";
+
+        for ms in [10, 20, 30] {
+            insert_scan(&conn, &scan(ms)).unwrap();
+        }
+        conn.execute("UPDATE scans SET scan_time = '2026-01-05 10:15:00'", [])
+            .unwrap();
+
+        // One hourly and one daily bucket
+        assert_eq!(downsample_latency(&conn).unwrap(), 2);
+        assert_eq!(downsample_latency(&conn).unwrap(), 0, "nothing new");
+
+        let hourly = get_latency_series(&conn, mac, None, None, LatencyResolution::Hourly).unwrap();
+        assert_eq!(hourly.len(), 1);
+        assert_eq!(hourly[0].time.to_rfc3339(), "2026-01-05T10:00:00+00:00");
+        assert_eq!(hourly[0].samples, 3);
+        assert_eq!(hourly[0].avg_ms, 20.0);
+        assert_eq!((hourly[0].min_ms, hourly[0].max_ms), (10, 30));
+        assert_eq!(hourly[0].p50_ms, 20);
+
+        // A later scan in the same hour rebuilds that bucket from all samples
+        let late = insert_scan(&conn, &scan(40)).unwrap();
+        conn.execute(
+            "UPDATE scans SET scan_time = '2026-01-05 10:45:00' WHERE id = ?1",
+            params![late],
+        )
+        .unwrap();
+        downsample_latency(&conn).unwrap();
+        let daily = get_latency_series(&conn, mac, None, None, LatencyResolution::Daily).unwrap();
+        assert_eq!(daily.len(), 1);
+        assert_eq!(daily[0].samples, 4);
+        assert_eq!(daily[0].max_ms, 40);
+
+        let raw = get_latency_series(&conn, mac, None, None, LatencyResolution::Raw).unwrap();
+        assert_eq!(raw.len(), 4);
+
+        let stats = get_latency_percentiles(&conn, mac, None, None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(stats.samples, 4);
+        assert_eq!(stats.avg_ms, 25.0);
+        assert_eq!((stats.p50_ms, stats.p99_ms), (20, 40));
+        assert!(
+            get_latency_percentiles(&conn, "00:00:00:00:00:00", None, None)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_resolution_for_range() {
+        let now = Utc::now();
+        assert_eq!(
+            LatencyResolution::for_range(Some(now - Duration::hours(6)), None),
+            LatencyResolution::Raw
+        );
+        assert_eq!(
+            LatencyResolution::for_range(Some(now - Duration::days(14)), Some(now)),
+            LatencyResolution::Hourly
+        );
+        assert_eq!(
+            LatencyResolution::for_range(None, None),
+            LatencyResolution::Daily
+        );
+    }
+}
@@ -4,17 +4,40 @@
 //! - Scan history
 //! - Device tracking
 //! - Alerts
+//! - Retention pruning with daily summaries of old history
+//! - Latency history downsampled into hourly and daily buckets
+//! - Network profiles scoping devices, scans and alerts to one network
+//! - Optional at-rest encryption of the live database (`sqlcipher` feature)
+//! - Whole-database JSON export and import
 
+pub mod cache;
 pub mod connection;
+pub mod dump;
 pub mod encryption;
+pub mod latency;
 pub mod models;
+pub mod networks;
 pub mod queries;
+pub mod retention;
 pub mod schema;
 pub mod seed_cves;
 
-pub use connection::Database;
+pub use cache::QueryCache;
+pub use connection::{AtRestEncryption, ConnectionGuard, Database, MaintenanceReport};
+pub use dump::{export_database_json, import_database_json, DatabaseDump, ImportSummary};
+pub use latency::{
+    downsample_latency, get_latency_percentiles, get_latency_series, LatencyPercentiles,
+    LatencyPoint, LatencyResolution,
+};
 pub use models::*;
+pub use networks::{
+    get_network, get_networks, identify_network, latest_network_id, rename_network,
+};
 pub use queries::*;
+pub use retention::{
+    get_device_daily_summaries, get_scan_daily_summaries, load_retention_policy, prune,
+    save_retention_policy, PruneSummary, RetentionPolicy,
+};
 
 #[cfg(test)]
 mod encryption_tests;
@@ -4,6 +4,10 @@
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::groups::GroupPolicy;
+use crate::models::HostState;
 
 /// Scan record from database
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,6 +23,30 @@ pub struct ScanRecord {
     pub icmp_discovered: i32,
     pub total_hosts: i32,
     pub duration_ms: i64,
+    /// Earlier scan this one repeated (same hosts, run back-to-back)
+    pub duplicate_of: Option<i64>,
+    /// Number of later duplicate scans collapsed into this one
+    pub duplicate_count: i64,
+    /// User note on the scan, e.g. "after router firmware update"
+    pub label: Option<String>,
+    /// Network profile the scan was taken on
+    #[serde(default)]
+    pub network_id: Option<i64>,
+}
+
+/// A network the app has scanned (home, office, a customer site)
+///
+/// Identified by subnet plus gateway MAC and/or Wi-Fi SSID, so two networks
+/// using the same private range stay apart.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NetworkProfile {
+    pub id: i64,
+    pub name: String,
+    pub subnet: String,
+    pub gateway_mac: Option<String>,
+    pub ssid: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
 }
 
 /// Device record from database
@@ -36,6 +64,58 @@ pub struct DeviceRecord {
     pub custom_name: Option<String>,
     pub notes: Option<String>,
     pub security_grade: Option<String>,
+    /// User tags such as "kids", "work" or "critical", alphabetical
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// User-defined fields such as asset tag, owner or location, by name
+    #[serde(default)]
+    pub attributes: BTreeMap<String, String>,
+    /// Share of recent scans that found the device
+    #[serde(default)]
+    pub availability: DeviceAvailability,
+    /// Open ports seen by the latest scan that found the device
+    #[serde(default)]
+    pub open_ports: Vec<u16>,
+    /// Network profile the device was last seen on
+    #[serde(default)]
+    pub network_id: Option<i64>,
+}
+
+/// Percent of scans of a device's networks that found it, per window
+///
+/// Only scans after the device was first seen count. A window is `None`
+/// when none of its networks were scanned in that time.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct DeviceAvailability {
+    pub last_24h: Option<f64>,
+    pub last_7d: Option<f64>,
+    pub last_30d: Option<f64>,
+}
+
+/// Scans that found a device out of those that could have, over one window
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AvailabilityWindow {
+    pub hours: u32,
+    /// Scans of the device's networks in the window
+    pub scans: u32,
+    /// Of those, scans that found the device
+    pub seen: u32,
+    /// `seen` as a percentage of `scans`, `None` without scans
+    pub percent: Option<f64>,
+}
+
+/// A device tag and how many devices carry it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TagCount {
+    pub tag: String,
+    pub device_count: u32,
+}
+
+/// A custom device field name and how many devices set it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttributeCount {
+    pub name: String,
+    pub device_count: u32,
 }
 
 /// Device history entry (per-scan snapshot)
@@ -51,6 +131,558 @@ pub struct DeviceHistoryRecord {
     pub is_online: bool,
     pub discovery_method: Option<String>,
     pub open_ports: Vec<u16>,
+    /// Ports that were firewalled during this scan
+    #[serde(default)]
+    pub filtered_ports: Vec<u16>,
+    pub host_state: Option<HostState>,
+}
+
+/// A sensitive port observed open on a device during a period
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortExposureRecord {
+    /// Subnet of the scans that observed the port
+    pub subnet: String,
+    pub mac: String,
+    pub display_name: Option<String>,
+    pub vendor: Option<String>,
+    pub port: u16,
+    /// Address the device had when the port was last observed open
+    pub last_ip: String,
+    pub first_observed: DateTime<Utc>,
+    pub last_observed: DateTime<Utc>,
+    /// Number of scans that found the port open
+    pub scan_count: u32,
+}
+
+/// The devices one scan found, for replaying changes between scans
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanSnapshot {
+    pub scan_id: i64,
+    pub scan_time: DateTime<Utc>,
+    pub subnet: String,
+    pub devices: Vec<DeviceSighting>,
+}
+
+/// A device as one scan saw it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeviceSighting {
+    pub mac: String,
+    /// Custom name, else hostname
+    pub name: Option<String>,
+    pub ip: String,
+    pub open_ports: Vec<u16>,
+    /// Whether this is the earliest recorded sighting of the device
+    pub first_sighting: bool,
+}
+
+/// A port seen open on a device, with when it was first and last observed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DevicePortRecord {
+    pub device_mac: String,
+    pub port: u16,
+    /// Transport protocol, `tcp` for ports found by the TCP probe
+    pub protocol: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service: Option<String>,
+    /// First bytes the service sent, when a probe captured them
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub banner: Option<String>,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    /// Whether the port was open in the device's most recent scan
+    pub is_open: bool,
+}
+
+/// A port opening or closing on a device between two of its scans
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PortChangeRecord {
+    pub device_mac: String,
+    pub port: u16,
+    pub protocol: String,
+    /// `true` when the port opened, `false` when it closed
+    pub opened: bool,
+    /// Scan that saw the change, until pruned
+    pub scan_id: Option<i64>,
+    pub changed_at: DateTime<Utc>,
+}
+
+/// A link from a device port to an LLDP neighbor, kept across scans
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TopologyEdgeRecord {
+    pub local_device_mac: String,
+    pub local_port: String,
+    /// Neighbor's advertised system name
+    pub remote_device: String,
+    pub remote_port: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remote_ip: Option<String>,
+    /// Known device last seen at `remote_ip`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remote_device_mac: Option<String>,
+    /// Discovery protocol that reported the link (`lldp`)
+    pub source: String,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    /// Whether the local device's most recent scan reported the link
+    pub is_current: bool,
+}
+
+/// A saved run of one of the network tools (ping, port scan)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolRunRecord {
+    pub id: i64,
+    /// Tool name, e.g. `ping` or `port_scan`
+    pub tool: String,
+    pub target: String,
+    /// Options the tool ran with
+    pub parameters: serde_json::Value,
+    /// What the tool returned, as sent to the UI
+    pub results: serde_json::Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// One day of a device's history, kept after the per-scan rows were pruned
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeviceDailySummary {
+    pub device_mac: String,
+    /// Calendar day (UTC), `YYYY-MM-DD`
+    pub day: String,
+    /// Scans that found the device that day
+    pub sightings: u32,
+    pub avg_response_ms: Option<f64>,
+    pub min_response_ms: Option<u64>,
+    pub max_response_ms: Option<u64>,
+    pub max_risk_score: u8,
+    pub last_ip: Option<String>,
+}
+
+/// One day of scans, kept after the scans themselves were pruned
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScanDailySummary {
+    /// Calendar day (UTC), `YYYY-MM-DD`
+    pub day: String,
+    pub scans: u32,
+    pub avg_hosts: f64,
+    pub max_hosts: u32,
+    pub avg_duration_ms: f64,
+}
+
+/// Per-device alerting overrides
+///
+/// `None` thresholds fall back to the global defaults. Disabling offline
+/// alerts suits devices that routinely leave the network (phones, laptops).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeviceSettings {
+    pub device_mac: String,
+    /// Alert when response time exceeds this value
+    pub latency_threshold_ms: Option<u64>,
+    /// How long a device may be missing before it is reported offline
+    pub offline_grace_secs: Option<u64>,
+    pub offline_alerts_enabled: bool,
+    /// Risk score at or above which a high-risk alert is raised
+    pub risk_threshold: Option<u8>,
+    pub risk_alerts_enabled: bool,
+    /// Ports this device should have open; any deviation raises an alert.
+    /// `None` means no baseline, `Some(vec![])` expects no open ports.
+    #[serde(default)]
+    pub expected_ports: Option<Vec<u16>>,
+    /// Checked every few seconds between full scans by the monitor
+    #[serde(default)]
+    pub watched: bool,
+    /// On the known-device allowlist; see [`crate::alerts::UntrustedDeviceAlerts`]
+    #[serde(default)]
+    pub trusted: bool,
+    /// No alerts for this device until then
+    #[serde(default)]
+    pub muted_until: Option<DateTime<Utc>>,
+}
+
+impl DeviceSettings {
+    /// Settings with no overrides for the given device
+    pub fn new(device_mac: impl Into<String>) -> Self {
+        Self {
+            device_mac: device_mac.into(),
+            latency_threshold_ms: None,
+            offline_grace_secs: None,
+            offline_alerts_enabled: true,
+            risk_threshold: None,
+            risk_alerts_enabled: true,
+            expected_ports: None,
+            watched: false,
+            trusted: false,
+            muted_until: None,
+        }
+    }
+
+    /// Whether the device's alerts are snoozed at `now`
+    pub fn is_muted(&self, now: DateTime<Utc>) -> bool {
+        self.muted_until.is_some_and(|until| until > now)
+    }
+}
+
+/// Difference between a device's expected and observed open ports
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PortDeviation {
+    /// Open but not expected
+    pub unexpected: Vec<u16>,
+    /// Expected but not open
+    pub missing: Vec<u16>,
+}
+
+impl PortDeviation {
+    /// Compare open ports with a baseline, returning `None` if they match
+    pub fn between(expected: &[u16], open: &[u16]) -> Option<Self> {
+        let mut unexpected: Vec<u16> = open
+            .iter()
+            .filter(|p| !expected.contains(p))
+            .copied()
+            .collect();
+        let mut missing: Vec<u16> = expected
+            .iter()
+            .filter(|p| !open.contains(p))
+            .copied()
+            .collect();
+        if unexpected.is_empty() && missing.is_empty() {
+            return None;
+        }
+        unexpected.sort_unstable();
+        unexpected.dedup();
+        missing.sort_unstable();
+        missing.dedup();
+        Some(Self {
+            unexpected,
+            missing,
+        })
+    }
+}
+
+/// A device as frozen in the network baseline
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BaselineDevice {
+    pub mac: String,
+    pub ip: Option<String>,
+    pub hostname: Option<String>,
+    pub vendor: Option<String>,
+    pub device_type: Option<String>,
+    pub open_ports: Vec<u16>,
+}
+
+/// The device and port inventory later scans are compared with
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NetworkBaseline {
+    pub captured_at: DateTime<Utc>,
+    pub devices: Vec<BaselineDevice>,
+}
+
+/// Saved smart group (device filter expression)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmartGroupRecord {
+    pub id: i64,
+    pub name: String,
+    pub expression: String,
+    pub description: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Named device group carrying an alerting policy for its members
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceGroupRecord {
+    pub id: i64,
+    pub name: String,
+    pub description: Option<String>,
+    pub policy: GroupPolicy,
+    /// MAC addresses of member devices
+    pub members: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// TLS SNI observation for a device (service contacted by the device)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SniRecord {
+    pub id: i64,
+    pub device_mac: String,
+    pub server_name: String,
+    pub last_server_ip: Option<String>,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    pub hit_count: i64,
+}
+
+/// mDNS/DNS-SD service advertised by a device
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceServiceRecord {
+    pub id: i64,
+    pub device_mac: String,
+    /// Service type without the domain, e.g. `_ipp._tcp`
+    pub service_type: String,
+    /// Advertised instance name (empty if unknown)
+    pub instance_name: String,
+    pub port: Option<u16>,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+}
+
+/// Device record together with the services it advertises and its port baseline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceDetails {
+    #[serde(flatten)]
+    pub device: DeviceRecord,
+    pub services: Vec<DeviceServiceRecord>,
+    /// Open ports seen in the device's latest scan
+    pub open_ports: Vec<u16>,
+    pub expected_ports: Option<Vec<u16>>,
+    /// Set when the latest open ports differ from `expected_ports`
+    pub port_deviation: Option<PortDeviation>,
+    /// Directory computer object; `None` for unmanaged (or not yet looked up) devices
+    pub directory: Option<DirectoryComputer>,
+}
+
+/// Active Directory computer object matched to a device by hostname
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DirectoryComputer {
+    /// Computer account name (`cn`)
+    pub name: String,
+    pub dns_hostname: Option<String>,
+    pub operating_system: Option<String>,
+    pub os_version: Option<String>,
+    /// OU path, outermost first (e.g. `HQ/Laptops`)
+    pub ou: Option<String>,
+    /// Replicated last logon (`lastLogonTimestamp`, accurate to ~14 days)
+    pub last_logon: Option<DateTime<Utc>>,
+    pub distinguished_name: String,
+    /// False if the computer account is disabled
+    pub enabled: bool,
+}
+
+/// What a device purge would remove, plus the token needed to confirm it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DevicePurgePreview {
+    pub mac: String,
+    pub history_rows: usize,
+    pub alerts: usize,
+    pub sni_observations: usize,
+    pub services: usize,
+    pub has_settings: bool,
+    /// Must be passed back to `purge_device_data` to confirm the wipe
+    pub confirmation_token: String,
+}
+
+/// Rows removed by a device purge
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DevicePurgeSummary {
+    pub mac: String,
+    pub history_rows: usize,
+    pub alerts: usize,
+    pub sni_observations: usize,
+    pub services: usize,
+    pub settings: usize,
+}
+
+/// User-defined vendor name for a MAC prefix or a single MAC
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VendorOverrideRecord {
+    /// Uppercase hex digits without separators (6-11 for a prefix, 12 for a MAC)
+    pub pattern: String,
+    pub vendor: String,
+    pub is_exact_mac: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+/// User-defined device type inference rule
+///
+/// This is also the import/export format. Every condition that is set must
+/// match; patterns are case-insensitive regular expressions and all listed
+/// ports must be open.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeviceTypeRule {
+    pub name: String,
+    #[serde(default)]
+    pub vendor_pattern: Option<String>,
+    #[serde(default)]
+    pub hostname_pattern: Option<String>,
+    #[serde(default)]
+    pub ports: Vec<u16>,
+    /// mDNS service type, e.g. `_ipp._tcp`
+    #[serde(default)]
+    pub service: Option<String>,
+    /// Device type name as stored on devices (e.g. `PRINTER`)
+    pub device_type: String,
+    /// 0-100; the highest-confidence matching rule wins
+    #[serde(default = "default_rule_confidence")]
+    pub confidence: u8,
+    #[serde(default = "default_rule_enabled")]
+    pub enabled: bool,
+}
+
+fn default_rule_confidence() -> u8 {
+    80
+}
+
+fn default_rule_enabled() -> bool {
+    true
+}
+
+/// Stored device type rule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceTypeRuleRecord {
+    pub id: i64,
+    #[serde(flatten)]
+    pub rule: DeviceTypeRule,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Evidence that linked a MAC address to a device
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CorrelationSignal {
+    Hostname,
+    /// Same mDNS instance name (e.g. "Anna's iPhone")
+    MdnsIdentity,
+    /// Same DHCP parameter request list
+    DhcpFingerprint,
+    /// Same non-empty set of open ports
+    PortProfile,
+    /// Merged by the user
+    Manual,
+}
+
+/// A MAC address merged into a canonical device record
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceMerge {
+    pub id: i64,
+    /// Canonical device the MAC now belongs to
+    pub device_id: i64,
+    pub mac: String,
+    /// Fingerprint score at merge time (0 for manual merges)
+    pub score: u32,
+    pub signals: Vec<CorrelationSignal>,
+    pub merged_at: DateTime<Utc>,
+}
+
+/// Health-score finding whose risk can be accepted for a device
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RiskFinding {
+    /// Medium or high risk score
+    RiskScore,
+    RandomizedMac,
+    UnknownType,
+    /// No ICMP reply
+    Unresponsive,
+}
+
+impl RiskFinding {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RiskFinding::RiskScore => "risk_score",
+            RiskFinding::RandomizedMac => "randomized_mac",
+            RiskFinding::UnknownType => "unknown_type",
+            RiskFinding::Unresponsive => "unresponsive",
+        }
+    }
+}
+
+impl std::fmt::Display for RiskFinding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for RiskFinding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "risk_score" => Ok(RiskFinding::RiskScore),
+            "randomized_mac" => Ok(RiskFinding::RandomizedMac),
+            "unknown_type" => Ok(RiskFinding::UnknownType),
+            "unresponsive" => Ok(RiskFinding::Unresponsive),
+            _ => Err(format!("Unknown risk finding: {}", s)),
+        }
+    }
+}
+
+/// Kind of security finding recorded per scan
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FindingKind {
+    /// CVE matched to the device, keyed by CVE id
+    Vulnerability,
+    /// Risky open port, keyed by port number
+    PortWarning,
+}
+
+impl FindingKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FindingKind::Vulnerability => "vulnerability",
+            FindingKind::PortWarning => "port_warning",
+        }
+    }
+}
+
+impl std::fmt::Display for FindingKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for FindingKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "vulnerability" => Ok(FindingKind::Vulnerability),
+            "port_warning" => Ok(FindingKind::PortWarning),
+            _ => Err(format!("Unknown finding kind: {}", s)),
+        }
+    }
+}
+
+/// Lifetime of a vulnerability or port warning on one device
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FindingHistoryRecord {
+    pub device_mac: String,
+    pub kind: FindingKind,
+    /// CVE id or port number
+    pub key: String,
+    pub severity: String,
+    pub description: String,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    /// Scans the finding was reported in
+    pub scan_count: u32,
+    /// First scan of the device without the finding after it was last seen
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remediated_at: Option<DateTime<Utc>>,
+}
+
+/// A formally accepted risk for a device finding
+///
+/// Accepted findings do not count against the health score until
+/// `expires_at`, after which a review alert is raised once.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RiskAcceptance {
+    pub id: i64,
+    pub device_mac: String,
+    pub finding: RiskFinding,
+    pub reason: String,
+    pub accepted_by: Option<String>,
+    pub accepted_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    /// Whether the expiry review alert was raised
+    pub review_alerted: bool,
+}
+
+impl RiskAcceptance {
+    /// Whether the acceptance is still in force at `now`
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at > now
+    }
 }
 
 /// Alert record from database
@@ -59,15 +691,36 @@ pub struct AlertRecord {
     pub id: i64,
     pub created_at: DateTime<Utc>,
     pub alert_type: AlertType,
+    /// Type as stored, kept when this build does not know it (`alert_type` is then `Custom`)
+    #[serde(default)]
+    pub alert_type_raw: String,
     pub device_id: Option<i64>,
     pub device_mac: Option<String>,
     pub device_ip: Option<String>,
     pub message: String,
     pub severity: AlertSeverity,
     pub is_read: bool,
+    /// Supporting data recorded with the alert
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub evidence: Option<serde_json::Value>,
+    /// Network profile the alert was raised on
+    #[serde(default)]
+    pub network_id: Option<i64>,
+    /// Times the alert was raised while unread, counting the first
+    #[serde(default = "default_occurrences")]
+    pub occurrences: u32,
+    /// When it was last raised again; `None` if it has not repeated
+    #[serde(default)]
+    pub last_seen_at: Option<DateTime<Utc>>,
+}
+
+fn default_occurrences() -> u32 {
+    1
 }
 
 /// Alert types
+///
+/// Unknown types (written by a newer version) read back as `Custom`.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum AlertType {
@@ -77,6 +730,33 @@ pub enum AlertType {
     HighRisk,
     PortChange,
     IpChange,
+    /// An accepted risk expired and needs review
+    RiskReview,
+    /// Several MACs claim the same IP address
+    IpConflict,
+    /// One MAC answers on several IP addresses (cloned or spoofed)
+    MacCloned,
+    /// The gateway's address is now answered by a different MAC
+    GatewayMacChanged,
+    /// A DHCP server other than the known ones answered
+    RogueDhcp,
+    /// A known device opened a port it did not have before
+    PortOpened,
+    /// A TLS certificate is close to (or past) its expiry date
+    CertificateExpiring,
+    /// Internet connectivity was lost
+    InternetDown,
+    /// A device keeps going offline and back online
+    Flapping,
+    /// A device not marked trusted is on the network
+    UntrustedDevice,
+    /// A device departs from its history (a port it never had, an unusual hour)
+    BehaviorAnomaly,
+    /// A known MAC now looks like a different device, or two hosts share it
+    MacSpoofing,
+    /// A device's SSH server presents a different host key than before
+    SshHostKeyChanged,
+    #[serde(other)]
     Custom,
 }
 
@@ -89,6 +769,19 @@ impl std::fmt::Display for AlertType {
             AlertType::HighRisk => write!(f, "high_risk"),
             AlertType::PortChange => write!(f, "port_change"),
             AlertType::IpChange => write!(f, "ip_change"),
+            AlertType::RiskReview => write!(f, "risk_review"),
+            AlertType::IpConflict => write!(f, "ip_conflict"),
+            AlertType::MacCloned => write!(f, "mac_cloned"),
+            AlertType::GatewayMacChanged => write!(f, "gateway_mac_changed"),
+            AlertType::RogueDhcp => write!(f, "rogue_dhcp"),
+            AlertType::PortOpened => write!(f, "port_opened"),
+            AlertType::CertificateExpiring => write!(f, "certificate_expiring"),
+            AlertType::InternetDown => write!(f, "internet_down"),
+            AlertType::Flapping => write!(f, "flapping"),
+            AlertType::UntrustedDevice => write!(f, "untrusted_device"),
+            AlertType::BehaviorAnomaly => write!(f, "behavior_anomaly"),
+            AlertType::MacSpoofing => write!(f, "mac_spoofing"),
+            AlertType::SshHostKeyChanged => write!(f, "ssh_host_key_changed"),
             AlertType::Custom => write!(f, "custom"),
         }
     }
@@ -105,6 +798,19 @@ impl std::str::FromStr for AlertType {
             "high_risk" => Ok(AlertType::HighRisk),
             "port_change" => Ok(AlertType::PortChange),
             "ip_change" => Ok(AlertType::IpChange),
+            "risk_review" => Ok(AlertType::RiskReview),
+            "ip_conflict" => Ok(AlertType::IpConflict),
+            "mac_cloned" => Ok(AlertType::MacCloned),
+            "gateway_mac_changed" => Ok(AlertType::GatewayMacChanged),
+            "rogue_dhcp" => Ok(AlertType::RogueDhcp),
+            "port_opened" => Ok(AlertType::PortOpened),
+            "certificate_expiring" => Ok(AlertType::CertificateExpiring),
+            "internet_down" => Ok(AlertType::InternetDown),
+            "flapping" => Ok(AlertType::Flapping),
+            "untrusted_device" => Ok(AlertType::UntrustedDevice),
+            "behavior_anomaly" => Ok(AlertType::BehaviorAnomaly),
+            "mac_spoofing" => Ok(AlertType::MacSpoofing),
+            "ssh_host_key_changed" => Ok(AlertType::SshHostKeyChanged),
             "custom" => Ok(AlertType::Custom),
             _ => Err(format!("Unknown alert type: {}", s)),
         }
@@ -155,5 +861,101 @@ pub struct NetworkStats {
     pub new_devices_24h: i64,
     pub high_risk_devices: i64,
     pub total_scans: i64,
+    /// Scans flagged as back-to-back duplicates
+    pub duplicate_scans: i64,
     pub last_scan_time: Option<DateTime<Utc>>,
 }
+
+/// One page of a listing, with the number of matching rows across all pages
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub total: i64,
+}
+
+/// Sort column for device listings
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeviceSort {
+    #[default]
+    LastSeen,
+    FirstSeen,
+    /// Custom name, then hostname, then MAC
+    Name,
+    Vendor,
+    DeviceType,
+}
+
+/// Filter, sort and page for device listings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DeviceQuery {
+    /// Page size; `None` returns all matches
+    pub limit: Option<u32>,
+    pub offset: u32,
+    pub sort: DeviceSort,
+    pub descending: bool,
+    pub device_type: Option<String>,
+    /// Seen (or missing) in the latest scan
+    pub online: Option<bool>,
+    /// Last seen at or after
+    pub seen_since: Option<DateTime<Utc>>,
+    /// Last seen at or before
+    pub seen_until: Option<DateTime<Utc>>,
+    /// Only devices last seen on this network profile
+    pub network_id: Option<i64>,
+}
+
+impl Default for DeviceQuery {
+    fn default() -> Self {
+        Self {
+            limit: None,
+            offset: 0,
+            sort: DeviceSort::LastSeen,
+            descending: true,
+            device_type: None,
+            online: None,
+            seen_since: None,
+            seen_until: None,
+            network_id: None,
+        }
+    }
+}
+
+/// Filter and page for alert listings (newest first unless `oldest_first`)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AlertQuery {
+    /// Page size; `None` returns all matches
+    pub limit: Option<u32>,
+    pub offset: u32,
+    pub oldest_first: bool,
+    pub severity: Option<AlertSeverity>,
+    pub unread_only: bool,
+    /// Raised at or after
+    pub since: Option<DateTime<Utc>>,
+    /// Raised at or before
+    pub until: Option<DateTime<Utc>>,
+    /// Only alerts raised on this network profile
+    pub network_id: Option<i64>,
+    /// Only alerts about this device (MAC, any case)
+    pub device_mac: Option<String>,
+}
+
+/// Filter and page for scan listings (newest first unless `oldest_first`)
+///
+/// Duplicate scans are hidden when the collapse setting is on, as in scan history.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScanQuery {
+    /// Page size; `None` returns all matches
+    pub limit: Option<u32>,
+    pub offset: u32,
+    pub oldest_first: bool,
+    /// Run at or after
+    pub since: Option<DateTime<Utc>>,
+    /// Run at or before
+    pub until: Option<DateTime<Utc>>,
+    /// Only scans of this network profile
+    pub network_id: Option<i64>,
+}
@@ -0,0 +1,262 @@
+//! Network profiles (home, office, customer sites)
+//!
+//! Devices are keyed by MAC, so without profiles a laptop scanning at home
+//! and at work mixes both inventories. Every saved scan is matched to a
+//! profile by subnet plus gateway MAC and/or Wi-Fi SSID, and a new profile
+//! is created the first time a network is seen. Scans, devices and alerts
+//! carry the profile id so listings can be scoped to one network.
+
+use anyhow::{bail, Context, Result};
+use rusqlite::{params, Connection, OptionalExtension};
+
+use super::models::NetworkProfile;
+use super::queries::parse_datetime_column;
+use crate::models::ScanResult;
+
+const NETWORK_COLUMNS: &str = "id, name, subnet, gateway_mac, ssid, created_at, last_seen";
+
+fn network_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<NetworkProfile> {
+    Ok(NetworkProfile {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        subnet: row.get(2)?,
+        gateway_mac: row.get(3)?,
+        ssid: row.get(4)?,
+        created_at: parse_datetime_column(row.get::<_, String>(5)?, 5)?,
+        last_seen: parse_datetime_column(row.get::<_, String>(6)?, 6)?,
+    })
+}
+
+/// MAC of the gateway found by a scan (the router with the lowest address)
+fn scan_gateway_mac(result: &ScanResult) -> Option<&str> {
+    result
+        .active_hosts
+        .iter()
+        .filter(|host| host.device_type.eq_ignore_ascii_case("router"))
+        .min_by_key(|host| host.ip.parse::<std::net::IpAddr>().ok())
+        .map(|host| host.mac.as_str())
+}
+
+/// How well a known identifier fits one seen by a scan
+///
+/// `None` when both are known and differ, which rules the profile out. A
+/// scan that found no gateway (or ran on a wired interface) still matches
+/// on the subnet, just less strongly than one that confirms it.
+fn match_score(known: Option<&str>, seen: Option<&str>) -> Option<u8> {
+    match (known, seen) {
+        (Some(known), Some(seen)) if known.eq_ignore_ascii_case(seen) => Some(1),
+        (Some(_), Some(_)) => None,
+        _ => Some(0),
+    }
+}
+
+/// Profile for the network a scan was taken on, created if new
+///
+/// Missing gateway or SSID details on a matched profile are filled in from
+/// the scan, and its last-seen time is updated.
+pub fn identify_network(conn: &Connection, result: &ScanResult) -> Result<i64> {
+    let gateway_mac = scan_gateway_mac(result);
+    let ssid = result.ssid.as_deref();
+
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT id, gateway_mac, ssid FROM networks
+        WHERE subnet = ?1
+        ORDER BY last_seen DESC, id DESC
+        "#,
+    )?;
+    let candidates = stmt
+        .query_map(params![result.subnet], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, Option<String>>(1)?,
+                row.get::<_, Option<String>>(2)?,
+            ))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("Failed to look up network profiles")?;
+
+    // Best confirmed match; ties go to the most recently seen profile
+    let matched = candidates
+        .iter()
+        .enumerate()
+        .filter_map(|(index, (id, known_gateway, known_ssid))| {
+            let score = match_score(known_gateway.as_deref(), gateway_mac)?
+                + match_score(known_ssid.as_deref(), ssid)?;
+            Some(((score, std::cmp::Reverse(index)), *id))
+        })
+        .max_by_key(|(rank, _)| *rank)
+        .map(|(_, id)| id);
+
+    if let Some(id) = matched {
+        conn.execute(
+            r#"
+            UPDATE networks SET
+                gateway_mac = COALESCE(gateway_mac, ?2),
+                ssid = COALESCE(ssid, ?3),
+                last_seen = datetime('now')
+            WHERE id = ?1
+            "#,
+            params![id, gateway_mac, ssid],
+        )
+        .context("Failed to update network profile")?;
+        return Ok(id);
+    }
+
+    let name = ssid.unwrap_or(&result.subnet);
+    conn.execute(
+        "INSERT INTO networks (name, subnet, gateway_mac, ssid) VALUES (?1, ?2, ?3, ?4)",
+        params![name, result.subnet, gateway_mac, ssid],
+    )
+    .context("Failed to create network profile")?;
+    let id = conn.last_insert_rowid();
+    tracing::info!("New network profile {} ({})", id, name);
+    Ok(id)
+}
+
+/// All network profiles, most recently scanned first
+pub fn get_networks(conn: &Connection) -> Result<Vec<NetworkProfile>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM networks ORDER BY last_seen DESC, id DESC",
+        NETWORK_COLUMNS
+    ))?;
+    let networks = stmt
+        .query_map([], network_from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(networks)
+}
+
+/// Get one network profile
+pub fn get_network(conn: &Connection, id: i64) -> Result<Option<NetworkProfile>> {
+    conn.query_row(
+        &format!("SELECT {} FROM networks WHERE id = ?1", NETWORK_COLUMNS),
+        params![id],
+        network_from_row,
+    )
+    .optional()
+    .context("Failed to get network profile")
+}
+
+/// The network of the most recent scan, if any
+pub fn latest_network_id(conn: &Connection) -> Result<Option<i64>> {
+    conn.query_row(
+        "SELECT network_id FROM scans WHERE network_id IS NOT NULL ORDER BY id DESC LIMIT 1",
+        [],
+        |row| row.get(0),
+    )
+    .optional()
+    .context("Failed to get latest network profile")
+}
+
+/// Rename a network profile, e.g. "192.168.1.0/24" to "Home"
+pub fn rename_network(conn: &Connection, id: i64, name: &str) -> Result<bool> {
+    let name = name.trim();
+    if name.is_empty() {
+        bail!("Network name must not be empty");
+    }
+    let updated = conn
+        .execute(
+            "UPDATE networks SET name = ?2 WHERE id = ?1",
+            params![id, name],
+        )
+        .context("Failed to rename network profile")?;
+    Ok(updated > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::queries::{
+        get_devices_in_network, get_network_stats_for, get_recent_scans_in_network, insert_scan,
+    };
+    use crate::database::Database;
+    use crate::models::HostInfo;
+
+    fn host(ip: &str, mac: &str, device_type: &str) -> HostInfo {
+        HostInfo::new(
+            ip.to_string(),
+            mac.to_string(),
+            device_type.to_string(),
+            "ARP".to_string(),
+        )
+    }
+
+    fn scan(gateway_mac: &str, ssid: Option<&str>, device_mac: &str) -> ScanResult {
+        ScanResult {
+            interface_name: "wlan0".to_string(),
+            local_ip: "192.168.1.50".to_string(),
+            local_mac: "AA:BB:CC:00:00:50".to_string(),
+            subnet: "192.168.1.0/24".to_string(),
+            scan_method: "ARP".to_string(),
+            arp_discovered: 2,
+            icmp_discovered: 0,
+            total_hosts: 2,
+            scan_duration_ms: 100,
+            active_hosts: vec![
+                host("192.168.1.1", gateway_mac, "ROUTER"),
+                host("192.168.1.20", device_mac, "PC"),
+            ],
+            wan: None,
+            ssid: ssid.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_networks_keep_inventories_apart() {
+        let db = Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
+
+        // Same private range at home and at the office, different gateways
+        let home = scan("AA:00:00:00:00:01", Some("HomeWifi"), "AA:00:00:00:00:20");
+        let office = scan("BB:00:00:00:00:01", None, "BB:00:00:00:00:20");
+        insert_scan(&conn, &home).unwrap();
+        insert_scan(&conn, &office).unwrap();
+        insert_scan(&conn, &home).unwrap();
+
+        let networks = get_networks(&conn).unwrap();
+        assert_eq!(networks.len(), 2);
+        let home_id = networks
+            .iter()
+            .find(|n| n.ssid.as_deref() == Some("HomeWifi"))
+            .unwrap()
+            .id;
+        assert_eq!(latest_network_id(&conn).unwrap(), Some(home_id));
+        assert_eq!(
+            get_network(&conn, home_id).unwrap().unwrap().name,
+            "HomeWifi"
+        );
+
+        let home_devices = get_devices_in_network(&conn, Some(home_id)).unwrap();
+        let macs: Vec<&str> = home_devices.iter().map(|d| d.mac.as_str()).collect();
+        assert_eq!(macs.len(), 2);
+        assert!(macs.iter().all(|mac| mac.starts_with("AA:")));
+        assert_eq!(get_devices_in_network(&conn, None).unwrap().len(), 4);
+
+        let stats = get_network_stats_for(&conn, Some(home_id)).unwrap();
+        assert_eq!((stats.total_devices, stats.online_devices), (2, 2));
+        assert_eq!(
+            get_recent_scans_in_network(&conn, Some(home_id), None, 10)
+                .unwrap()
+                .len(),
+            2
+        );
+
+        // A scan that found no gateway still lands on the profile its SSID names
+        let mut no_gateway = scan("AA:00:00:00:00:01", Some("HomeWifi"), "AA:00:00:00:00:21");
+        no_gateway.active_hosts.remove(0);
+        let scan_id = insert_scan(&conn, &no_gateway).unwrap();
+        let network_id: Option<i64> = conn
+            .query_row(
+                "SELECT network_id FROM scans WHERE id = ?1",
+                [scan_id],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(network_id, Some(home_id));
+        assert_eq!(get_networks(&conn).unwrap().len(), 2);
+
+        assert!(rename_network(&conn, home_id, "  Home ").unwrap());
+        assert_eq!(get_network(&conn, home_id).unwrap().unwrap().name, "Home");
+        assert!(rename_network(&conn, home_id, " ").is_err());
+    }
+}
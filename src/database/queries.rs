@@ -1,13 +1,23 @@
 //! Database query functions
 //!
 //! CRUD operations for scans, devices, and alerts
+//!
+//! Functions take the `&Connection` handed out by
+//! [`Database::read`](super::Database::read) or
+//! [`Database::write`](super::Database::write) rather than the pool itself,
+//! so a caller can run several of them on one connection and inside one
+//! transaction. Reads belong on a reader; anything that writes needs the
+//! writer.
 
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
 use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 
 use super::models::*;
-use crate::models::{HostInfo, ScanResult};
+use crate::groups::GroupPolicy;
+use crate::models::{HostInfo, ScanDiff, ScanResult};
+use crate::network::{annotate_ip_conflicts, best_correlation, DeviceFingerprint};
 
 /// Parameters used to insert an alert record.
 pub struct AlertInsert<'a> {
@@ -18,6 +28,8 @@ pub struct AlertInsert<'a> {
     pub dedupe_key: Option<&'a str>,
     pub message: &'a str,
     pub severity: AlertSeverity,
+    /// Supporting data stored with the alert
+    pub evidence: Option<&'a serde_json::Value>,
 }
 
 /// Insert a scan result into the database
@@ -26,12 +38,16 @@ pub fn insert_scan(conn: &Connection, result: &ScanResult) -> Result<i64> {
         .context("Failed to start insert_scan transaction")?;
 
     let insert_result = (|| -> Result<i64> {
+        let duplicate_of = find_duplicate_scan(conn, result)?;
+        let network_id = super::networks::identify_network(conn, result)?;
+
         conn.execute(
             r#"
             INSERT INTO scans (
                 interface_name, local_ip, local_mac, subnet, scan_method,
-                arp_discovered, icmp_discovered, total_hosts, duration_ms
-            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+                arp_discovered, icmp_discovered, total_hosts, duration_ms, duplicate_of,
+                network_id
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
             "#,
             params![
                 result.interface_name,
@@ -43,6 +59,8 @@ pub fn insert_scan(conn: &Connection, result: &ScanResult) -> Result<i64> {
                 result.icmp_discovered as i32,
                 result.total_hosts as i32,
                 result.scan_duration_ms as i64,
+                duplicate_of,
+                network_id,
             ],
         )
         .context("Failed to insert scan")?;
@@ -50,9 +68,18 @@ pub fn insert_scan(conn: &Connection, result: &ScanResult) -> Result<i64> {
         let scan_id = conn.last_insert_rowid();
 
         // Insert/update each discovered host
+        let scan_macs: HashSet<&str> = result.active_hosts.iter().map(|h| h.mac.as_str()).collect();
         for host in &result.active_hosts {
-            upsert_device_from_host(conn, host, scan_id)?;
+            upsert_device_from_host(conn, host, scan_id, &scan_macs)?;
         }
+        conn.execute(
+            r#"
+            UPDATE devices SET network_id = ?1
+            WHERE id IN (SELECT device_id FROM device_history WHERE scan_id = ?2)
+            "#,
+            params![network_id, scan_id],
+        )
+        .context("Failed to assign devices to network")?;
 
         Ok(scan_id)
     })();
@@ -72,16 +99,93 @@ pub fn insert_scan(conn: &Connection, result: &ScanResult) -> Result<i64> {
     }
 }
 
-/// Insert or update a device from scan result
-fn upsert_device_from_host(conn: &Connection, host: &HostInfo, scan_id: i64) -> Result<i64> {
-    // Try to get existing device
-    let device_id: Option<i64> = conn
+/// Find the scan a new result repeats, if any
+///
+/// A scan is a duplicate when the previous scan ran on the same interface and
+/// subnet within `DUPLICATE_SCAN_WINDOW_SECS` and found exactly the same
+/// hosts (MAC, IP and open ports). Duplicates point at the first scan of the run.
+fn find_duplicate_scan(conn: &Connection, result: &ScanResult) -> Result<Option<i64>> {
+    let previous: Option<(i64, Option<i64>, String, String)> = conn
         .query_row(
-            "SELECT id FROM devices WHERE mac = ?1",
-            params![&host.mac],
-            |row| row.get(0),
+            r#"
+            SELECT id, duplicate_of, interface_name, subnet FROM scans
+            WHERE scan_time >= datetime('now', ?1)
+            ORDER BY id DESC
+            LIMIT 1
+            "#,
+            params![format!(
+                "-{} seconds",
+                crate::config::DUPLICATE_SCAN_WINDOW_SECS
+            )],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
         )
-        .ok();
+        .optional()
+        .context("Failed to look up previous scan")?;
+
+    let Some((prev_id, prev_duplicate_of, interface_name, subnet)) = previous else {
+        return Ok(None);
+    };
+    if interface_name != result.interface_name || subnet != result.subnet {
+        return Ok(None);
+    }
+
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT d.mac, h.ip, h.open_ports FROM device_history h
+        JOIN devices d ON d.id = h.device_id
+        WHERE h.scan_id = ?1
+        "#,
+    )?;
+    let previous_hosts = stmt
+        .query_map(params![prev_id], |row| {
+            let ports: String = row.get::<_, Option<String>>(2)?.unwrap_or_default();
+            let mut ports: Vec<u16> = ports.split(',').filter_map(|p| p.parse().ok()).collect();
+            ports.sort_unstable();
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, ports))
+        })?
+        .collect::<rusqlite::Result<HashSet<_>>>()?;
+
+    let current_hosts: HashSet<(String, String, Vec<u16>)> = result
+        .active_hosts
+        .iter()
+        .map(|host| {
+            let mut ports = host.open_ports.clone();
+            ports.sort_unstable();
+            (host.mac.clone(), host.ip.clone(), ports)
+        })
+        .collect();
+
+    Ok((previous_hosts == current_hosts).then(|| prev_duplicate_of.unwrap_or(prev_id)))
+}
+
+/// Whether duplicate scans are hidden from history and scan counts (default on)
+pub fn collapse_duplicate_scans(conn: &Connection) -> Result<bool> {
+    Ok(get_setting(conn, SETTING_COLLAPSE_DUPLICATE_SCANS)?.is_none_or(|value| value != "false"))
+}
+
+/// Enable or disable collapsing of duplicate scans
+pub fn set_collapse_duplicate_scans(conn: &Connection, enabled: bool) -> Result<()> {
+    set_setting(
+        conn,
+        SETTING_COLLAPSE_DUPLICATE_SCANS,
+        if enabled { "true" } else { "false" },
+    )
+}
+
+/// Insert or update a device from scan result
+fn upsert_device_from_host(
+    conn: &Connection,
+    host: &HostInfo,
+    scan_id: i64,
+    scan_macs: &HashSet<&str>,
+) -> Result<i64> {
+    // Try to get existing device
+    let device_id = match find_device_id(conn, &host.mac)? {
+        Some(id) => Some(id),
+        // A new private address may belong to a device known under another one
+        None if host.is_randomized => link_randomized_mac(conn, host, scan_id, scan_macs)?,
+        None => None,
+    };
 
     let device_id = if let Some(id) = device_id {
         // Update existing device
@@ -143,8 +247,8 @@ fn upsert_device_from_host(conn: &Connection, host: &HostInfo, scan_id: i64) ->
         r#"
         INSERT INTO device_history (
             scan_id, device_id, ip, response_time_ms, ttl, risk_score, is_randomized,
-            security_grade, is_online, discovery_method, open_ports
-        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+            security_grade, is_online, discovery_method, open_ports, filtered_ports, host_state
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)
         "#,
         params![
             scan_id,
@@ -158,596 +262,5705 @@ fn upsert_device_from_host(conn: &Connection, host: &HostInfo, scan_id: i64) ->
             true,
             &host.discovery_method,
             open_ports_str,
+            join_ports(&host.filtered_ports),
+            host.host_state.map(|state| state.as_str()),
         ],
     )
     .context("Failed to insert device history")?;
 
+    record_device_ports(conn, device_id, scan_id, host)?;
+    record_topology_edges(conn, device_id, scan_id, host)?;
+    record_scan_findings(conn, device_id, scan_id, host)?;
+
     Ok(device_id)
 }
 
-/// Get recent scans
-pub fn get_recent_scans(conn: &Connection, limit: i32) -> Result<Vec<ScanRecord>> {
-    let mut stmt = conn.prepare(
-        r#"
-        SELECT id, scan_time, interface_name, local_ip, local_mac, subnet,
-               scan_method, arp_discovered, icmp_discovered, total_hosts, duration_ms
-        FROM scans
-        ORDER BY scan_time DESC
-        LIMIT ?1
-        "#,
-    )?;
+/// Store the vulnerabilities and port warnings a scan reported for a device
+fn record_scan_findings(
+    conn: &Connection,
+    device_id: i64,
+    scan_id: i64,
+    host: &HostInfo,
+) -> Result<()> {
+    let vulnerabilities = host.vulnerabilities.iter().map(|vuln| {
+        (
+            FindingKind::Vulnerability,
+            vuln.cve_id.clone(),
+            &vuln.severity,
+            &vuln.description,
+        )
+    });
+    let port_warnings = host.port_warnings.iter().map(|warning| {
+        (
+            FindingKind::PortWarning,
+            warning.port.to_string(),
+            &warning.severity,
+            &warning.warning,
+        )
+    });
 
-    let scans = stmt
-        .query_map(params![limit], |row| {
-            Ok(ScanRecord {
-                id: row.get(0)?,
-                scan_time: parse_datetime_column(row.get::<_, String>(1)?, 1)?,
-                interface_name: row.get(2)?,
-                local_ip: row.get(3)?,
-                local_mac: row.get(4)?,
-                subnet: row.get(5)?,
-                scan_method: row.get(6)?,
-                arp_discovered: row.get(7)?,
-                icmp_discovered: row.get(8)?,
-                total_hosts: row.get(9)?,
-                duration_ms: row.get(10)?,
-            })
-        })?
-        .collect::<rusqlite::Result<Vec<_>>>()?;
+    for (kind, key, severity, description) in vulnerabilities.chain(port_warnings) {
+        conn.execute(
+            r#"
+            INSERT OR IGNORE INTO scan_findings
+                (scan_id, device_id, kind, finding_key, severity, description)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            "#,
+            params![
+                scan_id,
+                device_id,
+                kind.as_str(),
+                key,
+                severity,
+                description
+            ],
+        )
+        .context("Failed to record scan finding")?;
+    }
+    Ok(())
+}
 
-    Ok(scans)
+/// Update the per-device port table with the ports a scan found open
+///
+/// Ports opened or closed since the device's previous sighting are added to
+/// its port change history.
+fn record_device_ports(
+    conn: &Connection,
+    device_id: i64,
+    scan_id: i64,
+    host: &HostInfo,
+) -> Result<()> {
+    let previous: BTreeSet<u16> = conn
+        .query_row(
+            r#"
+            SELECT open_ports FROM device_history
+            WHERE device_id = ?1 AND scan_id < ?2
+            ORDER BY scan_id DESC LIMIT 1
+            "#,
+            params![device_id, scan_id],
+            |row| row.get::<_, Option<String>>(0),
+        )
+        .optional()?
+        .flatten()
+        .map(|ports| ports.split(',').filter_map(|p| p.parse().ok()).collect())
+        .unwrap_or_default();
+    let current: BTreeSet<u16> = host.open_ports.iter().copied().collect();
+    let opened = current.difference(&previous).map(|&port| (port, true));
+    let closed = previous.difference(&current).map(|&port| (port, false));
+    for (port, opened) in opened.chain(closed) {
+        conn.execute(
+            r#"
+            INSERT INTO device_port_events (device_id, port, opened, scan_id, changed_at)
+            SELECT ?1, ?2, ?3, id, scan_time FROM scans WHERE id = ?4
+            "#,
+            params![device_id, port, opened, scan_id],
+        )
+        .context("Failed to record port change")?;
+    }
+
+    for &port in &host.open_ports {
+        let service = host
+            .port_warnings
+            .iter()
+            .find(|warning| warning.port == port)
+            .map(|warning| warning.service.as_str());
+
+        conn.execute(
+            r#"
+            INSERT INTO device_ports (device_id, port, protocol, first_seen, last_seen, last_scan_id, service)
+            SELECT ?1, ?2, 'tcp', scan_time, scan_time, id, ?4 FROM scans WHERE id = ?3
+            ON CONFLICT(device_id, port, protocol) DO UPDATE SET
+                last_seen = excluded.last_seen,
+                last_scan_id = excluded.last_scan_id,
+                service = COALESCE(excluded.service, service)
+            "#,
+            params![device_id, port, scan_id, service],
+        )
+        .context("Failed to record device port")?;
+    }
+    Ok(())
 }
 
-/// Get all devices
-pub fn get_all_devices(conn: &Connection) -> Result<Vec<DeviceRecord>> {
-    let mut stmt = conn.prepare(
+const DEVICE_PORT_COLUMNS: &str = r#"
+    d.mac, dp.port, dp.protocol, dp.service, dp.banner, dp.first_seen, dp.last_seen,
+    dp.last_scan_id IS NOT NULL AND dp.last_scan_id =
+        (SELECT MAX(scan_id) FROM device_history WHERE device_id = dp.device_id)
+"#;
+
+fn device_port_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<DevicePortRecord> {
+    Ok(DevicePortRecord {
+        device_mac: row.get(0)?,
+        port: row.get(1)?,
+        protocol: row.get(2)?,
+        service: row.get(3)?,
+        banner: row.get(4)?,
+        first_seen: parse_datetime_column(row.get(5)?, 5)?,
+        last_seen: parse_datetime_column(row.get(6)?, 6)?,
+        is_open: row.get(7)?,
+    })
+}
+
+/// Every port ever seen open on a device, by port number
+pub fn get_device_ports(conn: &Connection, mac: &str) -> Result<Vec<DevicePortRecord>> {
+    let Some(device_id) = find_device_id(conn, mac)? else {
+        return Ok(Vec::new());
+    };
+
+    let mut stmt = conn.prepare(&format!(
         r#"
-        SELECT id, mac, first_seen, last_seen, last_ip, vendor,
-               device_type, hostname, os_guess, custom_name, notes
-        FROM devices
-        ORDER BY last_seen DESC
+        SELECT {} FROM device_ports dp
+        JOIN devices d ON d.id = dp.device_id
+        WHERE dp.device_id = ?1
+        ORDER BY dp.port, dp.protocol
         "#,
-    )?;
-
-    let devices = stmt
-        .query_map([], |row| {
-            Ok(DeviceRecord {
-                id: row.get(0)?,
-                mac: row.get(1)?,
-                first_seen: parse_datetime_column(row.get::<_, String>(2)?, 2)?,
-                last_seen: parse_datetime_column(row.get::<_, String>(3)?, 3)?,
-                last_ip: row.get(4)?,
-                vendor: row.get(5)?,
-                device_type: row.get(6)?,
-                hostname: row.get(7)?,
-                os_guess: row.get(8)?,
-                custom_name: row.get(9)?,
-                notes: row.get(10)?,
-                security_grade: None,
-            })
-        })?
-        .collect::<rusqlite::Result<Vec<_>>>()?;
+        DEVICE_PORT_COLUMNS
+    ))?;
+    let ports = stmt
+        .query_map(params![device_id], device_port_from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("Failed to get device ports")?;
+    Ok(ports)
+}
 
-    Ok(devices)
+/// Devices a port has been seen open on, most recently seen first
+pub fn get_devices_with_port(
+    conn: &Connection,
+    port: u16,
+    protocol: &str,
+) -> Result<Vec<DevicePortRecord>> {
+    let mut stmt = conn.prepare(&format!(
+        r#"
+        SELECT {} FROM device_ports dp
+        JOIN devices d ON d.id = dp.device_id
+        WHERE dp.port = ?1 AND dp.protocol = ?2
+        ORDER BY dp.last_seen DESC, d.mac
+        "#,
+        DEVICE_PORT_COLUMNS
+    ))?;
+    let ports = stmt
+        .query_map(params![port, protocol], device_port_from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("Failed to get devices with port")?;
+    Ok(ports)
 }
 
-/// Get device by MAC address
-pub fn get_device_by_mac(conn: &Connection, mac: &str) -> Result<Option<DeviceRecord>> {
-    let result = conn.query_row(
+/// Ports first seen open on any device in the last `days` days, newest first
+///
+/// Ports that closed and reopened are not new; see
+/// [`get_port_changes_since`] for every change.
+pub fn get_new_ports(conn: &Connection, days: u32) -> Result<Vec<DevicePortRecord>> {
+    let mut stmt = conn.prepare(&format!(
         r#"
-        SELECT id, mac, first_seen, last_seen, last_ip, vendor,
-               device_type, hostname, os_guess, custom_name, notes
-        FROM devices WHERE mac = ?1
+        SELECT {} FROM device_ports dp
+        JOIN devices d ON d.id = dp.device_id
+        WHERE dp.first_seen >= datetime('now', ?1)
+        ORDER BY dp.first_seen DESC, d.mac, dp.port
         "#,
-        params![mac],
-        |row| {
-            Ok(DeviceRecord {
-                id: row.get(0)?,
-                mac: row.get(1)?,
-                first_seen: parse_datetime_column(row.get::<_, String>(2)?, 2)?,
-                last_seen: parse_datetime_column(row.get::<_, String>(3)?, 3)?,
-                last_ip: row.get(4)?,
-                vendor: row.get(5)?,
-                device_type: row.get(6)?,
-                hostname: row.get(7)?,
-                os_guess: row.get(8)?,
-                custom_name: row.get(9)?,
-                notes: row.get(10)?,
-                security_grade: None,
-            })
-        },
-    );
+        DEVICE_PORT_COLUMNS
+    ))?;
+    let ports = stmt
+        .query_map(params![format!("-{} days", days)], device_port_from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("Failed to get new ports")?;
+    Ok(ports)
+}
 
-    match result {
-        Ok(device) => Ok(Some(device)),
-        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
-        Err(e) => Err(e.into()),
-    }
+fn port_change_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<PortChangeRecord> {
+    Ok(PortChangeRecord {
+        device_mac: row.get(0)?,
+        port: row.get(1)?,
+        protocol: row.get(2)?,
+        opened: row.get(3)?,
+        scan_id: row.get(4)?,
+        changed_at: parse_datetime_column(row.get(5)?, 5)?,
+    })
 }
 
-/// Update device custom name
-pub fn update_device_name(conn: &Connection, mac: &str, custom_name: &str) -> Result<()> {
-    conn.execute(
-        "UPDATE devices SET custom_name = ?2 WHERE mac = ?1",
-        params![mac, custom_name],
-    )
-    .context("Failed to update device name")?;
-    Ok(())
+/// A device's port openings and closings, oldest first (for its timeline)
+pub fn get_device_port_changes(conn: &Connection, mac: &str) -> Result<Vec<PortChangeRecord>> {
+    let Some(device_id) = find_device_id(conn, mac)? else {
+        return Ok(Vec::new());
+    };
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT d.mac, e.port, e.protocol, e.opened, e.scan_id, e.changed_at
+        FROM device_port_events e
+        JOIN devices d ON d.id = e.device_id
+        WHERE e.device_id = ?1
+        ORDER BY e.changed_at, e.id
+        "#,
+    )?;
+    let changes = stmt
+        .query_map(params![device_id], port_change_from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("Failed to get port changes")?;
+    Ok(changes)
 }
 
-/// Get device history for a specific device
-pub fn get_device_history(
+/// Port openings and closings on every device since `since`, newest first
+pub fn get_port_changes_since(
     conn: &Connection,
-    device_id: i64,
-    limit: i32,
-) -> Result<Vec<DeviceHistoryRecord>> {
+    since: DateTime<Utc>,
+) -> Result<Vec<PortChangeRecord>> {
     let mut stmt = conn.prepare(
         r#"
-        SELECT id, scan_id, device_id, ip, response_time_ms, ttl,
-               risk_score, is_online, discovery_method, open_ports
-        FROM device_history
-        WHERE device_id = ?1
-        ORDER BY id DESC
-        LIMIT ?2
+        SELECT d.mac, e.port, e.protocol, e.opened, e.scan_id, e.changed_at
+        FROM device_port_events e
+        JOIN devices d ON d.id = e.device_id
+        WHERE e.changed_at >= ?1
+        ORDER BY e.changed_at DESC, e.id DESC
         "#,
     )?;
-
-    let history = stmt
-        .query_map(params![device_id, limit], |row| {
-            let ports_str: String = row.get::<_, Option<String>>(9)?.unwrap_or_default();
-            let open_ports: Vec<u16> = ports_str
-                .split(',')
-                .filter_map(|s| s.parse().ok())
-                .collect();
-
-            Ok(DeviceHistoryRecord {
-                id: row.get(0)?,
-                scan_id: row.get(1)?,
-                device_id: row.get(2)?,
-                ip: row.get(3)?,
-                response_time_ms: row.get(4)?,
-                ttl: row.get(5)?,
-                risk_score: row.get(6)?,
-                is_online: row.get::<_, i32>(7)? == 1,
-                discovery_method: row.get(8)?,
-                open_ports,
-            })
-        })?
-        .collect::<rusqlite::Result<Vec<_>>>()?;
-
-    Ok(history)
+    let changes = stmt
+        .query_map(
+            params![since.format("%Y-%m-%d %H:%M:%S").to_string()],
+            port_change_from_row,
+        )?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("Failed to get port changes")?;
+    Ok(changes)
 }
 
-/// Insert an alert
-pub fn insert_alert(
+/// Store the banner a service sent, returning whether the port is known
+pub fn set_device_port_banner(
     conn: &Connection,
-    alert_type: AlertType,
-    device_id: Option<i64>,
-    device_mac: Option<&str>,
-    device_ip: Option<&str>,
-    message: &str,
-    severity: AlertSeverity,
-) -> Result<i64> {
-    let alert = AlertInsert {
-        alert_type,
-        device_id,
-        device_mac,
-        device_ip,
-        dedupe_key: None,
-        message,
-        severity,
+    mac: &str,
+    port: u16,
+    protocol: &str,
+    banner: &str,
+) -> Result<bool> {
+    let Some(device_id) = find_device_id(conn, mac)? else {
+        return Ok(false);
     };
-    insert_alert_with_dedupe_key(conn, &alert)
+    let updated = conn
+        .execute(
+            "UPDATE device_ports SET banner = ?4 WHERE device_id = ?1 AND port = ?2 AND protocol = ?3",
+            params![device_id, port, protocol, banner],
+        )
+        .context("Failed to store port banner")?;
+    Ok(updated > 0)
 }
 
-/// Insert an alert with an optional semantic dedupe key.
-pub fn insert_alert_with_dedupe_key(conn: &Connection, alert: &AlertInsert<'_>) -> Result<i64> {
-    conn.execute(
+/// Every finding ever reported, when it first appeared and when it went away
+///
+/// Limited to one device when `mac` is given. A finding counts as
+/// remediated once a later scan of the device no longer reports it.
+pub fn get_finding_history(
+    conn: &Connection,
+    mac: Option<&str>,
+) -> Result<Vec<FindingHistoryRecord>> {
+    let device_id = match mac {
+        Some(mac) => match find_device_id(conn, mac)? {
+            Some(id) => Some(id),
+            None => return Ok(Vec::new()),
+        },
+        None => None,
+    };
+
+    let mut stmt = conn.prepare(
         r#"
-        INSERT INTO alerts (
-            alert_type, device_id, device_mac, device_ip, dedupe_key, message, severity
-        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+        WITH spans AS (
+            SELECT f.device_id, f.kind, f.finding_key,
+                   MIN(s.scan_time) AS first_seen, MAX(s.scan_time) AS last_seen,
+                   MAX(f.scan_id) AS last_scan_id, COUNT(*) AS scan_count
+            FROM scan_findings f
+            JOIN scans s ON s.id = f.scan_id
+            WHERE ?1 IS NULL OR f.device_id = ?1
+            GROUP BY f.device_id, f.kind, f.finding_key
+        )
+        SELECT d.mac, sp.kind, sp.finding_key, latest.severity, latest.description,
+               sp.first_seen, sp.last_seen, sp.scan_count,
+               (SELECT MIN(s.scan_time) FROM device_history h
+                JOIN scans s ON s.id = h.scan_id
+                WHERE h.device_id = sp.device_id AND h.scan_id > sp.last_scan_id)
+        FROM spans sp
+        JOIN devices d ON d.id = sp.device_id
+        JOIN scan_findings latest ON latest.scan_id = sp.last_scan_id
+            AND latest.device_id = sp.device_id
+            AND latest.kind = sp.kind
+            AND latest.finding_key = sp.finding_key
+        ORDER BY sp.first_seen DESC, d.mac, sp.kind, sp.finding_key
         "#,
-        params![
-            alert.alert_type.to_string(),
-            alert.device_id,
-            alert.device_mac,
-            alert.device_ip,
-            alert.dedupe_key,
-            alert.message,
-            alert.severity.to_string(),
-        ],
-    )
-    .context("Failed to insert alert")?;
+    )?;
 
-    Ok(conn.last_insert_rowid())
+    let findings = stmt
+        .query_map(params![device_id], |row| {
+            let kind: String = row.get(1)?;
+            Ok(FindingHistoryRecord {
+                device_mac: row.get(0)?,
+                kind: kind.parse().map_err(|e: String| {
+                    rusqlite::Error::FromSqlConversionFailure(
+                        1,
+                        rusqlite::types::Type::Text,
+                        e.into(),
+                    )
+                })?,
+                key: row.get(2)?,
+                severity: row.get(3)?,
+                description: row.get(4)?,
+                first_seen: parse_datetime_column(row.get(5)?, 5)?,
+                last_seen: parse_datetime_column(row.get(6)?, 6)?,
+                scan_count: row.get(7)?,
+                remediated_at: row
+                    .get::<_, Option<String>>(8)?
+                    .map(|t| parse_datetime_column(t, 8))
+                    .transpose()?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("Failed to get finding history")?;
+
+    Ok(findings)
 }
 
-/// Insert alert only if a matching unread alert does not already exist recently.
-pub fn insert_alert_if_not_exists(
-    conn: &Connection,
-    alert: &AlertInsert<'_>,
-    dedupe_key: &str,
-    dedupe_window_minutes: i64,
-) -> Result<Option<i64>> {
-    let window_minutes = dedupe_window_minutes.max(1);
-    let window_expr = format!("-{} minutes", window_minutes);
+/// Discovery protocol of the neighbors on `HostInfo`, which come from SNMP LLDP walks
+const LLDP_EDGE_SOURCE: &str = "lldp";
 
-    let existing: Option<i64> = conn
-        .query_row(
+/// Remember the LLDP neighbors a scan reported for a device
+fn record_topology_edges(
+    conn: &Connection,
+    device_id: i64,
+    scan_id: i64,
+    host: &HostInfo,
+) -> Result<()> {
+    for neighbor in &host.neighbors {
+        conn.execute(
             r#"
-            SELECT id
-            FROM alerts
-            WHERE alert_type = ?1
-              AND COALESCE(device_mac, '') = COALESCE(?2, '')
-              AND COALESCE(dedupe_key, '') = ?3
-              AND is_read = 0
-              AND created_at >= datetime('now', ?4)
-            ORDER BY id DESC
-            LIMIT 1
+            INSERT INTO topology_edges (
+                local_device_id, local_port, remote_device, remote_port, remote_ip,
+                source, first_seen, last_seen, last_scan_id
+            )
+            SELECT ?1, ?2, ?3, ?4, ?5, ?6, scan_time, scan_time, id FROM scans WHERE id = ?7
+            ON CONFLICT(local_device_id, local_port, remote_device, remote_port) DO UPDATE SET
+                last_seen = excluded.last_seen,
+                last_scan_id = excluded.last_scan_id,
+                remote_ip = COALESCE(excluded.remote_ip, remote_ip)
             "#,
             params![
-                alert.alert_type.to_string(),
-                alert.device_mac,
-                dedupe_key,
-                window_expr
+                device_id,
+                &neighbor.local_port,
+                &neighbor.remote_device,
+                &neighbor.remote_port,
+                &neighbor.remote_ip,
+                LLDP_EDGE_SOURCE,
+                scan_id,
             ],
-            |row| row.get(0),
         )
-        .optional()?;
-
-    if existing.is_some() {
-        return Ok(None);
+        .context("Failed to record topology edge")?;
     }
+    Ok(())
+}
 
-    let deduped_alert = AlertInsert {
-        alert_type: alert.alert_type.clone(),
-        device_id: alert.device_id,
-        device_mac: alert.device_mac,
-        device_ip: alert.device_ip,
-        dedupe_key: Some(dedupe_key),
-        message: alert.message,
-        severity: alert.severity.clone(),
+const TOPOLOGY_EDGE_COLUMNS: &str = r#"
+    d.mac, te.local_port, te.remote_device, te.remote_port, te.remote_ip,
+    (SELECT r.mac FROM devices r WHERE r.last_ip = te.remote_ip
+        ORDER BY r.last_seen DESC LIMIT 1),
+    te.source, te.first_seen, te.last_seen,
+    te.last_scan_id IS NOT NULL AND te.last_scan_id =
+        (SELECT MAX(scan_id) FROM device_history WHERE device_id = te.local_device_id)
+"#;
+
+fn topology_edge_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<TopologyEdgeRecord> {
+    Ok(TopologyEdgeRecord {
+        local_device_mac: row.get(0)?,
+        local_port: row.get(1)?,
+        remote_device: row.get(2)?,
+        remote_port: row.get(3)?,
+        remote_ip: row.get(4)?,
+        remote_device_mac: row.get(5)?,
+        source: row.get(6)?,
+        first_seen: parse_datetime_column(row.get(7)?, 7)?,
+        last_seen: parse_datetime_column(row.get(8)?, 8)?,
+        is_current: row.get(9)?,
+    })
+}
+
+/// Every known topology edge, optionally only those seen since a time
+///
+/// Edges stay after their device goes offline, so the topology can be
+/// drawn from history; `is_current` tells which ones the latest scan saw.
+pub fn get_topology_edges(
+    conn: &Connection,
+    since: Option<DateTime<Utc>>,
+) -> Result<Vec<TopologyEdgeRecord>> {
+    let mut stmt = conn.prepare(&format!(
+        r#"
+        SELECT {} FROM topology_edges te
+        JOIN devices d ON d.id = te.local_device_id
+        WHERE ?1 IS NULL OR te.last_seen >= ?1
+        ORDER BY d.mac, te.local_port, te.remote_device
+        "#,
+        TOPOLOGY_EDGE_COLUMNS
+    ))?;
+    let edges = stmt
+        .query_map(
+            params![since.map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())],
+            topology_edge_from_row,
+        )?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("Failed to get topology edges")?;
+    Ok(edges)
+}
+
+/// Topology edges reported by one device
+pub fn get_device_topology_edges(conn: &Connection, mac: &str) -> Result<Vec<TopologyEdgeRecord>> {
+    let Some(device_id) = find_device_id(conn, mac)? else {
+        return Ok(Vec::new());
     };
 
-    let id = insert_alert_with_dedupe_key(conn, &deduped_alert)?;
-    Ok(Some(id))
+    let mut stmt = conn.prepare(&format!(
+        r#"
+        SELECT {} FROM topology_edges te
+        JOIN devices d ON d.id = te.local_device_id
+        WHERE te.local_device_id = ?1
+        ORDER BY te.local_port, te.remote_device
+        "#,
+        TOPOLOGY_EDGE_COLUMNS
+    ))?;
+    let edges = stmt
+        .query_map(params![device_id], topology_edge_from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("Failed to get device topology edges")?;
+    Ok(edges)
 }
 
-/// Get unread alerts
-pub fn get_unread_alerts(conn: &Connection) -> Result<Vec<AlertRecord>> {
+/// Get recent scans
+///
+/// Duplicate scans are left out when collapsing is enabled; the scan they
+/// repeat carries the count instead.
+pub fn get_recent_scans(conn: &Connection, limit: i32) -> Result<Vec<ScanRecord>> {
+    query_recent_scans(conn, limit, None, None)
+}
+
+/// Get recent scans of one network profile (`None` for all networks),
+/// optionally only those that found a device carrying `tag`
+pub fn get_recent_scans_in_network(
+    conn: &Connection,
+    network_id: Option<i64>,
+    tag: Option<&str>,
+    limit: i32,
+) -> Result<Vec<ScanRecord>> {
+    query_recent_scans(conn, limit, tag.map(str::trim), network_id)
+}
+
+/// Get recent scans that found at least one device carrying `tag`
+pub fn get_recent_scans_with_tag(
+    conn: &Connection,
+    tag: &str,
+    limit: i32,
+) -> Result<Vec<ScanRecord>> {
+    query_recent_scans(conn, limit, Some(tag.trim()), None)
+}
+
+fn query_recent_scans(
+    conn: &Connection,
+    limit: i32,
+    tag: Option<&str>,
+    network_id: Option<i64>,
+) -> Result<Vec<ScanRecord>> {
+    let collapse = collapse_duplicate_scans(conn)?;
     let mut stmt = conn.prepare(
         r#"
-        SELECT id, created_at, alert_type, device_id, device_mac, device_ip,
-               message, severity, is_read
-        FROM alerts
-        WHERE is_read = 0
-        ORDER BY created_at DESC
+        SELECT s.id, s.scan_time, s.interface_name, s.local_ip, s.local_mac, s.subnet,
+               s.scan_method, s.arp_discovered, s.icmp_discovered, s.total_hosts,
+               s.duration_ms, s.duplicate_of,
+               (SELECT COUNT(*) FROM scans d WHERE d.duplicate_of = s.id), s.label,
+               s.network_id
+        FROM scans s
+        WHERE (?2 = 0 OR s.duplicate_of IS NULL)
+          AND (?3 IS NULL OR EXISTS (
+              SELECT 1 FROM device_history dh
+              JOIN device_tags t ON t.device_id = dh.device_id
+              WHERE dh.scan_id = s.id AND t.tag = ?3
+          ))
+          AND (?4 IS NULL OR s.network_id = ?4)
+        ORDER BY s.scan_time DESC, s.id DESC
+        LIMIT ?1
         "#,
     )?;
 
-    let alerts = stmt
-        .query_map([], |row| {
-            let alert_type_str: String = row.get(2)?;
-            let severity_str: String = row.get(7)?;
+    let scans = stmt
+        .query_map(params![limit, collapse, tag, network_id], scan_from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
 
-            Ok(AlertRecord {
-                id: row.get(0)?,
-                created_at: parse_datetime_column(row.get::<_, String>(1)?, 1)?,
-                alert_type: parse_alert_type_or_default(&alert_type_str),
-                device_id: row.get(3)?,
-                device_mac: row.get(4)?,
-                device_ip: row.get(5)?,
-                message: row.get(6)?,
-                severity: parse_alert_severity_or_default(&severity_str),
-                is_read: row.get::<_, i32>(8)? == 1,
-            })
+    Ok(scans)
+}
+
+fn scan_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<ScanRecord> {
+    Ok(ScanRecord {
+        id: row.get(0)?,
+        scan_time: parse_datetime_column(row.get::<_, String>(1)?, 1)?,
+        interface_name: row.get(2)?,
+        local_ip: row.get(3)?,
+        local_mac: row.get(4)?,
+        subnet: row.get(5)?,
+        scan_method: row.get(6)?,
+        arp_discovered: row.get(7)?,
+        icmp_discovered: row.get(8)?,
+        total_hosts: row.get(9)?,
+        duration_ms: row.get(10)?,
+        duplicate_of: row.get(11)?,
+        duplicate_count: row.get(12)?,
+        label: row.get(13)?,
+        network_id: row.get(14)?,
+    })
+}
+
+/// Page of scans matching `query`
+pub fn query_scans(conn: &Connection, query: &ScanQuery) -> Result<Page<ScanRecord>> {
+    let collapse = collapse_duplicate_scans(conn)?;
+    let format = |t: DateTime<Utc>| t.format("%Y-%m-%d %H:%M:%S").to_string();
+    let filter = r#"
+        WHERE (?1 = 0 OR s.duplicate_of IS NULL)
+          AND (?2 IS NULL OR s.scan_time >= ?2)
+          AND (?3 IS NULL OR s.scan_time <= ?3)
+          AND (?4 IS NULL OR s.network_id = ?4)
+    "#;
+    let since = query.since.map(format);
+    let until = query.until.map(format);
+
+    let total: i64 = conn
+        .query_row(
+            &format!("SELECT COUNT(*) FROM scans s {}", filter),
+            params![collapse, since, until, query.network_id],
+            |row| row.get(0),
+        )
+        .context("Failed to count scans")?;
+
+    let order = if query.oldest_first { "ASC" } else { "DESC" };
+    let mut stmt = conn.prepare(&format!(
+        r#"
+        SELECT s.id, s.scan_time, s.interface_name, s.local_ip, s.local_mac, s.subnet,
+               s.scan_method, s.arp_discovered, s.icmp_discovered, s.total_hosts,
+               s.duration_ms, s.duplicate_of,
+               (SELECT COUNT(*) FROM scans d WHERE d.duplicate_of = s.id), s.label,
+               s.network_id
+        FROM scans s
+        {}
+        ORDER BY s.scan_time {order}, s.id {order}
+        LIMIT ?5 OFFSET ?6
+        "#,
+        filter,
+        order = order
+    ))?;
+    let items = stmt
+        .query_map(
+            params![
+                collapse,
+                since,
+                until,
+                query.network_id,
+                page_limit(query.limit),
+                query.offset
+            ],
+            scan_from_row,
+        )?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("Failed to query scans")?;
+
+    Ok(Page { items, total })
+}
+
+/// SQLite `LIMIT` for an optional page size (-1 is unlimited)
+fn page_limit(limit: Option<u32>) -> i64 {
+    limit.map(i64::from).unwrap_or(-1)
+}
+
+/// Compare the devices found by two scans (`scan_a` older, `scan_b` newer)
+pub fn diff_scans(conn: &Connection, scan_a: i64, scan_b: i64) -> Result<ScanDiff> {
+    let before = scan_hosts(conn, scan_a)?;
+    let after = scan_hosts(conn, scan_b)?;
+    Ok(ScanDiff::between(&before, &after))
+}
+
+/// Hosts recorded by one scan, with the open ports it saw
+fn scan_hosts(conn: &Connection, scan_id: i64) -> Result<Vec<HostInfo>> {
+    let exists: bool = conn
+        .query_row(
+            "SELECT EXISTS(SELECT 1 FROM scans WHERE id = ?1)",
+            params![scan_id],
+            |row| row.get(0),
+        )
+        .context("Failed to look up scan")?;
+    if !exists {
+        anyhow::bail!("Scan {} not found", scan_id);
+    }
+
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT d.mac, h.ip, h.open_ports, d.hostname, d.device_type, h.discovery_method
+        FROM device_history h
+        JOIN devices d ON d.id = h.device_id
+        WHERE h.scan_id = ?1
+        "#,
+    )?;
+    let hosts = stmt
+        .query_map(params![scan_id], |row| {
+            let mut host = HostInfo::new(
+                row.get(1)?,
+                row.get(0)?,
+                row.get::<_, Option<String>>(4)?.unwrap_or_default(),
+                row.get::<_, Option<String>>(5)?.unwrap_or_default(),
+            );
+            let ports: String = row.get::<_, Option<String>>(2)?.unwrap_or_default();
+            host.open_ports = ports.split(',').filter_map(|p| p.parse().ok()).collect();
+            host.hostname = row.get(3)?;
+            Ok(host)
         })?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("Failed to load scan hosts")?;
+    Ok(hosts)
+}
+
+/// Longest scan label accepted, in characters
+const MAX_SCAN_LABEL_CHARS: usize = 200;
+
+/// Set or clear (`None` or blank) the label of a scan, returning whether the scan exists
+pub fn set_scan_label(conn: &Connection, scan_id: i64, label: Option<&str>) -> Result<bool> {
+    let label = label.map(str::trim).filter(|l| !l.is_empty());
+    if label.is_some_and(|l| l.chars().count() > MAX_SCAN_LABEL_CHARS) {
+        anyhow::bail!(
+            "Scan label is longer than {} characters",
+            MAX_SCAN_LABEL_CHARS
+        );
+    }
+
+    let updated = conn
+        .execute(
+            "UPDATE scans SET label = ?2 WHERE id = ?1",
+            params![scan_id, label],
+        )
+        .context("Failed to set scan label")?;
+    Ok(updated > 0)
+}
+
+/// Columns read by `device_from_row`, for a query over `devices`
+const DEVICE_COLUMNS: &str = r#"
+    devices.id, devices.mac, devices.first_seen, devices.last_seen, devices.last_ip,
+    devices.vendor, devices.device_type, devices.hostname, devices.os_guess,
+    devices.custom_name, devices.notes,
+    (SELECT h.open_ports FROM device_history h
+     WHERE h.device_id = devices.id
+     ORDER BY h.scan_id DESC LIMIT 1),
+    devices.network_id
+"#;
+
+fn device_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<DeviceRecord> {
+    Ok(DeviceRecord {
+        id: row.get(0)?,
+        mac: row.get(1)?,
+        first_seen: parse_datetime_column(row.get::<_, String>(2)?, 2)?,
+        last_seen: parse_datetime_column(row.get::<_, String>(3)?, 3)?,
+        last_ip: row.get(4)?,
+        vendor: row.get(5)?,
+        device_type: row.get(6)?,
+        hostname: row.get(7)?,
+        os_guess: row.get(8)?,
+        custom_name: row.get(9)?,
+        notes: row.get(10)?,
+        security_grade: None,
+        tags: Vec::new(),
+        attributes: BTreeMap::new(),
+        availability: DeviceAvailability::default(),
+        open_ports: row
+            .get::<_, Option<String>>(11)?
+            .map(|ports| ports.split(',').filter_map(|p| p.parse().ok()).collect())
+            .unwrap_or_default(),
+        network_id: row.get(12)?,
+    })
+}
+
+/// Get all devices
+pub fn get_all_devices(conn: &Connection) -> Result<Vec<DeviceRecord>> {
+    get_devices_in_network(conn, None)
+}
+
+/// Get the devices last seen on one network profile (`None` for all networks)
+pub fn get_devices_in_network(
+    conn: &Connection,
+    network_id: Option<i64>,
+) -> Result<Vec<DeviceRecord>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM devices WHERE (?1 IS NULL OR network_id = ?1) ORDER BY last_seen DESC",
+        DEVICE_COLUMNS
+    ))?;
+
+    let devices = stmt
+        .query_map(params![network_id], device_from_row)?
         .collect::<rusqlite::Result<Vec<_>>>()?;
 
-    Ok(alerts)
+    let devices = attach_device_tags(conn, devices)?;
+    let devices = attach_device_attributes(conn, devices)?;
+    attach_device_availability(conn, devices)
 }
 
-/// Mark alert as read
-pub fn mark_alert_read(conn: &Connection, alert_id: i64) -> Result<()> {
-    conn.execute(
-        "UPDATE alerts SET is_read = 1 WHERE id = ?1",
-        params![alert_id],
-    )
-    .context("Failed to mark alert read")?;
-    Ok(())
+/// Get device by MAC address
+pub fn get_device_by_mac(conn: &Connection, mac: &str) -> Result<Option<DeviceRecord>> {
+    let result = conn.query_row(
+        &format!("SELECT {} FROM devices WHERE mac = ?1", DEVICE_COLUMNS),
+        params![mac],
+        device_from_row,
+    );
+
+    match result {
+        Ok(mut device) => {
+            device.tags = get_device_tags(conn, device.id)?;
+            device.attributes = get_device_attributes(conn, &device.mac)?;
+            let windows = availability_windows(conn, Some(device.id))?;
+            if let Some(windows) = windows.get(&device.id) {
+                device.availability = device_availability(windows);
+            }
+            Ok(Some(device))
+        }
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
 }
 
-/// Mark all alerts as read
-pub fn mark_all_alerts_read(conn: &Connection) -> Result<()> {
-    conn.execute("UPDATE alerts SET is_read = 1", [])
-        .context("Failed to mark all alerts read")?;
-    Ok(())
+/// Page of devices matching `query`
+pub fn query_devices(conn: &Connection, query: &DeviceQuery) -> Result<Page<DeviceRecord>> {
+    let format = |t: DateTime<Utc>| t.format("%Y-%m-%d %H:%M:%S").to_string();
+    let filter = r#"
+        WHERE (?1 IS NULL OR devices.device_type = ?1)
+          AND (?2 IS NULL OR (devices.id IN (
+              SELECT device_id FROM device_history
+              WHERE scan_id = (SELECT MAX(id) FROM scans)
+          )) = ?2)
+          AND (?3 IS NULL OR devices.last_seen >= ?3)
+          AND (?4 IS NULL OR devices.last_seen <= ?4)
+          AND (?5 IS NULL OR devices.network_id = ?5)
+    "#;
+    let device_type = query.device_type.as_deref();
+    let since = query.seen_since.map(format);
+    let until = query.seen_until.map(format);
+
+    let total: i64 = conn
+        .query_row(
+            &format!("SELECT COUNT(*) FROM devices {}", filter),
+            params![device_type, query.online, since, until, query.network_id],
+            |row| row.get(0),
+        )
+        .context("Failed to count devices")?;
+
+    let column = match query.sort {
+        DeviceSort::LastSeen => "devices.last_seen",
+        DeviceSort::FirstSeen => "devices.first_seen",
+        DeviceSort::Name => {
+            "COALESCE(devices.custom_name, devices.hostname, devices.mac) COLLATE NOCASE"
+        }
+        DeviceSort::Vendor => "devices.vendor COLLATE NOCASE",
+        DeviceSort::DeviceType => "devices.device_type",
+    };
+    let order = if query.descending { "DESC" } else { "ASC" };
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM devices {} ORDER BY {} {order}, devices.id {order} LIMIT ?6 OFFSET ?7",
+        DEVICE_COLUMNS,
+        filter,
+        column,
+        order = order
+    ))?;
+    let devices = stmt
+        .query_map(
+            params![
+                device_type,
+                query.online,
+                since,
+                until,
+                query.network_id,
+                page_limit(query.limit),
+                query.offset
+            ],
+            device_from_row,
+        )?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("Failed to query devices")?;
+
+    let devices = attach_device_tags(conn, devices)?;
+    let devices = attach_device_attributes(conn, devices)?;
+    let items = attach_device_availability(conn, devices)?;
+    Ok(Page { items, total })
 }
 
-/// Clear all alerts
-pub fn clear_all_alerts(conn: &Connection) -> Result<()> {
-    conn.execute("DELETE FROM alerts", [])
-        .context("Failed to clear all alerts")?;
-    Ok(())
+/// Most devices returned by one search
+const MAX_SEARCH_RESULTS: usize = 200;
+
+/// Full-text search over device MAC, IP, hostname, vendor, name, notes and
+/// custom fields
+///
+/// Every word of `query` must match (as a prefix), so "aa:bb" finds MACs
+/// starting with those octets and "192.168.1" finds addresses in that range.
+/// Best matches come first.
+pub fn search_devices(conn: &Connection, query: &str) -> Result<Vec<DeviceRecord>> {
+    let Some(fts_query) = fts_prefix_query(query) else {
+        return Ok(Vec::new());
+    };
+
+    let mut stmt = conn.prepare(&format!(
+        r#"
+        SELECT {} FROM device_search
+        JOIN devices ON devices.id = device_search.rowid
+        WHERE device_search MATCH ?1
+        ORDER BY device_search.rank
+        LIMIT ?2
+        "#,
+        DEVICE_COLUMNS
+    ))?;
+    let devices = stmt
+        .query_map(
+            params![fts_query, MAX_SEARCH_RESULTS as i64],
+            device_from_row,
+        )?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("Failed to search devices")?;
+
+    let devices = attach_device_tags(conn, devices)?;
+    let devices = attach_device_attributes(conn, devices)?;
+    attach_device_availability(conn, devices)
 }
 
-/// Get host-like records from the latest scan for insight calculations.
-pub fn get_latest_scan_hosts(conn: &Connection) -> Result<Vec<HostInfo>> {
+/// FTS5 query matching every word of `query` as a quoted prefix phrase
+fn fts_prefix_query(query: &str) -> Option<String> {
+    let terms: Vec<String> = query
+        .split_whitespace()
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect();
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+/// Availability windows reported for devices: 24 hours, 7 days, 30 days
+const AVAILABILITY_WINDOW_HOURS: [u32; 3] = [24, 24 * 7, 24 * 30];
+
+/// Scans seen out of scans possible, per device and window
+///
+/// Possible scans are those of any network the device has been seen on,
+/// from its first sighting on.
+fn availability_windows(
+    conn: &Connection,
+    device_id: Option<i64>,
+) -> Result<HashMap<i64, Vec<AvailabilityWindow>>> {
     let mut stmt = conn.prepare(
         r#"
-        SELECT
-            d.last_ip,
-            d.mac,
-            d.vendor,
-            d.device_type,
-            d.hostname,
-            dh.response_time_ms,
-            dh.risk_score,
-            dh.is_randomized,
-            dh.open_ports
-        FROM device_history dh
-        JOIN devices d ON d.id = dh.device_id
-        WHERE dh.scan_id = (SELECT MAX(id) FROM scans)
-        ORDER BY d.mac
+        SELECT d.id,
+            (SELECT COUNT(*) FROM scans s
+             WHERE s.scan_time >= MAX(datetime('now', ?1), d.first_seen)
+               AND s.subnet IN (
+                   SELECT DISTINCT hs.subnet FROM device_history h
+                   JOIN scans hs ON hs.id = h.scan_id
+                   WHERE h.device_id = d.id
+               )),
+            (SELECT COUNT(DISTINCT h.scan_id) FROM device_history h
+             JOIN scans s ON s.id = h.scan_id
+             WHERE h.device_id = d.id AND s.scan_time >= datetime('now', ?1))
+        FROM devices d
+        WHERE ?2 IS NULL OR d.id = ?2
         "#,
     )?;
 
-    let hosts = stmt
+    let mut windows: HashMap<i64, Vec<AvailabilityWindow>> = HashMap::new();
+    for hours in AVAILABILITY_WINDOW_HOURS {
+        let rows = stmt.query_map(params![format!("-{} hours", hours), device_id], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, u32>(1)?,
+                row.get::<_, u32>(2)?,
+            ))
+        })?;
+        for row in rows {
+            let (id, scans, seen) = row?;
+            let seen = seen.min(scans);
+            windows.entry(id).or_default().push(AvailabilityWindow {
+                hours,
+                scans,
+                seen,
+                percent: (scans > 0).then(|| f64::from(seen) * 100.0 / f64::from(scans)),
+            });
+        }
+    }
+    Ok(windows)
+}
+
+fn device_availability(windows: &[AvailabilityWindow]) -> DeviceAvailability {
+    let percent = |hours: u32| {
+        windows
+            .iter()
+            .find(|window| window.hours == hours)
+            .and_then(|window| window.percent)
+    };
+    DeviceAvailability {
+        last_24h: percent(AVAILABILITY_WINDOW_HOURS[0]),
+        last_7d: percent(AVAILABILITY_WINDOW_HOURS[1]),
+        last_30d: percent(AVAILABILITY_WINDOW_HOURS[2]),
+    }
+}
+
+/// Fill in the availability of each device
+fn attach_device_availability(
+    conn: &Connection,
+    mut devices: Vec<DeviceRecord>,
+) -> Result<Vec<DeviceRecord>> {
+    let windows = availability_windows(conn, None)?;
+    for device in &mut devices {
+        if let Some(windows) = windows.get(&device.id) {
+            device.availability = device_availability(windows);
+        }
+    }
+    Ok(devices)
+}
+
+/// How often a device was found over the last 24 hours, 7 days and 30 days
+pub fn get_device_availability(conn: &Connection, mac: &str) -> Result<Vec<AvailabilityWindow>> {
+    let Some(device_id) = find_device_id(conn, mac)? else {
+        return Ok(Vec::new());
+    };
+    Ok(availability_windows(conn, Some(device_id))?
+        .remove(&device_id)
+        .unwrap_or_default())
+}
+
+/// Longest device tag accepted, in characters
+const MAX_TAG_CHARS: usize = 32;
+
+/// Fill in the tags of each device
+fn attach_device_tags(
+    conn: &Connection,
+    mut devices: Vec<DeviceRecord>,
+) -> Result<Vec<DeviceRecord>> {
+    let mut stmt = conn.prepare("SELECT device_id, tag FROM device_tags ORDER BY tag")?;
+    let mut tags: HashMap<i64, Vec<String>> = HashMap::new();
+    for row in stmt.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get(1)?)))? {
+        let (device_id, tag) = row?;
+        tags.entry(device_id).or_default().push(tag);
+    }
+
+    for device in &mut devices {
+        device.tags = tags.remove(&device.id).unwrap_or_default();
+    }
+    Ok(devices)
+}
+
+fn get_device_tags(conn: &Connection, device_id: i64) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare("SELECT tag FROM device_tags WHERE device_id = ?1 ORDER BY tag")?;
+    let tags = stmt
+        .query_map(params![device_id], |row| row.get(0))?
+        .collect::<rusqlite::Result<Vec<String>>>()
+        .context("Failed to get device tags")?;
+    Ok(tags)
+}
+
+/// Trimmed tag, or an error if it is empty or too long
+fn normalize_tag(tag: &str) -> Result<&str> {
+    let tag = tag.trim();
+    if tag.is_empty() {
+        anyhow::bail!("Tag is empty");
+    }
+    if tag.chars().count() > MAX_TAG_CHARS {
+        anyhow::bail!("Tag is longer than {} characters", MAX_TAG_CHARS);
+    }
+    Ok(tag)
+}
+
+/// Tag a device, returning whether the device exists
+///
+/// Tags are matched case-insensitively; re-adding an existing tag is a no-op.
+pub fn add_tag(conn: &Connection, mac: &str, tag: &str) -> Result<bool> {
+    let tag = normalize_tag(tag)?;
+    let Some(device_id) = find_device_id(conn, mac)? else {
+        return Ok(false);
+    };
+    conn.execute(
+        "INSERT OR IGNORE INTO device_tags (device_id, tag) VALUES (?1, ?2)",
+        params![device_id, tag],
+    )
+    .context("Failed to add device tag")?;
+    Ok(true)
+}
+
+/// Remove a tag from a device, returning whether the device had it
+pub fn remove_tag(conn: &Connection, mac: &str, tag: &str) -> Result<bool> {
+    let Some(device_id) = find_device_id(conn, mac)? else {
+        return Ok(false);
+    };
+    let removed = conn
+        .execute(
+            "DELETE FROM device_tags WHERE device_id = ?1 AND tag = ?2",
+            params![device_id, tag.trim()],
+        )
+        .context("Failed to remove device tag")?;
+    Ok(removed > 0)
+}
+
+/// Tags of every tagged device, keyed by lowercase MAC
+pub fn get_tags_by_mac(conn: &Connection) -> Result<HashMap<String, Vec<String>>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT d.mac, t.tag FROM device_tags t
+        JOIN devices d ON d.id = t.device_id
+        ORDER BY t.tag
+        "#,
+    )?;
+    let mut tags: HashMap<String, Vec<String>> = HashMap::new();
+    for row in stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get(1)?)))? {
+        let (mac, tag) = row.context("Failed to get device tags")?;
+        tags.entry(mac.to_lowercase()).or_default().push(tag);
+    }
+    Ok(tags)
+}
+
+/// Devices carrying a tag (case-insensitive), most recently seen first
+pub fn get_devices_by_tag(conn: &Connection, tag: &str) -> Result<Vec<DeviceRecord>> {
+    let tag = tag.trim();
+    Ok(get_all_devices(conn)?
+        .into_iter()
+        .filter(|device| device.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)))
+        .collect())
+}
+
+/// Every tag in use with the number of devices carrying it, alphabetical
+pub fn get_all_tags(conn: &Connection) -> Result<Vec<TagCount>> {
+    let mut stmt =
+        conn.prepare("SELECT MIN(tag), COUNT(*) FROM device_tags GROUP BY tag ORDER BY tag")?;
+    let tags = stmt
         .query_map([], |row| {
-            let ip = row
-                .get::<_, Option<String>>(0)?
-                .unwrap_or_else(|| "0.0.0.0".to_string());
-            let mac: String = row.get(1)?;
-            let vendor: Option<String> = row.get(2)?;
-            let device_type = row
-                .get::<_, Option<String>>(3)?
-                .unwrap_or_else(|| "UNKNOWN".to_string());
-            let hostname: Option<String> = row.get(4)?;
-            let response_time_ms = row.get::<_, Option<i64>>(5)?.map(|v| v as u64);
-            let raw_risk_score: i32 = row.get(6)?;
-            let risk_score = if raw_risk_score < 0 {
-                tracing::warn!("Negative risk_score {} found in database; clamping to 0", raw_risk_score);
-                0
-            } else if raw_risk_score > 100 {
-                tracing::warn!("Out-of-range risk_score {} found in database; clamping to 100", raw_risk_score);
-                100
-            } else {
-                raw_risk_score as u8
-            };
-            let is_randomized = row.get::<_, i32>(7)? == 1;
-            let ports_str = row.get::<_, Option<String>>(8)?.unwrap_or_default();
+            Ok(TagCount {
+                tag: row.get(0)?,
+                device_count: row.get(1)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("Failed to get tags")?;
+    Ok(tags)
+}
 
-            let mut host = HostInfo::new(ip, mac, device_type, "DATABASE".to_string());
-            host.vendor = vendor;
-            host.hostname = hostname;
-            host.response_time_ms = response_time_ms;
-            host.risk_score = risk_score;
-            host.is_randomized = is_randomized;
-            host.open_ports = ports_str
-                .split(',')
-                .filter(|p| !p.is_empty())
-                .filter_map(|p| p.parse::<u16>().ok())
+/// Longest custom field name accepted, in characters
+const MAX_ATTRIBUTE_NAME_CHARS: usize = 64;
+
+/// Longest custom field value accepted, in characters
+const MAX_ATTRIBUTE_VALUE_CHARS: usize = 1000;
+
+/// All custom fields, keyed by device id
+fn load_attributes(conn: &Connection) -> Result<HashMap<i64, BTreeMap<String, String>>> {
+    let mut stmt = conn.prepare("SELECT device_id, name, value FROM device_attributes")?;
+    let mut attributes: HashMap<i64, BTreeMap<String, String>> = HashMap::new();
+    for row in stmt.query_map([], |row| {
+        Ok((row.get::<_, i64>(0)?, row.get(1)?, row.get(2)?))
+    })? {
+        let (device_id, name, value) = row?;
+        attributes.entry(device_id).or_default().insert(name, value);
+    }
+    Ok(attributes)
+}
+
+/// Fill in the custom fields of each device
+fn attach_device_attributes(
+    conn: &Connection,
+    mut devices: Vec<DeviceRecord>,
+) -> Result<Vec<DeviceRecord>> {
+    let mut attributes = load_attributes(conn)?;
+    for device in &mut devices {
+        device.attributes = attributes.remove(&device.id).unwrap_or_default();
+    }
+    Ok(devices)
+}
+
+/// Custom fields of a device, by name
+pub fn get_device_attributes(conn: &Connection, mac: &str) -> Result<BTreeMap<String, String>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT a.name, a.value FROM device_attributes a
+        JOIN devices d ON d.id = a.device_id
+        WHERE d.mac = ?1
+        "#,
+    )?;
+    let attributes = stmt
+        .query_map(params![mac], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<BTreeMap<String, String>>>()
+        .context("Failed to get device attributes")?;
+    Ok(attributes)
+}
+
+/// Custom fields of every device, keyed by MAC (for reports built from scans)
+pub fn get_attributes_by_mac(
+    conn: &Connection,
+) -> Result<HashMap<String, BTreeMap<String, String>>> {
+    let mut by_id = load_attributes(conn)?;
+    let mut stmt = conn.prepare("SELECT id, mac FROM devices")?;
+    let mut by_mac = HashMap::new();
+    for row in stmt.query_map([], |row| {
+        Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+    })? {
+        let (device_id, mac) = row?;
+        if let Some(attributes) = by_id.remove(&device_id) {
+            by_mac.insert(mac, attributes);
+        }
+    }
+    Ok(by_mac)
+}
+
+/// Set a custom field on a device, returning whether the device exists
+///
+/// Names are trimmed and matched case-insensitively, so "Owner" replaces an
+/// existing "owner". An empty value removes the field.
+pub fn set_device_attribute(conn: &Connection, mac: &str, name: &str, value: &str) -> Result<bool> {
+    let name = name.trim();
+    if name.is_empty() {
+        anyhow::bail!("Field name is empty");
+    }
+    if name.chars().count() > MAX_ATTRIBUTE_NAME_CHARS {
+        anyhow::bail!(
+            "Field name is longer than {} characters",
+            MAX_ATTRIBUTE_NAME_CHARS
+        );
+    }
+    let value = value.trim();
+    if value.chars().count() > MAX_ATTRIBUTE_VALUE_CHARS {
+        anyhow::bail!(
+            "Field value is longer than {} characters",
+            MAX_ATTRIBUTE_VALUE_CHARS
+        );
+    }
+    let Some(device_id) = find_device_id(conn, mac)? else {
+        return Ok(false);
+    };
+    if value.is_empty() {
+        remove_device_attribute(conn, mac, name)?;
+        return Ok(true);
+    }
+
+    conn.execute(
+        r#"
+        INSERT INTO device_attributes (device_id, name, value) VALUES (?1, ?2, ?3)
+        ON CONFLICT(device_id, name) DO UPDATE SET
+            name = excluded.name,
+            value = excluded.value,
+            updated_at = datetime('now')
+        "#,
+        params![device_id, name, value],
+    )
+    .context("Failed to set device attribute")?;
+    Ok(true)
+}
+
+/// Remove a custom field from a device, returning whether it was set
+pub fn remove_device_attribute(conn: &Connection, mac: &str, name: &str) -> Result<bool> {
+    let Some(device_id) = find_device_id(conn, mac)? else {
+        return Ok(false);
+    };
+    let removed = conn
+        .execute(
+            "DELETE FROM device_attributes WHERE device_id = ?1 AND name = ?2",
+            params![device_id, name.trim()],
+        )
+        .context("Failed to remove device attribute")?;
+    Ok(removed > 0)
+}
+
+/// Every custom field name in use with the number of devices setting it,
+/// alphabetical
+pub fn get_attribute_names(conn: &Connection) -> Result<Vec<AttributeCount>> {
+    let mut stmt = conn
+        .prepare("SELECT MIN(name), COUNT(*) FROM device_attributes GROUP BY name ORDER BY name")?;
+    let names = stmt
+        .query_map([], |row| {
+            Ok(AttributeCount {
+                name: row.get(0)?,
+                device_count: row.get(1)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("Failed to get attribute names")?;
+    Ok(names)
+}
+
+/// Update device custom name
+pub fn update_device_name(conn: &Connection, mac: &str, custom_name: &str) -> Result<()> {
+    conn.execute(
+        "UPDATE devices SET custom_name = ?2 WHERE mac = ?1",
+        params![mac, custom_name],
+    )
+    .context("Failed to update device name")?;
+    Ok(())
+}
+
+/// Device ID for a MAC, following merges into a canonical device
+pub(super) fn find_device_id(conn: &Connection, mac: &str) -> Result<Option<i64>> {
+    conn.query_row(
+        r#"
+        SELECT id FROM devices WHERE mac = ?1
+        UNION ALL
+        SELECT device_id FROM device_merges WHERE mac = ?1
+        LIMIT 1
+        "#,
+        params![mac],
+        |row| row.get(0),
+    )
+    .optional()
+    .context("Failed to look up device")
+}
+
+/// Fill in the stored device (following merges) and its last sighting for each host
+pub fn attach_device_ids(conn: &Connection, hosts: &mut [HostInfo]) -> Result<()> {
+    for host in hosts {
+        let Some(device_id) = find_device_id(conn, &host.mac)? else {
+            continue;
+        };
+        let last_seen: String = conn
+            .query_row(
+                "SELECT last_seen FROM devices WHERE id = ?1",
+                params![device_id],
+                |row| row.get(0),
+            )
+            .context("Failed to look up device")?;
+        host.device_id = Some(device_id);
+        host.last_seen = Some(parse_datetime(last_seen)?);
+    }
+    Ok(())
+}
+
+/// A device's own MAC followed by the MACs merged into it
+fn device_macs(conn: &Connection, device_id: i64, mac: &str) -> Result<Vec<String>> {
+    let mut stmt =
+        conn.prepare("SELECT mac FROM device_merges WHERE device_id = ?1 ORDER BY id")?;
+    let merged = stmt
+        .query_map(params![device_id], |row| row.get::<_, String>(0))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(std::iter::once(mac.to_string()).chain(merged).collect())
+}
+
+/// Fingerprint of a device seen under any of `macs`
+fn fingerprint_for_macs(
+    conn: &Connection,
+    macs: &[String],
+    hostname: Option<String>,
+    open_ports: Vec<u16>,
+) -> Result<DeviceFingerprint> {
+    let mut mdns_names = Vec::new();
+    let mut dhcp_fingerprint = None;
+    for mac in macs {
+        let mut stmt = conn.prepare_cached(
+            "SELECT DISTINCT instance_name FROM device_services WHERE device_mac = ?1 AND instance_name != ''",
+        )?;
+        for name in stmt.query_map(params![mac], |row| row.get::<_, String>(0))? {
+            mdns_names.push(name?);
+        }
+        if dhcp_fingerprint.is_none() {
+            dhcp_fingerprint = conn
+                .query_row(
+                    "SELECT fingerprint FROM dhcp_fingerprints WHERE mac = ?1",
+                    params![mac],
+                    |row| row.get(0),
+                )
+                .optional()?;
+        }
+    }
+
+    Ok(DeviceFingerprint {
+        hostname,
+        mdns_names,
+        dhcp_fingerprint,
+        open_ports,
+    })
+}
+
+fn insert_device_merge(
+    conn: &Connection,
+    device_id: i64,
+    mac: &str,
+    score: u32,
+    signals: &[CorrelationSignal],
+) -> Result<()> {
+    conn.execute(
+        "INSERT INTO device_merges (device_id, mac, score, signals) VALUES (?1, ?2, ?3, ?4)",
+        params![device_id, mac, score, serde_json::to_string(signals)?],
+    )
+    .context("Failed to record device merge")?;
+    Ok(())
+}
+
+/// Link an unknown randomized MAC to the known device it most likely is
+///
+/// Candidates are devices with randomized MACs that are not part of this
+/// scan under another address (one device cannot answer under two at once).
+fn link_randomized_mac(
+    conn: &Connection,
+    host: &HostInfo,
+    scan_id: i64,
+    scan_macs: &HashSet<&str>,
+) -> Result<Option<i64>> {
+    let fingerprint = fingerprint_for_macs(
+        conn,
+        std::slice::from_ref(&host.mac),
+        host.hostname.clone(),
+        host.open_ports.clone(),
+    )?;
+    if fingerprint.hostname.is_none()
+        && fingerprint.mdns_names.is_empty()
+        && fingerprint.dhcp_fingerprint.is_none()
+    {
+        return Ok(None);
+    }
+
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT id, mac, hostname FROM devices d
+        WHERE is_randomized = 1
+          AND NOT EXISTS (
+              SELECT 1 FROM device_history h WHERE h.device_id = d.id AND h.scan_id = ?1
+          )
+        "#,
+    )?;
+    let rows = stmt
+        .query_map(params![scan_id], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<String>>(2)?,
+            ))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let mut candidates = Vec::with_capacity(rows.len());
+    for (id, mac, hostname) in rows {
+        let macs = device_macs(conn, id, &mac)?;
+        if macs.iter().any(|mac| scan_macs.contains(mac.as_str())) {
+            continue;
+        }
+        let ports = get_latest_open_ports(conn, id)?;
+        candidates.push((id, fingerprint_for_macs(conn, &macs, hostname, ports)?));
+    }
+
+    let Some((device_id, score)) = best_correlation(&fingerprint, candidates) else {
+        return Ok(None);
+    };
+    insert_device_merge(conn, device_id, &host.mac, score.score, &score.signals)?;
+    tracing::info!(
+        "Linked randomized MAC {} to device {} (score {}, {:?})",
+        host.mac,
+        device_id,
+        score.score,
+        score.signals
+    );
+    Ok(Some(device_id))
+}
+
+/// Merge a duplicate device record into a canonical one
+///
+/// History and alerts move to the canonical device, the duplicate's MAC
+/// resolves to it from now on and the record is deleted.
+pub fn merge_devices(conn: &Connection, canonical_mac: &str, duplicate_mac: &str) -> Result<()> {
+    let canonical = find_device_id(conn, canonical_mac)?
+        .with_context(|| format!("Device {} not found", canonical_mac))?;
+    let duplicate: i64 = conn
+        .query_row(
+            "SELECT id FROM devices WHERE mac = ?1",
+            params![duplicate_mac],
+            |row| row.get(0),
+        )
+        .optional()?
+        .with_context(|| format!("Device {} not found", duplicate_mac))?;
+    if canonical == duplicate {
+        anyhow::bail!("Cannot merge a device into itself");
+    }
+
+    conn.execute_batch("SAVEPOINT merge_devices")
+        .context("Failed to start merge_devices transaction")?;
+
+    let result = (|| -> Result<()> {
+        conn.execute(
+            r#"
+            UPDATE devices SET
+                first_seen = MIN(first_seen, (SELECT first_seen FROM devices WHERE id = ?2)),
+                last_ip = CASE
+                    WHEN (SELECT last_seen FROM devices WHERE id = ?2) > last_seen
+                    THEN (SELECT last_ip FROM devices WHERE id = ?2)
+                    ELSE last_ip
+                END,
+                last_seen = MAX(last_seen, (SELECT last_seen FROM devices WHERE id = ?2)),
+                custom_name = COALESCE(custom_name, (SELECT custom_name FROM devices WHERE id = ?2))
+            WHERE id = ?1
+            "#,
+            params![canonical, duplicate],
+        )?;
+        conn.execute(
+            r#"
+            INSERT INTO device_ports
+                (device_id, port, protocol, first_seen, last_seen, last_scan_id, service, banner)
+            SELECT ?1, port, protocol, first_seen, last_seen, last_scan_id, service, banner
+            FROM device_ports WHERE device_id = ?2
+            ON CONFLICT(device_id, port, protocol) DO UPDATE SET
+                first_seen = MIN(first_seen, excluded.first_seen),
+                last_scan_id = CASE WHEN excluded.last_seen > last_seen
+                    THEN excluded.last_scan_id ELSE last_scan_id END,
+                last_seen = MAX(last_seen, excluded.last_seen),
+                service = COALESCE(service, excluded.service),
+                banner = COALESCE(banner, excluded.banner)
+            "#,
+            params![canonical, duplicate],
+        )?;
+        conn.execute(
+            "DELETE FROM device_ports WHERE device_id = ?1",
+            params![duplicate],
+        )?;
+        conn.execute(
+            "UPDATE device_port_events SET device_id = ?1 WHERE device_id = ?2",
+            params![canonical, duplicate],
+        )?;
+        conn.execute(
+            r#"
+            INSERT INTO topology_edges (
+                local_device_id, local_port, remote_device, remote_port, remote_ip,
+                source, first_seen, last_seen, last_scan_id
+            )
+            SELECT ?1, local_port, remote_device, remote_port, remote_ip,
+                source, first_seen, last_seen, last_scan_id
+            FROM topology_edges WHERE local_device_id = ?2
+            ON CONFLICT(local_device_id, local_port, remote_device, remote_port) DO UPDATE SET
+                first_seen = MIN(first_seen, excluded.first_seen),
+                last_scan_id = CASE WHEN excluded.last_seen > last_seen
+                    THEN excluded.last_scan_id ELSE last_scan_id END,
+                last_seen = MAX(last_seen, excluded.last_seen),
+                remote_ip = COALESCE(remote_ip, excluded.remote_ip)
+            "#,
+            params![canonical, duplicate],
+        )?;
+        conn.execute(
+            "DELETE FROM topology_edges WHERE local_device_id = ?1",
+            params![duplicate],
+        )?;
+        super::retention::merge_daily_summaries(conn, canonical, duplicate)?;
+        super::latency::merge_latency_rollups(conn, canonical, duplicate)?;
+        conn.execute(
+            "UPDATE OR IGNORE device_tags SET device_id = ?1 WHERE device_id = ?2",
+            params![canonical, duplicate],
+        )?;
+        conn.execute(
+            "DELETE FROM device_tags WHERE device_id = ?1",
+            params![duplicate],
+        )?;
+        conn.execute(
+            "UPDATE OR IGNORE device_attributes SET device_id = ?1 WHERE device_id = ?2",
+            params![canonical, duplicate],
+        )?;
+        conn.execute(
+            "DELETE FROM device_attributes WHERE device_id = ?1",
+            params![duplicate],
+        )?;
+        conn.execute(
+            "UPDATE OR IGNORE device_group_members SET device_id = ?1 WHERE device_id = ?2",
+            params![canonical, duplicate],
+        )?;
+        conn.execute(
+            "DELETE FROM device_group_members WHERE device_id = ?1",
+            params![duplicate],
+        )?;
+        conn.execute(
+            "UPDATE OR IGNORE scan_findings SET device_id = ?1 WHERE device_id = ?2",
+            params![canonical, duplicate],
+        )?;
+        conn.execute(
+            "DELETE FROM scan_findings WHERE device_id = ?1",
+            params![duplicate],
+        )?;
+        for table in ["device_history", "alerts", "device_merges"] {
+            conn.execute(
+                &format!("UPDATE {} SET device_id = ?1 WHERE device_id = ?2", table),
+                params![canonical, duplicate],
+            )?;
+        }
+        conn.execute("DELETE FROM devices WHERE id = ?1", params![duplicate])?;
+        insert_device_merge(
+            conn,
+            canonical,
+            duplicate_mac,
+            0,
+            &[CorrelationSignal::Manual],
+        )
+    })();
+
+    match result {
+        Ok(()) => {
+            conn.execute_batch("RELEASE SAVEPOINT merge_devices")
+                .context("Failed to commit merge_devices transaction")?;
+            Ok(())
+        }
+        Err(e) => {
+            let _ = conn.execute_batch(
+                "ROLLBACK TO SAVEPOINT merge_devices; RELEASE SAVEPOINT merge_devices",
+            );
+            Err(e)
+        }
+    }
+}
+
+/// MACs merged into a device, oldest first
+pub fn get_device_merges(conn: &Connection, mac: &str) -> Result<Vec<DeviceMerge>> {
+    let Some(device_id) = find_device_id(conn, mac)? else {
+        return Ok(Vec::new());
+    };
+
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT id, device_id, mac, score, signals, merged_at
+        FROM device_merges WHERE device_id = ?1
+        ORDER BY id
+        "#,
+    )?;
+    let merges = stmt
+        .query_map(params![device_id], |row| {
+            let signals: String = row.get(4)?;
+            Ok(DeviceMerge {
+                id: row.get(0)?,
+                device_id: row.get(1)?,
+                mac: row.get(2)?,
+                score: row.get(3)?,
+                signals: serde_json::from_str(&signals).unwrap_or_default(),
+                merged_at: parse_datetime_column(row.get::<_, String>(5)?, 5)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(merges)
+}
+
+/// Record the DHCP parameter request list (option 55) sent by a MAC
+pub fn record_dhcp_fingerprint(conn: &Connection, mac: &str, fingerprint: &str) -> Result<()> {
+    conn.execute(
+        r#"
+        INSERT INTO dhcp_fingerprints (mac, fingerprint) VALUES (?1, ?2)
+        ON CONFLICT(mac) DO UPDATE SET
+            fingerprint = excluded.fingerprint,
+            last_seen = datetime('now')
+        "#,
+        params![mac, fingerprint],
+    )
+    .context("Failed to record DHCP fingerprint")?;
+    Ok(())
+}
+
+/// Get device history for a specific device
+pub fn get_device_history(
+    conn: &Connection,
+    device_id: i64,
+    limit: i32,
+) -> Result<Vec<DeviceHistoryRecord>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT id, scan_id, device_id, ip, response_time_ms, ttl,
+               risk_score, is_online, discovery_method, open_ports, filtered_ports, host_state
+        FROM device_history
+        WHERE device_id = ?1
+        ORDER BY id DESC
+        LIMIT ?2
+        "#,
+    )?;
+
+    let history = stmt
+        .query_map(params![device_id, limit], |row| {
+            let ports_str: String = row.get::<_, Option<String>>(9)?.unwrap_or_default();
+            let open_ports: Vec<u16> = ports_str
+                .split(',')
+                .filter_map(|s| s.parse().ok())
                 .collect();
-            Ok(host)
+
+            Ok(DeviceHistoryRecord {
+                id: row.get(0)?,
+                scan_id: row.get(1)?,
+                device_id: row.get(2)?,
+                ip: row.get(3)?,
+                response_time_ms: row.get(4)?,
+                ttl: row.get(5)?,
+                risk_score: row.get(6)?,
+                is_online: row.get::<_, i32>(7)? == 1,
+                discovery_method: row.get(8)?,
+                open_ports,
+                filtered_ports: split_ports(row.get::<_, Option<String>>(10)?),
+                host_state: row
+                    .get::<_, Option<String>>(11)?
+                    .and_then(|state| state.parse().ok()),
+            })
         })?
         .collect::<rusqlite::Result<Vec<_>>>()?;
 
-    Ok(hosts)
-}
+    Ok(history)
+}
+
+/// Insert an alert
+pub fn insert_alert(
+    conn: &Connection,
+    alert_type: AlertType,
+    device_id: Option<i64>,
+    device_mac: Option<&str>,
+    device_ip: Option<&str>,
+    message: &str,
+    severity: AlertSeverity,
+) -> Result<i64> {
+    let alert = AlertInsert {
+        alert_type,
+        device_id,
+        device_mac,
+        device_ip,
+        dedupe_key: None,
+        message,
+        severity,
+        evidence: None,
+    };
+    insert_alert_with_dedupe_key(conn, &alert)
+}
+
+/// Insert an alert with an optional semantic dedupe key.
+pub fn insert_alert_with_dedupe_key(conn: &Connection, alert: &AlertInsert<'_>) -> Result<i64> {
+    conn.execute(
+        r#"
+        INSERT INTO alerts (
+            alert_type, device_id, device_mac, device_ip, dedupe_key, message, severity, evidence,
+            network_id
+        ) VALUES (
+            ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8,
+            -- The device's network, else the network scanned last
+            COALESCE(
+                (SELECT network_id FROM devices WHERE id = ?2 OR mac = ?3),
+                (SELECT network_id FROM scans ORDER BY id DESC LIMIT 1)
+            )
+        )
+        "#,
+        params![
+            alert.alert_type.to_string(),
+            alert.device_id,
+            alert.device_mac,
+            alert.device_ip,
+            alert.dedupe_key,
+            alert.message,
+            alert.severity.to_string(),
+            alert.evidence.map(|evidence| evidence.to_string()),
+        ],
+    )
+    .context("Failed to insert alert")?;
+
+    Ok(conn.last_insert_rowid())
+}
+
+/// Insert alert only if a matching unread alert does not already exist recently.
+///
+/// A repeat within `dedupe_window_minutes` of the matching alert being raised
+/// is counted on it instead (`occurrences`, `last_seen_at`) and `None` is
+/// returned; after the window, the alert is stored afresh.
+pub fn insert_alert_if_not_exists(
+    conn: &Connection,
+    alert: &AlertInsert<'_>,
+    dedupe_key: &str,
+    dedupe_window_minutes: i64,
+) -> Result<Option<i64>> {
+    let window_minutes = dedupe_window_minutes.max(1);
+    let window_expr = format!("-{} minutes", window_minutes);
+
+    let existing: Option<i64> = conn
+        .query_row(
+            r#"
+            SELECT id
+            FROM alerts
+            WHERE alert_type = ?1
+              AND COALESCE(device_mac, '') = COALESCE(?2, '')
+              AND COALESCE(dedupe_key, '') = ?3
+              AND is_read = 0
+              AND created_at >= datetime('now', ?4)
+            ORDER BY id DESC
+            LIMIT 1
+            "#,
+            params![
+                alert.alert_type.to_string(),
+                alert.device_mac,
+                dedupe_key,
+                window_expr
+            ],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    if let Some(id) = existing {
+        conn.execute(
+            r#"
+            UPDATE alerts
+            SET occurrences = occurrences + 1, last_seen_at = datetime('now'),
+                device_ip = COALESCE(?2, device_ip)
+            WHERE id = ?1
+            "#,
+            params![id, alert.device_ip],
+        )
+        .context("Failed to count repeated alert")?;
+        return Ok(None);
+    }
+
+    let deduped_alert = AlertInsert {
+        alert_type: alert.alert_type.clone(),
+        device_id: alert.device_id,
+        device_mac: alert.device_mac,
+        device_ip: alert.device_ip,
+        dedupe_key: Some(dedupe_key),
+        message: alert.message,
+        severity: alert.severity.clone(),
+        evidence: alert.evidence,
+    };
+
+    let id = insert_alert_with_dedupe_key(conn, &deduped_alert)?;
+    Ok(Some(id))
+}
+
+fn alert_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<AlertRecord> {
+    let alert_type_str: String = row.get(2)?;
+    let severity_str: String = row.get(7)?;
+
+    Ok(AlertRecord {
+        id: row.get(0)?,
+        created_at: parse_datetime_column(row.get::<_, String>(1)?, 1)?,
+        alert_type: parse_alert_type_or_default(&alert_type_str),
+        alert_type_raw: alert_type_str,
+        device_id: row.get(3)?,
+        device_mac: row.get(4)?,
+        device_ip: row.get(5)?,
+        message: row.get(6)?,
+        severity: parse_alert_severity_or_default(&severity_str),
+        is_read: row.get::<_, i32>(8)? == 1,
+        evidence: row
+            .get::<_, Option<String>>(9)?
+            .and_then(|evidence| serde_json::from_str(&evidence).ok()),
+        network_id: row.get(10)?,
+        occurrences: row.get(11)?,
+        last_seen_at: row
+            .get::<_, Option<String>>(12)?
+            .map(|s| parse_datetime_column(s, 12))
+            .transpose()?,
+    })
+}
+
+/// Get unread alerts
+pub fn get_unread_alerts(conn: &Connection) -> Result<Vec<AlertRecord>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT id, created_at, alert_type, device_id, device_mac, device_ip,
+               message, severity, is_read, evidence, network_id, occurrences,
+               last_seen_at
+        FROM alerts
+        WHERE is_read = 0
+        ORDER BY created_at DESC
+        "#,
+    )?;
+
+    let alerts = stmt
+        .query_map([], alert_from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(alerts)
+}
+
+/// Get the most recent alerts, read or not
+pub fn get_recent_alerts(conn: &Connection, limit: usize) -> Result<Vec<AlertRecord>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT id, created_at, alert_type, device_id, device_mac, device_ip,
+               message, severity, is_read, evidence, network_id, occurrences,
+               last_seen_at
+        FROM alerts
+        ORDER BY created_at DESC, id DESC
+        LIMIT ?1
+        "#,
+    )?;
+
+    let alerts = stmt
+        .query_map(params![limit as i64], alert_from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(alerts)
+}
+
+/// Alerts raised between `since` and `until` (either open-ended), oldest first
+pub fn get_alerts_between(
+    conn: &Connection,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+) -> Result<Vec<AlertRecord>> {
+    let format = |t: DateTime<Utc>| t.format("%Y-%m-%d %H:%M:%S").to_string();
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT id, created_at, alert_type, device_id, device_mac, device_ip,
+               message, severity, is_read, evidence, network_id, occurrences,
+               last_seen_at
+        FROM alerts
+        WHERE (?1 IS NULL OR created_at >= ?1) AND (?2 IS NULL OR created_at <= ?2)
+        ORDER BY created_at, id
+        "#,
+    )?;
+
+    let alerts = stmt
+        .query_map(
+            params![since.map(format), until.map(format)],
+            alert_from_row,
+        )?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(alerts)
+}
+
+/// Page of alerts matching `query`
+pub fn query_alerts(conn: &Connection, query: &AlertQuery) -> Result<Page<AlertRecord>> {
+    let format = |t: DateTime<Utc>| t.format("%Y-%m-%d %H:%M:%S").to_string();
+    let filter = r#"
+        WHERE (?1 IS NULL OR severity = ?1)
+          AND (?2 = 0 OR is_read = 0)
+          AND (?3 IS NULL OR created_at >= ?3)
+          AND (?4 IS NULL OR created_at <= ?4)
+          AND (?5 IS NULL OR network_id = ?5)
+          AND (?6 IS NULL OR device_mac = ?6 COLLATE NOCASE)
+    "#;
+    let severity = query.severity.as_ref().map(|s| s.to_string());
+    let since = query.since.map(format);
+    let until = query.until.map(format);
+
+    let total: i64 = conn
+        .query_row(
+            &format!("SELECT COUNT(*) FROM alerts {}", filter),
+            params![
+                severity,
+                query.unread_only,
+                since,
+                until,
+                query.network_id,
+                query.device_mac
+            ],
+            |row| row.get(0),
+        )
+        .context("Failed to count alerts")?;
+
+    let order = if query.oldest_first { "ASC" } else { "DESC" };
+    let mut stmt = conn.prepare(&format!(
+        r#"
+        SELECT id, created_at, alert_type, device_id, device_mac, device_ip,
+               message, severity, is_read, evidence, network_id, occurrences,
+               last_seen_at
+        FROM alerts
+        {}
+        ORDER BY created_at {order}, id {order}
+        LIMIT ?7 OFFSET ?8
+        "#,
+        filter,
+        order = order
+    ))?;
+    let items = stmt
+        .query_map(
+            params![
+                severity,
+                query.unread_only,
+                since,
+                until,
+                query.network_id,
+                query.device_mac,
+                page_limit(query.limit),
+                query.offset
+            ],
+            alert_from_row,
+        )?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("Failed to query alerts")?;
+
+    Ok(Page { items, total })
+}
+
+/// Mark alert as read
+pub fn mark_alert_read(conn: &Connection, alert_id: i64) -> Result<()> {
+    conn.execute(
+        "UPDATE alerts SET is_read = 1 WHERE id = ?1",
+        params![alert_id],
+    )
+    .context("Failed to mark alert read")?;
+    Ok(())
+}
+
+/// Mark all alerts as read
+pub fn mark_all_alerts_read(conn: &Connection) -> Result<()> {
+    conn.execute("UPDATE alerts SET is_read = 1", [])
+        .context("Failed to mark all alerts read")?;
+    Ok(())
+}
+
+/// Clear all alerts
+pub fn clear_all_alerts(conn: &Connection) -> Result<()> {
+    conn.execute("DELETE FROM alerts", [])
+        .context("Failed to clear all alerts")?;
+    Ok(())
+}
+
+/// Get host-like records from the latest scan for insight calculations.
+pub fn get_latest_scan_hosts(conn: &Connection) -> Result<Vec<HostInfo>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT
+            d.last_ip,
+            d.mac,
+            d.vendor,
+            d.device_type,
+            d.hostname,
+            dh.response_time_ms,
+            dh.risk_score,
+            dh.is_randomized,
+            dh.open_ports,
+            dh.filtered_ports,
+            dh.host_state
+        FROM device_history dh
+        JOIN devices d ON d.id = dh.device_id
+        WHERE dh.scan_id = (SELECT MAX(id) FROM scans)
+        ORDER BY d.mac
+        "#,
+    )?;
+
+    let mut hosts = stmt
+        .query_map([], |row| {
+            let ip = row
+                .get::<_, Option<String>>(0)?
+                .unwrap_or_else(|| "0.0.0.0".to_string());
+            let mac: String = row.get(1)?;
+            let vendor: Option<String> = row.get(2)?;
+            let device_type = row
+                .get::<_, Option<String>>(3)?
+                .unwrap_or_else(|| "UNKNOWN".to_string());
+            let hostname: Option<String> = row.get(4)?;
+            let response_time_ms = row.get::<_, Option<i64>>(5)?.map(|v| v as u64);
+            let raw_risk_score: i32 = row.get(6)?;
+            let risk_score = if raw_risk_score < 0 {
+                tracing::warn!("Negative risk_score {} found in database; clamping to 0", raw_risk_score);
+                0
+            } else if raw_risk_score > 100 {
+                tracing::warn!("Out-of-range risk_score {} found in database; clamping to 100", raw_risk_score);
+                100
+            } else {
+                raw_risk_score as u8
+            };
+            let is_randomized = row.get::<_, i32>(7)? == 1;
+            let ports_str = row.get::<_, Option<String>>(8)?.unwrap_or_default();
+
+            let mut host = HostInfo::new(ip, mac, device_type, "DATABASE".to_string());
+            host.vendor = vendor;
+            host.hostname = hostname;
+            host.response_time_ms = response_time_ms;
+            host.risk_score = risk_score;
+            host.is_randomized = is_randomized;
+            host.open_ports = ports_str
+                .split(',')
+                .filter(|p| !p.is_empty())
+                .filter_map(|p| p.parse::<u16>().ok())
+                .collect();
+            host.filtered_ports = split_ports(row.get::<_, Option<String>>(9)?);
+            host.host_state = row
+                .get::<_, Option<String>>(10)?
+                .and_then(|state| state.parse().ok());
+            Ok(host)
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    annotate_ip_conflicts(&mut hosts);
+    Ok(hosts)
+}
+
+/// Freeze the latest scan's devices and open ports as the network baseline
+///
+/// Replaces any earlier baseline. Fails if nothing has been scanned yet.
+pub fn set_network_baseline(conn: &Connection) -> Result<NetworkBaseline> {
+    let hosts = get_latest_scan_hosts(conn)?;
+    if hosts.is_empty() {
+        anyhow::bail!("No scan to take a baseline from");
+    }
+
+    conn.execute_batch("SAVEPOINT set_network_baseline")
+        .context("Failed to start set_network_baseline transaction")?;
+
+    let result = (|| -> Result<()> {
+        conn.execute("DELETE FROM network_baseline", [])
+            .context("Failed to clear network baseline")?;
+        for host in &hosts {
+            conn.execute(
+                r#"
+                INSERT INTO network_baseline (mac, ip, hostname, vendor, device_type, open_ports)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                ON CONFLICT(mac) DO NOTHING
+                "#,
+                params![
+                    host.mac,
+                    host.ip,
+                    host.hostname,
+                    host.vendor,
+                    host.device_type,
+                    join_ports(&host.open_ports)
+                ],
+            )
+            .context("Failed to save network baseline")?;
+        }
+        Ok(())
+    })();
+
+    match result {
+        Ok(()) => conn
+            .execute_batch("RELEASE SAVEPOINT set_network_baseline")
+            .context("Failed to commit set_network_baseline transaction")?,
+        Err(e) => {
+            let _ = conn.execute_batch(
+                "ROLLBACK TO SAVEPOINT set_network_baseline; RELEASE SAVEPOINT set_network_baseline",
+            );
+            return Err(e);
+        }
+    }
+
+    get_network_baseline(conn)?.context("Network baseline was not saved")
+}
+
+/// The saved network baseline, if one was taken
+pub fn get_network_baseline(conn: &Connection) -> Result<Option<NetworkBaseline>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT mac, ip, hostname, vendor, device_type, open_ports, captured_at
+        FROM network_baseline
+        ORDER BY mac
+        "#,
+    )?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                BaselineDevice {
+                    mac: row.get(0)?,
+                    ip: row.get(1)?,
+                    hostname: row.get(2)?,
+                    vendor: row.get(3)?,
+                    device_type: row.get(4)?,
+                    open_ports: split_ports(row.get(5)?),
+                },
+                parse_datetime_column(row.get(6)?, 6)?,
+            ))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("Failed to get network baseline")?;
+
+    let Some(captured_at) = rows.iter().map(|(_, at)| *at).min() else {
+        return Ok(None);
+    };
+    Ok(Some(NetworkBaseline {
+        captured_at,
+        devices: rows.into_iter().map(|(device, _)| device).collect(),
+    }))
+}
+
+/// Delete the network baseline, returning whether there was one
+pub fn clear_network_baseline(conn: &Connection) -> Result<bool> {
+    let affected = conn
+        .execute("DELETE FROM network_baseline", [])
+        .context("Failed to clear network baseline")?;
+    Ok(affected > 0)
+}
+
+/// Number of consecutive latest scans in which each device answered only ARP
+///
+/// Counts back from each device's most recent history entry and stops at the
+/// first scan where it replied to ICMP, had an open port or was found another
+/// way. Devices whose latest scan was not ARP-only are left out.
+pub fn get_arp_only_streaks(conn: &Connection) -> Result<HashMap<String, u32>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT d.mac, dh.discovery_method, dh.open_ports, dh.response_time_ms
+        FROM device_history dh
+        JOIN devices d ON d.id = dh.device_id
+        ORDER BY dh.device_id, dh.id DESC
+        "#,
+    )?;
+
+    let mut streaks: HashMap<String, u32> = HashMap::new();
+    let mut broken: HashSet<String> = HashSet::new();
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let mac: String = row.get(0)?;
+        if broken.contains(&mac) {
+            continue;
+        }
+        let method: Option<String> = row.get(1)?;
+        let ports: Option<String> = row.get(2)?;
+        let response_time: Option<i64> = row.get(3)?;
+
+        let arp_only = method.as_deref() == Some("ARP")
+            && ports.is_none_or(|p| p.is_empty())
+            && response_time.is_none();
+        if arp_only {
+            *streaks.entry(mac).or_insert(0) += 1;
+        } else {
+            broken.insert(mac);
+        }
+    }
+
+    Ok(streaks)
+}
+
+/// Sensitive ports ever observed open, per network and device
+///
+/// Covers scans of `subnet` (all networks if `None`) between `since` and
+/// `until`, and reports each device/port pair once with the first and last
+/// scan that found it open. Ordered by network, port and MAC.
+pub fn get_port_exposure(
+    conn: &Connection,
+    ports: &[u16],
+    subnet: Option<&str>,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+) -> Result<Vec<PortExposureRecord>> {
+    let format = |t: DateTime<Utc>| t.format("%Y-%m-%d %H:%M:%S").to_string();
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT s.subnet, d.mac, COALESCE(d.custom_name, d.hostname), d.vendor,
+               dh.ip, dh.open_ports, s.scan_time
+        FROM device_history dh
+        JOIN scans s ON s.id = dh.scan_id
+        JOIN devices d ON d.id = dh.device_id
+        WHERE dh.open_ports IS NOT NULL AND dh.open_ports != ''
+          AND (?1 IS NULL OR s.subnet = ?1)
+          AND (?2 IS NULL OR s.scan_time >= ?2)
+          AND (?3 IS NULL OR s.scan_time <= ?3)
+        ORDER BY s.scan_time, dh.id
+        "#,
+    )?;
+
+    let mut exposures: BTreeMap<(String, u16, String), PortExposureRecord> = BTreeMap::new();
+    let mut rows = stmt.query(params![subnet, since.map(format), until.map(format)])?;
+    while let Some(row) = rows.next()? {
+        let subnet: String = row.get(0)?;
+        let mac: String = row.get(1)?;
+        let ip: String = row.get(4)?;
+        let observed = parse_datetime_column(row.get::<_, String>(6)?, 6)?;
+
+        for port in split_ports(row.get(5)?) {
+            if !ports.contains(&port) {
+                continue;
+            }
+            exposures
+                .entry((subnet.clone(), port, mac.clone()))
+                .and_modify(|exposure| {
+                    exposure.last_ip = ip.clone();
+                    exposure.last_observed = observed;
+                    exposure.scan_count += 1;
+                })
+                .or_insert(PortExposureRecord {
+                    subnet: subnet.clone(),
+                    mac: mac.clone(),
+                    display_name: row.get(2)?,
+                    vendor: row.get(3)?,
+                    port,
+                    last_ip: ip.clone(),
+                    first_observed: observed,
+                    last_observed: observed,
+                    scan_count: 1,
+                });
+        }
+    }
+
+    Ok(exposures.into_values().collect())
+}
+
+/// Scans between `since` and `until` with the devices each found, oldest first
+///
+/// When `since` is set, the last scan of each network before it is included
+/// too, so changes in the first scan of the period can be worked out.
+/// Duplicate scans are skipped; they found the same hosts as the scan they repeat.
+pub fn get_scan_snapshots(
+    conn: &Connection,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+) -> Result<Vec<ScanSnapshot>> {
+    let format = |t: DateTime<Utc>| t.format("%Y-%m-%d %H:%M:%S").to_string();
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT s.id, s.scan_time, s.subnet, d.mac, COALESCE(d.custom_name, d.hostname),
+               dh.ip, dh.open_ports,
+               dh.id = (SELECT MIN(id) FROM device_history WHERE device_id = dh.device_id)
+        FROM scans s
+        LEFT JOIN device_history dh ON dh.scan_id = s.id
+        LEFT JOIN devices d ON d.id = dh.device_id
+        WHERE s.duplicate_of IS NULL
+          AND (?2 IS NULL OR s.scan_time <= ?2)
+          AND (?1 IS NULL OR s.scan_time >= ?1 OR s.id IN (
+              SELECT MAX(id) FROM scans
+              WHERE scan_time < ?1 AND duplicate_of IS NULL
+              GROUP BY subnet
+          ))
+        ORDER BY s.scan_time, s.id, dh.id
+        "#,
+    )?;
+
+    let mut snapshots: Vec<ScanSnapshot> = Vec::new();
+    let mut rows = stmt.query(params![since.map(format), until.map(format)])?;
+    while let Some(row) = rows.next()? {
+        let scan_id: i64 = row.get(0)?;
+        if snapshots.last().map(|s| s.scan_id) != Some(scan_id) {
+            snapshots.push(ScanSnapshot {
+                scan_id,
+                scan_time: parse_datetime_column(row.get(1)?, 1)?,
+                subnet: row.get(2)?,
+                devices: Vec::new(),
+            });
+        }
+
+        let Some(mac) = row.get::<_, Option<String>>(3)? else {
+            continue;
+        };
+        if let Some(snapshot) = snapshots.last_mut() {
+            snapshot.devices.push(DeviceSighting {
+                mac,
+                name: row.get(4)?,
+                ip: row.get(5)?,
+                open_ports: split_ports(row.get(6)?),
+                first_sighting: row.get::<_, Option<bool>>(7)?.unwrap_or(false),
+            });
+        }
+    }
+
+    Ok(snapshots)
+}
+
+/// Get network statistics
+pub fn get_network_stats(conn: &Connection) -> Result<NetworkStats> {
+    get_network_stats_for(conn, None)
+}
+
+/// Get statistics for one network profile (`None` for all networks)
+pub fn get_network_stats_for(conn: &Connection, network_id: Option<i64>) -> Result<NetworkStats> {
+    let total_devices: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM devices WHERE (?1 IS NULL OR network_id = ?1)",
+        params![network_id],
+        |row| row.get(0),
+    )?;
+
+    // Devices seen in last scan (online)
+    let online_devices: i64 = conn
+        .query_row(
+            r#"
+        SELECT COUNT(DISTINCT device_id) FROM device_history
+        WHERE scan_id = (SELECT MAX(id) FROM scans WHERE ?1 IS NULL OR network_id = ?1)
+        "#,
+            params![network_id],
+            |row| row.get(0),
+        )
+        .unwrap_or(0);
+
+    let offline_devices = total_devices - online_devices;
+
+    // New devices in last 24 hours
+    let new_devices_24h: i64 = conn.query_row(
+        r#"
+        SELECT COUNT(*) FROM devices
+        WHERE first_seen >= datetime('now', '-24 hours')
+          AND (?1 IS NULL OR network_id = ?1)
+        "#,
+        params![network_id],
+        |row| row.get(0),
+    )?;
+
+    // High risk devices (risk_score > 70)
+    let high_risk_devices: i64 = conn
+        .query_row(
+            r#"
+        SELECT COUNT(DISTINCT device_id) FROM device_history
+        WHERE scan_id = (SELECT MAX(id) FROM scans WHERE ?1 IS NULL OR network_id = ?1)
+          AND risk_score > 70
+        "#,
+            params![network_id],
+            |row| row.get(0),
+        )
+        .unwrap_or(0);
+
+    let total_scans: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM scans WHERE (?1 IS NULL OR network_id = ?1)",
+        params![network_id],
+        |row| row.get(0),
+    )?;
+    let duplicate_scans: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM scans WHERE duplicate_of IS NOT NULL AND (?1 IS NULL OR network_id = ?1)",
+        params![network_id],
+        |row| row.get(0),
+    )?;
+    let total_scans = if collapse_duplicate_scans(conn)? {
+        total_scans - duplicate_scans
+    } else {
+        total_scans
+    };
+
+    let last_scan_time_raw: Option<String> = conn
+        .query_row(
+            "SELECT scan_time FROM scans WHERE (?1 IS NULL OR network_id = ?1) ORDER BY id DESC LIMIT 1",
+            params![network_id],
+            |row| row.get::<_, String>(0),
+        )
+        .optional()?;
+
+    let last_scan_time = match last_scan_time_raw {
+        Some(raw) => Some(parse_datetime(raw)?),
+        None => None,
+    };
+
+    Ok(NetworkStats {
+        total_devices,
+        online_devices,
+        offline_devices,
+        new_devices_24h,
+        high_risk_devices,
+        total_scans,
+        duplicate_scans,
+        last_scan_time,
+    })
+}
+
+/// Get alerting overrides for a device
+pub fn get_device_settings(conn: &Connection, device_mac: &str) -> Result<Option<DeviceSettings>> {
+    conn.query_row(
+        r#"
+        SELECT device_mac, latency_threshold_ms, offline_grace_secs, offline_alerts_enabled,
+               risk_threshold, risk_alerts_enabled, expected_ports, watched, trusted,
+               muted_until
+        FROM device_settings
+        WHERE device_mac = ?1
+        "#,
+        params![device_mac],
+        map_device_settings_row,
+    )
+    .optional()
+    .context("Failed to get device settings")
+}
+
+/// Get alerting overrides for all devices
+pub fn get_all_device_settings(conn: &Connection) -> Result<Vec<DeviceSettings>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT device_mac, latency_threshold_ms, offline_grace_secs, offline_alerts_enabled,
+               risk_threshold, risk_alerts_enabled, expected_ports, watched, trusted,
+               muted_until
+        FROM device_settings
+        ORDER BY device_mac
+        "#,
+    )?;
+
+    let settings = stmt
+        .query_map([], map_device_settings_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(settings)
+}
+
+/// Insert or replace alerting overrides for a device
+pub fn upsert_device_settings(conn: &Connection, settings: &DeviceSettings) -> Result<()> {
+    conn.execute(
+        r#"
+        INSERT INTO device_settings (
+            device_mac, latency_threshold_ms, offline_grace_secs, offline_alerts_enabled,
+            risk_threshold, risk_alerts_enabled, expected_ports, watched, trusted, muted_until
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+        ON CONFLICT(device_mac) DO UPDATE SET
+            latency_threshold_ms = excluded.latency_threshold_ms,
+            offline_grace_secs = excluded.offline_grace_secs,
+            offline_alerts_enabled = excluded.offline_alerts_enabled,
+            risk_threshold = excluded.risk_threshold,
+            risk_alerts_enabled = excluded.risk_alerts_enabled,
+            expected_ports = excluded.expected_ports,
+            watched = excluded.watched,
+            trusted = excluded.trusted,
+            muted_until = excluded.muted_until,
+            updated_at = datetime('now')
+        "#,
+        params![
+            settings.device_mac,
+            settings.latency_threshold_ms.map(|v| v as i64),
+            settings.offline_grace_secs.map(|v| v as i64),
+            settings.offline_alerts_enabled as i32,
+            settings.risk_threshold.map(|v| v as i32),
+            settings.risk_alerts_enabled as i32,
+            settings.expected_ports.as_deref().map(join_ports),
+            settings.watched as i32,
+            settings.trusted as i32,
+            settings
+                .muted_until
+                .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string()),
+        ],
+    )
+    .context("Failed to save device settings")?;
+    Ok(())
+}
+
+/// Revert a device's alert thresholds to the defaults, keeping its trust,
+/// watch, mute and expected ports
+pub fn reset_device_thresholds(conn: &Connection, device_mac: &str) -> Result<bool> {
+    let affected = conn
+        .execute(
+            r#"
+            UPDATE device_settings SET
+                latency_threshold_ms = NULL,
+                offline_grace_secs = NULL,
+                offline_alerts_enabled = 1,
+                risk_threshold = NULL,
+                risk_alerts_enabled = 1,
+                updated_at = datetime('now')
+            WHERE device_mac = ?1
+            "#,
+            params![device_mac],
+        )
+        .context("Failed to reset device settings")?;
+    Ok(affected > 0)
+}
+
+fn map_device_settings_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<DeviceSettings> {
+    Ok(DeviceSettings {
+        device_mac: row.get(0)?,
+        latency_threshold_ms: row.get::<_, Option<i64>>(1)?.map(|v| v.max(0) as u64),
+        offline_grace_secs: row.get::<_, Option<i64>>(2)?.map(|v| v.max(0) as u64),
+        offline_alerts_enabled: row.get::<_, i32>(3)? != 0,
+        risk_threshold: row
+            .get::<_, Option<i32>>(4)?
+            .map(|v| v.clamp(0, u8::MAX as i32) as u8),
+        risk_alerts_enabled: row.get::<_, i32>(5)? != 0,
+        expected_ports: row
+            .get::<_, Option<String>>(6)?
+            .map(|ports| ports.split(',').filter_map(|p| p.parse().ok()).collect()),
+        watched: row.get::<_, i32>(7)? != 0,
+        trusted: row.get::<_, i32>(8)? != 0,
+        muted_until: row
+            .get::<_, Option<String>>(9)?
+            .map(|s| parse_datetime_column(s, 9))
+            .transpose()?,
+    })
+}
+
+/// Set or clear the expected open ports for a device, keeping its other settings
+pub fn set_expected_ports(
+    conn: &Connection,
+    device_mac: &str,
+    ports: Option<&[u16]>,
+) -> Result<()> {
+    let mut settings =
+        get_device_settings(conn, device_mac)?.unwrap_or_else(|| DeviceSettings::new(device_mac));
+    settings.expected_ports = ports.map(|ports| {
+        let mut ports = ports.to_vec();
+        ports.sort_unstable();
+        ports.dedup();
+        ports
+    });
+    upsert_device_settings(conn, &settings)
+}
+
+/// Add a device to or remove it from the monitor's watch list, keeping its other settings
+pub fn set_device_watched(conn: &Connection, device_mac: &str, watched: bool) -> Result<()> {
+    let mut settings =
+        get_device_settings(conn, device_mac)?.unwrap_or_else(|| DeviceSettings::new(device_mac));
+    settings.watched = watched;
+    upsert_device_settings(conn, &settings)
+}
+
+/// Add a device to or remove it from the known-device allowlist, keeping its other settings
+pub fn set_device_trusted(conn: &Connection, device_mac: &str, trusted: bool) -> Result<()> {
+    let mut settings =
+        get_device_settings(conn, device_mac)?.unwrap_or_else(|| DeviceSettings::new(device_mac));
+    settings.trusted = trusted;
+    upsert_device_settings(conn, &settings)
+}
+
+/// Snooze a device's alerts until `until` (`None` lifts the mute), keeping its other settings
+pub fn mute_alerts_for_device(
+    conn: &Connection,
+    device_mac: &str,
+    until: Option<DateTime<Utc>>,
+) -> Result<()> {
+    if until.is_some_and(|until| until <= Utc::now()) {
+        anyhow::bail!("Mute end must be in the future");
+    }
+    let mut settings =
+        get_device_settings(conn, device_mac)?.unwrap_or_else(|| DeviceSettings::new(device_mac));
+    settings.muted_until = until;
+    upsert_device_settings(conn, &settings)
+}
+
+/// Mark every stored device trusted, returning how many were not yet
+///
+/// For seeding the allowlist from a network the user knows is clean.
+pub fn trust_all_devices(conn: &Connection) -> Result<usize> {
+    let affected = conn
+        .execute(
+            r#"
+            INSERT INTO device_settings (device_mac, trusted)
+            SELECT mac, 1 FROM devices WHERE true
+            ON CONFLICT(device_mac) DO UPDATE SET
+                trusted = 1,
+                updated_at = datetime('now')
+            WHERE trusted = 0
+            "#,
+            [],
+        )
+        .context("Failed to trust devices")?;
+    Ok(affected)
+}
+
+/// Accept the risk of a device finding until `expires_at`
+///
+/// Replaces any earlier acceptance of the same finding, so re-accepting
+/// after a review restarts the period. Returns the acceptance ID.
+pub fn accept_risk(
+    conn: &Connection,
+    device_mac: &str,
+    finding: RiskFinding,
+    reason: &str,
+    accepted_by: Option<&str>,
+    expires_at: DateTime<Utc>,
+) -> Result<i64> {
+    if reason.trim().is_empty() {
+        anyhow::bail!("A reason is required to accept a risk");
+    }
+    if expires_at <= Utc::now() {
+        anyhow::bail!("Risk acceptance expiry must be in the future");
+    }
+
+    conn.query_row(
+        r#"
+        INSERT INTO risk_acceptances (device_mac, finding, reason, accepted_by, expires_at)
+        VALUES (?1, ?2, ?3, ?4, ?5)
+        ON CONFLICT(device_mac, finding) DO UPDATE SET
+            reason = excluded.reason,
+            accepted_by = excluded.accepted_by,
+            accepted_at = datetime('now'),
+            expires_at = excluded.expires_at,
+            review_alerted = 0
+        RETURNING id
+        "#,
+        params![
+            device_mac,
+            finding.as_str(),
+            reason.trim(),
+            accepted_by,
+            expires_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+        ],
+        |row| row.get(0),
+    )
+    .context("Failed to save risk acceptance")
+}
+
+/// Withdraw a risk acceptance, returning whether it existed
+pub fn revoke_risk_acceptance(conn: &Connection, id: i64) -> Result<bool> {
+    let affected = conn
+        .execute("DELETE FROM risk_acceptances WHERE id = ?1", params![id])
+        .context("Failed to delete risk acceptance")?;
+    Ok(affected > 0)
+}
+
+/// All risk acceptances, active and expired, soonest expiry first
+pub fn get_risk_acceptances(conn: &Connection) -> Result<Vec<RiskAcceptance>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT id, device_mac, finding, reason, accepted_by, accepted_at, expires_at,
+               review_alerted
+        FROM risk_acceptances
+        ORDER BY expires_at, id
+        "#,
+    )?;
+
+    let acceptances = stmt
+        .query_map([], map_risk_acceptance_row)?
+        .filter_map(|row| match row {
+            Ok(Some(acceptance)) => Some(Ok(acceptance)),
+            Ok(None) => None,
+            Err(e) => Some(Err(e)),
+        })
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(acceptances)
+}
+
+/// Raise one review alert for each acceptance that has expired
+///
+/// Returns the acceptances that were alerted on; later calls skip them
+/// until the risk is accepted again.
+pub fn alert_expired_risk_acceptances(conn: &Connection) -> Result<Vec<RiskAcceptance>> {
+    let now = Utc::now();
+    let expired: Vec<RiskAcceptance> = get_risk_acceptances(conn)?
+        .into_iter()
+        .filter(|acceptance| !acceptance.review_alerted && !acceptance.is_active(now))
+        .collect();
+
+    for acceptance in &expired {
+        let message = format!(
+            "Accepted risk '{}' for {} expired on {}; review the device",
+            acceptance.finding,
+            acceptance.device_mac,
+            acceptance.expires_at.format("%Y-%m-%d"),
+        );
+        insert_alert_with_dedupe_key(
+            conn,
+            &AlertInsert {
+                alert_type: AlertType::RiskReview,
+                device_id: None,
+                device_mac: Some(&acceptance.device_mac),
+                device_ip: None,
+                dedupe_key: Some(&format!("risk_review:{}", acceptance.id)),
+                message: &message,
+                severity: AlertSeverity::Warning,
+                evidence: None,
+            },
+        )?;
+        conn.execute(
+            "UPDATE risk_acceptances SET review_alerted = 1 WHERE id = ?1",
+            params![acceptance.id],
+        )
+        .context("Failed to mark risk acceptance as reviewed")?;
+    }
+
+    Ok(expired)
+}
+
+/// Maps a row, skipping findings this version does not know
+fn map_risk_acceptance_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Option<RiskAcceptance>> {
+    let Ok(finding) = row.get::<_, String>(2)?.parse() else {
+        return Ok(None);
+    };
+    Ok(Some(RiskAcceptance {
+        id: row.get(0)?,
+        device_mac: row.get(1)?,
+        finding,
+        reason: row.get(3)?,
+        accepted_by: row.get(4)?,
+        accepted_at: parse_datetime_column(row.get::<_, String>(5)?, 5)?,
+        expires_at: parse_datetime_column(row.get::<_, String>(6)?, 6)?,
+        review_alerted: row.get::<_, i32>(7)? != 0,
+    }))
+}
+
+/// Open ports recorded for a device in its most recent scan
+pub fn get_latest_open_ports(conn: &Connection, device_id: i64) -> Result<Vec<u16>> {
+    Ok(get_device_history(conn, device_id, 1)?
+        .into_iter()
+        .next()
+        .map(|record| record.open_ports)
+        .unwrap_or_default())
+}
+
+/// Use the device's currently open ports as its expected baseline
+///
+/// Returns the recorded ports, or `None` if the device is unknown.
+pub fn record_expected_ports_baseline(conn: &Connection, mac: &str) -> Result<Option<Vec<u16>>> {
+    let Some(device) = get_device_by_mac(conn, mac)? else {
+        return Ok(None);
+    };
+    let ports = get_latest_open_ports(conn, device.id)?;
+    set_expected_ports(conn, mac, Some(&ports))?;
+    Ok(Some(ports))
+}
+
+/// Settings key for ARP anomaly alert thresholds
+pub const SETTING_ARP_THRESHOLDS: &str = "alert_rules.arp_thresholds";
+
+/// Settings key where older builds kept the export signing key (hex-encoded
+/// Ed25519 seed); read only to move it into the key file
+pub const SETTING_EXPORT_SIGNING_KEY: &str = "exports.signing_key";
+
+/// Settings key for the saved risk scoring profile (JSON)
+pub const SETTING_RISK_PROFILE: &str = "risk.profile";
+
+/// Settings key for the LDAP directory connection (JSON)
+pub const SETTING_LDAP_CONFIG: &str = "enrichment.ldap";
+
+/// Settings key for hiding back-to-back duplicate scans ("true"/"false")
+pub const SETTING_COLLAPSE_DUPLICATE_SCANS: &str = "scans.collapse_duplicates";
+
+/// Settings key for mDNS self-advertisement (JSON)
+pub const SETTING_PEER_SETTINGS: &str = "peers.settings";
+
+/// Settings key for this install's peer instance ID (hex)
+pub const SETTING_INSTANCE_ID: &str = "peers.instance_id";
+
+/// Settings key for public IP / ISP lookup endpoints (JSON)
+pub const SETTING_WAN_LOOKUP: &str = "network.wan_lookup";
+
+/// Settings key for local GeoIP database paths (JSON)
+pub const SETTING_GEOIP: &str = "network.geoip";
+
+/// Settings key for the forward DNS sweep of the local domain (JSON)
+pub const SETTING_DNS_SWEEP: &str = "network.dns_sweep";
+
+/// Settings key for the reverse DNS lookup backend (JSON)
+pub const SETTING_DNS_RESOLVER: &str = "network.dns_resolver";
+
+/// Settings key for the static public dashboard export (JSON)
+pub const SETTING_PUBLIC_DASHBOARD: &str = "exports.public_dashboard";
+
+/// Settings key for the OUI vendor data source (JSON)
+pub const SETTING_VENDOR_SOURCE: &str = "network.vendor_source";
+
+/// Settings key for how much scan history and alerting is kept (JSON)
+pub const SETTING_RETENTION_POLICY: &str = "database.retention";
+
+/// Settings key for the last scan id rolled into latency buckets
+pub const SETTING_LATENCY_ROLLUP_SCAN: &str = "database.latency_rollup_scan";
+
+/// Settings key for the office-hours monitoring schedule (JSON)
+pub const SETTING_MONITOR_SCHEDULE: &str = "monitor.schedule";
+
+/// Settings key for the monitor's internet health check (JSON)
+pub const SETTING_INTERNET_CHECK: &str = "monitor.internet_check";
+
+/// Settings key for the channels alerts are sent to (JSON)
+pub const SETTING_NOTIFICATIONS: &str = "alerts.notifications";
+
+/// Settings key for the day each email channel's digest was last sent (JSON)
+pub const SETTING_DIGESTS_SENT: &str = "alerts.digests_sent";
+
+/// Settings key for alerting on devices missing from the allowlist (JSON)
+pub const SETTING_UNTRUSTED_DEVICE_ALERTS: &str = "alerts.untrusted_devices";
+
+/// Get a raw setting value
+pub fn get_setting(conn: &Connection, key: &str) -> Result<Option<String>> {
+    conn.query_row(
+        "SELECT value FROM app_settings WHERE key = ?1",
+        params![key],
+        |row| row.get(0),
+    )
+    .optional()
+    .context("Failed to get setting")
+}
+
+/// Insert or replace a raw setting value
+pub fn set_setting(conn: &Connection, key: &str, value: &str) -> Result<()> {
+    conn.execute(
+        r#"
+        INSERT INTO app_settings (key, value) VALUES (?1, ?2)
+        ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = datetime('now')
+        "#,
+        params![key, value],
+    )
+    .context("Failed to save setting")?;
+    Ok(())
+}
+
+/// Remove a raw setting value
+pub fn delete_setting(conn: &Connection, key: &str) -> Result<()> {
+    conn.execute("DELETE FROM app_settings WHERE key = ?1", params![key])
+        .context("Failed to delete setting")?;
+    Ok(())
+}
+
+/// Get a JSON-encoded setting, or `None` if unset
+pub fn get_json_setting<T: serde::de::DeserializeOwned>(
+    conn: &Connection,
+    key: &str,
+) -> Result<Option<T>> {
+    match get_setting(conn, key)? {
+        Some(raw) => Ok(Some(serde_json::from_str(&raw).with_context(|| {
+            format!("Invalid JSON stored for setting '{}'", key)
+        })?)),
+        None => Ok(None),
+    }
+}
+
+/// Store a setting as JSON
+pub fn set_json_setting<T: serde::Serialize>(conn: &Connection, key: &str, value: &T) -> Result<()> {
+    let raw = serde_json::to_string(value).context("Failed to encode setting")?;
+    set_setting(conn, key, &raw)
+}
+
+/// Latest recorded risk score per device (device_id -> risk score)
+pub fn get_latest_risk_scores(conn: &Connection) -> Result<HashMap<i64, u8>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT dh.device_id, dh.risk_score
+        FROM device_history dh
+        WHERE dh.id = (
+            SELECT MAX(id) FROM device_history WHERE device_id = dh.device_id
+        )
+        "#,
+    )?;
+
+    let scores = stmt
+        .query_map([], |row| {
+            let score: i32 = row.get(1)?;
+            Ok((row.get::<_, i64>(0)?, score.clamp(0, 100) as u8))
+        })?
+        .collect::<rusqlite::Result<HashMap<_, _>>>()?;
+
+    Ok(scores)
+}
+
+/// Create a smart group and return its ID
+pub fn create_smart_group(
+    conn: &Connection,
+    name: &str,
+    expression: &str,
+    description: Option<&str>,
+) -> Result<i64> {
+    conn.execute(
+        "INSERT INTO smart_groups (name, expression, description) VALUES (?1, ?2, ?3)",
+        params![name, expression, description],
+    )
+    .context("Failed to create smart group")?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Update an existing smart group
+pub fn update_smart_group(
+    conn: &Connection,
+    id: i64,
+    name: &str,
+    expression: &str,
+    description: Option<&str>,
+) -> Result<bool> {
+    let affected = conn
+        .execute(
+            r#"
+            UPDATE smart_groups
+            SET name = ?2, expression = ?3, description = ?4, updated_at = datetime('now')
+            WHERE id = ?1
+            "#,
+            params![id, name, expression, description],
+        )
+        .context("Failed to update smart group")?;
+    Ok(affected > 0)
+}
+
+/// Delete a smart group
+pub fn delete_smart_group(conn: &Connection, id: i64) -> Result<bool> {
+    let affected = conn
+        .execute("DELETE FROM smart_groups WHERE id = ?1", params![id])
+        .context("Failed to delete smart group")?;
+    Ok(affected > 0)
+}
+
+/// Get a smart group by ID
+pub fn get_smart_group(conn: &Connection, id: i64) -> Result<Option<SmartGroupRecord>> {
+    conn.query_row(
+        r#"
+        SELECT id, name, expression, description, created_at, updated_at
+        FROM smart_groups WHERE id = ?1
+        "#,
+        params![id],
+        map_smart_group_row,
+    )
+    .optional()
+    .context("Failed to get smart group")
+}
+
+/// Get all smart groups ordered by name
+pub fn get_smart_groups(conn: &Connection) -> Result<Vec<SmartGroupRecord>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT id, name, expression, description, created_at, updated_at
+        FROM smart_groups
+        ORDER BY name COLLATE NOCASE
+        "#,
+    )?;
+
+    let groups = stmt
+        .query_map([], map_smart_group_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(groups)
+}
+
+fn map_smart_group_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<SmartGroupRecord> {
+    Ok(SmartGroupRecord {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        expression: row.get(2)?,
+        description: row.get(3)?,
+        created_at: parse_datetime_column(row.get::<_, String>(4)?, 4)?,
+        updated_at: parse_datetime_column(row.get::<_, String>(5)?, 5)?,
+    })
+}
+
+/// Create a device group and return its ID
+pub fn create_device_group(
+    conn: &Connection,
+    name: &str,
+    description: Option<&str>,
+    policy: &GroupPolicy,
+) -> Result<i64> {
+    let name = normalize_group_name(name)?;
+    policy.validate()?;
+    conn.execute(
+        "INSERT INTO device_groups (name, description, policy) VALUES (?1, ?2, ?3)",
+        params![name, description, serde_json::to_string(policy)?],
+    )
+    .context("Failed to create device group")?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Update a device group's name, description and policy
+pub fn update_device_group(
+    conn: &Connection,
+    id: i64,
+    name: &str,
+    description: Option<&str>,
+    policy: &GroupPolicy,
+) -> Result<bool> {
+    let name = normalize_group_name(name)?;
+    policy.validate()?;
+    let affected = conn
+        .execute(
+            r#"
+            UPDATE device_groups
+            SET name = ?2, description = ?3, policy = ?4, updated_at = datetime('now')
+            WHERE id = ?1
+            "#,
+            params![id, name, description, serde_json::to_string(policy)?],
+        )
+        .context("Failed to update device group")?;
+    Ok(affected > 0)
+}
+
+/// Delete a device group and its memberships
+pub fn delete_device_group(conn: &Connection, id: i64) -> Result<bool> {
+    conn.execute(
+        "DELETE FROM device_group_members WHERE group_id = ?1",
+        params![id],
+    )
+    .context("Failed to delete device group members")?;
+    let affected = conn
+        .execute("DELETE FROM device_groups WHERE id = ?1", params![id])
+        .context("Failed to delete device group")?;
+    Ok(affected > 0)
+}
+
+/// Get a device group by ID
+pub fn get_device_group(conn: &Connection, id: i64) -> Result<Option<DeviceGroupRecord>> {
+    Ok(get_device_groups(conn)?
+        .into_iter()
+        .find(|group| group.id == id))
+}
+
+/// Get all device groups with their members, ordered by name
+pub fn get_device_groups(conn: &Connection) -> Result<Vec<DeviceGroupRecord>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT m.group_id, d.mac
+        FROM device_group_members m
+        JOIN devices d ON d.id = m.device_id
+        ORDER BY d.mac
+        "#,
+    )?;
+    let mut members: HashMap<i64, Vec<String>> = HashMap::new();
+    for row in stmt.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get(1)?)))? {
+        let (group_id, mac) = row?;
+        members.entry(group_id).or_default().push(mac);
+    }
+
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT id, name, description, policy, created_at, updated_at
+        FROM device_groups
+        ORDER BY name COLLATE NOCASE
+        "#,
+    )?;
+    let groups = stmt
+        .query_map([], |row| {
+            let id = row.get(0)?;
+            Ok(DeviceGroupRecord {
+                id,
+                name: row.get(1)?,
+                description: row.get(2)?,
+                policy: serde_json::from_str(&row.get::<_, String>(3)?).unwrap_or_default(),
+                members: members.remove(&id).unwrap_or_default(),
+                created_at: parse_datetime_column(row.get::<_, String>(4)?, 4)?,
+                updated_at: parse_datetime_column(row.get::<_, String>(5)?, 5)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("Failed to get device groups")?;
+
+    Ok(groups)
+}
+
+/// Add a device to a group, returning whether both exist
+pub fn add_device_to_group(conn: &Connection, group_id: i64, mac: &str) -> Result<bool> {
+    let group_exists = conn
+        .query_row(
+            "SELECT 1 FROM device_groups WHERE id = ?1",
+            params![group_id],
+            |_| Ok(()),
+        )
+        .optional()?
+        .is_some();
+    let device_id = match find_device_id(conn, mac)? {
+        Some(device_id) if group_exists => device_id,
+        _ => return Ok(false),
+    };
+    conn.execute(
+        "INSERT OR IGNORE INTO device_group_members (group_id, device_id) VALUES (?1, ?2)",
+        params![group_id, device_id],
+    )
+    .context("Failed to add device to group")?;
+    Ok(true)
+}
+
+/// Remove a device from a group, returning whether it was a member
+pub fn remove_device_from_group(conn: &Connection, group_id: i64, mac: &str) -> Result<bool> {
+    let Some(device_id) = find_device_id(conn, mac)? else {
+        return Ok(false);
+    };
+    let removed = conn
+        .execute(
+            "DELETE FROM device_group_members WHERE group_id = ?1 AND device_id = ?2",
+            params![group_id, device_id],
+        )
+        .context("Failed to remove device from group")?;
+    Ok(removed > 0)
+}
+
+/// Trimmed group name, or an error if it is empty
+fn normalize_group_name(name: &str) -> Result<&str> {
+    let name = name.trim();
+    if name.is_empty() {
+        anyhow::bail!("Group name is empty");
+    }
+    Ok(name)
+}
+
+/// Record a TLS SNI observation for a device (upsert by MAC + server name)
+pub fn record_sni_observation(
+    conn: &Connection,
+    device_mac: &str,
+    server_name: &str,
+    server_ip: Option<&str>,
+) -> Result<()> {
+    conn.execute(
+        r#"
+        INSERT INTO device_sni (device_mac, server_name, last_server_ip)
+        VALUES (?1, ?2, ?3)
+        ON CONFLICT(device_mac, server_name) DO UPDATE SET
+            last_server_ip = COALESCE(excluded.last_server_ip, last_server_ip),
+            last_seen = datetime('now'),
+            hit_count = hit_count + 1
+        "#,
+        params![device_mac, server_name, server_ip],
+    )
+    .context("Failed to record SNI observation")?;
+    Ok(())
+}
+
+/// Get SNI observations for a single device, most recent first
+pub fn get_device_sni(conn: &Connection, device_mac: &str) -> Result<Vec<SniRecord>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT id, device_mac, server_name, last_server_ip, first_seen, last_seen, hit_count
+        FROM device_sni
+        WHERE device_mac = ?1
+        ORDER BY last_seen DESC, hit_count DESC
+        "#,
+    )?;
+
+    let records = stmt
+        .query_map(params![device_mac], map_sni_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(records)
+}
+
+/// Get all SNI observations across devices
+pub fn get_all_sni(conn: &Connection) -> Result<Vec<SniRecord>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT id, device_mac, server_name, last_server_ip, first_seen, last_seen, hit_count
+        FROM device_sni
+        ORDER BY device_mac, hit_count DESC
+        "#,
+    )?;
+
+    let records = stmt
+        .query_map([], map_sni_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(records)
+}
+
+fn map_sni_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<SniRecord> {
+    Ok(SniRecord {
+        id: row.get(0)?,
+        device_mac: row.get(1)?,
+        server_name: row.get(2)?,
+        last_server_ip: row.get(3)?,
+        first_seen: parse_datetime_column(row.get::<_, String>(4)?, 4)?,
+        last_seen: parse_datetime_column(row.get::<_, String>(5)?, 5)?,
+        hit_count: row.get(6)?,
+    })
+}
+
+/// Record an mDNS service advertised by a device (upsert by MAC + type + instance)
+pub fn record_device_service(
+    conn: &Connection,
+    device_mac: &str,
+    service_type: &str,
+    instance_name: Option<&str>,
+    port: Option<u16>,
+) -> Result<()> {
+    conn.execute(
+        r#"
+        INSERT INTO device_services (device_mac, service_type, instance_name, port)
+        VALUES (?1, ?2, ?3, ?4)
+        ON CONFLICT(device_mac, service_type, instance_name) DO UPDATE SET
+            port = COALESCE(excluded.port, port),
+            last_seen = datetime('now')
+        "#,
+        params![device_mac, service_type, instance_name.unwrap_or(""), port],
+    )
+    .context("Failed to record device service")?;
+    Ok(())
+}
+
+/// Get mDNS services advertised by a device
+pub fn get_device_services(
+    conn: &Connection,
+    device_mac: &str,
+) -> Result<Vec<DeviceServiceRecord>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT id, device_mac, service_type, instance_name, port, first_seen, last_seen
+        FROM device_services
+        WHERE device_mac = ?1
+        ORDER BY service_type, instance_name
+        "#,
+    )?;
+
+    let records = stmt
+        .query_map(params![device_mac], map_device_service_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(records)
+}
+
+/// Get the distinct service types advertised by each device (MAC -> types)
+pub fn get_service_types_by_device(conn: &Connection) -> Result<HashMap<String, Vec<String>>> {
+    let mut stmt = conn.prepare(
+        "SELECT DISTINCT device_mac, service_type FROM device_services ORDER BY device_mac, service_type",
+    )?;
+
+    let mut services: HashMap<String, Vec<String>> = HashMap::new();
+    let rows = stmt.query_map([], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+    })?;
+    for row in rows {
+        let (mac, service_type) = row?;
+        services.entry(mac).or_default().push(service_type);
+    }
+
+    Ok(services)
+}
+
+/// Get a device together with its advertised services
+pub fn get_device_details(conn: &Connection, mac: &str) -> Result<Option<DeviceDetails>> {
+    let Some(device) = get_device_by_mac(conn, mac)? else {
+        return Ok(None);
+    };
+    let services = get_device_services(conn, mac)?;
+    let open_ports = get_latest_open_ports(conn, device.id)?;
+    let expected_ports = get_device_settings(conn, mac)?.and_then(|s| s.expected_ports);
+    let port_deviation = expected_ports
+        .as_deref()
+        .and_then(|expected| PortDeviation::between(expected, &open_ports));
+
+    Ok(Some(DeviceDetails {
+        device,
+        services,
+        open_ports,
+        expected_ports,
+        port_deviation,
+        directory: get_device_directory(conn, mac)?,
+    }))
+}
+
+/// Record the directory computer object matched to a device
+///
+/// The directory's operating system replaces the device's OS guess.
+pub fn upsert_device_directory(
+    conn: &Connection,
+    device_mac: &str,
+    computer: &DirectoryComputer,
+) -> Result<()> {
+    conn.execute(
+        r#"
+        INSERT INTO device_directory (
+            device_mac, computer_name, dns_hostname, operating_system, os_version,
+            ou, last_logon, distinguished_name, enabled
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+        ON CONFLICT(device_mac) DO UPDATE SET
+            computer_name = excluded.computer_name,
+            dns_hostname = excluded.dns_hostname,
+            operating_system = excluded.operating_system,
+            os_version = excluded.os_version,
+            ou = excluded.ou,
+            last_logon = excluded.last_logon,
+            distinguished_name = excluded.distinguished_name,
+            enabled = excluded.enabled,
+            updated_at = datetime('now')
+        "#,
+        params![
+            device_mac,
+            computer.name,
+            computer.dns_hostname,
+            computer.operating_system,
+            computer.os_version,
+            computer.ou,
+            computer
+                .last_logon
+                .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string()),
+            computer.distinguished_name,
+            computer.enabled,
+        ],
+    )
+    .context("Failed to save directory computer")?;
+
+    if let Some(os) = &computer.operating_system {
+        conn.execute(
+            "UPDATE devices SET os_guess = ?2 WHERE mac = ?1",
+            params![device_mac, os],
+        )
+        .context("Failed to update device OS from directory")?;
+    }
+    Ok(())
+}
+
+/// Forget a device's directory match, returning whether it had one
+pub fn delete_device_directory(conn: &Connection, device_mac: &str) -> Result<bool> {
+    let affected = conn
+        .execute(
+            "DELETE FROM device_directory WHERE device_mac = ?1",
+            params![device_mac],
+        )
+        .context("Failed to delete directory computer")?;
+    Ok(affected > 0)
+}
+
+/// Get the directory computer object matched to a device
+pub fn get_device_directory(
+    conn: &Connection,
+    device_mac: &str,
+) -> Result<Option<DirectoryComputer>> {
+    conn.query_row(
+        r#"
+        SELECT computer_name, dns_hostname, operating_system, os_version, ou,
+               last_logon, distinguished_name, enabled
+        FROM device_directory
+        WHERE device_mac = ?1
+        "#,
+        params![device_mac],
+        |row| {
+            Ok(DirectoryComputer {
+                name: row.get(0)?,
+                dns_hostname: row.get(1)?,
+                operating_system: row.get(2)?,
+                os_version: row.get(3)?,
+                ou: row.get(4)?,
+                last_logon: row
+                    .get::<_, Option<String>>(5)?
+                    .map(|raw| parse_datetime_column(raw, 5))
+                    .transpose()?,
+                distinguished_name: row.get(6)?,
+                enabled: row.get(7)?,
+            })
+        },
+    )
+    .optional()
+    .context("Failed to get directory computer")
+}
+
+/// MACs of devices matched to a directory computer object (managed devices)
+pub fn get_managed_device_macs(conn: &Connection) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare("SELECT device_mac FROM device_directory ORDER BY device_mac")?;
+    let macs = stmt
+        .query_map([], |row| row.get(0))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(macs)
+}
+
+/// Find the MAC of the device most recently seen at an IP address
+pub fn find_device_mac_by_ip(conn: &Connection, ip: &str) -> Result<Option<String>> {
+    conn.query_row(
+        "SELECT mac FROM devices WHERE last_ip = ?1 ORDER BY last_seen DESC LIMIT 1",
+        params![ip],
+        |row| row.get(0),
+    )
+    .optional()
+    .context("Failed to look up device by IP")
+}
+
+fn map_device_service_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<DeviceServiceRecord> {
+    Ok(DeviceServiceRecord {
+        id: row.get(0)?,
+        device_mac: row.get(1)?,
+        service_type: row.get(2)?,
+        instance_name: row.get(3)?,
+        port: row.get(4)?,
+        first_seen: parse_datetime_column(row.get::<_, String>(5)?, 5)?,
+        last_seen: parse_datetime_column(row.get::<_, String>(6)?, 6)?,
+    })
+}
+
+/// Replace the stored OUI registry with `entries` (prefix, vendor)
+///
+/// Returns the number of stored entries.
+pub fn replace_oui_registry(conn: &Connection, entries: &[(String, String)]) -> Result<usize> {
+    conn.execute_batch("SAVEPOINT replace_oui_registry")
+        .context("Failed to start OUI registry transaction")?;
+
+    let result = (|| -> Result<usize> {
+        conn.execute("DELETE FROM oui_registry", [])
+            .context("Failed to clear OUI registry")?;
+
+        let mut stmt =
+            conn.prepare("INSERT OR REPLACE INTO oui_registry (prefix, vendor) VALUES (?1, ?2)")?;
+        for (prefix, vendor) in entries {
+            stmt.execute(params![prefix, vendor])
+                .context("Failed to insert OUI entry")?;
+        }
+
+        let count: i64 =
+            conn.query_row("SELECT COUNT(*) FROM oui_registry", [], |row| row.get(0))?;
+        Ok(count as usize)
+    })();
+
+    match result {
+        Ok(count) => {
+            conn.execute_batch("RELEASE SAVEPOINT replace_oui_registry")
+                .context("Failed to commit OUI registry transaction")?;
+            Ok(count)
+        }
+        Err(e) => {
+            let _ = conn.execute_batch(
+                "ROLLBACK TO SAVEPOINT replace_oui_registry; RELEASE SAVEPOINT replace_oui_registry",
+            );
+            Err(e)
+        }
+    }
+}
+
+/// Get all stored OUI registry entries (prefix, vendor)
+pub fn get_oui_registry(conn: &Connection) -> Result<Vec<(String, String)>> {
+    let mut stmt = conn.prepare("SELECT prefix, vendor FROM oui_registry")?;
+    let entries = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(entries)
+}
+
+/// When the stored OUI registry was last refreshed (`None` if never)
+pub fn get_oui_registry_updated_at(conn: &Connection) -> Result<Option<DateTime<Utc>>> {
+    let updated_at: Option<String> =
+        conn.query_row("SELECT MAX(updated_at) FROM oui_registry", [], |row| {
+            row.get(0)
+        })?;
+    updated_at.map(parse_datetime).transpose()
+}
+
+/// Insert or replace a vendor override (pattern must already be normalized)
+pub fn upsert_vendor_override(conn: &Connection, pattern: &str, vendor: &str) -> Result<()> {
+    conn.execute(
+        r#"
+        INSERT INTO vendor_overrides (pattern, vendor) VALUES (?1, ?2)
+        ON CONFLICT(pattern) DO UPDATE SET vendor = excluded.vendor
+        "#,
+        params![pattern, vendor],
+    )
+    .context("Failed to save vendor override")?;
+    Ok(())
+}
+
+/// Delete a vendor override, returning whether it existed
+pub fn delete_vendor_override(conn: &Connection, pattern: &str) -> Result<bool> {
+    let affected = conn
+        .execute(
+            "DELETE FROM vendor_overrides WHERE pattern = ?1",
+            params![pattern],
+        )
+        .context("Failed to delete vendor override")?;
+    Ok(affected > 0)
+}
+
+/// Get all vendor overrides, most specific first
+pub fn get_vendor_overrides(conn: &Connection) -> Result<Vec<VendorOverrideRecord>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT pattern, vendor, created_at
+        FROM vendor_overrides
+        ORDER BY LENGTH(pattern) DESC, pattern
+        "#,
+    )?;
+
+    let records = stmt
+        .query_map([], |row| {
+            let pattern: String = row.get(0)?;
+            Ok(VendorOverrideRecord {
+                is_exact_mac: pattern.len() == 12,
+                pattern,
+                vendor: row.get(1)?,
+                created_at: parse_datetime_column(row.get::<_, String>(2)?, 2)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(records)
+}
+
+/// Insert a device type rule (must already be validated), returning its id
+pub fn insert_device_type_rule(conn: &Connection, rule: &DeviceTypeRule) -> Result<i64> {
+    conn.execute(
+        r#"
+        INSERT INTO device_type_rules (
+            name, vendor_pattern, hostname_pattern, ports, service,
+            device_type, confidence, enabled
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+        "#,
+        params![
+            rule.name,
+            rule.vendor_pattern,
+            rule.hostname_pattern,
+            join_ports(&rule.ports),
+            rule.service,
+            rule.device_type,
+            rule.confidence,
+            rule.enabled,
+        ],
+    )
+    .context("Failed to insert device type rule")?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Insert several device type rules, all or none
+pub fn insert_device_type_rules(conn: &Connection, rules: &[DeviceTypeRule]) -> Result<Vec<i64>> {
+    conn.execute_batch("SAVEPOINT insert_device_type_rules")
+        .context("Failed to start device type rule transaction")?;
+
+    let result = rules
+        .iter()
+        .map(|rule| insert_device_type_rule(conn, rule))
+        .collect::<Result<Vec<_>>>();
+
+    match result {
+        Ok(ids) => {
+            conn.execute_batch("RELEASE SAVEPOINT insert_device_type_rules")
+                .context("Failed to commit device type rule transaction")?;
+            Ok(ids)
+        }
+        Err(e) => {
+            let _ = conn.execute_batch(
+                "ROLLBACK TO SAVEPOINT insert_device_type_rules; \
+                 RELEASE SAVEPOINT insert_device_type_rules",
+            );
+            Err(e)
+        }
+    }
+}
+
+/// Replace a device type rule, returning whether it existed
+pub fn update_device_type_rule(conn: &Connection, id: i64, rule: &DeviceTypeRule) -> Result<bool> {
+    let affected = conn
+        .execute(
+            r#"
+            UPDATE device_type_rules SET
+                name = ?2, vendor_pattern = ?3, hostname_pattern = ?4, ports = ?5,
+                service = ?6, device_type = ?7, confidence = ?8, enabled = ?9
+            WHERE id = ?1
+            "#,
+            params![
+                id,
+                rule.name,
+                rule.vendor_pattern,
+                rule.hostname_pattern,
+                join_ports(&rule.ports),
+                rule.service,
+                rule.device_type,
+                rule.confidence,
+                rule.enabled,
+            ],
+        )
+        .context("Failed to update device type rule")?;
+    Ok(affected > 0)
+}
+
+/// Delete a device type rule, returning whether it existed
+pub fn delete_device_type_rule(conn: &Connection, id: i64) -> Result<bool> {
+    let affected = conn
+        .execute("DELETE FROM device_type_rules WHERE id = ?1", params![id])
+        .context("Failed to delete device type rule")?;
+    Ok(affected > 0)
+}
+
+/// Get all device type rules, highest confidence first
+pub fn get_device_type_rules(conn: &Connection) -> Result<Vec<DeviceTypeRuleRecord>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT id, name, vendor_pattern, hostname_pattern, ports, service,
+               device_type, confidence, enabled, created_at
+        FROM device_type_rules
+        ORDER BY confidence DESC, id
+        "#,
+    )?;
+
+    let rules = stmt
+        .query_map([], |row| {
+            let ports: String = row.get(4)?;
+            Ok(DeviceTypeRuleRecord {
+                id: row.get(0)?,
+                rule: DeviceTypeRule {
+                    name: row.get(1)?,
+                    vendor_pattern: row.get(2)?,
+                    hostname_pattern: row.get(3)?,
+                    ports: ports.split(',').filter_map(|p| p.parse().ok()).collect(),
+                    service: row.get(5)?,
+                    device_type: row.get(6)?,
+                    confidence: row.get(7)?,
+                    enabled: row.get(8)?,
+                },
+                created_at: parse_datetime_column(row.get::<_, String>(9)?, 9)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(rules)
+}
+
+/// Save a network tool run, returning its id
+pub fn insert_tool_run(
+    conn: &Connection,
+    tool: &str,
+    target: &str,
+    parameters: &serde_json::Value,
+    results: &serde_json::Value,
+    duration_ms: Option<u64>,
+) -> Result<i64> {
+    conn.execute(
+        r#"
+        INSERT INTO tool_runs (tool, target, parameters, results, duration_ms)
+        VALUES (?1, ?2, ?3, ?4, ?5)
+        "#,
+        params![
+            tool,
+            target,
+            parameters.to_string(),
+            results.to_string(),
+            duration_ms.map(|ms| ms as i64),
+        ],
+    )
+    .context("Failed to save tool run")?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Saved tool runs, newest first, optionally for one tool and/or target
+pub fn get_tool_runs(
+    conn: &Connection,
+    tool: Option<&str>,
+    target: Option<&str>,
+    limit: i32,
+) -> Result<Vec<ToolRunRecord>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT id, tool, target, parameters, results, duration_ms, created_at
+        FROM tool_runs
+        WHERE (?1 IS NULL OR tool = ?1) AND (?2 IS NULL OR target = ?2)
+        ORDER BY created_at DESC, id DESC
+        LIMIT ?3
+        "#,
+    )?;
+
+    let runs = stmt
+        .query_map(params![tool, target, limit], |row| {
+            let parameters: String = row.get(3)?;
+            let results: String = row.get(4)?;
+            Ok(ToolRunRecord {
+                id: row.get(0)?,
+                tool: row.get(1)?,
+                target: row.get(2)?,
+                parameters: serde_json::from_str(&parameters).unwrap_or_default(),
+                results: serde_json::from_str(&results).unwrap_or_default(),
+                duration_ms: row.get::<_, Option<i64>>(5)?.map(|ms| ms as u64),
+                created_at: parse_datetime_column(row.get(6)?, 6)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("Failed to get tool runs")?;
+
+    Ok(runs)
+}
+
+/// Delete a saved tool run, returning whether it existed
+pub fn delete_tool_run(conn: &Connection, id: i64) -> Result<bool> {
+    let affected = conn
+        .execute("DELETE FROM tool_runs WHERE id = ?1", params![id])
+        .context("Failed to delete tool run")?;
+    Ok(affected > 0)
+}
+
+fn join_ports(ports: &[u16]) -> String {
+    ports
+        .iter()
+        .map(|p| p.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn split_ports(ports: Option<String>) -> Vec<u16> {
+    ports
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|p| p.parse().ok())
+        .collect()
+}
+
+/// Show what `purge_device_data` would remove for a device
+///
+/// Returns `None` if the device is unknown. The confirmation token is tied to
+/// this device record, so it cannot be reused for another device.
+pub fn preview_device_purge(conn: &Connection, mac: &str) -> Result<Option<DevicePurgePreview>> {
+    let Some((device_id, token)) = purge_token(conn, mac)? else {
+        return Ok(None);
+    };
+
+    let count = |sql: &str, value: &dyn rusqlite::ToSql| -> Result<usize> {
+        let n: i64 = conn.query_row(sql, [value], |row| row.get(0))?;
+        Ok(n as usize)
+    };
+
+    Ok(Some(DevicePurgePreview {
+        mac: mac.to_string(),
+        history_rows: count(
+            "SELECT COUNT(*) FROM device_history WHERE device_id = ?1",
+            &device_id,
+        )?,
+        alerts: conn.query_row(
+            "SELECT COUNT(*) FROM alerts WHERE device_id = ?1 OR device_mac = ?2",
+            params![device_id, mac],
+            |row| row.get::<_, i64>(0),
+        )? as usize,
+        sni_observations: count(
+            "SELECT COUNT(*) FROM device_sni WHERE device_mac = ?1",
+            &mac,
+        )?,
+        services: count(
+            "SELECT COUNT(*) FROM device_services WHERE device_mac = ?1",
+            &mac,
+        )?,
+        has_settings: count(
+            "SELECT COUNT(*) FROM device_settings WHERE device_mac = ?1",
+            &mac,
+        )? > 0,
+        confirmation_token: token,
+    }))
+}
+
+/// Irreversibly remove a device and everything recorded about it
+///
+/// Deletes the device row, its per-scan and daily history, ports and tags, alerts, SNI
+/// observations, advertised services, settings, presence sessions, TLS certificates and SSH
+/// host keys in one transaction, along with the randomized MACs linked to it, their DHCP
+/// fingerprints and every stored monitor event that names one of its MACs; the device also
+/// drops out of the network baseline. Rows kept by MAC are removed for every linked MAC, not
+/// just `mac`. `confirmation_token` must match the one returned by [`preview_device_purge`].
+pub fn purge_device_data(
+    conn: &Connection,
+    mac: &str,
+    confirmation_token: &str,
+) -> Result<DevicePurgeSummary> {
+    let (device_id, expected) =
+        purge_token(conn, mac)?.ok_or_else(|| anyhow::anyhow!("Device {} not found", mac))?;
+    if confirmation_token.trim() != expected {
+        anyhow::bail!("Invalid confirmation token for device {}", mac);
+    }
+
+    conn.execute_batch("SAVEPOINT purge_device")
+        .context("Failed to start purge_device transaction")?;
+
+    let result = (|| -> Result<DevicePurgeSummary> {
+        let macs = device_macs(conn, device_id, mac)?;
+        // Rows removed for any of the device's MACs
+        let delete_by_mac = |sql: &str| -> Result<usize> {
+            let mut deleted = 0;
+            for mac in &macs {
+                deleted += conn.execute(sql, params![mac])?;
+            }
+            Ok(deleted)
+        };
+
+        let summary = DevicePurgeSummary {
+            mac: mac.to_string(),
+            history_rows: conn.execute(
+                "DELETE FROM device_history WHERE device_id = ?1",
+                params![device_id],
+            )?,
+            alerts: conn.execute(
+                "DELETE FROM alerts WHERE device_id = ?1",
+                params![device_id],
+            )? + delete_by_mac("DELETE FROM alerts WHERE device_mac = ?1")?,
+            sni_observations: delete_by_mac("DELETE FROM device_sni WHERE device_mac = ?1")?,
+            services: delete_by_mac("DELETE FROM device_services WHERE device_mac = ?1")?,
+            settings: delete_by_mac("DELETE FROM device_settings WHERE device_mac = ?1")?,
+        };
+        conn.execute(
+            "DELETE FROM device_ports WHERE device_id = ?1",
+            params![device_id],
+        )?;
+        conn.execute(
+            "DELETE FROM device_port_events WHERE device_id = ?1",
+            params![device_id],
+        )?;
+        conn.execute(
+            "DELETE FROM topology_edges WHERE local_device_id = ?1",
+            params![device_id],
+        )?;
+        conn.execute(
+            "DELETE FROM scan_findings WHERE device_id = ?1",
+            params![device_id],
+        )?;
+        conn.execute(
+            "DELETE FROM latency_rollups WHERE device_id = ?1",
+            params![device_id],
+        )?;
+        conn.execute(
+            "DELETE FROM device_history_daily WHERE device_id = ?1",
+            params![device_id],
+        )?;
+        conn.execute(
+            "DELETE FROM device_tags WHERE device_id = ?1",
+            params![device_id],
+        )?;
+        conn.execute(
+            "DELETE FROM device_attributes WHERE device_id = ?1",
+            params![device_id],
+        )?;
+        conn.execute(
+            "DELETE FROM device_group_members WHERE device_id = ?1",
+            params![device_id],
+        )?;
+        delete_by_mac("DELETE FROM device_directory WHERE device_mac = ?1")?;
+        delete_by_mac("DELETE FROM risk_acceptances WHERE device_mac = ?1")?;
+        delete_by_mac("DELETE FROM dhcp_fingerprints WHERE mac = ?1")?;
+        delete_by_mac("DELETE FROM network_baseline WHERE mac = ?1")?;
+        // Presence, certificate and SSH key tracking store MACs upper-cased
+        delete_by_mac("DELETE FROM presence_sessions WHERE mac = ?1 COLLATE NOCASE")?;
+        delete_by_mac("DELETE FROM device_certificates WHERE mac = ?1 COLLATE NOCASE")?;
+        delete_by_mac("DELETE FROM device_ssh_keys WHERE mac = ?1 COLLATE NOCASE")?;
+        // The MAC may sit in any field of the event payload (`mac`, `new_mac`, `macs`, ...)
+        delete_by_mac(
+            r#"
+            DELETE FROM network_events
+            WHERE instr(lower(payload), lower(?1)) > 0
+              AND json_valid(payload)
+              AND EXISTS (
+                  SELECT 1 FROM json_tree(network_events.payload)
+                  WHERE json_tree.type = 'text' AND lower(json_tree.atom) = lower(?1)
+              )
+            "#,
+        )?;
+        conn.execute(
+            "DELETE FROM device_merges WHERE device_id = ?1",
+            params![device_id],
+        )?;
+        conn.execute("DELETE FROM devices WHERE id = ?1", params![device_id])?;
+        Ok(summary)
+    })();
+
+    match result {
+        Ok(summary) => {
+            conn.execute_batch("RELEASE SAVEPOINT purge_device")
+                .context("Failed to commit purge_device transaction")?;
+            tracing::info!("Purged all data for device {}", mac);
+            Ok(summary)
+        }
+        Err(e) => {
+            let _ = conn.execute_batch(
+                "ROLLBACK TO SAVEPOINT purge_device; RELEASE SAVEPOINT purge_device",
+            );
+            Err(e.context("Failed to purge device data"))
+        }
+    }
+}
+
+/// Device id and purge confirmation token for a MAC
+fn purge_token(conn: &Connection, mac: &str) -> Result<Option<(i64, String)>> {
+    let device = conn
+        .query_row(
+            "SELECT id, first_seen FROM devices WHERE mac = ?1",
+            params![mac],
+            |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)),
+        )
+        .optional()?;
+
+    Ok(device.map(|(id, first_seen)| {
+        use sha2::{Digest, Sha256};
+        let digest = Sha256::digest(format!("purge-device:{}:{}:{}", mac, id, first_seen));
+        (id, hex::encode(&digest[..6]))
+    }))
+}
+
+/// Helper: Parse SQLite datetime string to chrono DateTime
+fn parse_datetime(s: String) -> Result<DateTime<Utc>> {
+    DateTime::parse_from_str(&format!("{} +0000", s), "%Y-%m-%d %H:%M:%S %z")
+        .map(|dt| dt.with_timezone(&Utc))
+        .with_context(|| format!("Invalid datetime value in database: {}", s))
+}
+
+pub(crate) fn parse_datetime_column(s: String, column: usize) -> rusqlite::Result<DateTime<Utc>> {
+    DateTime::parse_from_str(&format!("{} +0000", s), "%Y-%m-%d %H:%M:%S %z")
+        .map(|dt| dt.with_timezone(&Utc))
+        .map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(column, rusqlite::types::Type::Text, Box::new(e))
+    })
+}
+
+fn parse_alert_type_or_default(s: &str) -> AlertType {
+    match s.parse() {
+        Ok(value) => value,
+        Err(_) => {
+            tracing::warn!("Unknown alert type in database: {}", s);
+            AlertType::Custom
+        }
+    }
+}
+
+fn parse_alert_severity_or_default(s: &str) -> AlertSeverity {
+    match s.parse() {
+        Ok(value) => value,
+        Err(_) => {
+            tracing::warn!("Unknown alert severity in database: {}", s);
+            AlertSeverity::Info
+        }
+    }
+}
+
+/// Lookup vulnerabilities for a vendor from CVE cache
+pub fn lookup_vulnerabilities(
+    conn: &Connection,
+    vendor: &str,
+) -> Result<Vec<crate::models::VulnerabilityInfo>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT cve_id, description, severity, cvss_score
+        FROM cve_cache
+        WHERE LOWER(vendor) = LOWER(?1) OR vendor = '*'
+        ORDER BY cvss_score DESC NULLS LAST
+        "#,
+    )?;
+
+    let vulns = stmt
+        .query_map(params![vendor], |row| {
+            Ok(crate::models::VulnerabilityInfo {
+                cve_id: row.get(0)?,
+                description: row.get(1)?,
+                severity: row.get(2)?,
+                cvss_score: row.get(3)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(vulns)
+}
+
+/// Lookup port warnings for given ports
+pub fn lookup_port_warnings(
+    conn: &Connection,
+    ports: &[u16],
+) -> Result<Vec<crate::models::PortWarning>> {
+    if ports.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let placeholders = ports.iter().map(|_| "?").collect::<Vec<_>>().join(",");
+    let query = format!(
+        "SELECT port, service, warning, severity, recommendation FROM port_warnings WHERE port IN ({})",
+        placeholders
+    );
+
+    let mut stmt = conn.prepare(&query)?;
+    let params: Vec<&dyn rusqlite::ToSql> =
+        ports.iter().map(|p| p as &dyn rusqlite::ToSql).collect();
+
+    let warnings = stmt
+        .query_map(params.as_slice(), |row| {
+            Ok(crate::models::PortWarning {
+                port: row.get::<_, i64>(0)? as u16,
+                service: row.get(1)?,
+                warning: row.get(2)?,
+                severity: row.get(3)?,
+                recommendation: row.get(4)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(warnings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Database;
+    use crate::models::{HostSort, HostState};
+
+    #[test]
+    fn test_insert_and_get_scan() {
+        let db = Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
+
+        let result = ScanResult {
+            interface_name: "eth0".to_string(),
+            local_ip: "192.168.1.1".to_string(),
+            local_mac: "AA:BB:CC:DD:EE:FF".to_string(),
+            subnet: "192.168.1.0/24".to_string(),
+            scan_method: "arp+icmp".to_string(),
+            arp_discovered: 5,
+            icmp_discovered: 3,
+            total_hosts: 5,
+            scan_duration_ms: 1500,
+            active_hosts: vec![],
+            wan: None,
+            ssid: None,
+        };
+
+        let scan_id = insert_scan(&conn, &result).unwrap();
+        assert!(scan_id > 0);
+
+        let scans = get_recent_scans(&conn, 10).unwrap();
+        assert_eq!(scans.len(), 1);
+        assert_eq!(scans[0].interface_name, "eth0");
+        assert_eq!(scans[0].label, None);
+
+        assert!(set_scan_label(&conn, scan_id, Some("  before adding new AP ")).unwrap());
+        let scans = get_recent_scans(&conn, 10).unwrap();
+        assert_eq!(scans[0].label.as_deref(), Some("before adding new AP"));
+        assert!(set_scan_label(&conn, scan_id, Some(&"x".repeat(201))).is_err());
+        assert!(set_scan_label(&conn, scan_id, Some(" ")).unwrap());
+        assert_eq!(get_recent_scans(&conn, 10).unwrap()[0].label, None);
+        assert!(!set_scan_label(&conn, scan_id + 1, Some("missing")).unwrap());
+    }
+
+    #[test]
+    fn test_device_tags() {
+        let db = Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
+
+        let hosts = ["AA:BB:CC:DD:EE:21", "AA:BB:CC:DD:EE:22"]
+            .iter()
+            .enumerate()
+            .map(|(i, mac)| {
+                HostInfo::new(
+                    format!("192.168.1.2{}", i),
+                    mac.to_string(),
+                    "PC".to_string(),
+                    "ARP".to_string(),
+                )
+            })
+            .collect::<Vec<_>>();
+        let result = ScanResult {
+            interface_name: "eth0".to_string(),
+            local_ip: "192.168.1.1".to_string(),
+            local_mac: "AA:BB:CC:DD:EE:FF".to_string(),
+            subnet: "192.168.1.0/24".to_string(),
+            scan_method: "arp".to_string(),
+            arp_discovered: 2,
+            icmp_discovered: 0,
+            total_hosts: 2,
+            scan_duration_ms: 100,
+            active_hosts: hosts,
+            wan: None,
+            ssid: None,
+        };
+        let scan_id = insert_scan(&conn, &result).unwrap();
+
+        assert!(add_tag(&conn, "AA:BB:CC:DD:EE:21", " kids ").unwrap());
+        assert!(add_tag(&conn, "AA:BB:CC:DD:EE:21", "IoT").unwrap());
+        assert!(add_tag(&conn, "AA:BB:CC:DD:EE:21", "KIDS").unwrap());
+        assert!(add_tag(&conn, "AA:BB:CC:DD:EE:22", "work").unwrap());
+        assert!(!add_tag(&conn, "00:00:00:00:00:00", "work").unwrap());
+        assert!(add_tag(&conn, "AA:BB:CC:DD:EE:22", " ").is_err());
+
+        let device = get_device_by_mac(&conn, "AA:BB:CC:DD:EE:21")
+            .unwrap()
+            .unwrap();
+        assert_eq!(device.tags, vec!["IoT".to_string(), "kids".to_string()]);
+
+        let kids = get_devices_by_tag(&conn, "Kids").unwrap();
+        assert_eq!(kids.len(), 1);
+        assert_eq!(kids[0].mac, "AA:BB:CC:DD:EE:21");
+        assert_eq!(
+            get_all_tags(&conn).unwrap(),
+            vec![
+                TagCount {
+                    tag: "IoT".to_string(),
+                    device_count: 1
+                },
+                TagCount {
+                    tag: "kids".to_string(),
+                    device_count: 1
+                },
+                TagCount {
+                    tag: "work".to_string(),
+                    device_count: 1
+                },
+            ]
+        );
+        assert_eq!(
+            get_tags_by_mac(&conn).unwrap()["aa:bb:cc:dd:ee:21"],
+            vec!["IoT".to_string(), "kids".to_string()]
+        );
+
+        let scans = get_recent_scans_with_tag(&conn, "work", 10).unwrap();
+        assert_eq!(scans.len(), 1);
+        assert_eq!(scans[0].id, scan_id);
+        assert!(get_recent_scans_with_tag(&conn, "guest", 10)
+            .unwrap()
+            .is_empty());
+
+        assert!(remove_tag(&conn, "AA:BB:CC:DD:EE:21", "kids").unwrap());
+        assert!(!remove_tag(&conn, "AA:BB:CC:DD:EE:21", "kids").unwrap());
+        assert!(get_devices_by_tag(&conn, "kids").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_device_attributes() {
+        let db = Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
+
+        let host = HostInfo::new(
+            "192.168.1.30".to_string(),
+            "AA:BB:CC:DD:EE:30".to_string(),
+            "PC".to_string(),
+            "ARP".to_string(),
+        );
+        let result = ScanResult {
+            interface_name: "eth0".to_string(),
+            local_ip: "192.168.1.1".to_string(),
+            local_mac: "AA:BB:CC:DD:EE:FF".to_string(),
+            subnet: "192.168.1.0/24".to_string(),
+            scan_method: "arp".to_string(),
+            arp_discovered: 1,
+            icmp_discovered: 0,
+            total_hosts: 1,
+            scan_duration_ms: 100,
+            active_hosts: vec![host],
+            wan: None,
+            ssid: None,
+        };
+        insert_scan(&conn, &result).unwrap();
+        let mac = "AA:BB:CC:DD:EE:30";
+
+        assert!(set_device_attribute(&conn, mac, " Asset Tag ", "IT-0042").unwrap());
+        assert!(set_device_attribute(&conn, mac, "owner", "alice").unwrap());
+        assert!(set_device_attribute(&conn, mac, "Owner", "Bob").unwrap());
+        assert!(!set_device_attribute(&conn, "00:00:00:00:00:00", "owner", "x").unwrap());
+        assert!(set_device_attribute(&conn, mac, " ", "x").is_err());
+        assert!(set_device_attribute(&conn, mac, "notes", &"x".repeat(1001)).is_err());
+
+        let device = get_device_by_mac(&conn, mac).unwrap().unwrap();
+        assert_eq!(device.attributes.len(), 2);
+        assert_eq!(device.attributes["Asset Tag"], "IT-0042");
+        assert_eq!(device.attributes["Owner"], "Bob");
+        assert_eq!(
+            get_all_devices(&conn).unwrap()[0].attributes,
+            device.attributes
+        );
+        assert_eq!(
+            get_attributes_by_mac(&conn).unwrap()[mac],
+            device.attributes
+        );
+
+        // Values are searchable and the index follows edits
+        assert_eq!(search_devices(&conn, "IT-0042").unwrap().len(), 1);
+        assert_eq!(search_devices(&conn, "bob").unwrap().len(), 1);
+        assert!(set_device_attribute(&conn, mac, "owner", "Carol").unwrap());
+        assert!(search_devices(&conn, "bob").unwrap().is_empty());
+        assert_eq!(search_devices(&conn, "carol").unwrap().len(), 1);
+
+        assert_eq!(
+            get_attribute_names(&conn).unwrap(),
+            vec![
+                AttributeCount {
+                    name: "Asset Tag".to_string(),
+                    device_count: 1
+                },
+                AttributeCount {
+                    name: "owner".to_string(),
+                    device_count: 1
+                },
+            ]
+        );
+
+        // An empty value clears the field
+        assert!(set_device_attribute(&conn, mac, "owner", "").unwrap());
+        assert!(!remove_device_attribute(&conn, mac, "owner").unwrap());
+        assert!(remove_device_attribute(&conn, mac, "asset tag").unwrap());
+        assert!(get_device_attributes(&conn, mac).unwrap().is_empty());
+        assert!(search_devices(&conn, "carol").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_network_stats() {
+        let db = Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
+
+        let stats = get_network_stats(&conn).unwrap();
+        assert_eq!(stats.total_devices, 0);
+        assert_eq!(stats.total_scans, 0);
+    }
+
+    #[test]
+    fn test_duplicate_scans_are_collapsed() {
+        let db = Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
+
+        let host = HostInfo::new(
+            "192.168.1.10".to_string(),
+            "AA:BB:CC:DD:EE:01".to_string(),
+            "UNKNOWN".to_string(),
+            "ARP".to_string(),
+        );
+        let mut result = ScanResult {
+            interface_name: "eth0".to_string(),
+            local_ip: "192.168.1.1".to_string(),
+            local_mac: "AA:BB:CC:DD:EE:FF".to_string(),
+            subnet: "192.168.1.0/24".to_string(),
+            scan_method: "arp+icmp".to_string(),
+            arp_discovered: 1,
+            icmp_discovered: 0,
+            total_hosts: 1,
+            scan_duration_ms: 1500,
+            active_hosts: vec![host],
+            wan: None,
+            ssid: None,
+        };
+
+        let first = insert_scan(&conn, &result).unwrap();
+        insert_scan(&conn, &result).unwrap();
+        insert_scan(&conn, &result).unwrap();
+
+        let scans = get_recent_scans(&conn, 10).unwrap();
+        assert_eq!(scans.len(), 1);
+        assert_eq!(scans[0].id, first);
+        assert_eq!(scans[0].duplicate_count, 2);
+
+        let stats = get_network_stats(&conn).unwrap();
+        assert_eq!(stats.total_scans, 1);
+        assert_eq!(stats.duplicate_scans, 2);
+
+        // A changed host set or port set starts a new run
+        result.active_hosts[0].ip = "192.168.1.11".to_string();
+        insert_scan(&conn, &result).unwrap();
+        result.active_hosts[0].open_ports = vec![22];
+        insert_scan(&conn, &result).unwrap();
+        assert_eq!(get_recent_scans(&conn, 10).unwrap().len(), 3);
+
+        set_collapse_duplicate_scans(&conn, false).unwrap();
+        let scans = get_recent_scans(&conn, 10).unwrap();
+        assert_eq!(scans.len(), 5);
+        assert_eq!(scans[2].duplicate_of, Some(first));
+        assert_eq!(get_network_stats(&conn).unwrap().total_scans, 5);
+    }
+
+    #[test]
+    fn test_diff_scans() {
+        let db = Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
+
+        let host = |ip: &str, mac: &str, ports: Vec<u16>| {
+            let mut host = HostInfo::new(
+                ip.to_string(),
+                mac.to_string(),
+                "UNKNOWN".to_string(),
+                "ARP".to_string(),
+            );
+            host.open_ports = ports;
+            host
+        };
+        let scan = |active_hosts: Vec<HostInfo>| ScanResult {
+            interface_name: "eth0".to_string(),
+            local_ip: "192.168.1.1".to_string(),
+            local_mac: "AA:BB:CC:DD:EE:FF".to_string(),
+            subnet: "192.168.1.0/24".to_string(),
+            scan_method: "arp+icmp".to_string(),
+            arp_discovered: active_hosts.len(),
+            icmp_discovered: 0,
+            total_hosts: active_hosts.len(),
+            scan_duration_ms: 1500,
+            active_hosts,
+            wan: None,
+            ssid: None,
+        };
+        let older = scan(vec![
+            host("192.168.1.10", "AA:BB:CC:DD:EE:01", vec![22, 80]),
+            host("192.168.1.11", "AA:BB:CC:DD:EE:02", vec![]),
+            host("192.168.1.12", "AA:BB:CC:DD:EE:03", vec![443]),
+        ]);
+        let newer = scan(vec![
+            host("192.168.1.10", "AA:BB:CC:DD:EE:01", vec![80, 8080]),
+            host("192.168.1.20", "AA:BB:CC:DD:EE:02", vec![]),
+            host("192.168.1.13", "AA:BB:CC:DD:EE:04", vec![]),
+        ]);
+        let first = insert_scan(&conn, &older).unwrap();
+        let second = insert_scan(&conn, &newer).unwrap();
+
+        let diff = diff_scans(&conn, first, second).unwrap();
+        assert_eq!(diff.appeared.len(), 1);
+        assert_eq!(diff.appeared[0].mac, "AA:BB:CC:DD:EE:04");
+        assert_eq!(diff.disappeared.len(), 1);
+        assert_eq!(diff.disappeared[0].ip, "192.168.1.12");
+        assert_eq!(diff.ip_changes.len(), 1);
+        assert_eq!(diff.ip_changes[0].old_ip, "192.168.1.11");
+        assert_eq!(diff.ip_changes[0].new_ip, "192.168.1.20");
+        assert_eq!(diff.port_changes.len(), 1);
+        assert_eq!(diff.port_changes[0].opened, vec![8080]);
+        assert_eq!(diff.port_changes[0].closed, vec![22]);
+
+        // The library-level diff agrees with the stored one
+        assert_eq!(older.diff(&newer), diff);
+
+        assert!(diff_scans(&conn, second, second).unwrap().is_empty());
+        assert!(diff_scans(&conn, first, second + 1).is_err());
+    }
+
+    #[test]
+    fn test_attach_device_ids_and_sort() {
+        let db = Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
+
+        let host = |ip: &str, mac: &str, risk: u8, hostname: Option<&str>| {
+            let mut host = HostInfo::new(
+                ip.to_string(),
+                mac.to_string(),
+                "UNKNOWN".to_string(),
+                "ARP".to_string(),
+            );
+            host.risk_score = risk;
+            host.hostname = hostname.map(str::to_string);
+            host
+        };
+        let mut result = ScanResult {
+            interface_name: "eth0".to_string(),
+            local_ip: "192.168.1.1".to_string(),
+            local_mac: "AA:BB:CC:DD:EE:FF".to_string(),
+            subnet: "192.168.1.0/24".to_string(),
+            scan_method: "arp+icmp".to_string(),
+            arp_discovered: 2,
+            icmp_discovered: 0,
+            total_hosts: 2,
+            scan_duration_ms: 1500,
+            active_hosts: vec![
+                host("192.168.1.10", "AA:BB:CC:DD:EE:01", 10, Some("nas")),
+                host("192.168.1.9", "AA:BB:CC:DD:EE:02", 80, Some("Camera")),
+            ],
+            wan: None,
+            ssid: None,
+        };
+        insert_scan(&conn, &result).unwrap();
+
+        // A MAC merged into a device resolves to that device
+        result
+            .active_hosts
+            .push(host("192.168.1.11", "AA:BB:CC:DD:EE:03", 50, None));
+        insert_scan(&conn, &result).unwrap();
+        merge_devices(&conn, "AA:BB:CC:DD:EE:01", "AA:BB:CC:DD:EE:03").unwrap();
+        result
+            .active_hosts
+            .push(host("192.168.1.12", "AA:BB:CC:DD:EE:04", 0, None));
+
+        attach_device_ids(&conn, &mut result.active_hosts).unwrap();
+        let nas = &result.active_hosts[0];
+        let merged = &result.active_hosts[2];
+        assert!(nas.device_id.is_some() && nas.last_seen.is_some());
+        assert_eq!(merged.device_id, nas.device_id);
+        assert!(merged.same_host(nas));
+        assert!(result.active_hosts[3].device_id.is_none());
+        assert!(std::ptr::eq(result.find_host(merged).unwrap(), nas));
+
+        // Last octet of each host, in order
+        let ips = |result: &ScanResult| {
+            result
+                .active_hosts
+                .iter()
+                .map(|h| h.ip.rsplit('.').next().unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+        result.sort_hosts(HostSort::Ip);
+        assert_eq!(ips(&result), ["9", "10", "11", "12"]);
+        result.sort_hosts(HostSort::Risk);
+        assert_eq!(ips(&result), ["9", "11", "10", "12"]);
+        result.sort_hosts(HostSort::Hostname);
+        assert_eq!(ips(&result), ["9", "10", "11", "12"]);
+        result.sort_hosts(HostSort::LastSeen);
+        assert_eq!(ips(&result)[3], "12");
+        assert_eq!("last_seen".parse::<HostSort>().unwrap(), HostSort::LastSeen);
+    }
+
+    #[test]
+    fn test_search_devices() {
+        let db = Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
+
+        let host = |ip: &str, mac: &str, vendor: &str, hostname: &str| {
+            let mut host = HostInfo::new(
+                ip.to_string(),
+                mac.to_string(),
+                "UNKNOWN".to_string(),
+                "ARP".to_string(),
+            );
+            host.vendor = Some(vendor.to_string());
+            host.hostname = Some(hostname.to_string());
+            host
+        };
+        let result = ScanResult {
+            interface_name: "eth0".to_string(),
+            local_ip: "192.168.1.1".to_string(),
+            local_mac: "AA:BB:CC:DD:EE:FF".to_string(),
+            subnet: "192.168.1.0/24".to_string(),
+            scan_method: "arp+icmp".to_string(),
+            arp_discovered: 2,
+            icmp_discovered: 0,
+            total_hosts: 2,
+            scan_duration_ms: 1500,
+            active_hosts: vec![
+                host("192.168.1.10", "AA:BB:CC:00:00:01", "Synology", "nas"),
+                host("10.0.0.5", "DE:AD:BE:EF:00:02", "Hikvision", "camera-porch"),
+            ],
+            wan: None,
+            ssid: None,
+        };
+        insert_scan(&conn, &result).unwrap();
+
+        let macs = |query: &str| {
+            search_devices(&conn, query)
+                .unwrap()
+                .into_iter()
+                .map(|d| d.mac)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(macs("synology"), ["AA:BB:CC:00:00:01"]);
+        assert_eq!(macs("camera"), ["DE:AD:BE:EF:00:02"]);
+        assert_eq!(macs("de:ad"), ["DE:AD:BE:EF:00:02"]);
+        assert_eq!(macs("192.168"), ["AA:BB:CC:00:00:01"]);
+        assert!(macs("nas hikvision").is_empty());
+        assert!(macs("  ").is_empty());
+        assert!(macs("\"unbalanced").is_empty());
+
+        // Edits to names are searchable right away
+        update_device_name(&conn, "DE:AD:BE:EF:00:02", "Front Door").unwrap();
+        assert_eq!(macs("front door"), ["DE:AD:BE:EF:00:02"]);
+    }
+
+    #[test]
+    fn test_query_pagination_and_filters() {
+        let db = Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
+
+        let host = |last_octet: u8, device_type: &str| {
+            HostInfo::new(
+                format!("192.168.1.{}", last_octet),
+                format!("AA:BB:CC:DD:EE:{:02X}", last_octet),
+                device_type.to_string(),
+                "ARP".to_string(),
+            )
+        };
+        let mut result = ScanResult {
+            interface_name: "eth0".to_string(),
+            local_ip: "192.168.1.1".to_string(),
+            local_mac: "AA:BB:CC:DD:EE:FF".to_string(),
+            subnet: "192.168.1.0/24".to_string(),
+            scan_method: "arp+icmp".to_string(),
+            arp_discovered: 5,
+            icmp_discovered: 0,
+            total_hosts: 5,
+            scan_duration_ms: 1500,
+            active_hosts: (10..15).map(|n| host(n, "PHONE")).collect(),
+            wan: None,
+            ssid: None,
+        };
+        result.active_hosts[0].device_type = "ROUTER".to_string();
+        insert_scan(&conn, &result).unwrap();
+        // The last two devices drop off the network
+        result.active_hosts.truncate(3);
+        insert_scan(&conn, &result).unwrap();
+
+        let page = query_devices(
+            &conn,
+            &DeviceQuery {
+                limit: Some(2),
+                offset: 2,
+                sort: DeviceSort::Name,
+                descending: false,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(page.total, 5);
+        let macs: Vec<&str> = page.items.iter().map(|d| d.mac.as_str()).collect();
+        assert_eq!(macs, ["AA:BB:CC:DD:EE:0C", "AA:BB:CC:DD:EE:0D"]);
+
+        let offline = DeviceQuery {
+            online: Some(false),
+            ..Default::default()
+        };
+        assert_eq!(query_devices(&conn, &offline).unwrap().total, 2);
+        let routers = DeviceQuery {
+            device_type: Some("ROUTER".to_string()),
+            online: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(query_devices(&conn, &routers).unwrap().items.len(), 1);
+
+        let scans = query_scans(
+            &conn,
+            &ScanQuery {
+                limit: Some(1),
+                oldest_first: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(scans.total, 2);
+        assert_eq!(scans.items[0].total_hosts, 5);
+
+        for severity in [
+            AlertSeverity::Info,
+            AlertSeverity::Critical,
+            AlertSeverity::Critical,
+        ] {
+            let message = format!("{} alert", severity);
+            insert_alert(
+                &conn,
+                AlertType::NewDevice,
+                None,
+                None,
+                None,
+                &message,
+                severity,
+            )
+            .unwrap();
+        }
+        mark_alert_read(&conn, 2).unwrap();
+        let critical = AlertQuery {
+            severity: Some(AlertSeverity::Critical),
+            ..Default::default()
+        };
+        assert_eq!(query_alerts(&conn, &critical).unwrap().total, 2);
+        let unread = AlertQuery {
+            unread_only: true,
+            limit: Some(1),
+            ..Default::default()
+        };
+        let page = query_alerts(&conn, &unread).unwrap();
+        assert_eq!((page.total, page.items[0].id), (2, 3));
+        let future = AlertQuery {
+            since: Some(Utc::now() + chrono::Duration::hours(1)),
+            ..Default::default()
+        };
+        assert_eq!(query_alerts(&conn, &future).unwrap().total, 0);
+
+        insert_alert(
+            &conn,
+            AlertType::PortChange,
+            None,
+            Some("AA:BB:CC:00:00:01"),
+            Some("192.168.1.10"),
+            "device alert",
+            AlertSeverity::Warning,
+        )
+        .unwrap();
+        let device = AlertQuery {
+            device_mac: Some("aa:bb:cc:00:00:01".to_string()),
+            ..Default::default()
+        };
+        let page = query_alerts(&conn, &device).unwrap();
+        assert_eq!((page.total, page.items[0].id), (1, 4));
+    }
+
+    #[test]
+    fn test_device_settings_roundtrip() {
+        let db = Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
+
+        let mac = "aa:bb:cc:dd:ee:02";
+        assert!(get_device_settings(&conn, mac).unwrap().is_none());
+
+        let mut settings = DeviceSettings::new(mac);
+        settings.offline_grace_secs = Some(120);
+        settings.risk_threshold = Some(70);
+        upsert_device_settings(&conn, &settings).unwrap();
+        assert_eq!(get_device_settings(&conn, mac).unwrap(), Some(settings.clone()));
+
+        settings.offline_alerts_enabled = false;
+        settings.offline_grace_secs = None;
+        upsert_device_settings(&conn, &settings).unwrap();
+        assert_eq!(get_all_device_settings(&conn).unwrap(), vec![settings.clone()]);
+
+        // Watching keeps the other overrides
+        set_device_watched(&conn, mac, true).unwrap();
+        settings.watched = true;
+        assert_eq!(get_device_settings(&conn, mac).unwrap(), Some(settings.clone()));
+
+        // So does trusting
+        set_device_trusted(&conn, mac, true).unwrap();
+        settings.trusted = true;
+        assert_eq!(get_device_settings(&conn, mac).unwrap(), Some(settings.clone()));
+
+        // Muting keeps them too, to the second
+        let until = Utc::now() + chrono::Duration::hours(2);
+        mute_alerts_for_device(&conn, mac, Some(until)).unwrap();
+        let muted = get_device_settings(&conn, mac).unwrap().unwrap();
+        assert_eq!(muted.muted_until.unwrap().timestamp(), until.timestamp());
+        assert!(muted.is_muted(Utc::now()));
+        assert!(!muted.is_muted(until));
+        assert!(mute_alerts_for_device(&conn, mac, Some(Utc::now())).is_err());
+        mute_alerts_for_device(&conn, mac, None).unwrap();
+        assert_eq!(get_device_settings(&conn, mac).unwrap(), Some(settings));
+
+        assert!(reset_device_thresholds(&conn, mac).unwrap());
+        let reset = DeviceSettings {
+            watched: true,
+            trusted: true,
+            ..DeviceSettings::new(mac)
+        };
+        assert_eq!(get_device_settings(&conn, mac).unwrap(), Some(reset));
+        assert!(!reset_device_thresholds(&conn, "aa:bb:cc:dd:ee:99").unwrap());
+    }
+
+    #[test]
+    fn test_reset_device_thresholds_keeps_trust_watch_and_mute() {
+        let db = Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
+
+        let mac = "aa:bb:cc:dd:ee:04";
+        let until = Utc::now() + chrono::Duration::hours(1);
+        let mut settings = DeviceSettings::new(mac);
+        settings.latency_threshold_ms = Some(250);
+        settings.offline_grace_secs = Some(600);
+        settings.offline_alerts_enabled = false;
+        settings.risk_threshold = Some(40);
+        settings.risk_alerts_enabled = false;
+        settings.expected_ports = Some(vec![22, 443]);
+        settings.trusted = true;
+        settings.watched = true;
+        upsert_device_settings(&conn, &settings).unwrap();
+        mute_alerts_for_device(&conn, mac, Some(until)).unwrap();
+
+        assert!(reset_device_thresholds(&conn, mac).unwrap());
+        let reset = get_device_settings(&conn, mac).unwrap().unwrap();
+        assert!(reset.trusted);
+        assert!(reset.watched);
+        assert_eq!(reset.muted_until.unwrap().timestamp(), until.timestamp());
+        assert_eq!(reset.expected_ports, Some(vec![22, 443]));
+        assert_eq!(reset.latency_threshold_ms, None);
+        assert_eq!(reset.offline_grace_secs, None);
+        assert_eq!(reset.risk_threshold, None);
+        assert!(reset.offline_alerts_enabled);
+        assert!(reset.risk_alerts_enabled);
+    }
+
+    #[test]
+    fn test_expected_ports_baseline_in_details() {
+        let db = Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
+
+        let mac = "AA:BB:CC:DD:EE:03";
+        let mut host = HostInfo::new(
+            "192.168.1.30".to_string(),
+            mac.to_string(),
+            "NAS".to_string(),
+            "ARP".to_string(),
+        );
+        host.open_ports = vec![22, 445];
+        let mut result = ScanResult {
+            interface_name: "eth0".to_string(),
+            local_ip: "192.168.1.1".to_string(),
+            local_mac: "AA:BB:CC:DD:EE:FF".to_string(),
+            subnet: "192.168.1.0/24".to_string(),
+            scan_method: "arp".to_string(),
+            arp_discovered: 1,
+            icmp_discovered: 0,
+            total_hosts: 1,
+            scan_duration_ms: 100,
+            active_hosts: vec![host],
+            wan: None,
+            ssid: None,
+        };
+        insert_scan(&conn, &result).unwrap();
+
+        assert!(record_expected_ports_baseline(&conn, "00:00:00:00:00:00")
+            .unwrap()
+            .is_none());
+        assert_eq!(
+            record_expected_ports_baseline(&conn, mac).unwrap(),
+            Some(vec![22, 445])
+        );
+        let details = get_device_details(&conn, mac).unwrap().unwrap();
+        assert!(details.port_deviation.is_none());
+
+        result.active_hosts[0].open_ports = vec![22, 3389];
+        insert_scan(&conn, &result).unwrap();
+        let details = get_device_details(&conn, mac).unwrap().unwrap();
+        assert_eq!(
+            details.port_deviation,
+            Some(PortDeviation {
+                unexpected: vec![3389],
+                missing: vec![445],
+            })
+        );
+
+        set_expected_ports(&conn, mac, None).unwrap();
+        let settings = get_device_settings(&conn, mac).unwrap().unwrap();
+        assert!(settings.expected_ports.is_none());
+    }
+
+    #[test]
+    fn test_network_baseline() {
+        let db = Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
+        assert!(set_network_baseline(&conn).is_err());
+        assert_eq!(get_network_baseline(&conn).unwrap(), None);
+
+        let mut nas = HostInfo::new(
+            "192.168.1.30".to_string(),
+            "AA:BB:CC:DD:EE:03".to_string(),
+            "NAS".to_string(),
+            "ARP".to_string(),
+        );
+        nas.open_ports = vec![22, 445];
+        let mut result = ScanResult {
+            interface_name: "eth0".to_string(),
+            local_ip: "192.168.1.1".to_string(),
+            local_mac: "AA:BB:CC:DD:EE:FF".to_string(),
+            subnet: "192.168.1.0/24".to_string(),
+            scan_method: "arp".to_string(),
+            arp_discovered: 1,
+            icmp_discovered: 0,
+            total_hosts: 1,
+            scan_duration_ms: 100,
+            active_hosts: vec![nas],
+            wan: None,
+            ssid: None,
+        };
+        insert_scan(&conn, &result).unwrap();
+
+        let baseline = set_network_baseline(&conn).unwrap();
+        assert_eq!(baseline.devices.len(), 1);
+        assert_eq!(baseline.devices[0].open_ports, vec![22, 445]);
+        assert_eq!(get_network_baseline(&conn).unwrap(), Some(baseline));
+
+        // Later scans do not change it until it is taken again
+        result.active_hosts[0].open_ports = vec![22];
+        insert_scan(&conn, &result).unwrap();
+        let baseline = get_network_baseline(&conn).unwrap().unwrap();
+        assert_eq!(baseline.devices[0].open_ports, vec![22, 445]);
+        assert_eq!(
+            set_network_baseline(&conn).unwrap().devices[0].open_ports,
+            vec![22]
+        );
+
+        assert!(clear_network_baseline(&conn).unwrap());
+        assert!(!clear_network_baseline(&conn).unwrap());
+        assert_eq!(get_network_baseline(&conn).unwrap(), None);
+    }
+
+    #[test]
+    fn test_filtered_state_roundtrip() {
+        let db = Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
+
+        let mut host = HostInfo::new(
+            "192.168.1.40".to_string(),
+            "AA:BB:CC:DD:EE:04".to_string(),
+            "PC".to_string(),
+            "ARP+TCP".to_string(),
+        );
+        host.open_ports = vec![443];
+        host.filtered_ports = vec![22, 3389];
+        host.host_state = Some(HostState::Filtered);
+        let result = ScanResult {
+            interface_name: "eth0".to_string(),
+            local_ip: "192.168.1.1".to_string(),
+            local_mac: "AA:BB:CC:DD:EE:FF".to_string(),
+            subnet: "192.168.1.0/24".to_string(),
+            scan_method: "arp".to_string(),
+            arp_discovered: 1,
+            icmp_discovered: 0,
+            total_hosts: 1,
+            scan_duration_ms: 100,
+            active_hosts: vec![host],
+            wan: None,
+            ssid: None,
+        };
+        insert_scan(&conn, &result).unwrap();
+
+        let hosts = get_latest_scan_hosts(&conn).unwrap();
+        assert_eq!(hosts[0].filtered_ports, vec![22, 3389]);
+        assert_eq!(hosts[0].host_state, Some(HostState::Filtered));
+
+        let device = get_device_by_mac(&conn, "AA:BB:CC:DD:EE:04")
+            .unwrap()
+            .unwrap();
+        let history = get_device_history(&conn, device.id, 1).unwrap();
+        assert_eq!(history[0].open_ports, vec![443]);
+        assert_eq!(history[0].filtered_ports, vec![22, 3389]);
+        assert_eq!(history[0].host_state, Some(HostState::Filtered));
+    }
+
+    #[test]
+    fn test_risk_acceptance_expiry_raises_review_alert() {
+        let db = Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
+
+        let mac = "AA:BB:CC:DD:EE:06";
+        let next_month = Utc::now() + chrono::Duration::days(30);
+        assert!(accept_risk(&conn, mac, RiskFinding::RiskScore, " ", None, next_month).is_err());
+        let yesterday = Utc::now() - chrono::Duration::days(1);
+        assert!(accept_risk(
+            &conn,
+            mac,
+            RiskFinding::RiskScore,
+            "Legacy",
+            None,
+            yesterday
+        )
+        .is_err());
+
+        let id = accept_risk(
+            &conn,
+            mac,
+            RiskFinding::RiskScore,
+            "Legacy NAS on isolated VLAN",
+            Some("alice"),
+            next_month,
+        )
+        .unwrap();
+        assert!(alert_expired_risk_acceptances(&conn).unwrap().is_empty());
+
+        conn.execute(
+            "UPDATE risk_acceptances SET expires_at = datetime('now', '-1 minute') WHERE id = ?1",
+            params![id],
+        )
+        .unwrap();
+        let expired = alert_expired_risk_acceptances(&conn).unwrap();
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].finding, RiskFinding::RiskScore);
+        // Only one review alert per expiry
+        assert!(alert_expired_risk_acceptances(&conn).unwrap().is_empty());
+
+        let alerts = get_unread_alerts(&conn).unwrap();
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].alert_type, AlertType::RiskReview);
+        assert_eq!(alerts[0].device_mac.as_deref(), Some(mac));
+
+        // Re-accepting after review restarts the period under the same ID
+        let again = accept_risk(
+            &conn,
+            mac,
+            RiskFinding::RiskScore,
+            "Reviewed",
+            None,
+            next_month,
+        )
+        .unwrap();
+        assert_eq!(again, id);
+        let acceptances = get_risk_acceptances(&conn).unwrap();
+        assert!(!acceptances[0].review_alerted);
+        assert!(revoke_risk_acceptance(&conn, id).unwrap());
+        assert!(get_risk_acceptances(&conn).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_port_exposure_report() {
+        let db = Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
+
+        let scan = |subnet: &str, ip: &str, ports: Vec<u16>| {
+            let mut host = HostInfo::new(
+                ip.to_string(),
+                "AA:BB:CC:DD:EE:05".to_string(),
+                "PC".to_string(),
+                "ARP+TCP".to_string(),
+            );
+            host.open_ports = ports;
+            ScanResult {
+                interface_name: "eth0".to_string(),
+                local_ip: "192.168.1.1".to_string(),
+                local_mac: "AA:BB:CC:DD:EE:FF".to_string(),
+                subnet: subnet.to_string(),
+                scan_method: "arp".to_string(),
+                arp_discovered: 1,
+                icmp_discovered: 0,
+                total_hosts: 1,
+                scan_duration_ms: 100,
+                active_hosts: vec![host],
+                wan: None,
+                ssid: None,
+            }
+        };
+        insert_scan(
+            &conn,
+            &scan("192.168.1.0/24", "192.168.1.50", vec![80, 3389]),
+        )
+        .unwrap();
+        insert_scan(
+            &conn,
+            &scan("192.168.1.0/24", "192.168.1.51", vec![445, 3389]),
+        )
+        .unwrap();
+        insert_scan(&conn, &scan("10.0.0.0/24", "10.0.0.50", vec![22])).unwrap();
+
+        let sensitive = crate::config::SENSITIVE_PORTS;
+        let report =
+            get_port_exposure(&conn, sensitive, Some("192.168.1.0/24"), None, None).unwrap();
+        let summary: Vec<(u16, u32, &str)> = report
+            .iter()
+            .map(|r| (r.port, r.scan_count, r.last_ip.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![(445, 1, "192.168.1.51"), (3389, 2, "192.168.1.51")]
+        );
+        assert!(report[1].first_observed <= report[1].last_observed);
+
+        // Nothing sensitive on the other network, and nothing in the future
+        assert!(
+            get_port_exposure(&conn, sensitive, Some("10.0.0.0/24"), None, None)
+                .unwrap()
+                .is_empty()
+        );
+        let tomorrow = Utc::now() + chrono::Duration::days(1);
+        assert!(
+            get_port_exposure(&conn, sensitive, None, Some(tomorrow), None)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_device_ports_track_first_and_last_seen() {
+        let db = Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
+
+        let scan = |ports: Vec<u16>| {
+            let mut host = HostInfo::new(
+                "192.168.1.60".to_string(),
+                "AA:BB:CC:DD:EE:06".to_string(),
+                "PC".to_string(),
+                "ARP+TCP".to_string(),
+            );
+            host.port_warnings = lookup_port_warnings(&conn, &ports).unwrap();
+            host.open_ports = ports;
+            ScanResult {
+                interface_name: "eth0".to_string(),
+                local_ip: "192.168.1.1".to_string(),
+                local_mac: "AA:BB:CC:DD:EE:FF".to_string(),
+                subnet: "192.168.1.0/24".to_string(),
+                scan_method: "arp".to_string(),
+                arp_discovered: 1,
+                icmp_discovered: 0,
+                total_hosts: 1,
+                scan_duration_ms: 100,
+                active_hosts: vec![host],
+                wan: None,
+                ssid: None,
+            }
+        };
+        insert_scan(&conn, &scan(vec![22, 23])).unwrap();
+        insert_scan(&conn, &scan(vec![23, 443])).unwrap();
+
+        let ports = get_device_ports(&conn, "AA:BB:CC:DD:EE:06").unwrap();
+        let summary: Vec<(u16, bool)> = ports.iter().map(|p| (p.port, p.is_open)).collect();
+        assert_eq!(summary, vec![(22, false), (23, true), (443, true)]);
+        assert!(ports.iter().all(|p| p.protocol == "tcp"));
+        assert!(ports[1].first_seen <= ports[1].last_seen);
+        assert!(
+            ports[1].service.is_some(),
+            "Telnet is named from port warnings"
+        );
+
+        assert!(set_device_port_banner(
+            &conn,
+            "AA:BB:CC:DD:EE:06",
+            22,
+            "tcp",
+            "SSH-2.0-OpenSSH_9.6"
+        )
+        .unwrap());
+        assert!(!set_device_port_banner(&conn, "AA:BB:CC:DD:EE:06", 8080, "tcp", "x").unwrap());
+        let with_ssh = get_devices_with_port(&conn, 22, "tcp").unwrap();
+        assert_eq!(with_ssh.len(), 1);
+        assert_eq!(with_ssh[0].banner.as_deref(), Some("SSH-2.0-OpenSSH_9.6"));
+        assert!(get_devices_with_port(&conn, 22, "udp").unwrap().is_empty());
+        assert!(get_device_ports(&conn, "00:00:00:00:00:00")
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_port_changes_between_scans() {
+        let db = Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
+
+        let scan = |ports: Vec<u16>| {
+            let mut host = HostInfo::new(
+                "192.168.1.61".to_string(),
+                "AA:BB:CC:DD:EE:07".to_string(),
+                "PC".to_string(),
+                "ARP+TCP".to_string(),
+            );
+            host.open_ports = ports;
+            ScanResult {
+                interface_name: "eth0".to_string(),
+                local_ip: "192.168.1.1".to_string(),
+                local_mac: "AA:BB:CC:DD:EE:FF".to_string(),
+                subnet: "192.168.1.0/24".to_string(),
+                scan_method: "arp".to_string(),
+                arp_discovered: 1,
+                icmp_discovered: 0,
+                total_hosts: 1,
+                scan_duration_ms: 100,
+                active_hosts: vec![host],
+                wan: None,
+                ssid: None,
+            }
+        };
+        let since = Utc::now() - chrono::Duration::minutes(1);
+        insert_scan(&conn, &scan(vec![22, 23])).unwrap();
+        insert_scan(&conn, &scan(vec![23, 443])).unwrap();
+        let last = insert_scan(&conn, &scan(vec![22, 443])).unwrap();
+
+        let changes = get_device_port_changes(&conn, "AA:BB:CC:DD:EE:07").unwrap();
+        let timeline: Vec<(u16, bool)> = changes.iter().map(|c| (c.port, c.opened)).collect();
+        assert_eq!(
+            timeline,
+            vec![
+                (22, true),
+                (23, true),
+                (443, true),
+                (22, false),
+                (22, true),
+                (23, false),
+            ]
+        );
+        assert_eq!(changes[5].scan_id, Some(last));
+
+        // Reopening 22 does not list it twice
+        let mut new_ports: Vec<u16> = get_new_ports(&conn, 1)
+            .unwrap()
+            .iter()
+            .map(|p| p.port)
+            .collect();
+        new_ports.sort_unstable();
+        assert_eq!(new_ports, vec![22, 23, 443]);
+        assert_eq!(get_port_changes_since(&conn, since).unwrap().len(), 6);
+        assert!(
+            get_port_changes_since(&conn, Utc::now() + chrono::Duration::minutes(1))
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_topology_edges_outlive_the_scan_that_found_them() {
+        let db = Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
+
+        let scan = |with_neighbor: bool| {
+            let mut switch = HostInfo::new(
+                "192.168.1.2".to_string(),
+                "AA:BB:CC:DD:EE:07".to_string(),
+                "Switch".to_string(),
+                "ARP".to_string(),
+            );
+            if with_neighbor {
+                switch.neighbors.push(crate::models::NeighborInfo {
+                    local_port: "Gi0/1".to_string(),
+                    remote_device: "ap-lobby".to_string(),
+                    remote_port: "eth0".to_string(),
+                    remote_ip: Some("192.168.1.3".to_string()),
+                });
+            }
+            let ap = HostInfo::new(
+                "192.168.1.3".to_string(),
+                "AA:BB:CC:DD:EE:08".to_string(),
+                "Access Point".to_string(),
+                "ARP".to_string(),
+            );
+            ScanResult {
+                interface_name: "eth0".to_string(),
+                local_ip: "192.168.1.1".to_string(),
+                local_mac: "AA:BB:CC:DD:EE:FF".to_string(),
+                subnet: "192.168.1.0/24".to_string(),
+                scan_method: "arp".to_string(),
+                arp_discovered: 2,
+                icmp_discovered: 0,
+                total_hosts: 2,
+                scan_duration_ms: 100,
+                active_hosts: vec![switch, ap],
+                wan: None,
+                ssid: None,
+            }
+        };
+        insert_scan(&conn, &scan(true)).unwrap();
+        insert_scan(&conn, &scan(true)).unwrap();
+
+        let edges = get_topology_edges(&conn, None).unwrap();
+        assert_eq!(edges.len(), 1, "repeat sightings update one edge");
+        assert_eq!(edges[0].local_device_mac, "AA:BB:CC:DD:EE:07");
+        assert_eq!(
+            edges[0].remote_device_mac.as_deref(),
+            Some("AA:BB:CC:DD:EE:08")
+        );
+        assert_eq!(edges[0].source, "lldp");
+        assert!(edges[0].is_current);
+
+        insert_scan(&conn, &scan(false)).unwrap();
+        let edges = get_device_topology_edges(&conn, "AA:BB:CC:DD:EE:07").unwrap();
+        assert_eq!(edges.len(), 1);
+        assert!(!edges[0].is_current);
+
+        let tomorrow = Utc::now() + chrono::Duration::days(1);
+        assert!(get_topology_edges(&conn, Some(tomorrow))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_device_availability_counts_scans_since_first_seen() {
+        let db = Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
+
+        let scan = |subnet: &str, macs: &[&str]| {
+            let hosts = macs
+                .iter()
+                .enumerate()
+                .map(|(i, mac)| {
+                    HostInfo::new(
+                        format!("192.168.1.{}", 100 + i),
+                        mac.to_string(),
+                        "PC".to_string(),
+                        "ARP".to_string(),
+                    )
+                })
+                .collect::<Vec<_>>();
+            ScanResult {
+                interface_name: "eth0".to_string(),
+                local_ip: "192.168.1.1".to_string(),
+                local_mac: "AA:BB:CC:DD:EE:FF".to_string(),
+                subnet: subnet.to_string(),
+                scan_method: "arp".to_string(),
+                arp_discovered: hosts.len(),
+                icmp_discovered: 0,
+                total_hosts: hosts.len(),
+                scan_duration_ms: 100,
+                active_hosts: hosts,
+                wan: None,
+                ssid: None,
+            }
+        };
+        let a = "AA:BB:CC:DD:EE:11";
+        let b = "AA:BB:CC:DD:EE:12";
+        let c = "AA:BB:CC:DD:EE:13";
+        let lan = "192.168.1.0/24";
+        for (minutes_ago, macs) in [
+            (120, vec![a]),
+            (90, vec![a, b]),
+            (60, vec![b]),
+            (30, vec![a, b]),
+        ] {
+            let id = insert_scan(&conn, &scan(lan, &macs)).unwrap();
+            conn.execute(
+                "UPDATE scans SET scan_time = datetime('now', ?2) WHERE id = ?1",
+                params![id, format!("-{} minutes", minutes_ago)],
+            )
+            .unwrap();
+        }
+        insert_scan(&conn, &scan("10.0.0.0/24", &[c])).unwrap();
+        conn.execute(
+            r#"
+            UPDATE devices SET first_seen = (
+                SELECT MIN(s.scan_time) FROM device_history h
+                JOIN scans s ON s.id = h.scan_id WHERE h.device_id = devices.id
+            )
+            "#,
+            [],
+        )
+        .unwrap();
+
+        let windows = get_device_availability(&conn, a).unwrap();
+        assert_eq!(
+            windows.iter().map(|w| w.hours).collect::<Vec<_>>(),
+            vec![24, 168, 720]
+        );
+        assert_eq!((windows[0].seen, windows[0].scans), (3, 4));
+        assert_eq!(windows[0].percent, Some(75.0));
+
+        // Scans before a device appeared do not count against it
+        let devices = get_all_devices(&conn).unwrap();
+        let availability = |mac: &str| {
+            devices
+                .iter()
+                .find(|device| device.mac == mac)
+                .unwrap()
+                .availability
+        };
+        assert_eq!(availability(b).last_24h, Some(100.0));
+        assert_eq!(availability(c).last_30d, Some(100.0));
+        let device = get_device_by_mac(&conn, a).unwrap().unwrap();
+        assert_eq!(device.availability.last_7d, Some(75.0));
+        assert!(get_device_availability(&conn, "00:00:00:00:00:00")
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_finding_history_tracks_first_seen_and_remediation() {
+        let db = Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
+
+        let scan = |ports: Vec<u16>| {
+            let mut host = HostInfo::new(
+                "192.168.1.70".to_string(),
+                "AA:BB:CC:DD:EE:09".to_string(),
+                "Camera".to_string(),
+                "ARP+TCP".to_string(),
+            );
+            host.vulnerabilities.push(crate::models::VulnerabilityInfo {
+                cve_id: "CVE-2021-36260".to_string(),
+                description: "Command injection in web server".to_string(),
+                severity: "CRITICAL".to_string(),
+                cvss_score: Some(9.8),
+            });
+            host.port_warnings = lookup_port_warnings(&conn, &ports).unwrap();
+            host.open_ports = ports;
+            ScanResult {
+                interface_name: "eth0".to_string(),
+                local_ip: "192.168.1.1".to_string(),
+                local_mac: "AA:BB:CC:DD:EE:FF".to_string(),
+                subnet: "192.168.1.0/24".to_string(),
+                scan_method: "arp".to_string(),
+                arp_discovered: 1,
+                icmp_discovered: 0,
+                total_hosts: 1,
+                scan_duration_ms: 100,
+                active_hosts: vec![host],
+                wan: None,
+                ssid: None,
+            }
+        };
+        insert_scan(&conn, &scan(vec![23])).unwrap();
+        insert_scan(&conn, &scan(vec![])).unwrap();
+
+        let history = get_finding_history(&conn, Some("AA:BB:CC:DD:EE:09")).unwrap();
+        let cve = history
+            .iter()
+            .find(|f| f.kind == FindingKind::Vulnerability)
+            .unwrap();
+        assert_eq!(cve.key, "CVE-2021-36260");
+        assert_eq!(cve.scan_count, 2);
+        assert_eq!(cve.remediated_at, None);
+
+        let telnet = history
+            .iter()
+            .find(|f| f.kind == FindingKind::PortWarning)
+            .unwrap();
+        assert_eq!(telnet.key, "23");
+        assert_eq!(telnet.scan_count, 1);
+        assert!(telnet.remediated_at.is_some());
+        assert!(telnet.first_seen <= telnet.remediated_at.unwrap());
+
+        assert_eq!(get_finding_history(&conn, None).unwrap().len(), 2);
+        assert!(get_finding_history(&conn, Some("00:00:00:00:00:00"))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_tool_runs_filter_by_tool_and_target() {
+        let db = Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
+
+        let ping = serde_json::json!([{ "success": true, "latency_ms": 1.5 }]);
+        let first = insert_tool_run(
+            &conn,
+            "ping",
+            "192.168.1.1",
+            &serde_json::json!({ "count": 1 }),
+            &ping,
+            Some(500),
+        )
+        .unwrap();
+        let second = insert_tool_run(
+            &conn,
+            "ping",
+            "192.168.1.2",
+            &serde_json::json!({}),
+            &ping,
+            None,
+        )
+        .unwrap();
+        insert_tool_run(
+            &conn,
+            "port_scan",
+            "192.168.1.1",
+            &serde_json::json!({ "ports": [22] }),
+            &serde_json::json!([]),
+            None,
+        )
+        .unwrap();
+
+        let pings = get_tool_runs(&conn, Some("ping"), None, 10).unwrap();
+        assert_eq!(
+            pings.iter().map(|run| run.id).collect::<Vec<_>>(),
+            vec![second, first]
+        );
+        let router = get_tool_runs(&conn, Some("ping"), Some("192.168.1.1"), 10).unwrap();
+        assert_eq!(router.len(), 1);
+        assert_eq!(router[0].parameters["count"], 1);
+        assert_eq!(router[0].results, ping);
+        assert_eq!(router[0].duration_ms, Some(500));
+        assert_eq!(get_tool_runs(&conn, None, None, 2).unwrap().len(), 2);
+
+        assert!(delete_tool_run(&conn, first).unwrap());
+        assert!(!delete_tool_run(&conn, first).unwrap());
+    }
+
+    #[test]
+    fn test_json_setting_roundtrip() {
+        let db = Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
+
+        let thresholds = crate::scanner::ArpThresholds {
+            storm_request_threshold: 25,
+            ..Default::default()
+        };
+        assert!(
+            get_json_setting::<crate::scanner::ArpThresholds>(&conn, SETTING_ARP_THRESHOLDS)
+                .unwrap()
+                .is_none()
+        );
+
+        set_json_setting(&conn, SETTING_ARP_THRESHOLDS, &thresholds).unwrap();
+        let loaded: Option<crate::scanner::ArpThresholds> =
+            get_json_setting(&conn, SETTING_ARP_THRESHOLDS).unwrap();
+        assert_eq!(loaded, Some(thresholds));
+
+        set_setting(&conn, SETTING_ARP_THRESHOLDS, "not json").unwrap();
+        assert!(
+            get_json_setting::<crate::scanner::ArpThresholds>(&conn, SETTING_ARP_THRESHOLDS)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_alert_evidence_roundtrip() {
+        let db = Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
+
+        let evidence = serde_json::json!({
+            "mac": "aa:00:00:00:00:07",
+            "ips": ["192.168.1.30", "192.168.1.31"],
+        });
+        for evidence in [Some(&evidence), None] {
+            insert_alert_with_dedupe_key(
+                &conn,
+                &AlertInsert {
+                    alert_type: AlertType::MacCloned,
+                    device_id: None,
+                    device_mac: Some("aa:00:00:00:00:07"),
+                    device_ip: Some("192.168.1.30"),
+                    dedupe_key: None,
+                    message: "cloned",
+                    severity: AlertSeverity::Error,
+                    evidence,
+                },
+            )
+            .unwrap();
+        }
 
-/// Get network statistics
-pub fn get_network_stats(conn: &Connection) -> Result<NetworkStats> {
-    let total_devices: i64 =
-        conn.query_row("SELECT COUNT(*) FROM devices", [], |row| row.get(0))?;
+        let alerts = get_recent_alerts(&conn, 10).unwrap();
+        assert_eq!(alerts[0].evidence, None);
+        assert_eq!(alerts[1].alert_type, AlertType::MacCloned);
+        assert_eq!(alerts[1].evidence.as_ref(), Some(&evidence));
+    }
 
-    // Devices seen in last scan (online)
-    let online_devices: i64 = conn
-        .query_row(
-            r#"
-        SELECT COUNT(DISTINCT device_id) FROM device_history
-        WHERE scan_id = (SELECT MAX(id) FROM scans)
-        "#,
-            [],
-            |row| row.get(0),
-        )
-        .unwrap_or(0);
+    #[test]
+    fn test_repeated_alert_counted_on_existing() {
+        let db = Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
 
-    let offline_devices = total_devices - online_devices;
+        let alert = |ip| AlertInsert {
+            alert_type: AlertType::NewDevice,
+            device_id: None,
+            device_mac: Some("aa:00:00:00:00:08"),
+            device_ip: Some(ip),
+            dedupe_key: None,
+            message: "New device",
+            severity: AlertSeverity::Info,
+            evidence: None,
+        };
+        let key = "new-device:aa:00:00:00:00:08";
+        let id = insert_alert_if_not_exists(&conn, &alert("192.168.1.40"), key, 60)
+            .unwrap()
+            .unwrap();
+        for _ in 0..2 {
+            assert_eq!(
+                insert_alert_if_not_exists(&conn, &alert("192.168.1.41"), key, 60).unwrap(),
+                None
+            );
+        }
 
-    // New devices in last 24 hours
-    let new_devices_24h: i64 = conn.query_row(
-        r#"
-        SELECT COUNT(*) FROM devices
-        WHERE first_seen >= datetime('now', '-24 hours')
-        "#,
-        [],
-        |row| row.get(0),
-    )?;
+        let alerts = get_unread_alerts(&conn).unwrap();
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].id, id);
+        assert_eq!(alerts[0].occurrences, 3);
+        assert!(alerts[0].last_seen_at.is_some());
+        assert_eq!(alerts[0].device_ip.as_deref(), Some("192.168.1.41"));
 
-    // High risk devices (risk_score > 70)
-    let high_risk_devices: i64 = conn
-        .query_row(
-            r#"
-        SELECT COUNT(DISTINCT device_id) FROM device_history
-        WHERE scan_id = (SELECT MAX(id) FROM scans) AND risk_score > 70
-        "#,
-            [],
-            |row| row.get(0),
-        )
-        .unwrap_or(0);
+        // Once the alert was read, a repeat is stored again
+        mark_alert_read(&conn, id).unwrap();
+        assert!(
+            insert_alert_if_not_exists(&conn, &alert("192.168.1.41"), key, 60)
+                .unwrap()
+                .is_some()
+        );
+        assert_eq!(get_unread_alerts(&conn).unwrap()[0].occurrences, 1);
+    }
 
-    let total_scans: i64 = conn.query_row("SELECT COUNT(*) FROM scans", [], |row| row.get(0))?;
+    #[test]
+    fn test_unknown_alert_type_keeps_raw_value() {
+        let db = Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
 
-    let last_scan_time_raw: Option<String> = conn
-        .query_row(
-            "SELECT scan_time FROM scans ORDER BY id DESC LIMIT 1",
+        conn.execute(
+            "INSERT INTO alerts (alert_type, message, severity) VALUES ('future_kind', 'x', 'info')",
             [],
-            |row| row.get::<_, String>(0),
         )
-        .optional()?;
+        .unwrap();
+        insert_alert_with_dedupe_key(
+            &conn,
+            &AlertInsert {
+                alert_type: AlertType::GatewayMacChanged,
+                device_id: None,
+                device_mac: Some("aa:00:00:00:00:08"),
+                device_ip: Some("192.168.1.1"),
+                dedupe_key: None,
+                message: "gateway",
+                severity: AlertSeverity::Critical,
+                evidence: None,
+            },
+        )
+        .unwrap();
 
-    let last_scan_time = match last_scan_time_raw {
-        Some(raw) => Some(parse_datetime(raw)?),
-        None => None,
-    };
+        let alerts = get_recent_alerts(&conn, 10).unwrap();
+        assert_eq!(alerts[0].alert_type, AlertType::GatewayMacChanged);
+        assert_eq!(alerts[0].alert_type_raw, "gateway_mac_changed");
+        assert_eq!(alerts[1].alert_type, AlertType::Custom);
+        assert_eq!(alerts[1].alert_type_raw, "future_kind");
 
-    Ok(NetworkStats {
-        total_devices,
-        online_devices,
-        offline_devices,
-        new_devices_24h,
-        high_risk_devices,
-        total_scans,
-        last_scan_time,
-    })
-}
+        let parsed: AlertType = serde_json::from_str("\"future_kind\"").unwrap();
+        assert_eq!(parsed, AlertType::Custom);
+    }
 
-/// Helper: Parse SQLite datetime string to chrono DateTime
-fn parse_datetime(s: String) -> Result<DateTime<Utc>> {
-    DateTime::parse_from_str(&format!("{} +0000", s), "%Y-%m-%d %H:%M:%S %z")
-        .map(|dt| dt.with_timezone(&Utc))
-        .with_context(|| format!("Invalid datetime value in database: {}", s))
-}
+    #[test]
+    fn test_smart_group_crud() {
+        let db = Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
 
-fn parse_datetime_column(s: String, column: usize) -> rusqlite::Result<DateTime<Utc>> {
-    DateTime::parse_from_str(&format!("{} +0000", s), "%Y-%m-%d %H:%M:%S %z")
-        .map(|dt| dt.with_timezone(&Utc))
-        .map_err(|e| {
-        rusqlite::Error::FromSqlConversionFailure(column, rusqlite::types::Type::Text, Box::new(e))
-    })
-}
+        let id = create_smart_group(&conn, "Risky IoT", "type = IoT AND risk > 50", None).unwrap();
+        assert!(create_smart_group(&conn, "Risky IoT", "risk > 10", None).is_err());
 
-fn parse_alert_type_or_default(s: &str) -> AlertType {
-    match s.parse() {
-        Ok(value) => value,
-        Err(_) => {
-            tracing::warn!("Unknown alert type in database: {}", s);
-            AlertType::Custom
-        }
-    }
-}
+        assert!(update_smart_group(&conn, id, "Risky IoT", "risk > 70", Some("High risk")).unwrap());
+        let group = get_smart_group(&conn, id).unwrap().unwrap();
+        assert_eq!(group.expression, "risk > 70");
+        assert_eq!(group.description.as_deref(), Some("High risk"));
+        assert_eq!(get_smart_groups(&conn).unwrap().len(), 1);
 
-fn parse_alert_severity_or_default(s: &str) -> AlertSeverity {
-    match s.parse() {
-        Ok(value) => value,
-        Err(_) => {
-            tracing::warn!("Unknown alert severity in database: {}", s);
-            AlertSeverity::Info
-        }
+        assert!(delete_smart_group(&conn, id).unwrap());
+        assert!(get_smart_group(&conn, id).unwrap().is_none());
     }
-}
 
-/// Lookup vulnerabilities for a vendor from CVE cache
-pub fn lookup_vulnerabilities(
-    conn: &Connection,
-    vendor: &str,
-) -> Result<Vec<crate::models::VulnerabilityInfo>> {
-    let mut stmt = conn.prepare(
-        r#"
-        SELECT cve_id, description, severity, cvss_score
-        FROM cve_cache
-        WHERE LOWER(vendor) = LOWER(?1) OR vendor = '*'
-        ORDER BY cvss_score DESC NULLS LAST
-        "#,
-    )?;
+    #[test]
+    fn test_device_group_crud() {
+        use crate::groups::OnlineSchedule;
 
-    let vulns = stmt
-        .query_map(params![vendor], |row| {
-            Ok(crate::models::VulnerabilityInfo {
-                cve_id: row.get(0)?,
-                description: row.get(1)?,
-                severity: row.get(2)?,
-                cvss_score: row.get(3)?,
-            })
-        })?
-        .collect::<rusqlite::Result<Vec<_>>>()?;
+        let db = Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
 
-    Ok(vulns)
-}
+        let host = HostInfo::new(
+            "192.168.1.40".to_string(),
+            "AA:BB:CC:DD:EE:40".to_string(),
+            "SERVER".to_string(),
+            "ARP".to_string(),
+        );
+        insert_scan(
+            &conn,
+            &ScanResult {
+                interface_name: "eth0".to_string(),
+                local_ip: "192.168.1.1".to_string(),
+                local_mac: "AA:BB:CC:DD:EE:FF".to_string(),
+                subnet: "192.168.1.0/24".to_string(),
+                scan_method: "arp".to_string(),
+                arp_discovered: 1,
+                icmp_discovered: 0,
+                total_hosts: 1,
+                scan_duration_ms: 100,
+                active_hosts: vec![host],
+                wan: None,
+                ssid: None,
+            },
+        )
+        .unwrap();
 
-/// Lookup port warnings for given ports
-pub fn lookup_port_warnings(
-    conn: &Connection,
-    ports: &[u16],
-) -> Result<Vec<crate::models::PortWarning>> {
-    if ports.is_empty() {
-        return Ok(Vec::new());
-    }
+        let policy = GroupPolicy {
+            expected_online: Some(OnlineSchedule::Always),
+            ..Default::default()
+        };
+        let id = create_device_group(&conn, " Servers ", None, &policy).unwrap();
+        assert!(create_device_group(&conn, "servers", None, &policy).is_err());
+        assert!(create_device_group(&conn, " ", None, &policy).is_err());
 
-    let placeholders = ports.iter().map(|_| "?").collect::<Vec<_>>().join(",");
-    let query = format!(
-        "SELECT port, service, warning, severity, recommendation FROM port_warnings WHERE port IN ({})",
-        placeholders
-    );
+        let mut invalid = policy.clone();
+        invalid
+            .severity_overrides
+            .insert("NOT_A_TYPE".to_string(), crate::alerts::AlertSeverity::High);
+        assert!(update_device_group(&conn, id, "Servers", None, &invalid).is_err());
 
-    let mut stmt = conn.prepare(&query)?;
-    let params: Vec<&dyn rusqlite::ToSql> =
-        ports.iter().map(|p| p as &dyn rusqlite::ToSql).collect();
+        assert!(add_device_to_group(&conn, id, "AA:BB:CC:DD:EE:40").unwrap());
+        assert!(!add_device_to_group(&conn, id, "00:00:00:00:00:00").unwrap());
+        assert!(!add_device_to_group(&conn, id + 1, "AA:BB:CC:DD:EE:40").unwrap());
 
-    let warnings = stmt
-        .query_map(params.as_slice(), |row| {
-            Ok(crate::models::PortWarning {
-                port: row.get::<_, i64>(0)? as u16,
-                service: row.get(1)?,
-                warning: row.get(2)?,
-                severity: row.get(3)?,
-                recommendation: row.get(4)?,
-            })
-        })?
-        .collect::<rusqlite::Result<Vec<_>>>()?;
+        let group = get_device_group(&conn, id).unwrap().unwrap();
+        assert_eq!(group.name, "Servers");
+        assert_eq!(group.policy, policy);
+        assert_eq!(group.members, vec!["AA:BB:CC:DD:EE:40".to_string()]);
 
-    Ok(warnings)
-}
+        assert!(remove_device_from_group(&conn, id, "AA:BB:CC:DD:EE:40").unwrap());
+        assert!(get_device_groups(&conn).unwrap()[0].members.is_empty());
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::database::Database;
+        assert!(delete_device_group(&conn, id).unwrap());
+        assert!(get_device_group(&conn, id).unwrap().is_none());
+    }
 
     #[test]
-    fn test_insert_and_get_scan() {
+    fn test_record_sni_observation_upserts() {
         let db = Database::in_memory().unwrap();
-        let conn = db.connection();
-        let conn = conn.lock().unwrap();
+        let conn = db.writer().unwrap();
 
-        let result = ScanResult {
+        let mac = "aa:bb:cc:dd:ee:01";
+        record_sni_observation(&conn, mac, "a1.tuyaeu.com", Some("3.120.1.1")).unwrap();
+        record_sni_observation(&conn, mac, "a1.tuyaeu.com", Some("3.120.1.2")).unwrap();
+        record_sni_observation(&conn, mac, "time.google.com", None).unwrap();
+
+        let records = get_device_sni(&conn, mac).unwrap();
+        assert_eq!(records.len(), 2);
+
+        let tuya = records
+            .iter()
+            .find(|r| r.server_name == "a1.tuyaeu.com")
+            .unwrap();
+        assert_eq!(tuya.hit_count, 2);
+        assert_eq!(tuya.last_server_ip.as_deref(), Some("3.120.1.2"));
+        assert_eq!(get_all_sni(&conn).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_device_services_surface_in_details() {
+        let db = Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
+
+        let mut host = HostInfo::new(
+            "192.168.1.40".to_string(),
+            "aa:bb:cc:dd:ee:40".to_string(),
+            "UNKNOWN".to_string(),
+            "ARP".to_string(),
+        );
+        host.hostname = Some("office-printer".to_string());
+        let scan = ScanResult {
             interface_name: "eth0".to_string(),
-            local_ip: "192.168.1.1".to_string(),
-            local_mac: "AA:BB:CC:DD:EE:FF".to_string(),
+            local_ip: "192.168.1.2".to_string(),
+            local_mac: "aa:bb:cc:dd:ee:02".to_string(),
             subnet: "192.168.1.0/24".to_string(),
-            scan_method: "arp+icmp".to_string(),
-            arp_discovered: 5,
-            icmp_discovered: 3,
-            total_hosts: 5,
-            scan_duration_ms: 1500,
-            active_hosts: vec![],
+            scan_method: "arp".to_string(),
+            arp_discovered: 1,
+            icmp_discovered: 0,
+            total_hosts: 1,
+            scan_duration_ms: 10,
+            active_hosts: vec![host],
+            wan: None,
+            ssid: None,
         };
+        insert_scan(&conn, &scan).unwrap();
 
-        let scan_id = insert_scan(&conn, &result).unwrap();
-        assert!(scan_id > 0);
+        let mac = find_device_mac_by_ip(&conn, "192.168.1.40")
+            .unwrap()
+            .unwrap();
+        assert_eq!(mac, "aa:bb:cc:dd:ee:40");
+        assert!(find_device_mac_by_ip(&conn, "192.168.1.41")
+            .unwrap()
+            .is_none());
 
-        let scans = get_recent_scans(&conn, 10).unwrap();
-        assert_eq!(scans.len(), 1);
-        assert_eq!(scans[0].interface_name, "eth0");
+        record_device_service(&conn, &mac, "_ipp._tcp", Some("Office Printer"), Some(631)).unwrap();
+        record_device_service(&conn, &mac, "_ipp._tcp", Some("Office Printer"), None).unwrap();
+        record_device_service(&conn, &mac, "_http._tcp", None, Some(80)).unwrap();
+
+        let details = get_device_details(&conn, &mac).unwrap().unwrap();
+        assert_eq!(details.device.mac, mac);
+        assert_eq!(details.services.len(), 2);
+        let ipp = details
+            .services
+            .iter()
+            .find(|s| s.service_type == "_ipp._tcp")
+            .unwrap();
+        assert_eq!(ipp.port, Some(631));
+        assert_eq!(ipp.instance_name, "Office Printer");
+
+        let by_device = get_service_types_by_device(&conn).unwrap();
+        assert_eq!(
+            by_device.get(&mac),
+            Some(&vec!["_http._tcp".to_string(), "_ipp._tcp".to_string()])
+        );
+        assert!(get_device_details(&conn, "aa:bb:cc:dd:ee:99")
+            .unwrap()
+            .is_none());
     }
 
     #[test]
-    fn test_network_stats() {
+    fn test_purge_device_data_requires_token() {
         let db = Database::in_memory().unwrap();
-        let conn = db.connection();
-        let conn = conn.lock().unwrap();
+        let conn = db.writer().unwrap();
 
-        let stats = get_network_stats(&conn).unwrap();
-        assert_eq!(stats.total_devices, 0);
-        assert_eq!(stats.total_scans, 0);
+        let hosts: Vec<HostInfo> = ["aa:bb:cc:dd:ee:50", "aa:bb:cc:dd:ee:51"]
+            .iter()
+            .enumerate()
+            .map(|(i, mac)| {
+                HostInfo::new(
+                    format!("192.168.1.{}", 50 + i),
+                    mac.to_string(),
+                    "MOBILE".to_string(),
+                    "ARP".to_string(),
+                )
+            })
+            .collect();
+        let scan = ScanResult {
+            interface_name: "eth0".to_string(),
+            local_ip: "192.168.1.2".to_string(),
+            local_mac: "aa:bb:cc:dd:ee:02".to_string(),
+            subnet: "192.168.1.0/24".to_string(),
+            scan_method: "arp".to_string(),
+            arp_discovered: 2,
+            icmp_discovered: 0,
+            total_hosts: 2,
+            scan_duration_ms: 10,
+            active_hosts: hosts,
+            wan: None,
+            ssid: None,
+        };
+        insert_scan(&conn, &scan).unwrap();
+        insert_scan(&conn, &scan).unwrap();
+
+        let mac = "aa:bb:cc:dd:ee:50";
+        record_sni_observation(&conn, mac, "example.com", None).unwrap();
+        record_device_service(&conn, mac, "_companion-link._tcp", None, None).unwrap();
+        upsert_device_settings(&conn, &DeviceSettings::new(mac)).unwrap();
+        conn.execute(
+            "INSERT INTO alerts (alert_type, device_mac, message) VALUES ('NEW_DEVICE', ?1, 'x')",
+            params![mac],
+        )
+        .unwrap();
+
+        set_network_baseline(&conn).unwrap();
+
+        // A randomized MAC linked to the device
+        let device_id = get_device_by_mac(&conn, mac).unwrap().unwrap().id;
+        let linked = "da:bb:cc:dd:ee:70";
+        insert_device_merge(&conn, device_id, linked, 80, &[]).unwrap();
+        record_dhcp_fingerprint(&conn, mac, "1,3,6,15").unwrap();
+        record_dhcp_fingerprint(&conn, linked, "1,3,6,15").unwrap();
+        record_sni_observation(&conn, linked, "example.org", None).unwrap();
+        for tracked_mac in [mac, linked, "aa:bb:cc:dd:ee:51"] {
+            let tracked_mac = tracked_mac.to_uppercase();
+            conn.execute(
+                r#"
+                INSERT INTO presence_sessions (mac, ip, arrived_at, last_seen_at)
+                VALUES (?1, '192.168.1.50', datetime('now'), datetime('now'))
+                "#,
+                params![tracked_mac],
+            )
+            .unwrap();
+            conn.execute(
+                r#"
+                INSERT INTO device_certificates
+                    (mac, port, ip, subject, issuer, not_before, not_after, fingerprint,
+                     first_seen, last_seen)
+                VALUES (?1, 443, '192.168.1.50', 'CN=x', 'CN=x', datetime('now'),
+                        datetime('now'), 'ab', datetime('now'), datetime('now'))
+                "#,
+                params![tracked_mac],
+            )
+            .unwrap();
+            conn.execute(
+                r#"
+                INSERT INTO device_ssh_keys
+                    (mac, port, ip, key_type, fingerprint, first_seen, last_seen)
+                VALUES (?1, 22, '192.168.1.50', 'ssh-ed25519', 'SHA256:x',
+                        datetime('now'), datetime('now'))
+                "#,
+                params![tracked_mac],
+            )
+            .unwrap();
+        }
+        for payload in [
+            r#"{"type":"DeviceWentOffline","data":{"mac":"AA:BB:CC:DD:EE:50","ip":"192.168.1.50"}}"#,
+            r#"{"type":"IpConflict","data":{"ip":"192.168.1.9","macs":["aa:bb:cc:dd:ee:51","aa:bb:cc:dd:ee:50"]}}"#,
+            r#"{"type":"DeviceWentOffline","data":{"mac":"aa:bb:cc:dd:ee:51","ip":"192.168.1.51"}}"#,
+        ] {
+            conn.execute(
+                "INSERT INTO network_events (event_type, payload) VALUES ('test', ?1)",
+                params![payload],
+            )
+            .unwrap();
+        }
+
+        let preview = preview_device_purge(&conn, mac).unwrap().unwrap();
+        assert_eq!(preview.history_rows, 2);
+        assert_eq!(preview.alerts, 1);
+        assert!(preview.has_settings);
+
+        assert!(purge_device_data(&conn, mac, "wrong").is_err());
+        let other = preview_device_purge(&conn, "aa:bb:cc:dd:ee:51")
+            .unwrap()
+            .unwrap();
+        assert!(purge_device_data(&conn, mac, &other.confirmation_token).is_err());
+        assert!(get_device_by_mac(&conn, mac).unwrap().is_some());
+
+        let summary = purge_device_data(&conn, mac, &preview.confirmation_token).unwrap();
+        assert_eq!(summary.history_rows, 2);
+        assert_eq!(summary.alerts, 1);
+        assert_eq!(summary.sni_observations, 2);
+        assert_eq!(summary.services, 1);
+        assert_eq!(summary.settings, 1);
+
+        assert!(get_device_by_mac(&conn, mac).unwrap().is_none());
+        assert!(preview_device_purge(&conn, mac).unwrap().is_none());
+        assert!(get_device_sni(&conn, mac).unwrap().is_empty());
+        assert!(get_device_sni(&conn, linked).unwrap().is_empty());
+        let rows = |table: &str| -> i64 {
+            conn.query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| {
+                row.get(0)
+            })
+            .unwrap()
+        };
+        assert_eq!(rows("device_merges"), 0);
+        assert_eq!(rows("dhcp_fingerprints"), 0);
+        assert_eq!(rows("presence_sessions"), 1);
+        assert_eq!(rows("device_certificates"), 1);
+        assert_eq!(rows("device_ssh_keys"), 1);
+        let baseline = get_network_baseline(&conn).unwrap().unwrap();
+        assert_eq!(baseline.devices.len(), 1);
+        assert_eq!(baseline.devices[0].mac, "aa:bb:cc:dd:ee:51");
+        // Only the event about the other device is left
+        assert_eq!(rows("network_events"), 1);
+        let payload: String = conn
+            .query_row("SELECT payload FROM network_events", [], |row| row.get(0))
+            .unwrap();
+        assert!(payload.contains("aa:bb:cc:dd:ee:51") && !payload.contains("ee:50"));
+        // Other devices are untouched
+        assert_eq!(get_all_devices(&conn).unwrap().len(), 1);
     }
 
     #[test]
     fn test_insert_scan_is_atomic_on_host_failure() {
         let db = Database::in_memory().unwrap();
-        let conn = db.connection();
-        let conn = conn.lock().unwrap();
+        let conn = db.writer().unwrap();
 
         // Force device persistence failure after scan row insert.
         conn.execute_batch(
@@ -779,6 +5992,8 @@ mod tests {
             total_hosts: 1,
             scan_duration_ms: 1500,
             active_hosts: vec![host],
+            wan: None,
+            ssid: None,
         };
 
         assert!(insert_scan(&conn, &result).is_err());
@@ -788,4 +6003,77 @@ mod tests {
             .unwrap();
         assert_eq!(scan_count, 0, "scan row must rollback on host failure");
     }
+
+    #[test]
+    fn test_randomized_macs_merge_into_one_device() {
+        let db = Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
+
+        let phone = |mac: &str, hostname: &str| {
+            let mut host = HostInfo::new(
+                "192.168.1.60".to_string(),
+                mac.to_string(),
+                "Mobile".to_string(),
+                "ARP".to_string(),
+            );
+            host.is_randomized = true;
+            host.hostname = Some(hostname.to_string());
+            host
+        };
+        let scan = |hosts: Vec<HostInfo>| ScanResult {
+            interface_name: "eth0".to_string(),
+            local_ip: "192.168.1.1".to_string(),
+            local_mac: "AA:BB:CC:DD:EE:FF".to_string(),
+            subnet: "192.168.1.0/24".to_string(),
+            scan_method: "arp".to_string(),
+            arp_discovered: hosts.len(),
+            icmp_discovered: 0,
+            total_hosts: hosts.len(),
+            scan_duration_ms: 100,
+            active_hosts: hosts,
+            wan: None,
+            ssid: None,
+        };
+
+        // Same phone under a new private address the next day
+        insert_scan(
+            &conn,
+            &scan(vec![phone("DA:00:00:00:00:01", "annas-iphone")]),
+        )
+        .unwrap();
+        record_dhcp_fingerprint(&conn, "DA:00:00:00:00:02", "1,121,3,6,15,119,252").unwrap();
+        insert_scan(
+            &conn,
+            &scan(vec![phone("DA:00:00:00:00:02", "Annas-iPhone")]),
+        )
+        .unwrap();
+        let devices = get_all_devices(&conn).unwrap();
+        assert_eq!(devices.len(), 1);
+        assert_eq!(devices[0].mac, "DA:00:00:00:00:01");
+        let merges = get_device_merges(&conn, "DA:00:00:00:00:02").unwrap();
+        assert_eq!(merges.len(), 1);
+        assert_eq!(merges[0].signals, vec![CorrelationSignal::Hostname]);
+        assert_eq!(
+            get_device_history(&conn, devices[0].id, 10).unwrap().len(),
+            2
+        );
+
+        // Two phones online at the same time are never the same device
+        insert_scan(
+            &conn,
+            &scan(vec![
+                phone("DA:00:00:00:00:03", "annas-iphone"),
+                phone("DA:00:00:00:00:02", "annas-iphone"),
+            ]),
+        )
+        .unwrap();
+        assert_eq!(get_all_devices(&conn).unwrap().len(), 2);
+
+        // ... but can be merged by hand
+        merge_devices(&conn, "DA:00:00:00:00:01", "DA:00:00:00:00:03").unwrap();
+        assert_eq!(get_all_devices(&conn).unwrap().len(), 1);
+        let merges = get_device_merges(&conn, "DA:00:00:00:00:01").unwrap();
+        assert_eq!(merges[1].signals, vec![CorrelationSignal::Manual]);
+        assert!(merge_devices(&conn, "DA:00:00:00:00:01", "DA:00:00:00:00:02").is_err());
+    }
 }
@@ -0,0 +1,520 @@
+//! Retention policy and pruning of old scan data
+//!
+//! Scans, per-scan device history, monitor events, internet health checks,
+//! ended presence sessions and read alerts are removed once they fall outside
+//! the configured retention. Before history is dropped it is rolled up into
+//! one row per device and day (`device_history_daily`) and one row
+//! per day of scans (`scan_daily`), so long-term trends survive pruning.
+
+use anyhow::{bail, Context, Result};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+use super::models::{DeviceDailySummary, ScanDailySummary};
+use super::queries::{self, find_device_id, SETTING_RETENTION_POLICY};
+
+/// How much history is kept; `None` keeps that kind of data forever
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetentionPolicy {
+    /// Days of scans and per-scan device history kept in full
+    pub history_days: Option<u32>,
+    /// Most recent scans kept in full
+    pub max_scans: Option<u32>,
+    /// Days read alerts are kept (unread alerts are never pruned)
+    pub alert_days: Option<u32>,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            history_days: Some(90),
+            max_scans: Some(500),
+            alert_days: Some(180),
+        }
+    }
+}
+
+impl RetentionPolicy {
+    fn validate(&self) -> Result<()> {
+        if self.history_days == Some(0) || self.alert_days == Some(0) {
+            bail!("Retention periods must be at least one day");
+        }
+        if self.max_scans == Some(0) {
+            bail!("At least one scan must be kept");
+        }
+        Ok(())
+    }
+}
+
+/// What a [`prune`] run removed
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PruneSummary {
+    pub scans: usize,
+    pub history_rows: usize,
+    pub alerts: usize,
+    /// Device/day summary rows written or extended from the removed history
+    pub summary_days: usize,
+    /// Stored monitor events, kept as long as scan history
+    pub events: usize,
+    /// Internet health checks, kept as long as scan history
+    pub internet_checks: usize,
+    /// Ended presence sessions, kept as long as scan history
+    pub presence_sessions: usize,
+}
+
+/// Load the retention policy (90 days / 500 scans / 180 days by default)
+pub fn load_retention_policy(conn: &Connection) -> Result<RetentionPolicy> {
+    Ok(queries::get_json_setting(conn, SETTING_RETENTION_POLICY)?.unwrap_or_default())
+}
+
+/// Validate and persist the retention policy
+pub fn save_retention_policy(conn: &Connection, policy: &RetentionPolicy) -> Result<()> {
+    policy.validate()?;
+    queries::set_json_setting(conn, SETTING_RETENTION_POLICY, policy)
+}
+
+/// Remove data outside `policy`, summarizing pruned history by day
+///
+/// The newest scan is always kept so the current network state stays
+/// available. Runs in one transaction.
+pub fn prune(conn: &Connection, policy: &RetentionPolicy) -> Result<PruneSummary> {
+    policy.validate()?;
+
+    conn.execute_batch("SAVEPOINT prune")
+        .context("Failed to start prune transaction")?;
+
+    let result = prune_inner(conn, policy);
+
+    match result {
+        Ok(summary) => {
+            conn.execute_batch("RELEASE SAVEPOINT prune")
+                .context("Failed to commit prune transaction")?;
+            if summary != PruneSummary::default() {
+                tracing::info!(
+                    "Pruned {} scans, {} history rows and {} alerts",
+                    summary.scans,
+                    summary.history_rows,
+                    summary.alerts
+                );
+            }
+            Ok(summary)
+        }
+        Err(e) => {
+            let _ = conn.execute_batch("ROLLBACK TO SAVEPOINT prune; RELEASE SAVEPOINT prune");
+            let _ = conn.execute_batch("DROP TABLE IF EXISTS temp.prune_scans");
+            Err(e.context("Failed to prune database"))
+        }
+    }
+}
+
+fn prune_inner(conn: &Connection, policy: &RetentionPolicy) -> Result<PruneSummary> {
+    // Latency buckets are built from the raw history about to be removed
+    super::latency::downsample_latency(conn)?;
+
+    conn.execute_batch(
+        "DROP TABLE IF EXISTS temp.prune_scans; CREATE TEMP TABLE prune_scans (id INTEGER PRIMARY KEY);",
+    )?;
+
+    if let Some(days) = policy.history_days {
+        conn.execute(
+            r#"
+            INSERT OR IGNORE INTO prune_scans
+            SELECT id FROM scans
+            WHERE scan_time < datetime('now', ?1) AND id < (SELECT MAX(id) FROM scans)
+            "#,
+            params![format!("-{} days", days)],
+        )?;
+    }
+    if let Some(max_scans) = policy.max_scans {
+        conn.execute(
+            r#"
+            INSERT OR IGNORE INTO prune_scans
+            SELECT id FROM scans
+            WHERE id <= (SELECT id FROM scans ORDER BY id DESC LIMIT 1 OFFSET ?1)
+            "#,
+            params![max_scans],
+        )?;
+    }
+
+    let summary_days = conn
+        .execute(
+            r#"
+            INSERT INTO device_history_daily (
+                device_id, day, sightings, response_samples, response_total_ms,
+                min_response_ms, max_response_ms, max_risk_score, last_ip
+            )
+            SELECT dh.device_id, date(s.scan_time), COUNT(*), COUNT(dh.response_time_ms),
+                   COALESCE(SUM(dh.response_time_ms), 0), MIN(dh.response_time_ms),
+                   MAX(dh.response_time_ms), MAX(dh.risk_score),
+                   (SELECT h.ip FROM device_history h JOIN scans hs ON hs.id = h.scan_id
+                    WHERE h.device_id = dh.device_id AND date(hs.scan_time) = date(s.scan_time)
+                      AND h.scan_id IN (SELECT id FROM prune_scans)
+                    ORDER BY h.id DESC LIMIT 1)
+            FROM device_history dh
+            JOIN scans s ON s.id = dh.scan_id
+            WHERE dh.scan_id IN (SELECT id FROM prune_scans)
+            GROUP BY dh.device_id, date(s.scan_time)
+            ON CONFLICT(device_id, day) DO UPDATE SET
+                sightings = sightings + excluded.sightings,
+                response_samples = response_samples + excluded.response_samples,
+                response_total_ms = response_total_ms + excluded.response_total_ms,
+                min_response_ms = COALESCE(MIN(min_response_ms, excluded.min_response_ms),
+                                           min_response_ms, excluded.min_response_ms),
+                max_response_ms = COALESCE(MAX(max_response_ms, excluded.max_response_ms),
+                                           max_response_ms, excluded.max_response_ms),
+                max_risk_score = MAX(max_risk_score, excluded.max_risk_score),
+                last_ip = COALESCE(excluded.last_ip, last_ip)
+            "#,
+            [],
+        )
+        .context("Failed to summarize device history")?;
+
+    conn.execute(
+        r#"
+        INSERT INTO scan_daily (day, scans, total_hosts, max_hosts, duration_total_ms)
+        SELECT date(scan_time), COUNT(*), SUM(total_hosts), MAX(total_hosts), SUM(duration_ms)
+        FROM scans
+        WHERE id IN (SELECT id FROM prune_scans)
+        GROUP BY date(scan_time)
+        ON CONFLICT(day) DO UPDATE SET
+            scans = scans + excluded.scans,
+            total_hosts = total_hosts + excluded.total_hosts,
+            max_hosts = MAX(max_hosts, excluded.max_hosts),
+            duration_total_ms = duration_total_ms + excluded.duration_total_ms
+        "#,
+        [],
+    )
+    .context("Failed to summarize scans")?;
+
+    let history_rows = conn.execute(
+        "DELETE FROM device_history WHERE scan_id IN (SELECT id FROM prune_scans)",
+        [],
+    )?;
+    conn.execute(
+        "DELETE FROM scan_findings WHERE scan_id IN (SELECT id FROM prune_scans)",
+        [],
+    )?;
+    conn.execute(
+        "UPDATE scans SET duplicate_of = NULL WHERE duplicate_of IN (SELECT id FROM prune_scans)",
+        [],
+    )?;
+    // Port change history is kept; only its link to the scan goes
+    conn.execute(
+        "UPDATE device_port_events SET scan_id = NULL WHERE scan_id IN (SELECT id FROM prune_scans)",
+        [],
+    )?;
+    let scans = conn.execute(
+        "DELETE FROM scans WHERE id IN (SELECT id FROM prune_scans)",
+        [],
+    )?;
+    conn.execute_batch("DROP TABLE temp.prune_scans")?;
+
+    let events = match policy.history_days {
+        Some(days) => conn.execute(
+            "DELETE FROM network_events WHERE created_at < datetime('now', ?1)",
+            params![format!("-{} days", days)],
+        )?,
+        None => 0,
+    };
+    let internet_checks = match policy.history_days {
+        Some(days) => conn.execute(
+            "DELETE FROM internet_health WHERE checked_at < datetime('now', ?1)",
+            params![format!("-{} days", days)],
+        )?,
+        None => 0,
+    };
+    let presence_sessions = match policy.history_days {
+        Some(days) => conn.execute(
+            "DELETE FROM presence_sessions WHERE left_at < datetime('now', ?1)",
+            params![format!("-{} days", days)],
+        )?,
+        None => 0,
+    };
+
+    let alerts = match policy.alert_days {
+        Some(days) => conn.execute(
+            "DELETE FROM alerts WHERE is_read = 1 AND created_at < datetime('now', ?1)",
+            params![format!("-{} days", days)],
+        )?,
+        None => 0,
+    };
+
+    Ok(PruneSummary {
+        scans,
+        history_rows,
+        alerts,
+        summary_days,
+        events,
+        internet_checks,
+        presence_sessions,
+    })
+}
+
+/// Fold a merged device's daily summaries into the canonical device's
+pub(super) fn merge_daily_summaries(
+    conn: &Connection,
+    canonical: i64,
+    duplicate: i64,
+) -> Result<()> {
+    conn.execute(
+        r#"
+        INSERT INTO device_history_daily (
+            device_id, day, sightings, response_samples, response_total_ms,
+            min_response_ms, max_response_ms, max_risk_score, last_ip
+        )
+        SELECT ?1, day, sightings, response_samples, response_total_ms,
+               min_response_ms, max_response_ms, max_risk_score, last_ip
+        FROM device_history_daily WHERE device_id = ?2
+        ON CONFLICT(device_id, day) DO UPDATE SET
+            sightings = sightings + excluded.sightings,
+            response_samples = response_samples + excluded.response_samples,
+            response_total_ms = response_total_ms + excluded.response_total_ms,
+            min_response_ms = COALESCE(MIN(min_response_ms, excluded.min_response_ms),
+                                       min_response_ms, excluded.min_response_ms),
+            max_response_ms = COALESCE(MAX(max_response_ms, excluded.max_response_ms),
+                                       max_response_ms, excluded.max_response_ms),
+            max_risk_score = MAX(max_risk_score, excluded.max_risk_score),
+            last_ip = COALESCE(last_ip, excluded.last_ip)
+        "#,
+        params![canonical, duplicate],
+    )?;
+    conn.execute(
+        "DELETE FROM device_history_daily WHERE device_id = ?1",
+        params![duplicate],
+    )?;
+    Ok(())
+}
+
+/// Daily summaries of a device's pruned history, oldest day first
+pub fn get_device_daily_summaries(conn: &Connection, mac: &str) -> Result<Vec<DeviceDailySummary>> {
+    let Some(device_id) = find_device_id(conn, mac)? else {
+        return Ok(Vec::new());
+    };
+
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT day, sightings, response_samples, response_total_ms,
+               min_response_ms, max_response_ms, max_risk_score, last_ip
+        FROM device_history_daily
+        WHERE device_id = ?1
+        ORDER BY day
+        "#,
+    )?;
+    let summaries = stmt
+        .query_map(params![device_id], |row| {
+            let samples: i64 = row.get(2)?;
+            let total_ms: i64 = row.get(3)?;
+            Ok(DeviceDailySummary {
+                device_mac: mac.to_string(),
+                day: row.get(0)?,
+                sightings: row.get(1)?,
+                avg_response_ms: (samples > 0).then_some(total_ms as f64 / samples as f64),
+                min_response_ms: row
+                    .get::<_, Option<i64>>(4)?
+                    .and_then(|ms| u64::try_from(ms).ok()),
+                max_response_ms: row
+                    .get::<_, Option<i64>>(5)?
+                    .and_then(|ms| u64::try_from(ms).ok()),
+                max_risk_score: row.get(6)?,
+                last_ip: row.get(7)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("Failed to get device daily summaries")?;
+    Ok(summaries)
+}
+
+/// Daily summaries of pruned scans, oldest day first
+pub fn get_scan_daily_summaries(conn: &Connection) -> Result<Vec<ScanDailySummary>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT day, scans, total_hosts, max_hosts, duration_total_ms
+        FROM scan_daily
+        WHERE scans > 0
+        ORDER BY day
+        "#,
+    )?;
+    let summaries = stmt
+        .query_map([], |row| {
+            let scans: u32 = row.get(1)?;
+            let total_hosts: i64 = row.get(2)?;
+            let duration_ms: i64 = row.get(4)?;
+            Ok(ScanDailySummary {
+                day: row.get(0)?,
+                scans,
+                avg_hosts: total_hosts as f64 / f64::from(scans),
+                max_hosts: row.get(3)?,
+                avg_duration_ms: duration_ms as f64 / f64::from(scans),
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("Failed to get scan daily summaries")?;
+    Ok(summaries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{queries::insert_scan, Database};
+    use crate::models::{HostInfo, ScanResult};
+
+    fn scan(ip: &str, response_ms: u64) -> ScanResult {
+        let mut host = HostInfo::new(
+            ip.to_string(),
+            "AA:BB:CC:DD:EE:07".to_string(),
+            "PC".to_string(),
+            "ARP+ICMP".to_string(),
+        );
+        host.response_time_ms = Some(response_ms);
+        host.risk_score = response_ms as u8;
+        ScanResult {
+            interface_name: "eth0".to_string(),
+            local_ip: "192.168.1.1".to_string(),
+            local_mac: "AA:BB:CC:DD:EE:FF".to_string(),
+            subnet: "192.168.1.0/24".to_string(),
+            scan_method: "arp".to_string(),
+            arp_discovered: 1,
+            icmp_discovered: 1,
+            total_hosts: 1,
+            scan_duration_ms: 100,
+            active_hosts: vec![host],
+            wan: None,
+            ssid: None,
+        }
+    }
+
+    fn count(conn: &Connection, table: &str) -> i64 {
+        conn.query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| {
+            row.get(0)
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_prune_summarizes_old_history() {
+        let db = Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
+
+        let old_a = insert_scan(&conn, &scan("192.168.1.70", 10)).unwrap();
+        let old_b = insert_scan(&conn, &scan("192.168.1.71", 30)).unwrap();
+        insert_scan(&conn, &scan("192.168.1.72", 20)).unwrap();
+        conn.execute(
+            "UPDATE scans SET scan_time = datetime('now', '-100 days') WHERE id IN (?1, ?2)",
+            params![old_a, old_b],
+        )
+        .unwrap();
+        conn.execute_batch(
+            r#"
+            INSERT INTO alerts (created_at, alert_type, message, is_read)
+            VALUES (datetime('now', '-200 days'), 'NEW_DEVICE', 'old read', 1),
+                   (datetime('now', '-200 days'), 'NEW_DEVICE', 'old unread', 0),
+                   (datetime('now', '-1 days'), 'NEW_DEVICE', 'recent read', 1);
+            INSERT INTO network_events (created_at, event_type, payload)
+            VALUES (datetime('now', '-100 days'), 'MonitoringStopped', '{"type":"MonitoringStopped"}'),
+                   (datetime('now'), 'MonitoringStopped', '{"type":"MonitoringStopped"}');
+            INSERT INTO internet_health (checked_at, status, loss_percent, payload)
+            VALUES (datetime('now', '-100 days'), 'up', 0, '{}');
+            INSERT INTO presence_sessions (mac, ip, arrived_at, last_seen_at, left_at)
+            VALUES ('AA:00:00:00:00:01', '192.168.1.40', datetime('now', '-101 days'),
+                    datetime('now', '-100 days'), datetime('now', '-100 days')),
+                   ('AA:00:00:00:00:01', '192.168.1.40', datetime('now', '-100 days'),
+                    datetime('now'), NULL);
+            "#,
+        )
+        .unwrap();
+
+        let summary = prune(&conn, &RetentionPolicy::default()).unwrap();
+        assert_eq!(
+            summary,
+            PruneSummary {
+                scans: 2,
+                history_rows: 2,
+                alerts: 1,
+                summary_days: 1,
+                events: 1,
+                internet_checks: 1,
+                presence_sessions: 1,
+            }
+        );
+        assert_eq!(count(&conn, "scans"), 1);
+        assert_eq!(count(&conn, "device_history"), 1);
+        assert_eq!(count(&conn, "alerts"), 2);
+
+        let days = get_device_daily_summaries(&conn, "AA:BB:CC:DD:EE:07").unwrap();
+        assert_eq!(days.len(), 1);
+        assert_eq!(days[0].sightings, 2);
+        assert_eq!(days[0].avg_response_ms, Some(20.0));
+        assert_eq!(days[0].min_response_ms, Some(10));
+        assert_eq!(days[0].max_response_ms, Some(30));
+        assert_eq!(days[0].max_risk_score, 30);
+        assert_eq!(days[0].last_ip.as_deref(), Some("192.168.1.71"));
+
+        let scans = get_scan_daily_summaries(&conn).unwrap();
+        assert_eq!(scans.len(), 1);
+        assert_eq!(scans[0].scans, 2);
+        assert_eq!(scans[0].max_hosts, 1);
+
+        assert_eq!(
+            prune(&conn, &RetentionPolicy::default()).unwrap(),
+            PruneSummary::default()
+        );
+    }
+
+    #[test]
+    fn test_prune_keeps_newest_scans() {
+        let db = Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
+
+        for i in 0..4 {
+            insert_scan(&conn, &scan(&format!("192.168.1.8{}", i), 5)).unwrap();
+        }
+        conn.execute(
+            "UPDATE scans SET scan_time = datetime('now', '-1 years')",
+            [],
+        )
+        .unwrap();
+
+        let policy = RetentionPolicy {
+            history_days: None,
+            max_scans: Some(3),
+            alert_days: None,
+        };
+        assert_eq!(prune(&conn, &policy).unwrap().scans, 1);
+        assert_eq!(count(&conn, "scans"), 3);
+
+        // Everything is older than the history window, but the latest scan stays
+        let summary = prune(&conn, &RetentionPolicy::default()).unwrap();
+        assert_eq!(summary.scans, 2);
+        assert_eq!(count(&conn, "scans"), 1);
+        assert_eq!(
+            get_device_daily_summaries(&conn, "AA:BB:CC:DD:EE:07").unwrap()[0].sightings,
+            3
+        );
+    }
+
+    #[test]
+    fn test_policy_validation_and_roundtrip() {
+        let db = Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
+
+        assert_eq!(
+            load_retention_policy(&conn).unwrap(),
+            RetentionPolicy::default()
+        );
+        let invalid = RetentionPolicy {
+            max_scans: Some(0),
+            ..Default::default()
+        };
+        assert!(save_retention_policy(&conn, &invalid).is_err());
+        assert!(prune(&conn, &invalid).is_err());
+
+        let policy = RetentionPolicy {
+            history_days: Some(30),
+            max_scans: None,
+            alert_days: Some(7),
+        };
+        save_retention_policy(&conn, &policy).unwrap();
+        assert_eq!(load_retention_policy(&conn).unwrap(), policy);
+    }
+}
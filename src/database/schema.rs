@@ -1,12 +1,637 @@
 //! Database schema definitions
 //!
-//! Creates and manages the SQLite tables
+//! Creates and manages the SQLite tables. The schema is versioned: every
+//! change is a numbered migration, applied in order and recorded in the
+//! `schema_version` table, so an old install is upgraded one step at a time.
+//! Released migrations are never edited; schema changes add a new one.
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use rusqlite::Connection;
+use std::collections::BTreeSet;
 
-/// Create all database tables
+/// A schema change, applied once in version order
+struct Migration {
+    version: u32,
+    description: &'static str,
+    apply: fn(&Connection) -> Result<()>,
+}
+
+/// All schema changes, oldest first
+///
+/// Versions 2-8 predate versioning and may find their column already added
+/// on old installs, so they check before altering.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "baseline tables",
+        apply: create_baseline,
+    },
+    Migration {
+        version: 2,
+        description: "alert dedupe keys",
+        apply: |conn| {
+            add_column(conn, "alerts", "dedupe_key", "TEXT")?;
+            conn.execute(
+                "CREATE INDEX IF NOT EXISTS idx_alerts_dedupe ON alerts(alert_type, device_mac, dedupe_key, created_at)",
+                [],
+            )
+            .context("Failed to create idx_alerts_dedupe index")?;
+            Ok(())
+        },
+    },
+    Migration {
+        version: 3,
+        description: "expected ports per device",
+        apply: |conn| add_column(conn, "device_settings", "expected_ports", "TEXT"),
+    },
+    Migration {
+        version: 4,
+        description: "duplicate scan links",
+        apply: |conn| {
+            add_column(
+                conn,
+                "scans",
+                "duplicate_of",
+                "INTEGER REFERENCES scans(id) ON DELETE SET NULL",
+            )
+        },
+    },
+    Migration {
+        version: 5,
+        description: "randomized MAC flag on devices",
+        apply: |conn| {
+            add_column(
+                conn,
+                "devices",
+                "is_randomized",
+                "INTEGER NOT NULL DEFAULT 0",
+            )
+        },
+    },
+    Migration {
+        version: 6,
+        description: "randomized MAC flag in device history",
+        apply: |conn| {
+            add_column(
+                conn,
+                "device_history",
+                "is_randomized",
+                "INTEGER NOT NULL DEFAULT 0",
+            )
+        },
+    },
+    Migration {
+        version: 7,
+        description: "filtered ports and host state in device history",
+        apply: |conn| {
+            add_column(conn, "device_history", "filtered_ports", "TEXT")?;
+            add_column(conn, "device_history", "host_state", "TEXT")
+        },
+    },
+    Migration {
+        version: 8,
+        description: "alert evidence",
+        apply: |conn| add_column(conn, "alerts", "evidence", "TEXT"),
+    },
+    Migration {
+        version: 9,
+        description: "per-device port table",
+        apply: create_device_ports,
+    },
+    Migration {
+        version: 10,
+        description: "daily summaries of pruned history",
+        apply: create_daily_summaries,
+    },
+    Migration {
+        version: 11,
+        description: "scan labels",
+        apply: |conn| add_column(conn, "scans", "label", "TEXT"),
+    },
+    Migration {
+        version: 12,
+        description: "device tags",
+        apply: |conn| {
+            conn.execute_batch(
+                r#"
+                CREATE TABLE device_tags (
+                    device_id INTEGER NOT NULL,
+                    tag TEXT NOT NULL COLLATE NOCASE,
+                    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+                    PRIMARY KEY (device_id, tag),
+                    FOREIGN KEY (device_id) REFERENCES devices(id) ON DELETE CASCADE
+                );
+                CREATE INDEX idx_device_tags_tag ON device_tags(tag);
+                "#,
+            )
+            .context("Failed to create device_tags table")
+        },
+    },
+    Migration {
+        version: 13,
+        description: "device groups with policies",
+        apply: |conn| {
+            conn.execute_batch(
+                r#"
+                CREATE TABLE device_groups (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    name TEXT UNIQUE NOT NULL COLLATE NOCASE,
+                    description TEXT,
+                    policy TEXT NOT NULL DEFAULT '{}',
+                    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+                    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+                );
+                CREATE TABLE device_group_members (
+                    group_id INTEGER NOT NULL,
+                    device_id INTEGER NOT NULL,
+                    PRIMARY KEY (group_id, device_id),
+                    FOREIGN KEY (group_id) REFERENCES device_groups(id) ON DELETE CASCADE,
+                    FOREIGN KEY (device_id) REFERENCES devices(id) ON DELETE CASCADE
+                );
+                CREATE INDEX idx_device_group_members_device ON device_group_members(device_id);
+                "#,
+            )
+            .context("Failed to create device group tables")
+        },
+    },
+    Migration {
+        version: 14,
+        description: "topology edges from LLDP neighbors",
+        apply: |conn| {
+            conn.execute_batch(
+                r#"
+                CREATE TABLE topology_edges (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    local_device_id INTEGER NOT NULL,
+                    local_port TEXT NOT NULL,
+                    remote_device TEXT NOT NULL,
+                    remote_port TEXT NOT NULL,
+                    remote_ip TEXT,
+                    source TEXT NOT NULL,
+                    first_seen TEXT NOT NULL,
+                    last_seen TEXT NOT NULL,
+                    last_scan_id INTEGER,
+                    UNIQUE (local_device_id, local_port, remote_device, remote_port),
+                    FOREIGN KEY (local_device_id) REFERENCES devices(id) ON DELETE CASCADE
+                );
+                CREATE INDEX idx_topology_edges_last_seen ON topology_edges(last_seen);
+                "#,
+            )
+            .context("Failed to create topology edge table")
+        },
+    },
+    Migration {
+        version: 15,
+        description: "saved network tool runs",
+        apply: |conn| {
+            conn.execute_batch(
+                r#"
+                CREATE TABLE tool_runs (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    tool TEXT NOT NULL,
+                    target TEXT NOT NULL,
+                    parameters TEXT NOT NULL DEFAULT '{}',
+                    results TEXT NOT NULL,
+                    duration_ms INTEGER,
+                    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+                );
+                CREATE INDEX idx_tool_runs_tool_target ON tool_runs(tool, target, created_at);
+                "#,
+            )
+            .context("Failed to create tool_runs table")
+        },
+    },
+    Migration {
+        version: 16,
+        description: "per-scan security findings",
+        apply: |conn| {
+            conn.execute_batch(
+                r#"
+                CREATE TABLE scan_findings (
+                    scan_id INTEGER NOT NULL,
+                    device_id INTEGER NOT NULL,
+                    kind TEXT NOT NULL,
+                    finding_key TEXT NOT NULL,
+                    severity TEXT NOT NULL,
+                    description TEXT NOT NULL,
+                    PRIMARY KEY (scan_id, device_id, kind, finding_key),
+                    FOREIGN KEY (scan_id) REFERENCES scans(id) ON DELETE CASCADE,
+                    FOREIGN KEY (device_id) REFERENCES devices(id) ON DELETE CASCADE
+                );
+                CREATE INDEX idx_scan_findings_device ON scan_findings(device_id, kind, finding_key);
+                "#,
+            )
+            .context("Failed to create scan_findings table")
+        },
+    },
+    Migration {
+        version: 17,
+        description: "hourly and daily latency rollups",
+        apply: |conn| {
+            conn.execute_batch(
+                r#"
+                CREATE TABLE latency_rollups (
+                    device_id INTEGER NOT NULL,
+                    resolution TEXT NOT NULL,
+                    bucket TEXT NOT NULL,
+                    samples INTEGER NOT NULL,
+                    total_ms INTEGER NOT NULL,
+                    min_ms INTEGER NOT NULL,
+                    max_ms INTEGER NOT NULL,
+                    p50_ms INTEGER NOT NULL,
+                    p95_ms INTEGER NOT NULL,
+                    PRIMARY KEY (device_id, resolution, bucket),
+                    FOREIGN KEY (device_id) REFERENCES devices(id) ON DELETE CASCADE
+                );
+                "#,
+            )
+            .context("Failed to create latency_rollups table")
+        },
+    },
+    Migration {
+        version: 18,
+        description: "full-text device search",
+        apply: |conn| {
+            // Rows share the device id; triggers keep the index in step with devices
+            conn.execute_batch(
+                r#"
+                CREATE VIRTUAL TABLE device_search USING fts5(
+                    mac, ip, hostname, vendor, custom_name, notes
+                );
+                INSERT INTO device_search (rowid, mac, ip, hostname, vendor, custom_name, notes)
+                SELECT id, mac, last_ip, hostname, vendor, custom_name, notes FROM devices;
+
+                CREATE TRIGGER devices_search_insert AFTER INSERT ON devices BEGIN
+                    INSERT INTO device_search (rowid, mac, ip, hostname, vendor, custom_name, notes)
+                    VALUES (new.id, new.mac, new.last_ip, new.hostname, new.vendor,
+                            new.custom_name, new.notes);
+                END;
+                CREATE TRIGGER devices_search_delete AFTER DELETE ON devices BEGIN
+                    DELETE FROM device_search WHERE rowid = old.id;
+                END;
+                CREATE TRIGGER devices_search_update
+                AFTER UPDATE OF mac, last_ip, hostname, vendor, custom_name, notes ON devices
+                BEGIN
+                    DELETE FROM device_search WHERE rowid = old.id;
+                    INSERT INTO device_search (rowid, mac, ip, hostname, vendor, custom_name, notes)
+                    VALUES (new.id, new.mac, new.last_ip, new.hostname, new.vendor,
+                            new.custom_name, new.notes);
+                END;
+                "#,
+            )
+            .context("Failed to create device_search index")
+        },
+    },
+    Migration {
+        version: 19,
+        description: "network profiles",
+        apply: create_networks,
+    },
+    Migration {
+        version: 20,
+        description: "custom device fields",
+        apply: create_device_attributes,
+    },
+    Migration {
+        version: 21,
+        description: "port change history",
+        apply: create_device_port_events,
+    },
+    Migration {
+        version: 22,
+        description: "device watch list",
+        apply: |conn| {
+            add_column(
+                conn,
+                "device_settings",
+                "watched",
+                "INTEGER NOT NULL DEFAULT 0",
+            )
+        },
+    },
+    Migration {
+        version: 23,
+        description: "persisted monitor events",
+        apply: |conn| {
+            conn.execute_batch(
+                r#"
+                CREATE TABLE network_events (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    event_type TEXT NOT NULL,
+                    payload TEXT NOT NULL,
+                    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+                );
+                CREATE INDEX idx_network_events_created ON network_events(created_at);
+                "#,
+            )
+            .context("Failed to create network_events table")
+        },
+    },
+    Migration {
+        version: 24,
+        description: "internet health checks",
+        apply: |conn| {
+            conn.execute_batch(
+                r#"
+                CREATE TABLE internet_health (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    checked_at TEXT NOT NULL DEFAULT (datetime('now')),
+                    status TEXT NOT NULL,
+                    loss_percent REAL NOT NULL,
+                    latency_ms REAL,
+                    dns_ms INTEGER,
+                    payload TEXT NOT NULL
+                );
+                CREATE INDEX idx_internet_health_checked ON internet_health(checked_at);
+                "#,
+            )
+            .context("Failed to create internet_health table")
+        },
+    },
+    Migration {
+        version: 25,
+        description: "scheduled scans",
+        apply: |conn| {
+            conn.execute_batch(
+                r#"
+                CREATE TABLE scan_schedules (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    name TEXT NOT NULL,
+                    cron TEXT NOT NULL,
+                    interface TEXT,
+                    generate_report INTEGER NOT NULL DEFAULT 0,
+                    enabled INTEGER NOT NULL DEFAULT 1,
+                    last_run TEXT,
+                    last_error TEXT,
+                    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+                    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+                );
+                "#,
+            )
+            .context("Failed to create scan_schedules table")
+        },
+    },
+    Migration {
+        version: 26,
+        description: "network baseline",
+        apply: |conn| {
+            conn.execute_batch(
+                r#"
+                CREATE TABLE network_baseline (
+                    mac TEXT PRIMARY KEY,
+                    ip TEXT,
+                    hostname TEXT,
+                    vendor TEXT,
+                    device_type TEXT,
+                    open_ports TEXT NOT NULL DEFAULT '',
+                    captured_at TEXT NOT NULL DEFAULT (datetime('now'))
+                );
+                "#,
+            )
+            .context("Failed to create network_baseline table")
+        },
+    },
+    Migration {
+        version: 27,
+        description: "presence sessions",
+        apply: |conn| {
+            conn.execute_batch(
+                r#"
+                CREATE TABLE presence_sessions (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    mac TEXT NOT NULL,
+                    ip TEXT NOT NULL,
+                    arrived_at TEXT NOT NULL,
+                    last_seen_at TEXT NOT NULL,
+                    left_at TEXT
+                );
+                CREATE INDEX idx_presence_sessions_mac ON presence_sessions(mac, arrived_at);
+                CREATE INDEX idx_presence_sessions_open ON presence_sessions(left_at);
+                "#,
+            )
+            .context("Failed to create presence_sessions table")
+        },
+    },
+    Migration {
+        version: 28,
+        description: "alert rules",
+        apply: |conn| {
+            conn.execute_batch(
+                r#"
+                CREATE TABLE alert_rules (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    name TEXT NOT NULL,
+                    event_types TEXT NOT NULL DEFAULT '[]',
+                    device_filter TEXT,
+                    threshold REAL,
+                    severity TEXT NOT NULL,
+                    notify TEXT NOT NULL DEFAULT '[]',
+                    enabled INTEGER NOT NULL DEFAULT 1,
+                    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+                    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+                );
+                "#,
+            )
+            .context("Failed to create alert_rules table")
+        },
+    },
+    Migration {
+        version: 29,
+        description: "alert occurrence counts and rule cooldowns",
+        apply: |conn| {
+            add_column(conn, "alerts", "occurrences", "INTEGER NOT NULL DEFAULT 1")?;
+            add_column(conn, "alerts", "last_seen_at", "TEXT")?;
+            add_column(conn, "alert_rules", "cooldown_minutes", "INTEGER")
+        },
+    },
+    Migration {
+        version: 30,
+        description: "trusted device allowlist",
+        apply: |conn| {
+            add_column(
+                conn,
+                "device_settings",
+                "trusted",
+                "INTEGER NOT NULL DEFAULT 0",
+            )
+        },
+    },
+    Migration {
+        version: 31,
+        description: "per-device alert mute",
+        apply: |conn| add_column(conn, "device_settings", "muted_until", "TEXT"),
+    },
+    Migration {
+        version: 32,
+        description: "TLS certificates",
+        apply: |conn| {
+            conn.execute_batch(
+                r#"
+                CREATE TABLE device_certificates (
+                    mac TEXT NOT NULL,
+                    port INTEGER NOT NULL,
+                    ip TEXT NOT NULL,
+                    subject TEXT NOT NULL,
+                    issuer TEXT NOT NULL,
+                    not_before TEXT NOT NULL,
+                    not_after TEXT NOT NULL,
+                    fingerprint TEXT NOT NULL,
+                    first_seen TEXT NOT NULL,
+                    last_seen TEXT NOT NULL,
+                    PRIMARY KEY (mac, port)
+                );
+                CREATE INDEX idx_device_certificates_expiry ON device_certificates(not_after);
+                "#,
+            )
+            .context("Failed to create device_certificates table")
+        },
+    },
+    Migration {
+        version: 33,
+        description: "SSH host keys",
+        apply: |conn| {
+            conn.execute_batch(
+                r#"
+                CREATE TABLE device_ssh_keys (
+                    mac TEXT NOT NULL,
+                    port INTEGER NOT NULL,
+                    ip TEXT NOT NULL,
+                    banner TEXT,
+                    key_type TEXT NOT NULL,
+                    fingerprint TEXT NOT NULL,
+                    first_seen TEXT NOT NULL,
+                    last_seen TEXT NOT NULL,
+                    PRIMARY KEY (mac, port)
+                );
+                "#,
+            )
+            .context("Failed to create device_ssh_keys table")
+        },
+    },
+];
+
+/// Schema version of a database created by this build
+pub const SCHEMA_VERSION: u32 = 33;
+
+/// Create all database tables, upgrading an older schema to the current one
 pub fn create_tables(conn: &Connection) -> Result<()> {
+    migrate_to(conn, SCHEMA_VERSION)
+}
+
+/// Version of the schema in `conn` (0 for an empty or unversioned database)
+///
+/// Only reads, so it also works on read-only connections.
+pub fn schema_version(conn: &Connection) -> Result<u32> {
+    let versioned: bool = conn
+        .query_row(
+            "SELECT EXISTS (
+                SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'schema_version'
+            )",
+            [],
+            |row| row.get(0),
+        )
+        .context("Failed to look up schema_version table")?;
+    if !versioned {
+        return Ok(0);
+    }
+
+    let version: Option<u32> = conn
+        .query_row("SELECT MAX(version) FROM schema_version", [], |row| {
+            row.get(0)
+        })
+        .context("Failed to read schema version")?;
+    Ok(version.unwrap_or(0))
+}
+
+/// Apply pending migrations up to and including `target`
+///
+/// Each migration runs in its own savepoint, so a failure leaves the
+/// database at the last version that applied cleanly.
+fn migrate_to(conn: &Connection, target: u32) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS schema_version (
+            version INTEGER PRIMARY KEY,
+            description TEXT NOT NULL,
+            applied_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+        "#,
+    )
+    .context("Failed to create schema_version table")?;
+
+    let current = schema_version(conn)?;
+    if current > SCHEMA_VERSION {
+        bail!(
+            "Database schema version {} is newer than this build supports ({})",
+            current,
+            SCHEMA_VERSION
+        );
+    }
+
+    for migration in MIGRATIONS
+        .iter()
+        .filter(|m| m.version > current && m.version <= target)
+    {
+        conn.execute_batch("SAVEPOINT schema_migration")
+            .context("Failed to start schema migration")?;
+
+        let result = (migration.apply)(conn).and_then(|_| {
+            conn.execute(
+                "INSERT INTO schema_version (version, description) VALUES (?1, ?2)",
+                rusqlite::params![migration.version, migration.description],
+            )?;
+            Ok(())
+        });
+
+        match result {
+            Ok(()) => conn
+                .execute_batch("RELEASE SAVEPOINT schema_migration")
+                .context("Failed to commit schema migration")?,
+            Err(e) => {
+                let _ = conn.execute_batch(
+                    "ROLLBACK TO SAVEPOINT schema_migration; RELEASE SAVEPOINT schema_migration",
+                );
+                return Err(e).with_context(|| {
+                    format!(
+                        "Schema migration {} ({}) failed",
+                        migration.version, migration.description
+                    )
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Add a column unless the table already has it
+fn add_column(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<()> {
+    let exists: bool = conn
+        .prepare(&format!("PRAGMA table_info({})", table))
+        .and_then(|mut stmt| {
+            let mut rows = stmt.query([])?;
+            while let Some(row) = rows.next()? {
+                let col_name: String = row.get(1)?;
+                if col_name == column {
+                    return Ok(true);
+                }
+            }
+            Ok(false)
+        })
+        .with_context(|| format!("Failed to inspect {} table schema", table))?;
+
+    if !exists {
+        conn.execute(
+            &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+            [],
+        )
+        .with_context(|| format!("Failed to migrate {} table with {} column", table, column))?;
+    }
+    Ok(())
+}
+
+/// Version 1: the tables as they were before the schema was versioned
+fn create_baseline(conn: &Connection) -> Result<()> {
     conn.execute_batch(
         r#"
         -- Scans table: stores each scan session
@@ -32,7 +657,6 @@ pub fn create_tables(conn: &Connection) -> Result<()> {
             last_seen TEXT NOT NULL DEFAULT (datetime('now')),
             last_ip TEXT,
             vendor TEXT,
-            is_randomized INTEGER NOT NULL DEFAULT 0,
             device_type TEXT,
             hostname TEXT,
             os_guess TEXT,
@@ -49,7 +673,6 @@ pub fn create_tables(conn: &Connection) -> Result<()> {
             response_time_ms INTEGER,
             ttl INTEGER,
             risk_score INTEGER NOT NULL DEFAULT 0,
-            is_randomized INTEGER NOT NULL DEFAULT 0,
             security_grade TEXT DEFAULT '',
             is_online INTEGER NOT NULL DEFAULT 1,
             discovery_method TEXT,
@@ -66,7 +689,6 @@ pub fn create_tables(conn: &Connection) -> Result<()> {
             device_id INTEGER,
             device_mac TEXT,
             device_ip TEXT,
-            dedupe_key TEXT,
             message TEXT NOT NULL,
             severity TEXT NOT NULL DEFAULT 'info',
             is_read INTEGER NOT NULL DEFAULT 0,
@@ -97,6 +719,130 @@ pub fn create_tables(conn: &Connection) -> Result<()> {
             recommendation TEXT
         );
 
+        -- TLS SNI observations: services contacted by each device
+        CREATE TABLE IF NOT EXISTS device_sni (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            device_mac TEXT NOT NULL,
+            server_name TEXT NOT NULL,
+            last_server_ip TEXT,
+            first_seen TEXT NOT NULL DEFAULT (datetime('now')),
+            last_seen TEXT NOT NULL DEFAULT (datetime('now')),
+            hit_count INTEGER NOT NULL DEFAULT 1,
+            UNIQUE(device_mac, server_name)
+        );
+
+        -- mDNS/DNS-SD services advertised by each device (passive discovery)
+        CREATE TABLE IF NOT EXISTS device_services (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            device_mac TEXT NOT NULL,
+            service_type TEXT NOT NULL,
+            instance_name TEXT NOT NULL DEFAULT '',
+            port INTEGER,
+            first_seen TEXT NOT NULL DEFAULT (datetime('now')),
+            last_seen TEXT NOT NULL DEFAULT (datetime('now')),
+            UNIQUE(device_mac, service_type, instance_name)
+        );
+
+        -- Per-device alerting overrides (NULL columns fall back to defaults)
+        CREATE TABLE IF NOT EXISTS device_settings (
+            device_mac TEXT PRIMARY KEY,
+            latency_threshold_ms INTEGER,
+            offline_grace_secs INTEGER,
+            offline_alerts_enabled INTEGER NOT NULL DEFAULT 1,
+            risk_threshold INTEGER,
+            risk_alerts_enabled INTEGER NOT NULL DEFAULT 1,
+            updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        -- Smart groups: saved device filter expressions
+        CREATE TABLE IF NOT EXISTS smart_groups (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT UNIQUE NOT NULL,
+            expression TEXT NOT NULL,
+            description TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        -- IEEE OUI registry (downloaded on demand, overrides the bundled vendor table)
+        CREATE TABLE IF NOT EXISTS oui_registry (
+            prefix TEXT PRIMARY KEY,
+            vendor TEXT NOT NULL,
+            updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        -- User-defined vendor names by MAC prefix (6-11 hex digits) or full MAC (12)
+        CREATE TABLE IF NOT EXISTS vendor_overrides (
+            pattern TEXT PRIMARY KEY,
+            vendor TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        -- User-defined device type rules (all set conditions must match)
+        CREATE TABLE IF NOT EXISTS device_type_rules (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            vendor_pattern TEXT,
+            hostname_pattern TEXT,
+            ports TEXT NOT NULL DEFAULT '',
+            service TEXT,
+            device_type TEXT NOT NULL,
+            confidence INTEGER NOT NULL DEFAULT 80,
+            enabled INTEGER NOT NULL DEFAULT 1,
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        -- Active Directory computer objects matched to devices (managed devices)
+        CREATE TABLE IF NOT EXISTS device_directory (
+            device_mac TEXT PRIMARY KEY,
+            computer_name TEXT NOT NULL,
+            dns_hostname TEXT,
+            operating_system TEXT,
+            os_version TEXT,
+            ou TEXT,
+            last_logon TEXT,
+            distinguished_name TEXT NOT NULL,
+            enabled INTEGER NOT NULL DEFAULT 1,
+            updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        -- Accepted risks: findings left out of the health score until they expire
+        CREATE TABLE IF NOT EXISTS risk_acceptances (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            device_mac TEXT NOT NULL,
+            finding TEXT NOT NULL,
+            reason TEXT NOT NULL,
+            accepted_by TEXT,
+            accepted_at TEXT NOT NULL DEFAULT (datetime('now')),
+            expires_at TEXT NOT NULL,
+            review_alerted INTEGER NOT NULL DEFAULT 0,
+            UNIQUE(device_mac, finding)
+        );
+
+        -- MAC addresses merged into a canonical device (randomized MAC correlation)
+        CREATE TABLE IF NOT EXISTS device_merges (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            device_id INTEGER NOT NULL,
+            mac TEXT UNIQUE NOT NULL,
+            score INTEGER NOT NULL DEFAULT 0,
+            signals TEXT NOT NULL DEFAULT '[]',
+            merged_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        -- DHCP parameter request lists (option 55) by MAC
+        CREATE TABLE IF NOT EXISTS dhcp_fingerprints (
+            mac TEXT PRIMARY KEY,
+            fingerprint TEXT NOT NULL,
+            last_seen TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        -- Application settings (key/value, JSON-encoded values)
+        CREATE TABLE IF NOT EXISTS app_settings (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL,
+            updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
         -- Indexes for performance
         CREATE INDEX IF NOT EXISTS idx_scans_time ON scans(scan_time);
         CREATE INDEX IF NOT EXISTS idx_devices_mac ON devices(mac);
@@ -107,78 +853,301 @@ pub fn create_tables(conn: &Connection) -> Result<()> {
         CREATE INDEX IF NOT EXISTS idx_alerts_unread ON alerts(is_read) WHERE is_read = 0;
         CREATE INDEX IF NOT EXISTS idx_cve_vendor ON cve_cache(vendor);
         CREATE INDEX IF NOT EXISTS idx_cve_severity ON cve_cache(severity);
+        CREATE INDEX IF NOT EXISTS idx_device_sni_mac ON device_sni(device_mac);
+        CREATE INDEX IF NOT EXISTS idx_device_services_mac ON device_services(device_mac);
+        CREATE INDEX IF NOT EXISTS idx_device_merges_device ON device_merges(device_id);
         "#,
     )
     .context("Failed to create database tables")?;
 
-    // Backward-compatible migration for older databases created before dedupe_key existed.
-    let has_dedupe_key: bool = conn
-        .prepare("PRAGMA table_info(alerts)")
-        .and_then(|mut stmt| {
-            let mut rows = stmt.query([])?;
-            while let Some(row) = rows.next()? {
-                let col_name: String = row.get(1)?;
-                if col_name == "dedupe_key" {
-                    return Ok(true);
-                }
-            }
-            Ok(false)
-        })
-        .context("Failed to inspect alerts table schema")?;
+    Ok(())
+}
+
+/// Version 9: open ports per device, filled from the port lists in history
+fn create_device_ports(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE device_ports (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            device_id INTEGER NOT NULL,
+            port INTEGER NOT NULL,
+            protocol TEXT NOT NULL DEFAULT 'tcp',
+            first_seen TEXT NOT NULL,
+            last_seen TEXT NOT NULL,
+            last_scan_id INTEGER,
+            service TEXT,
+            banner TEXT,
+            UNIQUE(device_id, port, protocol),
+            FOREIGN KEY (device_id) REFERENCES devices(id) ON DELETE CASCADE
+        );
+        CREATE INDEX idx_device_ports_port ON device_ports(port, protocol);
+        "#,
+    )
+    .context("Failed to create device_ports table")?;
 
-    if !has_dedupe_key {
-        conn.execute("ALTER TABLE alerts ADD COLUMN dedupe_key TEXT", [])
-            .context("Failed to migrate alerts table with dedupe_key column")?;
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT dh.device_id, dh.scan_id, s.scan_time, dh.open_ports
+        FROM device_history dh
+        JOIN scans s ON s.id = dh.scan_id
+        WHERE dh.open_ports IS NOT NULL AND dh.open_ports != ''
+        ORDER BY s.scan_time, dh.id
+        "#,
+    )?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let (device_id, scan_id, scan_time): (i64, i64, String) =
+            (row.get(0)?, row.get(1)?, row.get(2)?);
+        let ports: String = row.get(3)?;
+
+        for port in ports.split(',').filter_map(|p| p.parse::<u16>().ok()) {
+            conn.execute(
+                r#"
+                INSERT INTO device_ports (device_id, port, first_seen, last_seen, last_scan_id)
+                VALUES (?1, ?2, ?3, ?3, ?4)
+                ON CONFLICT(device_id, port, protocol) DO UPDATE SET
+                    last_seen = excluded.last_seen,
+                    last_scan_id = excluded.last_scan_id
+                "#,
+                rusqlite::params![device_id, port, scan_time, scan_id],
+            )
+            .context("Failed to copy open ports from device history")?;
+        }
     }
 
-    conn.execute(
-        "CREATE INDEX IF NOT EXISTS idx_alerts_dedupe ON alerts(alert_type, device_mac, dedupe_key, created_at)",
-        [],
+    Ok(())
+}
+
+/// Version 10: per-day rollups that outlive scans removed by retention pruning
+fn create_daily_summaries(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE device_history_daily (
+            device_id INTEGER NOT NULL,
+            day TEXT NOT NULL,
+            sightings INTEGER NOT NULL DEFAULT 0,
+            response_samples INTEGER NOT NULL DEFAULT 0,
+            response_total_ms INTEGER NOT NULL DEFAULT 0,
+            min_response_ms INTEGER,
+            max_response_ms INTEGER,
+            max_risk_score INTEGER NOT NULL DEFAULT 0,
+            last_ip TEXT,
+            PRIMARY KEY (device_id, day),
+            FOREIGN KEY (device_id) REFERENCES devices(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE scan_daily (
+            day TEXT PRIMARY KEY,
+            scans INTEGER NOT NULL DEFAULT 0,
+            total_hosts INTEGER NOT NULL DEFAULT 0,
+            max_hosts INTEGER NOT NULL DEFAULT 0,
+            duration_total_ms INTEGER NOT NULL DEFAULT 0
+        );
+        "#,
     )
-    .context("Failed to create idx_alerts_dedupe index")?;
+    .context("Failed to create daily summary tables")?;
+    Ok(())
+}
 
-    let has_devices_randomized: bool = conn
-        .prepare("PRAGMA table_info(devices)")
-        .and_then(|mut stmt| {
-            let mut rows = stmt.query([])?;
-            while let Some(row) = rows.next()? {
-                let col_name: String = row.get(1)?;
-                if col_name == "is_randomized" {
-                    return Ok(true);
-                }
-            }
-            Ok(false)
-        })
-        .context("Failed to inspect devices table schema")?;
+/// Version 19: network profiles, with scans, devices and alerts scoped to one
+///
+/// Existing history gets one profile per scanned subnet; gateway and SSID are
+/// filled in by the next scan of that network.
+fn create_networks(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE networks (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            subnet TEXT NOT NULL,
+            gateway_mac TEXT,
+            ssid TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            last_seen TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+        CREATE INDEX idx_networks_subnet ON networks(subnet);
+        "#,
+    )
+    .context("Failed to create networks table")?;
 
-    if !has_devices_randomized {
-        conn.execute(
-            "ALTER TABLE devices ADD COLUMN is_randomized INTEGER NOT NULL DEFAULT 0",
-            [],
-        )
-        .context("Failed to migrate devices table with is_randomized column")?;
+    for table in ["scans", "devices", "alerts"] {
+        add_column(
+            conn,
+            table,
+            "network_id",
+            "INTEGER REFERENCES networks(id) ON DELETE SET NULL",
+        )?;
     }
 
-    let has_history_randomized: bool = conn
-        .prepare("PRAGMA table_info(device_history)")
-        .and_then(|mut stmt| {
-            let mut rows = stmt.query([])?;
-            while let Some(row) = rows.next()? {
-                let col_name: String = row.get(1)?;
-                if col_name == "is_randomized" {
-                    return Ok(true);
-                }
-            }
-            Ok(false)
-        })
-        .context("Failed to inspect device_history table schema")?;
+    conn.execute_batch(
+        r#"
+        INSERT INTO networks (name, subnet, created_at, last_seen)
+        SELECT subnet, subnet, MIN(scan_time), MAX(scan_time) FROM scans GROUP BY subnet;
 
-    if !has_history_randomized {
-        conn.execute(
-            "ALTER TABLE device_history ADD COLUMN is_randomized INTEGER NOT NULL DEFAULT 0",
-            [],
-        )
-        .context("Failed to migrate device_history table with is_randomized column")?;
+        UPDATE scans SET network_id = (
+            SELECT id FROM networks WHERE networks.subnet = scans.subnet
+        );
+        UPDATE devices SET network_id = (
+            SELECT s.network_id FROM device_history h
+            JOIN scans s ON s.id = h.scan_id
+            WHERE h.device_id = devices.id
+            ORDER BY h.scan_id DESC LIMIT 1
+        );
+        UPDATE alerts SET network_id = COALESCE(
+            (SELECT network_id FROM devices WHERE devices.mac = alerts.device_mac),
+            (SELECT network_id FROM scans WHERE scans.scan_time <= alerts.created_at
+             ORDER BY scans.id DESC LIMIT 1)
+        );
+
+        CREATE INDEX idx_scans_network ON scans(network_id);
+        CREATE INDEX idx_devices_network ON devices(network_id);
+        CREATE INDEX idx_alerts_network ON alerts(network_id);
+        "#,
+    )
+    .context("Failed to assign history to network profiles")?;
+    Ok(())
+}
+
+/// Version 20: user-defined key-value fields on devices
+///
+/// The search index is rebuilt with an `attributes` column so values such as
+/// an asset tag or owner are found by device search; triggers on both tables
+/// keep it in step.
+fn create_device_attributes(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE device_attributes (
+            device_id INTEGER NOT NULL,
+            name TEXT NOT NULL COLLATE NOCASE,
+            value TEXT NOT NULL,
+            updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+            PRIMARY KEY (device_id, name),
+            FOREIGN KEY (device_id) REFERENCES devices(id) ON DELETE CASCADE
+        );
+        CREATE INDEX idx_device_attributes_name ON device_attributes(name);
+
+        DROP TRIGGER devices_search_insert;
+        DROP TRIGGER devices_search_delete;
+        DROP TRIGGER devices_search_update;
+        DROP TABLE device_search;
+
+        CREATE VIRTUAL TABLE device_search USING fts5(
+            mac, ip, hostname, vendor, custom_name, notes, attributes
+        );
+        INSERT INTO device_search (rowid, mac, ip, hostname, vendor, custom_name, notes)
+        SELECT id, mac, last_ip, hostname, vendor, custom_name, notes FROM devices;
+
+        CREATE TRIGGER devices_search_insert AFTER INSERT ON devices BEGIN
+            INSERT INTO device_search (rowid, mac, ip, hostname, vendor, custom_name, notes)
+            VALUES (new.id, new.mac, new.last_ip, new.hostname, new.vendor,
+                    new.custom_name, new.notes);
+        END;
+        CREATE TRIGGER devices_search_delete AFTER DELETE ON devices BEGIN
+            DELETE FROM device_search WHERE rowid = old.id;
+        END;
+        CREATE TRIGGER devices_search_update
+        AFTER UPDATE OF mac, last_ip, hostname, vendor, custom_name, notes ON devices
+        BEGIN
+            DELETE FROM device_search WHERE rowid = old.id;
+            INSERT INTO device_search
+                (rowid, mac, ip, hostname, vendor, custom_name, notes, attributes)
+            VALUES (new.id, new.mac, new.last_ip, new.hostname, new.vendor,
+                    new.custom_name, new.notes,
+                    (SELECT group_concat(name || ' ' || value, ' ')
+                     FROM device_attributes WHERE device_id = new.id));
+        END;
+
+        CREATE TRIGGER device_attributes_search_insert AFTER INSERT ON device_attributes BEGIN
+            DELETE FROM device_search WHERE rowid = new.device_id;
+            INSERT INTO device_search
+                (rowid, mac, ip, hostname, vendor, custom_name, notes, attributes)
+            SELECT id, mac, last_ip, hostname, vendor, custom_name, notes,
+                (SELECT group_concat(name || ' ' || value, ' ')
+                 FROM device_attributes WHERE device_id = devices.id)
+            FROM devices WHERE id = new.device_id;
+        END;
+        CREATE TRIGGER device_attributes_search_update AFTER UPDATE ON device_attributes BEGIN
+            DELETE FROM device_search WHERE rowid IN (old.device_id, new.device_id);
+            INSERT INTO device_search
+                (rowid, mac, ip, hostname, vendor, custom_name, notes, attributes)
+            SELECT id, mac, last_ip, hostname, vendor, custom_name, notes,
+                (SELECT group_concat(name || ' ' || value, ' ')
+                 FROM device_attributes WHERE device_id = devices.id)
+            FROM devices WHERE id IN (old.device_id, new.device_id);
+        END;
+        CREATE TRIGGER device_attributes_search_delete AFTER DELETE ON device_attributes BEGIN
+            DELETE FROM device_search WHERE rowid = old.device_id;
+            INSERT INTO device_search
+                (rowid, mac, ip, hostname, vendor, custom_name, notes, attributes)
+            SELECT id, mac, last_ip, hostname, vendor, custom_name, notes,
+                (SELECT group_concat(name || ' ' || value, ' ')
+                 FROM device_attributes WHERE device_id = devices.id)
+            FROM devices WHERE id = old.device_id;
+        END;
+        "#,
+    )
+    .context("Failed to create device_attributes table")
+}
+
+/// Version 21: each port opening or closing on a device, in order
+///
+/// Rebuilt from the port lists in history by comparing each device's
+/// consecutive scans.
+fn create_device_port_events(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE device_port_events (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            device_id INTEGER NOT NULL,
+            port INTEGER NOT NULL,
+            protocol TEXT NOT NULL DEFAULT 'tcp',
+            opened INTEGER NOT NULL,
+            scan_id INTEGER,
+            changed_at TEXT NOT NULL,
+            FOREIGN KEY (device_id) REFERENCES devices(id) ON DELETE CASCADE
+        );
+        CREATE INDEX idx_device_port_events_device ON device_port_events(device_id, changed_at);
+        CREATE INDEX idx_device_port_events_time ON device_port_events(changed_at);
+        "#,
+    )
+    .context("Failed to create device_port_events table")?;
+
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT dh.device_id, dh.scan_id, s.scan_time, COALESCE(dh.open_ports, '')
+        FROM device_history dh
+        JOIN scans s ON s.id = dh.scan_id
+        ORDER BY dh.device_id, s.scan_time, dh.id
+        "#,
+    )?;
+    let mut rows = stmt.query([])?;
+    let mut previous: Option<(i64, BTreeSet<u16>)> = None;
+    while let Some(row) = rows.next()? {
+        let (device_id, scan_id, scan_time): (i64, i64, String) =
+            (row.get(0)?, row.get(1)?, row.get(2)?);
+        let ports: BTreeSet<u16> = row
+            .get::<_, String>(3)?
+            .split(',')
+            .filter_map(|p| p.parse().ok())
+            .collect();
+
+        let before = match previous.take() {
+            Some((id, ports)) if id == device_id => ports,
+            _ => Default::default(),
+        };
+        let opened = ports.difference(&before).map(|&port| (port, true));
+        let closed = before.difference(&ports).map(|&port| (port, false));
+        for (port, opened) in opened.chain(closed) {
+            conn.execute(
+                r#"
+                INSERT INTO device_port_events (device_id, port, opened, scan_id, changed_at)
+                VALUES (?1, ?2, ?3, ?4, ?5)
+                "#,
+                rusqlite::params![device_id, port, opened, scan_id, scan_time],
+            )
+            .context("Failed to copy port changes from device history")?;
+        }
+        previous = Some((device_id, ports));
     }
 
     Ok(())
@@ -190,9 +1159,32 @@ pub fn drop_tables(conn: &Connection) -> Result<()> {
     conn.execute_batch(
         r#"
         DROP TABLE IF EXISTS alerts;
+        DROP TABLE IF EXISTS alert_rules;
+        DROP TABLE IF EXISTS network_events;
+        DROP TABLE IF EXISTS internet_health;
+        DROP TABLE IF EXISTS scan_schedules;
+        DROP TABLE IF EXISTS network_baseline;
+        DROP TABLE IF EXISTS presence_sessions;
+        DROP TABLE IF EXISTS device_certificates;
+        DROP TABLE IF EXISTS device_ssh_keys;
+        DROP TABLE IF EXISTS device_ports;
+        DROP TABLE IF EXISTS device_port_events;
+        DROP TABLE IF EXISTS topology_edges;
+        DROP TABLE IF EXISTS tool_runs;
+        DROP TABLE IF EXISTS scan_findings;
+        DROP TABLE IF EXISTS latency_rollups;
+        DROP TABLE IF EXISTS device_search;
+        DROP TABLE IF EXISTS device_group_members;
+        DROP TABLE IF EXISTS device_groups;
+        DROP TABLE IF EXISTS device_tags;
+        DROP TABLE IF EXISTS device_attributes;
+        DROP TABLE IF EXISTS device_history_daily;
+        DROP TABLE IF EXISTS scan_daily;
         DROP TABLE IF EXISTS device_history;
         DROP TABLE IF EXISTS devices;
         DROP TABLE IF EXISTS scans;
+        DROP TABLE IF EXISTS networks;
+        DROP TABLE IF EXISTS schema_version;
         "#,
     )
     .context("Failed to drop tables")?;
@@ -222,6 +1214,155 @@ mod tests {
         assert!(tables.contains(&"devices".to_string()));
         assert!(tables.contains(&"device_history".to_string()));
         assert!(tables.contains(&"alerts".to_string()));
+        assert!(tables.contains(&"device_sni".to_string()));
+        assert!(tables.contains(&"device_services".to_string()));
+        assert!(tables.contains(&"device_settings".to_string()));
+        assert!(tables.contains(&"smart_groups".to_string()));
+        assert!(tables.contains(&"app_settings".to_string()));
+        assert!(tables.contains(&"oui_registry".to_string()));
+        assert!(tables.contains(&"vendor_overrides".to_string()));
+        assert!(tables.contains(&"device_type_rules".to_string()));
+        assert!(tables.contains(&"device_directory".to_string()));
+    }
+
+    /// Tables with their sorted columns, and index names
+    fn schema_snapshot(conn: &Connection) -> (Vec<(String, Vec<String>)>, Vec<String>) {
+        let names = |kind: &str| -> Vec<String> {
+            conn.prepare(
+                "SELECT name FROM sqlite_master WHERE type = ?1 AND name NOT LIKE 'sqlite_%' ORDER BY name",
+            )
+            .unwrap()
+            .query_map([kind], |row| row.get(0))
+            .unwrap()
+            .filter_map(|r| r.ok())
+            .collect()
+        };
+
+        let tables = names("table")
+            .into_iter()
+            .map(|table| {
+                let mut columns: Vec<String> = conn
+                    .prepare(&format!("PRAGMA table_info({})", table))
+                    .unwrap()
+                    .query_map([], |row| row.get(1))
+                    .unwrap()
+                    .filter_map(|r| r.ok())
+                    .collect();
+                columns.sort();
+                (table, columns)
+            })
+            .collect();
+        (tables, names("index"))
+    }
+
+    #[test]
+    fn test_upgrade_from_every_prior_version() {
+        let fresh = Connection::open_in_memory().unwrap();
+        create_tables(&fresh).unwrap();
+        assert_eq!(schema_version(&fresh).unwrap(), SCHEMA_VERSION);
+        let expected = schema_snapshot(&fresh);
+
+        for version in 0..SCHEMA_VERSION {
+            let conn = Connection::open_in_memory().unwrap();
+            migrate_to(&conn, version).unwrap();
+            assert_eq!(schema_version(&conn).unwrap(), version);
+            if version >= 1 {
+                conn.execute(
+                    "INSERT INTO scans (interface_name, local_ip, local_mac, subnet, scan_method)
+                     VALUES ('eth0', '192.168.1.2', 'AA:BB:CC:DD:EE:FF', '192.168.1.0/24', 'ARP')",
+                    [],
+                )
+                .unwrap();
+            }
+
+            create_tables(&conn).unwrap();
+            assert_eq!(schema_version(&conn).unwrap(), SCHEMA_VERSION);
+            assert_eq!(
+                schema_snapshot(&conn),
+                expected,
+                "upgrade from v{}",
+                version
+            );
+
+            let scans: i64 = conn
+                .query_row("SELECT COUNT(*) FROM scans", [], |row| row.get(0))
+                .unwrap();
+            assert_eq!(
+                scans,
+                i64::from(version >= 1),
+                "rows kept from v{}",
+                version
+            );
+        }
+    }
+
+    #[test]
+    fn test_device_ports_backfilled_from_history() {
+        let conn = Connection::open_in_memory().unwrap();
+        migrate_to(&conn, 8).unwrap();
+        conn.execute_batch(
+            r#"
+            INSERT INTO scans (id, scan_time, interface_name, local_ip, local_mac, subnet, scan_method)
+            VALUES (1, '2024-01-01 10:00:00', 'eth0', '10.0.0.2', 'AA', '10.0.0.0/24', 'ARP'),
+                   (2, '2024-01-02 10:00:00', 'eth0', '10.0.0.2', 'AA', '10.0.0.0/24', 'ARP');
+            INSERT INTO devices (id, mac) VALUES (1, 'AA:BB:CC:DD:EE:01');
+            INSERT INTO device_history (scan_id, device_id, ip, open_ports)
+            VALUES (1, 1, '10.0.0.5', '22,80'), (2, 1, '10.0.0.5', '80');
+            "#,
+        )
+        .unwrap();
+
+        create_tables(&conn).unwrap();
+        let ports: Vec<(u16, String, String, i64)> = conn
+            .prepare(
+                "SELECT port, first_seen, last_seen, last_scan_id FROM device_ports ORDER BY port",
+            )
+            .unwrap()
+            .query_map([], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(
+            ports,
+            vec![
+                (
+                    22,
+                    "2024-01-01 10:00:00".to_string(),
+                    "2024-01-01 10:00:00".to_string(),
+                    1
+                ),
+                (
+                    80,
+                    "2024-01-01 10:00:00".to_string(),
+                    "2024-01-02 10:00:00".to_string(),
+                    2
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_migrations_are_sequential() {
+        let versions: Vec<u32> = MIGRATIONS.iter().map(|m| m.version).collect();
+        assert_eq!(versions, (1..=SCHEMA_VERSION).collect::<Vec<_>>());
+
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        create_tables(&conn).expect("Re-running migrations should be a no-op");
+        let applied: i64 = conn
+            .query_row("SELECT COUNT(*) FROM schema_version", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(applied, i64::from(SCHEMA_VERSION));
+
+        // A database from a newer build is left alone
+        conn.execute(
+            "INSERT INTO schema_version (version, description) VALUES (?1, 'future')",
+            [SCHEMA_VERSION + 1],
+        )
+        .unwrap();
+        assert!(create_tables(&conn).is_err());
     }
 
     #[test]
@@ -2,18 +2,28 @@
 //!
 //! Export device lists and scan history to CSV format
 
-use crate::database::DeviceRecord;
+use crate::database::{AlertRecord, DeviceRecord, PortExposureRecord, ToolRunRecord};
 use crate::models::HostInfo;
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use csv::Writer;
+use std::collections::BTreeMap;
 
 /// Export devices to CSV format
+///
+/// Custom device fields follow the fixed columns, one column per field name
+/// used by any of the devices.
 pub fn export_devices_csv(devices: &[DeviceRecord]) -> Result<String> {
     let mut writer = Writer::from_writer(vec![]);
 
+    // Field names differing only in case share a column
+    let mut attribute_names: BTreeMap<String, &str> = BTreeMap::new();
+    for name in devices.iter().flat_map(|device| device.attributes.keys()) {
+        attribute_names.entry(name.to_lowercase()).or_insert(name);
+    }
+
     // Write header
-    writer.write_record([
+    let mut header = vec![
         "IP Address",
         "MAC Address",
         "Hostname",
@@ -25,7 +35,10 @@ pub fn export_devices_csv(devices: &[DeviceRecord]) -> Result<String> {
         "First Seen",
         "Last Seen",
         "Status",
-    ])?;
+        "Tags",
+    ];
+    header.extend(attribute_names.values().copied());
+    writer.write_record(&header)?;
 
     // Write device records
     for device in devices {
@@ -34,8 +47,11 @@ pub fn export_devices_csv(devices: &[DeviceRecord]) -> Result<String> {
         } else {
             "Offline"
         };
+        let first_seen = device.first_seen.to_rfc3339();
+        let last_seen = device.last_seen.to_rfc3339();
+        let tags = device.tags.join("; ");
 
-        writer.write_record([
+        let mut record: Vec<&str> = vec![
             device.last_ip.as_deref().unwrap_or("N/A"),
             &device.mac,
             device.hostname.as_deref().unwrap_or("N/A"),
@@ -44,10 +60,19 @@ pub fn export_devices_csv(devices: &[DeviceRecord]) -> Result<String> {
             device.device_type.as_deref().unwrap_or("Unknown"),
             device.os_guess.as_deref().unwrap_or("Unknown"),
             "0", // Risk score not stored in DeviceRecord
-            &device.first_seen.to_rfc3339(),
-            &device.last_seen.to_rfc3339(),
+            &first_seen,
+            &last_seen,
             status,
-        ])?;
+            &tags,
+        ];
+        record.extend(attribute_names.keys().map(|key| {
+            device
+                .attributes
+                .iter()
+                .find(|(name, _)| name.to_lowercase() == *key)
+                .map_or("", |(_, value)| value.as_str())
+        }));
+        writer.write_record(&record)?;
     }
 
     let csv_data = String::from_utf8(writer.into_inner()?)?;
@@ -104,6 +129,113 @@ pub fn export_hosts_csv(hosts: &[HostInfo]) -> Result<String> {
     Ok(csv_data)
 }
 
+/// Export the sensitive port-exposure report to CSV
+pub fn export_port_exposure_csv(records: &[PortExposureRecord]) -> Result<String> {
+    let mut writer = Writer::from_writer(vec![]);
+
+    writer.write_record([
+        "Network",
+        "Port",
+        "MAC Address",
+        "Name",
+        "Vendor",
+        "Last IP Address",
+        "First Observed",
+        "Last Observed",
+        "Scans Open",
+    ])?;
+
+    for record in records {
+        writer.write_record([
+            &record.subnet,
+            &record.port.to_string(),
+            &record.mac,
+            record.display_name.as_deref().unwrap_or(""),
+            record.vendor.as_deref().unwrap_or("Unknown"),
+            &record.last_ip,
+            &record.first_observed.to_rfc3339(),
+            &record.last_observed.to_rfc3339(),
+            &record.scan_count.to_string(),
+        ])?;
+    }
+
+    let csv_data = String::from_utf8(writer.into_inner()?)?;
+    Ok(csv_data)
+}
+
+/// Export saved network tool runs to CSV, with parameters and results as JSON
+pub fn export_tool_runs_csv(runs: &[ToolRunRecord]) -> Result<String> {
+    let mut writer = Writer::from_writer(vec![]);
+
+    writer.write_record([
+        "Time",
+        "Tool",
+        "Target",
+        "Parameters",
+        "Results",
+        "Duration (ms)",
+    ])?;
+
+    for run in runs {
+        writer.write_record([
+            &run.created_at.to_rfc3339(),
+            &run.tool,
+            &run.target,
+            &run.parameters.to_string(),
+            &run.results.to_string(),
+            &run.duration_ms.map(|ms| ms.to_string()).unwrap_or_default(),
+        ])?;
+    }
+
+    let csv_data = String::from_utf8(writer.into_inner()?)?;
+    Ok(csv_data)
+}
+
+/// Export alerts to CSV as an incident timeline, with evidence as JSON
+pub fn export_alerts_csv(alerts: &[AlertRecord]) -> Result<String> {
+    let mut writer = Writer::from_writer(vec![]);
+
+    writer.write_record([
+        "Time",
+        "Last Seen",
+        "Type",
+        "Severity",
+        "MAC",
+        "IP",
+        "Message",
+        "Occurrences",
+        "Read",
+        "Evidence",
+    ])?;
+
+    for alert in alerts {
+        writer.write_record([
+            alert.created_at.to_rfc3339().as_str(),
+            alert
+                .last_seen_at
+                .map(|t| t.to_rfc3339())
+                .unwrap_or_default()
+                .as_str(),
+            alert.alert_type_raw.as_str(),
+            alert.severity.to_string().as_str(),
+            alert.device_mac.as_deref().unwrap_or(""),
+            alert.device_ip.as_deref().unwrap_or(""),
+            alert.message.as_str(),
+            alert.occurrences.to_string().as_str(),
+            if alert.is_read { "Yes" } else { "No" },
+            alert
+                .evidence
+                .as_ref()
+                .map(|e| e.to_string())
+                .unwrap_or_default()
+                .as_str(),
+        ])?;
+    }
+
+    let csv_data = String::from_utf8(writer.into_inner()?)?;
+    Ok(csv_data)
+}
+
 /// Helper: Check if device was seen recently (within last hour)
 fn is_recently_seen(last_seen: &str) -> bool {
     if let Ok(dt) = DateTime::parse_from_rfc3339(last_seen) {
@@ -141,6 +273,12 @@ mod tests {
             vulnerabilities: vec![],
             port_warnings: vec![],
             security_grade: String::new(),
+            filtered_ports: Vec::new(),
+            host_state: None,
+            conflicting_macs: Vec::new(),
+            ipv6_addresses: Vec::new(),
+            device_id: None,
+            last_seen: None,
         }];
 
         let csv = export_hosts_csv(&hosts).unwrap();
@@ -148,4 +286,34 @@ mod tests {
         assert!(csv.contains("router"));
         assert!(csv.contains("TP-Link"));
     }
+
+    #[test]
+    fn test_export_alerts_csv() {
+        use crate::database::{AlertSeverity, AlertType};
+
+        let alerts = vec![AlertRecord {
+            id: 1,
+            created_at: "2026-10-17T12:00:00Z".parse().unwrap(),
+            alert_type: AlertType::PortChange,
+            alert_type_raw: AlertType::PortChange.to_string(),
+            device_id: None,
+            device_mac: Some("AA:BB:CC:00:00:01".to_string()),
+            device_ip: Some("192.168.1.10".to_string()),
+            message: "Port 23 opened, port 22 closed".to_string(),
+            severity: AlertSeverity::Warning,
+            is_read: true,
+            evidence: Some(serde_json::json!({ "opened": [23] })),
+            network_id: None,
+            occurrences: 2,
+            last_seen_at: None,
+        }];
+
+        let csv = export_alerts_csv(&alerts).unwrap();
+        let row = csv.lines().nth(1).unwrap();
+        assert_eq!(
+            row,
+            "2026-10-17T12:00:00+00:00,,port_change,warning,AA:BB:CC:00:00:01,192.168.1.10,\
+             \"Port 23 opened, port 22 closed\",2,Yes,\"{\"\"opened\"\":[23]}\""
+        );
+    }
 }
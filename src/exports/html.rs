@@ -0,0 +1,401 @@
+//! Static HTML dashboard export
+//!
+//! Renders a single self-contained, read-only page (health score, topology
+//! diagram, device list and recent alerts) that can be dropped onto any web
+//! server or sent to a stakeholder. The page loads no scripts or external
+//! assets, so it works offline and under a strict Content-Security-Policy.
+
+use crate::config::DASHBOARD_RECENT_ALERTS;
+use crate::database::queries::{self, SETTING_PUBLIC_DASHBOARD};
+use crate::database::{AlertRecord, AlertSeverity};
+use crate::insights::NetworkHealth;
+use crate::models::HostInfo;
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+/// Nodes per ring in the topology diagram
+const RING_CAPACITY: usize = 36;
+
+/// Where the dashboard is written after each saved scan
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DashboardSettings {
+    /// Rewrite the dashboard whenever a scan is saved
+    #[serde(default)]
+    pub enabled: bool,
+    /// Output file, e.g. a web server's document root
+    #[serde(default)]
+    pub path: Option<String>,
+}
+
+impl DashboardSettings {
+    fn validate(&self) -> Result<()> {
+        let Some(path) = self.path.as_deref().filter(|p| !p.trim().is_empty()) else {
+            if self.enabled {
+                bail!("An output path is required to publish the dashboard");
+            }
+            return Ok(());
+        };
+        let parent = Path::new(path)
+            .parent()
+            .filter(|p| !p.as_os_str().is_empty());
+        if parent.is_some_and(|dir| !dir.is_dir()) {
+            bail!("Output directory for {} does not exist", path);
+        }
+        Ok(())
+    }
+}
+
+/// Load dashboard export settings (disabled by default)
+pub fn load_dashboard_settings(conn: &Connection) -> Result<DashboardSettings> {
+    Ok(queries::get_json_setting(conn, SETTING_PUBLIC_DASHBOARD)?.unwrap_or_default())
+}
+
+/// Validate and persist dashboard export settings
+pub fn save_dashboard_settings(conn: &Connection, settings: &DashboardSettings) -> Result<()> {
+    settings.validate()?;
+    queries::set_json_setting(conn, SETTING_PUBLIC_DASHBOARD, settings)
+}
+
+/// Everything shown on the dashboard
+#[derive(Debug, Clone)]
+pub struct DashboardSnapshot {
+    pub generated_at: DateTime<Utc>,
+    /// Subnet of the latest scan
+    pub network: String,
+    pub hosts: Vec<HostInfo>,
+    pub health: NetworkHealth,
+    pub alerts: Vec<AlertRecord>,
+}
+
+impl DashboardSnapshot {
+    /// Latest scan, its health score (honouring risk acceptances) and recent alerts
+    pub fn load(conn: &Connection) -> Result<Self> {
+        let network = queries::get_recent_scans(conn, 1)?
+            .into_iter()
+            .next()
+            .map(|scan| scan.subnet)
+            .unwrap_or_default();
+        let hosts = queries::get_latest_scan_hosts(conn)?;
+        let acceptances = queries::get_risk_acceptances(conn)?;
+        let health = NetworkHealth::calculate_with_acceptances(&hosts, &acceptances);
+        let alerts = queries::get_recent_alerts(conn, DASHBOARD_RECENT_ALERTS)?;
+
+        Ok(Self {
+            generated_at: Utc::now(),
+            network,
+            hosts,
+            health,
+            alerts,
+        })
+    }
+}
+
+/// Escape text for HTML element content and attribute values
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn is_router(host: &HostInfo) -> bool {
+    host.device_type.eq_ignore_ascii_case("router")
+}
+
+fn risk_color(risk_score: u8) -> &'static str {
+    match risk_score {
+        50.. => "#dc2626",
+        25..=49 => "#d97706",
+        _ => "#16a34a",
+    }
+}
+
+fn host_label(host: &HostInfo) -> &str {
+    host.hostname.as_deref().unwrap_or(&host.ip)
+}
+
+/// Star topology SVG: the router (or the network) in the middle, devices
+/// on rings around it, colored by risk
+pub fn topology_svg(hosts: &[HostInfo], network: &str) -> String {
+    let router = hosts.iter().find(|h| is_router(h));
+    let devices: Vec<&HostInfo> = hosts
+        .iter()
+        .filter(|h| router.is_none_or(|r| r.mac != h.mac))
+        .collect();
+
+    let rings = devices.len().div_ceil(RING_CAPACITY).max(1);
+    let radius = |ring: usize| 130.0 + ring as f64 * 70.0;
+    let size = 2.0 * (radius(rings - 1) + 60.0);
+    let center = size / 2.0;
+    let show_labels = devices.len() <= RING_CAPACITY;
+
+    let mut svg = String::new();
+    let _ = write!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {size:.0} {size:.0}" role="img" aria-label="Network topology">"#
+    );
+
+    let mut nodes = String::new();
+    for (index, host) in devices.iter().enumerate() {
+        let ring = index / RING_CAPACITY;
+        let in_ring = (devices.len() - ring * RING_CAPACITY).min(RING_CAPACITY);
+        let angle = (index % RING_CAPACITY) as f64 / in_ring as f64 * std::f64::consts::TAU;
+        let x = center + radius(ring) * angle.cos();
+        let y = center + radius(ring) * angle.sin();
+
+        let _ = write!(
+            svg,
+            r##"<line x1="{center:.1}" y1="{center:.1}" x2="{x:.1}" y2="{y:.1}" stroke="#cbd5e1"/>"##
+        );
+        let _ = write!(
+            nodes,
+            r#"<circle cx="{x:.1}" cy="{y:.1}" r="8" fill="{}"><title>{} ({}) - risk {}</title></circle>"#,
+            risk_color(host.risk_score),
+            escape_html(host_label(host)),
+            escape_html(&host.ip),
+            host.risk_score
+        );
+        if show_labels {
+            let _ = write!(
+                nodes,
+                r#"<text x="{x:.1}" y="{:.1}" text-anchor="middle">{}</text>"#,
+                y + 20.0,
+                escape_html(host_label(host))
+            );
+        }
+    }
+    svg.push_str(&nodes);
+
+    let hub = router.map(host_label).unwrap_or(network);
+    let _ = write!(
+        svg,
+        r##"<circle cx="{center:.1}" cy="{center:.1}" r="14" fill="#2563eb"><title>{hub}</title></circle><text x="{center:.1}" y="{:.1}" text-anchor="middle" font-weight="bold">{hub}</text></svg>"##,
+        center + 30.0,
+        hub = escape_html(hub)
+    );
+    svg
+}
+
+fn severity_class(severity: &AlertSeverity) -> &'static str {
+    match severity {
+        AlertSeverity::Info => "info",
+        AlertSeverity::Warning => "warning",
+        AlertSeverity::Error | AlertSeverity::Critical => "critical",
+    }
+}
+
+const STYLE: &str = "body{font-family:system-ui,sans-serif;margin:0 auto;max-width:1100px;padding:24px;color:#0f172a}\
+h1{margin-bottom:4px}.muted{color:#64748b}\
+.score{display:flex;gap:24px;align-items:center}.grade{font-size:48px;font-weight:bold}\
+table{border-collapse:collapse;width:100%}th,td{text-align:left;padding:6px 8px;border-bottom:1px solid #e2e8f0}\
+svg{width:100%;max-width:640px;font-size:11px}\
+.info{color:#2563eb}.warning{color:#d97706}.critical{color:#dc2626}";
+
+/// Render the dashboard as a complete HTML document
+pub fn generate_dashboard_html(snapshot: &DashboardSnapshot) -> String {
+    let health = &snapshot.health;
+    let title = if snapshot.network.is_empty() {
+        "Network Dashboard".to_string()
+    } else {
+        format!("Network Dashboard - {}", escape_html(&snapshot.network))
+    };
+
+    let mut html = String::new();
+    let _ = write!(
+        html,
+        "<!DOCTYPE html><html lang=\"en\"><head><meta charset=\"utf-8\">\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\
+         <meta http-equiv=\"Content-Security-Policy\" content=\"default-src 'none'; style-src 'unsafe-inline'\">\
+         <title>{title}</title><style>{STYLE}</style></head><body>\
+         <h1>{title}</h1><p class=\"muted\">Generated {} &middot; {} devices</p>",
+        snapshot.generated_at.format("%Y-%m-%d %H:%M UTC"),
+        snapshot.hosts.len()
+    );
+
+    // Health score
+    let _ = write!(
+        html,
+        "<h2>Network Health</h2><div class=\"score\"><div class=\"grade\">{}</div>\
+         <div><strong>{}/100</strong> &middot; {}<br>Security {}/40 &middot; Stability {}/30 &middot; Compliance {}/30</div></div><ul>",
+        health.grade,
+        health.score,
+        escape_html(&health.status),
+        health.breakdown.security,
+        health.breakdown.stability,
+        health.breakdown.compliance
+    );
+    for insight in &health.insights {
+        let _ = write!(html, "<li>{}</li>", escape_html(insight));
+    }
+    html.push_str("</ul>");
+
+    // Topology
+    let _ = write!(
+        html,
+        "<h2>Topology</h2>{}",
+        topology_svg(&snapshot.hosts, &snapshot.network)
+    );
+
+    // Devices
+    html.push_str(
+        "<h2>Devices</h2><table><thead><tr><th>IP</th><th>Name</th><th>MAC</th><th>Vendor</th>\
+         <th>Type</th><th>Open Ports</th><th>Risk</th></tr></thead><tbody>",
+    );
+    let mut hosts: Vec<&HostInfo> = snapshot.hosts.iter().collect();
+    hosts.sort_by_key(|h| h.ip.parse::<std::net::Ipv4Addr>().ok());
+    for host in hosts {
+        let ports: Vec<String> = host.open_ports.iter().map(u16::to_string).collect();
+        let _ = write!(
+            html,
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td>\
+             <td style=\"color:{}\">{}</td></tr>",
+            escape_html(&host.ip),
+            escape_html(host.hostname.as_deref().unwrap_or("")),
+            escape_html(&host.mac),
+            escape_html(host.vendor.as_deref().unwrap_or("Unknown")),
+            escape_html(&host.device_type),
+            ports.join(", "),
+            risk_color(host.risk_score),
+            host.risk_score
+        );
+    }
+    html.push_str("</tbody></table>");
+
+    // Recent alerts
+    html.push_str("<h2>Recent Alerts</h2>");
+    if snapshot.alerts.is_empty() {
+        html.push_str("<p class=\"muted\">No alerts.</p>");
+    } else {
+        html.push_str(
+            "<table><thead><tr><th>Time</th><th>Severity</th><th>Alert</th></tr></thead><tbody>",
+        );
+        for alert in &snapshot.alerts {
+            let _ = write!(
+                html,
+                "<tr><td>{}</td><td class=\"{}\">{}</td><td>{}</td></tr>",
+                alert.created_at.format("%Y-%m-%d %H:%M"),
+                severity_class(&alert.severity),
+                alert.severity,
+                escape_html(&alert.message)
+            );
+        }
+        html.push_str("</tbody></table>");
+    }
+
+    html.push_str("</body></html>\n");
+    html
+}
+
+/// Write the dashboard to its configured path, if publishing is enabled
+///
+/// The file is replaced atomically so a web server never serves a partial
+/// page. Returns the path written.
+pub fn refresh_public_dashboard(conn: &Connection) -> Result<Option<PathBuf>> {
+    let settings = load_dashboard_settings(conn)?;
+    let Some(path) = settings.path.filter(|_| settings.enabled) else {
+        return Ok(None);
+    };
+
+    let html = generate_dashboard_html(&DashboardSnapshot::load(conn)?);
+    let path = PathBuf::from(path);
+    let staging = path.with_extension("html.tmp");
+    std::fs::write(&staging, html)
+        .with_context(|| format!("Failed to write {}", staging.display()))?;
+    std::fs::rename(&staging, &path)
+        .with_context(|| format!("Failed to replace {}", path.display()))?;
+    Ok(Some(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Database;
+    use crate::models::ScanResult;
+
+    #[test]
+    fn test_public_dashboard_export() {
+        let db = Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
+
+        let mut router = HostInfo::new(
+            "192.168.1.1".to_string(),
+            "AA:BB:CC:DD:EE:01".to_string(),
+            "Router".to_string(),
+            "ARP".to_string(),
+        );
+        router.hostname = Some("gateway".to_string());
+        let mut camera = HostInfo::new(
+            "192.168.1.20".to_string(),
+            "AA:BB:CC:DD:EE:20".to_string(),
+            "IoT".to_string(),
+            "ARP".to_string(),
+        );
+        camera.hostname = Some("<script>cam</script>".to_string());
+        camera.risk_score = 70;
+        let hosts = vec![router, camera];
+        queries::insert_scan(
+            &conn,
+            &ScanResult {
+                interface_name: "eth0".to_string(),
+                local_ip: "192.168.1.2".to_string(),
+                local_mac: "AA:BB:CC:DD:EE:02".to_string(),
+                subnet: "192.168.1.0/24".to_string(),
+                scan_method: "arp".to_string(),
+                arp_discovered: hosts.len(),
+                icmp_discovered: 0,
+                total_hosts: hosts.len(),
+                scan_duration_ms: 100,
+                active_hosts: hosts,
+                wan: None,
+                ssid: None,
+            },
+        )
+        .unwrap();
+
+        let snapshot = DashboardSnapshot::load(&conn).unwrap();
+        assert_eq!(snapshot.network, "192.168.1.0/24");
+        let html = generate_dashboard_html(&snapshot);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<svg"));
+        assert!(html.contains("gateway"));
+        assert!(html.contains("&lt;script&gt;cam&lt;/script&gt;"));
+        assert!(!html.contains("<script>"));
+
+        // Publishing needs an output path
+        assert!(save_dashboard_settings(
+            &conn,
+            &DashboardSettings {
+                enabled: true,
+                path: None
+            }
+        )
+        .is_err());
+        assert_eq!(refresh_public_dashboard(&conn).unwrap(), None);
+
+        let dir = std::env::temp_dir().join(format!("dashboard-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("index.html");
+        let settings = DashboardSettings {
+            enabled: true,
+            path: Some(path.to_string_lossy().into_owned()),
+        };
+        save_dashboard_settings(&conn, &settings).unwrap();
+        assert_eq!(refresh_public_dashboard(&conn).unwrap(), Some(path.clone()));
+        assert!(std::fs::read_to_string(&path)
+            .unwrap()
+            .contains("Network Health"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,371 @@
+//! Network change journal export
+//!
+//! Replays saved scans over a period into a chronological changelog
+//! (devices joining and leaving, IP and port changes) merged with the alerts
+//! raised in that period. Rendered as Markdown for change-management notes
+//! or CSV for spreadsheets and home-automation logs.
+
+use crate::database::{queries, AlertRecord, DeviceSighting, ScanSnapshot};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use csv::Writer;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Write as _;
+
+/// What kind of change a journal entry records
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JournalEventKind {
+    DeviceJoined,
+    DeviceLeft,
+    IpChanged,
+    PortsChanged,
+    Alert,
+}
+
+impl JournalEventKind {
+    fn label(self) -> &'static str {
+        match self {
+            JournalEventKind::DeviceJoined => "Joined",
+            JournalEventKind::DeviceLeft => "Left",
+            JournalEventKind::IpChanged => "IP changed",
+            JournalEventKind::PortsChanged => "Ports changed",
+            JournalEventKind::Alert => "Alert",
+        }
+    }
+}
+
+/// One line of the change journal
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub time: DateTime<Utc>,
+    pub kind: JournalEventKind,
+    /// Network the change was seen on (not known for alerts)
+    pub subnet: Option<String>,
+    pub device_mac: Option<String>,
+    pub device_name: Option<String>,
+    pub detail: String,
+}
+
+/// Build the change journal for a period (either end open), oldest first
+pub fn build_change_journal(
+    conn: &Connection,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+) -> Result<Vec<JournalEntry>> {
+    let snapshots = queries::get_scan_snapshots(conn, since, until)?;
+    let alerts = queries::get_alerts_between(conn, since, until)?;
+    Ok(journal_from_history(&snapshots, &alerts, since))
+}
+
+/// Work out the journal from scan snapshots (oldest first) and alerts
+///
+/// Scans before `since` only set the starting state of their network. A
+/// network's first scan with no earlier state reports only devices never
+/// seen before, since nothing is known about what changed.
+fn journal_from_history(
+    snapshots: &[ScanSnapshot],
+    alerts: &[AlertRecord],
+    since: Option<DateTime<Utc>>,
+) -> Vec<JournalEntry> {
+    let mut entries = Vec::new();
+    let mut networks: HashMap<&str, HashMap<&str, &DeviceSighting>> = HashMap::new();
+
+    for snapshot in snapshots {
+        let current: HashMap<&str, &DeviceSighting> = snapshot
+            .devices
+            .iter()
+            .map(|device| (device.mac.as_str(), device))
+            .collect();
+        let previous = networks.insert(snapshot.subnet.as_str(), current.clone());
+        if since.is_some_and(|since| snapshot.scan_time < since) {
+            continue;
+        }
+
+        let entry = |kind, device: &DeviceSighting, detail: String| JournalEntry {
+            time: snapshot.scan_time,
+            kind,
+            subnet: Some(snapshot.subnet.clone()),
+            device_mac: Some(device.mac.clone()),
+            device_name: device.name.clone(),
+            detail,
+        };
+
+        let Some(previous) = previous else {
+            for device in snapshot.devices.iter().filter(|d| d.first_sighting) {
+                entries.push(entry(
+                    JournalEventKind::DeviceJoined,
+                    device,
+                    format!("New device at {}", device.ip),
+                ));
+            }
+            continue;
+        };
+
+        for device in &snapshot.devices {
+            let Some(before) = previous.get(device.mac.as_str()) else {
+                let detail = if device.first_sighting {
+                    format!("New device at {}", device.ip)
+                } else {
+                    format!("Back online at {}", device.ip)
+                };
+                entries.push(entry(JournalEventKind::DeviceJoined, device, detail));
+                continue;
+            };
+
+            if before.ip != device.ip {
+                entries.push(entry(
+                    JournalEventKind::IpChanged,
+                    device,
+                    format!("{} -> {}", before.ip, device.ip),
+                ));
+            }
+
+            let was: BTreeSet<u16> = before.open_ports.iter().copied().collect();
+            let now: BTreeSet<u16> = device.open_ports.iter().copied().collect();
+            let opened: Vec<String> = now.difference(&was).map(u16::to_string).collect();
+            let closed: Vec<String> = was.difference(&now).map(u16::to_string).collect();
+            if !opened.is_empty() || !closed.is_empty() {
+                let mut parts = Vec::new();
+                if !opened.is_empty() {
+                    parts.push(format!("opened {}", opened.join(", ")));
+                }
+                if !closed.is_empty() {
+                    parts.push(format!("closed {}", closed.join(", ")));
+                }
+                entries.push(entry(
+                    JournalEventKind::PortsChanged,
+                    device,
+                    parts.join("; "),
+                ));
+            }
+        }
+
+        let mut left: Vec<&&DeviceSighting> = previous
+            .iter()
+            .filter(|(mac, _)| !current.contains_key(*mac))
+            .map(|(_, device)| device)
+            .collect();
+        left.sort_by(|a, b| a.mac.cmp(&b.mac));
+        for device in left {
+            entries.push(entry(
+                JournalEventKind::DeviceLeft,
+                device,
+                format!("Last seen at {}", device.ip),
+            ));
+        }
+    }
+
+    entries.extend(alerts.iter().map(|alert| JournalEntry {
+        time: alert.created_at,
+        kind: JournalEventKind::Alert,
+        subnet: None,
+        device_mac: alert.device_mac.clone(),
+        device_name: None,
+        detail: format!("[{}] {}", alert.severity, alert.message),
+    }));
+
+    // Stable, so changes from one scan keep their order
+    entries.sort_by_key(|entry| entry.time);
+    entries
+}
+
+/// Render the journal as Markdown, grouped by day
+pub fn export_journal_markdown(
+    entries: &[JournalEntry],
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+) -> String {
+    let format_bound = |t: Option<DateTime<Utc>>, open: &str| {
+        t.map(|t| t.format("%Y-%m-%d %H:%M UTC").to_string())
+            .unwrap_or_else(|| open.to_string())
+    };
+
+    let mut out = String::from("# Network change journal\n\n");
+    let _ = writeln!(
+        out,
+        "{} to {} · {} change{}",
+        format_bound(since, "start of history"),
+        format_bound(until, "now"),
+        entries.len(),
+        if entries.len() == 1 { "" } else { "s" }
+    );
+
+    let mut day = None;
+    for entry in entries {
+        let entry_day = entry.time.date_naive();
+        if day != Some(entry_day) {
+            let _ = write!(out, "\n## {}\n\n", entry_day.format("%Y-%m-%d"));
+            day = Some(entry_day);
+        }
+
+        let _ = write!(
+            out,
+            "- {} **{}**",
+            entry.time.format("%H:%M"),
+            entry.kind.label()
+        );
+        if let Some(mac) = &entry.device_mac {
+            let _ = write!(out, " `{}`", mac);
+        }
+        if let Some(name) = &entry.device_name {
+            let _ = write!(out, " ({})", escape_markdown(name));
+        }
+        let _ = write!(out, ": {}", escape_markdown(&entry.detail));
+        if let Some(subnet) = &entry.subnet {
+            let _ = write!(out, " on {}", subnet);
+        }
+        out.push('\n');
+    }
+
+    out
+}
+
+/// Export the journal to CSV
+pub fn export_journal_csv(entries: &[JournalEntry]) -> Result<String> {
+    let mut writer = Writer::from_writer(vec![]);
+
+    writer.write_record(["Time", "Event", "Network", "MAC Address", "Name", "Details"])?;
+
+    for entry in entries {
+        writer.write_record([
+            &entry.time.to_rfc3339(),
+            entry.kind.label(),
+            entry.subnet.as_deref().unwrap_or(""),
+            entry.device_mac.as_deref().unwrap_or(""),
+            entry.device_name.as_deref().unwrap_or(""),
+            &entry.detail,
+        ])?;
+    }
+
+    let csv_data = String::from_utf8(writer.into_inner()?)?;
+    Ok(csv_data)
+}
+
+/// Backslash-escape characters Markdown would treat as formatting
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '`' | '*' | '_' | '[' | ']' | '#' | '|') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{AlertSeverity, AlertType};
+    use chrono::{Duration, TimeZone};
+
+    fn sighting(mac: &str, ip: &str, ports: &[u16], first: bool) -> DeviceSighting {
+        DeviceSighting {
+            mac: mac.to_string(),
+            name: None,
+            ip: ip.to_string(),
+            open_ports: ports.to_vec(),
+            first_sighting: first,
+        }
+    }
+
+    fn snapshot(id: i64, hours: i64, devices: Vec<DeviceSighting>) -> ScanSnapshot {
+        ScanSnapshot {
+            scan_id: id,
+            scan_time: Utc.with_ymd_and_hms(2026, 3, 1, 8, 0, 0).unwrap() + Duration::hours(hours),
+            subnet: "192.168.1.0/24".to_string(),
+            devices,
+        }
+    }
+
+    #[test]
+    fn test_journal_replays_changes_between_scans() {
+        let snapshots = vec![
+            snapshot(1, 0, vec![sighting("aa", "192.168.1.10", &[22], true)]),
+            snapshot(
+                2,
+                2,
+                vec![
+                    sighting("aa", "192.168.1.11", &[22, 80], false),
+                    sighting("bb", "192.168.1.20", &[], true),
+                ],
+            ),
+            snapshot(3, 4, vec![sighting("aa", "192.168.1.11", &[80], false)]),
+        ];
+        let since = Some(snapshots[1].scan_time);
+        let alerts = vec![AlertRecord {
+            id: 1,
+            created_at: snapshots[1].scan_time + Duration::minutes(1),
+            alert_type: AlertType::NewDevice,
+            alert_type_raw: AlertType::NewDevice.to_string(),
+            device_id: None,
+            device_mac: Some("bb".to_string()),
+            device_ip: Some("192.168.1.20".to_string()),
+            message: "New device".to_string(),
+            severity: AlertSeverity::Info,
+            is_read: false,
+            evidence: None,
+            network_id: None,
+            occurrences: 1,
+            last_seen_at: None,
+        }];
+
+        let journal = journal_from_history(&snapshots, &alerts, since);
+        let summary: Vec<(JournalEventKind, Option<&str>, &str)> = journal
+            .iter()
+            .map(|e| (e.kind, e.device_mac.as_deref(), e.detail.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    JournalEventKind::IpChanged,
+                    Some("aa"),
+                    "192.168.1.10 -> 192.168.1.11"
+                ),
+                (JournalEventKind::PortsChanged, Some("aa"), "opened 80"),
+                (
+                    JournalEventKind::DeviceJoined,
+                    Some("bb"),
+                    "New device at 192.168.1.20"
+                ),
+                (JournalEventKind::Alert, Some("bb"), "[info] New device"),
+                (JournalEventKind::PortsChanged, Some("aa"), "closed 22"),
+                (
+                    JournalEventKind::DeviceLeft,
+                    Some("bb"),
+                    "Last seen at 192.168.1.20"
+                ),
+            ]
+        );
+
+        let markdown = export_journal_markdown(&journal, since, None);
+        assert!(markdown.starts_with("# Network change journal\n"));
+        assert!(markdown.contains("## 2026-03-01"));
+        assert!(markdown.contains("- 10:00 **IP changed** `aa`: 192.168.1.10 -> 192.168.1.11"));
+        assert!(markdown.contains("6 changes"));
+
+        let csv = export_journal_csv(&journal).unwrap();
+        assert_eq!(csv.lines().count(), 7);
+        assert!(csv.contains("Ports changed,192.168.1.0/24,aa,,closed 22"));
+    }
+
+    #[test]
+    fn test_first_scan_without_baseline_reports_only_new_devices() {
+        let snapshots = vec![snapshot(
+            1,
+            0,
+            vec![
+                sighting("aa", "192.168.1.10", &[], false),
+                sighting("bb", "192.168.1.20", &[], true),
+            ],
+        )];
+        let journal = journal_from_history(&snapshots, &[], None);
+        assert_eq!(journal.len(), 1);
+        assert_eq!(journal[0].device_mac.as_deref(), Some("bb"));
+        assert_eq!(escape_markdown("a*b_[c]"), "a\\*b\\_\\[c\\]");
+    }
+}
@@ -122,6 +122,12 @@ mod tests {
                 vulnerabilities: vec![],
                 port_warnings: vec![],
                 security_grade: String::new(),
+                filtered_ports: Vec::new(),
+                host_state: None,
+                conflicting_macs: Vec::new(),
+                ipv6_addresses: Vec::new(),
+                device_id: None,
+                last_seen: None,
             },
             HostInfo {
                 ip: "192.168.1.10".to_string(),
@@ -142,6 +148,12 @@ mod tests {
                 vulnerabilities: vec![],
                 port_warnings: vec![],
                 security_grade: String::new(),
+                filtered_ports: Vec::new(),
+                host_state: None,
+                conflicting_macs: Vec::new(),
+                ipv6_addresses: Vec::new(),
+                device_id: None,
+                last_seen: None,
             },
         ];
 
@@ -1,11 +1,18 @@
 //! Export functionality for reports
 //!
-//! Provides PDF, CSV, and JSON export capabilities
+//! Provides PDF, CSV, JSON and static HTML export capabilities, a network change
+//! journal, plus optional signing
 
 pub mod csv;
+pub mod html;
+pub mod journal;
 pub mod json;
 pub mod pdf;
+pub mod signing;
 
 pub use csv::*;
+pub use html::*;
+pub use journal::*;
 pub use json::*;
 pub use pdf::*;
+pub use signing::{verify_export, ExportSignature, ExportSigner};
@@ -4,10 +4,11 @@
 
 use crate::database::NetworkStats;
 use crate::insights::SecurityReport;
-use crate::models::{HostInfo, ScanResult};
+use crate::models::{HostInfo, ScanDiff, ScanResult};
 use anyhow::Result;
 use chrono::Utc;
 use printpdf::*;
+use std::collections::{BTreeMap, HashMap};
 use std::io::BufWriter;
 
 const FONT_SIZE_TITLE: f32 = 24.0;
@@ -15,11 +16,19 @@ const FONT_SIZE_HEADING: f32 = 16.0;
 const FONT_SIZE_SUBHEADING: f32 = 12.0;
 const FONT_SIZE_BODY: f32 = 10.0;
 
+/// Changed devices listed individually in the "changes since last scan" section
+const MAX_CHANGE_LINES: usize = 10;
+
 /// Generate a scan report PDF
+///
+/// `changes` adds a "changes since last scan" section when given. Custom
+/// device fields in `attributes` (keyed by MAC) are listed under each device.
 pub fn generate_scan_report_pdf(
     scan: &ScanResult,
     devices: &[HostInfo],
     stats: Option<&NetworkStats>,
+    changes: Option<&ScanDiff>,
+    attributes: &HashMap<String, BTreeMap<String, String>>,
 ) -> Result<Vec<u8>> {
     let (doc, page1, layer1) =
         PdfDocument::new("Network Scan Report", Mm(210.0), Mm(297.0), "Layer 1");
@@ -63,6 +72,22 @@ pub fn generate_scan_report_pdf(
     );
     y_pos -= 7.0;
 
+    if let Some(wan) = &scan.wan {
+        let provider = match (&wan.isp, &wan.asn) {
+            (Some(isp), Some(asn)) => format!(" ({}, {})", isp, asn),
+            (Some(name), None) | (None, Some(name)) => format!(" ({})", name),
+            (None, None) => String::new(),
+        };
+        current_layer.use_text(
+            format!("External Network: {}{}", wan.public_ip, provider),
+            FONT_SIZE_BODY,
+            Mm(20.0),
+            Mm(y_pos),
+            &font,
+        );
+        y_pos -= 7.0;
+    }
+
     current_layer.use_text(
         format!("Total Devices Found: {}", scan.active_hosts.len()),
         FONT_SIZE_BODY,
@@ -131,6 +156,80 @@ pub fn generate_scan_report_pdf(
         y_pos -= 15.0;
     }
 
+    // === CHANGES SINCE LAST SCAN ===
+    if let Some(changes) = changes {
+        draw_section_header(
+            &current_layer,
+            &font_bold,
+            "Changes Since Last Scan",
+            &mut y_pos,
+        );
+
+        let mut lines = vec![if changes.is_empty() {
+            "• No changes".to_string()
+        } else {
+            format!(
+                "• {} new, {} gone, {} address changes, {} port changes",
+                changes.appeared.len(),
+                changes.disappeared.len(),
+                changes.ip_changes.len(),
+                changes.port_changes.len()
+            )
+        }];
+        let name = |mac: &str, hostname: &Option<String>| {
+            hostname.clone().unwrap_or_else(|| mac.to_string())
+        };
+        let details = changes
+            .appeared
+            .iter()
+            .map(|h| format!("  + {} at {}", name(&h.mac, &h.hostname), h.ip))
+            .chain(
+                changes
+                    .disappeared
+                    .iter()
+                    .map(|h| format!("  - {} (was {})", name(&h.mac, &h.hostname), h.ip)),
+            )
+            .chain(changes.ip_changes.iter().map(|c| {
+                format!(
+                    "  ~ {}: {} -> {}",
+                    name(&c.mac, &c.hostname),
+                    c.old_ip,
+                    c.new_ip
+                )
+            }))
+            .chain(changes.port_changes.iter().map(|c| {
+                let ports = |ports: &[u16]| {
+                    ports
+                        .iter()
+                        .map(u16::to_string)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                };
+                let mut parts = Vec::new();
+                if !c.opened.is_empty() {
+                    parts.push(format!("opened {}", ports(&c.opened)));
+                }
+                if !c.closed.is_empty() {
+                    parts.push(format!("closed {}", ports(&c.closed)));
+                }
+                format!("  ~ {}: {}", name(&c.mac, &c.hostname), parts.join("; "))
+            }));
+        let total = changes.appeared.len()
+            + changes.disappeared.len()
+            + changes.ip_changes.len()
+            + changes.port_changes.len();
+        lines.extend(details.take(MAX_CHANGE_LINES));
+        if total > MAX_CHANGE_LINES {
+            lines.push(format!("  ... and {} more", total - MAX_CHANGE_LINES));
+        }
+
+        for line in lines {
+            current_layer.use_text(line, FONT_SIZE_BODY, Mm(20.0), Mm(y_pos), &font);
+            y_pos -= 7.0;
+        }
+        y_pos -= 8.0;
+    }
+
     // === DEVICE INVENTORY ===
     draw_section_header(&current_layer, &font_bold, "Device Inventory", &mut y_pos);
 
@@ -205,6 +304,16 @@ pub fn generate_scan_report_pdf(
         );
 
         y_pos -= 6.0;
+
+        if let Some(fields) = attributes.get(&device.mac).filter(|f| !f.is_empty()) {
+            let fields = fields
+                .iter()
+                .map(|(name, value)| format!("{}: {}", name, value))
+                .collect::<Vec<_>>()
+                .join("   ");
+            current_layer.use_text(fields, FONT_SIZE_BODY - 2.0, Mm(col2_x), Mm(y_pos), &font);
+            y_pos -= 5.0;
+        }
     }
 
     // Save to bytes
@@ -366,6 +475,8 @@ mod tests {
             total_hosts: 1,
             scan_duration_ms: 12500,
             active_hosts: vec![],
+            wan: None,
+            ssid: None,
         };
 
         let devices = vec![HostInfo {
@@ -387,10 +498,24 @@ mod tests {
             vulnerabilities: vec![],
             port_warnings: vec![],
             security_grade: String::new(),
+            filtered_ports: Vec::new(),
+            host_state: None,
+            conflicting_macs: Vec::new(),
+            ipv6_addresses: Vec::new(),
+            device_id: None,
+            last_seen: None,
         }];
 
-        let result = generate_scan_report_pdf(&scan, &devices, None);
+        let result = generate_scan_report_pdf(&scan, &devices, None, None, &HashMap::new());
         assert!(result.is_ok());
         assert!(!result.unwrap().is_empty());
+
+        let changes = ScanDiff::between(&[], &devices);
+        let attributes = HashMap::from([(
+            "aa:bb:cc:dd:ee:ff".to_string(),
+            BTreeMap::from([("Location".to_string(), "Hall closet".to_string())]),
+        )]);
+        let result = generate_scan_report_pdf(&scan, &devices, None, Some(&changes), &attributes);
+        assert!(!result.unwrap().is_empty());
     }
 }
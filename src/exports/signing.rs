@@ -0,0 +1,273 @@
+//! Export signing
+//!
+//! Detached Ed25519 signatures for exported reports and scan JSON. The key
+//! pair is generated once per install and its seed kept in a key file next
+//! to the database, readable only by the owner, so backups, dumps and
+//! encrypted exports of the database never carry it. Recipients verify an
+//! export against the exported public key to confirm it was not edited after
+//! export.
+
+use anyhow::{anyhow, bail, Context, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use chrono::{DateTime, Utc};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::database::queries::{self, SETTING_EXPORT_SIGNING_KEY};
+
+pub const SIGNATURE_ALGORITHM: &str = "Ed25519";
+
+/// File name of the signing key, in the database directory
+pub const SIGNING_KEY_FILE: &str = "export_signing.key";
+
+/// DER prefix of an Ed25519 SubjectPublicKeyInfo (RFC 8410), followed by the 32 key bytes
+const ED25519_SPKI_PREFIX: [u8; 12] = [
+    0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00,
+];
+
+/// Detached signature over an exported file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportSignature {
+    pub algorithm: String,
+    /// Signer public key (base64, raw 32 bytes)
+    pub public_key: String,
+    /// Signature over the exported bytes (base64)
+    pub signature: String,
+    /// SHA-256 of the exported bytes (hex)
+    pub sha256: String,
+    pub signed_at: DateTime<Utc>,
+}
+
+/// Per-install signing key
+pub struct ExportSigner {
+    key: SigningKey,
+}
+
+impl ExportSigner {
+    /// Generate a fresh key pair
+    pub fn generate() -> Self {
+        let mut seed = [0u8; 32];
+        aes_gcm::aead::rand_core::RngCore::fill_bytes(&mut aes_gcm::aead::OsRng, &mut seed);
+        Self {
+            key: SigningKey::from_bytes(&seed),
+        }
+    }
+
+    /// Key file location for the database at `db_path`
+    pub fn key_path(db_path: &Path) -> PathBuf {
+        db_path.with_file_name(SIGNING_KEY_FILE)
+    }
+
+    /// Load the install's key pair from `key_path`, generating and storing
+    /// one on first use
+    ///
+    /// A seed left in `app_settings` by older builds is moved into the key
+    /// file and removed from the database.
+    pub fn load_or_create(conn: &Connection, key_path: &Path) -> Result<Self> {
+        if key_path.exists() {
+            let seed_hex = fs::read_to_string(key_path).with_context(|| {
+                format!("Failed to read export signing key {}", key_path.display())
+            })?;
+            return Self::from_seed_hex(&seed_hex);
+        }
+
+        if let Some(seed_hex) = queries::get_setting(conn, SETTING_EXPORT_SIGNING_KEY)? {
+            let signer = Self::from_seed_hex(&seed_hex)?;
+            signer.save(key_path)?;
+            queries::delete_setting(conn, SETTING_EXPORT_SIGNING_KEY)?;
+            tracing::info!("Moved export signing key to {}", key_path.display());
+            return Ok(signer);
+        }
+
+        let signer = Self::generate();
+        signer.save(key_path)?;
+        tracing::info!("Generated export signing key");
+        Ok(signer)
+    }
+
+    fn from_seed_hex(seed_hex: &str) -> Result<Self> {
+        let seed: [u8; 32] = hex::decode(seed_hex.trim())
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| anyhow!("Stored export signing key is corrupt"))?;
+        Ok(Self {
+            key: SigningKey::from_bytes(&seed),
+        })
+    }
+
+    /// Write the seed to a new file only the owner can read
+    fn save(&self, key_path: &Path) -> Result<()> {
+        if let Some(parent) = key_path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+
+        let mut file = options.open(key_path).with_context(|| {
+            format!("Failed to create export signing key {}", key_path.display())
+        })?;
+        file.write_all(hex::encode(self.key.to_bytes()).as_bytes())?;
+        file.sync_all()?;
+        Ok(())
+    }
+
+    /// Public key as base64 (raw 32 bytes)
+    pub fn public_key_base64(&self) -> String {
+        BASE64.encode(self.key.verifying_key().as_bytes())
+    }
+
+    /// Public key as a PEM `PUBLIC KEY` block, usable with `openssl pkeyutl -verify`
+    pub fn public_key_pem(&self) -> String {
+        let mut der = ED25519_SPKI_PREFIX.to_vec();
+        der.extend_from_slice(self.key.verifying_key().as_bytes());
+        format!(
+            "-----BEGIN PUBLIC KEY-----\n{}\n-----END PUBLIC KEY-----\n",
+            BASE64.encode(der)
+        )
+    }
+
+    /// Sign exported bytes
+    pub fn sign(&self, data: &[u8]) -> ExportSignature {
+        ExportSignature {
+            algorithm: SIGNATURE_ALGORITHM.to_string(),
+            public_key: self.public_key_base64(),
+            signature: BASE64.encode(self.key.sign(data).to_bytes()),
+            sha256: hex::encode(Sha256::digest(data)),
+            signed_at: Utc::now(),
+        }
+    }
+}
+
+/// Check a detached signature against a trusted public key (base64 or PEM)
+///
+/// Returns `Ok(false)` if the data or signature was altered or the export was
+/// signed by a different key; errors only on malformed input.
+pub fn verify_export(data: &[u8], signature: &ExportSignature, public_key: &str) -> Result<bool> {
+    if signature.algorithm != SIGNATURE_ALGORITHM {
+        bail!("Unsupported signature algorithm: {}", signature.algorithm);
+    }
+
+    let trusted = parse_public_key(public_key)?;
+    if BASE64.encode(trusted.as_bytes()) != signature.public_key {
+        return Ok(false);
+    }
+
+    let sig_bytes: [u8; 64] = BASE64
+        .decode(signature.signature.trim())
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| anyhow!("Malformed signature"))?;
+
+    Ok(trusted
+        .verify(data, &Signature::from_bytes(&sig_bytes))
+        .is_ok())
+}
+
+/// Parse a public key given as raw base64 or an SPKI PEM block
+fn parse_public_key(public_key: &str) -> Result<VerifyingKey> {
+    let body: String = public_key
+        .lines()
+        .filter(|line| !line.starts_with("-----"))
+        .collect::<Vec<_>>()
+        .join("");
+    let bytes = BASE64
+        .decode(body.trim())
+        .context("Public key is not valid base64")?;
+
+    let raw = match bytes.strip_prefix(&ED25519_SPKI_PREFIX[..]) {
+        Some(raw) => raw,
+        None => &bytes[..],
+    };
+    let raw: [u8; 32] = raw
+        .try_into()
+        .map_err(|_| anyhow!("Public key is not an Ed25519 key"))?;
+
+    VerifyingKey::from_bytes(&raw).context("Invalid Ed25519 public key")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Database;
+
+    fn key_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_sign_and_verify_export() {
+        let db = Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
+        let dir = key_dir("signing-key-test");
+        let key_path = ExportSigner::key_path(&dir.join("data.db"));
+
+        let signer = ExportSigner::load_or_create(&conn, &key_path).unwrap();
+        let reloaded = ExportSigner::load_or_create(&conn, &key_path).unwrap();
+        assert_eq!(signer.public_key_base64(), reloaded.public_key_base64());
+
+        // The seed lives only in the key file, readable by the owner alone
+        assert!(queries::get_setting(&conn, SETTING_EXPORT_SIGNING_KEY)
+            .unwrap()
+            .is_none());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&key_path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        let data = br#"{"total_hosts":3}"#;
+        let signature = signer.sign(data);
+        let pem = signer.public_key_pem();
+
+        assert!(verify_export(data, &signature, &pem).unwrap());
+        assert!(verify_export(data, &signature, &signer.public_key_base64()).unwrap());
+        assert!(!verify_export(br#"{"total_hosts":4}"#, &signature, &pem).unwrap());
+
+        // A valid signature from another key is rejected
+        let other = ExportSigner::generate();
+        assert!(!verify_export(data, &other.sign(data), &pem).unwrap());
+
+        assert!(verify_export(data, &signature, "not a key").is_err());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_signing_key_moves_out_of_settings() {
+        let db = Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
+        let dir = key_dir("signing-key-migrate-test");
+        let key_path = ExportSigner::key_path(&dir.join("data.db"));
+
+        let legacy = ExportSigner::generate();
+        queries::set_setting(
+            &conn,
+            SETTING_EXPORT_SIGNING_KEY,
+            &hex::encode(legacy.key.to_bytes()),
+        )
+        .unwrap();
+
+        let signer = ExportSigner::load_or_create(&conn, &key_path).unwrap();
+        assert_eq!(signer.public_key_base64(), legacy.public_key_base64());
+        assert!(key_path.exists());
+        assert!(queries::get_setting(&conn, SETTING_EXPORT_SIGNING_KEY)
+            .unwrap()
+            .is_none());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
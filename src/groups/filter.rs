@@ -0,0 +1,519 @@
+//! Device filter expressions
+//!
+//! Small boolean query language over device fields, e.g.
+//! `type = IoT AND risk > 50 AND last_seen < 7d`.
+//!
+//! - Combinators: `AND`, `OR`, `NOT`, parentheses (AND binds tighter than OR)
+//! - Operators: `=`, `!=`, `>`, `>=`, `<`, `<=`, `~` (case-insensitive contains)
+//! - Values: bare words, `"quoted strings"`, numbers, durations (`30m`, `12h`, `7d`, `2w`)
+//!
+//! Time fields compared against a duration are compared by age:
+//! `last_seen < 7d` matches devices seen within the last seven days.
+//! `tag` matches if any of the device's tags does (`tag != kids` if none does).
+
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Duration, Utc};
+
+/// Device attributes a filter can reference
+#[derive(Debug, Clone, Default)]
+pub struct FilterSubject {
+    pub mac: String,
+    pub ip: Option<String>,
+    pub vendor: Option<String>,
+    pub device_type: Option<String>,
+    pub hostname: Option<String>,
+    pub name: Option<String>,
+    pub os: Option<String>,
+    pub grade: Option<String>,
+    pub risk_score: Option<u8>,
+    pub first_seen: Option<DateTime<Utc>>,
+    pub last_seen: Option<DateTime<Utc>>,
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Mac,
+    Ip,
+    Vendor,
+    Type,
+    Hostname,
+    Name,
+    Os,
+    Grade,
+    Risk,
+    FirstSeen,
+    LastSeen,
+    Tag,
+}
+
+impl Field {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name.to_ascii_lowercase().as_str() {
+            "mac" => Field::Mac,
+            "ip" => Field::Ip,
+            "vendor" => Field::Vendor,
+            "type" | "device_type" => Field::Type,
+            "hostname" | "host" => Field::Hostname,
+            "name" => Field::Name,
+            "os" | "os_guess" => Field::Os,
+            "grade" | "security_grade" => Field::Grade,
+            "risk" | "risk_score" => Field::Risk,
+            "first_seen" => Field::FirstSeen,
+            "last_seen" => Field::LastSeen,
+            "tag" | "tags" => Field::Tag,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Contains,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Text(String),
+    Number(f64),
+    Age(Duration),
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Compare(Field, Op, Value),
+}
+
+/// A parsed, reusable device filter
+#[derive(Debug, Clone)]
+pub struct DeviceFilter {
+    source: String,
+    expr: Expr,
+}
+
+impl DeviceFilter {
+    /// Parse a filter expression
+    pub fn parse(source: &str) -> Result<Self> {
+        let tokens = tokenize(source)?;
+        if tokens.is_empty() {
+            bail!("Filter expression is empty");
+        }
+
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.parse_or()?;
+        if let Some(token) = parser.peek() {
+            bail!("Unexpected '{}' in filter expression", token.text());
+        }
+
+        Ok(Self {
+            source: source.trim().to_string(),
+            expr,
+        })
+    }
+
+    /// The original expression text
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Evaluate the filter against a device
+    pub fn matches(&self, subject: &FilterSubject) -> bool {
+        self.matches_at(subject, Utc::now())
+    }
+
+    /// Evaluate the filter with an explicit reference time for age comparisons
+    pub fn matches_at(&self, subject: &FilterSubject, now: DateTime<Utc>) -> bool {
+        eval(&self.expr, subject, now)
+    }
+}
+
+fn eval(expr: &Expr, subject: &FilterSubject, now: DateTime<Utc>) -> bool {
+    match expr {
+        Expr::And(a, b) => eval(a, subject, now) && eval(b, subject, now),
+        Expr::Or(a, b) => eval(a, subject, now) || eval(b, subject, now),
+        Expr::Not(inner) => !eval(inner, subject, now),
+        Expr::Compare(field, op, value) => compare(*field, *op, value, subject, now),
+    }
+}
+
+fn compare(
+    field: Field,
+    op: Op,
+    value: &Value,
+    subject: &FilterSubject,
+    now: DateTime<Utc>,
+) -> bool {
+    match field {
+        Field::Risk => match (subject.risk_score, value) {
+            (Some(score), Value::Number(n)) => compare_ord(op, f64::from(score), *n),
+            _ => false,
+        },
+        Field::FirstSeen | Field::LastSeen => {
+            let seen = if field == Field::FirstSeen {
+                subject.first_seen
+            } else {
+                subject.last_seen
+            };
+            match (seen, value) {
+                (Some(seen), Value::Age(age)) => {
+                    let actual = (now - seen).num_seconds() as f64;
+                    compare_ord(op, actual, age.num_seconds() as f64)
+                }
+                _ => false,
+            }
+        }
+        Field::Tag => {
+            let Value::Text(expected) = value else {
+                return false;
+            };
+            let any_tag = |op| {
+                subject
+                    .tags
+                    .iter()
+                    .any(|tag| compare_text(op, Some(tag.clone()), expected))
+            };
+            if op == Op::Ne {
+                !any_tag(Op::Eq)
+            } else {
+                any_tag(op)
+            }
+        }
+        Field::Type => {
+            let actual = subject.device_type.as_deref().map(normalize_type);
+            let expected = match value {
+                Value::Text(t) => normalize_type(t),
+                _ => return false,
+            };
+            compare_text(op, actual, &expected)
+        }
+        _ => {
+            let actual = match field {
+                Field::Mac => Some(subject.mac.clone()),
+                Field::Ip => subject.ip.clone(),
+                Field::Vendor => subject.vendor.clone(),
+                Field::Hostname => subject.hostname.clone(),
+                Field::Name => subject.name.clone().or_else(|| subject.hostname.clone()),
+                Field::Os => subject.os.clone(),
+                Field::Grade => subject.grade.clone(),
+                _ => None,
+            };
+            let expected = match value {
+                Value::Text(t) => t,
+                _ => return false,
+            };
+            compare_text(op, actual, expected)
+        }
+    }
+}
+
+fn compare_ord(op: Op, actual: f64, expected: f64) -> bool {
+    match op {
+        Op::Eq => actual == expected,
+        Op::Ne => actual != expected,
+        Op::Gt => actual > expected,
+        Op::Ge => actual >= expected,
+        Op::Lt => actual < expected,
+        Op::Le => actual <= expected,
+        Op::Contains => false,
+    }
+}
+
+fn compare_text(op: Op, actual: Option<String>, expected: &str) -> bool {
+    let Some(actual) = actual else {
+        // Missing values only satisfy "not equal"
+        return op == Op::Ne;
+    };
+    let actual = actual.to_lowercase();
+    let expected = expected.to_lowercase();
+
+    match op {
+        Op::Eq => actual == expected,
+        Op::Ne => actual != expected,
+        Op::Contains => actual.contains(&expected),
+        Op::Gt => actual > expected,
+        Op::Ge => actual >= expected,
+        Op::Lt => actual < expected,
+        Op::Le => actual <= expected,
+    }
+}
+
+/// Map user-friendly type names onto the stored `DeviceType` strings
+fn normalize_type(value: &str) -> String {
+    let upper = value.trim().to_uppercase().replace([' ', '-'], "_");
+    match upper.as_str() {
+        "IOT" => "IOT_DEVICE".to_string(),
+        "TV" => "SMART_TV".to_string(),
+        "AP" => "ACCESS_POINT".to_string(),
+        "PHONE" => "MOBILE".to_string(),
+        "CONSOLE" => "GAME_CONSOLE".to_string(),
+        _ => upper,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Quoted(String),
+    Op(Op),
+    LParen,
+    RParen,
+}
+
+impl Token {
+    fn text(&self) -> String {
+        match self {
+            Token::Word(w) | Token::Quoted(w) => w.clone(),
+            Token::Op(op) => format!("{:?}", op),
+            Token::LParen => "(".to_string(),
+            Token::RParen => ")".to_string(),
+        }
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self, Token::Word(w) if w.eq_ignore_ascii_case(keyword))
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::LParen);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::RParen);
+            }
+            '"' => {
+                chars.next();
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some(ch) => value.push(ch),
+                        None => bail!("Unterminated string in filter expression"),
+                    }
+                }
+                tokens.push(Token::Quoted(value));
+            }
+            '=' | '!' | '<' | '>' | '~' => {
+                chars.next();
+                let followed_by_eq = chars.peek() == Some(&'=');
+                let op = match (c, followed_by_eq) {
+                    ('=', _) => Op::Eq,
+                    ('!', true) => Op::Ne,
+                    ('<', true) => Op::Le,
+                    ('>', true) => Op::Ge,
+                    ('<', false) => Op::Lt,
+                    ('>', false) => Op::Gt,
+                    ('~', _) => Op::Contains,
+                    _ => bail!("Unexpected '!' in filter expression"),
+                };
+                if followed_by_eq && c != '~' {
+                    chars.next();
+                }
+                tokens.push(Token::Op(op));
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&ch) = chars.peek() {
+                    if ch.is_whitespace() || "()\"=!<>~".contains(ch) {
+                        break;
+                    }
+                    word.push(ch);
+                    chars.next();
+                }
+                tokens.push(Token::Word(word));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn parse_or(&mut self) -> Result<Expr> {
+        let mut left = self.parse_and()?;
+        while self.peek().is_some_and(|t| t.is_keyword("OR")) {
+            self.next();
+            left = Expr::Or(Box::new(left), Box::new(self.parse_and()?));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr> {
+        let mut left = self.parse_unary()?;
+        while self.peek().is_some_and(|t| t.is_keyword("AND")) {
+            self.next();
+            left = Expr::And(Box::new(left), Box::new(self.parse_unary()?));
+        }
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr> {
+        match self.next() {
+            Some(token) if token.is_keyword("NOT") => Ok(Expr::Not(Box::new(self.parse_unary()?))),
+            Some(Token::LParen) => {
+                let inner = self.parse_or()?;
+                match self.next() {
+                    Some(Token::RParen) => Ok(inner),
+                    _ => bail!("Missing ')' in filter expression"),
+                }
+            }
+            Some(Token::Word(name)) => self.parse_comparison(&name),
+            Some(token) => bail!("Expected a field name, found '{}'", token.text()),
+            None => bail!("Unexpected end of filter expression"),
+        }
+    }
+
+    fn parse_comparison(&mut self, name: &str) -> Result<Expr> {
+        let field = Field::parse(name).ok_or_else(|| anyhow!("Unknown filter field '{}'", name))?;
+
+        let op = match self.next() {
+            Some(Token::Op(op)) => op,
+            _ => bail!("Expected an operator after '{}'", name),
+        };
+
+        let raw = match self.next() {
+            Some(Token::Word(value)) | Some(Token::Quoted(value)) => value,
+            _ => bail!("Expected a value after '{}'", name),
+        };
+
+        let value = match field {
+            Field::Risk => Value::Number(
+                raw.parse::<f64>()
+                    .map_err(|_| anyhow!("Risk must be a number, got '{}'", raw))?,
+            ),
+            Field::FirstSeen | Field::LastSeen => {
+                if op == Op::Contains {
+                    bail!("'~' is not supported for '{}'", name);
+                }
+                Value::Age(parse_age(&raw)?)
+            }
+            _ => Value::Text(raw),
+        };
+
+        Ok(Expr::Compare(field, op, value))
+    }
+}
+
+/// Parse an age such as `30m`, `12h`, `7d` or `2w` (bare numbers are days)
+fn parse_age(value: &str) -> Result<Duration> {
+    let value = value.trim().to_ascii_lowercase();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let amount: i64 = number
+        .parse()
+        .map_err(|_| anyhow!("Invalid duration '{}'", value))?;
+
+    let age = match unit {
+        "s" => Duration::try_seconds(amount),
+        "m" => Duration::try_minutes(amount),
+        "h" => Duration::try_hours(amount),
+        "" | "d" => Duration::try_days(amount),
+        "w" => Duration::try_weeks(amount),
+        _ => bail!("Invalid duration unit in '{}'", value),
+    };
+    age.ok_or_else(|| anyhow!("Duration '{}' is too long", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn subject(device_type: &str, risk: u8, days_ago: i64) -> FilterSubject {
+        FilterSubject {
+            mac: "aa:bb:cc:00:00:01".to_string(),
+            vendor: Some("Tuya Smart".to_string()),
+            device_type: Some(device_type.to_string()),
+            risk_score: Some(risk),
+            last_seen: Some(Utc::now() - Duration::days(days_ago)),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_example_expression() {
+        let filter = DeviceFilter::parse("type = IoT AND risk > 50 AND last_seen < 7d").unwrap();
+
+        assert!(filter.matches(&subject("IOT_DEVICE", 60, 2)));
+        assert!(!filter.matches(&subject("IOT_DEVICE", 40, 2)));
+        assert!(!filter.matches(&subject("IOT_DEVICE", 60, 10)));
+        assert!(!filter.matches(&subject("PC", 60, 2)));
+    }
+
+    #[test]
+    fn test_precedence_not_and_contains() {
+        let filter =
+            DeviceFilter::parse(r#"NOT (type = PC OR type = laptop) and vendor ~ "tuya""#).unwrap();
+        assert!(filter.matches(&subject("CAMERA", 0, 0)));
+        assert!(!filter.matches(&subject("LAPTOP", 0, 0)));
+
+        let filter = DeviceFilter::parse("type = PC OR type = CAMERA AND risk >= 90").unwrap();
+        assert!(filter.matches(&subject("PC", 0, 0)));
+        assert!(!filter.matches(&subject("CAMERA", 10, 0)));
+    }
+
+    #[test]
+    fn test_tag_matches_any_tag() {
+        let tagged = FilterSubject {
+            tags: vec!["IoT".to_string(), "kids".to_string()],
+            ..subject("CAMERA", 0, 0)
+        };
+        let untagged = subject("CAMERA", 0, 0);
+
+        let filter = DeviceFilter::parse("tag = Kids").unwrap();
+        assert!(filter.matches(&tagged));
+        assert!(!filter.matches(&untagged));
+
+        let filter = DeviceFilter::parse("tag != kids").unwrap();
+        assert!(!filter.matches(&tagged));
+        assert!(filter.matches(&untagged));
+
+        assert!(DeviceFilter::parse("tags ~ io").unwrap().matches(&tagged));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(DeviceFilter::parse("").is_err());
+        assert!(DeviceFilter::parse("colour = red").is_err());
+        assert!(DeviceFilter::parse("risk > high").is_err());
+        assert!(DeviceFilter::parse("risk > 5 AND").is_err());
+        assert!(DeviceFilter::parse("(risk > 5").is_err());
+        assert!(DeviceFilter::parse("last_seen < 3y").is_err());
+        assert!(DeviceFilter::parse("last_seen > 99999999999999d").is_err());
+    }
+}
@@ -0,0 +1,132 @@
+//! Smart groups and device groups
+//!
+//! Saved device filter expressions, evaluated on demand against the device
+//! inventory. Used to scope the device list, exports and alerts. Named
+//! device groups with explicit members carry policies applied to alerting.
+
+pub mod filter;
+pub mod policy;
+
+pub use filter::{DeviceFilter, FilterSubject};
+pub use policy::{apply_group_policies, without_ignored, GroupPolicy, OnlineSchedule};
+
+use std::collections::HashSet;
+
+use anyhow::{anyhow, Result};
+use rusqlite::Connection;
+
+use crate::database::{queries, AlertRecord, DeviceRecord};
+
+impl FilterSubject {
+    /// Build a filter subject from a stored device and its latest risk score
+    pub fn from_device(device: &DeviceRecord, risk_score: Option<u8>) -> Self {
+        Self {
+            mac: device.mac.clone(),
+            ip: device.last_ip.clone(),
+            vendor: device.vendor.clone(),
+            device_type: device.device_type.clone(),
+            hostname: device.hostname.clone(),
+            name: device.custom_name.clone(),
+            os: device.os_guess.clone(),
+            grade: device.security_grade.clone(),
+            risk_score,
+            first_seen: Some(device.first_seen),
+            last_seen: Some(device.last_seen),
+            tags: device.tags.clone(),
+        }
+    }
+}
+
+/// Get all stored devices matching a filter
+pub fn devices_matching(conn: &Connection, filter: &DeviceFilter) -> Result<Vec<DeviceRecord>> {
+    let risk_scores = queries::get_latest_risk_scores(conn)?;
+    let devices = queries::get_all_devices(conn)?;
+
+    Ok(devices
+        .into_iter()
+        .filter(|device| {
+            let subject = FilterSubject::from_device(device, risk_scores.get(&device.id).copied());
+            filter.matches(&subject)
+        })
+        .collect())
+}
+
+/// Get the devices currently in a saved smart group
+pub fn resolve_smart_group(conn: &Connection, group_id: i64) -> Result<Vec<DeviceRecord>> {
+    let group = queries::get_smart_group(conn, group_id)?
+        .ok_or_else(|| anyhow!("Smart group {} not found", group_id))?;
+    let filter = DeviceFilter::parse(&group.expression)?;
+    devices_matching(conn, &filter)
+}
+
+/// Keep only alerts raised for one of the given devices
+pub fn scope_alerts(alerts: Vec<AlertRecord>, devices: &[DeviceRecord]) -> Vec<AlertRecord> {
+    let macs: HashSet<&str> = devices.iter().map(|d| d.mac.as_str()).collect();
+    alerts
+        .into_iter()
+        .filter(|alert| {
+            alert
+                .device_mac
+                .as_deref()
+                .is_some_and(|mac| macs.contains(mac))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Database;
+    use crate::models::{HostInfo, ScanResult};
+
+    fn host(ip: &str, mac: &str, device_type: &str, risk: u8) -> HostInfo {
+        let mut host = HostInfo::new(
+            ip.to_string(),
+            mac.to_string(),
+            device_type.to_string(),
+            "ARP".to_string(),
+        );
+        host.risk_score = risk;
+        host
+    }
+
+    #[test]
+    fn test_resolve_smart_group_uses_latest_risk() {
+        let db = Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
+
+        let hosts = vec![
+            host("192.168.1.10", "aa:00:00:00:00:01", "IOT_DEVICE", 70),
+            host("192.168.1.11", "aa:00:00:00:00:02", "IOT_DEVICE", 20),
+            host("192.168.1.12", "aa:00:00:00:00:03", "PC", 90),
+        ];
+        let scan = ScanResult {
+            interface_name: "eth0".to_string(),
+            local_ip: "192.168.1.2".to_string(),
+            local_mac: "aa:00:00:00:00:ff".to_string(),
+            subnet: "192.168.1.0/24".to_string(),
+            scan_method: "arp".to_string(),
+            arp_discovered: hosts.len(),
+            icmp_discovered: 0,
+            total_hosts: hosts.len(),
+            scan_duration_ms: 100,
+            active_hosts: hosts,
+            wan: None,
+            ssid: None,
+        };
+        queries::insert_scan(&conn, &scan).unwrap();
+
+        let id = queries::create_smart_group(
+            &conn,
+            "Risky IoT",
+            "type = IoT AND risk > 50 AND last_seen < 7d",
+            None,
+        )
+        .unwrap();
+        let members = resolve_smart_group(&conn, id).unwrap();
+        assert_eq!(members.len(), 1);
+        assert_eq!(members[0].mac, "aa:00:00:00:00:01");
+
+        assert!(resolve_smart_group(&conn, id + 1).is_err());
+    }
+}
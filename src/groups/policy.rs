@@ -0,0 +1,405 @@
+//! Device group policies
+//!
+//! A named device group carries a policy for its members: when they are
+//! expected online, which ports they may expose, per-alert-type severity
+//! overrides, or whether they are ignored altogether (e.g. guest devices).
+//! Applied to detected alerts and to the hosts fed into insights.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use anyhow::{bail, Result};
+use chrono::{Datelike, NaiveDateTime, Timelike};
+use serde::{Deserialize, Serialize};
+
+use crate::alerts::{Alert, AlertSeverity, AlertType};
+use crate::database::DeviceGroupRecord;
+use crate::HostInfo;
+
+/// When members of a group are expected to be online
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum OnlineSchedule {
+    /// Around the clock (servers, network gear)
+    Always,
+    /// Local hours `start_hour..end_hour` on the given ISO weekdays
+    /// (1 = Monday, empty for every day). A window ending before its
+    /// start runs past midnight and belongs to the day it started.
+    Hours {
+        #[serde(default)]
+        days: Vec<u8>,
+        start_hour: u8,
+        end_hour: u8,
+    },
+}
+
+impl OnlineSchedule {
+    /// Whether members should be online at a local time
+    pub fn expects_online_at(&self, local: NaiveDateTime) -> bool {
+        let OnlineSchedule::Hours {
+            days,
+            start_hour,
+            end_hour,
+        } = self
+        else {
+            return true;
+        };
+
+        let hour = local.hour() as u8;
+        let (in_window, day) = if start_hour < end_hour {
+            (hour >= *start_hour && hour < *end_hour, local.weekday())
+        } else if hour >= *start_hour {
+            (true, local.weekday())
+        } else {
+            (hour < *end_hour, local.weekday().pred())
+        };
+        in_window && (days.is_empty() || days.contains(&(day.number_from_monday() as u8)))
+    }
+
+    fn validate(&self) -> Result<()> {
+        if let OnlineSchedule::Hours {
+            days,
+            start_hour,
+            end_hour,
+        } = self
+        {
+            if *start_hour > 23 || *end_hour > 24 {
+                bail!("Online hours must be between 0 and 24");
+            }
+            if start_hour == end_hour {
+                bail!("Online hours window is empty");
+            }
+            if let Some(day) = days.iter().find(|d| !(1..=7).contains(*d)) {
+                bail!("Invalid weekday {} (1 = Monday … 7 = Sunday)", day);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Alerting policy shared by the members of a device group
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GroupPolicy {
+    /// Members raise no alerts and are left out of insights
+    pub ignored: bool,
+    /// Offline alerts are raised only while members are expected online,
+    /// and then at High severity
+    pub expected_online: Option<OnlineSchedule>,
+    /// Ports members may have open; any other open port raises an alert
+    pub allowed_ports: Option<Vec<u16>>,
+    /// Severity for member alerts, keyed by alert type (e.g. "DEVICE_OFFLINE")
+    pub severity_overrides: BTreeMap<String, AlertSeverity>,
+}
+
+impl GroupPolicy {
+    /// Check the schedule and that overrides name known alert types
+    pub fn validate(&self) -> Result<()> {
+        if let Some(schedule) = &self.expected_online {
+            schedule.validate()?;
+        }
+        for alert_type in self.severity_overrides.keys() {
+            alert_type
+                .parse::<AlertType>()
+                .map_err(anyhow::Error::msg)?;
+        }
+        Ok(())
+    }
+}
+
+/// Policy of one device, combined over every group it belongs to
+#[derive(Default)]
+struct MemberPolicy<'a> {
+    ignored: bool,
+    schedules: Vec<&'a OnlineSchedule>,
+    /// Union of the allowed ports, with the groups restricting them
+    allowed_ports: Option<(BTreeSet<u16>, Vec<&'a str>)>,
+    /// Most severe override per alert type
+    severity_overrides: HashMap<&'a str, AlertSeverity>,
+}
+
+fn member_policies(groups: &[DeviceGroupRecord]) -> HashMap<String, MemberPolicy<'_>> {
+    let mut policies: HashMap<String, MemberPolicy<'_>> = HashMap::new();
+    for group in groups {
+        for mac in &group.members {
+            let member = policies.entry(mac.to_lowercase()).or_default();
+            member.ignored |= group.policy.ignored;
+            member.schedules.extend(&group.policy.expected_online);
+            if let Some(ports) = &group.policy.allowed_ports {
+                let (allowed, names) = member.allowed_ports.get_or_insert_with(Default::default);
+                allowed.extend(ports);
+                names.push(group.name.as_str());
+            }
+            for (alert_type, severity) in &group.policy.severity_overrides {
+                let current = member
+                    .severity_overrides
+                    .entry(alert_type.as_str())
+                    .or_insert(*severity);
+                *current = (*current).max(*severity);
+            }
+        }
+    }
+    policies
+}
+
+/// Apply group policies to the alerts detected for a scan
+///
+/// Adds an alert for every member exposing ports its groups do not allow,
+/// drops alerts for ignored members and offline alerts outside a member's
+/// expected-online hours, then applies severity overrides. `local_now` is
+/// the local wall-clock time schedules are evaluated at.
+pub fn apply_group_policies(
+    mut alerts: Vec<Alert>,
+    hosts: &[HostInfo],
+    groups: &[DeviceGroupRecord],
+    local_now: NaiveDateTime,
+) -> Vec<Alert> {
+    let policies = member_policies(groups);
+    if policies.is_empty() {
+        return alerts;
+    }
+
+    for host in hosts {
+        let Some((allowed, names)) = policies
+            .get(&host.mac.to_lowercase())
+            .and_then(|p| p.allowed_ports.as_ref())
+        else {
+            continue;
+        };
+        let disallowed: Vec<u16> = host
+            .open_ports
+            .iter()
+            .copied()
+            .filter(|port| !allowed.contains(port))
+            .collect();
+        if disallowed.is_empty() {
+            continue;
+        }
+
+        let hostname_str = host.hostname.as_deref().unwrap_or("Unknown");
+        let list = disallowed
+            .iter()
+            .map(|p| p.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        alerts.push(
+            Alert::new(
+                AlertType::PortBaselineDeviation,
+                format!(
+                    "Ports not allowed by group policy open on {} ({}): {}",
+                    host.ip, hostname_str, list
+                ),
+            )
+            .with_device(&host.mac, &host.ip)
+            .with_evidence(serde_json::json!({
+                "source": "group_policy",
+                "groups": names,
+                "ports": disallowed,
+            })),
+        );
+    }
+
+    alerts
+        .into_iter()
+        .filter_map(|mut alert| {
+            let Some(policy) = alert
+                .device_mac
+                .as_deref()
+                .and_then(|mac| policies.get(&mac.to_lowercase()))
+            else {
+                return Some(alert);
+            };
+            if policy.ignored {
+                return None;
+            }
+            if alert.alert_type == AlertType::DeviceWentOffline && !policy.schedules.is_empty() {
+                if !policy
+                    .schedules
+                    .iter()
+                    .any(|schedule| schedule.expects_online_at(local_now))
+                {
+                    return None;
+                }
+                alert.severity = alert.severity.max(AlertSeverity::High);
+            }
+            if let Some(severity) = policy.severity_overrides.get(alert.alert_type.as_str()) {
+                alert.severity = *severity;
+            }
+            Some(alert)
+        })
+        .collect()
+}
+
+/// Hosts not in any ignored group, for health scoring and recommendations
+pub fn without_ignored(hosts: &[HostInfo], groups: &[DeviceGroupRecord]) -> Vec<HostInfo> {
+    let policies = member_policies(groups);
+    hosts
+        .iter()
+        .filter(|host| {
+            !policies
+                .get(&host.mac.to_lowercase())
+                .is_some_and(|p| p.ignored)
+        })
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{NaiveDate, Utc};
+
+    fn group(name: &str, members: &[&str], policy: GroupPolicy) -> DeviceGroupRecord {
+        DeviceGroupRecord {
+            id: 1,
+            name: name.to_string(),
+            description: None,
+            policy,
+            members: members.iter().map(|m| m.to_string()).collect(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn at(day: u32, hour: u32) -> NaiveDateTime {
+        // 2026-03-02 is a Monday
+        NaiveDate::from_ymd_opt(2026, 3, day)
+            .unwrap()
+            .and_hms_opt(hour, 0, 0)
+            .unwrap()
+    }
+
+    fn offline(mac: &str) -> Alert {
+        Alert::new(AlertType::DeviceWentOffline, "Device went offline").with_device(mac, "10.0.0.2")
+    }
+
+    #[test]
+    fn test_schedule_windows() {
+        let office = OnlineSchedule::Hours {
+            days: vec![1, 2, 3, 4, 5],
+            start_hour: 9,
+            end_hour: 17,
+        };
+        assert!(office.expects_online_at(at(2, 9)));
+        assert!(!office.expects_online_at(at(2, 17)));
+        assert!(!office.expects_online_at(at(7, 10)));
+
+        // Friday night shift runs into Saturday morning
+        let night = OnlineSchedule::Hours {
+            days: vec![5],
+            start_hour: 22,
+            end_hour: 6,
+        };
+        assert!(night.expects_online_at(at(6, 23)));
+        assert!(night.expects_online_at(at(7, 5)));
+        assert!(!night.expects_online_at(at(7, 23)));
+
+        assert!(OnlineSchedule::Hours {
+            days: vec![8],
+            start_hour: 9,
+            end_hour: 17
+        }
+        .validate()
+        .is_err());
+    }
+
+    #[test]
+    fn test_apply_group_policies() {
+        let servers = group(
+            "Servers",
+            &["AA:00:00:00:00:01"],
+            GroupPolicy {
+                expected_online: Some(OnlineSchedule::Always),
+                allowed_ports: Some(vec![22, 443]),
+                ..Default::default()
+            },
+        );
+        let guests = group(
+            "Guests",
+            &["aa:00:00:00:00:02"],
+            GroupPolicy {
+                ignored: true,
+                ..Default::default()
+            },
+        );
+        let office = group(
+            "Office",
+            &["aa:00:00:00:00:03"],
+            GroupPolicy {
+                expected_online: Some(OnlineSchedule::Hours {
+                    days: Vec::new(),
+                    start_hour: 9,
+                    end_hour: 17,
+                }),
+                severity_overrides: BTreeMap::from([(
+                    "IP_CHANGED".to_string(),
+                    AlertSeverity::Medium,
+                )]),
+                ..Default::default()
+            },
+        );
+        let groups = vec![servers, guests, office];
+
+        let mut server = HostInfo::new(
+            "10.0.0.1".to_string(),
+            "aa:00:00:00:00:01".to_string(),
+            "SERVER".to_string(),
+            "ARP".to_string(),
+        );
+        server.open_ports = vec![22, 443, 3306];
+
+        let alerts = vec![
+            offline("aa:00:00:00:00:01"),
+            offline("aa:00:00:00:00:02"),
+            offline("aa:00:00:00:00:03"),
+            Alert::new(AlertType::IpChanged, "IP changed")
+                .with_device("aa:00:00:00:00:03", "10.0.0.3"),
+            offline("aa:00:00:00:00:04"),
+        ];
+        let result = apply_group_policies(alerts, &[server], &groups, at(2, 20));
+
+        let summary: Vec<(AlertType, &str, AlertSeverity)> = result
+            .iter()
+            .map(|a| {
+                (
+                    a.alert_type.clone(),
+                    a.device_mac.as_deref().unwrap(),
+                    a.severity,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    AlertType::DeviceWentOffline,
+                    "aa:00:00:00:00:01",
+                    AlertSeverity::High
+                ),
+                (
+                    AlertType::IpChanged,
+                    "aa:00:00:00:00:03",
+                    AlertSeverity::Medium
+                ),
+                (
+                    AlertType::DeviceWentOffline,
+                    "aa:00:00:00:00:04",
+                    AlertSeverity::Low
+                ),
+                (
+                    AlertType::PortBaselineDeviation,
+                    "aa:00:00:00:00:01",
+                    AlertSeverity::High
+                ),
+            ]
+        );
+        assert!(result[3].message.ends_with(": 3306"));
+
+        let guest = HostInfo::new(
+            "10.0.0.2".to_string(),
+            "aa:00:00:00:00:02".to_string(),
+            "PHONE".to_string(),
+            "ARP".to_string(),
+        );
+        assert!(without_ignored(&[guest], &groups).is_empty());
+    }
+}
@@ -0,0 +1,240 @@
+//! Network baseline compliance
+//!
+//! A baseline freezes the devices and open ports of one scan (see
+//! `queries::set_network_baseline`). Later scans and monitor cycles are
+//! compared with it: devices that were not there, baseline devices that are
+//! gone, and ports open now that were closed when the baseline was taken.
+
+use crate::database::NetworkBaseline;
+use crate::HostInfo;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// One way the network differs from its baseline
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+pub enum BaselineDeviation {
+    /// A device not in the baseline is present
+    UnknownDevice {
+        mac: String,
+        ip: String,
+        hostname: Option<String>,
+    },
+    /// A baseline device was not found
+    MissingDevice {
+        mac: String,
+        /// Address in the baseline
+        ip: Option<String>,
+        hostname: Option<String>,
+    },
+    /// A baseline device has a port open that was closed in the baseline
+    UnexpectedPort { mac: String, ip: String, port: u16 },
+}
+
+impl BaselineDeviation {
+    /// What stays the same while the deviation persists (addresses may change)
+    fn key(&self) -> (u8, String, u16) {
+        match self {
+            BaselineDeviation::UnknownDevice { mac, .. } => (0, mac.to_uppercase(), 0),
+            BaselineDeviation::MissingDevice { mac, .. } => (1, mac.to_uppercase(), 0),
+            BaselineDeviation::UnexpectedPort { mac, port, .. } => (2, mac.to_uppercase(), *port),
+        }
+    }
+}
+
+/// Compare scanned hosts with the baseline
+///
+/// Unknown devices come first, then missing ones, then unexpected ports,
+/// each ordered by MAC.
+pub fn find_baseline_deviations(
+    baseline: &NetworkBaseline,
+    hosts: &[HostInfo],
+) -> Vec<BaselineDeviation> {
+    let expected: HashMap<String, _> = baseline
+        .devices
+        .iter()
+        .map(|device| (device.mac.to_uppercase(), device))
+        .collect();
+    let present: HashSet<String> = hosts.iter().map(|host| host.mac.to_uppercase()).collect();
+
+    let mut unknown = Vec::new();
+    let mut ports = Vec::new();
+    for host in hosts {
+        let Some(device) = expected.get(&host.mac.to_uppercase()) else {
+            unknown.push(BaselineDeviation::UnknownDevice {
+                mac: host.mac.clone(),
+                ip: host.ip.clone(),
+                hostname: host.hostname.clone(),
+            });
+            continue;
+        };
+        ports.extend(
+            host.open_ports
+                .iter()
+                .filter(|port| !device.open_ports.contains(port))
+                .map(|&port| BaselineDeviation::UnexpectedPort {
+                    mac: host.mac.clone(),
+                    ip: host.ip.clone(),
+                    port,
+                }),
+        );
+    }
+    let missing = baseline
+        .devices
+        .iter()
+        .filter(|device| !present.contains(&device.mac.to_uppercase()))
+        .map(|device| BaselineDeviation::MissingDevice {
+            mac: device.mac.clone(),
+            ip: device.ip.clone(),
+            hostname: device.hostname.clone(),
+        });
+
+    let mut deviations: Vec<BaselineDeviation> = unknown.into_iter().chain(missing).collect();
+    ports.sort_by_key(BaselineDeviation::key);
+    deviations.sort_by_key(BaselineDeviation::key);
+    deviations.extend(ports);
+    deviations
+}
+
+/// How closely the network matches its baseline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComplianceReport {
+    pub baseline_taken_at: DateTime<Utc>,
+    pub baseline_devices: usize,
+    /// Baseline devices present with no unexpected ports
+    pub compliant_devices: usize,
+    pub deviations: Vec<BaselineDeviation>,
+    /// Compliant devices out of the baseline and unknown devices
+    pub compliance_percent: u8,
+}
+
+/// Compare scanned hosts with the baseline for the compliance view
+pub fn compliance_report(baseline: &NetworkBaseline, hosts: &[HostInfo]) -> ComplianceReport {
+    let deviations = find_baseline_deviations(baseline, hosts);
+    let mut offending: HashSet<String> = HashSet::new();
+    let mut unknown = 0;
+    for deviation in &deviations {
+        match deviation {
+            BaselineDeviation::UnknownDevice { .. } => unknown += 1,
+            BaselineDeviation::MissingDevice { mac, .. }
+            | BaselineDeviation::UnexpectedPort { mac, .. } => {
+                offending.insert(mac.to_uppercase());
+            }
+        }
+    }
+
+    let baseline_devices = baseline.devices.len();
+    let compliant_devices = baseline_devices - offending.len();
+    let total = baseline_devices + unknown;
+    ComplianceReport {
+        baseline_taken_at: baseline.captured_at,
+        baseline_devices,
+        compliant_devices,
+        deviations,
+        compliance_percent: (compliant_devices * 100)
+            .checked_div(total)
+            .map_or(100, |percent| percent as u8),
+    }
+}
+
+/// Reports each deviation once, until it is resolved
+#[derive(Debug, Default)]
+pub struct DeviationTracker {
+    open: HashSet<(u8, String, u16)>,
+}
+
+impl DeviationTracker {
+    /// The deviations in `current` not reported before
+    ///
+    /// Deviations missing from `current` are resolved and will be reported
+    /// again if they come back.
+    pub fn update(&mut self, current: Vec<BaselineDeviation>) -> Vec<BaselineDeviation> {
+        let previous = std::mem::take(&mut self.open);
+        current
+            .into_iter()
+            .filter(|deviation| {
+                let key = deviation.key();
+                let new = !previous.contains(&key);
+                self.open.insert(key);
+                new
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::BaselineDevice;
+
+    fn host(ip: &str, mac: &str, ports: &[u16]) -> HostInfo {
+        let mut host = HostInfo::new(
+            ip.to_string(),
+            mac.to_string(),
+            "UNKNOWN".to_string(),
+            "ARP".to_string(),
+        );
+        host.open_ports = ports.to_vec();
+        host
+    }
+
+    #[test]
+    fn test_baseline_deviations() {
+        let device = |ip: &str, mac: &str, ports: &[u16]| BaselineDevice {
+            mac: mac.to_string(),
+            ip: Some(ip.to_string()),
+            hostname: None,
+            vendor: None,
+            device_type: None,
+            open_ports: ports.to_vec(),
+        };
+        let baseline = NetworkBaseline {
+            captured_at: Utc::now(),
+            devices: vec![
+                device("192.168.1.10", "AA:BB:CC:DD:EE:10", &[22, 443]),
+                device("192.168.1.11", "AA:BB:CC:DD:EE:11", &[]),
+                device("192.168.1.12", "AA:BB:CC:DD:EE:12", &[80]),
+            ],
+        };
+
+        let hosts = vec![
+            host("192.168.1.10", "aa:bb:cc:dd:ee:10", &[22]),
+            host("192.168.1.12", "AA:BB:CC:DD:EE:12", &[80, 3389]),
+            host("192.168.1.50", "AA:BB:CC:DD:EE:50", &[]),
+        ];
+        let deviations = find_baseline_deviations(&baseline, &hosts);
+        assert_eq!(
+            deviations,
+            vec![
+                BaselineDeviation::UnknownDevice {
+                    mac: "AA:BB:CC:DD:EE:50".to_string(),
+                    ip: "192.168.1.50".to_string(),
+                    hostname: None,
+                },
+                BaselineDeviation::MissingDevice {
+                    mac: "AA:BB:CC:DD:EE:11".to_string(),
+                    ip: Some("192.168.1.11".to_string()),
+                    hostname: None,
+                },
+                BaselineDeviation::UnexpectedPort {
+                    mac: "AA:BB:CC:DD:EE:12".to_string(),
+                    ip: "192.168.1.12".to_string(),
+                    port: 3389,
+                },
+            ]
+        );
+
+        // Only the first device is compliant, out of three plus one unknown
+        let report = compliance_report(&baseline, &hosts);
+        assert_eq!(report.compliant_devices, 1);
+        assert_eq!(report.compliance_percent, 25);
+
+        // Each deviation is reported once, and again after it was resolved
+        let mut tracker = DeviationTracker::default();
+        assert_eq!(tracker.update(deviations.clone()).len(), 3);
+        assert!(tracker.update(deviations.clone()).is_empty());
+        assert_eq!(tracker.update(deviations[1..].to_vec()).len(), 0);
+        assert_eq!(tracker.update(deviations).len(), 1);
+    }
+}
@@ -0,0 +1,253 @@
+//! Cloud service inventory
+//!
+//! Groups passively observed TLS SNI hostnames per device and maps them to
+//! known cloud providers, so it is visible which vendors an IoT device talks to.
+
+use crate::database::{DeviceRecord, SniRecord};
+use crate::network::{geoip_lookup, GeoInfo};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+
+/// Device types treated as IoT for the inventory view
+const IOT_DEVICE_TYPES: &[&str] = &["IOT_DEVICE", "CAMERA", "SMART_TV"];
+
+/// Known provider domain suffixes (checked in order, first match wins)
+const PROVIDER_SUFFIXES: &[(&str, &str)] = &[
+    ("amazonaws.com", "AWS"),
+    ("cloudfront.net", "AWS"),
+    ("amazon.com", "Amazon"),
+    ("amazonalexa.com", "Amazon"),
+    ("googleapis.com", "Google"),
+    ("gstatic.com", "Google"),
+    ("google.com", "Google"),
+    ("gvt1.com", "Google"),
+    ("nest.com", "Google"),
+    ("azure.com", "Microsoft Azure"),
+    ("azure-devices.net", "Microsoft Azure"),
+    ("windows.net", "Microsoft Azure"),
+    ("microsoft.com", "Microsoft"),
+    ("msftconnecttest.com", "Microsoft"),
+    ("icloud.com", "Apple"),
+    ("apple.com", "Apple"),
+    ("tuya.com", "Tuya"),
+    ("tuyaeu.com", "Tuya"),
+    ("tuyaus.com", "Tuya"),
+    ("tuyacn.com", "Tuya"),
+    ("aliyuncs.com", "Alibaba Cloud"),
+    ("xiaomi.com", "Xiaomi"),
+    ("mi.com", "Xiaomi"),
+    ("samsungiotcloud.com", "Samsung SmartThings"),
+    ("smartthings.com", "Samsung SmartThings"),
+    ("samsung.com", "Samsung"),
+    ("meethue.com", "Philips Hue"),
+    ("ring.com", "Ring"),
+    ("ui.com", "Ubiquiti"),
+    ("tplinkcloud.com", "TP-Link"),
+    ("tplinknbu.com", "TP-Link"),
+    ("netflix.com", "Netflix"),
+    ("nflxvideo.net", "Netflix"),
+    ("cloudflare.com", "Cloudflare"),
+    ("akamaized.net", "Akamai"),
+    ("akamaiedge.net", "Akamai"),
+];
+
+/// A single service contacted by a device
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CloudService {
+    pub server_name: String,
+    pub provider: Option<String>,
+    pub hit_count: i64,
+    pub last_seen: DateTime<Utc>,
+    /// Country / ASN of the last server address (needs local GeoIP databases)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub geo: Option<GeoInfo>,
+}
+
+/// Services contacted by one device
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceCloudServices {
+    pub mac: String,
+    pub ip: Option<String>,
+    pub display_name: Option<String>,
+    pub device_type: Option<String>,
+    pub is_iot: bool,
+    /// Distinct providers, sorted by name
+    pub providers: Vec<String>,
+    /// Services, most frequently contacted first
+    pub services: Vec<CloudService>,
+}
+
+/// Cloud service inventory across the network
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CloudServiceInventory {
+    pub devices: Vec<DeviceCloudServices>,
+    /// Number of devices contacting each provider
+    pub provider_device_counts: HashMap<String, usize>,
+}
+
+impl CloudServiceInventory {
+    /// Build the inventory from SNI observations and known devices
+    pub fn build(devices: &[DeviceRecord], observations: &[SniRecord]) -> Self {
+        let known: HashMap<String, &DeviceRecord> =
+            devices.iter().map(|d| (d.mac.to_lowercase(), d)).collect();
+
+        let mut grouped: HashMap<String, Vec<&SniRecord>> = HashMap::new();
+        for obs in observations {
+            grouped
+                .entry(obs.device_mac.to_lowercase())
+                .or_default()
+                .push(obs);
+        }
+
+        let mut provider_device_counts: HashMap<String, usize> = HashMap::new();
+        let mut entries: Vec<DeviceCloudServices> = grouped
+            .into_iter()
+            .map(|(mac, records)| {
+                let device = known.get(&mac);
+
+                let mut services: Vec<CloudService> = records
+                    .iter()
+                    .map(|r| CloudService {
+                        server_name: r.server_name.clone(),
+                        provider: classify_provider(&r.server_name).map(str::to_string),
+                        hit_count: r.hit_count,
+                        last_seen: r.last_seen,
+                        geo: r
+                            .last_server_ip
+                            .as_deref()
+                            .and_then(|ip| ip.parse().ok())
+                            .and_then(geoip_lookup),
+                    })
+                    .collect();
+                services.sort_by(|a, b| {
+                    b.hit_count
+                        .cmp(&a.hit_count)
+                        .then_with(|| a.server_name.cmp(&b.server_name))
+                });
+
+                let providers: BTreeSet<String> =
+                    services.iter().filter_map(|s| s.provider.clone()).collect();
+                for provider in &providers {
+                    *provider_device_counts.entry(provider.clone()).or_insert(0) += 1;
+                }
+
+                let device_type = device.and_then(|d| d.device_type.clone());
+                let is_iot = device_type
+                    .as_deref()
+                    .is_some_and(|t| IOT_DEVICE_TYPES.contains(&t));
+
+                DeviceCloudServices {
+                    ip: device.and_then(|d| d.last_ip.clone()),
+                    display_name: device
+                        .and_then(|d| d.custom_name.clone().or_else(|| d.hostname.clone())),
+                    device_type,
+                    is_iot,
+                    providers: providers.into_iter().collect(),
+                    services,
+                    mac,
+                }
+            })
+            .collect();
+
+        // IoT devices first, then by number of distinct services
+        entries.sort_by(|a, b| {
+            b.is_iot
+                .cmp(&a.is_iot)
+                .then_with(|| b.services.len().cmp(&a.services.len()))
+                .then_with(|| a.mac.cmp(&b.mac))
+        });
+
+        Self {
+            devices: entries,
+            provider_device_counts,
+        }
+    }
+
+    /// Only the IoT devices in the inventory
+    pub fn iot_devices(&self) -> impl Iterator<Item = &DeviceCloudServices> {
+        self.devices.iter().filter(|d| d.is_iot)
+    }
+}
+
+/// Map an SNI hostname to a known cloud provider
+pub fn classify_provider(server_name: &str) -> Option<&'static str> {
+    let name = server_name.trim_end_matches('.').to_lowercase();
+    PROVIDER_SUFFIXES
+        .iter()
+        .find(|(suffix, _)| name == *suffix || name.ends_with(&format!(".{}", suffix)))
+        .map(|(_, provider)| *provider)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sni(mac: &str, name: &str, hits: i64) -> SniRecord {
+        SniRecord {
+            id: 0,
+            device_mac: mac.to_string(),
+            server_name: name.to_string(),
+            last_server_ip: None,
+            first_seen: Utc::now(),
+            last_seen: Utc::now(),
+            hit_count: hits,
+        }
+    }
+
+    fn device(mac: &str, device_type: &str) -> DeviceRecord {
+        DeviceRecord {
+            id: 1,
+            mac: mac.to_string(),
+            first_seen: Utc::now(),
+            last_seen: Utc::now(),
+            last_ip: Some("192.168.1.50".to_string()),
+            vendor: None,
+            device_type: Some(device_type.to_string()),
+            hostname: Some("plug".to_string()),
+            os_guess: None,
+            custom_name: None,
+            notes: None,
+            security_grade: None,
+            tags: Vec::new(),
+            attributes: Default::default(),
+            availability: Default::default(),
+            open_ports: Vec::new(),
+            network_id: None,
+        }
+    }
+
+    #[test]
+    fn test_classify_provider() {
+        assert_eq!(classify_provider("a1.tuyaeu.com"), Some("Tuya"));
+        assert_eq!(
+            classify_provider("iot.eu-west-1.amazonaws.com"),
+            Some("AWS")
+        );
+        assert_eq!(classify_provider("notgoogle.com"), None);
+        assert_eq!(classify_provider("example.org"), None);
+    }
+
+    #[test]
+    fn test_inventory_groups_by_device() {
+        let devices = vec![
+            device("aa:aa:aa:aa:aa:01", "IOT_DEVICE"),
+            device("aa:aa:aa:aa:aa:02", "PC"),
+        ];
+        let observations = vec![
+            sni("aa:aa:aa:aa:aa:01", "a1.tuyaeu.com", 12),
+            sni("aa:aa:aa:aa:aa:01", "time.google.com", 3),
+            sni("aa:aa:aa:aa:aa:02", "www.google.com", 40),
+        ];
+
+        let inventory = CloudServiceInventory::build(&devices, &observations);
+        assert_eq!(inventory.devices.len(), 2);
+
+        let plug = &inventory.devices[0];
+        assert!(plug.is_iot);
+        assert_eq!(plug.services[0].server_name, "a1.tuyaeu.com");
+        assert_eq!(plug.providers, vec!["Google", "Tuya"]);
+        assert_eq!(inventory.iot_devices().count(), 1);
+        assert_eq!(inventory.provider_device_counts.get("Google"), Some(&2));
+    }
+}
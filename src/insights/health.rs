@@ -1,9 +1,13 @@
 //! Network health scoring
 //!
-//! Calculates overall network security health score
+//! Calculates overall network security health score. Findings whose risk
+//! was formally accepted are left out until the acceptance expires.
 
+use crate::database::{RiskAcceptance, RiskFinding};
 use crate::HostInfo;
+use chrono::Utc;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 
 /// Network health status
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -34,17 +38,34 @@ pub struct HealthBreakdown {
 impl NetworkHealth {
     /// Calculate network health from scan results
     pub fn calculate(hosts: &[HostInfo]) -> Self {
+        Self::calculate_with_acceptances(hosts, &[])
+    }
+
+    /// Calculate network health, ignoring findings with an active risk acceptance
+    pub fn calculate_with_acceptances(hosts: &[HostInfo], acceptances: &[RiskAcceptance]) -> Self {
         let total = hosts.len();
         if total == 0 {
             return Self::empty();
         }
 
-        // Calculate security score (0-40 points)
-        let high_risk_count = hosts.iter().filter(|h| h.risk_score >= 50).count();
-        let medium_risk_count = hosts
+        let now = Utc::now();
+        let accepted: HashSet<(&str, RiskFinding)> = acceptances
             .iter()
-            .filter(|h| h.risk_score >= 25 && h.risk_score < 50)
-            .count();
+            .filter(|a| a.is_active(now))
+            .map(|a| (a.device_mac.as_str(), a.finding))
+            .collect();
+        let counts = |finding: RiskFinding, matches: &dyn Fn(&HostInfo) -> bool| {
+            hosts
+                .iter()
+                .filter(|h| matches(h) && !accepted.contains(&(h.mac.as_str(), finding)))
+                .count()
+        };
+
+        // Calculate security score (0-40 points)
+        let high_risk_count = counts(RiskFinding::RiskScore, &|h| h.risk_score >= 50);
+        let medium_risk_count = counts(RiskFinding::RiskScore, &|h| {
+            h.risk_score >= 25 && h.risk_score < 50
+        });
 
         let security = if high_risk_count == 0 && medium_risk_count == 0 {
             40
@@ -54,16 +75,14 @@ impl NetworkHealth {
         };
 
         // Calculate stability score (0-30 points)
-        let responsive_count = hosts
-            .iter()
-            .filter(|h| h.response_time_ms.is_some())
-            .count();
-        let response_rate = responsive_count as f32 / total as f32;
+        let unresponsive_count =
+            counts(RiskFinding::Unresponsive, &|h| h.response_time_ms.is_none());
+        let response_rate = (total - unresponsive_count) as f32 / total as f32;
         let stability = (response_rate * 30.0) as u8;
 
         // Calculate compliance score (0-30 points)
-        let randomized_count = hosts.iter().filter(|h| h.is_randomized).count();
-        let unknown_count = hosts.iter().filter(|h| h.device_type == "UNKNOWN").count();
+        let randomized_count = counts(RiskFinding::RandomizedMac, &|h| h.is_randomized);
+        let unknown_count = counts(RiskFinding::UnknownType, &|h| h.device_type == "UNKNOWN");
         let compliance_penalty = (randomized_count * 3 + unknown_count * 2) as u8;
         let compliance = 30u8.saturating_sub(compliance_penalty);
 
@@ -104,6 +123,16 @@ impl NetworkHealth {
         if unknown_count > 0 {
             insights.push(format!("❓ {} unidentified device types", unknown_count));
         }
+        let accepted_hosts = hosts
+            .iter()
+            .filter(|h| accepted.iter().any(|(mac, _)| *mac == h.mac))
+            .count();
+        if accepted_hosts > 0 {
+            insights.push(format!(
+                "📝 {} devices with accepted risks excluded from scoring",
+                accepted_hosts
+            ));
+        }
         if score >= 80 {
             insights.push("✅ Network health is good".to_string());
         }
@@ -135,3 +164,56 @@ impl NetworkHealth {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn acceptance(mac: &str, finding: RiskFinding, expires_in: Duration) -> RiskAcceptance {
+        let now = Utc::now();
+        RiskAcceptance {
+            id: 1,
+            device_mac: mac.to_string(),
+            finding,
+            reason: "Legacy NAS, isolated VLAN".to_string(),
+            accepted_by: None,
+            accepted_at: now,
+            expires_at: now + expires_in,
+            review_alerted: false,
+        }
+    }
+
+    #[test]
+    fn test_accepted_risks_stop_counting_until_expiry() {
+        let mut nas = HostInfo::new(
+            "192.168.1.5".to_string(),
+            "AA:BB:CC:DD:EE:05".to_string(),
+            "SERVER".to_string(),
+            "ARP+ICMP".to_string(),
+        );
+        nas.risk_score = 60;
+        nas.response_time_ms = Some(2);
+        let hosts = vec![nas];
+
+        let baseline = NetworkHealth::calculate(&hosts);
+        assert_eq!(baseline.breakdown.security, 25);
+
+        let active = [acceptance(
+            "AA:BB:CC:DD:EE:05",
+            RiskFinding::RiskScore,
+            Duration::days(30),
+        )];
+        let accepted = NetworkHealth::calculate_with_acceptances(&hosts, &active);
+        assert_eq!(accepted.breakdown.security, 40);
+        assert!(accepted.score > baseline.score);
+
+        let expired = [acceptance(
+            "AA:BB:CC:DD:EE:05",
+            RiskFinding::RiskScore,
+            Duration::days(-1),
+        )];
+        let lapsed = NetworkHealth::calculate_with_acceptances(&hosts, &expired);
+        assert_eq!(lapsed.score, baseline.score);
+    }
+}
@@ -2,14 +2,22 @@
 //!
 //! Rule-based network analysis and recommendations
 
+pub mod baseline;
+pub mod cloud_services;
 pub mod distribution;
 pub mod health;
 pub mod recommendations;
 pub mod security;
+pub mod simulation;
+pub mod stealth;
 pub mod vulnerability_filter;
 
+pub use baseline::*;
+pub use cloud_services::*;
 pub use distribution::*;
 pub use health::*;
 pub use recommendations::*;
 pub use security::*;
+pub use simulation::*;
+pub use stealth::*;
 pub use vulnerability_filter::*;
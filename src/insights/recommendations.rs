@@ -2,6 +2,7 @@
 //!
 //! Generates actionable security advice based on scan results
 
+use super::stealth::is_arp_only;
 use crate::HostInfo;
 use serde::{Deserialize, Serialize};
 
@@ -141,6 +142,25 @@ impl SecurityReport {
             });
         }
 
+        // Check for hosts that answered ARP but no probes
+        let arp_only: Vec<_> = hosts.iter().filter(|h| is_arp_only(h)).collect();
+
+        if !arp_only.is_empty() {
+            recommendations.push(Recommendation {
+                priority: Priority::Low,
+                category: "Stealth Hosts".to_string(),
+                title: "Hosts answering only ARP".to_string(),
+                description: format!(
+                    "{} device(s) are on the network but ignored ICMP and every probed TCP port. Hosts that stay silent across scans are worth investigating.",
+                    arp_only.len()
+                ),
+                affected_devices: arp_only
+                    .iter()
+                    .map(|h| format!("{} ({})", h.ip, h.mac))
+                    .collect(),
+            });
+        }
+
         // Check for unknown device types
         let unknown: Vec<_> = hosts
             .iter()
@@ -81,6 +81,12 @@ mod tests {
             vulnerabilities: vec![],
             port_warnings: vec![],
             security_grade: String::new(),
+            filtered_ports: Vec::new(),
+            host_state: None,
+            conflicting_macs: Vec::new(),
+            ipv6_addresses: Vec::new(),
+            device_id: None,
+            last_seen: None,
         };
 
         assert_eq!(calculate_security_grade(&host), "A");
@@ -107,6 +113,12 @@ mod tests {
             vulnerabilities: vec![],
             port_warnings: vec![],
             security_grade: String::new(),
+            filtered_ports: Vec::new(),
+            host_state: None,
+            conflicting_macs: Vec::new(),
+            ipv6_addresses: Vec::new(),
+            device_id: None,
+            last_seen: None,
         };
 
         // Add critical vulnerabilities
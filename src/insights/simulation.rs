@@ -0,0 +1,207 @@
+//! What-if simulation of planned network changes
+//!
+//! Applies hypothetical changes (closing a port everywhere, removing a
+//! device, moving a device type onto its own segment) to a copy of the
+//! latest scan and rescores it, so the expected improvement is visible
+//! before anything on the network is touched.
+
+use serde::{Deserialize, Serialize};
+
+use super::health::NetworkHealth;
+use crate::database::RiskAcceptance;
+use crate::network::{calculate_risk_score, DeviceType};
+use crate::HostInfo;
+
+/// A hypothetical change to the network
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PlannedChange {
+    /// Close a port on every device (e.g. disable Telnet everywhere)
+    BlockPort { port: u16 },
+    /// Close a port on one device
+    BlockDevicePort { mac: String, port: u16 },
+    /// Take a device off the network
+    RemoveDevice { mac: String },
+    /// Move every device of a type (e.g. "IOT_DEVICE") onto an isolated
+    /// segment. Their services are then unreachable from the main network,
+    /// so only device-type and MAC penalties remain in their score.
+    SegmentDeviceType { device_type: String },
+}
+
+/// Risk score of one device before and after the planned changes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeviceRiskChange {
+    pub mac: String,
+    pub ip: String,
+    pub before: u8,
+    /// `None` if the device is removed
+    pub after: Option<u8>,
+}
+
+/// Outcome of a what-if simulation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimulationResult {
+    pub before: NetworkHealth,
+    pub after: NetworkHealth,
+    /// Health score difference (positive is an improvement)
+    pub score_change: i16,
+    /// Devices whose risk score changes or that are removed
+    pub devices: Vec<DeviceRiskChange>,
+    /// Changes that affect no device (e.g. a port open nowhere)
+    pub unmatched: Vec<PlannedChange>,
+}
+
+/// Simulate planned changes against scanned hosts and compare network health
+pub fn simulate_changes(
+    hosts: &[HostInfo],
+    changes: &[PlannedChange],
+    acceptances: &[RiskAcceptance],
+) -> SimulationResult {
+    let mut simulated: Vec<Option<HostInfo>> = hosts.iter().cloned().map(Some).collect();
+    let mut touched = vec![false; hosts.len()];
+    let mut unmatched = Vec::new();
+
+    for change in changes {
+        let mut matched = false;
+        for (slot, touched) in simulated.iter_mut().zip(touched.iter_mut()) {
+            let Some(host) = slot.as_mut() else {
+                continue;
+            };
+            let hit = match change {
+                PlannedChange::BlockPort { port } => close_port(host, *port),
+                PlannedChange::BlockDevicePort { mac, port } => {
+                    host.mac.eq_ignore_ascii_case(mac) && close_port(host, *port)
+                }
+                PlannedChange::RemoveDevice { mac } => {
+                    if host.mac.eq_ignore_ascii_case(mac) {
+                        *slot = None;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                PlannedChange::SegmentDeviceType { device_type } => {
+                    if host.device_type.eq_ignore_ascii_case(device_type) {
+                        host.open_ports.clear();
+                        host.port_warnings.clear();
+                        true
+                    } else {
+                        false
+                    }
+                }
+            };
+            *touched |= hit;
+            matched |= hit;
+        }
+        if !matched {
+            unmatched.push(change.clone());
+        }
+    }
+
+    let mut devices = Vec::new();
+    for ((original, slot), touched) in hosts.iter().zip(simulated.iter_mut()).zip(touched) {
+        if !touched {
+            continue;
+        }
+        let after = slot.as_mut().map(|host| {
+            let device_type = host.device_type.parse().unwrap_or(DeviceType::Unknown);
+            host.risk_score =
+                calculate_risk_score(device_type, &host.open_ports, host.is_randomized);
+            host.risk_score
+        });
+        devices.push(DeviceRiskChange {
+            mac: original.mac.clone(),
+            ip: original.ip.clone(),
+            before: original.risk_score,
+            after,
+        });
+    }
+
+    let simulated: Vec<HostInfo> = simulated.into_iter().flatten().collect();
+    let before = NetworkHealth::calculate_with_acceptances(hosts, acceptances);
+    let after = NetworkHealth::calculate_with_acceptances(&simulated, acceptances);
+
+    SimulationResult {
+        score_change: i16::from(after.score) - i16::from(before.score),
+        before,
+        after,
+        devices,
+        unmatched,
+    }
+}
+
+/// Close a port on a host, returning whether it was open
+fn close_port(host: &mut HostInfo, port: u16) -> bool {
+    let open = host.open_ports.len();
+    host.open_ports.retain(|p| *p != port);
+    host.port_warnings.retain(|w| w.port != port);
+    host.open_ports.len() != open
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn host(ip: &str, mac: &str, device_type: &str, ports: &[u16]) -> HostInfo {
+        let mut host = HostInfo::new(
+            ip.to_string(),
+            mac.to_string(),
+            device_type.to_string(),
+            "ARP".to_string(),
+        );
+        host.open_ports = ports.to_vec();
+        host.response_time_ms = Some(3);
+        host.risk_score = calculate_risk_score(
+            device_type.parse().unwrap_or(DeviceType::Unknown),
+            ports,
+            false,
+        );
+        host
+    }
+
+    #[test]
+    fn test_simulated_changes_improve_health() {
+        let hosts = vec![
+            host("10.0.0.2", "aa:00:00:00:00:02", "SERVER", &[22, 23, 445]),
+            host(
+                "10.0.0.3",
+                "aa:00:00:00:00:03",
+                "IOT_DEVICE",
+                &[23, 80, 8080],
+            ),
+            host("10.0.0.4", "aa:00:00:00:00:04", "PC", &[]),
+        ];
+        let changes = vec![
+            PlannedChange::BlockPort { port: 23 },
+            PlannedChange::SegmentDeviceType {
+                device_type: "iot_device".to_string(),
+            },
+            PlannedChange::RemoveDevice {
+                mac: "AA:00:00:00:00:04".to_string(),
+            },
+            PlannedChange::BlockDevicePort {
+                mac: "aa:00:00:00:00:04".to_string(),
+                port: 3389,
+            },
+        ];
+
+        let result = simulate_changes(&hosts, &changes, &[]);
+        assert!(result.score_change > 0);
+        assert_eq!(
+            i16::from(result.after.score) - i16::from(result.before.score),
+            result.score_change
+        );
+
+        assert_eq!(result.devices.len(), 3);
+        assert!(result.devices[0].after.unwrap() < result.devices[0].before);
+        // Only the IoT base score remains once segmented
+        assert_eq!(
+            result.devices[1].after,
+            Some(calculate_risk_score(DeviceType::IotDevice, &[], false))
+        );
+        assert_eq!(result.devices[2].after, None);
+
+        // The PC is gone before its port would be closed
+        assert_eq!(result.unmatched, vec![changes[3].clone()]);
+    }
+}
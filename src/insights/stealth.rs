@@ -0,0 +1,124 @@
+//! Stealth host detection
+//!
+//! Some devices answer ARP (they have to, to be on the LAN) but drop every
+//! ICMP echo and TCP probe. A device that stays that way over several scans
+//! is deliberately hiding or heavily firewalled, and worth a closer look.
+
+use crate::database::DeviceRecord;
+use crate::HostInfo;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// A device that has only ever answered ARP in recent scans
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StealthHost {
+    pub mac: String,
+    pub ip: Option<String>,
+    pub display_name: Option<String>,
+    pub vendor: Option<String>,
+    /// Consecutive recent scans in which the device answered only ARP
+    pub arp_only_scans: u32,
+    pub last_seen: DateTime<Utc>,
+}
+
+/// Whether a scanned host answered ARP and nothing else
+pub fn is_arp_only(host: &HostInfo) -> bool {
+    host.discovery_method == "ARP" && host.open_ports.is_empty() && host.response_time_ms.is_none()
+}
+
+/// Devices that answered only ARP in at least `min_scans` consecutive scans
+///
+/// `streaks` maps MACs to their current ARP-only streak (see
+/// `queries::get_arp_only_streaks`). Longest streaks come first.
+pub fn find_stealth_hosts(
+    devices: &[DeviceRecord],
+    streaks: &HashMap<String, u32>,
+    min_scans: u32,
+) -> Vec<StealthHost> {
+    let mut hosts: Vec<StealthHost> = devices
+        .iter()
+        .filter_map(|device| {
+            let scans = *streaks.get(&device.mac)?;
+            (scans >= min_scans).then(|| StealthHost {
+                mac: device.mac.clone(),
+                ip: device.last_ip.clone(),
+                display_name: device
+                    .custom_name
+                    .clone()
+                    .or_else(|| device.hostname.clone()),
+                vendor: device.vendor.clone(),
+                arp_only_scans: scans,
+                last_seen: device.last_seen,
+            })
+        })
+        .collect();
+
+    hosts.sort_by(|a, b| {
+        b.arp_only_scans
+            .cmp(&a.arp_only_scans)
+            .then_with(|| a.mac.cmp(&b.mac))
+    });
+    hosts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{queries, Database};
+    use crate::ScanResult;
+
+    fn scan(hosts: Vec<HostInfo>) -> ScanResult {
+        ScanResult {
+            interface_name: "eth0".to_string(),
+            local_ip: "192.168.1.1".to_string(),
+            local_mac: "AA:BB:CC:DD:EE:FF".to_string(),
+            subnet: "192.168.1.0/24".to_string(),
+            scan_method: "arp".to_string(),
+            arp_discovered: hosts.len(),
+            icmp_discovered: 0,
+            total_hosts: hosts.len(),
+            scan_duration_ms: 100,
+            active_hosts: hosts,
+            wan: None,
+            ssid: None,
+        }
+    }
+
+    fn host(ip: &str, mac: &str, method: &str) -> HostInfo {
+        HostInfo::new(
+            ip.to_string(),
+            mac.to_string(),
+            "UNKNOWN".to_string(),
+            method.to_string(),
+        )
+    }
+
+    #[test]
+    fn test_stealth_hosts_need_consecutive_arp_only_scans() {
+        let db = Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
+
+        let silent = host("192.168.1.20", "AA:BB:CC:DD:EE:20", "ARP");
+        let mut pc = host("192.168.1.21", "AA:BB:CC:DD:EE:21", "ARP+ICMP");
+        pc.response_time_ms = Some(3);
+        assert!(is_arp_only(&silent));
+        assert!(!is_arp_only(&pc));
+
+        // The PC drops ICMP once, which must not make it a stealth host
+        let flaky = host("192.168.1.21", "AA:BB:CC:DD:EE:21", "ARP");
+        queries::insert_scan(&conn, &scan(vec![silent.clone(), pc.clone()])).unwrap();
+        queries::insert_scan(&conn, &scan(vec![silent.clone(), pc])).unwrap();
+        queries::insert_scan(&conn, &scan(vec![silent, flaky])).unwrap();
+
+        let streaks = queries::get_arp_only_streaks(&conn).unwrap();
+        assert_eq!(streaks.get("AA:BB:CC:DD:EE:20"), Some(&3));
+        assert_eq!(streaks.get("AA:BB:CC:DD:EE:21"), Some(&1));
+
+        let devices = queries::get_all_devices(&conn).unwrap();
+        let stealth = find_stealth_hosts(&devices, &streaks, 3);
+        assert_eq!(stealth.len(), 1);
+        assert_eq!(stealth[0].mac, "AA:BB:CC:DD:EE:20");
+        assert_eq!(stealth[0].ip.as_deref(), Some("192.168.1.20"));
+    }
+}
@@ -5,44 +5,95 @@
 //! - ICMP ping for latency measurement
 //! - TCP port probing for service detection
 //! - SNMP enrichment for device details (optional)
+//! - Active Directory / LDAP enrichment for hostnames (optional)
 //! - SQLite database for historical data storage
 //! - Real-time network monitoring
-//! - Alert detection and notifications
+//! - Scheduled full scans (cron-style)
+//! - Alert detection and notifications (webhooks)
 //! - AI-powered network insights
+//! - Smart groups (saved device filters)
+//! - Peer discovery between instances on the LAN (mDNS)
 
 pub mod alerts;
 pub mod config;
 pub mod database;
 pub mod exports;
+pub mod groups;
 pub mod insights;
 pub mod logging;
 pub mod models;
 pub mod monitor;
 pub mod network;
+pub mod notify;
+pub mod peers;
 pub mod scanner;
+pub mod scheduler;
 
-pub use alerts::{detect_alerts, detect_alerts_without_baseline, has_high_priority_alerts, Alert};
+pub use alerts::{
+    apply_alert_rules, detect_alerts, detect_alerts_with_settings, detect_alerts_without_baseline,
+    detect_alerts_without_baseline_with_settings, has_high_priority_alerts, scan_rule_inputs,
+    Alert, AlertRule, AlertRuleSpec, RuleInput, RuleMatch,
+};
 pub use config::*;
 pub use database::{
-    AlertRecord, AlertSeverity, AlertType, Database, DeviceRecord, NetworkStats, ScanRecord,
+    AlertQuery, AlertRecord, AlertSeverity, AlertType, AtRestEncryption, AttributeCount,
+    AvailabilityWindow, BaselineDevice, ConnectionGuard, CorrelationSignal, Database,
+    DeviceAvailability, DeviceDailySummary, DeviceDetails, DeviceGroupRecord, DeviceMerge,
+    DevicePurgePreview, DevicePurgeSummary, DeviceQuery, DeviceRecord, DeviceServiceRecord,
+    DeviceSettings, DeviceSort, DeviceTypeRule, DeviceTypeRuleRecord, DirectoryComputer,
+    FindingHistoryRecord, FindingKind, LatencyPercentiles, LatencyPoint, LatencyResolution,
+    MaintenanceReport, NetworkBaseline, NetworkProfile, NetworkStats, Page, PortChangeRecord,
+    PortDeviation, PortExposureRecord, PruneSummary, QueryCache, RetentionPolicy, RiskAcceptance,
+    RiskFinding, ScanDailySummary, ScanQuery, ScanRecord, SmartGroupRecord, TagCount,
+    ToolRunRecord, TopologyEdgeRecord, VendorOverrideRecord,
 };
 pub use exports::{
-    export_devices_csv, export_hosts_csv, export_scan_result_json, export_topology_json,
-    generate_network_health_pdf, generate_scan_report_pdf,
+    build_change_journal, export_alerts_csv, export_devices_csv, export_hosts_csv,
+    export_journal_csv, export_journal_markdown, export_port_exposure_csv, export_scan_result_json,
+    export_tool_runs_csv, export_topology_json, generate_dashboard_html,
+    generate_network_health_pdf, generate_scan_report_pdf, load_dashboard_settings,
+    refresh_public_dashboard, save_dashboard_settings, verify_export, DashboardSettings,
+    DashboardSnapshot, ExportSignature, ExportSigner, JournalEntry, JournalEventKind,
 };
+pub use groups::{apply_group_policies, DeviceFilter, FilterSubject, GroupPolicy, OnlineSchedule};
 pub use insights::{
-    DeviceDistribution, NetworkHealth, Recommendation, SecurityReport, VendorDistribution,
+    compliance_report, simulate_changes, BaselineDeviation, CloudServiceInventory,
+    ComplianceReport, DeviceDistribution, NetworkHealth, PlannedChange, Recommendation,
+    SecurityReport, SimulationResult, VendorDistribution,
 };
 pub use models::*;
-pub use monitor::{BackgroundMonitor, MonitoringStatus, NetworkEvent};
+pub use monitor::{
+    load_internet_check_settings, load_monitor_schedule, save_internet_check_settings,
+    save_monitor_schedule, serve_health, BackgroundMonitor, HealthReport, InternetCheckSettings,
+    InternetHealth, MonitorInterface, MonitorProfile, MonitorSchedule, MonitoringStats,
+    MonitoringStatus, NetworkEvent,
+};
 pub use network::{
-    calculate_risk_score, calculate_subnet_ips, dns_scan, find_valid_interface, infer_device_type,
-    is_local_subnet, is_special_address, list_valid_interfaces, lookup_vendor, lookup_vendor_info,
-    DeviceType,
+    annotate_ip_conflicts, calculate_risk_score, calculate_subnet_ips, correlation_score,
+    dns_forward_sweep, dns_scan, dns_scan_with, find_valid_interface, geoip_lookup,
+    infer_device_type, infer_device_type_with_services, is_local_subnet, is_special_address,
+    list_interfaces, list_valid_interfaces, load_device_type_rules, load_dns_resolver,
+    load_dns_sweep_settings, load_geoip, load_vendor_overrides, load_vendor_source,
+    load_wan_settings, lookup_vendor, lookup_vendor_info, match_forward_records,
+    merge_ip_conflicts, save_wan_settings, select_interface, select_interface_for, subnet_chunks,
+    update_oui_database, wan_info, wifi_ssid, DeviceFingerprint, DeviceType, DnsResolver,
+    DnsSweepResult, DnsSweepSettings, GeoInfo, GeoIpSettings, OuiUpdateSummary, RiskProfile,
+    VendorSource, VendorSourceSettings, WanLookupSettings,
+};
+pub use notify::{
+    load_notification_settings, notify_alerts, save_notification_settings, send_due_digests,
+    ChatPlatform, ChatSettings, DesktopNotice, DesktopSettings, EmailSettings, MqttPublisher,
+    MqttSettings, Notification, NotificationSettings, Notifier, SyslogForwarder, SyslogSettings,
+    SyslogTransport, WebhookSettings,
 };
 pub use scanner::{
-    active_arp_scan, guess_os_from_ttl, icmp_scan, snmp_enrich, tcp_probe_scan, IcmpResult,
-    SnmpData, SnmpNeighbor,
+    active_arp_scan, arp_scan_diagnostics, chunked_arp_scan, collect_ssh_host_keys,
+    guess_os_from_ttl, icmp_probe_scan, icmp_scan, inspect_certificates, ldap_enrich_devices,
+    merge_ipv6_neighbors, ndp_discover, parse_ndp_cache, ping_series, snmp_enrich, tcp_probe_scan,
+    tcp_probe_scan_detailed, ArpChunkProgress, ArpDiagnostics, ArpPacketKind, ArpPacketRecord,
+    ArpScan, IcmpResult, IcmpScan, Ipv6Neighbor, LatencyBucket, LatencySummary, LdapConfig,
+    LdapEnrichmentSummary, PacketCapture, PortProbeResult, SnmpData, SnmpNeighbor, SshHostKey,
+    TlsCertificate,
 };
 
 // Re-export logging macros for use across crate
@@ -5,15 +5,37 @@
 //! - ICMP ping (latency measurement)
 //! - TCP port probing (service detection)
 //! - SNMP enrichment (optional)
+//!
+//! Usage:
+//! - `host-discovery` - run a single scan and print the result as JSON
+//! - `host-discovery interfaces` - list the interfaces that can be scanned
+//! - `host-discovery monitor [--interval <secs>]` - continuous monitoring, one JSON event per line
+//! - `host-discovery monitor --tui [--interval <secs>]` - continuous monitoring in a terminal UI
+//!
+//! `monitor --health <addr>` also serves `/healthz` and `/readyz` on `addr`
+//! (e.g. `127.0.0.1:9090`) for supervisors; readiness needs a reachable
+//! database and a recently completed scan.
+//! `--interface <name>` scans a specific interface instead of the automatic choice.
+//! `monitor --passive` also listens for mDNS and ARP traffic between scans,
+//! reporting new devices as soon as they are heard from.
+//! `--arp-diagnostics` runs only the ARP phase and prints every request and
+//! reply with timestamps plus a reply latency summary.
 
-use anyhow::{Context, Result};
-use std::net::Ipv4Addr;
+mod tui;
+
+use anyhow::{bail, Context, Result};
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::Arc;
 use std::time::Instant;
 
 use host_discovery::{
-    active_arp_scan, calculate_risk_score, calculate_subnet_ips, dns_scan, find_valid_interface,
-    guess_os_from_ttl, icmp_scan, infer_device_type, lookup_vendor_info, snmp_enrich,
-    tcp_probe_scan, HostInfo, InterfaceInfo, NeighborInfo, ScanResult, SNMP_ENABLED,
+    arp_scan_diagnostics, calculate_risk_score, calculate_subnet_ips, chunked_arp_scan, dns_scan,
+    guess_os_from_ttl, icmp_probe_scan, infer_device_type, list_interfaces,
+    load_internet_check_settings, load_monitor_schedule, lookup_vendor_info, merge_ip_conflicts,
+    merge_ipv6_neighbors, ndp_discover, select_interface, serve_health, snmp_enrich, sort_hosts,
+    tcp_probe_scan_detailed, wan_info, wifi_ssid, ArpDiagnostics, ArpScan, AtRestEncryption,
+    BackgroundMonitor, Database, HostInfo, HostSort, IcmpScan, InterfaceInfo, NeighborInfo,
+    ScanResult, WanLookupSettings, SNMP_ENABLED,
 };
 
 /// Logs a message to stderr
@@ -23,6 +45,13 @@ macro_rules! log_stderr {
     };
 }
 
+/// Logs a warning to stderr
+macro_rules! log_warn {
+    ($($arg:tt)*) => {
+        eprintln!("[WARN] {}", format!($($arg)*));
+    };
+}
+
 /// Logs an error message to stderr
 macro_rules! log_error {
     ($($arg:tt)*) => {
@@ -30,6 +59,181 @@ macro_rules! log_error {
     };
 }
 
+const USAGE: &str = "Usage: host-discovery [--interface <name>] [--arp-diagnostics]
+       host-discovery interfaces
+       host-discovery monitor [--tui] [--interval <secs>] [--interface <name>] [--health <addr>]
+                              [--passive]";
+
+/// Command selected on the command line
+#[derive(Debug, PartialEq)]
+enum Command {
+    /// One-shot scan (default)
+    Scan {
+        interface: Option<String>,
+    },
+    /// ARP phase only, with a packet log and latency summary
+    ArpDiagnostics {
+        interface: Option<String>,
+    },
+    /// List scannable interfaces
+    Interfaces,
+    /// Continuous monitoring
+    Monitor {
+        tui: bool,
+        interval: Option<u64>,
+        interface: Option<String>,
+        /// Address for the `/healthz` and `/readyz` endpoints
+        health: Option<SocketAddr>,
+        /// Listen for mDNS and ARP traffic between scans
+        passive: bool,
+    },
+    Help,
+}
+
+/// Parses command-line arguments (without the program name)
+fn parse_args<I>(args: I) -> Result<Command>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut args = args.into_iter();
+
+    match args.next().as_deref() {
+        None => Ok(Command::Scan { interface: None }),
+        Some("-h" | "--help" | "help") => Ok(Command::Help),
+        Some("interfaces") => Ok(Command::Interfaces),
+        Some(first) if first.starts_with("--") => {
+            let mut interface = None;
+            let mut diagnostics = false;
+            let mut args = std::iter::once(first.to_string()).chain(args);
+
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--interface" => {
+                        interface = Some(args.next().context("--interface requires a value")?);
+                    }
+                    "--arp-diagnostics" => diagnostics = true,
+                    other => bail!("Unknown scan option: {}\n{}", other, USAGE),
+                }
+            }
+
+            if diagnostics {
+                Ok(Command::ArpDiagnostics { interface })
+            } else {
+                Ok(Command::Scan { interface })
+            }
+        }
+        Some("monitor") => {
+            let mut tui = false;
+            let mut interval = None;
+            let mut interface = None;
+            let mut health = None;
+            let mut passive = false;
+
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--tui" => tui = true,
+                    "--passive" => passive = true,
+                    "--interval" | "-i" => {
+                        let value = args.next().context("--interval requires a value")?;
+                        interval = Some(
+                            value
+                                .parse()
+                                .with_context(|| format!("Invalid interval: {}", value))?,
+                        );
+                    }
+                    "--interface" => {
+                        interface = Some(args.next().context("--interface requires a value")?);
+                    }
+                    "--health" => {
+                        let value = args.next().context("--health requires an address")?;
+                        health = Some(
+                            value
+                                .parse()
+                                .with_context(|| format!("Invalid health address: {}", value))?,
+                        );
+                    }
+                    "-h" | "--help" => return Ok(Command::Help),
+                    other => bail!("Unknown monitor option: {}\n{}", other, USAGE),
+                }
+            }
+
+            Ok(Command::Monitor {
+                tui,
+                interval,
+                interface,
+                health,
+                passive,
+            })
+        }
+        Some(other) => bail!("Unknown command: {}\n{}", other, USAGE),
+    }
+}
+
+/// Runs the background monitor, printing each event as a JSON line until Ctrl-C
+async fn run_monitor(
+    interval: Option<u64>,
+    interface: Option<String>,
+    health: Option<SocketAddr>,
+    passive: bool,
+) -> Result<()> {
+    // An unreachable database makes the monitor unready rather than failing it
+    let db = Database::open(Database::default_path(), &AtRestEncryption::from_env())
+        .map_err(|e| {
+            log_warn!("Monitor database unavailable: {:#}", e);
+        })
+        .ok();
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let monitor = Arc::new(BackgroundMonitor::new());
+    monitor.set_interface(interface).await;
+    monitor.set_passive_discovery(passive);
+    monitor.set_event_store(db.clone()).await;
+    if let Some(db) = &db {
+        match db.read(load_monitor_schedule) {
+            Ok(schedule) => monitor.set_schedule(schedule).await,
+            Err(e) => {
+                log_warn!("Failed to load monitoring schedule: {:#}", e);
+            }
+        }
+        match db.read(load_internet_check_settings) {
+            Ok(settings) => monitor.set_internet_check(Some(settings)).await,
+            Err(e) => {
+                log_warn!("Failed to load internet check settings: {:#}", e);
+            }
+        }
+    }
+    monitor
+        .start(
+            move |event| {
+                let _ = tx.send(event);
+            },
+            interval,
+        )
+        .await
+        .map_err(anyhow::Error::msg)?;
+
+    let health_server = match health {
+        Some(addr) => Some(serve_health(addr, Arc::clone(&monitor), db).await?),
+        None => None,
+    };
+
+    loop {
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => break,
+            event = rx.recv() => match event {
+                Some(event) => println!("{}", serde_json::to_string(&event)?),
+                None => break,
+            },
+        }
+    }
+
+    monitor.stop();
+    if let Some(server) = health_server {
+        server.abort();
+    }
+    Ok(())
+}
+
 /// Performs the complete network scan
 async fn scan_network(interface: &InterfaceInfo) -> Result<ScanResult> {
     let start_time = Instant::now();
@@ -38,11 +242,30 @@ async fn scan_network(interface: &InterfaceInfo) -> Result<ScanResult> {
     log_stderr!("Starting Active ARP + ICMP scan on subnet {}...", subnet);
     log_stderr!("================================================");
 
+    // Public IP lookup runs alongside the scan
+    let wan_lookup = tokio::task::spawn_blocking(|| wan_info(&WanLookupSettings::default()));
+
     // Phase 1: Active ARP Scan
-    let arp_hosts = tokio::task::spawn_blocking({
+    let ArpScan {
+        hosts: arp_hosts,
+        conflicts,
+        ..
+    } = tokio::task::spawn_blocking({
         let interface = interface.clone();
         let ips = ips.clone();
-        move || active_arp_scan(&interface, &ips, &subnet)
+        move || {
+            chunked_arp_scan(&interface, &ips, &subnet, |progress| {
+                if progress.chunks > 1 {
+                    log_stderr!(
+                        "ARP chunk {}/{} done ({}%, {} hosts so far)",
+                        progress.chunk,
+                        progress.chunks,
+                        progress.percent(),
+                        progress.hosts_found
+                    );
+                }
+            })
+        }
     })
     .await
     .context("ARP scan task failed")??;
@@ -50,10 +273,15 @@ async fn scan_network(interface: &InterfaceInfo) -> Result<ScanResult> {
     let arp_count = arp_hosts.len();
 
     // Phase 2 & 3: Run ICMP ping and TCP probe in parallel for faster scanning
-    let (response_times_result, port_results_result) =
-        tokio::join!(icmp_scan(&arp_hosts), tcp_probe_scan(&arp_hosts));
+    let (response_times_result, port_results_result) = tokio::join!(
+        icmp_probe_scan(&arp_hosts),
+        tcp_probe_scan_detailed(&arp_hosts)
+    );
 
-    let response_times = response_times_result?;
+    let IcmpScan {
+        replies: response_times,
+        states: host_states,
+    } = response_times_result?;
     let icmp_count = response_times.len();
     let port_results = port_results_result?;
 
@@ -82,7 +310,8 @@ async fn scan_network(interface: &InterfaceInfo) -> Result<ScanResult> {
             let response_time = icmp_result.map(|r| r.duration.as_millis() as u64);
             let ttl = icmp_result.and_then(|r| r.ttl);
             let os_guess = ttl.map(guess_os_from_ttl);
-            let open_ports = port_results.get(ip).cloned().unwrap_or_default();
+            let ports = port_results.get(ip).cloned().unwrap_or_default();
+            let open_ports = ports.open;
             let snmp = snmp_data.get(ip);
 
             let mut method = match (response_time.is_some(), !open_ports.is_empty()) {
@@ -125,6 +354,8 @@ async fn scan_network(interface: &InterfaceInfo) -> Result<ScanResult> {
             host.os_guess = os_guess;
             host.risk_score = risk_score;
             host.open_ports = open_ports;
+            host.filtered_ports = ports.filtered;
+            host.host_state = host_states.get(ip).copied();
             // DNS hostname takes precedence, fallback to SNMP hostname
             host.hostname = dns_hostnames
                 .get(ip)
@@ -165,12 +396,41 @@ async fn scan_network(interface: &InterfaceInfo) -> Result<ScanResult> {
     local_host.response_time_ms = Some(0);
     active_hosts.push(local_host);
 
-    // Sort by IP
-    active_hosts.sort_by(|a, b| {
-        let ip_a: Ipv4Addr = a.ip.parse().unwrap_or(Ipv4Addr::UNSPECIFIED);
-        let ip_b: Ipv4Addr = b.ip.parse().unwrap_or(Ipv4Addr::UNSPECIFIED);
-        ip_a.cmp(&ip_b)
-    });
+    // IPv6-only devices never answer ARP; find them on the link-local segment
+    let ndp = tokio::task::spawn_blocking({
+        let interface = interface.clone();
+        move || ndp_discover(&interface)
+    })
+    .await
+    .context("IPv6 neighbor discovery task failed")?;
+    match ndp {
+        Ok(neighbors) => {
+            let added = merge_ipv6_neighbors(&mut active_hosts, &neighbors);
+            if added > 0 {
+                log_stderr!("IPv6: {} devices found only over IPv6", added);
+            }
+        }
+        Err(e) => {
+            log_warn!("IPv6 neighbor discovery failed: {}", e);
+        }
+    }
+
+    // Keep every device answering for a contested address
+    merge_ip_conflicts(&mut active_hosts, &conflicts);
+    for host in active_hosts
+        .iter()
+        .filter(|h| !h.conflicting_macs.is_empty())
+    {
+        log_warn!(
+            "IP conflict: {} ({}) shares its address with {}",
+            host.ip,
+            host.mac,
+            host.conflicting_macs.join(", ")
+        );
+    }
+
+    // Sort by IP (IPv6-only devices last)
+    sort_hosts(&mut active_hosts, HostSort::Ip);
 
     let total_hosts = active_hosts.len();
     let scan_duration = start_time.elapsed();
@@ -184,6 +444,23 @@ async fn scan_network(interface: &InterfaceInfo) -> Result<ScanResult> {
         scan_duration.as_secs_f64()
     );
 
+    let wan = match wan_lookup.await {
+        Ok(Ok(wan)) => Some(wan),
+        Ok(Err(e)) => {
+            log_warn!("WAN lookup failed: {:#}", e);
+            None
+        }
+        Err(e) => {
+            log_warn!("WAN lookup task failed: {}", e);
+            None
+        }
+    };
+
+    let name = interface.name.clone();
+    let ssid = tokio::task::spawn_blocking(move || wifi_ssid(&name))
+        .await
+        .unwrap_or_default();
+
     Ok(ScanResult {
         interface_name: interface.name.clone(),
         local_ip: interface.ip.to_string(),
@@ -195,21 +472,83 @@ async fn scan_network(interface: &InterfaceInfo) -> Result<ScanResult> {
         total_hosts,
         scan_duration_ms: scan_duration.as_millis() as u64,
         active_hosts,
+        wan,
+        ssid,
     })
 }
 
 #[tokio::main]
 async fn main() {
-    match run().await {
-        Ok(result) => {
-            match serde_json::to_string_pretty(&result) {
+    let command = match parse_args(std::env::args().skip(1)) {
+        Ok(command) => command,
+        Err(e) => {
+            log_error!("{:#}", e);
+            std::process::exit(2);
+        }
+    };
+
+    let (outcome, interface) = match command {
+        Command::Scan { interface } => (None, interface),
+        Command::Help => {
+            println!("{}", USAGE);
+            return;
+        }
+        Command::Interfaces => {
+            match serde_json::to_string_pretty(&list_interfaces()) {
                 Ok(json) => println!("{}", json),
                 Err(e) => {
-                    log_error!("Failed to serialize scan result to JSON: {}", e);
+                    log_error!("Failed to serialize interfaces to JSON: {}", e);
                     std::process::exit(1);
                 }
             }
+            return;
         }
+        Command::ArpDiagnostics { interface } => {
+            match run_arp_diagnostics(interface.as_deref())
+                .and_then(|report| Ok(serde_json::to_string_pretty(&report)?))
+            {
+                Ok(json) => println!("{}", json),
+                Err(e) => {
+                    log_error!("{:#}", e);
+                    std::process::exit(1);
+                }
+            }
+            return;
+        }
+        Command::Monitor {
+            tui: true,
+            interval,
+            interface,
+            passive,
+            ..
+        } => (Some(tui::run(interval, interface, passive).await), None),
+        Command::Monitor {
+            tui: false,
+            interval,
+            interface,
+            health,
+            passive,
+        } => (
+            Some(run_monitor(interval, interface, health, passive).await),
+            None,
+        ),
+    };
+    if let Some(outcome) = outcome {
+        if let Err(e) = outcome {
+            log_error!("{:#}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    match run(interface.as_deref()).await {
+        Ok(result) => match serde_json::to_string_pretty(&result) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                log_error!("Failed to serialize scan result to JSON: {}", e);
+                std::process::exit(1);
+            }
+        },
         Err(e) => {
             log_error!("{:#}", e);
             std::process::exit(1);
@@ -218,17 +557,41 @@ async fn main() {
 }
 
 /// Main entry point
-async fn run() -> Result<ScanResult> {
+async fn run(interface: Option<&str>) -> Result<ScanResult> {
     log_stderr!("Network Topology Mapper - Host Discovery v0.3.0");
     log_stderr!("Active ARP + ICMP Scanning Mode");
     log_stderr!("================================================");
 
     log_stderr!("Detecting network interfaces...");
-    let interface = find_valid_interface()?;
+    let interface = select_interface(interface)?;
 
     scan_network(&interface).await
 }
 
+/// ARP phase of a scan in diagnostics mode
+fn run_arp_diagnostics(interface: Option<&str>) -> Result<ArpDiagnostics> {
+    let interface = select_interface(interface)?;
+    let (subnet, ips) = calculate_subnet_ips(&interface)?;
+    log_stderr!(
+        "ARP diagnostics on {} ({} targets, {})",
+        subnet,
+        ips.len(),
+        interface.name
+    );
+
+    let report = arp_scan_diagnostics(&interface, &ips, &subnet)?;
+    log_stderr!(
+        "{} requests, {} replies, {} hosts, {} unanswered; latency p50 {:.1}ms p99 {:.1}ms",
+        report.requests_sent,
+        report.replies_received,
+        report.hosts_found,
+        report.unanswered.len(),
+        report.latency.p50_ms,
+        report.latency.p99_ms
+    );
+    Ok(report)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -256,10 +619,68 @@ mod tests {
                 host.open_ports = vec![80];
                 host
             }],
+            wan: None,
+            ssid: None,
         };
 
         let json = serde_json::to_string(&result).unwrap();
         assert!(json.contains("\"interface_name\":\"eth0\""));
         assert!(json.contains("\"open_ports\":[80]"));
     }
+
+    #[test]
+    fn test_parse_args() {
+        let parse = |args: &[&str]| parse_args(args.iter().map(|a| a.to_string()));
+
+        assert_eq!(parse(&[]).unwrap(), Command::Scan { interface: None });
+        assert_eq!(
+            parse(&["--interface", "eth1"]).unwrap(),
+            Command::Scan {
+                interface: Some("eth1".to_string())
+            }
+        );
+        assert_eq!(
+            parse(&["--arp-diagnostics", "--interface", "eth1"]).unwrap(),
+            Command::ArpDiagnostics {
+                interface: Some("eth1".to_string())
+            }
+        );
+        assert_eq!(parse(&["interfaces"]).unwrap(), Command::Interfaces);
+        assert_eq!(
+            parse(&["monitor", "--tui", "--interval", "30"]).unwrap(),
+            Command::Monitor {
+                tui: true,
+                interval: Some(30),
+                interface: None,
+                health: None,
+                passive: false
+            }
+        );
+        assert_eq!(
+            parse(&["monitor", "--interface", "wlan0"]).unwrap(),
+            Command::Monitor {
+                tui: false,
+                interval: None,
+                interface: Some("wlan0".to_string()),
+                health: None,
+                passive: false
+            }
+        );
+        assert_eq!(
+            parse(&["monitor", "--health", "127.0.0.1:9090", "--passive"]).unwrap(),
+            Command::Monitor {
+                tui: false,
+                interval: None,
+                interface: None,
+                health: Some("127.0.0.1:9090".parse().unwrap()),
+                passive: true
+            }
+        );
+        assert!(parse(&["monitor", "--health", "localhost"]).is_err());
+        assert!(parse(&["monitor", "--interval"]).is_err());
+        assert!(parse(&["monitor", "--bogus"]).is_err());
+        assert!(parse(&["--interface"]).is_err());
+        assert!(parse(&["--interface", "eth0", "extra"]).is_err());
+        assert!(parse(&["frobnicate"]).is_err());
+    }
 }
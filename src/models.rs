@@ -1,9 +1,12 @@
 //! Data models for the Network Topology Mapper
 
+use chrono::{DateTime, Utc};
 use pnet::datalink::NetworkInterface;
 use pnet::util::MacAddr;
 use serde::{Deserialize, Serialize};
-use std::net::Ipv4Addr;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+use std::net::{IpAddr, Ipv4Addr};
 
 /// Result structure for the host discovery scan
 #[derive(Debug, Serialize, Deserialize)]
@@ -18,6 +21,178 @@ pub struct ScanResult {
     pub total_hosts: usize,
     pub scan_duration_ms: u64,
     pub active_hosts: Vec<HostInfo>,
+    /// External network the scan was taken from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wan: Option<WanInfo>,
+    /// Wi-Fi network the scanning interface was associated with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssid: Option<String>,
+}
+
+impl ScanResult {
+    /// Changes from this scan to a newer one
+    pub fn diff(&self, newer: &ScanResult) -> ScanDiff {
+        ScanDiff::between(&self.active_hosts, &newer.active_hosts)
+    }
+
+    /// Reorder the hosts
+    pub fn sort_hosts(&mut self, sort: HostSort) {
+        sort_hosts(&mut self.active_hosts, sort);
+    }
+
+    /// The host in this scan that is the same device as `host`
+    pub fn find_host(&self, host: &HostInfo) -> Option<&HostInfo> {
+        self.active_hosts.iter().find(|h| h.same_host(host))
+    }
+}
+
+/// Changes between two scans, matched by MAC address
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScanDiff {
+    /// In the newer scan only
+    pub appeared: Vec<DiffHost>,
+    /// In the older scan only
+    pub disappeared: Vec<DiffHost>,
+    pub ip_changes: Vec<IpChange>,
+    pub port_changes: Vec<PortChange>,
+}
+
+/// A device that appeared or disappeared between two scans
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiffHost {
+    pub mac: String,
+    pub ip: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hostname: Option<String>,
+}
+
+/// A device seen at a different address in the newer scan
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IpChange {
+    pub mac: String,
+    pub old_ip: String,
+    pub new_ip: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hostname: Option<String>,
+}
+
+/// Ports opened or closed on a device between two scans
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PortChange {
+    pub mac: String,
+    /// Address in the newer scan
+    pub ip: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hostname: Option<String>,
+    pub opened: Vec<u16>,
+    pub closed: Vec<u16>,
+}
+
+impl ScanDiff {
+    /// Compare the hosts of an older scan with those of a newer one
+    ///
+    /// Results are ordered by MAC address.
+    pub fn between(before: &[HostInfo], after: &[HostInfo]) -> Self {
+        let before: BTreeMap<&str, &HostInfo> =
+            before.iter().map(|h| (h.mac.as_str(), h)).collect();
+        let after: BTreeMap<&str, &HostInfo> = after.iter().map(|h| (h.mac.as_str(), h)).collect();
+        let diff_host = |host: &HostInfo| DiffHost {
+            mac: host.mac.clone(),
+            ip: host.ip.clone(),
+            hostname: host.hostname.clone(),
+        };
+
+        let mut diff = Self::default();
+        for (mac, old) in &before {
+            if !after.contains_key(mac) {
+                diff.disappeared.push(diff_host(old));
+            }
+        }
+        for (mac, new) in &after {
+            let Some(old) = before.get(mac) else {
+                diff.appeared.push(diff_host(new));
+                continue;
+            };
+
+            if old.ip != new.ip {
+                diff.ip_changes.push(IpChange {
+                    mac: new.mac.clone(),
+                    old_ip: old.ip.clone(),
+                    new_ip: new.ip.clone(),
+                    hostname: new.hostname.clone(),
+                });
+            }
+
+            let was: BTreeSet<u16> = old.open_ports.iter().copied().collect();
+            let now: BTreeSet<u16> = new.open_ports.iter().copied().collect();
+            if was != now {
+                diff.port_changes.push(PortChange {
+                    mac: new.mac.clone(),
+                    ip: new.ip.clone(),
+                    hostname: new.hostname.clone(),
+                    opened: now.difference(&was).copied().collect(),
+                    closed: was.difference(&now).copied().collect(),
+                });
+            }
+        }
+        diff
+    }
+
+    /// Whether the two scans saw the same devices, addresses and ports
+    pub fn is_empty(&self) -> bool {
+        self.appeared.is_empty()
+            && self.disappeared.is_empty()
+            && self.ip_changes.is_empty()
+            && self.port_changes.is_empty()
+    }
+}
+
+/// Public address of the network the scanning host is on
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WanInfo {
+    pub public_ip: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reverse_dns: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub isp: Option<String>,
+    /// Autonomous system number, e.g. "AS15169"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub asn: Option<String>,
+}
+
+/// Reachability of a host as seen by ICMP probing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum HostState {
+    /// Answered with an echo reply
+    Up,
+    /// Reported unreachable by a router, or its TTL expired on the way
+    Down,
+    /// Present on the LAN but ICMP is blocked or administratively prohibited
+    Filtered,
+}
+
+impl HostState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Up => "UP",
+            Self::Down => "DOWN",
+            Self::Filtered => "FILTERED",
+        }
+    }
+}
+
+impl std::str::FromStr for HostState {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_uppercase().as_str() {
+            "UP" => Ok(Self::Up),
+            "DOWN" => Ok(Self::Down),
+            "FILTERED" => Ok(Self::Filtered),
+            _ => Err(format!("Unknown host state: {}", s)),
+        }
+    }
 }
 
 /// Information about a discovered host
@@ -45,6 +220,24 @@ pub struct HostInfo {
     pub risk_score: u8,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub open_ports: Vec<u16>,
+    /// Probed ports that timed out or drew an ICMP unreachable (firewalled)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub filtered_ports: Vec<u16>,
+    /// Reachability as seen by ICMP probing (`None` if not probed)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host_state: Option<HostState>,
+    /// Other MACs that answered for this IP in the same scan (address conflict)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub conflicting_macs: Vec<String>,
+    /// IPv6 addresses seen for this MAC via NDP (link-local and global)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ipv6_addresses: Vec<String>,
+    /// Stored device this host belongs to (following MAC merges), once known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device_id: Option<i64>,
+    /// When the stored device was last seen
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_seen: Option<DateTime<Utc>>,
     pub discovery_method: String,
     // DNS/SNMP hostname
     pub hostname: Option<String>,
@@ -87,10 +280,89 @@ impl HostInfo {
             vulnerabilities: Vec::new(),
             port_warnings: Vec::new(),
             security_grade: String::new(),
+            filtered_ports: Vec::new(),
+            host_state: None,
+            conflicting_macs: Vec::new(),
+            ipv6_addresses: Vec::new(),
+            device_id: None,
+            last_seen: None,
+        }
+    }
+
+    /// Whether two hosts are the same device: same stored device when both
+    /// are known, otherwise the same MAC
+    pub fn same_host(&self, other: &HostInfo) -> bool {
+        match (self.device_id, other.device_id) {
+            (Some(a), Some(b)) => a == b,
+            _ => self.mac.eq_ignore_ascii_case(&other.mac),
         }
     }
 }
 
+/// Order for the hosts of a scan
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HostSort {
+    /// By address, IPv6-only hosts last
+    #[default]
+    Ip,
+    /// Highest risk score first
+    Risk,
+    /// Most recently seen first, hosts never stored last
+    LastSeen,
+    /// By hostname (case-insensitive), hosts without one last
+    Hostname,
+}
+
+impl HostSort {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HostSort::Ip => "ip",
+            HostSort::Risk => "risk",
+            HostSort::LastSeen => "last_seen",
+            HostSort::Hostname => "hostname",
+        }
+    }
+}
+
+impl std::str::FromStr for HostSort {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ip" => Ok(HostSort::Ip),
+            "risk" => Ok(HostSort::Risk),
+            "last_seen" => Ok(HostSort::LastSeen),
+            "hostname" => Ok(HostSort::Hostname),
+            _ => Err(format!("Unknown host sort: {}", s)),
+        }
+    }
+}
+
+/// Sort hosts in place; ties keep address order
+pub fn sort_hosts(hosts: &mut [HostInfo], sort: HostSort) {
+    let ip = |host: &HostInfo| {
+        host.ip
+            .parse::<IpAddr>()
+            .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED))
+    };
+    hosts.sort_by(|a, b| {
+        let order = match sort {
+            HostSort::Ip => Ordering::Equal,
+            HostSort::Risk => b.risk_score.cmp(&a.risk_score),
+            // `None` sorts before `Some`, so reversing puts never-stored hosts last
+            HostSort::LastSeen => b.last_seen.cmp(&a.last_seen),
+            HostSort::Hostname => match (&a.hostname, &b.hostname) {
+                (Some(x), Some(y)) => x.to_lowercase().cmp(&y.to_lowercase()),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            },
+        };
+        order.then_with(|| ip(a).cmp(&ip(b)))
+    });
+}
+
 /// Information about a network neighbor (from LLDP/CDP)
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct NeighborInfo {
@@ -115,6 +387,19 @@ pub struct InterfaceInfo {
     pub pnet_interface: NetworkInterface,
 }
 
+/// A usable interface address, as offered for manual selection
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct InterfaceCandidate {
+    pub name: String,
+    pub ip: String,
+    pub mac: String,
+    pub prefix_len: u8,
+    /// Selection priority (higher is preferred)
+    pub score: u32,
+    /// Whether automatic selection would pick this address
+    pub is_default: bool,
+}
+
 /// CVE vulnerability information
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct VulnerabilityInfo {
@@ -0,0 +1,1076 @@
+//! Change detection between monitor scans
+//!
+//! Compares each scan's devices with what the monitor knew before and emits
+//! lifecycle events (new device, offline, back online, IP changed), honouring
+//! per-device offline grace periods, offline alert suppression and mutes. Scan
+//! hysteresis keeps a device at the edge of Wi-Fi range from turning every
+//! missed scan into an offline/new pair; one that keeps appearing and
+//! disappearing is reported as flapping instead. Ping times are compared
+//! with each device's recent ones to report slowdowns.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::net::Ipv4Addr;
+use std::time::Instant;
+
+use chrono::Utc;
+
+use super::events::{DeviceSnapshot, NetworkEvent};
+use crate::config::{
+    FLAP_MIN_CHANGES, FLAP_WINDOW_SECS, LATENCY_BASELINE_SAMPLES, LATENCY_DEGRADED_FACTOR,
+    LATENCY_DEGRADED_MIN_MS,
+};
+use crate::database::DeviceSettings;
+
+/// How long an offline device is remembered for "came online" correlation
+/// (on top of its grace period)
+const OFFLINE_RETENTION_SECS: u64 = 3600;
+
+#[derive(Debug, Clone)]
+struct OfflineDeviceSnapshot {
+    device: DeviceSnapshot,
+    since: Instant,
+    /// Whether a `DeviceWentOffline` event was emitted (false while in grace period)
+    notified: bool,
+}
+
+/// Recent ping times of a device
+#[derive(Debug, Clone, Default)]
+struct LatencyHistory {
+    samples: VecDeque<u64>,
+    /// Whether the last reply was degraded, so a slow spell is reported once
+    degraded: bool,
+}
+
+impl LatencyHistory {
+    /// Median of the recent replies, once there are enough of them
+    fn baseline(&self) -> Option<u64> {
+        if self.samples.len() < LATENCY_BASELINE_SAMPLES {
+            return None;
+        }
+        let mut sorted: Vec<u64> = self.samples.iter().copied().collect();
+        sorted.sort_unstable();
+        Some(sorted[sorted.len() / 2])
+    }
+}
+
+/// Appearances and disappearances of a device, for flapping detection
+#[derive(Debug, Clone, Default)]
+struct PresenceHistory {
+    /// Whether the device was in the last scan
+    present: bool,
+    /// When it appeared or disappeared, within the flapping window
+    changes: VecDeque<Instant>,
+    /// Whether `DeviceFlapping` was emitted for the current spell
+    flapping: bool,
+}
+
+/// How many scans it takes to change a device's state
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hysteresis {
+    /// Consecutive scans a device must be missing from before it counts as offline
+    pub missed_scans: u32,
+    /// Consecutive scans an unknown device must be seen in before it is reported new
+    pub confirm_scans: u32,
+}
+
+impl Default for Hysteresis {
+    /// React to every scan
+    fn default() -> Self {
+        Self {
+            missed_scans: 1,
+            confirm_scans: 1,
+        }
+    }
+}
+
+/// Device state carried from one scan to the next
+#[derive(Debug, Clone, Default)]
+pub struct ChangeDetector {
+    hysteresis: Hysteresis,
+    /// Devices considered online (MAC -> snapshot)
+    online: HashMap<String, DeviceSnapshot>,
+    /// Recently-offline devices, for "came online" correlation
+    offline: HashMap<String, OfflineDeviceSnapshot>,
+    /// Online devices missing from recent scans: (scans missed, first missed)
+    missed: HashMap<String, (u32, Instant)>,
+    /// Unknown devices not yet confirmed new: scans seen in a row
+    unconfirmed: HashMap<String, u32>,
+    /// Presence changes of known devices, for flapping detection
+    presence: HashMap<String, PresenceHistory>,
+    /// Ping times of known devices, for latency degradation
+    latency: HashMap<String, LatencyHistory>,
+}
+
+impl ChangeDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Detector needing several scans to report a device offline or new
+    pub fn with_hysteresis(hysteresis: Hysteresis) -> Self {
+        Self {
+            hysteresis: Hysteresis {
+                missed_scans: hysteresis.missed_scans.max(1),
+                confirm_scans: hysteresis.confirm_scans.max(1),
+            },
+            ..Self::default()
+        }
+    }
+
+    /// Devices currently considered online
+    pub fn online_count(&self) -> usize {
+        self.online.len()
+    }
+
+    /// Snapshots of the devices currently considered online
+    pub fn online_devices(&self) -> Vec<DeviceSnapshot> {
+        self.online.values().cloned().collect()
+    }
+
+    /// Drop everything known about a device
+    pub fn forget(&mut self, mac: &str) {
+        self.online.remove(mac);
+        self.offline.remove(mac);
+        self.missed.remove(mac);
+        self.unconfirmed.remove(mac);
+        self.presence.remove(mac);
+        self.latency.remove(mac);
+    }
+
+    /// Compare a scan taken at `now` with the previous one and emit events
+    ///
+    /// A device goes offline after missing the configured number of scans
+    /// in a row, and an unknown one is new after being seen in that many.
+    /// Devices with an offline grace period are only reported once they have
+    /// been missing for that long (counted from the first missed scan);
+    /// devices with offline alerts disabled are never reported offline (nor
+    /// back online). Muted devices are not reported offline until their mute
+    /// ends, and their flapping and slowdowns are not reported at all. A MAC
+    /// listed more than once (claiming several addresses) is tracked by its
+    /// first entry.
+    pub fn observe<F>(
+        &mut self,
+        callback: &F,
+        device_settings: &HashMap<String, DeviceSettings>,
+        current: &[DeviceSnapshot],
+        now: Instant,
+    ) where
+        F: Fn(NetworkEvent) + ?Sized,
+    {
+        let grace_secs = |mac: &str| {
+            device_settings
+                .get(mac)
+                .and_then(|s| s.offline_grace_secs)
+                .unwrap_or(0)
+        };
+        let wall_now = Utc::now();
+        let muted = |mac: &str| {
+            device_settings
+                .get(mac)
+                .is_some_and(|s| s.is_muted(wall_now))
+        };
+        let offline_alerts_enabled = |mac: &str| {
+            device_settings
+                .get(mac)
+                .map(|s| s.offline_alerts_enabled && !s.is_muted(wall_now))
+                .unwrap_or(true)
+        };
+        let callback = &|event: NetworkEvent| {
+            let quiet = matches!(
+                event,
+                NetworkEvent::DeviceFlapping { .. } | NetworkEvent::LatencyDegraded { .. }
+            ) && event.device_macs().into_iter().all(muted);
+            if !quiet {
+                callback(event);
+            }
+        };
+
+        self.offline.retain(|mac, snap| {
+            now.duration_since(snap.since).as_secs() <= OFFLINE_RETENTION_SECS + grace_secs(mac)
+        });
+
+        let current_macs: HashMap<&str, &DeviceSnapshot> =
+            current.iter().rev().map(|d| (d.mac.as_str(), d)).collect();
+
+        self.track_presence(callback, &current_macs, now);
+
+        // Build next online device map while emitting change events.
+        let mut next_online: HashMap<String, DeviceSnapshot> =
+            HashMap::with_capacity(current.len());
+
+        // Online devices missing from this scan stay online until they have
+        // missed enough scans, then start their grace period.
+        for (mac, prev_device) in self.online.iter() {
+            if current_macs.contains_key(mac.as_str()) {
+                continue;
+            }
+            let (missed, first_missed) = self.missed.entry(mac.clone()).or_insert((0, now));
+            *missed += 1;
+            if *missed < self.hysteresis.missed_scans {
+                next_online.insert(mac.clone(), prev_device.clone());
+                continue;
+            }
+            let since = *first_missed;
+            self.missed.remove(mac);
+            self.offline.insert(
+                mac.clone(),
+                OfflineDeviceSnapshot {
+                    device: prev_device.clone(),
+                    since,
+                    notified: false,
+                },
+            );
+        }
+
+        // Report devices whose grace period has elapsed.
+        for (mac, snap) in self.offline.iter_mut() {
+            if snap.notified
+                || current_macs.contains_key(mac.as_str())
+                || !offline_alerts_enabled(mac)
+            {
+                continue;
+            }
+            if now.duration_since(snap.since).as_secs() < grace_secs(mac) {
+                continue;
+            }
+
+            tracing::debug!("[MONITOR] Device offline: {} ({})", snap.device.ip, mac);
+            callback(NetworkEvent::DeviceWentOffline {
+                mac: mac.clone(),
+                last_ip: snap.device.ip.clone(),
+                hostname: snap.device.hostname.clone(),
+                network: snap.device.network.clone(),
+            });
+            snap.notified = true;
+        }
+
+        let mut visited = HashSet::with_capacity(current.len());
+        for device in current {
+            if !visited.insert(device.mac.as_str()) {
+                continue;
+            }
+            self.missed.remove(&device.mac);
+
+            if let Some(prev_device) = self.online.get(&device.mac) {
+                if prev_device.ip != device.ip {
+                    tracing::debug!(
+                        "[MONITOR] IP changed: {} -> {} ({})",
+                        prev_device.ip,
+                        device.ip,
+                        device.mac
+                    );
+                    callback(NetworkEvent::DeviceIpChanged {
+                        mac: device.mac.clone(),
+                        old_ip: prev_device.ip.clone(),
+                        new_ip: device.ip.clone(),
+                        network: device.network.clone(),
+                    });
+                }
+            } else if let Some(was_offline) = self.offline.remove(&device.mac) {
+                if was_offline.notified {
+                    tracing::debug!(
+                        "[MONITOR] Device back online: {} ({})",
+                        device.ip,
+                        device.mac
+                    );
+                    callback(NetworkEvent::DeviceCameOnline {
+                        mac: device.mac.clone(),
+                        ip: device.ip.clone(),
+                        hostname: device.hostname.clone(),
+                        network: device.network.clone(),
+                    });
+                }
+
+                if was_offline.device.ip != device.ip {
+                    tracing::debug!(
+                        "[MONITOR] IP changed while offline: {} -> {} ({})",
+                        was_offline.device.ip,
+                        device.ip,
+                        device.mac
+                    );
+                    callback(NetworkEvent::DeviceIpChanged {
+                        mac: device.mac.clone(),
+                        old_ip: was_offline.device.ip,
+                        new_ip: device.ip.clone(),
+                        network: device.network.clone(),
+                    });
+                }
+            } else {
+                // Unknown devices must show up in enough scans in a row first
+                let sightings = self.unconfirmed.entry(device.mac.clone()).or_insert(0);
+                *sightings += 1;
+                if *sightings < self.hysteresis.confirm_scans {
+                    continue;
+                }
+                self.unconfirmed.remove(&device.mac);
+
+                tracing::debug!("[MONITOR] New device: {} ({})", device.ip, device.mac);
+                callback(NetworkEvent::NewDeviceDiscovered {
+                    ip: device.ip.clone(),
+                    mac: device.mac.clone(),
+                    hostname: device.hostname.clone(),
+                    device_type: device.device_type.clone(),
+                    vendor: device.vendor.clone(),
+                    risk_score: device.risk_score,
+                    network: device.network.clone(),
+                });
+            }
+
+            self.track_latency(callback, device);
+            next_online.insert(device.mac.clone(), device.clone());
+        }
+
+        self.online = next_online;
+        self.unconfirmed
+            .retain(|mac, _| current_macs.contains_key(mac.as_str()));
+        let (online, offline) = (&self.online, &self.offline);
+        let known = |mac: &String| online.contains_key(mac) || offline.contains_key(mac);
+        self.latency.retain(|mac, _| known(mac));
+        self.presence.retain(|mac, _| known(mac));
+    }
+
+    /// Record which known devices appeared or disappeared, reporting flapping
+    ///
+    /// A device that changes presence at least `FLAP_MIN_CHANGES` times
+    /// within `FLAP_WINDOW_SECS` is reported once, and again only after it
+    /// has been stable for a whole window.
+    fn track_presence<F>(
+        &mut self,
+        callback: &F,
+        current: &HashMap<&str, &DeviceSnapshot>,
+        now: Instant,
+    ) where
+        F: Fn(NetworkEvent) + ?Sized,
+    {
+        for (mac, history) in self.presence.iter_mut() {
+            let present = current.contains_key(mac.as_str());
+            if present != history.present {
+                history.present = present;
+                history.changes.push_back(now);
+            }
+            while history
+                .changes
+                .front()
+                .is_some_and(|at| now.duration_since(*at).as_secs() > FLAP_WINDOW_SECS)
+            {
+                history.changes.pop_front();
+            }
+            if history.changes.is_empty() {
+                history.flapping = false;
+            }
+            if history.flapping || history.changes.len() < FLAP_MIN_CHANGES {
+                continue;
+            }
+
+            let ip = current
+                .get(mac.as_str())
+                .map(|device| device.ip.clone())
+                .or_else(|| self.online.get(mac).map(|device| device.ip.clone()))
+                .or_else(|| self.offline.get(mac).map(|snap| snap.device.ip.clone()))
+                .unwrap_or_default();
+            tracing::debug!(
+                "[MONITOR] Device flapping: {} ({}), {} changes",
+                ip,
+                mac,
+                history.changes.len()
+            );
+            callback(NetworkEvent::DeviceFlapping {
+                mac: mac.clone(),
+                ip,
+                changes: history.changes.len() as u32,
+                window_secs: FLAP_WINDOW_SECS,
+            });
+            history.flapping = true;
+        }
+
+        for mac in current.keys() {
+            self.presence
+                .entry(mac.to_string())
+                .or_insert_with(|| PresenceHistory {
+                    present: true,
+                    ..PresenceHistory::default()
+                });
+        }
+    }
+
+    /// Add a device's ping time to its history, reporting a jump over its baseline
+    ///
+    /// Degraded replies count towards the baseline too, so a device that
+    /// stays slower (moved to a worse link) becomes the new normal.
+    fn track_latency<F>(&mut self, callback: &F, device: &DeviceSnapshot)
+    where
+        F: Fn(NetworkEvent) + ?Sized,
+    {
+        let Some(current_ms) = device.latency_ms else {
+            return;
+        };
+        let history = self.latency.entry(device.mac.clone()).or_default();
+        if let Some(baseline_ms) = history.baseline() {
+            let degraded = current_ms >= baseline_ms.saturating_mul(LATENCY_DEGRADED_FACTOR)
+                && current_ms >= baseline_ms + LATENCY_DEGRADED_MIN_MS;
+            if degraded && !history.degraded {
+                tracing::debug!(
+                    "[MONITOR] Latency degraded: {} ms -> {} ms ({})",
+                    baseline_ms,
+                    current_ms,
+                    device.mac
+                );
+                callback(NetworkEvent::LatencyDegraded {
+                    mac: device.mac.clone(),
+                    baseline_ms,
+                    current_ms,
+                });
+            }
+            history.degraded = degraded;
+        }
+        if history.samples.len() == LATENCY_BASELINE_SAMPLES {
+            history.samples.pop_front();
+        }
+        history.samples.push_back(current_ms);
+    }
+
+    /// Last known address of each device known to answer ping (MAC, IP)
+    ///
+    /// Covers online and recently-offline devices whose last scan got an
+    /// ICMP reply, so a light cycle can check them without a full scan.
+    pub fn ping_targets(&self) -> Vec<(String, String)> {
+        self.online
+            .values()
+            .chain(self.offline.values().map(|snap| &snap.device))
+            .filter(|device| device.latency_ms.is_some())
+            .map(|device| (device.mac.clone(), device.ip.clone()))
+            .collect()
+    }
+
+    /// Devices as a light cycle sees them, for [`ChangeDetector::observe`]
+    ///
+    /// `replies` maps the addresses that answered ping to their round-trip
+    /// time. Devices known to answer ping are present if they replied;
+    /// online devices that never answer are carried over as last seen, and
+    /// new devices are left to the next full scan.
+    pub fn light_scan(&self, replies: &HashMap<String, u64>) -> Vec<DeviceSnapshot> {
+        let carried = self
+            .online
+            .values()
+            .filter(|device| device.latency_ms.is_none())
+            .cloned();
+        let answered = self
+            .online
+            .values()
+            .chain(self.offline.values().map(|snap| &snap.device))
+            .filter(|device| device.latency_ms.is_some())
+            .filter_map(|device| {
+                let latency_ms = *replies.get(&device.ip)?;
+                Some(DeviceSnapshot {
+                    latency_ms: Some(latency_ms),
+                    ..device.clone()
+                })
+            });
+        carried.chain(answered).collect()
+    }
+
+    /// Address of the gateway: the online router with the lowest address
+    pub fn gateway_ip(&self) -> Option<Ipv4Addr> {
+        self.online
+            .values()
+            .filter(|device| device.device_type.eq_ignore_ascii_case("router"))
+            .filter_map(|device| device.ip.parse().ok())
+            .min()
+    }
+
+    /// MAC of the known device last seen at `ip`
+    pub fn mac_at(&self, ip: &str) -> Option<String> {
+        self.online
+            .values()
+            .chain(self.offline.values().map(|snap| &snap.device))
+            .find(|device| device.ip == ip)
+            .map(|device| device.mac.clone())
+    }
+
+    /// Fold in a device noticed between scans by a passive listener
+    ///
+    /// A sighting only proves the device is present: it is reported as new
+    /// or back online (with a moved address) like in a scan, but nothing is
+    /// marked offline, and address changes of devices already online are
+    /// left to the next scan, as ARP traffic may show a secondary address.
+    pub fn observe_sighting<F>(&mut self, callback: &F, device: &DeviceSnapshot)
+    where
+        F: Fn(NetworkEvent) + ?Sized,
+    {
+        if self.online.contains_key(&device.mac) {
+            self.missed.remove(&device.mac);
+            return;
+        }
+
+        if let Some(was_offline) = self.offline.remove(&device.mac) {
+            if was_offline.notified {
+                tracing::debug!(
+                    "[MONITOR] Device back online (passive): {} ({})",
+                    device.ip,
+                    device.mac
+                );
+                callback(NetworkEvent::DeviceCameOnline {
+                    mac: device.mac.clone(),
+                    ip: device.ip.clone(),
+                    hostname: device.hostname.clone(),
+                    network: device.network.clone(),
+                });
+            }
+            if was_offline.device.ip != device.ip {
+                callback(NetworkEvent::DeviceIpChanged {
+                    mac: device.mac.clone(),
+                    old_ip: was_offline.device.ip.clone(),
+                    new_ip: device.ip.clone(),
+                    network: device.network.clone(),
+                });
+            }
+            let mut known = was_offline.device;
+            known.ip = device.ip.clone();
+            known.hostname = device.hostname.clone().or(known.hostname);
+            self.online.insert(device.mac.clone(), known);
+            return;
+        }
+
+        self.unconfirmed.remove(&device.mac);
+        tracing::debug!(
+            "[MONITOR] New device (passive): {} ({})",
+            device.ip,
+            device.mac
+        );
+        callback(NetworkEvent::NewDeviceDiscovered {
+            ip: device.ip.clone(),
+            mac: device.mac.clone(),
+            hostname: device.hostname.clone(),
+            device_type: device.device_type.clone(),
+            vendor: device.vendor.clone(),
+            risk_score: device.risk_score,
+            network: device.network.clone(),
+        });
+        self.online.insert(device.mac.clone(), device.clone());
+    }
+
+    /// Last known address of each watched device (MAC, IP)
+    ///
+    /// Devices the monitor has not seen yet have no address to check and
+    /// are left to the next full scan.
+    pub fn watched_targets(
+        &self,
+        device_settings: &HashMap<String, DeviceSettings>,
+    ) -> Vec<(String, String)> {
+        device_settings
+            .values()
+            .filter(|s| s.watched)
+            .filter_map(|s| {
+                let device = self
+                    .online
+                    .get(&s.device_mac)
+                    .or_else(|| self.offline.get(&s.device_mac).map(|snap| &snap.device))?;
+                Some((s.device_mac.clone(), device.ip.clone()))
+            })
+            .collect()
+    }
+
+    /// Apply a targeted check of watched devices taken at `now`
+    ///
+    /// `reachable` holds each checked MAC and whether it answered at its last
+    /// known address. Unlike [`ChangeDetector::observe`] this says nothing
+    /// about other devices, and reports only going offline and coming back,
+    /// with the same grace periods and suppression; a device that moved to
+    /// another address looks offline here until the next full scan finds it.
+    pub fn observe_watched<F>(
+        &mut self,
+        callback: &F,
+        device_settings: &HashMap<String, DeviceSettings>,
+        reachable: &[(String, bool)],
+        now: Instant,
+    ) where
+        F: Fn(NetworkEvent) + ?Sized,
+    {
+        for (mac, answered) in reachable {
+            self.missed.remove(mac);
+            if *answered {
+                let Some(was_offline) = self.offline.remove(mac) else {
+                    continue;
+                };
+                if was_offline.notified {
+                    tracing::debug!(
+                        "[MONITOR] Watched device back online: {} ({})",
+                        was_offline.device.ip,
+                        mac
+                    );
+                    callback(NetworkEvent::DeviceCameOnline {
+                        mac: mac.clone(),
+                        ip: was_offline.device.ip.clone(),
+                        hostname: was_offline.device.hostname.clone(),
+                        network: was_offline.device.network.clone(),
+                    });
+                }
+                self.online.insert(mac.clone(), was_offline.device);
+                continue;
+            }
+
+            if let Some(device) = self.online.remove(mac) {
+                self.offline.insert(
+                    mac.clone(),
+                    OfflineDeviceSnapshot {
+                        device,
+                        since: now,
+                        notified: false,
+                    },
+                );
+            }
+            let Some(snap) = self.offline.get_mut(mac) else {
+                continue;
+            };
+            let settings = device_settings.get(mac);
+            let grace_secs = settings.and_then(|s| s.offline_grace_secs).unwrap_or(0);
+            let alerts_enabled = settings
+                .map(|s| s.offline_alerts_enabled && !s.is_muted(Utc::now()))
+                .unwrap_or(true);
+            if snap.notified
+                || !alerts_enabled
+                || now.duration_since(snap.since).as_secs() < grace_secs
+            {
+                continue;
+            }
+
+            tracing::debug!(
+                "[MONITOR] Watched device offline: {} ({})",
+                snap.device.ip,
+                mac
+            );
+            callback(NetworkEvent::DeviceWentOffline {
+                mac: mac.clone(),
+                last_ip: snap.device.ip.clone(),
+                hostname: snap.device.hostname.clone(),
+                network: snap.device.network.clone(),
+            });
+            snap.notified = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use std::collections::HashSet;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    fn snapshot(mac: &str, ip: &str) -> DeviceSnapshot {
+        DeviceSnapshot {
+            mac: mac.to_string(),
+            ip: ip.to_string(),
+            hostname: None,
+            device_type: "UNKNOWN".to_string(),
+            vendor: None,
+            risk_score: 0,
+            is_online: true,
+            latency_ms: None,
+            open_ports: Vec::new(),
+            network: None,
+        }
+    }
+
+    fn offline_events(events: &Mutex<Vec<NetworkEvent>>) -> usize {
+        events
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|e| matches!(e, NetworkEvent::DeviceWentOffline { .. }))
+            .count()
+    }
+
+    #[test]
+    fn test_offline_grace_and_suppression() {
+        let events = Mutex::new(Vec::new());
+        let callback = |event: NetworkEvent| events.lock().unwrap().push(event);
+
+        let mut settings = HashMap::new();
+        let mut server = DeviceSettings::new("aa:00:00:00:00:01");
+        server.offline_grace_secs = Some(120);
+        let mut phone = DeviceSettings::new("aa:00:00:00:00:02");
+        phone.offline_alerts_enabled = false;
+        let mut bulb = DeviceSettings::new("aa:00:00:00:00:04");
+        bulb.muted_until = Some(Utc::now() + chrono::Duration::hours(1));
+        settings.insert(server.device_mac.clone(), server);
+        settings.insert(phone.device_mac.clone(), phone);
+        settings.insert(bulb.device_mac.clone(), bulb);
+
+        let all = vec![
+            snapshot("aa:00:00:00:00:01", "192.168.1.40"),
+            snapshot("aa:00:00:00:00:02", "192.168.1.40"),
+            snapshot("aa:00:00:00:00:03", "192.168.1.40"),
+            snapshot("aa:00:00:00:00:04", "192.168.1.40"),
+        ];
+        let now = Instant::now();
+        let mut detector = ChangeDetector::new();
+        detector.observe(&callback, &settings, &all, now);
+
+        // All four disappear: only the device without overrides is reported now
+        detector.observe(&callback, &settings, &[], now);
+        assert_eq!(offline_events(&events), 1);
+        assert_eq!(detector.offline.len(), 4);
+
+        // The muted bulb is reported once its mute is lifted
+        settings.get_mut("aa:00:00:00:00:04").unwrap().muted_until = None;
+        detector.observe(&callback, &settings, &[], now);
+        assert_eq!(offline_events(&events), 2);
+
+        // Server returns within its grace period: no offline/online churn
+        events.lock().unwrap().clear();
+        detector.observe(&callback, &settings, &all[..1], now);
+        assert!(events.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_repeated_mac_reported_once() {
+        let events = Mutex::new(Vec::new());
+        let callback = |event: NetworkEvent| events.lock().unwrap().push(event);
+
+        let claims = [
+            snapshot("aa:00:00:00:00:01", "192.168.1.40"),
+            snapshot("aa:00:00:00:00:01", "192.168.1.41"),
+        ];
+        let mut detector = ChangeDetector::new();
+        detector.observe(&callback, &HashMap::new(), &claims, Instant::now());
+        assert_eq!(events.lock().unwrap().len(), 1);
+        assert_eq!(detector.online_count(), 1);
+    }
+
+    #[test]
+    fn test_watched_device_checks() {
+        let events = Mutex::new(Vec::new());
+        let callback = |event: NetworkEvent| events.lock().unwrap().push(event);
+
+        let mut camera = DeviceSettings::new("aa:00:00:00:00:01");
+        camera.watched = true;
+        let settings = HashMap::from([(camera.device_mac.clone(), camera)]);
+
+        let now = Instant::now();
+        let mut detector = ChangeDetector::new();
+        detector.observe(
+            &callback,
+            &settings,
+            &[
+                snapshot("aa:00:00:00:00:01", "192.168.1.40"),
+                snapshot("aa:00:00:00:00:02", "192.168.1.41"),
+            ],
+            now,
+        );
+        assert_eq!(
+            detector.watched_targets(&settings),
+            vec![("aa:00:00:00:00:01".to_string(), "192.168.1.40".to_string())]
+        );
+
+        // A missed check reports the camera at once, a later answer brings it back
+        events.lock().unwrap().clear();
+        let camera_mac = "aa:00:00:00:00:01".to_string();
+        detector.observe_watched(&callback, &settings, &[(camera_mac.clone(), false)], now);
+        assert_eq!(offline_events(&events), 1);
+        assert_eq!(detector.online_count(), 1);
+        detector.observe_watched(&callback, &settings, &[(camera_mac.clone(), false)], now);
+        assert_eq!(offline_events(&events), 1);
+        detector.observe_watched(&callback, &settings, &[(camera_mac, true)], now);
+        assert!(matches!(
+            events.lock().unwrap().last(),
+            Some(NetworkEvent::DeviceCameOnline { .. })
+        ));
+
+        // The next full scan agrees and reports nothing new
+        events.lock().unwrap().clear();
+        detector.observe(
+            &callback,
+            &settings,
+            &[
+                snapshot("aa:00:00:00:00:01", "192.168.1.40"),
+                snapshot("aa:00:00:00:00:02", "192.168.1.41"),
+            ],
+            now,
+        );
+        assert!(events.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_passive_sightings() {
+        let events = Mutex::new(Vec::new());
+        let callback = |event: NetworkEvent| events.lock().unwrap().push(event);
+
+        let now = Instant::now();
+        let mut detector = ChangeDetector::new();
+        detector.observe(
+            &callback,
+            &HashMap::new(),
+            &[snapshot("aa:00:00:00:00:01", "192.168.1.40")],
+            now,
+        );
+        assert_eq!(
+            detector.mac_at("192.168.1.40").as_deref(),
+            Some("aa:00:00:00:00:01")
+        );
+
+        // A device announcing itself is new once, and the next scan agrees
+        events.lock().unwrap().clear();
+        let phone = snapshot("aa:00:00:00:00:02", "192.168.1.41");
+        detector.observe_sighting(&callback, &phone);
+        detector.observe_sighting(&callback, &phone);
+        assert_eq!(events.lock().unwrap().len(), 1);
+        assert!(matches!(
+            events.lock().unwrap()[0],
+            NetworkEvent::NewDeviceDiscovered { .. }
+        ));
+        let scanned = DeviceSnapshot {
+            vendor: Some("Apple".to_string()),
+            risk_score: 20,
+            open_ports: vec![62078],
+            ..phone.clone()
+        };
+        detector.observe(
+            &callback,
+            &HashMap::new(),
+            &[snapshot("aa:00:00:00:00:01", "192.168.1.40"), scanned],
+            now,
+        );
+        assert_eq!(events.lock().unwrap().len(), 1);
+
+        // Offline, then heard from at a new address
+        detector.observe(
+            &callback,
+            &HashMap::new(),
+            &[snapshot("aa:00:00:00:00:01", "192.168.1.40")],
+            now,
+        );
+        events.lock().unwrap().clear();
+        detector.observe_sighting(&callback, &snapshot("aa:00:00:00:00:02", "192.168.1.42"));
+        let emitted = events.lock().unwrap().clone();
+        assert!(matches!(emitted[0], NetworkEvent::DeviceCameOnline { .. }));
+        assert!(matches!(emitted[1], NetworkEvent::DeviceIpChanged { .. }));
+        assert_eq!(detector.online_count(), 2);
+
+        // What the last scan found about it is kept
+        let online = detector.online_devices();
+        let phone = online.iter().find(|d| d.mac == phone.mac).unwrap();
+        assert_eq!(phone.ip, "192.168.1.42");
+        assert_eq!(phone.vendor.as_deref(), Some("Apple"));
+        assert_eq!(phone.risk_score, 20);
+        assert_eq!(phone.open_ports, vec![62078]);
+    }
+
+    #[test]
+    fn test_hysteresis_and_flapping() {
+        let events = Mutex::new(Vec::new());
+        let callback = |event: NetworkEvent| events.lock().unwrap().push(event);
+        let mut detector = ChangeDetector::with_hysteresis(Hysteresis {
+            missed_scans: 2,
+            confirm_scans: 2,
+        });
+        let laptop = [snapshot("aa:00:00:00:00:01", "192.168.1.40")];
+        let mut now = Instant::now();
+        let mut scan = |detector: &mut ChangeDetector, devices: &[DeviceSnapshot]| {
+            now += std::time::Duration::from_secs(60);
+            detector.observe(&callback, &HashMap::new(), devices, now);
+        };
+
+        // New only once seen twice in a row
+        scan(&mut detector, &laptop);
+        scan(&mut detector, &[]);
+        scan(&mut detector, &laptop);
+        assert!(events.lock().unwrap().is_empty());
+        scan(&mut detector, &laptop);
+        assert!(matches!(
+            events.lock().unwrap()[..],
+            [NetworkEvent::NewDeviceDiscovered { .. }]
+        ));
+
+        // Missing every other scan: never offline, but flapping, reported once
+        events.lock().unwrap().clear();
+        for _ in 0..4 {
+            scan(&mut detector, &[]);
+            scan(&mut detector, &laptop);
+        }
+        assert_eq!(detector.online_count(), 1);
+        let emitted = events.lock().unwrap().clone();
+        assert_eq!(emitted.len(), 1);
+        assert!(matches!(
+            &emitted[0],
+            NetworkEvent::DeviceFlapping { changes: 4, ip, .. } if ip == "192.168.1.40"
+        ));
+
+        // Two misses in a row take it offline
+        events.lock().unwrap().clear();
+        scan(&mut detector, &[]);
+        scan(&mut detector, &[]);
+        assert_eq!(offline_events(&events), 1);
+        assert_eq!(detector.online_count(), 0);
+    }
+
+    #[test]
+    fn test_latency_degradation() {
+        let events = Mutex::new(Vec::new());
+        let callback = |event: NetworkEvent| events.lock().unwrap().push(event);
+        let now = Instant::now();
+        let mut detector = ChangeDetector::new();
+        let scan = |detector: &mut ChangeDetector, latency_ms: u64| {
+            let mut device = snapshot("aa:00:00:00:00:01", "192.168.1.40");
+            device.latency_ms = Some(latency_ms);
+            detector.observe(&callback, &HashMap::new(), &[device], now);
+        };
+
+        // Jitter and a slow reply before there is a baseline are ignored
+        scan(&mut detector, 90);
+        for latency_ms in [2, 3, 2, 4, 2, 3, 2, 3, 2] {
+            scan(&mut detector, latency_ms);
+        }
+        scan(&mut detector, 12);
+        events.lock().unwrap().clear();
+
+        // A jump is reported once while it lasts, and again after recovering
+        scan(&mut detector, 80);
+        scan(&mut detector, 85);
+        scan(&mut detector, 3);
+        scan(&mut detector, 90);
+        let emitted = events.lock().unwrap().clone();
+        assert_eq!(emitted.len(), 2);
+        assert!(matches!(
+            emitted[0],
+            NetworkEvent::LatencyDegraded {
+                baseline_ms: 3,
+                current_ms: 80,
+                ..
+            }
+        ));
+    }
+
+    /// Lifecycle of a MAC as told by the events emitted so far
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum Reported {
+        Online,
+        Offline,
+    }
+
+    const MACS: usize = 6;
+
+    fn mac(index: usize) -> String {
+        format!("aa:00:00:00:00:{:02x}", index)
+    }
+
+    /// Per-device overrides: (grace seconds, offline alerts enabled)
+    fn settings_strategy() -> impl Strategy<Value = Vec<(Option<u64>, bool)>> {
+        proptest::collection::vec(
+            (
+                prop_oneof![Just(None), Just(Some(60)), Just(Some(600))],
+                any::<bool>(),
+            ),
+            MACS,
+        )
+    }
+
+    /// Scans as (seconds since the previous scan, (MAC, IP) claims)
+    fn scans_strategy() -> impl Strategy<Value = Vec<(u64, Vec<(usize, u8)>)>> {
+        proptest::collection::vec(
+            (
+                0u64..900,
+                proptest::collection::vec((0..MACS, 10u8..14), 0..8),
+            ),
+            1..30,
+        )
+    }
+
+    proptest! {
+        #[test]
+        fn prop_events_follow_device_lifecycle(
+            overrides in settings_strategy(),
+            scans in scans_strategy(),
+        ) {
+            let settings: HashMap<String, DeviceSettings> = overrides
+                .iter()
+                .enumerate()
+                .map(|(i, (grace, enabled))| {
+                    let mut settings = DeviceSettings::new(mac(i));
+                    settings.offline_grace_secs = *grace;
+                    settings.offline_alerts_enabled = *enabled;
+                    (mac(i), settings)
+                })
+                .collect();
+
+            let events = Mutex::new(Vec::new());
+            let callback = |event: NetworkEvent| events.lock().unwrap().push(event);
+            let mut detector = ChangeDetector::new();
+            let mut reported: HashMap<String, Reported> = HashMap::new();
+            let mut previous: HashSet<String> = HashSet::new();
+            // When each MAC was first missed since it was last seen
+            let mut missing_since: HashMap<String, Instant> = HashMap::new();
+            let mut now = Instant::now();
+
+            for (step, claims) in scans {
+                now += Duration::from_secs(step);
+                let current: Vec<DeviceSnapshot> = claims
+                    .iter()
+                    .map(|(m, ip)| snapshot(&mac(*m), &format!("192.168.1.{}", ip)))
+                    .collect();
+                let seen: HashSet<String> = current.iter().map(|d| d.mac.clone()).collect();
+                for mac in previous.difference(&seen) {
+                    missing_since.entry(mac.clone()).or_insert(now);
+                }
+                for mac in &seen {
+                    missing_since.remove(mac);
+                }
+
+                events.lock().unwrap().clear();
+                detector.observe(&callback, &settings, &current, now);
+                let emitted = events.lock().unwrap().clone();
+
+                // No event is emitted twice for one device in one scan
+                let mut keys = HashSet::new();
+                for event in &emitted {
+                    let key = match event {
+                        NetworkEvent::NewDeviceDiscovered { mac, .. } => ("new", mac),
+                        NetworkEvent::DeviceWentOffline { mac, .. } => ("offline", mac),
+                        NetworkEvent::DeviceCameOnline { mac, .. } => ("online", mac),
+                        NetworkEvent::DeviceIpChanged { mac, .. } => ("ip", mac),
+                        NetworkEvent::DeviceFlapping { mac, .. } => ("flapping", mac),
+                        other => panic!("unexpected event {:?}", other),
+                    };
+                    prop_assert!(keys.insert(key), "duplicate event {:?}", event);
+                }
+
+                for event in &emitted {
+                    match event {
+                        NetworkEvent::NewDeviceDiscovered { mac, .. } => {
+                            prop_assert!(seen.contains(mac));
+                            prop_assert!(!previous.contains(mac));
+                            reported.insert(mac.clone(), Reported::Online);
+                        }
+                        NetworkEvent::DeviceWentOffline { mac, .. } => {
+                            // Offline is always preceded by online, and honours overrides
+                            prop_assert_eq!(reported.get(mac), Some(&Reported::Online));
+                            prop_assert!(!seen.contains(mac));
+                            let device = &settings[mac];
+                            prop_assert!(device.offline_alerts_enabled);
+                            let missing = now.duration_since(missing_since[mac]).as_secs();
+                            prop_assert!(missing >= device.offline_grace_secs.unwrap_or(0));
+                            reported.insert(mac.clone(), Reported::Offline);
+                        }
+                        NetworkEvent::DeviceCameOnline { mac, .. } => {
+                            prop_assert_eq!(reported.get(mac), Some(&Reported::Offline));
+                            prop_assert!(seen.contains(mac));
+                            reported.insert(mac.clone(), Reported::Online);
+                        }
+                        NetworkEvent::DeviceIpChanged {
+                            mac, old_ip, new_ip, ..
+                        } => {
+                            prop_assert!(seen.contains(mac));
+                            prop_assert_ne!(old_ip, new_ip);
+                        }
+                        _ => {}
+                    }
+                }
+
+                // The detector's view matches the scan
+                prop_assert_eq!(detector.online_count(), seen.len());
+                previous = seen;
+            }
+        }
+    }
+}
@@ -0,0 +1,188 @@
+//! Persisted monitor events
+//!
+//! The monitor writes every event (apart from scan progress) to the
+//! `network_events` table as it happens, so a UI opened in the morning can
+//! replay what the monitor saw overnight. Rows keep the event's JSON as
+//! emitted; ids increase with every event and serve as a replay cursor.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+use super::events::NetworkEvent;
+use crate::database::queries::parse_datetime_column;
+
+/// A monitor event as stored
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredEvent {
+    /// Increases with every event; pass the last one seen to [`get_events_after`]
+    pub id: i64,
+    pub created_at: DateTime<Utc>,
+    pub event: NetworkEvent,
+}
+
+/// Store an event, returning its id (`None` for scan progress, which is not kept)
+pub fn record_event(conn: &Connection, event: &NetworkEvent) -> Result<Option<i64>> {
+    if matches!(event, NetworkEvent::ScanProgress { .. }) {
+        return Ok(None);
+    }
+    let payload = serde_json::to_string(event).context("Failed to encode event")?;
+    conn.execute(
+        "INSERT INTO network_events (event_type, payload) VALUES (?1, ?2)",
+        params![event.kind(), payload],
+    )
+    .context("Failed to store event")?;
+    Ok(Some(conn.last_insert_rowid()))
+}
+
+fn stored_events(
+    conn: &Connection,
+    sql: &str,
+    params: impl rusqlite::Params,
+) -> Result<Vec<StoredEvent>> {
+    let mut stmt = conn.prepare(sql)?;
+    let rows = stmt
+        .query_map(params, |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                parse_datetime_column(row.get::<_, String>(2)?, 2)?,
+            ))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("Failed to load events")?;
+
+    // Events written by a newer build may not decode; they are skipped
+    Ok(rows
+        .into_iter()
+        .filter_map(
+            |(id, payload, created_at)| match serde_json::from_str(&payload) {
+                Ok(event) => Some(StoredEvent {
+                    id,
+                    created_at,
+                    event,
+                }),
+                Err(e) => {
+                    tracing::warn!("Skipping undecodable event {}: {}", id, e);
+                    None
+                }
+            },
+        )
+        .collect())
+}
+
+/// The most recent `limit` events, optionally only those from `since` on, oldest first
+pub fn get_recent_events(
+    conn: &Connection,
+    limit: usize,
+    since: Option<DateTime<Utc>>,
+) -> Result<Vec<StoredEvent>> {
+    let since = since.map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string());
+    let mut events = stored_events(
+        conn,
+        r#"
+        SELECT id, payload, created_at FROM network_events
+        WHERE ?1 IS NULL OR created_at >= ?1
+        ORDER BY id DESC
+        LIMIT ?2
+        "#,
+        params![since, limit as i64],
+    )?;
+    events.reverse();
+    Ok(events)
+}
+
+/// Replay: up to `limit` events stored after the one with id `after_id`, oldest first
+///
+/// Start from 0 (or the last id returned by [`get_recent_events`]) and pass
+/// the last id received to continue, without gaps or repeats.
+pub fn get_events_after(
+    conn: &Connection,
+    after_id: i64,
+    limit: usize,
+) -> Result<Vec<StoredEvent>> {
+    stored_events(
+        conn,
+        r#"
+        SELECT id, payload, created_at FROM network_events
+        WHERE id > ?1
+        ORDER BY id
+        LIMIT ?2
+        "#,
+        params![after_id, limit as i64],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Database;
+
+    #[test]
+    fn test_events_roundtrip_and_replay() {
+        let db = Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
+
+        let progress = NetworkEvent::ScanProgress {
+            phase: "ARP".to_string(),
+            percent: 20,
+            message: "ARP scanning".to_string(),
+        };
+        assert_eq!(record_event(&conn, &progress).unwrap(), None);
+
+        let first = record_event(
+            &conn,
+            &NetworkEvent::MonitoringStarted {
+                interval_seconds: 60,
+            },
+        )
+        .unwrap()
+        .unwrap();
+        for scan_number in 1..=3 {
+            record_event(
+                &conn,
+                &NetworkEvent::ScanStarted {
+                    scan_number,
+                    light: false,
+                },
+            )
+            .unwrap();
+        }
+        record_event(
+            &conn,
+            &NetworkEvent::DeviceWentOffline {
+                mac: "aa:00:00:00:00:01".to_string(),
+                last_ip: "192.168.1.40".to_string(),
+                hostname: None,
+                network: None,
+            },
+        )
+        .unwrap();
+
+        // Latest two, oldest first
+        let recent = get_recent_events(&conn, 2, None).unwrap();
+        assert_eq!(recent.len(), 2);
+        assert!(matches!(
+            recent[0].event,
+            NetworkEvent::ScanStarted { scan_number: 3, .. }
+        ));
+        assert!(matches!(
+            recent[1].event,
+            NetworkEvent::DeviceWentOffline { .. }
+        ));
+        let since = Utc::now() - chrono::Duration::hours(1);
+        assert_eq!(get_recent_events(&conn, 100, Some(since)).unwrap().len(), 5);
+        let later = Utc::now() + chrono::Duration::hours(1);
+        assert!(get_recent_events(&conn, 100, Some(later))
+            .unwrap()
+            .is_empty());
+
+        // Replay in pages picks up exactly where the last one ended
+        let page = get_events_after(&conn, 0, 3).unwrap();
+        assert_eq!(page[0].id, first);
+        let rest = get_events_after(&conn, page[2].id, 10).unwrap();
+        assert_eq!(rest.len(), 2);
+        assert_eq!(rest[1].id, recent[1].id);
+    }
+}
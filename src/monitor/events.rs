@@ -3,6 +3,10 @@
 //! Event types for real-time UI updates
 
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::alerts::AlertSeverity;
+use crate::insights::BaselineDeviation;
 
 /// Network monitoring events emitted to frontend
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -14,8 +18,19 @@ pub enum NetworkEvent {
     /// Monitoring session stopped
     MonitoringStopped,
 
+    /// Scanning paused; the session and its device state are kept
+    MonitoringPaused,
+
+    /// Scanning resumed after a pause
+    MonitoringResumed,
+
     /// Scan cycle started
-    ScanStarted { scan_number: u32 },
+    ScanStarted {
+        scan_number: u32,
+        /// Only known devices are pinged this cycle, not a full scan
+        #[serde(default)]
+        light: bool,
+    },
 
     /// Scan progress update
     ScanProgress {
@@ -37,6 +52,14 @@ pub enum NetworkEvent {
         mac: String,
         hostname: Option<String>,
         device_type: String,
+        #[serde(default)]
+        vendor: Option<String>,
+        /// 0-100, as in an on-demand scan
+        #[serde(default)]
+        risk_score: u8,
+        /// Network the device was found on (the scanned subnet or range)
+        #[serde(default)]
+        network: Option<String>,
     },
 
     /// Device went offline
@@ -44,6 +67,9 @@ pub enum NetworkEvent {
         mac: String,
         last_ip: String,
         hostname: Option<String>,
+        /// Network the device was found on (the scanned subnet or range)
+        #[serde(default)]
+        network: Option<String>,
     },
 
     /// Device came back online  
@@ -51,6 +77,18 @@ pub enum NetworkEvent {
         mac: String,
         ip: String,
         hostname: Option<String>,
+        /// Network the device was found on (the scanned subnet or range)
+        #[serde(default)]
+        network: Option<String>,
+    },
+
+    /// Device keeps appearing and disappearing between scans
+    DeviceFlapping {
+        mac: String,
+        ip: String,
+        /// Appearances plus disappearances within the window
+        changes: u32,
+        window_secs: u64,
     },
 
     /// Device IP address changed
@@ -58,36 +96,285 @@ pub enum NetworkEvent {
         mac: String,
         old_ip: String,
         new_ip: String,
+        /// Network the device was found on (the scanned subnet or range)
+        #[serde(default)]
+        network: Option<String>,
+    },
+
+    /// Another MAC claimed an IP recently bound to a different device
+    ArpAddressTakeover {
+        ip: String,
+        previous_mac: String,
+        new_mac: String,
+        gratuitous: bool,
+    },
+
+    /// Several MACs claim the same IP address
+    IpConflict { ip: String, macs: Vec<String> },
+
+    /// One MAC keeps claiming several IP addresses (two hosts sharing it)
+    SharedMacDetected { mac: String, ips: Vec<String> },
+
+    /// A device's ping round-trip time jumped well above its usual one
+    LatencyDegraded {
+        mac: String,
+        baseline_ms: u64,
+        current_ms: u64,
+    },
+
+    /// A device exceeded the ARP request rate threshold
+    ArpStormDetected {
+        mac: String,
+        ip: String,
+        request_count: u32,
+        window_secs: u64,
+    },
+
+    /// The office-hours schedule switched monitoring profiles
+    ProfileChanged {
+        profile: String,
+        interval_seconds: u64,
+    },
+
+    /// The internet connection lost packets, slowed down or failed DNS
+    InternetDegraded {
+        /// Packet loss across the external anchors
+        loss_percent: f64,
+        latency_ms: Option<f64>,
+        dns_ms: Option<u64>,
+        reason: String,
+    },
+
+    /// No external anchor answered (or the gateway is gone)
+    InternetDown { reason: String },
+
+    /// Quiet hours started (active scanning stops) or ended
+    QuietHoursChanged { active: bool },
+
+    /// A device not in the network baseline is on the network
+    UnknownDevicePresent {
+        mac: String,
+        ip: String,
+        hostname: Option<String>,
+    },
+
+    /// A device in the network baseline is not on the network
+    ExpectedDeviceMissing {
+        mac: String,
+        /// Address in the baseline
+        ip: Option<String>,
+        hostname: Option<String>,
     },
 
+    /// A baseline device has a port open that was closed in the baseline
+    UnexpectedPortOpen { mac: String, ip: String, port: u16 },
+
     /// Error during monitoring
     MonitoringError { message: String },
 }
 
+impl NetworkEvent {
+    /// Variant name, as in the serialized `type` field
+    pub fn kind(&self) -> &'static str {
+        match self {
+            NetworkEvent::MonitoringStarted { .. } => "MonitoringStarted",
+            NetworkEvent::MonitoringStopped => "MonitoringStopped",
+            NetworkEvent::MonitoringPaused => "MonitoringPaused",
+            NetworkEvent::MonitoringResumed => "MonitoringResumed",
+            NetworkEvent::ScanStarted { .. } => "ScanStarted",
+            NetworkEvent::ScanProgress { .. } => "ScanProgress",
+            NetworkEvent::ScanCompleted { .. } => "ScanCompleted",
+            NetworkEvent::NewDeviceDiscovered { .. } => "NewDeviceDiscovered",
+            NetworkEvent::DeviceWentOffline { .. } => "DeviceWentOffline",
+            NetworkEvent::DeviceCameOnline { .. } => "DeviceCameOnline",
+            NetworkEvent::DeviceFlapping { .. } => "DeviceFlapping",
+            NetworkEvent::DeviceIpChanged { .. } => "DeviceIpChanged",
+            NetworkEvent::ArpAddressTakeover { .. } => "ArpAddressTakeover",
+            NetworkEvent::IpConflict { .. } => "IpConflict",
+            NetworkEvent::SharedMacDetected { .. } => "SharedMacDetected",
+            NetworkEvent::LatencyDegraded { .. } => "LatencyDegraded",
+            NetworkEvent::ArpStormDetected { .. } => "ArpStormDetected",
+            NetworkEvent::ProfileChanged { .. } => "ProfileChanged",
+            NetworkEvent::InternetDegraded { .. } => "InternetDegraded",
+            NetworkEvent::InternetDown { .. } => "InternetDown",
+            NetworkEvent::QuietHoursChanged { .. } => "QuietHoursChanged",
+            NetworkEvent::UnknownDevicePresent { .. } => "UnknownDevicePresent",
+            NetworkEvent::ExpectedDeviceMissing { .. } => "ExpectedDeviceMissing",
+            NetworkEvent::UnexpectedPortOpen { .. } => "UnexpectedPortOpen",
+            NetworkEvent::MonitoringError { .. } => "MonitoringError",
+        }
+    }
+
+    /// MACs of the devices the event is about
+    pub fn device_macs(&self) -> Vec<&str> {
+        match self {
+            NetworkEvent::NewDeviceDiscovered { mac, .. }
+            | NetworkEvent::DeviceWentOffline { mac, .. }
+            | NetworkEvent::DeviceCameOnline { mac, .. }
+            | NetworkEvent::DeviceFlapping { mac, .. }
+            | NetworkEvent::DeviceIpChanged { mac, .. }
+            | NetworkEvent::ArpStormDetected { mac, .. }
+            | NetworkEvent::SharedMacDetected { mac, .. }
+            | NetworkEvent::LatencyDegraded { mac, .. }
+            | NetworkEvent::UnknownDevicePresent { mac, .. }
+            | NetworkEvent::ExpectedDeviceMissing { mac, .. }
+            | NetworkEvent::UnexpectedPortOpen { mac, .. } => vec![mac.as_str()],
+            NetworkEvent::ArpAddressTakeover {
+                previous_mac,
+                new_mac,
+                ..
+            } => vec![previous_mac.as_str(), new_mac.as_str()],
+            NetworkEvent::IpConflict { macs, .. } => macs.iter().map(String::as_str).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// How much the event deserves attention
+    ///
+    /// Lifecycle and progress events are low; devices appearing, leaving,
+    /// flapping or slowing down, departures from the network baseline, a
+    /// degraded connection and failing scans are medium; ARP anomalies and
+    /// internet outages are high.
+    pub fn severity(&self) -> AlertSeverity {
+        match self {
+            NetworkEvent::NewDeviceDiscovered { .. }
+            | NetworkEvent::DeviceWentOffline { .. }
+            | NetworkEvent::DeviceFlapping { .. }
+            | NetworkEvent::LatencyDegraded { .. }
+            | NetworkEvent::UnknownDevicePresent { .. }
+            | NetworkEvent::ExpectedDeviceMissing { .. }
+            | NetworkEvent::UnexpectedPortOpen { .. }
+            | NetworkEvent::InternetDegraded { .. }
+            | NetworkEvent::MonitoringError { .. } => AlertSeverity::Medium,
+            NetworkEvent::ArpAddressTakeover { .. }
+            | NetworkEvent::IpConflict { .. }
+            | NetworkEvent::SharedMacDetected { .. }
+            | NetworkEvent::ArpStormDetected { .. }
+            | NetworkEvent::InternetDown { .. } => AlertSeverity::High,
+            _ => AlertSeverity::Low,
+        }
+    }
+}
+
+impl From<BaselineDeviation> for NetworkEvent {
+    fn from(deviation: BaselineDeviation) -> Self {
+        match deviation {
+            BaselineDeviation::UnknownDevice { mac, ip, hostname } => {
+                NetworkEvent::UnknownDevicePresent { mac, ip, hostname }
+            }
+            BaselineDeviation::MissingDevice { mac, ip, hostname } => {
+                NetworkEvent::ExpectedDeviceMissing { mac, ip, hostname }
+            }
+            BaselineDeviation::UnexpectedPort { mac, ip, port } => {
+                NetworkEvent::UnexpectedPortOpen { mac, ip, port }
+            }
+        }
+    }
+}
+
+/// Which events a subscriber receives
+///
+/// Every condition given must hold; empty lists and `None` match anything.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EventFilter {
+    /// Event kinds by name, e.g. `DeviceWentOffline`
+    pub kinds: Vec<String>,
+    /// Only events about a device carrying one of these tags (case-insensitive)
+    pub tags: Vec<String>,
+    /// Only events at least this severe
+    pub min_severity: Option<AlertSeverity>,
+}
+
+impl EventFilter {
+    /// Filter passing only the given event kinds
+    pub fn kinds<I, S>(kinds: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            kinds: kinds.into_iter().map(Into::into).collect(),
+            ..Self::default()
+        }
+    }
+
+    /// Check an event, with `device_tags` mapping MACs to their tags
+    pub fn matches(
+        &self,
+        event: &NetworkEvent,
+        device_tags: &HashMap<String, Vec<String>>,
+    ) -> bool {
+        if !self.kinds.is_empty() && !self.kinds.iter().any(|k| k == event.kind()) {
+            return false;
+        }
+        if self.min_severity.is_some_and(|min| event.severity() < min) {
+            return false;
+        }
+        self.tags.is_empty()
+            || event.device_macs().into_iter().any(|mac| {
+                device_tags.get(mac).is_some_and(|tags| {
+                    tags.iter()
+                        .any(|tag| self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)))
+                })
+            })
+    }
+}
+
 /// Monitoring status information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MonitoringStatus {
     pub is_running: bool,
+    /// Running but not scanning until resumed
+    #[serde(default)]
+    pub is_paused: bool,
     pub interval_seconds: u64,
+    /// Scheduled profile in use, when the office-hours schedule is enabled
+    pub active_profile: Option<String>,
     pub scan_count: u32,
     pub last_scan_time: Option<String>,
     pub devices_online: usize,
     pub devices_total: usize,
+    /// Interface scanned by the last completed cycle
+    pub interface: Option<MonitorInterface>,
+    /// ARP requests and TCP connection attempts sent by the last completed cycle
+    pub last_cycle_packets_sent: Option<u64>,
+    pub last_cycle_duration_ms: Option<u64>,
+    /// Devices currently considered online, by address
+    #[serde(default)]
+    pub devices: Vec<DeviceSnapshot>,
 }
 
 impl Default for MonitoringStatus {
     fn default() -> Self {
         Self {
             is_running: false,
+            is_paused: false,
             interval_seconds: 60,
+            active_profile: None,
             scan_count: 0,
             last_scan_time: None,
             devices_online: 0,
             devices_total: 0,
+            interface: None,
+            last_cycle_packets_sent: None,
+            last_cycle_duration_ms: None,
+            devices: Vec::new(),
         }
     }
 }
 
+/// Network interface the monitor is scanning
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MonitorInterface {
+    pub name: String,
+    pub ip: String,
+    /// Negotiated link speed in Mbit/s, when the OS reports it
+    pub link_speed_mbps: Option<u32>,
+    /// Wi-Fi network, when the interface is wireless
+    pub ssid: Option<String>,
+}
+
 /// Simple device info for events
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DeviceSnapshot {
@@ -95,5 +382,67 @@ pub struct DeviceSnapshot {
     pub ip: String,
     pub hostname: Option<String>,
     pub device_type: String,
+    #[serde(default)]
+    pub vendor: Option<String>,
+    /// 0-100, from the device type and open ports as in an on-demand scan
+    #[serde(default)]
+    pub risk_score: u8,
     pub is_online: bool,
+    /// Ping round-trip time, when the device answered ICMP
+    #[serde(default)]
+    pub latency_ms: Option<u64>,
+    /// Open ports found by the last full scan
+    #[serde(default)]
+    pub open_ports: Vec<u16>,
+    /// Subnet or range of the sweep that found the device
+    #[serde(default)]
+    pub network: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_filter() {
+        let offline = NetworkEvent::DeviceWentOffline {
+            mac: "aa:00:00:00:00:01".to_string(),
+            last_ip: "192.168.1.40".to_string(),
+            hostname: None,
+            network: None,
+        };
+        let started = NetworkEvent::ScanStarted {
+            scan_number: 1,
+            light: false,
+        };
+        let tags = HashMap::from([("aa:00:00:00:00:01".to_string(), vec!["Kids".to_string()])]);
+
+        assert!(EventFilter::default().matches(&started, &tags));
+        let offline_only = EventFilter::kinds(["DeviceWentOffline"]);
+        assert!(offline_only.matches(&offline, &tags));
+        assert!(!offline_only.matches(&started, &tags));
+
+        let tagged = EventFilter {
+            tags: vec!["kids".to_string()],
+            ..EventFilter::default()
+        };
+        assert!(tagged.matches(&offline, &tags));
+        assert!(!tagged.matches(&offline, &HashMap::new()));
+        assert!(!tagged.matches(&started, &tags));
+
+        let severe = EventFilter {
+            min_severity: Some(AlertSeverity::High),
+            ..EventFilter::default()
+        };
+        assert!(!severe.matches(&offline, &tags));
+        let conflict = NetworkEvent::IpConflict {
+            ip: "192.168.1.40".to_string(),
+            macs: vec![
+                "aa:00:00:00:00:01".to_string(),
+                "aa:00:00:00:00:02".to_string(),
+            ],
+        };
+        assert!(severe.matches(&conflict, &tags));
+        assert!(tagged.matches(&conflict, &tags));
+    }
 }
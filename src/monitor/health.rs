@@ -0,0 +1,213 @@
+//! Health endpoints for headless monitoring
+//!
+//! Serves `/healthz` (liveness) and `/readyz` (readiness) over plain HTTP so
+//! orchestrators and cron checks can supervise `host-discovery monitor`.
+//! Both return a JSON body with the monitor state, database reachability and
+//! the time of the last successful scan.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use super::watcher::BackgroundMonitor;
+use crate::database::Database;
+
+/// Scan intervals without a completed scan before the monitor is not ready
+const READY_MISSED_INTERVALS: u64 = 3;
+
+/// How long a health client gets to send its request
+const HEALTH_REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Monitor state reported by the health endpoints
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthReport {
+    /// Whether the monitor loop is running (liveness)
+    pub live: bool,
+    /// Live, database reachable and a scan completed recently
+    pub ready: bool,
+    pub monitor_running: bool,
+    pub interval_seconds: u64,
+    pub scan_count: u32,
+    pub last_scan_time: Option<String>,
+    pub db_ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub db_error: Option<String>,
+}
+
+impl HealthReport {
+    /// Build a report from the monitor and a database check
+    pub async fn collect(monitor: &BackgroundMonitor, db: Option<&Database>) -> Self {
+        let status = monitor.status().await;
+        let db_check = match db {
+            Some(db) => db
+                .read(|conn| Ok(conn.query_row("SELECT 1", [], |row| row.get::<_, i64>(0))?))
+                .map(|_| ())
+                .map_err(|e| format!("{:#}", e)),
+            None => Err("Database not opened".to_string()),
+        };
+
+        let last_scan = status
+            .last_scan_time
+            .as_deref()
+            .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+            .map(|t| t.with_timezone(&Utc));
+        let scan_fresh = is_scan_fresh(last_scan, status.interval_seconds, Utc::now());
+
+        Self {
+            live: status.is_running,
+            ready: status.is_running && db_check.is_ok() && scan_fresh,
+            monitor_running: status.is_running,
+            interval_seconds: status.interval_seconds,
+            scan_count: status.scan_count,
+            last_scan_time: status.last_scan_time,
+            db_ok: db_check.is_ok(),
+            db_error: db_check.err(),
+        }
+    }
+}
+
+/// Whether the last scan finished within the allowed number of intervals
+fn is_scan_fresh(last_scan: Option<DateTime<Utc>>, interval_secs: u64, now: DateTime<Utc>) -> bool {
+    let Some(last_scan) = last_scan else {
+        return false;
+    };
+    let max_age = interval_secs.saturating_mul(READY_MISSED_INTERVALS) as i64;
+    (now - last_scan).num_seconds() <= max_age
+}
+
+/// Serve `/healthz` and `/readyz` on `bind` until the task is dropped
+///
+/// Binds before returning, so a taken port is reported to the caller.
+pub async fn serve_health(
+    bind: SocketAddr,
+    monitor: Arc<BackgroundMonitor>,
+    db: Option<Database>,
+) -> Result<tokio::task::JoinHandle<()>> {
+    let listener = TcpListener::bind(bind)
+        .await
+        .with_context(|| format!("Failed to listen on {}", bind))?;
+    tracing::info!("[HEALTH] Serving /healthz and /readyz on {}", bind);
+
+    Ok(tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((stream, addr)) => {
+                    let monitor = Arc::clone(&monitor);
+                    let db = db.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = serve_request(stream, &monitor, db.as_ref()).await {
+                            tracing::debug!("Health request from {} failed: {}", addr, e);
+                        }
+                    });
+                }
+                Err(e) => {
+                    tracing::warn!("Health listener error: {}", e);
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                }
+            }
+        }
+    }))
+}
+
+async fn serve_request(
+    mut stream: TcpStream,
+    monitor: &BackgroundMonitor,
+    db: Option<&Database>,
+) -> Result<()> {
+    let mut buf = vec![0u8; 1024];
+    let n = tokio::time::timeout(HEALTH_REQUEST_TIMEOUT, stream.read(&mut buf))
+        .await
+        .context("Health request timed out")??;
+    let request = String::from_utf8_lossy(&buf[..n]);
+
+    let (status, body) = match request_path(&request) {
+        Some("/healthz") => {
+            let report = HealthReport::collect(monitor, db).await;
+            (status_line(report.live), serde_json::to_string(&report)?)
+        }
+        Some("/readyz") => {
+            let report = HealthReport::collect(monitor, db).await;
+            (status_line(report.ready), serde_json::to_string(&report)?)
+        }
+        _ => ("404 Not Found", r#"{"error":"not found"}"#.to_string()),
+    };
+
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+fn status_line(ok: bool) -> &'static str {
+    if ok {
+        "200 OK"
+    } else {
+        "503 Service Unavailable"
+    }
+}
+
+/// Path of a `GET` or `HEAD` request, without any query string
+fn request_path(request: &str) -> Option<&str> {
+    let mut parts = request.lines().next()?.split_whitespace();
+    match parts.next()? {
+        "GET" | "HEAD" => {}
+        _ => return None,
+    }
+    parts.next()?.split('?').next()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_path() {
+        assert_eq!(
+            request_path("GET /healthz HTTP/1.1\r\nHost: x\r\n\r\n"),
+            Some("/healthz")
+        );
+        assert_eq!(
+            request_path("HEAD /readyz?verbose=1 HTTP/1.0\r\n"),
+            Some("/readyz")
+        );
+        assert_eq!(request_path("POST /healthz HTTP/1.1\r\n"), None);
+        assert_eq!(request_path(""), None);
+    }
+
+    #[test]
+    fn test_scan_freshness() {
+        let now = Utc::now();
+        assert!(!is_scan_fresh(None, 60, now));
+        assert!(is_scan_fresh(
+            Some(now - chrono::Duration::seconds(150)),
+            60,
+            now
+        ));
+        assert!(!is_scan_fresh(
+            Some(now - chrono::Duration::seconds(200)),
+            60,
+            now
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_stopped_monitor_is_not_live() {
+        let monitor = BackgroundMonitor::new();
+        let db = Database::in_memory().unwrap();
+        let report = HealthReport::collect(&monitor, Some(&db)).await;
+        assert!(!report.live);
+        assert!(!report.ready);
+        assert!(report.db_ok);
+        assert!(report.last_scan_time.is_none());
+    }
+}
@@ -0,0 +1,481 @@
+//! Internet and gateway health checks
+//!
+//! Each monitor cycle can ping the gateway and a few external anchors
+//! (1.1.1.1 and 8.8.8.8 by default) and time a DNS resolution. The samples
+//! are stored in `internet_health` for charting, and a change for the worse
+//! raises `InternetDegraded` or `InternetDown`, so an outage is told apart
+//! from devices simply going quiet.
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::net::{Ipv4Addr, ToSocketAddrs};
+use std::time::{Duration, Instant};
+
+use super::events::NetworkEvent;
+use crate::config::{
+    DEFAULT_INTERNET_ANCHORS, DEFAULT_INTERNET_DNS_NAME, INTERNET_CHECK_PINGS,
+    INTERNET_DNS_TIMEOUT, MAX_INTERNET_ANCHORS,
+};
+use crate::database::queries::{self, SETTING_INTERNET_CHECK};
+use crate::ping_series;
+
+/// What the internet health check pings and when it complains
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InternetCheckSettings {
+    pub enabled: bool,
+    /// Gateway address; the router found by the last scan when unset
+    pub gateway: Option<String>,
+    /// External IPv4 addresses to ping
+    pub anchors: Vec<String>,
+    /// Name resolved to time DNS
+    pub dns_name: String,
+    /// Packet loss, in percent, above which the connection is degraded
+    pub max_loss_percent: u32,
+    /// Average round trip to the anchors above which the connection is degraded
+    pub max_latency_ms: u64,
+    /// DNS resolution time above which the connection is degraded
+    pub max_dns_ms: u64,
+}
+
+impl Default for InternetCheckSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            gateway: None,
+            anchors: DEFAULT_INTERNET_ANCHORS.map(String::from).into(),
+            dns_name: DEFAULT_INTERNET_DNS_NAME.to_string(),
+            max_loss_percent: 20,
+            max_latency_ms: 150,
+            max_dns_ms: 1000,
+        }
+    }
+}
+
+impl InternetCheckSettings {
+    fn validate(&self) -> Result<()> {
+        if self.anchors.len() > MAX_INTERNET_ANCHORS {
+            bail!("At most {} anchors can be pinged", MAX_INTERNET_ANCHORS);
+        }
+        for address in self.anchors.iter().chain(&self.gateway) {
+            if address.parse::<Ipv4Addr>().is_err() {
+                bail!("Invalid IPv4 address '{}'", address);
+            }
+        }
+        if self.dns_name.trim().is_empty() {
+            bail!("A DNS name to resolve is required");
+        }
+        if !(1..=100).contains(&self.max_loss_percent) {
+            bail!("Packet loss threshold must be between 1 and 100 percent");
+        }
+        if self.max_latency_ms == 0 || self.max_dns_ms == 0 {
+            bail!("Latency thresholds must be above zero");
+        }
+        Ok(())
+    }
+}
+
+/// Load internet check settings (defaults if none are saved)
+pub fn load_internet_check_settings(conn: &Connection) -> Result<InternetCheckSettings> {
+    Ok(queries::get_json_setting(conn, SETTING_INTERNET_CHECK)?.unwrap_or_default())
+}
+
+/// Validate and persist internet check settings
+pub fn save_internet_check_settings(
+    conn: &Connection,
+    settings: &InternetCheckSettings,
+) -> Result<()> {
+    settings.validate()?;
+    queries::set_json_setting(conn, SETTING_INTERNET_CHECK, settings)
+}
+
+/// Overall state of the connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InternetStatus {
+    Up,
+    Degraded,
+    Down,
+}
+
+impl InternetStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            InternetStatus::Up => "up",
+            InternetStatus::Degraded => "degraded",
+            InternetStatus::Down => "down",
+        }
+    }
+}
+
+/// Ping results for one address
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TargetStats {
+    pub target: String,
+    pub sent: u32,
+    pub received: u32,
+    /// Average round trip of the replies
+    pub avg_ms: Option<f64>,
+}
+
+impl TargetStats {
+    /// Summarize round trips, `None` for requests without a reply
+    pub fn from_samples(target: String, samples: &[Option<Duration>]) -> Self {
+        let replies: Vec<f64> = samples
+            .iter()
+            .flatten()
+            .map(|rtt| rtt.as_secs_f64() * 1000.0)
+            .collect();
+        Self {
+            target,
+            sent: samples.len() as u32,
+            received: replies.len() as u32,
+            avg_ms: (!replies.is_empty())
+                .then(|| replies.iter().sum::<f64>() / replies.len() as f64),
+        }
+    }
+
+    pub fn loss_percent(&self) -> f64 {
+        loss_percent(self.sent, self.received)
+    }
+}
+
+fn loss_percent(sent: u32, received: u32) -> f64 {
+    if sent == 0 {
+        return 0.0;
+    }
+    f64::from(sent - received) * 100.0 / f64::from(sent)
+}
+
+/// One internet health check
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InternetHealth {
+    pub checked_at: DateTime<Utc>,
+    pub status: InternetStatus,
+    /// What made the connection degraded or down
+    pub reason: Option<String>,
+    pub gateway: Option<TargetStats>,
+    pub anchors: Vec<TargetStats>,
+    /// Packet loss across all anchors
+    pub loss_percent: f64,
+    /// Average round trip across all anchors
+    pub latency_ms: Option<f64>,
+    /// DNS resolution time, `None` if it failed
+    pub dns_ms: Option<u64>,
+}
+
+impl InternetHealth {
+    /// Judge a set of measurements against the thresholds in `settings`
+    ///
+    /// Down when no anchor answers (or, without anchors, DNS fails);
+    /// degraded when loss, latency or DNS time exceed their limits or the
+    /// gateway drops packets.
+    pub fn assess(
+        settings: &InternetCheckSettings,
+        gateway: Option<TargetStats>,
+        anchors: Vec<TargetStats>,
+        dns_ms: Option<u64>,
+    ) -> Self {
+        let sent = anchors.iter().map(|a| a.sent).sum();
+        let received: u32 = anchors.iter().map(|a| a.received).sum();
+        let loss = loss_percent(sent, received);
+        let weighted: f64 = anchors
+            .iter()
+            .filter_map(|a| Some(a.avg_ms? * f64::from(a.received)))
+            .sum();
+        let latency_ms = (received > 0).then(|| weighted / f64::from(received));
+        let gateway_down = gateway.as_ref().is_some_and(|g| g.received == 0);
+
+        let unreachable = if anchors.is_empty() {
+            dns_ms.is_none()
+        } else {
+            received == 0
+        };
+        let (status, reason) = if unreachable {
+            let reason = match &gateway {
+                Some(g) if gateway_down => format!("Gateway {} unreachable", g.target),
+                _ if anchors.is_empty() => "DNS resolution failed".to_string(),
+                _ => "No reply from any internet anchor".to_string(),
+            };
+            (InternetStatus::Down, Some(reason))
+        } else {
+            let mut problems = Vec::new();
+            if let Some(g) = gateway.as_ref() {
+                if g.loss_percent() > f64::from(settings.max_loss_percent) {
+                    problems.push(format!(
+                        "gateway {} losing {:.0}% of pings",
+                        g.target,
+                        g.loss_percent()
+                    ));
+                }
+            }
+            if loss > f64::from(settings.max_loss_percent) {
+                problems.push(format!("{:.0}% packet loss", loss));
+            }
+            if let Some(ms) = latency_ms.filter(|ms| *ms > settings.max_latency_ms as f64) {
+                problems.push(format!("{:.0} ms latency", ms));
+            }
+            match dns_ms {
+                None => problems.push("DNS resolution failed".to_string()),
+                Some(ms) if ms > settings.max_dns_ms => {
+                    problems.push(format!("DNS took {} ms", ms))
+                }
+                Some(_) => {}
+            }
+            if problems.is_empty() {
+                (InternetStatus::Up, None)
+            } else {
+                (InternetStatus::Degraded, Some(problems.join(", ")))
+            }
+        };
+
+        Self {
+            checked_at: Utc::now(),
+            status,
+            reason,
+            gateway,
+            anchors,
+            loss_percent: loss,
+            latency_ms,
+            dns_ms,
+        }
+    }
+}
+
+async fn ping_target(ip: Ipv4Addr) -> Result<TargetStats> {
+    let samples = ping_series(ip, INTERNET_CHECK_PINGS).await?;
+    Ok(TargetStats::from_samples(ip.to_string(), &samples))
+}
+
+/// Time resolving `name` with the system resolver, `None` on failure or timeout
+async fn time_dns(name: String) -> Option<u64> {
+    let start = Instant::now();
+    let lookup = tokio::task::spawn_blocking(move || (name.as_str(), 0).to_socket_addrs());
+    match tokio::time::timeout(INTERNET_DNS_TIMEOUT, lookup).await {
+        Ok(Ok(Ok(mut addrs))) => addrs.next().map(|_| start.elapsed().as_millis() as u64),
+        _ => None,
+    }
+}
+
+/// Ping the gateway and anchors and time DNS, all at once
+///
+/// `gateway` is used unless the settings name one. Fails when ICMP is not
+/// available (e.g. without the needed privileges), since every ping would
+/// then look lost.
+pub async fn check_internet(
+    settings: &InternetCheckSettings,
+    gateway: Option<Ipv4Addr>,
+) -> Result<InternetHealth> {
+    let gateway = settings
+        .gateway
+        .as_deref()
+        .and_then(|g| g.parse().ok())
+        .or(gateway);
+    let anchors: Vec<Ipv4Addr> = settings
+        .anchors
+        .iter()
+        .filter_map(|a| a.parse().ok())
+        .collect();
+
+    let dns = tokio::spawn(time_dns(settings.dns_name.clone()));
+    let gateway_ping = gateway.map(|ip| tokio::spawn(ping_target(ip)));
+    let anchor_pings: Vec<_> = anchors
+        .into_iter()
+        .map(|ip| tokio::spawn(ping_target(ip)))
+        .collect();
+
+    let gateway = match gateway_ping {
+        Some(handle) => Some(handle.await.context("Gateway ping task failed")??),
+        None => None,
+    };
+    let mut anchors = Vec::new();
+    for handle in anchor_pings {
+        anchors.push(handle.await.context("Anchor ping task failed")??);
+    }
+    let dns_ms = dns.await.context("DNS timing task failed")?;
+
+    Ok(InternetHealth::assess(settings, gateway, anchors, dns_ms))
+}
+
+/// Turns successive checks into events when the connection gets worse
+#[derive(Debug, Default)]
+pub struct InternetTracker {
+    status: Option<InternetStatus>,
+}
+
+impl InternetTracker {
+    /// Record a check; returns an event when the connection turns degraded
+    /// or down (again after recovering)
+    pub fn observe(&mut self, health: &InternetHealth) -> Option<NetworkEvent> {
+        let previous = self.status.replace(health.status);
+        if previous == Some(health.status) {
+            return None;
+        }
+        let reason = health.reason.clone().unwrap_or_default();
+        match health.status {
+            InternetStatus::Up => None,
+            InternetStatus::Degraded => Some(NetworkEvent::InternetDegraded {
+                loss_percent: health.loss_percent,
+                latency_ms: health.latency_ms,
+                dns_ms: health.dns_ms,
+                reason,
+            }),
+            InternetStatus::Down => Some(NetworkEvent::InternetDown { reason }),
+        }
+    }
+}
+
+/// Store a check, returning its id
+pub fn record_internet_health(conn: &Connection, health: &InternetHealth) -> Result<i64> {
+    let payload = serde_json::to_string(health).context("Failed to encode internet health")?;
+    conn.execute(
+        r#"
+        INSERT INTO internet_health (checked_at, status, loss_percent, latency_ms, dns_ms, payload)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+        "#,
+        params![
+            health.checked_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+            health.status.as_str(),
+            health.loss_percent,
+            health.latency_ms,
+            health.dns_ms.map(|ms| ms as i64),
+            payload
+        ],
+    )
+    .context("Failed to store internet health")?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// The most recent `limit` checks, optionally only those from `since` on, oldest first
+pub fn get_internet_health(
+    conn: &Connection,
+    since: Option<DateTime<Utc>>,
+    limit: usize,
+) -> Result<Vec<InternetHealth>> {
+    let since = since.map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string());
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT id, payload FROM internet_health
+        WHERE ?1 IS NULL OR checked_at >= ?1
+        ORDER BY id DESC
+        LIMIT ?2
+        "#,
+    )?;
+    let rows = stmt
+        .query_map(params![since, limit as i64], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("Failed to load internet health")?;
+
+    let mut checks: Vec<InternetHealth> = rows
+        .into_iter()
+        .filter_map(|(id, payload)| match serde_json::from_str(&payload) {
+            Ok(health) => Some(health),
+            Err(e) => {
+                tracing::warn!("Skipping undecodable internet health check {}: {}", id, e);
+                None
+            }
+        })
+        .collect();
+    checks.reverse();
+    Ok(checks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Database;
+
+    fn target(target: &str, replies: &[Option<u64>]) -> TargetStats {
+        let samples: Vec<Option<Duration>> = replies
+            .iter()
+            .map(|ms| ms.map(Duration::from_millis))
+            .collect();
+        TargetStats::from_samples(target.to_string(), &samples)
+    }
+
+    #[test]
+    fn test_internet_assessment_and_events() {
+        let settings = InternetCheckSettings::default();
+        let gateway = || Some(target("192.168.1.1", &[Some(1), Some(1), Some(2), Some(1)]));
+        let good = || target("1.1.1.1", &[Some(12), Some(14), Some(12), Some(14)]);
+        let mut tracker = InternetTracker::default();
+
+        let up = InternetHealth::assess(&settings, gateway(), vec![good()], Some(20));
+        assert_eq!(up.status, InternetStatus::Up);
+        assert_eq!(up.latency_ms, Some(13.0));
+        assert!(tracker.observe(&up).is_none());
+
+        // Half the pings to one of two anchors lost, and slow DNS
+        let lossy = target("8.8.8.8", &[Some(15), None, None, Some(15)]);
+        let degraded =
+            InternetHealth::assess(&settings, gateway(), vec![good(), lossy], Some(2500));
+        assert_eq!(degraded.status, InternetStatus::Degraded);
+        assert_eq!(degraded.loss_percent, 25.0);
+        assert_eq!(
+            degraded.reason.as_deref(),
+            Some("25% packet loss, DNS took 2500 ms")
+        );
+        assert!(matches!(
+            tracker.observe(&degraded),
+            Some(NetworkEvent::InternetDegraded { .. })
+        ));
+        assert!(tracker.observe(&degraded).is_none());
+
+        let silent = target("1.1.1.1", &[None, None, None, None]);
+        let gateway_lost = Some(target("192.168.1.1", &[None, None, None, None]));
+        let down = InternetHealth::assess(&settings, gateway_lost, vec![silent], None);
+        assert_eq!(down.status, InternetStatus::Down);
+        assert!(matches!(
+            tracker.observe(&down),
+            Some(NetworkEvent::InternetDown { reason }) if reason == "Gateway 192.168.1.1 unreachable"
+        ));
+
+        // Recovery is quiet, and the next outage is reported again
+        assert!(tracker.observe(&up).is_none());
+        assert!(tracker.observe(&down).is_some());
+    }
+
+    #[test]
+    fn test_internet_health_storage_and_settings() {
+        let db = Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
+        let settings = InternetCheckSettings::default();
+
+        for dns_ms in [Some(20), None] {
+            let health = InternetHealth::assess(
+                &settings,
+                None,
+                vec![target("1.1.1.1", &[Some(10), Some(12)])],
+                dns_ms,
+            );
+            record_internet_health(&conn, &health).unwrap();
+        }
+        let checks = get_internet_health(&conn, None, 10).unwrap();
+        assert_eq!(checks.len(), 2);
+        assert_eq!(checks[0].status, InternetStatus::Up);
+        assert_eq!(checks[1].status, InternetStatus::Degraded);
+        assert_eq!(get_internet_health(&conn, None, 1).unwrap()[0].dns_ms, None);
+        let later = Utc::now() + chrono::Duration::hours(1);
+        assert!(get_internet_health(&conn, Some(later), 10)
+            .unwrap()
+            .is_empty());
+
+        assert_eq!(load_internet_check_settings(&conn).unwrap(), settings);
+        let mut custom = InternetCheckSettings {
+            gateway: Some("10.0.0.1".to_string()),
+            anchors: vec!["9.9.9.9".to_string()],
+            ..Default::default()
+        };
+        save_internet_check_settings(&conn, &custom).unwrap();
+        assert_eq!(load_internet_check_settings(&conn).unwrap(), custom);
+        custom.anchors.push("dns.google".to_string());
+        assert!(save_internet_check_settings(&conn, &custom).is_err());
+        custom.anchors.pop();
+        custom.max_loss_percent = 0;
+        assert!(save_internet_check_settings(&conn, &custom).is_err());
+    }
+}
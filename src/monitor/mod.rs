@@ -2,10 +2,26 @@
 //!
 //! Provides background scanning and live event emission
 
+pub mod changes;
+pub mod event_log;
 pub mod events;
+pub mod health;
+pub mod internet;
 pub mod passive_integration;
+pub mod presence;
+pub mod queue;
+pub mod schedule;
+pub mod stats;
 pub mod watcher;
 
+pub use changes::*;
+pub use event_log::*;
 pub use events::*;
+pub use health::*;
+pub use internet::*;
 pub use passive_integration::*;
+pub use presence::*;
+pub use queue::*;
+pub use schedule::*;
+pub use stats::*;
 pub use watcher::*;
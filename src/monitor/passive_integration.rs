@@ -3,9 +3,13 @@
 //! This module provides helper functions to integrate passive discovery
 //! into the background monitor
 
+use crate::database::{queries, Database};
 use crate::monitor::events::{DeviceSnapshot, NetworkEvent};
+use crate::network::lookup_vendor_info;
 use crate::scanner::passive::mdns::PassiveDevice;
-use crate::scanner::passive::{ArpEvent, ArpMonitor, PassiveScanner};
+use crate::scanner::passive::{
+    ArpAnomaly, ArpEvent, ArpMonitor, ArpThresholds, PassiveScanner, SniEvent, TlsSniMonitor,
+};
 use std::sync::Arc;
 use tokio::sync::mpsc;
 
@@ -43,14 +47,8 @@ pub async fn start_passive_listeners() -> Result<
 
 /// Try to start ARP monitor (may fail without admin privileges)
 async fn try_start_arp_monitor() -> Result<mpsc::Receiver<ArpEvent>, Box<dyn std::error::Error>> {
-    use pnet::datalink;
-
     // Find suitable interface
-    let interfaces = datalink::interfaces();
-    let interface = interfaces
-        .into_iter()
-        .find(|iface| !iface.is_loopback() && iface.is_up() && !iface.ips.is_empty())
-        .ok_or("No suitable network interface")?;
+    let interface = find_capture_interface().ok_or("No suitable network interface")?;
 
     let (tx, rx) = mpsc::channel(100);
     let monitor = ArpMonitor::new(interface);
@@ -64,6 +62,273 @@ async fn try_start_arp_monitor() -> Result<mpsc::Receiver<ArpEvent>, Box<dyn std
     Ok(rx)
 }
 
+/// Aborts a listener task when the task forwarding its output ends or is aborted
+struct AbortOnDrop(tokio::task::JoinHandle<()>);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// A device heard from by a passive listener
+#[derive(Debug, Clone, PartialEq)]
+pub struct PassiveSighting {
+    /// Sender of an ARP packet; mDNS announcements usually only carry an address
+    pub mac: Option<String>,
+    pub ip: String,
+    pub hostname: Option<String>,
+    /// Advertised mDNS service types
+    pub services: Vec<String>,
+}
+
+impl From<PassiveDevice> for PassiveSighting {
+    fn from(device: PassiveDevice) -> Self {
+        Self {
+            mac: device.mac,
+            ip: device.ip,
+            hostname: Some(device.hostname),
+            services: device.services,
+        }
+    }
+}
+
+/// Start passive mDNS and ARP listening for the background monitor
+///
+/// Every mDNS announcement and every ARP packet with a sender address is
+/// reported as a sighting. ARP is left out, with a warning, when packet
+/// capture is unavailable. Aborting the returned handle stops the listeners.
+pub fn start_passive_sightings() -> Result<
+    (mpsc::Receiver<PassiveSighting>, tokio::task::JoinHandle<()>),
+    Box<dyn std::error::Error>,
+> {
+    let scanner = PassiveScanner::new()?;
+    let (mdns_tx, mut mdns_rx) = mpsc::channel::<PassiveDevice>(100);
+    let mdns = tokio::spawn(async move {
+        if let Err(e) = scanner.start_listening(mdns_tx).await {
+            tracing::error!("mDNS listener error: {}", e);
+        }
+    });
+
+    let (arp, mut arp_rx) = match find_capture_interface() {
+        Some(interface) => {
+            let (arp_tx, arp_rx) = mpsc::channel::<ArpEvent>(256);
+            let monitor = ArpMonitor::new(interface);
+            let capture = tokio::spawn(async move {
+                if let Err(e) = monitor.start_monitoring(arp_tx).await {
+                    tracing::error!("ARP monitor error: {}", e);
+                }
+            });
+            (Some(capture), Some(arp_rx))
+        }
+        None => {
+            tracing::warn!("Passive ARP discovery disabled: no suitable network interface");
+            (None, None)
+        }
+    };
+
+    let (tx, rx) = mpsc::channel(256);
+    let forward = tokio::spawn(async move {
+        let _mdns = AbortOnDrop(mdns);
+        let _arp = arp.map(AbortOnDrop);
+        loop {
+            let sighting = tokio::select! {
+                device = mdns_rx.recv() => match device {
+                    Some(device) => PassiveSighting::from(device),
+                    None => break,
+                },
+                event = async {
+                    match arp_rx.as_mut() {
+                        Some(rx) => rx.recv().await,
+                        None => std::future::pending().await,
+                    }
+                } => match event {
+                    // Probes from hosts still choosing an address carry 0.0.0.0
+                    Some(event) if event.sender_ip != "0.0.0.0" => PassiveSighting {
+                        mac: Some(event.sender_mac),
+                        ip: event.sender_ip,
+                        hostname: None,
+                        services: Vec::new(),
+                    },
+                    Some(_) => continue,
+                    None => break,
+                },
+            };
+            if tx.send(sighting).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    Ok((rx, forward))
+}
+
+/// Start ARP anomaly detection, forwarding takeover, conflict and storm events to `callback`
+///
+/// Aborting the returned handle stops the underlying ARP monitor as well.
+pub fn start_arp_anomaly_watch<F>(
+    thresholds: ArpThresholds,
+    callback: F,
+) -> Result<tokio::task::JoinHandle<()>, Box<dyn std::error::Error>>
+where
+    F: Fn(NetworkEvent) + Send + Sync + 'static,
+{
+    let interface = find_capture_interface().ok_or("No suitable network interface")?;
+    let (event_tx, mut event_rx) = mpsc::channel::<ArpEvent>(256);
+    let (anomaly_tx, mut anomaly_rx) = mpsc::channel::<ArpAnomaly>(64);
+    let monitor = ArpMonitor::new(interface).with_anomaly_detection(thresholds, anomaly_tx);
+
+    let capture = tokio::spawn(async move {
+        if let Err(e) = monitor.start_monitoring(event_tx).await {
+            tracing::error!("ARP monitor error: {}", e);
+        }
+    });
+
+    Ok(tokio::spawn(async move {
+        // Stops the capture when the watch task ends or is aborted
+        let _capture = AbortOnDrop(capture);
+        loop {
+            tokio::select! {
+                anomaly = anomaly_rx.recv() => match anomaly {
+                    Some(anomaly) => callback(arp_anomaly_to_event(anomaly)),
+                    None => break,
+                },
+                // Plain ARP events are only needed for anomaly tracking here
+                event = event_rx.recv() => if event.is_none() {
+                    break;
+                },
+            }
+        }
+    }))
+}
+
+/// Convert an ARP anomaly into a monitoring event
+pub fn arp_anomaly_to_event(anomaly: ArpAnomaly) -> NetworkEvent {
+    match anomaly {
+        ArpAnomaly::AddressTakeover {
+            ip,
+            previous_mac,
+            new_mac,
+            gratuitous,
+            ..
+        } => NetworkEvent::ArpAddressTakeover {
+            ip,
+            previous_mac,
+            new_mac,
+            gratuitous,
+        },
+        ArpAnomaly::IpConflict { ip, macs, .. } => NetworkEvent::IpConflict { ip, macs },
+        ArpAnomaly::SharedMac { mac, ips, .. } => NetworkEvent::SharedMacDetected { mac, ips },
+        ArpAnomaly::ArpStorm {
+            mac,
+            ip,
+            request_count,
+            window_secs,
+            ..
+        } => NetworkEvent::ArpStormDetected {
+            mac,
+            ip,
+            request_count,
+            window_secs,
+        },
+    }
+}
+
+/// Start TLS SNI capture and persist observations to the database
+///
+/// Capture runs on its own thread, owned by the returned writer task:
+/// aborting the handle drops the capture, which stops the thread within its
+/// read timeout.
+pub fn start_sni_inventory(
+    db: Database,
+) -> Result<tokio::task::JoinHandle<()>, Box<dyn std::error::Error>> {
+    let interface = find_capture_interface().ok_or("No suitable network interface")?;
+    let (tx, mut rx) = mpsc::channel::<SniEvent>(256);
+    let capture = TlsSniMonitor::new(interface).start_monitoring(tx)?;
+
+    Ok(tokio::spawn(async move {
+        while let Some(event) = rx.recv().await {
+            let result = match db.writer() {
+                Ok(conn) => queries::record_sni_observation(
+                    &conn,
+                    &event.client_mac,
+                    &event.server_name,
+                    Some(&event.server_ip),
+                ),
+                Err(_) => {
+                    tracing::error!("Database lock poisoned, stopping SNI inventory");
+                    break;
+                }
+            };
+            if let Err(e) = result {
+                tracing::warn!("Failed to store SNI observation: {}", e);
+            }
+        }
+        capture.stop();
+    }))
+}
+
+/// Start passive mDNS listening and persist advertised services per device
+///
+/// Announcements are matched to a device by MAC when known, otherwise by the
+/// device last seen at the announcing IP; unmatched announcements are skipped.
+pub fn start_mdns_catalog(
+    db: Database,
+) -> Result<tokio::task::JoinHandle<()>, Box<dyn std::error::Error>> {
+    let scanner = PassiveScanner::new()?;
+    let (tx, mut rx) = mpsc::channel::<PassiveDevice>(100);
+
+    let listener = tokio::spawn(async move {
+        if let Err(e) = scanner.start_listening(tx).await {
+            tracing::error!("mDNS listener error: {}", e);
+        }
+    });
+
+    Ok(tokio::spawn(async move {
+        while let Some(device) = rx.recv().await {
+            let conn = match db.writer() {
+                Ok(conn) => conn,
+                Err(_) => {
+                    tracing::error!("Database lock poisoned, stopping mDNS catalog");
+                    break;
+                }
+            };
+
+            let mac = match device.mac.clone() {
+                Some(mac) => Some(mac),
+                None => queries::find_device_mac_by_ip(&conn, &device.ip).unwrap_or_else(|e| {
+                    tracing::warn!("Failed to resolve mDNS device {}: {}", device.ip, e);
+                    None
+                }),
+            };
+            let Some(mac) = mac else {
+                tracing::debug!("Skipping mDNS services from unknown device {}", device.ip);
+                continue;
+            };
+
+            for service_type in &device.services {
+                if let Err(e) = queries::record_device_service(
+                    &conn,
+                    &mac,
+                    service_type,
+                    device.instance_name.as_deref(),
+                    device.port,
+                ) {
+                    tracing::warn!("Failed to store mDNS service: {}", e);
+                }
+            }
+        }
+        listener.abort();
+    }))
+}
+
+/// Pick the first up, non-loopback interface with an address
+fn find_capture_interface() -> Option<pnet::datalink::NetworkInterface> {
+    pnet::datalink::interfaces()
+        .into_iter()
+        .find(|iface| !iface.is_loopback() && iface.is_up() && !iface.ips.is_empty())
+}
+
 /// Convert PassiveDevice to DeviceSnapshot
 pub fn passive_device_to_snapshot(device: PassiveDevice) -> DeviceSnapshot {
     // Use device type hint if available, otherwise default to "Unknown"
@@ -71,6 +336,11 @@ pub fn passive_device_to_snapshot(device: PassiveDevice) -> DeviceSnapshot {
         .device_type_hint
         .unwrap_or_else(|| "Unknown".to_string());
 
+    let vendor = device
+        .mac
+        .as_deref()
+        .and_then(|mac| lookup_vendor_info(mac).vendor);
+
     DeviceSnapshot {
         mac: device
             .mac
@@ -78,7 +348,13 @@ pub fn passive_device_to_snapshot(device: PassiveDevice) -> DeviceSnapshot {
         ip: device.ip,
         hostname: Some(device.hostname),
         device_type,
+        vendor,
+        // No ports are known from mDNS alone
+        risk_score: 0,
         is_online: true,
+        latency_ms: None,
+        open_ports: Vec::new(),
+        network: None,
     }
 }
 
@@ -100,6 +376,10 @@ where
     );
 
     // Emit as new device discovered (matching existing NetworkEvent structure)
+    let vendor = device
+        .mac
+        .as_deref()
+        .and_then(|mac| lookup_vendor_info(mac).vendor);
     callback(NetworkEvent::NewDeviceDiscovered {
         ip: device.ip.clone(),
         mac: device
@@ -107,6 +387,9 @@ where
             .unwrap_or_else(|| format!("unknown_{}", device.ip)),
         hostname: Some(device.hostname),
         device_type,
+        vendor,
+        risk_score: 0,
+        network: None,
     });
 }
 
@@ -130,6 +413,8 @@ mod tests {
             ip: "192.168.1.100".to_string(),
             mac: Some("aa:bb:cc:dd:ee:ff".to_string()),
             services: vec!["_http._tcp".to_string()],
+            instance_name: None,
+            port: Some(80),
             discovered_at: chrono::Utc::now(),
             device_type_hint: Some("Web Server".to_string()),
         };
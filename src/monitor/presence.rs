@@ -0,0 +1,224 @@
+//! Device presence sessions
+//!
+//! After each cycle the monitor records which devices it considers online
+//! in `presence_sessions`: a device that comes online opens a session, and
+//! one that drops out (after the usual missed-scan hysteresis) has its
+//! session closed at the time it was last seen. Sessions still open when
+//! monitoring stops, or that were left open by a crash, are closed the same
+//! way, so a monitoring gap never counts as presence. This answers questions
+//! like "when was this phone home last week".
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::events::DeviceSnapshot;
+use crate::database::queries::parse_datetime_column;
+
+/// A stretch of time a device was on the network
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PresenceSession {
+    pub id: i64,
+    pub mac: String,
+    /// Last address seen during the session
+    pub ip: String,
+    pub arrived_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+    /// When the device was last seen before leaving; `None` while present
+    pub left_at: Option<DateTime<Utc>>,
+    /// From arrival until the device left (or was last seen, while present)
+    pub duration_secs: i64,
+}
+
+fn format_time(time: DateTime<Utc>) -> String {
+    time.format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+/// Bring the open sessions in line with the devices online at `now`
+///
+/// Online devices without an open session get one; those with one are
+/// marked seen. Open sessions of devices no longer online are closed.
+pub fn update_presence(
+    conn: &Connection,
+    online: &[DeviceSnapshot],
+    now: DateTime<Utc>,
+) -> Result<()> {
+    let now = format_time(now);
+    let online: HashMap<String, &str> = online
+        .iter()
+        .map(|device| (device.mac.to_uppercase(), device.ip.as_str()))
+        .collect();
+
+    conn.execute_batch("SAVEPOINT update_presence")
+        .context("Failed to start update_presence transaction")?;
+
+    let result = (|| -> Result<()> {
+        let open: Vec<(i64, String)> = conn
+            .prepare("SELECT id, mac FROM presence_sessions WHERE left_at IS NULL")?
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+
+        let mut present = Vec::with_capacity(open.len());
+        for (id, mac) in open {
+            match online.get(&mac) {
+                Some(ip) => {
+                    conn.execute(
+                        "UPDATE presence_sessions SET ip = ?1, last_seen_at = ?2 WHERE id = ?3",
+                        params![ip, now, id],
+                    )?;
+                    present.push(mac);
+                }
+                None => {
+                    conn.execute(
+                        "UPDATE presence_sessions SET left_at = last_seen_at WHERE id = ?1",
+                        params![id],
+                    )?;
+                }
+            }
+        }
+
+        for (mac, ip) in &online {
+            if present.contains(mac) {
+                continue;
+            }
+            conn.execute(
+                r#"
+                INSERT INTO presence_sessions (mac, ip, arrived_at, last_seen_at)
+                VALUES (?1, ?2, ?3, ?3)
+                "#,
+                params![mac, ip, now],
+            )?;
+        }
+        Ok(())
+    })();
+
+    match result {
+        Ok(()) => conn
+            .execute_batch("RELEASE SAVEPOINT update_presence")
+            .context("Failed to commit update_presence transaction"),
+        Err(e) => {
+            let _ = conn.execute_batch(
+                "ROLLBACK TO SAVEPOINT update_presence; RELEASE SAVEPOINT update_presence",
+            );
+            Err(e).context("Failed to update presence sessions")
+        }
+    }
+}
+
+/// Close every open session at the time its device was last seen
+///
+/// Called when monitoring stops or starts, since nothing is known about
+/// presence while the monitor is not running. Returns the sessions closed.
+pub fn close_presence_sessions(conn: &Connection) -> Result<usize> {
+    conn.execute(
+        "UPDATE presence_sessions SET left_at = last_seen_at WHERE left_at IS NULL",
+        [],
+    )
+    .context("Failed to close presence sessions")
+}
+
+/// Sessions overlapping `since`..`until`, optionally for one device, newest first
+pub fn get_presence_sessions(
+    conn: &Connection,
+    mac: Option<&str>,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+) -> Result<Vec<PresenceSession>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT id, mac, ip, arrived_at, last_seen_at, left_at
+        FROM presence_sessions
+        WHERE (?1 IS NULL OR mac = UPPER(?1))
+          AND (?2 IS NULL OR COALESCE(left_at, last_seen_at) >= ?2)
+          AND (?3 IS NULL OR arrived_at <= ?3)
+        ORDER BY arrived_at DESC, id DESC
+        "#,
+    )?;
+    let sessions = stmt
+        .query_map(
+            params![mac, since.map(format_time), until.map(format_time)],
+            |row| {
+                let arrived_at = parse_datetime_column(row.get(3)?, 3)?;
+                let last_seen_at = parse_datetime_column(row.get(4)?, 4)?;
+                let left_at = row
+                    .get::<_, Option<String>>(5)?
+                    .map(|s| parse_datetime_column(s, 5))
+                    .transpose()?;
+                Ok(PresenceSession {
+                    id: row.get(0)?,
+                    mac: row.get(1)?,
+                    ip: row.get(2)?,
+                    arrived_at,
+                    last_seen_at,
+                    left_at,
+                    duration_secs: (left_at.unwrap_or(last_seen_at) - arrived_at).num_seconds(),
+                })
+            },
+        )?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("Failed to load presence sessions")?;
+    Ok(sessions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Database;
+    use chrono::TimeZone;
+
+    fn device(mac: &str, ip: &str) -> DeviceSnapshot {
+        DeviceSnapshot {
+            mac: mac.to_string(),
+            ip: ip.to_string(),
+            hostname: None,
+            device_type: "UNKNOWN".to_string(),
+            vendor: None,
+            risk_score: 0,
+            is_online: true,
+            latency_ms: None,
+            open_ports: Vec::new(),
+            network: None,
+        }
+    }
+
+    #[test]
+    fn test_presence_sessions() {
+        let db = Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
+        let at = |hour: u32| Utc.with_ymd_and_hms(2026, 3, 2, hour, 0, 0).unwrap();
+        let phone = device("aa:bb:cc:00:00:01", "192.168.1.20");
+        let laptop = device("AA:BB:CC:00:00:02", "192.168.1.21");
+
+        update_presence(&conn, &[phone.clone(), laptop.clone()], at(8)).unwrap();
+        update_presence(&conn, &[phone.clone(), laptop.clone()], at(9)).unwrap();
+        // The phone leaves, and comes back on another address
+        update_presence(&conn, std::slice::from_ref(&laptop), at(10)).unwrap();
+        update_presence(
+            &conn,
+            &[device("AA:BB:CC:00:00:01", "192.168.1.30"), laptop],
+            at(12),
+        )
+        .unwrap();
+
+        let sessions = get_presence_sessions(&conn, Some(&phone.mac), None, None).unwrap();
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[0].ip, "192.168.1.30");
+        assert_eq!(sessions[0].left_at, None);
+        assert_eq!(sessions[1].arrived_at, at(8));
+        assert_eq!(sessions[1].left_at, Some(at(9)));
+        assert_eq!(sessions[1].duration_secs, 3600);
+
+        // Only sessions overlapping the window
+        let morning = get_presence_sessions(&conn, None, Some(at(7)), Some(at(11))).unwrap();
+        assert_eq!(morning.len(), 2);
+        assert!(morning.iter().all(|s| s.arrived_at == at(8)));
+
+        // Stopping the monitor closes what is still open
+        assert_eq!(close_presence_sessions(&conn).unwrap(), 2);
+        let laptop = get_presence_sessions(&conn, Some("AA:BB:CC:00:00:02"), None, None).unwrap();
+        assert_eq!(laptop[0].left_at, Some(at(12)));
+        assert_eq!(laptop[0].duration_secs, 4 * 3600);
+    }
+}
@@ -0,0 +1,184 @@
+//! Bounded delivery of monitor events
+//!
+//! The scan loop hands its events to an [`EventQueue`] rather than calling
+//! the consumer directly, so a slow consumer (a busy UI, a locked database)
+//! no longer holds up scanning. A dispatcher thread delivers the events in
+//! order. Repeats of an event that is still waiting to be delivered are
+//! merged into it; when the queue is full, scan progress is dropped quietly
+//! and anything else is counted, and the consumer is told how many events
+//! it missed as soon as there is room again.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc::{self, error::TrySendError};
+
+use super::events::NetworkEvent;
+
+/// What [`EventQueue::push`] did with an event
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Delivery {
+    /// Queued for the dispatcher
+    Queued,
+    /// Identical to an event still waiting in the queue
+    Merged,
+    /// The queue was full
+    Dropped,
+}
+
+#[derive(Debug, Default)]
+struct Backlog {
+    /// The last event queued, as JSON, with its sequence number
+    last: Option<(String, u64)>,
+    /// Sequence number of the last event queued
+    queued: u64,
+    /// Events lost to overflow and not yet reported
+    dropped: u64,
+}
+
+/// Bounded queue in front of a monitor event consumer
+pub struct EventQueue {
+    tx: mpsc::Sender<(u64, NetworkEvent)>,
+    backlog: Mutex<Backlog>,
+    /// Sequence number of the last event handed to the consumer
+    delivered: Arc<AtomicU64>,
+}
+
+impl EventQueue {
+    /// Start a dispatcher that feeds `consumer` from a queue of `capacity` events
+    ///
+    /// The dispatcher runs until the queue is dropped and every queued
+    /// event has been delivered.
+    pub fn spawn<F>(capacity: usize, consumer: F) -> Self
+    where
+        F: Fn(NetworkEvent) + Send + 'static,
+    {
+        let (tx, mut rx) = mpsc::channel::<(u64, NetworkEvent)>(capacity.max(1));
+        let delivered = Arc::new(AtomicU64::new(0));
+        let progress = Arc::clone(&delivered);
+        tokio::task::spawn_blocking(move || {
+            while let Some((seq, event)) = rx.blocking_recv() {
+                progress.store(seq, Ordering::SeqCst);
+                consumer(event);
+            }
+        });
+
+        Self {
+            tx,
+            backlog: Mutex::new(Backlog::default()),
+            delivered,
+        }
+    }
+
+    /// Queue an event without waiting
+    pub fn push(&self, event: NetworkEvent) -> Delivery {
+        let json = serde_json::to_string(&event).unwrap_or_default();
+        let mut backlog = self.backlog.lock().unwrap_or_else(|e| e.into_inner());
+
+        let waiting = self.delivered.load(Ordering::SeqCst);
+        if matches!(&backlog.last, Some((last, seq)) if *last == json && *seq > waiting) {
+            return Delivery::Merged;
+        }
+
+        if backlog.dropped > 0 {
+            let notice = NetworkEvent::MonitoringError {
+                message: format!(
+                    "Event queue overflowed; {} events were dropped",
+                    backlog.dropped
+                ),
+            };
+            if self.tx.try_send((backlog.queued + 1, notice)).is_ok() {
+                backlog.queued += 1;
+                backlog.dropped = 0;
+            }
+        }
+
+        let seq = backlog.queued + 1;
+        match self.tx.try_send((seq, event)) {
+            Ok(()) => {
+                backlog.queued = seq;
+                backlog.last = Some((json, seq));
+                Delivery::Queued
+            }
+            Err(TrySendError::Full((_, NetworkEvent::ScanProgress { .. }))) => Delivery::Dropped,
+            Err(TrySendError::Full(_)) => {
+                if backlog.dropped == 0 {
+                    tracing::warn!("[MONITOR] Event queue full; dropping events");
+                }
+                backlog.dropped += 1;
+                Delivery::Dropped
+            }
+            Err(TrySendError::Closed(_)) => Delivery::Dropped,
+        }
+    }
+
+    /// Wait until at least half the queue is free
+    ///
+    /// The scan loop calls this before each cycle, so a consumer that
+    /// cannot keep up slows scanning down instead of losing its events.
+    pub async fn wait_for_room(&self) {
+        let half = (self.tx.max_capacity() / 2).max(1);
+        let _ = self.tx.reserve_many(half).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc as std_mpsc;
+
+    fn started(scan_number: u32) -> NetworkEvent {
+        NetworkEvent::ScanStarted {
+            scan_number,
+            light: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_queue_merges_and_drops_under_load() {
+        let gate = Arc::new(Mutex::new(()));
+        let (entered_tx, entered_rx) = std_mpsc::channel();
+        let (out_tx, out_rx) = std_mpsc::channel();
+        let consumer_gate = Arc::clone(&gate);
+        let queue = EventQueue::spawn(3, move |event| {
+            let _ = entered_tx.send(());
+            let _open = consumer_gate.lock().unwrap();
+            let _ = out_tx.send(event);
+        });
+
+        // Hold the consumer on its first event
+        let closed = gate.lock().unwrap();
+        assert_eq!(queue.push(started(1)), Delivery::Queued);
+        entered_rx.recv().unwrap();
+
+        let error = NetworkEvent::MonitoringError {
+            message: "ARP scan error".to_string(),
+        };
+        assert_eq!(queue.push(error.clone()), Delivery::Queued);
+        assert_eq!(queue.push(error.clone()), Delivery::Merged);
+        assert_eq!(queue.push(started(2)), Delivery::Queued);
+        assert_eq!(queue.push(started(3)), Delivery::Queued);
+        let progress = NetworkEvent::ScanProgress {
+            phase: "ARP".to_string(),
+            percent: 10,
+            message: "ARP scanning".to_string(),
+        };
+        assert_eq!(queue.push(progress), Delivery::Dropped);
+        assert_eq!(queue.push(started(4)), Delivery::Dropped);
+
+        // Once delivered, the same event is news again
+        drop(closed);
+        let delivered: Vec<NetworkEvent> = (0..4).map(|_| out_rx.recv().unwrap()).collect();
+        assert!(matches!(delivered[1], NetworkEvent::MonitoringError { .. }));
+        queue.wait_for_room().await;
+        assert_eq!(queue.push(error), Delivery::Queued);
+        drop(queue);
+
+        // The overflow is reported ahead of the next event
+        let rest: Vec<NetworkEvent> = out_rx.iter().collect();
+        assert_eq!(rest.len(), 2);
+        assert!(matches!(
+            &rest[0],
+            NetworkEvent::MonitoringError { message } if message.contains("1 events were dropped")
+        ));
+    }
+}
@@ -0,0 +1,300 @@
+//! Office-hours monitoring schedule
+//!
+//! Two monitoring profiles, one for office hours and one for the rest of the
+//! week (e.g. a short interval during the working day, a long one overnight).
+//! Quiet hours (e.g. 22:00 to 07:00) stop active scanning altogether and
+//! leave only passive discovery running, whatever the profile. The schedule
+//! is saved in app settings and the background monitor checks it before
+//! every cycle, so switching needs no restart.
+
+use anyhow::{bail, Result};
+use chrono::{DateTime, Datelike, NaiveTime, TimeZone, Weekday};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{MAX_FULL_SCAN_EVERY, MAX_MONITOR_INTERVAL, MIN_MONITOR_INTERVAL};
+use crate::database::queries::{self, SETTING_MONITOR_SCHEDULE};
+
+/// One monitoring configuration
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MonitorProfile {
+    pub name: String,
+    /// Seconds between scan cycles
+    pub interval_seconds: u64,
+    /// Run the full ARP+TCP+DNS scan every this many cycles; the cycles in
+    /// between only ping known devices (1 = always full)
+    #[serde(default = "default_full_scan_every")]
+    pub full_scan_every: u32,
+}
+
+fn default_full_scan_every() -> u32 {
+    1
+}
+
+/// Hours without active scanning (passive discovery keeps running)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QuietHours {
+    pub enabled: bool,
+    /// Days on which quiet hours start
+    pub days: Vec<Weekday>,
+    /// Local time quiet hours start, "HH:MM"
+    pub start: String,
+    /// Local time quiet hours end, "HH:MM"; before `start` runs past midnight
+    pub end: String,
+}
+
+impl Default for QuietHours {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            days: vec![
+                Weekday::Mon,
+                Weekday::Tue,
+                Weekday::Wed,
+                Weekday::Thu,
+                Weekday::Fri,
+                Weekday::Sat,
+                Weekday::Sun,
+            ],
+            start: "22:00".to_string(),
+            end: "07:00".to_string(),
+        }
+    }
+}
+
+/// Weekly schedule switching between two monitoring profiles
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MonitorSchedule {
+    /// When off, the interval passed to `start` is used all week
+    pub enabled: bool,
+    /// Days on which office hours start
+    pub days: Vec<Weekday>,
+    /// Local time office hours start, "HH:MM"
+    pub start: String,
+    /// Local time office hours end, "HH:MM"; before `start` runs past midnight
+    pub end: String,
+    pub office_hours: MonitorProfile,
+    pub off_hours: MonitorProfile,
+    /// Applies whether or not profile switching is enabled
+    pub quiet_hours: QuietHours,
+}
+
+impl Default for MonitorSchedule {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            days: vec![
+                Weekday::Mon,
+                Weekday::Tue,
+                Weekday::Wed,
+                Weekday::Thu,
+                Weekday::Fri,
+            ],
+            start: "08:00".to_string(),
+            end: "18:00".to_string(),
+            office_hours: MonitorProfile {
+                name: "Office hours".to_string(),
+                interval_seconds: 30,
+                full_scan_every: 1,
+            },
+            off_hours: MonitorProfile {
+                name: "Off hours".to_string(),
+                interval_seconds: 600,
+                full_scan_every: 1,
+            },
+            quiet_hours: QuietHours::default(),
+        }
+    }
+}
+
+impl MonitorSchedule {
+    fn validate(&self) -> Result<()> {
+        let start = parse_time(&self.start)?;
+        let end = parse_time(&self.end)?;
+        if start == end {
+            bail!("Office hours must not start and end at the same time");
+        }
+        let quiet = &self.quiet_hours;
+        if parse_time(&quiet.start)? == parse_time(&quiet.end)? {
+            bail!("Quiet hours must not start and end at the same time");
+        }
+        for profile in [&self.office_hours, &self.off_hours] {
+            if profile.name.trim().is_empty() {
+                bail!("Monitoring profiles need a name");
+            }
+            if !(MIN_MONITOR_INTERVAL..=MAX_MONITOR_INTERVAL).contains(&profile.interval_seconds) {
+                bail!(
+                    "Interval for '{}' must be between {} and {} seconds",
+                    profile.name,
+                    MIN_MONITOR_INTERVAL,
+                    MAX_MONITOR_INTERVAL
+                );
+            }
+            if !(1..=MAX_FULL_SCAN_EVERY).contains(&profile.full_scan_every) {
+                bail!(
+                    "Full scans for '{}' must run every 1 to {} cycles",
+                    profile.name,
+                    MAX_FULL_SCAN_EVERY
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether `at` falls within office hours
+    pub fn is_office_hours<Tz: TimeZone>(&self, at: &DateTime<Tz>) -> bool {
+        in_window(&self.days, &self.start, &self.end, at)
+    }
+
+    /// Whether active scanning is off at `at`
+    pub fn is_quiet_hours<Tz: TimeZone>(&self, at: &DateTime<Tz>) -> bool {
+        let quiet = &self.quiet_hours;
+        quiet.enabled && in_window(&quiet.days, &quiet.start, &quiet.end, at)
+    }
+
+    /// Profile in effect at `at`, or `None` when the schedule is off
+    pub fn profile_at<Tz: TimeZone>(&self, at: &DateTime<Tz>) -> Option<&MonitorProfile> {
+        if !self.enabled {
+            return None;
+        }
+        Some(if self.is_office_hours(at) {
+            &self.office_hours
+        } else {
+            &self.off_hours
+        })
+    }
+}
+
+/// Whether `at` falls in the weekly window from `start` to `end` on `days`
+fn in_window<Tz: TimeZone>(days: &[Weekday], start: &str, end: &str, at: &DateTime<Tz>) -> bool {
+    let (Ok(start), Ok(end)) = (parse_time(start), parse_time(end)) else {
+        return false;
+    };
+    let time = at.time();
+    let weekday = at.weekday();
+
+    if start < end {
+        days.contains(&weekday) && start <= time && time < end
+    } else {
+        // Overnight hours belong to the day they start on
+        (days.contains(&weekday) && time >= start) || (days.contains(&weekday.pred()) && time < end)
+    }
+}
+
+fn parse_time(value: &str) -> Result<NaiveTime> {
+    match NaiveTime::parse_from_str(value, "%H:%M") {
+        Ok(time) => Ok(time),
+        Err(_) => bail!("Invalid time '{}', expected HH:MM", value),
+    }
+}
+
+/// Load the monitoring schedule (disabled if none is saved)
+pub fn load_monitor_schedule(conn: &Connection) -> Result<MonitorSchedule> {
+    Ok(queries::get_json_setting(conn, SETTING_MONITOR_SCHEDULE)?.unwrap_or_default())
+}
+
+/// Validate and persist the monitoring schedule
+pub fn save_monitor_schedule(conn: &Connection, schedule: &MonitorSchedule) -> Result<()> {
+    schedule.validate()?;
+    queries::set_json_setting(conn, SETTING_MONITOR_SCHEDULE, schedule)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Database;
+    use chrono::Utc;
+
+    fn at(date: &str, time: &str) -> DateTime<Utc> {
+        format!("{}T{}:00Z", date, time).parse().unwrap()
+    }
+
+    #[test]
+    fn test_profile_switching() {
+        let mut schedule = MonitorSchedule::default();
+        // 2026-03-02 is a Monday, 2026-03-07 a Saturday
+        assert_eq!(schedule.profile_at(&at("2026-03-02", "09:00")), None);
+
+        schedule.enabled = true;
+        let name = |at: DateTime<Utc>| schedule.profile_at(&at).unwrap().name.clone();
+        assert_eq!(name(at("2026-03-02", "08:00")), "Office hours");
+        assert_eq!(name(at("2026-03-02", "18:00")), "Off hours");
+        assert_eq!(name(at("2026-03-02", "07:59")), "Off hours");
+        assert_eq!(name(at("2026-03-07", "10:00")), "Off hours");
+
+        // Night shift: Friday 22:00 runs into Saturday morning
+        schedule.start = "22:00".to_string();
+        schedule.end = "06:00".to_string();
+        assert!(schedule.is_office_hours(&at("2026-03-06", "23:00")));
+        assert!(schedule.is_office_hours(&at("2026-03-07", "05:00")));
+        assert!(!schedule.is_office_hours(&at("2026-03-07", "23:00")));
+        assert!(!schedule.is_office_hours(&at("2026-03-02", "05:00")));
+    }
+
+    #[test]
+    fn test_quiet_hours() {
+        let mut schedule = MonitorSchedule::default();
+        assert!(!schedule.is_quiet_hours(&at("2026-03-02", "23:00")));
+
+        // Independent of profile switching; weeknights only
+        schedule.quiet_hours.enabled = true;
+        schedule.quiet_hours.days = vec![
+            Weekday::Mon,
+            Weekday::Tue,
+            Weekday::Wed,
+            Weekday::Thu,
+            Weekday::Fri,
+        ];
+        assert!(schedule.is_quiet_hours(&at("2026-03-02", "22:00")));
+        assert!(schedule.is_quiet_hours(&at("2026-03-03", "06:59")));
+        assert!(!schedule.is_quiet_hours(&at("2026-03-03", "07:00")));
+        assert!(schedule.is_quiet_hours(&at("2026-03-07", "03:00")));
+        assert!(!schedule.is_quiet_hours(&at("2026-03-07", "23:00")));
+        assert!(!schedule.is_quiet_hours(&at("2026-03-02", "03:00")));
+
+        // Schedules saved before quiet hours existed have them off
+        let old: MonitorSchedule = serde_json::from_str(r#"{"enabled":true}"#).unwrap();
+        assert!(!old.quiet_hours.enabled);
+    }
+
+    #[test]
+    fn test_schedule_roundtrip_and_validation() {
+        let db = Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
+
+        assert_eq!(
+            load_monitor_schedule(&conn).unwrap(),
+            MonitorSchedule::default()
+        );
+
+        let mut schedule = MonitorSchedule {
+            enabled: true,
+            ..Default::default()
+        };
+        save_monitor_schedule(&conn, &schedule).unwrap();
+        assert_eq!(load_monitor_schedule(&conn).unwrap(), schedule);
+
+        schedule.end = "8am".to_string();
+        assert!(save_monitor_schedule(&conn, &schedule).is_err());
+        schedule.end = "08:00".to_string();
+        assert!(save_monitor_schedule(&conn, &schedule).is_err());
+        schedule.end = "18:00".to_string();
+        schedule.off_hours.interval_seconds = 0;
+        assert!(save_monitor_schedule(&conn, &schedule).is_err());
+        schedule.off_hours.interval_seconds = 600;
+        schedule.off_hours.full_scan_every = 0;
+        assert!(save_monitor_schedule(&conn, &schedule).is_err());
+        schedule.off_hours.full_scan_every = 10;
+        schedule.quiet_hours.end = "22:00".to_string();
+        assert!(save_monitor_schedule(&conn, &schedule).is_err());
+        schedule.quiet_hours.end = "07:00".to_string();
+        save_monitor_schedule(&conn, &schedule).unwrap();
+
+        // Profiles saved before light cycles existed always scan fully
+        let old: MonitorProfile =
+            serde_json::from_str(r#"{"name":"Night","interval_seconds":600}"#).unwrap();
+        assert_eq!(old.full_scan_every, 1);
+    }
+}
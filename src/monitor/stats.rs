@@ -0,0 +1,188 @@
+//! Monitor statistics
+//!
+//! Counters kept while the monitor runs, so users can check it is healthy:
+//! how long it has been up, how long scans take, how many failed and how
+//! often each kind of event fires. They are reset on every start.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+use super::events::NetworkEvent;
+
+/// Aggregated metrics of the current (or last) monitoring session
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MonitoringStats {
+    pub is_running: bool,
+    /// When the session started; `None` if the monitor never ran
+    pub started_at: Option<DateTime<Utc>>,
+    /// Time since the session started (or until it stopped)
+    pub uptime_secs: u64,
+    pub scans_completed: u64,
+    pub scans_failed: u64,
+    /// Failed scans since the last one that succeeded
+    pub consecutive_failures: u32,
+    pub avg_scan_duration_ms: Option<u64>,
+    pub last_scan_duration_ms: Option<u64>,
+    /// Times the watchdog restarted the scan loop
+    pub restarts: u32,
+    /// Events emitted, scan progress aside
+    pub events_total: u64,
+    /// Events per hour of uptime, by kind
+    pub events_per_hour: BTreeMap<String, f64>,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    started_at: Option<DateTime<Utc>>,
+    stopped_at: Option<DateTime<Utc>>,
+    scans_completed: u64,
+    scans_failed: u64,
+    scan_ms_total: u64,
+    last_scan_ms: Option<u64>,
+    restarts: u32,
+    events: HashMap<&'static str, u64>,
+}
+
+/// Counters shared by the scan loop, its watchdog and the event dispatcher
+#[derive(Debug, Default)]
+pub(crate) struct StatsRecorder {
+    counters: Mutex<Counters>,
+}
+
+impl StatsRecorder {
+    fn counters(&self) -> std::sync::MutexGuard<'_, Counters> {
+        self.counters.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Start counting a new session
+    pub(crate) fn start(&self, now: DateTime<Utc>) {
+        *self.counters() = Counters {
+            started_at: Some(now),
+            ..Counters::default()
+        };
+    }
+
+    pub(crate) fn scan_completed(&self, duration_ms: u64) {
+        let mut counters = self.counters();
+        counters.scans_completed += 1;
+        counters.scan_ms_total += duration_ms;
+        counters.last_scan_ms = Some(duration_ms);
+    }
+
+    pub(crate) fn scan_failed(&self) {
+        self.counters().scans_failed += 1;
+    }
+
+    pub(crate) fn restarted(&self) {
+        self.counters().restarts += 1;
+    }
+
+    /// Count an event; `MonitoringStopped` also ends the session's uptime
+    pub(crate) fn event(&self, event: &NetworkEvent, now: DateTime<Utc>) {
+        if matches!(event, NetworkEvent::ScanProgress { .. }) {
+            return;
+        }
+        let mut counters = self.counters();
+        *counters.events.entry(event.kind()).or_insert(0) += 1;
+        if matches!(event, NetworkEvent::MonitoringStopped) {
+            counters.stopped_at = Some(now);
+        }
+    }
+
+    pub(crate) fn snapshot(
+        &self,
+        now: DateTime<Utc>,
+        is_running: bool,
+        consecutive_failures: u32,
+    ) -> MonitoringStats {
+        let counters = self.counters();
+        let uptime_secs = counters.started_at.map_or(0, |started| {
+            let until = match counters.stopped_at {
+                Some(stopped) if !is_running => stopped,
+                _ => now,
+            };
+            (until - started).num_seconds().max(0) as u64
+        });
+        // At least a minute, so a fresh start does not show huge rates
+        let hours = uptime_secs.max(60) as f64 / 3600.0;
+
+        MonitoringStats {
+            is_running,
+            started_at: counters.started_at,
+            uptime_secs,
+            scans_completed: counters.scans_completed,
+            scans_failed: counters.scans_failed,
+            consecutive_failures,
+            avg_scan_duration_ms: counters.scan_ms_total.checked_div(counters.scans_completed),
+            last_scan_duration_ms: counters.last_scan_ms,
+            restarts: counters.restarts,
+            events_total: counters.events.values().sum(),
+            events_per_hour: counters
+                .events
+                .iter()
+                .map(|(kind, count)| (kind.to_string(), *count as f64 / hours))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_monitoring_stats() {
+        let stats = StatsRecorder::default();
+        assert_eq!(
+            stats.snapshot(Utc::now(), false, 0),
+            MonitoringStats::default()
+        );
+
+        let start = Utc::now();
+        stats.start(start);
+        stats.scan_completed(1000);
+        stats.scan_completed(3000);
+        stats.scan_failed();
+        for scan_number in 1..=4 {
+            stats.event(
+                &NetworkEvent::ScanStarted {
+                    scan_number,
+                    light: false,
+                },
+                start,
+            );
+        }
+        stats.event(
+            &NetworkEvent::ScanProgress {
+                phase: "ARP".to_string(),
+                percent: 10,
+                message: "ARP scanning".to_string(),
+            },
+            start,
+        );
+
+        let snapshot = stats.snapshot(start + Duration::hours(2), true, 1);
+        assert_eq!(snapshot.uptime_secs, 7200);
+        assert_eq!(snapshot.avg_scan_duration_ms, Some(2000));
+        assert_eq!(snapshot.last_scan_duration_ms, Some(3000));
+        assert_eq!(snapshot.events_total, 4);
+        assert_eq!(snapshot.events_per_hour["ScanStarted"], 2.0);
+
+        // Uptime ends with the session
+        stats.event(&NetworkEvent::MonitoringStopped, start + Duration::hours(3));
+        let stopped = stats.snapshot(start + Duration::hours(5), false, 0);
+        assert_eq!(stopped.uptime_secs, 3 * 3600);
+
+        // A new start resets everything
+        stats.start(start + Duration::hours(6));
+        assert_eq!(
+            stats
+                .snapshot(start + Duration::hours(6), true, 0)
+                .scans_completed,
+            0
+        );
+    }
+}
@@ -1,56 +1,330 @@
 //! Background network watcher
 //!
 //! Provides continuous network scanning in background thread
-//! Uses callbacks for event notification (Tauri-agnostic)
+//! Uses callbacks for event notification (Tauri-agnostic), fed from a
+//! bounded queue so a slow consumer does not stall scanning. A watchdog
+//! restarts the scan loop if it crashes, hangs or keeps failing.
 
+use ipnetwork::Ipv4Network;
+use pnet::util::MacAddr;
 use std::collections::HashMap;
-use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
-use std::sync::Arc;
+use std::net::Ipv4Addr;
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
 use std::time::{Duration, Instant};
 use tokio::sync::Mutex;
 
-use super::events::{DeviceSnapshot, MonitoringStatus, NetworkEvent};
-use crate::config::{DEFAULT_MONITOR_INTERVAL, MAX_MONITOR_INTERVAL, MIN_MONITOR_INTERVAL};
+use super::changes::{ChangeDetector, Hysteresis};
+use super::event_log::record_event;
+use super::events::{
+    DeviceSnapshot, EventFilter, MonitorInterface, MonitoringStatus, NetworkEvent,
+};
+use super::internet::{
+    check_internet, record_internet_health, InternetCheckSettings, InternetTracker,
+};
+use super::passive_integration::{start_passive_sightings, PassiveSighting};
+use super::presence::{close_presence_sessions, update_presence};
+use super::queue::EventQueue;
+use super::schedule::{MonitorProfile, MonitorSchedule};
+use super::stats::{MonitoringStats, StatsRecorder};
+use crate::alerts::{apply_alert_rules, record_untrusted_device, RuleInput};
+use crate::config::{
+    DEFAULT_MONITOR_INTERVAL, MAX_FULL_SCAN_EVERY, MAX_MONITOR_INTERVAL, MAX_SCAN_HOSTS,
+    MIN_MONITOR_INTERVAL, MONITOR_CONFIRM_SCANS, MONITOR_EVENT_QUEUE_SIZE, MONITOR_MISSED_SCANS,
+    MONITOR_RECOVERY_FAILURES, MONITOR_RESTART_DELAY, MONITOR_STALL_TIMEOUT_SECS,
+    MONITOR_WATCHDOG_INTERVAL, TCP_PROBE_PORTS, WATCH_CHECK_INTERVAL_SECS,
+};
+use crate::database::{get_network_baseline, Database, DeviceSettings};
+use crate::insights::{find_baseline_deviations, DeviationTracker};
+use crate::models::{HostInfo, InterfaceInfo};
+use crate::network::{link_speed_mbps, wifi_ssid};
 use crate::{
-    active_arp_scan, calculate_subnet_ips, dns_scan, find_valid_interface, infer_device_type,
-    lookup_vendor_info, tcp_probe_scan,
+    active_arp_scan, calculate_risk_score, calculate_subnet_ips, chunked_arp_scan, dns_scan_with,
+    icmp_probe_scan, infer_device_type, infer_device_type_with_services, lookup_vendor_info,
+    select_interface, select_interface_for, tcp_probe_scan, ArpScan, DnsResolver,
 };
 
-const OFFLINE_RETENTION_SECS: u64 = 3600;
-
+/// What the last completed scan cycle did
 #[derive(Debug, Clone)]
-struct OfflineDeviceSnapshot {
-    device: DeviceSnapshot,
-    since: Instant,
+struct CycleStats {
+    interface: MonitorInterface,
+    packets_sent: u64,
+    duration_ms: u64,
+}
+
+/// Result of one background scan
+struct ScanCycle {
+    devices: Vec<DeviceSnapshot>,
+    interface: MonitorInterface,
+    packets_sent: u64,
 }
 
 /// Event callback type
 pub type EventCallback = Arc<dyn Fn(NetworkEvent) + Send + Sync>;
 
+/// A listener added with [`BackgroundMonitor::subscribe`]
+struct Subscriber {
+    id: u64,
+    filter: EventFilter,
+    callback: EventCallback,
+}
+
+/// Subscribers and what their filters need to know about devices
+#[derive(Default)]
+struct Subscribers {
+    list: RwLock<Vec<Subscriber>>,
+    /// Device tags (MAC -> tags) for tag filters
+    device_tags: RwLock<HashMap<String, Vec<String>>>,
+    next_id: AtomicU64,
+}
+
+impl Subscribers {
+    /// Pass an event to every subscriber whose filter matches
+    fn dispatch(&self, event: &NetworkEvent) {
+        let (Ok(list), Ok(tags)) = (self.list.read(), self.device_tags.read()) else {
+            return;
+        };
+        for subscriber in list.iter() {
+            if subscriber.filter.matches(event, &tags) {
+                (subscriber.callback)(event.clone());
+            }
+        }
+    }
+}
+
 /// Background network monitor
 pub struct BackgroundMonitor {
     is_running: Arc<AtomicBool>,
+    /// Scans are skipped while set; the loop and its state stay alive
+    is_paused: Arc<AtomicBool>,
     interval_seconds: Arc<Mutex<u64>>,
+    /// Cycles per full scan when no scheduled profile is in force
+    full_scan_every: Arc<AtomicU32>,
     scan_count: Arc<AtomicU32>,
     last_scan_time: Arc<Mutex<Option<String>>>,
-    /// Current online devices from previous scan (MAC -> DeviceSnapshot)
-    previous_devices: Arc<Mutex<HashMap<String, DeviceSnapshot>>>,
-    /// Recently-offline devices for "came online" event correlation.
-    offline_devices: Arc<Mutex<HashMap<String, OfflineDeviceSnapshot>>>,
+    last_cycle: Arc<Mutex<Option<CycleStats>>>,
+    /// Online and recently-offline devices carried between scans
+    changes: Arc<Mutex<ChangeDetector>>,
+    /// Per-device alerting overrides (MAC -> settings)
+    device_settings: Arc<Mutex<HashMap<String, DeviceSettings>>>,
+    /// Interface to scan (`None` selects one automatically)
+    interface_name: Arc<Mutex<Option<String>>>,
+    /// Interfaces or CIDR ranges swept in parallel (empty: just `interface_name`)
+    targets: Arc<Mutex<Vec<String>>>,
+    /// Backend for reverse DNS lookups
+    dns_resolver: Arc<Mutex<DnsResolver>>,
+    /// Office-hours schedule overriding the interval when enabled
+    schedule: Arc<Mutex<MonitorSchedule>>,
+    /// Name of the scheduled profile used by the current cycle
+    active_profile: Arc<Mutex<Option<String>>>,
+    /// Also listen for mDNS and ARP traffic between scans
+    passive_discovery: Arc<AtomicBool>,
+    /// Database every event is written to, for replay
+    event_store: Arc<Mutex<Option<Database>>>,
+    /// Internet health check run after every cycle (off when `None`)
+    internet_check: Arc<Mutex<Option<InternetCheckSettings>>>,
+    /// Filtered listeners besides the callback given to `start`
+    subscribers: Arc<Subscribers>,
+    /// Scan cycles failed in a row
+    failures: Arc<AtomicU32>,
+    /// Counters behind [`BackgroundMonitor::stats`]
+    stats: Arc<StatsRecorder>,
 }
 
 impl BackgroundMonitor {
     pub fn new() -> Self {
         Self {
             is_running: Arc::new(AtomicBool::new(false)),
+            is_paused: Arc::new(AtomicBool::new(false)),
             interval_seconds: Arc::new(Mutex::new(DEFAULT_MONITOR_INTERVAL)),
+            full_scan_every: Arc::new(AtomicU32::new(1)),
             scan_count: Arc::new(AtomicU32::new(0)),
             last_scan_time: Arc::new(Mutex::new(None)),
-            previous_devices: Arc::new(Mutex::new(HashMap::new())),
-            offline_devices: Arc::new(Mutex::new(HashMap::new())),
+            last_cycle: Arc::new(Mutex::new(None)),
+            changes: Arc::new(Mutex::new(ChangeDetector::with_hysteresis(Hysteresis {
+                missed_scans: MONITOR_MISSED_SCANS,
+                confirm_scans: MONITOR_CONFIRM_SCANS,
+            }))),
+            device_settings: Arc::new(Mutex::new(HashMap::new())),
+            interface_name: Arc::new(Mutex::new(None)),
+            targets: Arc::new(Mutex::new(Vec::new())),
+            dns_resolver: Arc::new(Mutex::new(DnsResolver::default())),
+            schedule: Arc::new(Mutex::new(MonitorSchedule::default())),
+            active_profile: Arc::new(Mutex::new(None)),
+            passive_discovery: Arc::new(AtomicBool::new(false)),
+            event_store: Arc::new(Mutex::new(None)),
+            internet_check: Arc::new(Mutex::new(None)),
+            subscribers: Arc::default(),
+            failures: Arc::new(AtomicU32::new(0)),
+            stats: Arc::default(),
+        }
+    }
+
+    /// Replace the per-device overrides used for offline detection
+    ///
+    /// Takes effect from the next scan; safe to call while running.
+    pub async fn set_device_settings(&self, settings: Vec<DeviceSettings>) {
+        *self.device_settings.lock().await = settings
+            .into_iter()
+            .map(|s| (s.device_mac.clone(), s))
+            .collect();
+    }
+
+    /// Choose the interface to scan (`None` selects one automatically)
+    ///
+    /// Takes effect from the next scan; safe to call while running.
+    pub async fn set_interface(&self, name: Option<String>) {
+        *self.interface_name.lock().await = name;
+    }
+
+    /// Sweep several networks each cycle, for multi-VLAN environments
+    ///
+    /// Each target is an interface name (`eth0.20`) or a CIDR range on one
+    /// of the local subnets (`10.0.20.0/24`); they are swept in parallel and
+    /// device events name the network they came from. An empty list scans
+    /// the single interface chosen with [`BackgroundMonitor::set_interface`].
+    /// Takes effect from the next scan; safe to call while running.
+    pub async fn set_targets(&self, targets: Vec<String>) {
+        let mut cleaned: Vec<String> = Vec::new();
+        for target in targets {
+            let target = target.trim();
+            if !target.is_empty() && !cleaned.iter().any(|t| t == target) {
+                cleaned.push(target.to_string());
+            }
+        }
+        *self.targets.lock().await = cleaned;
+    }
+
+    /// Choose the backend for reverse DNS lookups
+    ///
+    /// Takes effect from the next scan; safe to call while running.
+    pub async fn set_dns_resolver(&self, resolver: DnsResolver) {
+        *self.dns_resolver.lock().await = resolver;
+    }
+
+    /// Replace the office-hours schedule
+    ///
+    /// Takes effect immediately, including during the wait for the next scan;
+    /// safe to call while running.
+    pub async fn set_schedule(&self, schedule: MonitorSchedule) {
+        *self.schedule.lock().await = schedule;
+    }
+
+    /// Listen passively for mDNS announcements and ARP traffic while running
+    ///
+    /// New devices and returning ones are then reported within seconds of
+    /// being heard from instead of at the next scan. Takes effect from the
+    /// next start.
+    pub fn set_passive_discovery(&self, enabled: bool) {
+        self.passive_discovery.store(enabled, Ordering::SeqCst);
+    }
+
+    /// Write every event (apart from scan progress) to `db` as it is emitted
+    ///
+    /// Takes effect from the next start; `None` stops persisting events.
+    pub async fn set_event_store(&self, db: Option<Database>) {
+        *self.event_store.lock().await = db;
+    }
+
+    /// Ping the gateway and internet anchors and time DNS after every cycle
+    ///
+    /// Checks are stored with the events (see [`Self::set_event_store`]) and
+    /// the connection turning degraded or down is reported as an event.
+    /// Takes effect from the next cycle; `None` or disabled settings turn
+    /// the check off.
+    pub async fn set_internet_check(&self, settings: Option<InternetCheckSettings>) {
+        *self.internet_check.lock().await = settings.filter(|s| s.enabled);
+    }
+
+    /// Add a listener receiving only the events `filter` passes
+    ///
+    /// Subscribers are kept across stop and start; the callback given to
+    /// [`BackgroundMonitor::start`] still receives every event. Returns an id
+    /// for [`BackgroundMonitor::unsubscribe`].
+    pub fn subscribe<F>(&self, filter: EventFilter, callback: F) -> u64
+    where
+        F: Fn(NetworkEvent) + Send + Sync + 'static,
+    {
+        let id = self.subscribers.next_id.fetch_add(1, Ordering::SeqCst) + 1;
+        if let Ok(mut list) = self.subscribers.list.write() {
+            list.push(Subscriber {
+                id,
+                filter,
+                callback: Arc::new(callback),
+            });
+        }
+        id
+    }
+
+    /// Remove a listener, returning whether it was subscribed
+    pub fn unsubscribe(&self, id: u64) -> bool {
+        let Ok(mut list) = self.subscribers.list.write() else {
+            return false;
+        };
+        let before = list.len();
+        list.retain(|subscriber| subscriber.id != id);
+        list.len() < before
+    }
+
+    /// Replace the device tags used by subscriber tag filters (MAC -> tags)
+    ///
+    /// Takes effect immediately; safe to call while running.
+    pub fn set_device_tags(&self, tags: HashMap<String, Vec<String>>) {
+        if let Ok(mut device_tags) = self.subscribers.device_tags.write() {
+            *device_tags = tags;
         }
     }
 
+    /// Set the interval between scans, clamped to the allowed range
+    ///
+    /// Takes effect during the current wait, without restarting the loop;
+    /// an office-hours profile in force still overrides it. Returns the
+    /// interval applied.
+    pub async fn set_interval(&self, seconds: u64) -> u64 {
+        let seconds = seconds.clamp(MIN_MONITOR_INTERVAL, MAX_MONITOR_INTERVAL);
+        *self.interval_seconds.lock().await = seconds;
+        seconds
+    }
+
+    /// Run the full scan only every `cycles` cycles, pinging known devices
+    /// in between (1 = always full), clamped to the allowed range
+    ///
+    /// Light cycles cut background load on laptops: they send one ping per
+    /// device known to answer, while new devices wait for the next full
+    /// scan. An office-hours profile in force uses its own setting. Returns
+    /// the value applied.
+    pub fn set_full_scan_every(&self, cycles: u32) -> u32 {
+        let cycles = cycles.clamp(1, MAX_FULL_SCAN_EVERY);
+        self.full_scan_every.store(cycles, Ordering::SeqCst);
+        cycles
+    }
+
+    /// Stop scanning until [`BackgroundMonitor::resume`], keeping the loop
+    /// and the devices seen so far
+    ///
+    /// A scan in progress finishes first.
+    pub fn pause(&self) {
+        self.is_paused.store(true, Ordering::SeqCst);
+    }
+
+    /// Continue scanning after [`BackgroundMonitor::pause`]
+    ///
+    /// The wait for the next scan picks up where it was paused.
+    pub fn resume(&self) {
+        self.is_paused.store(false, Ordering::SeqCst);
+    }
+
+    /// Check if monitoring is paused
+    pub fn is_paused(&self) -> bool {
+        self.is_paused.load(Ordering::SeqCst)
+    }
+
+    /// Drop all in-memory state about a device (used when its data is purged)
+    pub async fn forget_device(&self, mac: &str) {
+        self.changes.lock().await.forget(mac);
+        self.device_settings.lock().await.remove(mac);
+    }
+
     /// Start background monitoring with event callback
     pub async fn start<F>(&self, callback: F, interval: Option<u64>) -> Result<(), String>
     where
@@ -70,10 +344,56 @@ impl BackgroundMonitor {
 
         *self.interval_seconds.lock().await = interval_secs;
         self.is_running.store(true, Ordering::SeqCst);
+        self.is_paused.store(false, Ordering::SeqCst);
         self.scan_count.store(0, Ordering::SeqCst);
+        self.failures.store(0, Ordering::SeqCst);
+        self.stats.start(chrono::Utc::now());
+        *self.active_profile.lock().await = None;
 
-        // Wrap callback in Arc
-        let callback = Arc::new(callback);
+        // Events reach the callback through a bounded queue, being stored and
+        // passed to subscribers on the way; scanning only ever queues them
+        let event_store = self.event_store.lock().await.clone();
+        let health_store = event_store.clone();
+        // Sessions left open by a crash end when their device was last seen
+        if let Some(db) = &health_store {
+            if let Err(e) = db.write(close_presence_sessions) {
+                tracing::warn!("[MONITOR] Failed to close presence sessions: {:#}", e);
+            }
+        }
+        let subscribers = Arc::clone(&self.subscribers);
+        let stats = Arc::clone(&self.stats);
+        let queue = Arc::new(EventQueue::spawn(
+            MONITOR_EVENT_QUEUE_SIZE,
+            move |event: NetworkEvent| {
+                if let Some(db) = &event_store {
+                    if let Err(e) = db.write(|conn| record_event(conn, &event)) {
+                        tracing::warn!("[MONITOR] Failed to store event: {:#}", e);
+                    }
+                    if let Some(input) = RuleInput::from_event(&event) {
+                        if let Err(e) = db.write(|conn| apply_alert_rules(conn, &[input])) {
+                            tracing::warn!("[MONITOR] Failed to apply alert rules: {:#}", e);
+                        }
+                    }
+                    if let Some(host) = discovered_host(&event) {
+                        if let Err(e) = db.write(|conn| record_untrusted_device(conn, &host)) {
+                            tracing::warn!("[MONITOR] Failed to check device trust: {:#}", e);
+                        }
+                    }
+                    if matches!(event, NetworkEvent::MonitoringStopped) {
+                        if let Err(e) = db.write(close_presence_sessions) {
+                            tracing::warn!("[MONITOR] Failed to close presence sessions: {:#}", e);
+                        }
+                    }
+                }
+                stats.event(&event, chrono::Utc::now());
+                subscribers.dispatch(&event);
+                callback(event);
+            },
+        ));
+        let events = Arc::clone(&queue);
+        let callback: EventCallback = Arc::new(move |event: NetworkEvent| {
+            events.push(event);
+        });
 
         // Emit monitoring started event
         callback(NetworkEvent::MonitoringStarted {
@@ -82,77 +402,78 @@ impl BackgroundMonitor {
 
         // Clone Arc references for the spawned task
         let is_running = Arc::clone(&self.is_running);
+        let is_paused = Arc::clone(&self.is_paused);
         let scan_count = Arc::clone(&self.scan_count);
         let last_scan_time = Arc::clone(&self.last_scan_time);
-        let previous_devices = Arc::clone(&self.previous_devices);
-        let offline_devices = Arc::clone(&self.offline_devices);
+        let last_cycle = Arc::clone(&self.last_cycle);
+        let changes = Arc::clone(&self.changes);
+        let device_settings = Arc::clone(&self.device_settings);
         let interval_seconds = Arc::clone(&self.interval_seconds);
+        let full_scan_every = Arc::clone(&self.full_scan_every);
+        let interface_name = Arc::clone(&self.interface_name);
+        let monitor_targets = Arc::clone(&self.targets);
+        let internet_check = Arc::clone(&self.internet_check);
+        let dns_resolver = Arc::clone(&self.dns_resolver);
+        let schedule = Arc::clone(&self.schedule);
+        let active_profile = Arc::clone(&self.active_profile);
         let cb = Arc::clone(&callback);
 
-        // Spawn background scanning task
-        tokio::spawn(async move {
-            tracing::info!(
-                "[MONITOR] Background monitoring started (interval: {}s)",
-                interval_secs
-            );
-
-            while is_running.load(Ordering::SeqCst) {
-                let current_scan = scan_count.fetch_add(1, Ordering::SeqCst) + 1;
-                let interval = *interval_seconds.lock().await;
-
-                // Emit scan started
-                (*cb)(NetworkEvent::ScanStarted {
-                    scan_number: current_scan,
-                });
-
-                tracing::debug!("[MONITOR] Starting scan #{}", current_scan);
-                let start = Instant::now();
-
-                // Run the actual scan
-                match run_background_scan(&*cb).await {
-                    Ok(devices) => {
-                        let duration = start.elapsed().as_millis() as u64;
-
-                        // Update last scan time
-                        *last_scan_time.lock().await = Some(chrono::Utc::now().to_rfc3339());
-
-                        // Detect changes
-                        let mut prev = previous_devices.lock().await;
-                        let mut offline = offline_devices.lock().await;
-                        detect_and_emit_changes(&*cb, &mut prev, &mut offline, &devices);
-
-                        // Emit scan completed
-                        (*cb)(NetworkEvent::ScanCompleted {
-                            scan_number: current_scan,
-                            hosts_found: devices.len(),
-                            duration_ms: duration,
-                        });
-
-                        tracing::debug!(
-                            "[MONITOR] Scan #{} complete: {} hosts in {}ms",
-                            current_scan,
-                            devices.len(),
-                            duration
-                        );
-                    }
-                    Err(e) => {
-                        tracing::warn!("[MONITOR] Scan #{} failed: {}", current_scan, e);
-                        (*cb)(NetworkEvent::MonitoringError { message: e });
-                    }
+        // Passive listeners cover the first network only
+        if self.passive_discovery.load(Ordering::SeqCst) {
+            let interface = sweep_targets(&self.interface_name, &self.targets)
+                .await
+                .swap_remove(0);
+            match start_passive_sightings() {
+                Ok((sightings, listeners)) => {
+                    tokio::spawn(fold_passive_sightings(
+                        sightings,
+                        listeners,
+                        Arc::clone(&is_running),
+                        Arc::clone(&is_paused),
+                        Arc::clone(&changes),
+                        interface,
+                        Arc::clone(&callback) as EventCallback,
+                    ));
                 }
-
-                // Wait for next interval (check every second to allow quick stop)
-                for _ in 0..interval {
-                    if !is_running.load(Ordering::SeqCst) {
-                        break;
-                    }
-                    tokio::time::sleep(Duration::from_secs(1)).await;
+                Err(e) => {
+                    let message = format!("Passive discovery unavailable: {}", e);
+                    tracing::warn!("[MONITOR] {}", message);
+                    callback(NetworkEvent::MonitoringError { message });
                 }
             }
+        }
 
-            tracing::info!("[MONITOR] Background monitoring stopped");
-            (*cb)(NetworkEvent::MonitoringStopped);
-        });
+        tracing::info!(
+            "[MONITOR] Background monitoring started (interval: {}s)",
+            interval_secs
+        );
+        let scan_loop = ScanLoop {
+            is_running,
+            is_paused,
+            scan_count,
+            last_scan_time,
+            last_cycle,
+            changes,
+            device_settings,
+            interval_seconds,
+            full_scan_every,
+            interface_name,
+            monitor_targets,
+            internet_check,
+            dns_resolver,
+            schedule,
+            active_profile,
+            cb,
+            queue,
+            health_store,
+            heartbeat: Arc::new(AtomicI64::new(0)),
+            failures: Arc::clone(&self.failures),
+            stats: Arc::clone(&self.stats),
+        };
+
+        // Spawn background scanning task, restarted by a watchdog if it
+        // dies or hangs
+        tokio::spawn(supervise(scan_loop));
 
         Ok(())
     }
@@ -164,35 +485,856 @@ impl BackgroundMonitor {
 
     /// Get current monitoring status
     pub async fn status(&self) -> MonitoringStatus {
-        let prev = self.previous_devices.lock().await;
-        let online_count = prev.len();
+        let mut devices = self.changes.lock().await.online_devices();
+        devices.sort_by_key(|device| device.ip.parse::<Ipv4Addr>().ok());
+        let online_count = devices.len();
+        let last_cycle = self.last_cycle.lock().await.clone();
 
         MonitoringStatus {
             is_running: self.is_running.load(Ordering::SeqCst),
-            interval_seconds: *self.interval_seconds.lock().await,
+            is_paused: self.is_paused.load(Ordering::SeqCst),
+            interval_seconds: current_interval(&self.interval_seconds, &self.schedule)
+                .await
+                .0,
+            active_profile: self.active_profile.lock().await.clone(),
             scan_count: self.scan_count.load(Ordering::SeqCst),
             last_scan_time: self.last_scan_time.lock().await.clone(),
             devices_online: online_count,
             devices_total: online_count,
+            last_cycle_packets_sent: last_cycle.as_ref().map(|c| c.packets_sent),
+            last_cycle_duration_ms: last_cycle.as_ref().map(|c| c.duration_ms),
+            interface: last_cycle.map(|c| c.interface),
+            devices,
         }
     }
 
+    /// Aggregated metrics of the current (or last) session: uptime, scan
+    /// durations and failures, watchdog restarts and event rates
+    pub fn stats(&self) -> MonitoringStats {
+        self.stats.snapshot(
+            chrono::Utc::now(),
+            self.is_running.load(Ordering::SeqCst),
+            self.failures.load(Ordering::SeqCst),
+        )
+    }
+
     /// Check if monitoring is running
     pub fn is_running(&self) -> bool {
         self.is_running.load(Ordering::SeqCst)
     }
 }
 
+/// What the scan loop works with; cloned for each (re)start
+#[derive(Clone)]
+struct ScanLoop {
+    is_running: Arc<AtomicBool>,
+    is_paused: Arc<AtomicBool>,
+    scan_count: Arc<AtomicU32>,
+    last_scan_time: Arc<Mutex<Option<String>>>,
+    last_cycle: Arc<Mutex<Option<CycleStats>>>,
+    changes: Arc<Mutex<ChangeDetector>>,
+    device_settings: Arc<Mutex<HashMap<String, DeviceSettings>>>,
+    interval_seconds: Arc<Mutex<u64>>,
+    full_scan_every: Arc<AtomicU32>,
+    interface_name: Arc<Mutex<Option<String>>>,
+    monitor_targets: Arc<Mutex<Vec<String>>>,
+    internet_check: Arc<Mutex<Option<InternetCheckSettings>>>,
+    dns_resolver: Arc<Mutex<DnsResolver>>,
+    schedule: Arc<Mutex<MonitorSchedule>>,
+    active_profile: Arc<Mutex<Option<String>>>,
+    cb: EventCallback,
+    queue: Arc<EventQueue>,
+    health_store: Option<Database>,
+    /// Unix time the loop last showed signs of life
+    heartbeat: Arc<AtomicI64>,
+    /// Scan cycles failed in a row
+    failures: Arc<AtomicU32>,
+    stats: Arc<StatsRecorder>,
+}
+
+impl ScanLoop {
+    /// Scan until monitoring stops
+    async fn run(self) {
+        let Self {
+            is_running,
+            is_paused,
+            scan_count,
+            last_scan_time,
+            last_cycle,
+            changes,
+            device_settings,
+            interval_seconds,
+            full_scan_every,
+            interface_name,
+            monitor_targets,
+            internet_check,
+            dns_resolver,
+            schedule,
+            active_profile,
+            cb,
+            queue,
+            health_store,
+            heartbeat,
+            failures,
+            stats,
+        } = self;
+
+        // Light cycles since the last full scan (none before the first)
+        let mut light_cycles: Option<u32> = None;
+        let mut quiet = false;
+        let mut internet = InternetTracker::default();
+        let mut deviations = DeviationTracker::default();
+
+        while is_running.load(Ordering::SeqCst) {
+            beat(&heartbeat);
+
+            // Active scanning waits out quiet hours; the first scan after
+            // them is a full one
+            if wait_out_quiet_hours(
+                &*cb,
+                &schedule,
+                &is_running,
+                &is_paused,
+                &heartbeat,
+                &mut quiet,
+            )
+            .await
+            {
+                light_cycles = None;
+            }
+            if !is_running.load(Ordering::SeqCst) {
+                break;
+            }
+
+            let current_scan = scan_count.fetch_add(1, Ordering::SeqCst) + 1;
+            let sweeps = sweep_targets(&interface_name, &monitor_targets).await;
+            let resolver = dns_resolver.lock().await.clone();
+
+            // Announce switches between scheduled profiles
+            let (interval, profile) = current_interval(&interval_seconds, &schedule).await;
+            let full_every = profile
+                .as_ref()
+                .map_or_else(
+                    || full_scan_every.load(Ordering::SeqCst),
+                    |p| p.full_scan_every,
+                )
+                .clamp(1, MAX_FULL_SCAN_EVERY);
+            let profile_name = profile.map(|p| p.name);
+            let mut active = active_profile.lock().await;
+            if *active != profile_name {
+                if let Some(name) = &profile_name {
+                    tracing::info!(
+                        "[MONITOR] Switched to '{}' profile (interval: {}s)",
+                        name,
+                        interval
+                    );
+                    (*cb)(NetworkEvent::ProfileChanged {
+                        profile: name.clone(),
+                        interval_seconds: interval,
+                    });
+                }
+                *active = profile_name;
+            }
+            drop(active);
+
+            // Between full scans only known devices are pinged; the
+            // interface of the last full scan is reported for those
+            let light_interface = match light_cycles {
+                Some(n) if n + 1 < full_every => last_cycle
+                    .lock()
+                    .await
+                    .as_ref()
+                    .map(|c| c.interface.clone()),
+                _ => None,
+            };
+            light_cycles = match light_interface {
+                Some(_) => light_cycles.map(|n| n + 1),
+                None => Some(0),
+            };
+
+            // Let a backed-up consumer catch up before queueing more
+            queue.wait_for_room().await;
+
+            // Emit scan started
+            let light = light_interface.is_some();
+            (*cb)(NetworkEvent::ScanStarted {
+                scan_number: current_scan,
+                light,
+            });
+
+            tracing::debug!("[MONITOR] Starting scan #{}", current_scan);
+            let start = Instant::now();
+
+            // Run the actual scan
+            let result = match light_interface {
+                Some(interface) => run_light_scan(&*cb, &changes, interface).await,
+                None => run_sweeps(&cb, &sweeps, &resolver).await,
+            };
+            match result {
+                Ok(ScanCycle {
+                    devices,
+                    interface,
+                    packets_sent,
+                }) => {
+                    let duration = start.elapsed().as_millis() as u64;
+                    failures.store(0, Ordering::SeqCst);
+                    stats.scan_completed(duration);
+
+                    // Update last scan time
+                    *last_scan_time.lock().await = Some(chrono::Utc::now().to_rfc3339());
+                    *last_cycle.lock().await = Some(CycleStats {
+                        interface,
+                        packets_sent,
+                        duration_ms: duration,
+                    });
+
+                    // Detect changes
+                    let settings = device_settings.lock().await;
+                    changes
+                        .lock()
+                        .await
+                        .observe(&*cb, &settings, &devices, Instant::now());
+
+                    if let Some(db) = &health_store {
+                        let online = changes.lock().await.online_devices();
+                        let now = chrono::Utc::now();
+                        if let Err(e) = db.write(|conn| update_presence(conn, &online, now)) {
+                            tracing::warn!("[MONITOR] Failed to record presence: {:#}", e);
+                        }
+                        // Light scans do not see new devices or ports
+                        if !light {
+                            check_baseline(&*cb, db, &online, &mut deviations);
+                        }
+                    }
+
+                    // Emit scan completed
+                    (*cb)(NetworkEvent::ScanCompleted {
+                        scan_number: current_scan,
+                        hosts_found: devices.len(),
+                        duration_ms: duration,
+                    });
+
+                    tracing::debug!(
+                        "[MONITOR] Scan #{} complete: {} hosts in {}ms",
+                        current_scan,
+                        devices.len(),
+                        duration
+                    );
+                }
+                Err(e) => {
+                    tracing::warn!("[MONITOR] Scan #{} failed: {}", current_scan, e);
+                    failures.fetch_add(1, Ordering::SeqCst);
+                    stats.scan_failed();
+                    (*cb)(NetworkEvent::MonitoringError { message: e });
+                }
+            }
+
+            let check = internet_check.lock().await.clone();
+            if let Some(settings) = check {
+                let gateway = changes.lock().await.gateway_ip();
+                run_internet_check(
+                    &*cb,
+                    &settings,
+                    gateway,
+                    health_store.as_ref(),
+                    &mut internet,
+                )
+                .await;
+            }
+
+            // Wait for next interval (check every second to allow quick stop
+            // and to follow interval or profile changes); paused time
+            // does not count towards it
+            let mut waited = 0;
+            let mut paused = false;
+            while is_running.load(Ordering::SeqCst)
+                && (paused || waited < current_interval(&interval_seconds, &schedule).await.0)
+            {
+                if is_paused.load(Ordering::SeqCst) != paused {
+                    paused = !paused;
+                    tracing::info!(
+                        "[MONITOR] Monitoring {}",
+                        if paused { "paused" } else { "resumed" }
+                    );
+                    (*cb)(if paused {
+                        NetworkEvent::MonitoringPaused
+                    } else {
+                        NetworkEvent::MonitoringResumed
+                    });
+                    continue;
+                }
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                beat(&heartbeat);
+                if paused {
+                    continue;
+                }
+                waited += 1;
+
+                // Watched devices get a quick ARP check between full scans
+                if waited % WATCH_CHECK_INTERVAL_SECS == 0 {
+                    if schedule.lock().await.is_quiet_hours(&chrono::Local::now()) {
+                        continue;
+                    }
+                    let settings = device_settings.lock().await.clone();
+                    let targets = changes.lock().await.watched_targets(&settings);
+                    if targets.is_empty() {
+                        continue;
+                    }
+                    let sweeps = sweep_targets(&interface_name, &monitor_targets).await;
+                    match check_watched_devices(&sweeps, &targets).await {
+                        Ok(reachable) => changes.lock().await.observe_watched(
+                            &*cb,
+                            &settings,
+                            &reachable,
+                            Instant::now(),
+                        ),
+                        Err(e) => {
+                            tracing::debug!("[MONITOR] Watched device check failed: {}", e)
+                        }
+                    }
+                }
+            }
+        }
+
+        tracing::info!("[MONITOR] Background monitoring stopped");
+        (*cb)(NetworkEvent::MonitoringStopped);
+    }
+}
+
+fn beat(heartbeat: &AtomicI64) {
+    heartbeat.store(chrono::Utc::now().timestamp(), Ordering::SeqCst);
+}
+
+/// Run the scan loop, restarting it when it dies, hangs or keeps failing
+///
+/// A panic, no sign of life for [`MONITOR_STALL_TIMEOUT_SECS`] or
+/// [`MONITOR_RECOVERY_FAILURES`] failed scans in a row are reported as a
+/// monitoring error; the interface is looked up again and scanning restarts
+/// after [`MONITOR_RESTART_DELAY`].
+async fn supervise(scan_loop: ScanLoop) {
+    let is_running = Arc::clone(&scan_loop.is_running);
+    let cb = Arc::clone(&scan_loop.cb);
+    loop {
+        beat(&scan_loop.heartbeat);
+        scan_loop.failures.store(0, Ordering::SeqCst);
+        let mut task = tokio::spawn(scan_loop.clone().run());
+
+        let problem = loop {
+            tokio::select! {
+                result = &mut task => break match result {
+                    Ok(()) => None,
+                    Err(e) if e.is_panic() => {
+                        let panic = e.into_panic();
+                        let message = panic
+                            .downcast_ref::<&str>()
+                            .map(|s| s.to_string())
+                            .or_else(|| panic.downcast_ref::<String>().cloned())
+                            .unwrap_or_default();
+                        Some(format!("Monitor loop crashed: {}", message))
+                    }
+                    Err(e) => Some(format!("Monitor loop ended: {}", e)),
+                },
+                _ = tokio::time::sleep(MONITOR_WATCHDOG_INTERVAL) => {}
+            }
+
+            let silent =
+                chrono::Utc::now().timestamp() - scan_loop.heartbeat.load(Ordering::SeqCst);
+            let failures = scan_loop.failures.load(Ordering::SeqCst);
+            if silent >= MONITOR_STALL_TIMEOUT_SECS as i64 {
+                task.abort();
+                break Some(format!("Monitor loop stalled for {}s", silent));
+            }
+            if failures >= MONITOR_RECOVERY_FAILURES && is_running.load(Ordering::SeqCst) {
+                task.abort();
+                break Some(format!("{} scans in a row failed", failures));
+            }
+        };
+
+        let Some(problem) = problem else {
+            break;
+        };
+        if !is_running.load(Ordering::SeqCst) {
+            (*cb)(NetworkEvent::MonitoringStopped);
+            break;
+        }
+
+        let message = match redetect_interface(&scan_loop.interface_name).await {
+            Some(note) => format!("{}; {}; restarting", problem, note),
+            None => format!("{}; restarting", problem),
+        };
+        tracing::warn!("[MONITOR] {}", message);
+        (*cb)(NetworkEvent::MonitoringError { message });
+        scan_loop.stats.restarted();
+
+        tokio::time::sleep(MONITOR_RESTART_DELAY).await;
+        if !is_running.load(Ordering::SeqCst) {
+            (*cb)(NetworkEvent::MonitoringStopped);
+            break;
+        }
+    }
+}
+
+/// Fall back to automatic interface selection if the chosen interface is gone
+///
+/// Returns what changed, for the restart message.
+async fn redetect_interface(interface_name: &Mutex<Option<String>>) -> Option<String> {
+    let mut name = interface_name.lock().await;
+    let configured = name.clone()?;
+    if select_interface(Some(&configured)).is_ok() {
+        return None;
+    }
+    match select_interface(None) {
+        Ok(interface) => {
+            *name = None;
+            Some(format!(
+                "interface {} is gone, switched to {}",
+                configured, interface.name
+            ))
+        }
+        Err(_) => Some(format!(
+            "interface {} is gone and no other is available",
+            configured
+        )),
+    }
+}
+
 impl Default for BackgroundMonitor {
     fn default() -> Self {
         Self::new()
     }
 }
 
-/// Run a background scan and return device snapshots
-async fn run_background_scan<F>(callback: &F) -> Result<Vec<DeviceSnapshot>, String>
+/// Interval in effect now, and the scheduled profile providing it
+async fn current_interval(
+    interval_seconds: &Mutex<u64>,
+    schedule: &Mutex<MonitorSchedule>,
+) -> (u64, Option<MonitorProfile>) {
+    match schedule.lock().await.profile_at(&chrono::Local::now()) {
+        Some(profile) => (
+            profile
+                .interval_seconds
+                .clamp(MIN_MONITOR_INTERVAL, MAX_MONITOR_INTERVAL),
+            Some(profile.clone()),
+        ),
+        None => (*interval_seconds.lock().await, None),
+    }
+}
+
+/// Sit out quiet hours, announcing when they start and end
+///
+/// Returns once active scanning may go ahead or monitoring stops; `true`
+/// if it had to wait. A monitor paused during quiet hours stays idle until
+/// it is resumed.
+async fn wait_out_quiet_hours<F>(
+    callback: &F,
+    schedule: &Mutex<MonitorSchedule>,
+    is_running: &AtomicBool,
+    is_paused: &AtomicBool,
+    heartbeat: &AtomicI64,
+    quiet: &mut bool,
+) -> bool
+where
+    F: Fn(NetworkEvent) + ?Sized,
+{
+    let mut waited = false;
+    while is_running.load(Ordering::SeqCst) {
+        let now = schedule.lock().await.is_quiet_hours(&chrono::Local::now());
+        if now != *quiet {
+            *quiet = now;
+            tracing::info!(
+                "[MONITOR] Quiet hours {}",
+                if now { "started" } else { "ended" }
+            );
+            callback(NetworkEvent::QuietHoursChanged { active: now });
+        }
+        if !(now || waited && is_paused.load(Ordering::SeqCst)) {
+            break;
+        }
+        waited = true;
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        beat(heartbeat);
+    }
+    waited
+}
+
+/// The host a new-device event describes, for checking it against the allowlist
+fn discovered_host(event: &NetworkEvent) -> Option<HostInfo> {
+    let NetworkEvent::NewDeviceDiscovered {
+        ip,
+        mac,
+        hostname,
+        device_type,
+        vendor,
+        ..
+    } = event
+    else {
+        return None;
+    };
+    let mut host = HostInfo::new(
+        ip.clone(),
+        mac.clone(),
+        device_type.clone(),
+        "MONITOR".to_string(),
+    );
+    host.hostname = hostname.clone();
+    host.vendor = vendor.clone();
+    Some(host)
+}
+
+/// Report departures from the network baseline, each until it is resolved
+fn check_baseline<F>(
+    callback: &F,
+    db: &Database,
+    online: &[DeviceSnapshot],
+    tracker: &mut DeviationTracker,
+) where
+    F: Fn(NetworkEvent) + ?Sized,
+{
+    let baseline = match db.read(get_network_baseline) {
+        Ok(Some(baseline)) => baseline,
+        Ok(None) => {
+            *tracker = DeviationTracker::default();
+            return;
+        }
+        Err(e) => {
+            tracing::warn!("[MONITOR] Failed to load network baseline: {:#}", e);
+            return;
+        }
+    };
+
+    let hosts: Vec<HostInfo> = online
+        .iter()
+        .map(|device| {
+            let mut host = HostInfo::new(
+                device.ip.clone(),
+                device.mac.clone(),
+                device.device_type.clone(),
+                "MONITOR".to_string(),
+            );
+            host.hostname = device.hostname.clone();
+            host.vendor = device.vendor.clone();
+            host.open_ports = device.open_ports.clone();
+            host
+        })
+        .collect();
+    for deviation in tracker.update(find_baseline_deviations(&baseline, &hosts)) {
+        callback(deviation.into());
+    }
+}
+
+/// Check the internet connection, store the result and report a change for the worse
+async fn run_internet_check<F>(
+    callback: &F,
+    settings: &InternetCheckSettings,
+    gateway: Option<Ipv4Addr>,
+    store: Option<&Database>,
+    tracker: &mut InternetTracker,
+) where
+    F: Fn(NetworkEvent) + ?Sized,
+{
+    let health = match check_internet(settings, gateway).await {
+        Ok(health) => health,
+        Err(e) => {
+            tracing::debug!("[MONITOR] Internet check skipped: {:#}", e);
+            return;
+        }
+    };
+    if let Some(db) = store {
+        if let Err(e) = db.write(|conn| record_internet_health(conn, &health)) {
+            tracing::warn!("[MONITOR] Failed to store internet health: {:#}", e);
+        }
+    }
+    if let Some(event) = tracker.observe(&health) {
+        tracing::info!(
+            "[MONITOR] Internet {}: {}",
+            health.status.as_str(),
+            health.reason.as_deref().unwrap_or_default()
+        );
+        callback(event);
+    }
+}
+
+/// Fold passive sightings into the monitor's device state until it stops
+///
+/// Only addresses in the monitored subnet count, and sightings are dropped
+/// while monitoring is paused.
+async fn fold_passive_sightings(
+    mut sightings: tokio::sync::mpsc::Receiver<PassiveSighting>,
+    listeners: tokio::task::JoinHandle<()>,
+    is_running: Arc<AtomicBool>,
+    is_paused: Arc<AtomicBool>,
+    changes: Arc<Mutex<ChangeDetector>>,
+    target: Option<String>,
+    callback: EventCallback,
+) {
+    let (range, local_ip) = match resolve_target(target.as_deref()) {
+        Ok(sweep) => (sweep.range, sweep.interface.ip),
+        Err(e) => {
+            tracing::warn!("[MONITOR] Passive discovery stopped: {}", e);
+            listeners.abort();
+            return;
+        }
+    };
+    tracing::info!("[MONITOR] Passive discovery listening on {}", range);
+
+    while is_running.load(Ordering::SeqCst) {
+        // Wake up every second to notice a stop
+        let sighting = match tokio::time::timeout(Duration::from_secs(1), sightings.recv()).await {
+            Ok(Some(sighting)) => sighting,
+            Ok(None) => break,
+            Err(_) => continue,
+        };
+        if is_paused.load(Ordering::SeqCst) {
+            continue;
+        }
+        let Ok(ip) = sighting.ip.parse::<std::net::Ipv4Addr>() else {
+            continue;
+        };
+        if ip == local_ip || !range.contains(ip) {
+            continue;
+        }
+
+        let mut changes = changes.lock().await;
+        let Some(mac) = sighting.mac.or_else(|| changes.mac_at(&sighting.ip)) else {
+            tracing::debug!("[MONITOR] Skipping passive sighting of unknown {}", ip);
+            continue;
+        };
+        let vendor_info = lookup_vendor_info(&mac);
+        let device_type = infer_device_type_with_services(
+            vendor_info.vendor.as_deref(),
+            sighting.hostname.as_deref(),
+            &[],
+            &sighting.services,
+            false,
+        );
+        changes.observe_sighting(
+            &*callback,
+            &DeviceSnapshot {
+                mac,
+                ip: sighting.ip,
+                hostname: sighting.hostname,
+                device_type: device_type.as_str().to_string(),
+                risk_score: calculate_risk_score(device_type, &[], vendor_info.is_randomized),
+                vendor: vendor_info.vendor,
+                is_online: true,
+                latency_ms: None,
+                open_ports: Vec::new(),
+                network: Some(range.to_string()),
+            },
+        );
+    }
+
+    listeners.abort();
+    tracing::info!("[MONITOR] Passive discovery stopped");
+}
+
+/// ARP the last known address of each watched device (MAC, IP)
+///
+/// Returns each MAC and whether that address still answers with it.
+async fn check_watched_devices(
+    sweeps: &[Option<String>],
+    targets: &[(String, String)],
+) -> Result<Vec<(String, bool)>, String> {
+    let mut replies = HashMap::new();
+    for sweep in sweeps {
+        let SweepTarget {
+            interface,
+            subnet,
+            range,
+            ..
+        } = resolve_target(sweep.as_deref())?;
+        let ips: Vec<Ipv4Addr> = targets
+            .iter()
+            .filter_map(|(_, ip)| ip.parse().ok())
+            .filter(|ip| range.contains(*ip))
+            .collect();
+        if ips.is_empty() {
+            continue;
+        }
+        let answered =
+            tokio::task::spawn_blocking(move || active_arp_scan(&interface, &ips, &subnet))
+                .await
+                .map_err(|e| format!("ARP task error: {}", e))?
+                .map_err(|e| format!("ARP scan error: {}", e))?;
+        replies.extend(answered);
+    }
+
+    Ok(targets
+        .iter()
+        .map(|(mac, ip)| {
+            let answered = ip
+                .parse()
+                .ok()
+                .and_then(|ip| replies.get(&ip))
+                .is_some_and(|reply| reply.to_string().eq_ignore_ascii_case(mac));
+            (mac.clone(), answered)
+        })
+        .collect())
+}
+
+/// Ping the devices known to answer ICMP instead of scanning the subnet
+async fn run_light_scan<F>(
+    callback: &F,
+    changes: &Mutex<ChangeDetector>,
+    interface: MonitorInterface,
+) -> Result<ScanCycle, String>
 where
-    F: Fn(NetworkEvent),
+    F: Fn(NetworkEvent) + ?Sized,
+{
+    let targets = changes.lock().await.ping_targets();
+    callback(NetworkEvent::ScanProgress {
+        phase: "ICMP".to_string(),
+        percent: 20,
+        message: format!("Pinging {} known hosts...", targets.len()),
+    });
+
+    let hosts: HashMap<std::net::Ipv4Addr, MacAddr> = targets
+        .iter()
+        .filter_map(|(mac, ip)| Some((ip.parse().ok()?, mac.parse().ok()?)))
+        .collect();
+    let replies: HashMap<String, u64> = icmp_probe_scan(&hosts)
+        .await
+        .map_err(|e| format!("ICMP scan error: {}", e))?
+        .replies
+        .into_iter()
+        .map(|(ip, reply)| (ip.to_string(), reply.duration.as_millis() as u64))
+        .collect();
+
+    callback(NetworkEvent::ScanProgress {
+        phase: "COMPLETE".to_string(),
+        percent: 100,
+        message: "Light scan complete".to_string(),
+    });
+
+    Ok(ScanCycle {
+        devices: changes.lock().await.light_scan(&replies),
+        interface,
+        packets_sent: hosts.len() as u64,
+    })
+}
+
+/// What one sweep covers
+struct SweepTarget {
+    interface: InterfaceInfo,
+    subnet: Ipv4Network,
+    /// Addresses to scan
+    ips: Vec<Ipv4Addr>,
+    /// The requested CIDR range, or the whole subnet; the devices' network
+    range: Ipv4Network,
+}
+
+/// Find what a monitor target covers
+///
+/// A target is an interface name or a CIDR range on one of the local
+/// subnets; `None` picks the interface automatically.
+fn resolve_target(target: Option<&str>) -> Result<SweepTarget, String> {
+    let range = match target {
+        Some(target) if target.contains('/') => Some(
+            target
+                .parse::<Ipv4Network>()
+                .map_err(|e| format!("Invalid range {}: {}", target, e))?,
+        ),
+        _ => None,
+    };
+    let interface = match range {
+        Some(range) => select_interface_for(range),
+        None => select_interface(target),
+    }
+    .map_err(|e| format!("Interface error: {}", e))?;
+    let (subnet, ips) =
+        calculate_subnet_ips(&interface).map_err(|e| format!("Subnet error: {}", e))?;
+
+    let ips = match range {
+        Some(range) => range
+            .iter()
+            .filter(|ip| *ip != subnet.network() && *ip != subnet.broadcast())
+            .take(MAX_SCAN_HOSTS)
+            .collect(),
+        None => ips,
+    };
+    Ok(SweepTarget {
+        interface,
+        subnet,
+        ips,
+        range: range.unwrap_or(subnet),
+    })
+}
+
+/// The configured targets, or the single (possibly automatic) interface
+async fn sweep_targets(
+    interface_name: &Mutex<Option<String>>,
+    targets: &Mutex<Vec<String>>,
+) -> Vec<Option<String>> {
+    let targets = targets.lock().await;
+    if targets.is_empty() {
+        return vec![interface_name.lock().await.clone()];
+    }
+    targets.iter().cloned().map(Some).collect()
+}
+
+/// Sweep every target in parallel and merge the results
+///
+/// The cycle reports the first target's interface. A target that fails is
+/// reported as a monitoring error while the others still count; the cycle
+/// fails only if every sweep does.
+async fn run_sweeps(
+    callback: &EventCallback,
+    sweeps: &[Option<String>],
+    resolver: &DnsResolver,
+) -> Result<ScanCycle, String> {
+    if let [target] = sweeps {
+        let callback = |event: NetworkEvent| (**callback)(event);
+        return run_background_scan(&callback, target.as_deref(), resolver).await;
+    }
+
+    let handles: Vec<_> = sweeps
+        .iter()
+        .cloned()
+        .map(|target| {
+            let callback = Arc::clone(callback);
+            let resolver = resolver.clone();
+            tokio::spawn(async move {
+                let callback = |event: NetworkEvent| (*callback)(event);
+                run_background_scan(&callback, target.as_deref(), &resolver).await
+            })
+        })
+        .collect();
+
+    let mut merged: Option<ScanCycle> = None;
+    let mut errors = Vec::new();
+    for (handle, target) in handles.into_iter().zip(sweeps) {
+        let result = handle
+            .await
+            .map_err(|e| format!("Sweep task error: {}", e))
+            .and_then(|result| result);
+        match (result, merged.as_mut()) {
+            (Ok(cycle), Some(merged)) => {
+                merged.devices.extend(cycle.devices);
+                merged.packets_sent += cycle.packets_sent;
+            }
+            (Ok(cycle), None) => merged = Some(cycle),
+            (Err(e), _) => errors.push(format!("{}: {}", target.as_deref().unwrap_or("auto"), e)),
+        }
+    }
+
+    let Some(cycle) = merged else {
+        return Err(errors.join("; "));
+    };
+    for message in errors {
+        tracing::warn!("[MONITOR] Sweep failed: {}", message);
+        (**callback)(NetworkEvent::MonitoringError { message });
+    }
+    Ok(cycle)
+}
+
+/// Run a background scan of one target and return device snapshots
+async fn run_background_scan<F>(
+    callback: &F,
+    target: Option<&str>,
+    resolver: &DnsResolver,
+) -> Result<ScanCycle, String>
+where
+    F: Fn(NetworkEvent) + ?Sized,
 {
     // Emit progress: Finding interface
     callback(NetworkEvent::ScanProgress {
@@ -201,10 +1343,12 @@ where
         message: "Finding network interface...".to_string(),
     });
 
-    let interface = find_valid_interface().map_err(|e| format!("Interface error: {}", e))?;
-
-    let (subnet, ips) =
-        calculate_subnet_ips(&interface).map_err(|e| format!("Subnet error: {}", e))?;
+    let SweepTarget {
+        interface,
+        subnet,
+        ips,
+        range,
+    } = resolve_target(target)?;
 
     // Emit progress: ARP scan
     callback(NetworkEvent::ScanProgress {
@@ -213,17 +1357,38 @@ where
         message: format!("ARP scanning {} hosts...", ips.len()),
     });
 
-    let arp_hosts = {
+    let ArpScan {
+        hosts: arp_hosts,
+        conflicts,
+        requests_sent,
+    } = {
         let interface_clone = interface.clone();
         let ips_clone = ips.clone();
         let subnet_clone = subnet;
 
-        tokio::task::spawn_blocking(move || {
-            active_arp_scan(&interface_clone, &ips_clone, &subnet_clone)
-        })
-        .await
-        .map_err(|e| format!("ARP task error: {}", e))?
-        .map_err(|e| format!("ARP scan error: {}", e))?
+        // Large subnets are scanned in chunks; relay their progress
+        let (progress_tx, mut progress_rx) = tokio::sync::mpsc::unbounded_channel();
+        let scan = tokio::task::spawn_blocking(move || {
+            chunked_arp_scan(&interface_clone, &ips_clone, &subnet_clone, |progress| {
+                let _ = progress_tx.send(progress);
+            })
+        });
+        while let Some(progress) = progress_rx.recv().await {
+            if progress.chunks > 1 {
+                callback(NetworkEvent::ScanProgress {
+                    phase: "ARP".to_string(),
+                    percent: 20 + progress.percent() * 3 / 10,
+                    message: format!(
+                        "ARP scanned chunk {}/{} ({} hosts found)...",
+                        progress.chunk, progress.chunks, progress.hosts_found
+                    ),
+                });
+            }
+        }
+
+        scan.await
+            .map_err(|e| format!("ARP task error: {}", e))?
+            .map_err(|e| format!("ARP scan error: {}", e))?
     };
 
     // Emit progress: TCP scan
@@ -233,7 +1398,19 @@ where
         message: format!("TCP probing {} hosts...", arp_hosts.len()),
     });
 
-    let port_results = tcp_probe_scan(&arp_hosts).await.unwrap_or_default();
+    // Ping alongside the TCP probes, for latency tracking
+    let (icmp_result, port_results) =
+        tokio::join!(icmp_probe_scan(&arp_hosts), tcp_probe_scan(&arp_hosts));
+    let response_times = icmp_result.map(|scan| scan.replies).unwrap_or_default();
+    let port_results = port_results.unwrap_or_default();
+    let packets_sent = (requests_sent + arp_hosts.len() * (TCP_PROBE_PORTS.len() + 1)) as u64;
+
+    // Link details need a sysfs read or a helper process
+    let name = interface.name.clone();
+    let (link_speed_mbps, ssid) =
+        tokio::task::spawn_blocking(move || (link_speed_mbps(&name), wifi_ssid(&name)))
+            .await
+            .unwrap_or_default();
 
     // Emit progress: DNS lookup
     callback(NetworkEvent::ScanProgress {
@@ -248,7 +1425,7 @@ where
         .copied()
         .collect();
 
-    let dns_hostnames = dns_scan(&host_ips).await;
+    let dns_hostnames = dns_scan_with(&host_ips, resolver).await;
 
     // Build device snapshots
     callback(NetworkEvent::ScanProgress {
@@ -257,8 +1434,26 @@ where
         message: "Scan complete".to_string(),
     });
 
+    // Every device claiming a contested address is tracked
+    for (ip, macs) in &conflicts {
+        let mut claimants: Vec<String> = arp_hosts
+            .get(ip)
+            .map(|mac| mac.to_string())
+            .into_iter()
+            .collect();
+        claimants.extend(macs.iter().map(|mac| mac.to_string()));
+        callback(NetworkEvent::IpConflict {
+            ip: ip.to_string(),
+            macs: claimants,
+        });
+    }
+    let claims = conflicts
+        .iter()
+        .flat_map(|(ip, macs)| macs.iter().map(move |mac| (ip, mac)));
+
     let devices: Vec<DeviceSnapshot> = arp_hosts
         .iter()
+        .chain(claims)
         .filter(|(ip, _)| **ip != interface.ip)
         .map(|(ip, mac)| {
             let mac_str = format!("{}", mac);
@@ -278,95 +1473,30 @@ where
                 ip: ip.to_string(),
                 hostname: dns_hostnames.get(ip).cloned(),
                 device_type: device_type.as_str().to_string(),
+                vendor: vendor_info.vendor,
+                risk_score: calculate_risk_score(
+                    device_type,
+                    &open_ports,
+                    vendor_info.is_randomized,
+                ),
                 is_online: true,
+                latency_ms: response_times
+                    .get(ip)
+                    .map(|reply| reply.duration.as_millis() as u64),
+                open_ports,
+                network: Some(range.to_string()),
             }
         })
         .collect();
 
-    Ok(devices)
-}
-
-/// Detect changes between scans and emit events
-fn detect_and_emit_changes<F>(
-    callback: &F,
-    previous_online: &mut HashMap<String, DeviceSnapshot>,
-    offline_devices: &mut HashMap<String, OfflineDeviceSnapshot>,
-    current: &[DeviceSnapshot],
-) where
-    F: Fn(NetworkEvent),
-{
-    let now = Instant::now();
-    offline_devices.retain(|_, snap| now.duration_since(snap.since).as_secs() <= OFFLINE_RETENTION_SECS);
-
-    let current_macs: HashMap<String, &DeviceSnapshot> =
-        current.iter().map(|d| (d.mac.clone(), d)).collect();
-
-    // Check for offline devices (known online previously, now missing).
-    for (mac, prev_device) in previous_online.iter() {
-        if !current_macs.contains_key(mac) {
-            tracing::debug!("[MONITOR] Device offline: {} ({})", prev_device.ip, mac);
-            callback(NetworkEvent::DeviceWentOffline {
-                mac: mac.clone(),
-                last_ip: prev_device.ip.clone(),
-                hostname: prev_device.hostname.clone(),
-            });
-            offline_devices.insert(
-                mac.clone(),
-                OfflineDeviceSnapshot {
-                    device: prev_device.clone(),
-                    since: now,
-                },
-            );
-        }
-    }
-
-    // Build next online device map while emitting change events.
-    let mut next_online: HashMap<String, DeviceSnapshot> = HashMap::with_capacity(current.len());
-
-    for device in current {
-        if let Some(prev_device) = previous_online.get(&device.mac) {
-            if prev_device.ip != device.ip {
-                tracing::debug!(
-                    "[MONITOR] IP changed: {} -> {} ({})",
-                    prev_device.ip, device.ip, device.mac
-                );
-                callback(NetworkEvent::DeviceIpChanged {
-                    mac: device.mac.clone(),
-                    old_ip: prev_device.ip.clone(),
-                    new_ip: device.ip.clone(),
-                });
-            }
-        } else if let Some(was_offline) = offline_devices.remove(&device.mac) {
-            tracing::debug!("[MONITOR] Device back online: {} ({})", device.ip, device.mac);
-            callback(NetworkEvent::DeviceCameOnline {
-                mac: device.mac.clone(),
-                ip: device.ip.clone(),
-                hostname: device.hostname.clone(),
-            });
-
-            if was_offline.device.ip != device.ip {
-                tracing::debug!(
-                    "[MONITOR] IP changed while offline: {} -> {} ({})",
-                    was_offline.device.ip, device.ip, device.mac
-                );
-                callback(NetworkEvent::DeviceIpChanged {
-                    mac: device.mac.clone(),
-                    old_ip: was_offline.device.ip,
-                    new_ip: device.ip.clone(),
-                });
-            }
-        } else {
-            tracing::debug!("[MONITOR] New device: {} ({})", device.ip, device.mac);
-            callback(NetworkEvent::NewDeviceDiscovered {
-                ip: device.ip.clone(),
-                mac: device.mac.clone(),
-                hostname: device.hostname.clone(),
-                device_type: device.device_type.clone(),
-            });
-        }
-
-        next_online.insert(device.mac.clone(), device.clone());
-    }
-
-    *previous_online = next_online;
+    Ok(ScanCycle {
+        devices,
+        interface: MonitorInterface {
+            name: interface.name.clone(),
+            ip: interface.ip.to_string(),
+            link_speed_mbps,
+            ssid,
+        },
+        packets_sent,
+    })
 }
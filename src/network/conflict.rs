@@ -0,0 +1,98 @@
+//! Duplicate IP address (conflict) detection
+//!
+//! Two devices configured with the same address take turns receiving its
+//! traffic, which users experience as a flaky network. Every MAC that answers
+//! for an address is kept as its own host, and the hosts sharing an address
+//! are cross-referenced so the conflict shows on each of them.
+
+use pnet::util::MacAddr;
+use std::collections::HashMap;
+use std::net::Ipv4Addr;
+
+use super::device::{calculate_risk_score, infer_device_type};
+use super::vendor::lookup_vendor_info;
+use crate::models::HostInfo;
+
+/// Add a host for every further MAC that answered for an address, then
+/// annotate all hosts sharing an address
+///
+/// `conflicts` holds the extra claimants reported by the ARP scan; the first
+/// claimant of each address is expected to be in `hosts` already.
+pub fn merge_ip_conflicts(hosts: &mut Vec<HostInfo>, conflicts: &HashMap<Ipv4Addr, Vec<MacAddr>>) {
+    for (ip, macs) in conflicts {
+        for mac in macs {
+            let mac = mac.to_string();
+            let vendor_info = lookup_vendor_info(&mac);
+            let device_type = infer_device_type(vendor_info.vendor.as_deref(), None, &[], false);
+
+            let mut host = HostInfo::new(
+                ip.to_string(),
+                mac,
+                device_type.as_str().to_string(),
+                "ARP".to_string(),
+            );
+            host.vendor = vendor_info.vendor;
+            host.is_randomized = vendor_info.is_randomized;
+            host.risk_score = calculate_risk_score(device_type, &[], vendor_info.is_randomized);
+            hosts.push(host);
+        }
+    }
+
+    annotate_ip_conflicts(hosts);
+}
+
+/// Set `conflicting_macs` on every host whose IP is shared with another MAC
+pub fn annotate_ip_conflicts(hosts: &mut [HostInfo]) {
+    let mut claimants: HashMap<String, Vec<String>> = HashMap::new();
+    for host in hosts.iter() {
+        let macs = claimants.entry(host.ip.clone()).or_default();
+        if !macs.contains(&host.mac) {
+            macs.push(host.mac.clone());
+        }
+    }
+
+    for host in hosts.iter_mut() {
+        if host.ip == "0.0.0.0" {
+            continue;
+        }
+        host.conflicting_macs = claimants
+            .get(&host.ip)
+            .map(|macs| macs.iter().filter(|m| **m != host.mac).cloned().collect())
+            .unwrap_or_default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ip_conflicts_annotate_both_hosts() {
+        let ip = Ipv4Addr::new(192, 168, 1, 50);
+        let mut hosts = vec![
+            HostInfo::new(
+                ip.to_string(),
+                "AA:00:00:00:00:01".to_string(),
+                "PC".to_string(),
+                "ARP+ICMP".to_string(),
+            ),
+            HostInfo::new(
+                "192.168.1.60".to_string(),
+                "AA:00:00:00:00:02".to_string(),
+                "PC".to_string(),
+                "ARP".to_string(),
+            ),
+        ];
+        let conflicts = HashMap::from([(ip, vec![MacAddr(0xbb, 0, 0, 0, 0, 3)])]);
+
+        merge_ip_conflicts(&mut hosts, &conflicts);
+        assert_eq!(hosts.len(), 3);
+        assert_eq!(hosts[0].conflicting_macs, vec![hosts[2].mac.clone()]);
+        assert_eq!(hosts[2].ip, ip.to_string());
+        assert_eq!(
+            hosts[2].conflicting_macs,
+            vec!["AA:00:00:00:00:01".to_string()]
+        );
+        assert!(hosts[1].conflicting_macs.is_empty());
+    }
+}
@@ -0,0 +1,175 @@
+//! MAC randomization correlation
+//!
+//! Phones and laptops with private Wi-Fi addresses show up under a new MAC
+//! every so often. A randomized MAC that has never been seen before is
+//! compared with known devices by hostname, mDNS identity, DHCP fingerprint
+//! and open port profile; a confident match is merged into the existing
+//! (canonical) device record instead of creating a new one.
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{GENERIC_HOSTNAMES, MAC_CORRELATION_MIN_SCORE};
+use crate::database::CorrelationSignal;
+
+const HOSTNAME_WEIGHT: u32 = 50;
+const GENERIC_HOSTNAME_WEIGHT: u32 = 20;
+const MDNS_WEIGHT: u32 = 40;
+const DHCP_WEIGHT: u32 = 30;
+const PORT_PROFILE_WEIGHT: u32 = 10;
+
+/// Identifying traits of a device, independent of its MAC address
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeviceFingerprint {
+    pub hostname: Option<String>,
+    /// mDNS instance names the device advertises
+    pub mdns_names: Vec<String>,
+    /// DHCP option 55 parameter request list, e.g. "1,121,3,6,15,119,252"
+    pub dhcp_fingerprint: Option<String>,
+    pub open_ports: Vec<u16>,
+}
+
+/// How well two fingerprints match
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CorrelationScore {
+    pub score: u32,
+    pub signals: Vec<CorrelationSignal>,
+}
+
+impl CorrelationScore {
+    /// Whether the match is strong enough to merge the devices
+    pub fn is_match(&self) -> bool {
+        self.score >= MAC_CORRELATION_MIN_SCORE
+    }
+}
+
+/// First label of a host name, lowercased (`Annas-iPhone.local` -> `annas-iphone`)
+fn normalize_hostname(hostname: &str) -> Option<String> {
+    let label = hostname.trim().split('.').next()?.to_lowercase();
+    (!label.is_empty()).then_some(label)
+}
+
+fn is_generic_hostname(label: &str) -> bool {
+    GENERIC_HOSTNAMES.contains(&label)
+}
+
+/// Score how likely two fingerprints belong to the same device
+pub fn correlation_score(a: &DeviceFingerprint, b: &DeviceFingerprint) -> CorrelationScore {
+    let mut result = CorrelationScore::default();
+
+    let hostname_a = a.hostname.as_deref().and_then(normalize_hostname);
+    let hostname_b = b.hostname.as_deref().and_then(normalize_hostname);
+    if let (Some(ha), Some(hb)) = (hostname_a, hostname_b) {
+        if ha == hb {
+            result.score += if is_generic_hostname(&ha) {
+                GENERIC_HOSTNAME_WEIGHT
+            } else {
+                HOSTNAME_WEIGHT
+            };
+            result.signals.push(CorrelationSignal::Hostname);
+        }
+    }
+
+    let shares_mdns_name = a.mdns_names.iter().any(|name| {
+        !name.is_empty()
+            && b.mdns_names
+                .iter()
+                .any(|other| other.eq_ignore_ascii_case(name))
+    });
+    if shares_mdns_name {
+        result.score += MDNS_WEIGHT;
+        result.signals.push(CorrelationSignal::MdnsIdentity);
+    }
+
+    if let (Some(da), Some(db)) = (&a.dhcp_fingerprint, &b.dhcp_fingerprint) {
+        if !da.is_empty() && da == db {
+            result.score += DHCP_WEIGHT;
+            result.signals.push(CorrelationSignal::DhcpFingerprint);
+        }
+    }
+
+    let mut ports_a = a.open_ports.clone();
+    let mut ports_b = b.open_ports.clone();
+    ports_a.sort_unstable();
+    ports_b.sort_unstable();
+    if !ports_a.is_empty() && ports_a == ports_b {
+        result.score += PORT_PROFILE_WEIGHT;
+        result.signals.push(CorrelationSignal::PortProfile);
+    }
+
+    result
+}
+
+/// Best match for `fingerprint` among candidate devices
+///
+/// Returns `None` unless exactly one candidate reaches the top score and it
+/// is a match; two equally good candidates are ambiguous and never merged.
+pub fn best_correlation<K: Copy>(
+    fingerprint: &DeviceFingerprint,
+    candidates: impl IntoIterator<Item = (K, DeviceFingerprint)>,
+) -> Option<(K, CorrelationScore)> {
+    let mut best: Option<(K, CorrelationScore)> = None;
+    let mut tied = false;
+
+    for (key, candidate) in candidates {
+        let score = correlation_score(fingerprint, &candidate);
+        match &best {
+            Some((_, top)) if score.score < top.score => {}
+            Some((_, top)) if score.score == top.score => tied = true,
+            _ => {
+                best = Some((key, score));
+                tied = false;
+            }
+        }
+    }
+
+    best.filter(|(_, score)| !tied && score.is_match())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fingerprint(hostname: Option<&str>, mdns: &[&str], ports: &[u16]) -> DeviceFingerprint {
+        DeviceFingerprint {
+            hostname: hostname.map(str::to_string),
+            mdns_names: mdns.iter().map(|name| name.to_string()).collect(),
+            dhcp_fingerprint: None,
+            open_ports: ports.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_correlation_needs_identifying_signals() {
+        let phone = fingerprint(Some("Annas-iPhone.local"), &["Anna's iPhone"], &[62078]);
+        let same = fingerprint(Some("annas-iphone"), &[], &[62078]);
+        let score = correlation_score(&phone, &same);
+        assert_eq!(
+            score.signals,
+            vec![CorrelationSignal::Hostname, CorrelationSignal::PortProfile]
+        );
+        assert!(score.is_match());
+
+        // A generic name or a shared port profile alone is not enough
+        let generic = fingerprint(Some("iPhone"), &[], &[62078]);
+        assert!(
+            !correlation_score(&fingerprint(Some("iphone"), &[], &[62078]), &generic).is_match()
+        );
+        assert!(!correlation_score(&fingerprint(None, &[], &[62078]), &same).is_match());
+
+        // mDNS identity plus DHCP fingerprint
+        let mut a = fingerprint(None, &["Living Room TV"], &[]);
+        let mut b = fingerprint(None, &["living room tv"], &[]);
+        a.dhcp_fingerprint = Some("1,3,6,15,119,252".to_string());
+        b.dhcp_fingerprint = a.dhcp_fingerprint.clone();
+        assert_eq!(correlation_score(&a, &b).score, MDNS_WEIGHT + DHCP_WEIGHT);
+
+        // Two equally good candidates are ambiguous
+        let candidates = vec![(1, same.clone()), (2, same.clone())];
+        assert!(best_correlation(&phone, candidates).is_none());
+        let candidates = vec![(1, same), (2, generic)];
+        assert_eq!(
+            best_correlation(&phone, candidates).map(|(id, _)| id),
+            Some(1)
+        );
+    }
+}
@@ -1,12 +1,16 @@
 //! Device Type Inference Module
 //!
-//! Infers device types from vendor names, hostnames, and open ports.
+//! Infers device types from vendor names, hostnames, open ports and mDNS
+//! services, after any user-defined rules (see `rules`).
 //! Also calculates risk scores based on device characteristics.
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+
+use super::risk::score_with_active_profile;
+use super::rules::match_device_type_rules;
 
 /// Device type enumeration
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum DeviceType {
     Router,
@@ -50,6 +54,32 @@ impl DeviceType {
     }
 }
 
+impl std::str::FromStr for DeviceType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_uppercase().as_str() {
+            "ROUTER" => Ok(DeviceType::Router),
+            "SWITCH" => Ok(DeviceType::Switch),
+            "ACCESS_POINT" => Ok(DeviceType::AccessPoint),
+            "FIREWALL" => Ok(DeviceType::Firewall),
+            "SERVER" => Ok(DeviceType::Server),
+            "NAS" => Ok(DeviceType::Nas),
+            "PC" => Ok(DeviceType::Pc),
+            "LAPTOP" => Ok(DeviceType::Laptop),
+            "MOBILE" => Ok(DeviceType::Mobile),
+            "TABLET" => Ok(DeviceType::Tablet),
+            "SMART_TV" => Ok(DeviceType::SmartTv),
+            "IOT_DEVICE" => Ok(DeviceType::IotDevice),
+            "PRINTER" => Ok(DeviceType::Printer),
+            "CAMERA" => Ok(DeviceType::Camera),
+            "GAME_CONSOLE" => Ok(DeviceType::GameConsole),
+            "UNKNOWN" => Ok(DeviceType::Unknown),
+            _ => Err(format!("Unknown device type: {}", s)),
+        }
+    }
+}
+
 /// Infer device type from vendor name
 pub fn infer_device_type_from_vendor(vendor: &str) -> Option<DeviceType> {
     let vendor_lower = vendor.to_lowercase();
@@ -290,6 +320,66 @@ pub fn infer_device_type_from_ports(ports: &[u16]) -> Option<DeviceType> {
     None
 }
 
+/// Infer device type from advertised mDNS/DNS-SD service types (e.g. `_ipp._tcp`)
+///
+/// Services are checked as a set, so a printer that also advertises `_http`
+/// is still recognised as a printer.
+pub fn infer_device_type_from_services<S: AsRef<str>>(services: &[S]) -> Option<DeviceType> {
+    let has = |names: &[&str]| {
+        services.iter().any(|s| {
+            let service = s.as_ref().to_lowercase();
+            names
+                .iter()
+                .any(|name| service == *name || service.starts_with(&format!("{}.", name)))
+        })
+    };
+
+    if has(&[
+        "_ipp",
+        "_ipps",
+        "_printer",
+        "_pdl-datastream",
+        "_uscan",
+        "_scanner",
+    ]) {
+        return Some(DeviceType::Printer);
+    }
+    if has(&[
+        "_googlecast",
+        "_airplay",
+        "_roku",
+        "_amzn-wplay",
+        "_androidtvremote2",
+    ]) {
+        return Some(DeviceType::SmartTv);
+    }
+    if has(&["_rtsp", "_onvif"]) {
+        return Some(DeviceType::Camera);
+    }
+    if has(&["_adisk", "_afpovertcp", "_nfs"]) {
+        return Some(DeviceType::Nas);
+    }
+    if has(&[
+        "_homekit",
+        "_hap",
+        "_hue",
+        "_matter",
+        "_matterc",
+        "_raop",
+        "_spotify-connect",
+    ]) {
+        return Some(DeviceType::IotDevice);
+    }
+    if has(&["_companion-link", "_apple-mobdev2"]) {
+        return Some(DeviceType::Mobile);
+    }
+    if has(&["_workstation", "_rdp"]) {
+        return Some(DeviceType::Pc);
+    }
+
+    None
+}
+
 /// Infer device type using all available information
 pub fn infer_device_type(
     vendor: Option<&str>,
@@ -297,11 +387,33 @@ pub fn infer_device_type(
     ports: &[u16],
     is_gateway: bool,
 ) -> DeviceType {
+    infer_device_type_with_services::<&str>(vendor, hostname, ports, &[], is_gateway)
+}
+
+/// Infer device type, also using mDNS services the device was seen advertising
+pub fn infer_device_type_with_services<S: AsRef<str>>(
+    vendor: Option<&str>,
+    hostname: Option<&str>,
+    ports: &[u16],
+    services: &[S],
+    is_gateway: bool,
+) -> DeviceType {
+    // User-defined rules correct any of the built-in heuristics below
+    if let Some(rule) = match_device_type_rules(vendor, hostname, ports, services) {
+        return rule.device_type;
+    }
+
     // Gateway is typically a router
     if is_gateway {
         return DeviceType::Router;
     }
 
+    // Advertised services describe what the device does, which beats guessing
+    // from the vendor (an Apple vendor prefix may be a phone or an Apple TV)
+    if let Some(dt) = infer_device_type_from_services(services) {
+        return dt;
+    }
+
     // Try vendor first (most reliable)
     if let Some(v) = vendor {
         if let Some(dt) = infer_device_type_from_vendor(v) {
@@ -326,53 +438,14 @@ pub fn infer_device_type(
 
 /// Calculate risk score for a device (0-100)
 /// Higher score = higher risk
+///
+/// Uses the active `RiskProfile` (the built-in weights unless one was saved).
 pub fn calculate_risk_score(
     device_type: DeviceType,
     open_ports: &[u16],
     is_randomized_mac: bool,
 ) -> u8 {
-    let mut score: u8 = 0;
-
-    // Base score by device type
-    score += match device_type {
-        DeviceType::Server => 20,
-        DeviceType::Router | DeviceType::Firewall => 15,
-        DeviceType::Nas => 15,
-        DeviceType::Camera => 25,    // IoT cameras are often vulnerable
-        DeviceType::IotDevice => 30, // IoT devices are risky
-        DeviceType::Printer => 10,
-        DeviceType::Pc | DeviceType::Laptop => 10,
-        DeviceType::Mobile | DeviceType::Tablet => 5,
-        DeviceType::SmartTv => 15,
-        DeviceType::GameConsole => 5,
-        DeviceType::Switch | DeviceType::AccessPoint => 10,
-        DeviceType::Unknown => 20, // Unknown devices are concerning
-    };
-
-    // Add risk for open ports
-    for &port in open_ports {
-        score += match port {
-            21 => 15,          // FTP - unencrypted
-            23 => 20,          // Telnet - very insecure
-            25 => 5,           // SMTP
-            53 => 5,           // DNS
-            80 => 5,           // HTTP
-            139 | 445 => 15,   // SMB - often targeted
-            443 => 2,          // HTTPS - relatively safe
-            3389 => 15,        // RDP - often targeted
-            5900..=5910 => 15, // VNC
-            8080 | 8443 => 5,  // Alt HTTP/HTTPS
-            _ => 2,
-        };
-    }
-
-    // Randomized MAC slightly increases uncertainty
-    if is_randomized_mac {
-        score += 5;
-    }
-
-    // Cap at 100
-    score.min(100)
+    score_with_active_profile(device_type, open_ports, is_randomized_mac)
 }
 
 /// Helper function to check if string contains any of the patterns
@@ -383,6 +456,7 @@ fn contains_any(s: &str, patterns: &[&str]) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::network::rules::RulesTestGuard;
 
     #[test]
     fn test_vendor_inference() {
@@ -460,8 +534,29 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_infer_device_type_from_services() {
+        let _rules = RulesTestGuard::new();
+        assert_eq!(
+            infer_device_type_from_services(&["_http._tcp", "_ipp._tcp"]),
+            Some(DeviceType::Printer)
+        );
+        assert_eq!(
+            infer_device_type_from_services(&["_hap._tcp"]),
+            Some(DeviceType::IotDevice)
+        );
+        assert_eq!(infer_device_type_from_services(&["_ippx._tcp"]), None);
+
+        // Services take precedence over the vendor guess
+        assert_eq!(
+            infer_device_type_with_services(Some("Apple"), None, &[], &["_airplay._tcp"], false),
+            DeviceType::SmartTv
+        );
+    }
+
     #[test]
     fn test_infer_device_type_gateway_is_router() {
+        let _rules = RulesTestGuard::new();
         let result = infer_device_type(
             None,
             None,
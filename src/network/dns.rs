@@ -1,21 +1,71 @@
 //! DNS Reverse Lookup for hostname resolution
 //!
-//! Resolves IP addresses to hostnames using reverse DNS queries.
+//! Resolves IP addresses to hostnames using reverse DNS queries, through the
+//! system resolver by default. Where that is captive or filtered, lookups can
+//! go to a specific DNS server or a DNS-over-HTTPS endpoint instead.
 
+use anyhow::{bail, Context, Result};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
 use dns_lookup::lookup_addr;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::net::{IpAddr, Ipv4Addr};
+use std::io::Read;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::Mutex;
 use tokio::sync::Semaphore;
 
+use super::dns_message::{
+    encode_query, parse_response, query_id, reverse_name, DNS_PORT, TYPE_PTR,
+};
+use crate::database::queries::{self, SETTING_DNS_RESOLVER};
+
 /// Maximum concurrent DNS lookups
 const MAX_CONCURRENT_DNS: usize = 10;
 
 /// DNS lookup timeout (synchronous, so we use spawn_blocking)
 const DNS_TIMEOUT_MS: u64 = 2000;
 
+/// Largest DNS message accepted from a DoH endpoint
+const DOH_MAX_BYTES: u64 = 65_535;
+
+/// Backend used for reverse lookups
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DnsResolver {
+    /// Operating system resolver
+    #[default]
+    System,
+    /// A specific DNS server, queried over UDP
+    Server { address: IpAddr },
+    /// DNS-over-HTTPS endpoint (RFC 8484), e.g. `https://cloudflare-dns.com/dns-query`
+    Doh { url: String },
+}
+
+impl DnsResolver {
+    fn validate(&self) -> Result<()> {
+        match self {
+            Self::Doh { url } if !url.starts_with("https://") => {
+                bail!("DNS-over-HTTPS endpoint must start with https://")
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Load the reverse lookup backend (system resolver if none is saved)
+pub fn load_dns_resolver(conn: &Connection) -> Result<DnsResolver> {
+    Ok(queries::get_json_setting(conn, SETTING_DNS_RESOLVER)?.unwrap_or_default())
+}
+
+/// Validate and persist the reverse lookup backend
+pub fn save_dns_resolver(conn: &Connection, resolver: &DnsResolver) -> Result<()> {
+    resolver.validate()?;
+    queries::set_json_setting(conn, SETTING_DNS_RESOLVER, resolver)
+}
+
 /// Logs a message to stderr
 macro_rules! log_stderr {
     ($($arg:tt)*) => {
@@ -46,8 +96,74 @@ pub fn reverse_lookup(ip: Ipv4Addr) -> Option<String> {
     }
 }
 
+/// First PTR name in a response
+fn ptr_answer(msg: &[u8], id: u16) -> Result<Option<String>> {
+    Ok(parse_response(msg, id)?.ptr.into_iter().next())
+}
+
+/// Reverse lookup against a specific DNS server
+fn server_reverse_lookup(server: IpAddr, ip: Ipv4Addr) -> Result<Option<String>> {
+    let bind: SocketAddr = match server {
+        IpAddr::V4(_) => (Ipv4Addr::UNSPECIFIED, 0).into(),
+        IpAddr::V6(_) => (Ipv6Addr::UNSPECIFIED, 0).into(),
+    };
+    let socket = UdpSocket::bind(bind).context("Failed to open DNS socket")?;
+    socket.set_read_timeout(Some(Duration::from_millis(DNS_TIMEOUT_MS)))?;
+    socket.connect((server, DNS_PORT))?;
+
+    let id = query_id();
+    socket.send(&encode_query(id, &reverse_name(ip), TYPE_PTR)?)?;
+    let mut buf = [0u8; 1500];
+    let len = socket.recv(&mut buf).context("No answer from DNS server")?;
+    ptr_answer(&buf[..len], id)
+}
+
+/// Reverse lookup through a DNS-over-HTTPS endpoint (GET, wire format)
+fn doh_reverse_lookup(url: &str, ip: Ipv4Addr) -> Result<Option<String>> {
+    // RFC 8484 asks for ID 0 so responses can be cached
+    let query = encode_query(0, &reverse_name(ip), TYPE_PTR)?;
+    let response = ureq::get(url)
+        .query("dns", &URL_SAFE_NO_PAD.encode(query))
+        .set("Accept", "application/dns-message")
+        .timeout(Duration::from_millis(DNS_TIMEOUT_MS))
+        .call()
+        .with_context(|| format!("Failed to query {}", url))?;
+
+    let mut body = Vec::new();
+    response
+        .into_reader()
+        .take(DOH_MAX_BYTES)
+        .read_to_end(&mut body)
+        .with_context(|| format!("Failed to read response from {}", url))?;
+    ptr_answer(&body, 0)
+}
+
+/// Reverse lookup through the given backend
+///
+/// Blocking; errors from the configured backend are logged and treated as
+/// no name.
+pub fn resolve_hostname(resolver: &DnsResolver, ip: Ipv4Addr) -> Option<String> {
+    let result = match resolver {
+        DnsResolver::System => return reverse_lookup(ip),
+        DnsResolver::Server { address } => server_reverse_lookup(*address, ip),
+        DnsResolver::Doh { url } => doh_reverse_lookup(url, ip),
+    };
+    match result {
+        Ok(name) => name.filter(|name| *name != ip.to_string()),
+        Err(e) => {
+            tracing::debug!("Reverse lookup of {} failed: {:#}", ip, e);
+            None
+        }
+    }
+}
+
 /// Perform reverse DNS lookup for multiple IP addresses concurrently
 pub async fn dns_scan(ips: &[Ipv4Addr]) -> HashMap<Ipv4Addr, String> {
+    dns_scan_with(ips, &DnsResolver::System).await
+}
+
+/// Reverse DNS lookup for multiple IP addresses through `resolver`
+pub async fn dns_scan_with(ips: &[Ipv4Addr], resolver: &DnsResolver) -> HashMap<Ipv4Addr, String> {
     if ips.is_empty() {
         return HashMap::new();
     }
@@ -62,6 +178,7 @@ pub async fn dns_scan(ips: &[Ipv4Addr]) -> HashMap<Ipv4Addr, String> {
     for &ip in ips {
         let semaphore = Arc::clone(&semaphore);
         let results = Arc::clone(&results);
+        let resolver = resolver.clone();
 
         let handle = tokio::spawn(async move {
             let _permit = match semaphore.acquire().await {
@@ -75,7 +192,7 @@ pub async fn dns_scan(ips: &[Ipv4Addr]) -> HashMap<Ipv4Addr, String> {
             // Run DNS lookup in blocking thread with timeout
             let lookup_result = tokio::time::timeout(
                 Duration::from_millis(DNS_TIMEOUT_MS),
-                tokio::task::spawn_blocking(move || reverse_lookup(ip)),
+                tokio::task::spawn_blocking(move || resolve_hostname(&resolver, ip)),
             )
             .await;
 
@@ -117,4 +234,26 @@ mod tests {
         println!("Localhost reverse lookup: {:?}", result);
         // Usually returns "localhost" or similar
     }
+
+    #[test]
+    fn test_dns_resolver_settings() {
+        let json = r#"{"kind":"doh","url":"https://cloudflare-dns.com/dns-query"}"#;
+        let resolver: DnsResolver = serde_json::from_str(json).unwrap();
+        assert!(resolver.validate().is_ok());
+        assert_eq!(serde_json::to_string(&resolver).unwrap(), json);
+
+        let server: DnsResolver =
+            serde_json::from_str(r#"{"kind":"server","address":"192.168.1.1"}"#).unwrap();
+        assert_eq!(
+            server,
+            DnsResolver::Server {
+                address: "192.168.1.1".parse().unwrap()
+            }
+        );
+
+        let plain = DnsResolver::Doh {
+            url: "http://dns.example/dns-query".to_string(),
+        };
+        assert!(plain.validate().is_err());
+    }
 }
@@ -0,0 +1,205 @@
+//! Minimal DNS wire format
+//!
+//! Just enough of RFC 1035 to send single-question queries and read A, PTR
+//! and SOA answers, shared by the forward sweep and the reverse lookup
+//! backends that bypass the system resolver.
+
+use anyhow::{bail, Context, Result};
+use std::net::Ipv4Addr;
+
+pub const DNS_PORT: u16 = 53;
+
+pub const TYPE_A: u16 = 1;
+pub const TYPE_SOA: u16 = 6;
+pub const TYPE_PTR: u16 = 12;
+pub const TYPE_AXFR: u16 = 252;
+const CLASS_IN: u16 = 1;
+
+/// Encode a domain name as length-prefixed labels
+pub fn encode_name(name: &str) -> Result<Vec<u8>> {
+    let name = name.trim().trim_end_matches('.');
+    if name.is_empty() || name.len() > 253 {
+        bail!("Name must be 1-253 characters");
+    }
+
+    let mut encoded = Vec::with_capacity(name.len() + 2);
+    for label in name.split('.') {
+        if label.is_empty() || label.len() > 63 {
+            bail!("Labels must be 1-63 characters");
+        }
+        if !label
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+        {
+            bail!("Invalid character in {}", label);
+        }
+        encoded.push(label.len() as u8);
+        encoded.extend_from_slice(label.as_bytes());
+    }
+    encoded.push(0);
+    Ok(encoded)
+}
+
+/// Build a single-question query
+pub fn encode_query(id: u16, name: &str, qtype: u16) -> Result<Vec<u8>> {
+    let mut query = Vec::with_capacity(32);
+    query.extend_from_slice(&id.to_be_bytes());
+    // Standard query, recursion desired; one question
+    query.extend_from_slice(&[0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0]);
+    query.extend_from_slice(&encode_name(name)?);
+    query.extend_from_slice(&qtype.to_be_bytes());
+    query.extend_from_slice(&CLASS_IN.to_be_bytes());
+    Ok(query)
+}
+
+/// `in-addr.arpa` name for a reverse lookup
+pub fn reverse_name(ip: Ipv4Addr) -> String {
+    let [a, b, c, d] = ip.octets();
+    format!("{}.{}.{}.{}.in-addr.arpa", d, c, b, a)
+}
+
+fn read_u16(msg: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_be_bytes([
+        *msg.get(offset)?,
+        *msg.get(offset + 1)?,
+    ]))
+}
+
+/// Read a possibly compressed name, returning it and the offset after it
+fn read_name(msg: &[u8], mut offset: usize) -> Option<(String, usize)> {
+    let mut labels: Vec<String> = Vec::new();
+    let mut end = None;
+    // Bounds the number of compression pointers followed
+    let mut jumps = 0;
+
+    loop {
+        let len = *msg.get(offset)? as usize;
+        match len {
+            0 => {
+                let end = end.unwrap_or(offset + 1);
+                return Some((labels.join(".").to_lowercase(), end));
+            }
+            l if l & 0xC0 == 0xC0 => {
+                jumps += 1;
+                if jumps > 16 {
+                    return None;
+                }
+                end.get_or_insert(offset + 2);
+                offset = (read_u16(msg, offset)? & 0x3FFF) as usize;
+            }
+            l if l <= 63 => {
+                let label = msg.get(offset + 1..offset + 1 + l)?;
+                labels.push(String::from_utf8_lossy(label).into_owned());
+                offset += 1 + l;
+            }
+            _ => return None,
+        }
+    }
+}
+
+/// Answer records of interest in a response
+#[derive(Debug, Default, PartialEq)]
+pub struct Answers {
+    /// A records as (owner name, address)
+    pub a: Vec<(String, Ipv4Addr)>,
+    /// PTR target names
+    pub ptr: Vec<String>,
+    /// Number of SOA records
+    pub soa: usize,
+}
+
+/// Parse the answer section of a response to query `id`
+///
+/// NXDOMAIN yields no answers; other error codes are errors.
+pub fn parse_response(msg: &[u8], id: u16) -> Result<Answers> {
+    if read_u16(msg, 0) != Some(id) {
+        bail!("Response does not match the query");
+    }
+    let flags = read_u16(msg, 2).context("Truncated DNS header")?;
+    match flags & 0x000F {
+        0 => {}
+        3 => return Ok(Answers::default()), // NXDOMAIN
+        5 => bail!("Query refused by the server"),
+        rcode => bail!("DNS error (rcode {})", rcode),
+    }
+
+    let questions = read_u16(msg, 4).context("Truncated DNS header")?;
+    let answers = read_u16(msg, 6).context("Truncated DNS header")?;
+    let mut offset = 12;
+    for _ in 0..questions {
+        offset = read_name(msg, offset).context("Malformed question")?.1 + 4;
+    }
+
+    let mut parsed = Answers::default();
+    for _ in 0..answers {
+        let (name, next) = read_name(msg, offset).context("Malformed answer")?;
+        let rtype = read_u16(msg, next).context("Truncated answer")?;
+        let rdlength = read_u16(msg, next + 8).context("Truncated answer")? as usize;
+        let rdata = msg
+            .get(next + 10..next + 10 + rdlength)
+            .context("Truncated answer")?;
+
+        match (rtype, rdata) {
+            (TYPE_A, &[a, b, c, d]) => parsed.a.push((name, Ipv4Addr::new(a, b, c, d))),
+            (TYPE_PTR, _) => {
+                let (target, _) = read_name(msg, next + 10).context("Malformed PTR record")?;
+                parsed.ptr.push(target);
+            }
+            (TYPE_SOA, _) => parsed.soa += 1,
+            _ => {}
+        }
+        offset = next + 10 + rdlength;
+    }
+
+    Ok(parsed)
+}
+
+/// Query ID that is hard to guess from outside
+pub fn query_id() -> u16 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or_default();
+    (nanos ^ (nanos >> 16)) as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dns_message_roundtrip() {
+        let query = encode_query(0x1234, "nas.home.lan", TYPE_A).unwrap();
+        assert_eq!(&query[..2], &[0x12, 0x34]);
+        assert_eq!(&query[12..26], b"\x03nas\x04home\x03lan\x00");
+        assert!(encode_query(1, "bad name.lan", TYPE_A).is_err());
+
+        // Response: the question plus a compressed A answer and an SOA
+        let mut response = query.clone();
+        response[2..8].copy_from_slice(&[0x81, 0x80, 0, 1, 0, 2]);
+        response.extend_from_slice(&[0xC0, 12, 0, 1, 0, 1, 0, 0, 0, 60, 0, 4, 192, 168, 1, 5]);
+        response.extend_from_slice(&[0xC0, 16, 0, 6, 0, 1, 0, 0, 0, 60, 0, 2, 0xC0, 16]);
+        let answers = parse_response(&response, 0x1234).unwrap();
+        assert_eq!(
+            answers.a,
+            vec![("nas.home.lan".to_string(), Ipv4Addr::new(192, 168, 1, 5))]
+        );
+        assert_eq!(answers.soa, 1);
+        assert!(parse_response(&response, 0x4321).is_err());
+
+        // REFUSED
+        response[3] = 0x85;
+        assert!(parse_response(&response, 0x1234).is_err());
+
+        // PTR answer, owner name compressed against the question
+        let name = reverse_name(Ipv4Addr::new(192, 168, 1, 5));
+        assert_eq!(name, "5.1.168.192.in-addr.arpa");
+        let mut response = encode_query(7, &name, TYPE_PTR).unwrap();
+        response[2..8].copy_from_slice(&[0x81, 0x80, 0, 1, 0, 1]);
+        response.extend_from_slice(&[0xC0, 12, 0, 12, 0, 1, 0, 0, 0, 60, 0, 9]);
+        response.extend_from_slice(b"\x03nas\x03lan\x00");
+        let answers = parse_response(&response, 7).unwrap();
+        assert_eq!(answers.ptr, vec!["nas.lan".to_string()]);
+    }
+}
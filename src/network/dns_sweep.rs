@@ -0,0 +1,341 @@
+//! Forward DNS sweep of the local domain
+//!
+//! Reverse lookups miss hosts whose static DNS entries have no PTR record.
+//! The sweep asks the local DNS server for a zone transfer (AXFR) of the
+//! search domain and, if that is refused, resolves a list of common host
+//! names in it. Answers are matched back onto the addresses found by ARP.
+
+use anyhow::{bail, Context, Result};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{Read, Write};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpStream, UdpSocket};
+
+use super::dns_message::{
+    encode_name, encode_query, parse_response, query_id, DNS_PORT, TYPE_A, TYPE_AXFR,
+};
+use crate::config::{DNS_SWEEP_MAX_RECORDS, DNS_SWEEP_NAMES, DNS_SWEEP_TIMEOUT};
+use crate::database::queries::{self, SETTING_DNS_SWEEP};
+
+/// Where and how the forward sweep runs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DnsSweepSettings {
+    /// Run the sweep as part of every scan
+    #[serde(default)]
+    pub enabled: bool,
+    /// Domain to sweep (system search domain, or the domain of resolved hosts, if unset)
+    #[serde(default)]
+    pub domain: Option<String>,
+    /// DNS server to query (first system nameserver if unset)
+    #[serde(default)]
+    pub server: Option<IpAddr>,
+    /// Try a zone transfer before falling back to common names
+    #[serde(default = "default_zone_transfer")]
+    pub zone_transfer: bool,
+    /// Host names tried in addition to the built-in list
+    #[serde(default)]
+    pub extra_names: Vec<String>,
+}
+
+fn default_zone_transfer() -> bool {
+    true
+}
+
+impl Default for DnsSweepSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            domain: None,
+            server: None,
+            zone_transfer: true,
+            extra_names: Vec::new(),
+        }
+    }
+}
+
+/// A forward (A) record found by the sweep
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ForwardRecord {
+    pub name: String,
+    pub ip: Ipv4Addr,
+}
+
+/// Outcome of a forward sweep
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DnsSweepResult {
+    pub domain: String,
+    pub server: IpAddr,
+    /// Whether the server allowed a zone transfer
+    pub zone_transfer: bool,
+    pub records: Vec<ForwardRecord>,
+}
+
+/// Load forward sweep settings (disabled by default)
+pub fn load_dns_sweep_settings(conn: &Connection) -> Result<DnsSweepSettings> {
+    Ok(queries::get_json_setting(conn, SETTING_DNS_SWEEP)?.unwrap_or_default())
+}
+
+/// Validate and persist forward sweep settings
+pub fn save_dns_sweep_settings(conn: &Connection, settings: &DnsSweepSettings) -> Result<()> {
+    if let Some(domain) = &settings.domain {
+        encode_name(domain).with_context(|| format!("Invalid domain: {}", domain))?;
+    }
+    for name in &settings.extra_names {
+        encode_name(name).with_context(|| format!("Invalid host name: {}", name))?;
+    }
+    queries::set_json_setting(conn, SETTING_DNS_SWEEP, settings)
+}
+
+/// Search domain and first nameserver from `resolv.conf` contents
+pub fn parse_resolv_conf(contents: &str) -> (Option<String>, Option<IpAddr>) {
+    let mut domain = None;
+    let mut nameserver = None;
+
+    for line in contents.lines() {
+        let mut fields = line.split_whitespace();
+        match (fields.next(), fields.next()) {
+            // The last `domain` or `search` line wins, as in the resolver
+            (Some("domain" | "search"), Some(value)) => {
+                domain = Some(value.trim_end_matches('.').to_lowercase());
+            }
+            (Some("nameserver"), Some(value)) if nameserver.is_none() => {
+                nameserver = value.parse().ok();
+            }
+            _ => {}
+        }
+    }
+
+    (domain, nameserver)
+}
+
+fn system_resolver() -> (Option<String>, Option<IpAddr>) {
+    if cfg!(unix) {
+        std::fs::read_to_string("/etc/resolv.conf")
+            .map(|contents| parse_resolv_conf(&contents))
+            .unwrap_or_default()
+    } else {
+        (None, None)
+    }
+}
+
+/// Most common domain among resolved host names (`nas.home.lan` -> `home.lan`)
+pub fn domain_from_hostnames<'a>(hostnames: impl IntoIterator<Item = &'a str>) -> Option<String> {
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for hostname in hostnames {
+        let hostname = hostname.trim_end_matches('.').to_lowercase();
+        if let Some((_, domain)) = hostname.split_once('.') {
+            *counts.entry(domain.to_string()).or_default() += 1;
+        }
+    }
+    counts
+        .into_iter()
+        .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(&a.0)))
+        .map(|(domain, _)| domain)
+}
+
+/// Zone transfer over TCP; fails if the server refuses it
+fn zone_transfer(server: SocketAddr, domain: &str) -> Result<Vec<ForwardRecord>> {
+    let mut stream = TcpStream::connect_timeout(&server, DNS_SWEEP_TIMEOUT)
+        .with_context(|| format!("Failed to connect to {}", server))?;
+    stream.set_read_timeout(Some(DNS_SWEEP_TIMEOUT))?;
+    stream.set_write_timeout(Some(DNS_SWEEP_TIMEOUT))?;
+
+    let id = query_id();
+    let query = encode_query(id, domain, TYPE_AXFR)?;
+    stream.write_all(&(query.len() as u16).to_be_bytes())?;
+    stream.write_all(&query)?;
+
+    // The zone is framed by its SOA record at the start and the end
+    let mut records = Vec::new();
+    let mut soa = 0;
+    while soa < 2 {
+        let mut len = [0u8; 2];
+        stream
+            .read_exact(&mut len)
+            .context("Zone transfer ended early")?;
+        let mut msg = vec![0u8; u16::from_be_bytes(len) as usize];
+        stream.read_exact(&mut msg)?;
+
+        let answers = parse_response(&msg, id)?;
+        if soa == 0 && answers.soa == 0 {
+            bail!("Zone transfer refused");
+        }
+        soa += answers.soa;
+        records.extend(
+            answers
+                .a
+                .into_iter()
+                .map(|(name, ip)| ForwardRecord { name, ip }),
+        );
+        if records.len() > DNS_SWEEP_MAX_RECORDS {
+            bail!("Zone transfer exceeded {} records", DNS_SWEEP_MAX_RECORDS);
+        }
+    }
+
+    Ok(records)
+}
+
+/// Resolve `names` in `domain` over UDP, one query each
+fn resolve_names(server: SocketAddr, domain: &str, names: &[String]) -> Result<Vec<ForwardRecord>> {
+    let bind: SocketAddr = if server.is_ipv4() {
+        (Ipv4Addr::UNSPECIFIED, 0).into()
+    } else {
+        (std::net::Ipv6Addr::UNSPECIFIED, 0).into()
+    };
+    let socket = UdpSocket::bind(bind).context("Failed to open DNS socket")?;
+    socket.set_read_timeout(Some(DNS_SWEEP_TIMEOUT))?;
+    socket
+        .connect(server)
+        .with_context(|| format!("Failed to reach {}", server))?;
+
+    let mut records = Vec::new();
+    let mut buf = [0u8; 1500];
+    for name in names {
+        let fqdn = format!("{}.{}", name, domain);
+        let id = query_id();
+        socket.send(&encode_query(id, &fqdn, TYPE_A)?)?;
+
+        // A timeout or malformed answer only loses this one name
+        let Ok(len) = socket.recv(&mut buf) else {
+            continue;
+        };
+        match parse_response(&buf[..len], id) {
+            // CNAME chains answer with the target's name; keep the one asked for
+            Ok(answers) => records.extend(answers.a.into_iter().map(|(_, ip)| ForwardRecord {
+                name: fqdn.to_lowercase(),
+                ip,
+            })),
+            Err(e) => tracing::debug!("Forward lookup of {} failed: {}", fqdn, e),
+        }
+    }
+
+    Ok(records)
+}
+
+/// Sweep the local domain for forward records
+///
+/// `known_hostnames` (e.g. reverse DNS results) are used to guess the domain
+/// when neither the settings nor the system resolver name one. Blocking; call
+/// from `spawn_blocking` in async code.
+pub fn dns_forward_sweep(
+    settings: &DnsSweepSettings,
+    known_hostnames: &[String],
+) -> Result<DnsSweepResult> {
+    let (system_domain, system_server) = system_resolver();
+
+    let domain = settings
+        .domain
+        .clone()
+        .or(system_domain)
+        .or_else(|| domain_from_hostnames(known_hostnames.iter().map(String::as_str)))
+        .context("No search domain configured or detected")?;
+    let server = settings
+        .server
+        .or(system_server)
+        .context("No DNS server configured or detected")?;
+    let address = SocketAddr::new(server, DNS_PORT);
+
+    let transferred = if settings.zone_transfer {
+        zone_transfer(address, &domain)
+            .map_err(|e| tracing::debug!("Zone transfer of {} failed: {:#}", domain, e))
+            .ok()
+    } else {
+        None
+    };
+    let zone_transfer = transferred.is_some();
+
+    let mut records = match transferred {
+        Some(records) => records,
+        None => {
+            let mut seen = HashSet::new();
+            let names: Vec<String> = DNS_SWEEP_NAMES
+                .iter()
+                .map(|name| name.to_string())
+                .chain(
+                    settings
+                        .extra_names
+                        .iter()
+                        .map(|name| name.trim().to_lowercase()),
+                )
+                .filter(|name| seen.insert(name.clone()))
+                .collect();
+            resolve_names(address, &domain, &names)?
+        }
+    };
+
+    records.sort_by(|a, b| a.ip.cmp(&b.ip).then_with(|| a.name.cmp(&b.name)));
+    records.dedup();
+
+    Ok(DnsSweepResult {
+        domain,
+        server,
+        zone_transfer,
+        records,
+    })
+}
+
+/// Host names for discovered addresses, from forward records
+///
+/// Where several names point at one address the shortest is used.
+pub fn match_forward_records(
+    records: &[ForwardRecord],
+    discovered: &[Ipv4Addr],
+) -> HashMap<Ipv4Addr, String> {
+    let discovered: HashSet<&Ipv4Addr> = discovered.iter().collect();
+    let mut names: HashMap<Ipv4Addr, String> = HashMap::new();
+
+    for record in records.iter().filter(|r| discovered.contains(&r.ip)) {
+        names
+            .entry(record.ip)
+            .and_modify(|name| {
+                if (record.name.len(), &record.name) < (name.len(), name) {
+                    *name = record.name.clone();
+                }
+            })
+            .or_insert_with(|| record.name.clone());
+    }
+
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dns_sweep_domain_and_matching() {
+        let (domain, server) =
+            parse_resolv_conf("# generated\nnameserver 192.168.1.1\nsearch corp.example.\n");
+        assert_eq!(domain.as_deref(), Some("corp.example"));
+        assert_eq!(server, Some("192.168.1.1".parse().unwrap()));
+        assert_eq!(
+            domain_from_hostnames(["nas.home.lan", "tv.home.lan", "host.other"]).as_deref(),
+            Some("home.lan")
+        );
+
+        // Hosts that never answer PTR get the forward name
+        let records = vec![
+            ForwardRecord {
+                name: "fileserver.home.lan".to_string(),
+                ip: Ipv4Addr::new(192, 168, 1, 5),
+            },
+            ForwardRecord {
+                name: "nas.home.lan".to_string(),
+                ip: Ipv4Addr::new(192, 168, 1, 5),
+            },
+            ForwardRecord {
+                name: "old.home.lan".to_string(),
+                ip: Ipv4Addr::new(192, 168, 1, 99),
+            },
+        ];
+        let names = match_forward_records(&records, &[Ipv4Addr::new(192, 168, 1, 5)]);
+        assert_eq!(names.len(), 1);
+        assert_eq!(
+            names
+                .get(&Ipv4Addr::new(192, 168, 1, 5))
+                .map(String::as_str),
+            Some("nas.home.lan")
+        );
+    }
+}
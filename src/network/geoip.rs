@@ -0,0 +1,213 @@
+//! Offline GeoIP enrichment for external addresses
+//!
+//! Looks up country and ASN of off-subnet endpoints (passive DNS / SNI
+//! destinations, ping targets, the WAN address) in local MaxMind-format
+//! databases such as GeoLite2-Country/City and GeoLite2-ASN. No online
+//! service is contacted; without configured databases lookups return nothing.
+
+use anyhow::{Context, Result};
+use maxminddb::{geoip2, Reader};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+use crate::database::queries::{self, SETTING_GEOIP};
+
+/// Databases used for lookups; `None` until `load_geoip` finds one
+static GEOIP: RwLock<Option<Arc<GeoIpDatabase>>> = RwLock::new(None);
+
+/// Paths of the local MaxMind-format databases
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GeoIpSettings {
+    /// GeoLite2/GeoIP2 Country or City database (`.mmdb`)
+    #[serde(default)]
+    pub country_db: Option<String>,
+    /// GeoLite2/GeoIP2 ASN database (`.mmdb`)
+    #[serde(default)]
+    pub asn_db: Option<String>,
+}
+
+/// Location and network owner of an external address
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GeoInfo {
+    /// ISO 3166-1 alpha-2 code, e.g. "DE"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub country_code: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub city: Option<String>,
+    /// Autonomous system number, e.g. "AS15169"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub asn: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub as_org: Option<String>,
+}
+
+impl GeoInfo {
+    fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Opened GeoIP databases
+pub struct GeoIpDatabase {
+    country: Option<Reader<Vec<u8>>>,
+    asn: Option<Reader<Vec<u8>>>,
+}
+
+fn open_reader(path: Option<&str>) -> Result<Option<Reader<Vec<u8>>>> {
+    let Some(path) = path.filter(|p| !p.trim().is_empty()) else {
+        return Ok(None);
+    };
+    Reader::open_readfile(Path::new(path))
+        .map(Some)
+        .with_context(|| format!("Failed to open GeoIP database {}", path))
+}
+
+/// English name from a MaxMind `names` map
+fn english_name(names: Option<&std::collections::BTreeMap<&str, &str>>) -> Option<String> {
+    names
+        .and_then(|names| names.get("en"))
+        .map(|name| name.to_string())
+}
+
+impl GeoIpDatabase {
+    /// Open the configured databases (`None` if none are configured)
+    pub fn open(settings: &GeoIpSettings) -> Result<Option<Self>> {
+        let country = open_reader(settings.country_db.as_deref())?;
+        let asn = open_reader(settings.asn_db.as_deref())?;
+        if country.is_none() && asn.is_none() {
+            return Ok(None);
+        }
+        Ok(Some(Self { country, asn }))
+    }
+
+    /// Look up an address (`None` for private addresses and misses)
+    pub fn lookup(&self, ip: IpAddr) -> Option<GeoInfo> {
+        if !is_public_address(ip) {
+            return None;
+        }
+
+        let mut info = GeoInfo::default();
+        if let Some(Ok(city)) = self
+            .country
+            .as_ref()
+            .map(|reader| reader.lookup::<geoip2::City>(ip))
+        {
+            if let Some(country) = city.country.or(city.registered_country) {
+                info.country_code = country.iso_code.map(str::to_string);
+                info.country = english_name(country.names.as_ref());
+            }
+            info.city = english_name(city.city.and_then(|c| c.names).as_ref());
+        }
+        if let Some(Ok(asn)) = self
+            .asn
+            .as_ref()
+            .map(|reader| reader.lookup::<geoip2::Asn>(ip))
+        {
+            info.asn = asn.autonomous_system_number.map(|n| format!("AS{}", n));
+            info.as_org = asn.autonomous_system_organization.map(str::to_string);
+        }
+
+        (!info.is_empty()).then_some(info)
+    }
+}
+
+/// Whether an address is routable on the internet
+///
+/// Private, loopback, link-local, CGNAT, multicast and documentation ranges
+/// never have GeoIP data and are skipped.
+pub fn is_public_address(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_unspecified()
+                || ip.is_documentation()
+                || (a == 100 && (64..128).contains(&b)))
+        }
+        IpAddr::V6(ip) => {
+            let first = ip.segments()[0];
+            !(ip.is_loopback()
+                || ip.is_multicast()
+                || ip.is_unspecified()
+                || (first & 0xfe00) == 0xfc00 // unique local
+                || (first & 0xffc0) == 0xfe80 // link-local
+                || (first == 0x2001 && ip.segments()[1] == 0x0db8)) // documentation
+        }
+    }
+}
+
+/// Look up an address in the loaded databases
+pub fn geoip_lookup(ip: IpAddr) -> Option<GeoInfo> {
+    GEOIP.read().ok()?.as_ref()?.lookup(ip)
+}
+
+/// Whether GeoIP databases are loaded
+pub fn geoip_available() -> bool {
+    GEOIP.read().is_ok_and(|db| db.is_some())
+}
+
+fn install_database(db: Option<GeoIpDatabase>) {
+    if let Ok(mut active) = GEOIP.write() {
+        *active = db.map(Arc::new);
+    }
+}
+
+/// Saved GeoIP database paths
+pub fn load_geoip_settings(conn: &Connection) -> Result<GeoIpSettings> {
+    Ok(queries::get_json_setting(conn, SETTING_GEOIP)?.unwrap_or_default())
+}
+
+/// Open the databases configured in settings and use them for lookups
+///
+/// Returns whether any database was loaded.
+pub fn load_geoip(conn: &Connection) -> Result<bool> {
+    let db = GeoIpDatabase::open(&load_geoip_settings(conn)?)?;
+    let loaded = db.is_some();
+    install_database(db);
+    Ok(loaded)
+}
+
+/// Check that the databases open, then persist and apply the settings
+pub fn save_geoip_settings(conn: &Connection, settings: &GeoIpSettings) -> Result<()> {
+    let db = GeoIpDatabase::open(settings)?;
+    queries::set_json_setting(conn, SETTING_GEOIP, settings)?;
+    install_database(db);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_geoip_skips_local_addresses() {
+        for local in [
+            "192.168.1.10",
+            "10.0.0.1",
+            "100.64.0.1",
+            "169.254.1.1",
+            "fd00::1",
+        ] {
+            assert!(!is_public_address(local.parse().unwrap()), "{}", local);
+        }
+        assert!(is_public_address("8.8.8.8".parse().unwrap()));
+        assert!(is_public_address("2606:4700::1111".parse().unwrap()));
+
+        let none = GeoIpSettings::default();
+        assert!(GeoIpDatabase::open(&none).unwrap().is_none());
+        let missing = GeoIpSettings {
+            asn_db: Some("/nonexistent/GeoLite2-ASN.mmdb".to_string()),
+            ..Default::default()
+        };
+        assert!(GeoIpDatabase::open(&missing).is_err());
+    }
+}
@@ -1,11 +1,12 @@
 //! Network interface detection and selection
 
 use anyhow::{anyhow, Result};
+use ipnetwork::Ipv4Network;
 use pnet::datalink;
 use pnet::util::MacAddr;
 use std::net::{IpAddr, Ipv4Addr};
 
-use crate::models::InterfaceInfo;
+use crate::models::{InterfaceCandidate, InterfaceInfo};
 
 /// Logs a message using structured tracing.
 macro_rules! log_debug {
@@ -107,23 +108,100 @@ fn collect_candidate_interfaces(
     candidates
 }
 
+/// Candidate interfaces, best first
+fn ranked_candidates(
+    pnet_interfaces: &[datalink::NetworkInterface],
+    verbose: bool,
+) -> Vec<InterfaceInfo> {
+    let mut candidates = collect_candidate_interfaces(pnet_interfaces, verbose);
+
+    // Sort candidates: prefer 192.168.x.x, then 10.x.x.x, then others
+    candidates.sort_by(|a, b| {
+        let score_a = interface_score(&a.ip);
+        let score_b = interface_score(&b.ip);
+        score_b.cmp(&score_a)
+    });
+    candidates
+}
+
+/// Picks the best-ranked candidate, or the best address on a named interface
+fn pick_interface(candidates: Vec<InterfaceInfo>, name: Option<&str>) -> Option<InterfaceInfo> {
+    candidates
+        .into_iter()
+        .find(|candidate| name.is_none_or(|name| candidate.name == name))
+}
+
+/// Picks the best-ranked candidate whose subnet contains all of `network`
+fn pick_interface_for(
+    candidates: Vec<InterfaceInfo>,
+    network: Ipv4Network,
+) -> Option<InterfaceInfo> {
+    candidates.into_iter().find(|candidate| {
+        Ipv4Network::new(candidate.ip, candidate.prefix_len)
+            .is_ok_and(|own| own.prefix() <= network.prefix() && own.contains(network.network()))
+    })
+}
+
+/// Finds the interface attached to a network, for scanning a CIDR range
+///
+/// ARP only reaches the local link, so the range must lie within the
+/// subnet of one of the usable interfaces (e.g. a VLAN sub-interface).
+pub fn select_interface_for(network: Ipv4Network) -> Result<InterfaceInfo> {
+    let candidates = ranked_candidates(&datalink::interfaces(), false);
+    let selected = pick_interface_for(candidates, network)
+        .ok_or_else(|| anyhow!("No usable interface is attached to {}", network))?;
+    log_debug!(
+        "Using interface {} (IP: {}/{}) for {}",
+        selected.name,
+        selected.ip,
+        selected.prefix_len,
+        network
+    );
+    Ok(selected)
+}
+
 /// Finds the first valid IPv4 network interface with MAC address
 /// Prefers physical adapters over virtual ones (Hyper-V, VMware, etc.)
 pub fn find_valid_interface() -> Result<InterfaceInfo> {
+    select_interface(None)
+}
+
+/// Finds the interface to scan, by name or automatically
+///
+/// With `None` this is the same as `find_valid_interface`. A name selects
+/// that adapter's best IPv4 address, for multi-homed machines where the
+/// automatic choice is the wrong network.
+pub fn select_interface(name: Option<&str>) -> Result<InterfaceInfo> {
     let pnet_interfaces = datalink::interfaces();
 
     log_debug!("Scanning {} network interfaces...", pnet_interfaces.len());
 
-    let mut candidates = collect_candidate_interfaces(&pnet_interfaces, true);
+    let candidates = ranked_candidates(&pnet_interfaces, true);
 
-    // Sort candidates: prefer 192.168.x.x, then 10.x.x.x, then others
-    candidates.sort_by(|a, b| {
-        let score_a = interface_score(&a.ip);
-        let score_b = interface_score(&b.ip);
-        score_b.cmp(&score_a)
-    });
+    if let Some(name) = name {
+        let available: Vec<String> = candidates.iter().map(|c| c.name.clone()).collect();
+        let Some(selected) = pick_interface(candidates, Some(name)) else {
+            return Err(anyhow!(
+                "Interface '{}' is not a usable IPv4 interface. Available: {}",
+                name,
+                if available.is_empty() {
+                    "none".to_string()
+                } else {
+                    available.join(", ")
+                }
+            ));
+        };
+        log_debug!(
+            "Using requested interface: {} (IP: {}/{}, MAC: {})",
+            selected.name,
+            selected.ip,
+            selected.prefix_len,
+            selected.mac
+        );
+        return Ok(selected);
+    }
 
-    if let Some(best) = candidates.into_iter().next() {
+    if let Some(best) = pick_interface(candidates, None) {
         log_debug!(
             "Selected interface: {} (IP: {}/{}, MAC: {})",
             best.name,
@@ -154,17 +232,8 @@ pub fn find_valid_interface() -> Result<InterfaceInfo> {
 
 /// List valid interface names in priority order.
 pub fn list_valid_interfaces() -> Vec<String> {
-    let pnet_interfaces = datalink::interfaces();
-    let mut candidates = collect_candidate_interfaces(&pnet_interfaces, false);
-
-    candidates.sort_by(|a, b| {
-        let score_a = interface_score(&a.ip);
-        let score_b = interface_score(&b.ip);
-        score_b.cmp(&score_a)
-    });
-
     let mut names = Vec::new();
-    for candidate in candidates {
+    for candidate in ranked_candidates(&datalink::interfaces(), false) {
         if !names.iter().any(|n: &String| n == &candidate.name) {
             names.push(candidate.name);
         }
@@ -172,6 +241,28 @@ pub fn list_valid_interfaces() -> Vec<String> {
     names
 }
 
+/// List every usable IPv4 interface address in priority order
+///
+/// The first entry is the one `find_valid_interface` would pick.
+pub fn list_interfaces() -> Vec<InterfaceCandidate> {
+    describe_candidates(ranked_candidates(&datalink::interfaces(), false))
+}
+
+fn describe_candidates(candidates: Vec<InterfaceInfo>) -> Vec<InterfaceCandidate> {
+    candidates
+        .into_iter()
+        .enumerate()
+        .map(|(i, candidate)| InterfaceCandidate {
+            score: interface_score(&candidate.ip),
+            is_default: i == 0,
+            name: candidate.name,
+            ip: candidate.ip.to_string(),
+            mac: candidate.mac.to_string(),
+            prefix_len: candidate.prefix_len,
+        })
+        .collect()
+}
+
 /// Scores an IP address for interface selection priority
 pub fn interface_score(ip: &Ipv4Addr) -> u32 {
     let octets = ip.octets();
@@ -182,3 +273,148 @@ pub fn interface_score(ip: &Ipv4Addr) -> u32 {
         _ => 70,                        // Other private IPs
     }
 }
+
+/// Negotiated link speed of an interface in Mbit/s, where the OS reports it
+///
+/// Read from sysfs on Linux. Wi-Fi and virtual links usually report none.
+pub fn link_speed_mbps(name: &str) -> Option<u32> {
+    #[cfg(target_os = "linux")]
+    {
+        std::fs::read_to_string(format!("/sys/class/net/{}/speed", name))
+            .ok()
+            .and_then(|speed| speed.trim().parse::<i64>().ok())
+            .filter(|speed| *speed > 0)
+            .and_then(|speed| u32::try_from(speed).ok())
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = name;
+        None
+    }
+}
+
+/// SSID of the Wi-Fi network an interface is associated with
+pub fn wifi_ssid(name: &str) -> Option<String> {
+    #[cfg(target_os = "linux")]
+    let output = std::process::Command::new("iwgetid")
+        .args([name, "--raw"])
+        .output();
+    #[cfg(target_os = "macos")]
+    let output = std::process::Command::new("networksetup")
+        .args(["-getairportnetwork", name])
+        .output();
+    // Interface names are adapter GUIDs here, so the first associated adapter is used
+    #[cfg(target_os = "windows")]
+    let output = {
+        let _ = name;
+        std::process::Command::new("netsh")
+            .args(["wlan", "show", "interfaces"])
+            .output()
+    };
+    #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+    let output: std::io::Result<std::process::Output> = {
+        let _ = name;
+        Err(std::io::ErrorKind::Unsupported.into())
+    };
+
+    let output = output.ok().filter(|output| output.status.success())?;
+    parse_ssid(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Extract the SSID from `iwgetid --raw`, `networksetup -getairportnetwork`
+/// or `netsh wlan show interfaces` output
+fn parse_ssid(output: &str) -> Option<String> {
+    let lines: Vec<&str> = output
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .collect();
+
+    let ssid = lines
+        .iter()
+        .find_map(|line| {
+            if let Some(network) = line
+                .strip_prefix("Current Wi-Fi Network:")
+                .or_else(|| line.strip_prefix("Current AirPort Network:"))
+            {
+                return Some(network);
+            }
+            let (key, value) = line.split_once(':')?;
+            (key.trim() == "SSID").then_some(value)
+        })
+        .or_else(|| match lines.as_slice() {
+            // `iwgetid --raw` prints the bare SSID
+            [ssid] if !ssid.contains("not associated") => Some(*ssid),
+            _ => None,
+        })?
+        .trim();
+
+    (!ssid.is_empty()).then(|| ssid.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pnet::ipnetwork::{IpNetwork, Ipv4Network};
+
+    fn pnet_interface(name: &str, ip: [u8; 4], mac: u8) -> datalink::NetworkInterface {
+        datalink::NetworkInterface {
+            name: name.to_string(),
+            description: String::new(),
+            index: u32::from(mac),
+            mac: Some(MacAddr::new(0x02, 0, 0, 0, 0, mac)),
+            ips: vec![IpNetwork::V4(Ipv4Network::new(ip.into(), 24).unwrap())],
+            // IFF_UP | IFF_BROADCAST | IFF_RUNNING | IFF_MULTICAST
+            flags: 0x1 | 0x2 | 0x40 | 0x1000,
+        }
+    }
+
+    #[test]
+    fn test_interface_listing_and_selection() {
+        let interfaces = vec![
+            pnet_interface("eth1", [172, 20, 0, 5], 1),
+            pnet_interface("eth0", [192, 168, 1, 10], 2),
+            pnet_interface("docker0", [172, 17, 0, 1], 3),
+            pnet_interface("wlan0", [10, 0, 0, 7], 4),
+        ];
+
+        let listed = describe_candidates(ranked_candidates(&interfaces, false));
+        let names: Vec<&str> = listed.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["eth0", "wlan0", "eth1"]);
+        assert!(listed[0].is_default && !listed[1].is_default);
+        assert_eq!(listed[0].ip, "192.168.1.10");
+        assert_eq!(listed[0].mac, "02:00:00:00:00:02");
+        assert_eq!((listed[0].prefix_len, listed[0].score), (24, 100));
+
+        let auto = pick_interface(ranked_candidates(&interfaces, false), None).unwrap();
+        assert_eq!(auto.name, "eth0");
+        let chosen = pick_interface(ranked_candidates(&interfaces, false), Some("eth1")).unwrap();
+        assert_eq!(chosen.ip, Ipv4Addr::new(172, 20, 0, 5));
+        // Filtered-out adapters cannot be selected
+        assert!(pick_interface(ranked_candidates(&interfaces, false), Some("docker0")).is_none());
+
+        // A CIDR range needs an interface on a subnet covering it
+        let range = |cidr: &str| {
+            pick_interface_for(ranked_candidates(&interfaces, false), cidr.parse().unwrap())
+        };
+        assert_eq!(range("10.0.0.128/25").unwrap().name, "wlan0");
+        assert!(range("10.0.0.0/16").is_none());
+        assert!(range("10.1.0.0/24").is_none());
+    }
+
+    #[test]
+    fn test_parse_ssid() {
+        assert_eq!(parse_ssid("HomeNet\n").as_deref(), Some("HomeNet"));
+        assert_eq!(
+            parse_ssid("Current Wi-Fi Network: Office 5G\n").as_deref(),
+            Some("Office 5G")
+        );
+        assert_eq!(
+            parse_ssid("You are not associated with an AirPort network.\n"),
+            None
+        );
+        let netsh = "    Name                   : Wi-Fi\n    State                  : connected\n    SSID                   : Cafe: Guest\n    BSSID                  : aa:bb:cc:dd:ee:ff\n";
+        assert_eq!(parse_ssid(netsh).as_deref(), Some("Cafe: Guest"));
+        assert_eq!(parse_ssid(""), None);
+    }
+}
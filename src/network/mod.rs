@@ -1,13 +1,59 @@
-//! Network module - interface detection, subnet utilities, DNS resolution, device inference
+//! Network module - interface detection, subnet utilities, DNS resolution (reverse lookups and
+//! forward sweeps), device inference (built-in heuristics plus user-defined rules), WAN / public
+//! IP lookup, offline GeoIP, MAC randomization correlation and duplicate IP detection
 
+mod conflict;
+mod correlation;
 mod device;
 mod dns;
+mod dns_message;
+mod dns_sweep;
+mod geoip;
 mod interface;
+mod risk;
+mod rules;
 mod subnet;
 mod vendor;
+mod wan;
 
-pub use device::{calculate_risk_score, infer_device_type, DeviceType};
-pub use dns::dns_scan;
-pub use interface::{find_valid_interface, interface_score, list_valid_interfaces};
-pub use subnet::{calculate_subnet_ips, is_local_subnet, is_special_address};
-pub use vendor::{lookup_vendor, lookup_vendor_info};
+pub use conflict::{annotate_ip_conflicts, merge_ip_conflicts};
+pub use correlation::{best_correlation, correlation_score, CorrelationScore, DeviceFingerprint};
+pub use device::{
+    calculate_risk_score, infer_device_type, infer_device_type_from_services,
+    infer_device_type_with_services, DeviceType,
+};
+pub use dns::{
+    dns_scan, dns_scan_with, load_dns_resolver, resolve_hostname, save_dns_resolver, DnsResolver,
+};
+pub use dns_sweep::{
+    dns_forward_sweep, domain_from_hostnames, load_dns_sweep_settings, match_forward_records,
+    parse_resolv_conf, save_dns_sweep_settings, DnsSweepResult, DnsSweepSettings, ForwardRecord,
+};
+pub use geoip::{
+    geoip_available, geoip_lookup, is_public_address, load_geoip, load_geoip_settings,
+    save_geoip_settings, GeoInfo, GeoIpDatabase, GeoIpSettings,
+};
+pub use interface::{
+    find_valid_interface, interface_score, link_speed_mbps, list_interfaces, list_valid_interfaces,
+    select_interface, select_interface_for, wifi_ssid,
+};
+pub use risk::{
+    active_risk_profile, load_risk_profile, save_risk_profile, RiskProfile, RISK_PROFILE_PRESETS,
+};
+pub use rules::{
+    add_device_type_rule, import_device_type_rules, load_device_type_rules,
+    match_device_type_rules, parse_device_type_rules, remove_device_type_rule,
+    update_device_type_rule, validate_device_type_rule, RuleMatch,
+};
+pub use subnet::{calculate_subnet_ips, is_local_subnet, is_special_address, subnet_chunks};
+pub use vendor::{
+    add_vendor_override, download_oui_csv, fetch_vendor_source, import_oui_csv,
+    install_vendor_source, load_vendor_overrides, load_vendor_source, load_vendor_source_settings,
+    lookup_vendor, lookup_vendor_info, normalize_vendor_pattern, parse_oui_csv,
+    remove_vendor_override, save_vendor_source_settings, update_oui_database, CsvVendorSource,
+    EmbeddedVendorSource, IeeeVendorSource, OuiUpdateSummary, VendorSource, VendorSourceSettings,
+};
+pub use wan::{
+    load_wan_settings, parse_isp_response, parse_public_ip, save_wan_settings, wan_info,
+    WanLookupSettings, WAN_IP_PLACEHOLDER,
+};
@@ -0,0 +1,239 @@
+//! Risk scoring profiles
+//!
+//! The weights behind `calculate_risk_score` live in a `RiskProfile` that is
+//! persisted in `app_settings`, so the same engine can score a homelab
+//! leniently and an audited office network strictly.
+
+use anyhow::{bail, Result};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::RwLock;
+
+use super::device::DeviceType;
+use crate::database::queries::{self, SETTING_RISK_PROFILE};
+
+/// Profile used for scoring; `None` means the built-in default
+static RISK_PROFILE: RwLock<Option<RiskProfile>> = RwLock::new(None);
+
+/// Preset names accepted by `RiskProfile::preset`
+pub const RISK_PROFILE_PRESETS: &[&str] = &["default", "homelab", "corporate"];
+
+/// Weights used to turn device facts into a 0-100 risk score
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RiskProfile {
+    pub name: String,
+    /// Base score per device type (types not listed score 0)
+    pub device_type_scores: HashMap<DeviceType, u8>,
+    /// Score added per open port
+    pub port_weights: BTreeMap<u16, u8>,
+    /// Score added for open ports not in `port_weights`
+    pub default_port_weight: u8,
+    /// Score added when the device uses a randomized MAC
+    pub randomized_mac_penalty: u8,
+    /// Ports that are never acceptable on this network
+    #[serde(default)]
+    pub blacklisted_ports: Vec<u16>,
+    /// Minimum score for a device with any blacklisted port open
+    pub blacklist_score: u8,
+}
+
+impl Default for RiskProfile {
+    fn default() -> Self {
+        let device_type_scores = HashMap::from([
+            (DeviceType::Server, 20),
+            (DeviceType::Router, 15),
+            (DeviceType::Firewall, 15),
+            (DeviceType::Nas, 15),
+            (DeviceType::Camera, 25),    // IoT cameras are often vulnerable
+            (DeviceType::IotDevice, 30), // IoT devices are risky
+            (DeviceType::Printer, 10),
+            (DeviceType::Pc, 10),
+            (DeviceType::Laptop, 10),
+            (DeviceType::Mobile, 5),
+            (DeviceType::Tablet, 5),
+            (DeviceType::SmartTv, 15),
+            (DeviceType::GameConsole, 5),
+            (DeviceType::Switch, 10),
+            (DeviceType::AccessPoint, 10),
+            (DeviceType::Unknown, 20), // Unknown devices are concerning
+        ]);
+
+        let mut port_weights = BTreeMap::from([
+            (21, 15),   // FTP - unencrypted
+            (23, 20),   // Telnet - very insecure
+            (25, 5),    // SMTP
+            (53, 5),    // DNS
+            (80, 5),    // HTTP
+            (139, 15),  // SMB - often targeted
+            (445, 15),  // SMB
+            (443, 2),   // HTTPS - relatively safe
+            (3389, 15), // RDP - often targeted
+            (8080, 5),  // Alt HTTP
+            (8443, 5),  // Alt HTTPS
+        ]);
+        // VNC
+        port_weights.extend((5900..=5910).map(|port| (port, 15)));
+
+        Self {
+            name: "default".to_string(),
+            device_type_scores,
+            port_weights,
+            default_port_weight: 2,
+            randomized_mac_penalty: 5,
+            blacklisted_ports: Vec::new(),
+            blacklist_score: 90,
+        }
+    }
+}
+
+impl RiskProfile {
+    /// Built-in profile by name (see `RISK_PROFILE_PRESETS`)
+    pub fn preset(name: &str) -> Option<Self> {
+        let mut profile = Self::default();
+        match name {
+            "default" => {}
+            // Self-hosted services and phones with private MACs are normal at home
+            "homelab" => {
+                profile.device_type_scores.insert(DeviceType::Server, 10);
+                profile.device_type_scores.insert(DeviceType::Unknown, 10);
+                for port in [22, 80, 443, 8080, 8443] {
+                    profile.port_weights.insert(port, 1);
+                }
+                profile.randomized_mac_penalty = 0;
+            }
+            // Cleartext and remote-desktop protocols are policy violations
+            "corporate" => {
+                profile.device_type_scores.insert(DeviceType::Unknown, 35);
+                profile.device_type_scores.insert(DeviceType::IotDevice, 40);
+                profile.randomized_mac_penalty = 15;
+                profile.blacklisted_ports = vec![21, 23, 69, 5900];
+            }
+            _ => return None,
+        }
+        profile.name = name.to_string();
+        Some(profile)
+    }
+
+    /// Score a device (0-100, higher is riskier)
+    pub fn score(
+        &self,
+        device_type: DeviceType,
+        open_ports: &[u16],
+        is_randomized_mac: bool,
+    ) -> u8 {
+        let mut score = u32::from(
+            self.device_type_scores
+                .get(&device_type)
+                .copied()
+                .unwrap_or(0),
+        );
+
+        for port in open_ports {
+            score += u32::from(
+                self.port_weights
+                    .get(port)
+                    .copied()
+                    .unwrap_or(self.default_port_weight),
+            );
+        }
+
+        if is_randomized_mac {
+            score += u32::from(self.randomized_mac_penalty);
+        }
+
+        if open_ports
+            .iter()
+            .any(|port| self.blacklisted_ports.contains(port))
+        {
+            score = score.max(u32::from(self.blacklist_score));
+        }
+
+        score.min(100) as u8
+    }
+
+    /// Reject profiles that cannot produce meaningful scores
+    pub fn validate(&self) -> Result<()> {
+        if self.name.trim().is_empty() {
+            bail!("Risk profile name cannot be empty");
+        }
+        if self.blacklist_score > 100 {
+            bail!("Blacklist score must be between 0 and 100");
+        }
+        if self.blacklisted_ports.contains(&0) || self.port_weights.contains_key(&0) {
+            bail!("Port 0 is not a valid port");
+        }
+        Ok(())
+    }
+}
+
+/// Profile currently used by `calculate_risk_score`
+pub fn active_risk_profile() -> RiskProfile {
+    RISK_PROFILE
+        .read()
+        .ok()
+        .and_then(|profile| profile.clone())
+        .unwrap_or_default()
+}
+
+/// Score a device with the active profile
+pub(crate) fn score_with_active_profile(
+    device_type: DeviceType,
+    open_ports: &[u16],
+    is_randomized_mac: bool,
+) -> u8 {
+    match RISK_PROFILE.read().ok().as_deref() {
+        Some(Some(profile)) => profile.score(device_type, open_ports, is_randomized_mac),
+        _ => RiskProfile::default().score(device_type, open_ports, is_randomized_mac),
+    }
+}
+
+fn install_profile(profile: Option<RiskProfile>) {
+    if let Ok(mut active) = RISK_PROFILE.write() {
+        *active = profile;
+    }
+}
+
+/// Load the saved profile from the database and use it for scoring
+pub fn load_risk_profile(conn: &Connection) -> Result<RiskProfile> {
+    let profile: Option<RiskProfile> = queries::get_json_setting(conn, SETTING_RISK_PROFILE)?;
+    install_profile(profile.clone());
+    Ok(profile.unwrap_or_default())
+}
+
+/// Validate, persist and apply a profile
+pub fn save_risk_profile(conn: &Connection, profile: &RiskProfile) -> Result<()> {
+    profile.validate()?;
+    queries::set_json_setting(conn, SETTING_RISK_PROFILE, profile)?;
+    install_profile(Some(profile.clone()));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_risk_profiles() {
+        let default = RiskProfile::default();
+        assert_eq!(default.score(DeviceType::Pc, &[22, 23], false), 32);
+        assert_eq!(default.score(DeviceType::Camera, &[5901], true), 45);
+        // Many open ports saturate instead of overflowing
+        let ports: Vec<u16> = (1..=200).collect();
+        assert_eq!(default.score(DeviceType::Unknown, &ports, true), 100);
+
+        let homelab = RiskProfile::preset("homelab").unwrap();
+        let corporate = RiskProfile::preset("corporate").unwrap();
+        assert!(homelab.score(DeviceType::Server, &[22, 443], true) < 20);
+        assert_eq!(corporate.score(DeviceType::Pc, &[23], false), 90);
+        assert!(RiskProfile::preset("paranoid").is_none());
+
+        let json = serde_json::to_string(&corporate).unwrap();
+        let parsed: RiskProfile = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, corporate);
+
+        let mut invalid = corporate;
+        invalid.blacklist_score = 150;
+        assert!(invalid.validate().is_err());
+    }
+}
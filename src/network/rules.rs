@@ -0,0 +1,271 @@
+//! User-defined device type rules
+//!
+//! Rules are stored in SQLite and compiled into a process-wide table that
+//! `infer_device_type_with_services` consults before the built-in heuristics,
+//! so chronic misclassifications can be fixed without a new release.
+
+use anyhow::{anyhow, bail, Context, Result};
+use regex::{Regex, RegexBuilder};
+use rusqlite::Connection;
+use serde::Serialize;
+use std::sync::RwLock;
+
+use super::device::DeviceType;
+use crate::database::{queries, DeviceTypeRule};
+
+/// Enabled rules, compiled from the database
+static DEVICE_TYPE_RULES: RwLock<Vec<CompiledRule>> = RwLock::new(Vec::new());
+
+/// A rule that matched a device
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RuleMatch {
+    pub rule_name: String,
+    pub device_type: DeviceType,
+    pub confidence: u8,
+}
+
+struct CompiledRule {
+    name: String,
+    vendor: Option<Regex>,
+    hostname: Option<Regex>,
+    ports: Vec<u16>,
+    service: Option<String>,
+    device_type: DeviceType,
+    confidence: u8,
+}
+
+impl CompiledRule {
+    fn compile(rule: &DeviceTypeRule) -> Result<Self> {
+        let pattern = |field: &str, value: &Option<String>| -> Result<Option<Regex>> {
+            value
+                .as_deref()
+                .map(str::trim)
+                .filter(|p| !p.is_empty())
+                .map(|p| {
+                    RegexBuilder::new(p)
+                        .case_insensitive(true)
+                        .build()
+                        .with_context(|| format!("Invalid {} pattern: {}", field, p))
+                })
+                .transpose()
+        };
+
+        let vendor = pattern("vendor", &rule.vendor_pattern)?;
+        let hostname = pattern("hostname", &rule.hostname_pattern)?;
+        let service = rule
+            .service
+            .as_deref()
+            .map(|s| s.trim().trim_end_matches('.').to_lowercase())
+            .filter(|s| !s.is_empty());
+
+        if vendor.is_none() && hostname.is_none() && rule.ports.is_empty() && service.is_none() {
+            bail!("Rule '{}' has no conditions", rule.name);
+        }
+
+        Ok(Self {
+            name: rule.name.clone(),
+            vendor,
+            hostname,
+            ports: rule.ports.clone(),
+            service,
+            device_type: rule.device_type.parse().map_err(|e: String| anyhow!(e))?,
+            confidence: rule.confidence.min(100),
+        })
+    }
+
+    fn matches<S: AsRef<str>>(
+        &self,
+        vendor: Option<&str>,
+        hostname: Option<&str>,
+        ports: &[u16],
+        services: &[S],
+    ) -> bool {
+        let pattern_matches = |re: &Option<Regex>, value: Option<&str>| {
+            re.as_ref()
+                .is_none_or(|re| value.is_some_and(|v| re.is_match(v)))
+        };
+
+        pattern_matches(&self.vendor, vendor)
+            && pattern_matches(&self.hostname, hostname)
+            && self.ports.iter().all(|p| ports.contains(p))
+            && self.service.as_ref().is_none_or(|wanted| {
+                services.iter().any(|s| {
+                    let s = s.as_ref().trim_end_matches('.').to_lowercase();
+                    s == *wanted || s.starts_with(&format!("{}.", wanted))
+                })
+            })
+    }
+}
+
+/// Check that a rule compiles (valid patterns, known device type, a condition)
+pub fn validate_device_type_rule(rule: &DeviceTypeRule) -> Result<()> {
+    if rule.name.trim().is_empty() {
+        bail!("Rule name cannot be empty");
+    }
+    CompiledRule::compile(rule).map(|_| ())
+}
+
+/// Parse rules in the import format (a JSON array of rules)
+pub fn parse_device_type_rules(json: &str) -> Result<Vec<DeviceTypeRule>> {
+    let rules: Vec<DeviceTypeRule> =
+        serde_json::from_str(json).context("Rules must be a JSON array of rule objects")?;
+    for rule in &rules {
+        validate_device_type_rule(rule)?;
+    }
+    Ok(rules)
+}
+
+/// Validate and store a new rule, then apply it to inference
+pub fn add_device_type_rule(conn: &Connection, rule: &DeviceTypeRule) -> Result<i64> {
+    validate_device_type_rule(rule)?;
+    let id = queries::insert_device_type_rule(conn, rule)?;
+    load_device_type_rules(conn)?;
+    Ok(id)
+}
+
+/// Validate and replace a stored rule, returning whether it existed
+pub fn update_device_type_rule(conn: &Connection, id: i64, rule: &DeviceTypeRule) -> Result<bool> {
+    validate_device_type_rule(rule)?;
+    let updated = queries::update_device_type_rule(conn, id, rule)?;
+    load_device_type_rules(conn)?;
+    Ok(updated)
+}
+
+/// Remove a stored rule, returning whether it existed
+pub fn remove_device_type_rule(conn: &Connection, id: i64) -> Result<bool> {
+    let removed = queries::delete_device_type_rule(conn, id)?;
+    load_device_type_rules(conn)?;
+    Ok(removed)
+}
+
+/// Import rules from JSON, returning how many were added
+///
+/// Nothing is stored unless every rule in the file is valid.
+pub fn import_device_type_rules(conn: &Connection, json: &str) -> Result<usize> {
+    let rules = parse_device_type_rules(json)?;
+    queries::insert_device_type_rules(conn, &rules)?;
+    load_device_type_rules(conn)?;
+    Ok(rules.len())
+}
+
+/// Load enabled rules from the database into the inference table
+///
+/// Rules that no longer compile are skipped with a warning. Returns the
+/// number of rules in use.
+pub fn load_device_type_rules(conn: &Connection) -> Result<usize> {
+    let compiled: Vec<CompiledRule> = queries::get_device_type_rules(conn)?
+        .into_iter()
+        .filter(|record| record.rule.enabled)
+        .filter_map(|record| match CompiledRule::compile(&record.rule) {
+            Ok(rule) => Some(rule),
+            Err(e) => {
+                tracing::warn!("Skipping device type rule {}: {}", record.id, e);
+                None
+            }
+        })
+        .collect();
+
+    let count = compiled.len();
+    if let Ok(mut rules) = DEVICE_TYPE_RULES.write() {
+        *rules = compiled;
+    }
+    Ok(count)
+}
+
+/// Find the highest-confidence user rule matching a device
+pub fn match_device_type_rules<S: AsRef<str>>(
+    vendor: Option<&str>,
+    hostname: Option<&str>,
+    ports: &[u16],
+    services: &[S],
+) -> Option<RuleMatch> {
+    let rules = DEVICE_TYPE_RULES.read().ok()?;
+    rules
+        .iter()
+        .filter(|rule| rule.matches(vendor, hostname, ports, services))
+        .max_by(|a, b| a.confidence.cmp(&b.confidence).then(b.name.cmp(&a.name)))
+        .map(|rule| RuleMatch {
+            rule_name: rule.name.clone(),
+            device_type: rule.device_type,
+            confidence: rule.confidence,
+        })
+}
+
+/// Serializes tests that touch the process-wide rule table
+#[cfg(test)]
+static RULES_TEST_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+/// Runs a test against an empty rule table, one test at a time, and puts
+/// the previous rules back when dropped
+///
+/// Held by every test that infers device types, so rules loaded by one
+/// test never change another's results.
+#[cfg(test)]
+pub(crate) struct RulesTestGuard {
+    saved: Vec<CompiledRule>,
+    _lock: std::sync::MutexGuard<'static, ()>,
+}
+
+#[cfg(test)]
+impl RulesTestGuard {
+    pub(crate) fn new() -> Self {
+        let lock = RULES_TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let saved = std::mem::take(&mut *DEVICE_TYPE_RULES.write().unwrap());
+        Self { saved, _lock: lock }
+    }
+}
+
+#[cfg(test)]
+impl Drop for RulesTestGuard {
+    fn drop(&mut self) {
+        if let Ok(mut rules) = DEVICE_TYPE_RULES.write() {
+            *rules = std::mem::take(&mut self.saved);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Database;
+    use crate::network::infer_device_type_with_services;
+
+    #[test]
+    fn test_device_type_rules_override_heuristics() {
+        let _rules = RulesTestGuard::new();
+        let db = Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
+
+        let json = r#"[
+            {"name": "Lab printers", "vendor_pattern": "^hp", "ports": [9100],
+             "device_type": "PRINTER", "confidence": 90},
+            {"name": "Sonos", "service": "_sonos._tcp", "device_type": "iot_device"}
+        ]"#;
+        assert_eq!(import_device_type_rules(&conn, json).unwrap(), 2);
+
+        // Vendor heuristics would say PC
+        assert_eq!(
+            infer_device_type_with_services::<&str>(Some("HP Inc."), None, &[9100, 80], &[], false),
+            DeviceType::Printer
+        );
+        assert_eq!(
+            infer_device_type_with_services::<&str>(Some("HP Inc."), None, &[80], &[], false),
+            DeviceType::Pc
+        );
+        let matched = match_device_type_rules(None, None, &[], &["_sonos._tcp.local."]).unwrap();
+        assert_eq!(matched.rule_name, "Sonos");
+        assert_eq!(matched.confidence, 80);
+
+        // Invalid files are rejected as a whole
+        let bad = r#"[{"name": "ok", "ports": [22], "device_type": "SERVER"},
+                      {"name": "bad", "vendor_pattern": "(", "device_type": "PC"}]"#;
+        assert!(import_device_type_rules(&conn, bad).is_err());
+        assert!(parse_device_type_rules(r#"[{"name": "x", "device_type": "PC"}]"#).is_err());
+        assert_eq!(queries::get_device_type_rules(&conn).unwrap().len(), 2);
+
+        for record in queries::get_device_type_rules(&conn).unwrap() {
+            assert!(remove_device_type_rule(&conn, record.id).unwrap());
+        }
+        assert!(match_device_type_rules::<&str>(Some("HP"), None, &[9100], &[]).is_none());
+    }
+}
@@ -4,7 +4,7 @@ use anyhow::{Context, Result};
 use ipnetwork::Ipv4Network;
 use std::net::Ipv4Addr;
 
-use crate::config::MAX_SCAN_HOSTS;
+use crate::config::{MAX_SCAN_HOSTS, SUBNET_CHUNK_PREFIX};
 use crate::models::InterfaceInfo;
 
 /// Logs a message to stderr
@@ -45,37 +45,39 @@ pub fn calculate_subnet_ips(interface: &InterfaceInfo) -> Result<(Ipv4Network, V
     let subnet = Ipv4Network::new(network.network(), interface.prefix_len)
         .context("Failed to create subnet network")?;
 
-    // Exclude network and broadcast addresses
-    let all_ips: Vec<Ipv4Addr> = subnet
-        .iter()
-        .filter(|ip| !is_special_address(*ip, &subnet))
-        .collect();
+    // Host addresses exclude network and broadcast; only the scanned window
+    // is materialized, so a /8 never becomes a 16M-entry vector
+    let first_host = u32::from(subnet.network()) + 1;
+    let host_count =
+        (u32::from(subnet.broadcast()) - u32::from(subnet.network())).saturating_sub(1) as usize;
 
     // Limit to MAX_SCAN_HOSTS for performance
-    let ips = if all_ips.len() > MAX_SCAN_HOSTS {
+    let start = if host_count > MAX_SCAN_HOSTS {
         log_warn!(
             "Subnet {} has {} hosts, limiting scan to {} hosts",
             subnet,
-            all_ips.len(),
+            host_count,
             MAX_SCAN_HOSTS
         );
         // Center the scan window around the local IP index within the subnet host list.
-        let center_index = all_ips
-            .iter()
-            .position(|ip| *ip == interface.ip)
-            .unwrap_or(all_ips.len() / 2);
+        let center_index = u32::from(interface.ip)
+            .checked_sub(first_host)
+            .map(|index| index as usize)
+            .filter(|index| *index < host_count)
+            .unwrap_or(host_count / 2);
         let half_window = MAX_SCAN_HOSTS / 2;
 
-        let mut start = center_index.saturating_sub(half_window);
-        if start + MAX_SCAN_HOSTS > all_ips.len() {
-            start = all_ips.len().saturating_sub(MAX_SCAN_HOSTS);
-        }
-
-        all_ips.into_iter().skip(start).take(MAX_SCAN_HOSTS).collect()
+        center_index
+            .saturating_sub(half_window)
+            .min(host_count - MAX_SCAN_HOSTS)
     } else {
-        all_ips
+        0
     };
 
+    let ips: Vec<Ipv4Addr> = (0..host_count.min(MAX_SCAN_HOSTS))
+        .map(|offset| Ipv4Addr::from(first_host + (start + offset) as u32))
+        .collect();
+
     log_stderr!(
         "Calculated subnet: {} with {} scannable hosts",
         subnet,
@@ -85,6 +87,16 @@ pub fn calculate_subnet_ips(interface: &InterfaceInfo) -> Result<(Ipv4Network, V
     Ok((subnet, ips))
 }
 
+/// Splits target IPs into per-/24 chunks (`SUBNET_CHUNK_PREFIX`)
+///
+/// Consecutive addresses in the same chunk network stay together, so a sorted
+/// /16 target list yields 256 chunks of at most 256 addresses.
+pub fn subnet_chunks(ips: &[Ipv4Addr]) -> Vec<&[Ipv4Addr]> {
+    let mask = u32::MAX << (32 - SUBNET_CHUNK_PREFIX as u32);
+    ips.chunk_by(|a, b| u32::from(*a) & mask == u32::from(*b) & mask)
+        .collect()
+}
+
 #[cfg(test)]
 #[path = "subnet_tests.rs"]
 mod subnet_tests;
@@ -96,4 +96,25 @@ mod tests {
         let (_, ips) = result.unwrap();
         assert_eq!(ips.len(), 2); // 4 - 2 (network + broadcast)
     }
+
+    #[test]
+    fn test_calculate_subnet_ips_large_prefixes_are_chunked() {
+        let interface = create_test_interface("10.20.30.40", 16);
+        let (_, ips) = calculate_subnet_ips(&interface).unwrap();
+        assert_eq!(ips.len(), 65_534);
+
+        let chunks = subnet_chunks(&ips);
+        assert_eq!(chunks.len(), 256);
+        assert_eq!(chunks[0].len(), 255); // 10.20.0.1 - 10.20.0.255
+        assert_eq!(chunks[255].len(), 255); // 10.20.255.0 - 10.20.255.254
+        assert!(chunks.iter().all(|chunk| chunk.len() <= 256));
+
+        // A /8 is capped to the window around the local address
+        let interface = create_test_interface("10.20.30.40", 8);
+        let (subnet, ips) = calculate_subnet_ips(&interface).unwrap();
+        assert_eq!(subnet.prefix(), 8);
+        assert_eq!(ips.len(), MAX_SCAN_HOSTS);
+        assert!(ips.contains(&"10.20.30.40".parse().unwrap()));
+        assert!(ips.windows(2).all(|pair| pair[0] < pair[1]));
+    }
 }
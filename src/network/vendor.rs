@@ -1,18 +1,443 @@
 //! MAC Address Vendor Lookup using OUI Database
 //!
-//! Uses the macaddress.io database to identify device manufacturers.
+//! Uses the macaddress.io database to identify device manufacturers. Vendor
+//! data comes from a `VendorSource` selected in settings: the bundled table,
+//! the IEEE registry (downloaded and stored in SQLite) or a CSV file supplied
+//! by the user for air-gapped hosts. The selected source takes precedence,
+//! with the bundled table as fallback. User-defined overrides (per prefix or
+//! per MAC) are consulted before both.
 
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
 use mac_oui::Oui;
-use std::sync::OnceLock;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Read;
+use std::sync::{OnceLock, RwLock};
+
+use crate::config::{OUI_DOWNLOAD_TIMEOUT, OUI_REGISTRY_URL};
+use crate::database::queries::{self, SETTING_VENDOR_SOURCE};
+use crate::database::VendorOverrideRecord;
 
 /// Global OUI database instance (loaded once)
 static OUI_DB: OnceLock<Option<Oui>> = OnceLock::new();
 
+/// Vendor data selected in settings; the bundled table answers when unset
+static VENDOR_SOURCE: RwLock<Option<Box<dyn VendorSource>>> = RwLock::new(None);
+
+/// User-defined overrides as (hex pattern, vendor), longest pattern first
+static VENDOR_OVERRIDES: RwLock<Vec<(String, String)>> = RwLock::new(Vec::new());
+
 /// Initialize the OUI database
 fn get_oui_db() -> Option<&'static Oui> {
     OUI_DB.get_or_init(|| Oui::default().ok()).as_ref()
 }
 
+/// Result of refreshing the OUI registry
+#[derive(Debug, Clone, Serialize)]
+pub struct OuiUpdateSummary {
+    pub entries: usize,
+    pub source: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A table of OUI prefix to vendor name assignments
+pub trait VendorSource: Send + Sync {
+    /// Where the data comes from (URL, file path or "bundled")
+    fn origin(&self) -> String;
+
+    /// Number of prefixes known
+    fn entries(&self) -> usize;
+
+    /// Vendor registered for the OUI of a MAC address
+    fn lookup(&self, mac: &str) -> Option<String>;
+
+    /// Keep freshly fetched data for the next start
+    ///
+    /// Sources that re-read their data on load have nothing to store.
+    fn persist(&self, _conn: &Connection) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// The table bundled with the `mac_oui` crate
+pub struct EmbeddedVendorSource;
+
+impl VendorSource for EmbeddedVendorSource {
+    fn origin(&self) -> String {
+        "bundled".to_string()
+    }
+
+    fn entries(&self) -> usize {
+        get_oui_db().map_or(0, |db| db.get_total_records().max(0) as usize)
+    }
+
+    fn lookup(&self, mac: &str) -> Option<String> {
+        let entry = get_oui_db()?.lookup_by_mac(mac).ok()??;
+        Some(entry.company_name.clone())
+    }
+}
+
+/// The IEEE registry, downloaded and stored in the database
+pub struct IeeeVendorSource {
+    url: String,
+    entries: HashMap<String, String>,
+}
+
+impl IeeeVendorSource {
+    /// Download and parse the registry (blocking)
+    pub fn download(url: &str) -> Result<Self> {
+        let bytes = download_oui_csv(url)?;
+        Self::from_csv(url, &bytes)
+    }
+
+    /// Parse registry CSV obtained from `url`
+    pub fn from_csv(url: &str, csv_bytes: &[u8]) -> Result<Self> {
+        Ok(Self {
+            url: url.to_string(),
+            entries: parse_oui_csv(csv_bytes)?.into_iter().collect(),
+        })
+    }
+
+    /// The copy stored by the last download (empty if there was none)
+    pub fn from_database(conn: &Connection, url: &str) -> Result<Self> {
+        Ok(Self {
+            url: url.to_string(),
+            entries: queries::get_oui_registry(conn)?.into_iter().collect(),
+        })
+    }
+}
+
+impl VendorSource for IeeeVendorSource {
+    fn origin(&self) -> String {
+        self.url.clone()
+    }
+
+    fn entries(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn lookup(&self, mac: &str) -> Option<String> {
+        lookup_prefix(&self.entries, mac)
+    }
+
+    fn persist(&self, conn: &Connection) -> Result<()> {
+        let entries: Vec<(String, String)> = self
+            .entries
+            .iter()
+            .map(|(prefix, vendor)| (prefix.clone(), vendor.clone()))
+            .collect();
+        queries::replace_oui_registry(conn, &entries)?;
+        Ok(())
+    }
+}
+
+/// A registry CSV supplied by the user, for hosts without internet access
+///
+/// The file is read again on every start, so replacing it and reloading is
+/// all an update takes.
+pub struct CsvVendorSource {
+    path: String,
+    entries: HashMap<String, String>,
+}
+
+impl CsvVendorSource {
+    /// Read and parse the file
+    pub fn open(path: &str) -> Result<Self> {
+        let file = std::fs::File::open(path)
+            .with_context(|| format!("Failed to open vendor CSV {}", path))?;
+        let entries = parse_oui_csv(std::io::BufReader::new(file))
+            .with_context(|| format!("Failed to read vendor CSV {}", path))?;
+        Ok(Self {
+            path: path.to_string(),
+            entries: entries.into_iter().collect(),
+        })
+    }
+}
+
+impl VendorSource for CsvVendorSource {
+    fn origin(&self) -> String {
+        self.path.clone()
+    }
+
+    fn entries(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn lookup(&self, mac: &str) -> Option<String> {
+        lookup_prefix(&self.entries, mac)
+    }
+}
+
+/// Look up the first 6 hex digits of a MAC in a prefix table
+fn lookup_prefix(entries: &HashMap<String, String>, mac: &str) -> Option<String> {
+    let hex = mac_hex(mac);
+    entries.get(hex.get(..6)?).cloned()
+}
+
+/// Parse the IEEE OUI CSV (`Registry,Assignment,Organization Name,...`)
+///
+/// Hand-made files with just `Prefix,Vendor` columns are accepted too, with
+/// or without separators in the prefix. Returns (prefix, vendor) pairs with
+/// the prefix as 6 uppercase hex digits.
+pub fn parse_oui_csv<R: Read>(reader: R) -> Result<Vec<(String, String)>> {
+    let mut csv = csv::ReaderBuilder::new().flexible(true).from_reader(reader);
+
+    let headers = csv.headers().context("OUI CSV has no header row")?.clone();
+    let column = |name: &str| {
+        headers
+            .iter()
+            .position(|h| h.trim().eq_ignore_ascii_case(name))
+    };
+    let (Some(assignment_col), Some(name_col)) = (
+        column("Assignment").or_else(|| column("Prefix")),
+        column("Organization Name").or_else(|| column("Vendor")),
+    ) else {
+        bail!("OUI CSV is missing Assignment/Organization Name columns");
+    };
+
+    let mut entries = Vec::new();
+    for record in csv.records() {
+        let record = record.context("Malformed OUI CSV row")?;
+        let (Some(prefix), Some(vendor)) = (record.get(assignment_col), record.get(name_col))
+        else {
+            continue;
+        };
+
+        let prefix = prefix
+            .trim()
+            .chars()
+            .filter(|c| !matches!(c, ':' | '-' | '.'))
+            .collect::<String>()
+            .to_uppercase();
+        let vendor = vendor.trim();
+        if prefix.len() == 6 && prefix.chars().all(|c| c.is_ascii_hexdigit()) && !vendor.is_empty()
+        {
+            entries.push((prefix, vendor.to_string()));
+        }
+    }
+
+    if entries.is_empty() {
+        bail!("OUI CSV contained no entries");
+    }
+    Ok(entries)
+}
+
+/// Download the IEEE OUI registry CSV
+///
+/// Blocking; call from `spawn_blocking` in async code.
+pub fn download_oui_csv(url: &str) -> Result<Vec<u8>> {
+    let response = ureq::get(url)
+        .timeout(OUI_DOWNLOAD_TIMEOUT)
+        .call()
+        .with_context(|| format!("Failed to download OUI registry from {}", url))?;
+
+    let mut bytes = Vec::new();
+    response
+        .into_reader()
+        .read_to_end(&mut bytes)
+        .context("Failed to read OUI registry download")?;
+    Ok(bytes)
+}
+
+/// Parse a downloaded IEEE registry CSV, store it and use it for lookups
+pub fn import_oui_csv(conn: &Connection, csv_bytes: &[u8]) -> Result<OuiUpdateSummary> {
+    install_vendor_source(
+        conn,
+        Box::new(IeeeVendorSource::from_csv(OUI_REGISTRY_URL, csv_bytes)?),
+    )
+}
+
+/// Refresh the configured vendor source (blocking)
+///
+/// On failure the data already loaded, or the bundled table, stays in use.
+pub fn update_oui_database(conn: &Connection) -> Result<OuiUpdateSummary> {
+    let source = fetch_vendor_source(&load_vendor_source_settings(conn)?)?;
+    install_vendor_source(conn, source)
+}
+
+/// Which vendor data is used for lookups
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum VendorSourceSettings {
+    /// Only the bundled table
+    Embedded,
+    /// The IEEE registry, downloaded on request
+    Ieee {
+        #[serde(default = "default_registry_url")]
+        url: String,
+    },
+    /// A registry CSV on disk, updated by hand
+    Csv { path: String },
+}
+
+fn default_registry_url() -> String {
+    OUI_REGISTRY_URL.to_string()
+}
+
+impl Default for VendorSourceSettings {
+    fn default() -> Self {
+        Self::Ieee {
+            url: default_registry_url(),
+        }
+    }
+}
+
+/// Saved vendor data source (the IEEE registry if none is saved)
+pub fn load_vendor_source_settings(conn: &Connection) -> Result<VendorSourceSettings> {
+    Ok(queries::get_json_setting(conn, SETTING_VENDOR_SOURCE)?.unwrap_or_default())
+}
+
+/// Open the configured source from local data, without downloading
+fn open_vendor_source(
+    conn: &Connection,
+    settings: &VendorSourceSettings,
+) -> Result<Box<dyn VendorSource>> {
+    Ok(match settings {
+        VendorSourceSettings::Embedded => Box::new(EmbeddedVendorSource),
+        VendorSourceSettings::Ieee { url } => Box::new(IeeeVendorSource::from_database(conn, url)?),
+        VendorSourceSettings::Csv { path } => Box::new(CsvVendorSource::open(path)?),
+    })
+}
+
+/// Fetch fresh data for the configured source
+///
+/// Downloads the IEEE registry or re-reads the CSV file. Blocking; call from
+/// `spawn_blocking` in async code.
+pub fn fetch_vendor_source(settings: &VendorSourceSettings) -> Result<Box<dyn VendorSource>> {
+    match settings {
+        VendorSourceSettings::Embedded => {
+            bail!("The bundled vendor table cannot be updated; select the IEEE or CSV source")
+        }
+        VendorSourceSettings::Ieee { url } => Ok(Box::new(IeeeVendorSource::download(url)?)),
+        VendorSourceSettings::Csv { path } => Ok(Box::new(CsvVendorSource::open(path)?)),
+    }
+}
+
+/// Store a fetched source and use it for lookups
+pub fn install_vendor_source(
+    conn: &Connection,
+    source: Box<dyn VendorSource>,
+) -> Result<OuiUpdateSummary> {
+    source.persist(conn)?;
+    let summary = OuiUpdateSummary {
+        entries: source.entries(),
+        source: source.origin(),
+        updated_at: Utc::now(),
+    };
+    set_active_source(source);
+    Ok(summary)
+}
+
+/// Open the configured source and use it for lookups
+///
+/// Returns the number of prefixes loaded; prefixes the source does not know
+/// still resolve through the bundled table.
+pub fn load_vendor_source(conn: &Connection) -> Result<usize> {
+    let source = open_vendor_source(conn, &load_vendor_source_settings(conn)?)?;
+    let entries = source.entries();
+    set_active_source(source);
+    Ok(entries)
+}
+
+/// Check that the source opens, then persist and apply the settings
+pub fn save_vendor_source_settings(
+    conn: &Connection,
+    settings: &VendorSourceSettings,
+) -> Result<()> {
+    if let VendorSourceSettings::Ieee { url } = settings {
+        if !(url.starts_with("https://") || url.starts_with("http://")) {
+            bail!("OUI registry URL must start with http:// or https://");
+        }
+    }
+    let source = open_vendor_source(conn, settings)?;
+    queries::set_json_setting(conn, SETTING_VENDOR_SOURCE, settings)?;
+    set_active_source(source);
+    Ok(())
+}
+
+fn set_active_source(source: Box<dyn VendorSource>) {
+    if let Ok(mut active) = VENDOR_SOURCE.write() {
+        *active = Some(source);
+    }
+}
+
+/// Look up a MAC in the active source
+fn lookup_source(mac: &str) -> Option<String> {
+    VENDOR_SOURCE.read().ok()?.as_ref()?.lookup(mac)
+}
+
+/// Uppercase hex digits of a MAC address, separators removed
+fn mac_hex(mac: &str) -> String {
+    mac.chars()
+        .filter(|c| c.is_ascii_hexdigit())
+        .collect::<String>()
+        .to_uppercase()
+}
+
+/// Normalize a user-supplied prefix or MAC (`00:1a:2b`, `00-1A-2B-3C`, ...)
+///
+/// Accepts 6 to 12 hex digits; 12 digits match a single device.
+pub fn normalize_vendor_pattern(pattern: &str) -> Result<String> {
+    let trimmed = pattern.trim();
+    if trimmed
+        .chars()
+        .any(|c| !c.is_ascii_hexdigit() && !matches!(c, ':' | '-' | '.'))
+    {
+        bail!("Invalid MAC prefix: {}", pattern);
+    }
+
+    let hex = mac_hex(trimmed);
+    if !(6..=12).contains(&hex.len()) {
+        bail!("MAC prefix must have 6 to 12 hex digits: {}", pattern);
+    }
+    Ok(hex)
+}
+
+/// Add or replace a vendor override and apply it to lookups
+pub fn add_vendor_override(conn: &Connection, pattern: &str, vendor: &str) -> Result<String> {
+    let pattern = normalize_vendor_pattern(pattern)?;
+    let vendor = vendor.trim();
+    if vendor.is_empty() {
+        bail!("Vendor name cannot be empty");
+    }
+
+    queries::upsert_vendor_override(conn, &pattern, vendor)?;
+    load_vendor_overrides(conn)?;
+    Ok(pattern)
+}
+
+/// Remove a vendor override, returning whether it existed
+pub fn remove_vendor_override(conn: &Connection, pattern: &str) -> Result<bool> {
+    let removed = queries::delete_vendor_override(conn, &normalize_vendor_pattern(pattern)?)?;
+    load_vendor_overrides(conn)?;
+    Ok(removed)
+}
+
+/// Load vendor overrides from the database into the lookup table
+pub fn load_vendor_overrides(conn: &Connection) -> Result<Vec<VendorOverrideRecord>> {
+    let records = queries::get_vendor_overrides(conn)?;
+    if let Ok(mut overrides) = VENDOR_OVERRIDES.write() {
+        // Query order is longest pattern first, so the first match is the most specific
+        *overrides = records
+            .iter()
+            .map(|r| (r.pattern.clone(), r.vendor.clone()))
+            .collect();
+    }
+    Ok(records)
+}
+
+/// Look up a MAC in the user-defined overrides
+fn lookup_override(mac: &str) -> Option<String> {
+    let hex = mac_hex(mac);
+    let overrides = VENDOR_OVERRIDES.read().ok()?;
+    overrides
+        .iter()
+        .find(|(pattern, _)| hex.starts_with(pattern.as_str()))
+        .map(|(_, vendor)| vendor.clone())
+}
+
 /// Vendor lookup result with randomization detection
 pub struct VendorInfo {
     pub vendor: Option<String>,
@@ -49,6 +474,14 @@ pub fn is_locally_administered(mac: &str) -> bool {
 pub fn lookup_vendor_info(mac: &str) -> VendorInfo {
     let is_randomized = is_locally_administered(mac);
 
+    // User overrides win, including for locally administered lab/white-label MACs
+    if let Some(vendor) = lookup_override(mac) {
+        return VendorInfo {
+            vendor: Some(vendor),
+            is_randomized,
+        };
+    }
+
     // If randomized, return special vendor name
     if is_randomized {
         return VendorInfo {
@@ -57,16 +490,8 @@ pub fn lookup_vendor_info(mac: &str) -> VendorInfo {
         };
     }
 
-    // Otherwise, look up in OUI database
-    let vendor = if let Some(db) = get_oui_db() {
-        if let Ok(Some(entry)) = db.lookup_by_mac(mac) {
-            Some(entry.company_name.clone())
-        } else {
-            None
-        }
-    } else {
-        None
-    };
+    // Otherwise, look up in the configured source, then the bundled OUI database
+    let vendor = lookup_source(mac).or_else(|| EmbeddedVendorSource.lookup(mac));
 
     VendorInfo {
         vendor,
@@ -82,6 +507,32 @@ pub fn lookup_vendor(mac: &str) -> Option<String> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::{Mutex, MutexGuard};
+
+    static OVERRIDES_LOCK: Mutex<()> = Mutex::new(());
+
+    /// Runs a test against an empty override table, one test at a time,
+    /// and puts the previous table back when dropped
+    struct OverridesGuard {
+        saved: Vec<(String, String)>,
+        _lock: MutexGuard<'static, ()>,
+    }
+
+    impl OverridesGuard {
+        fn new() -> Self {
+            let lock = OVERRIDES_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+            let saved = std::mem::take(&mut *VENDOR_OVERRIDES.write().unwrap());
+            Self { saved, _lock: lock }
+        }
+    }
+
+    impl Drop for OverridesGuard {
+        fn drop(&mut self) {
+            if let Ok(mut overrides) = VENDOR_OVERRIDES.write() {
+                *overrides = std::mem::take(&mut self.saved);
+            }
+        }
+    }
 
     #[test]
     fn test_locally_administered() {
@@ -95,6 +546,91 @@ mod tests {
         assert!(!is_locally_administered("00:1C:B3:00:00:00"));
     }
 
+    #[test]
+    fn test_oui_registry_csv_is_parsed_and_stored() {
+        let csv = "Registry,Assignment,Organization Name,Organization Address\n\
+                   MA-L,0CA1B2,\"Example Widgets, Inc.\",1 Main St\n\
+                   MA-L,ZZZZZZ,Bogus,Nowhere\n";
+        let entries = parse_oui_csv(csv.as_bytes()).unwrap();
+        assert_eq!(
+            entries,
+            vec![("0CA1B2".to_string(), "Example Widgets, Inc.".to_string())]
+        );
+        assert!(parse_oui_csv("a,b\n1,2\n".as_bytes()).is_err());
+
+        // Exercised on its own instance: installing it would change lookups
+        // for every other test running in the process
+        let db = crate::database::Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
+        let source = IeeeVendorSource::from_csv(OUI_REGISTRY_URL, csv.as_bytes()).unwrap();
+        source.persist(&conn).unwrap();
+        let stored = IeeeVendorSource::from_database(&conn, OUI_REGISTRY_URL).unwrap();
+        assert_eq!(stored.entries(), 1);
+        assert_eq!(
+            stored.lookup("0c:a1:b2:00:11:22").as_deref(),
+            Some("Example Widgets, Inc.")
+        );
+    }
+
+    #[test]
+    fn test_csv_vendor_source_for_offline_updates() {
+        let path = std::env::temp_dir().join(format!("oui-{}.csv", std::process::id()));
+        std::fs::write(&path, "Prefix,Vendor\n0c:a1:c3,Offline Widgets\n").unwrap();
+        let path = path.to_string_lossy().to_string();
+
+        let settings: VendorSourceSettings =
+            serde_json::from_str(&format!(r#"{{"kind":"csv","path":{:?}}}"#, path)).unwrap();
+        assert_eq!(settings, VendorSourceSettings::Csv { path: path.clone() });
+
+        let db = crate::database::Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
+        let source = open_vendor_source(&conn, &settings).unwrap();
+        assert_eq!(source.entries(), 1);
+        assert_eq!(source.origin(), path);
+        assert_eq!(
+            source.lookup("0C-A1-C3-00-00-01").as_deref(),
+            Some("Offline Widgets")
+        );
+        std::fs::remove_file(&path).unwrap();
+
+        // A missing file is reported rather than silently falling back
+        assert!(open_vendor_source(&conn, &settings).is_err());
+        assert!(fetch_vendor_source(&VendorSourceSettings::Embedded).is_err());
+        assert_eq!(
+            load_vendor_source_settings(&conn).unwrap(),
+            VendorSourceSettings::default()
+        );
+    }
+
+    #[test]
+    fn test_vendor_overrides_take_precedence() {
+        let _overrides = OverridesGuard::new();
+        let db = crate::database::Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
+
+        assert_eq!(normalize_vendor_pattern("0e-ab-cd").unwrap(), "0EABCD");
+        assert!(normalize_vendor_pattern("0e:ab").is_err());
+        assert!(normalize_vendor_pattern("zz:ab:cd").is_err());
+
+        add_vendor_override(&conn, "0e:ab:cd", "Lab Switches").unwrap();
+        add_vendor_override(&conn, "0e:ab:cd:00:00:01", "Bench PSU").unwrap();
+
+        let info = lookup_vendor_info("0e:ab:cd:12:34:56");
+        assert_eq!(info.vendor.as_deref(), Some("Lab Switches"));
+        assert!(info.is_randomized);
+        assert_eq!(
+            lookup_vendor("0E-AB-CD-00-00-01").as_deref(),
+            Some("Bench PSU")
+        );
+
+        assert!(remove_vendor_override(&conn, "0EABCD").unwrap());
+        assert_eq!(
+            lookup_vendor("0e:ab:cd:12:34:56").as_deref(),
+            Some("Private Device (Randomized MAC)")
+        );
+        assert_eq!(load_vendor_overrides(&conn).unwrap().len(), 1);
+    }
+
     #[test]
     fn test_lookup_vendor() {
         let result = lookup_vendor("00:1C:B3:00:00:00");
@@ -0,0 +1,231 @@
+//! WAN / public IP detection
+//!
+//! Asks an external service for the address the scan host is seen from, so
+//! results and reports record which external network a scan was taken from.
+//! ISP and ASN lookups are optional and go to a user-configured endpoint.
+
+use anyhow::{bail, Context, Result};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+use std::net::IpAddr;
+
+use super::geoip::geoip_lookup;
+use crate::config::{WAN_IP_ENDPOINT, WAN_LOOKUP_MAX_BYTES, WAN_LOOKUP_TIMEOUT};
+use crate::database::queries::{self, SETTING_WAN_LOOKUP};
+use crate::models::WanInfo;
+
+/// Placeholder replaced with the public IP in `isp_endpoint`
+pub const WAN_IP_PLACEHOLDER: &str = "{ip}";
+
+/// Where public IP and ISP information is looked up
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WanLookupSettings {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Returns the caller's address as plain text or JSON with an `ip` field
+    #[serde(default = "default_ip_endpoint")]
+    pub ip_endpoint: String,
+    /// JSON ISP/ASN lookup, e.g. `https://ipinfo.io/{ip}/json` (disabled if unset)
+    #[serde(default)]
+    pub isp_endpoint: Option<String>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_ip_endpoint() -> String {
+    WAN_IP_ENDPOINT.to_string()
+}
+
+impl Default for WanLookupSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            ip_endpoint: default_ip_endpoint(),
+            isp_endpoint: None,
+        }
+    }
+}
+
+impl WanLookupSettings {
+    fn validate(&self) -> Result<()> {
+        let endpoints = std::iter::once(&self.ip_endpoint).chain(self.isp_endpoint.as_ref());
+        for endpoint in endpoints {
+            if !(endpoint.starts_with("https://") || endpoint.starts_with("http://")) {
+                bail!("WAN lookup endpoint must start with http:// or https://");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Load WAN lookup settings (defaults if none are saved)
+pub fn load_wan_settings(conn: &Connection) -> Result<WanLookupSettings> {
+    Ok(queries::get_json_setting(conn, SETTING_WAN_LOOKUP)?.unwrap_or_default())
+}
+
+/// Validate and persist WAN lookup settings
+pub fn save_wan_settings(conn: &Connection, settings: &WanLookupSettings) -> Result<()> {
+    settings.validate()?;
+    queries::set_json_setting(conn, SETTING_WAN_LOOKUP, settings)
+}
+
+fn fetch(url: &str) -> Result<String> {
+    let response = ureq::get(url)
+        .timeout(WAN_LOOKUP_TIMEOUT)
+        .call()
+        .with_context(|| format!("Failed to query {}", url))?;
+
+    let mut body = String::new();
+    response
+        .into_reader()
+        .take(WAN_LOOKUP_MAX_BYTES)
+        .read_to_string(&mut body)
+        .with_context(|| format!("Failed to read response from {}", url))?;
+    Ok(body)
+}
+
+/// Extract the address from a plain-text or `{"ip": ...}` response
+pub fn parse_public_ip(body: &str) -> Option<IpAddr> {
+    let body = body.trim();
+    body.parse().ok().or_else(|| {
+        serde_json::from_str::<serde_json::Value>(body)
+            .ok()?
+            .get("ip")?
+            .as_str()?
+            .parse()
+            .ok()
+    })
+}
+
+/// Split an `AS15169 Google LLC` style string into ASN and name
+fn split_asn(value: &str) -> (Option<String>, Option<String>) {
+    let value = value.trim();
+    match value.split_once(' ') {
+        Some((asn, name)) if is_asn(asn) => (Some(asn.to_string()), Some(name.trim().to_string())),
+        _ if is_asn(value) => (Some(value.to_string()), None),
+        _ => (None, Some(value.to_string()).filter(|v| !v.is_empty())),
+    }
+}
+
+fn is_asn(value: &str) -> bool {
+    value
+        .strip_prefix("AS")
+        .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Read ISP name and ASN from a JSON lookup response
+///
+/// Understands the common shapes: `isp`/`as` (ip-api), `org` (ipinfo) and
+/// `asn` as a string or number.
+pub fn parse_isp_response(body: &str) -> Result<(Option<String>, Option<String>)> {
+    let json: serde_json::Value = serde_json::from_str(body).context("Malformed ISP response")?;
+    let field = |name: &str| {
+        json.get(name)
+            .and_then(|v| match v {
+                serde_json::Value::String(s) => Some(s.trim().to_string()),
+                serde_json::Value::Number(n) => Some(format!("AS{}", n)),
+                _ => None,
+            })
+            .filter(|v| !v.is_empty())
+    };
+
+    let (org_asn, org_name) = field("org").map(|org| split_asn(&org)).unwrap_or_default();
+    let (as_asn, as_name) = field("as").map(|a| split_asn(&a)).unwrap_or_default();
+
+    let asn = field("asn")
+        .map(|asn| split_asn(&asn).0.unwrap_or(asn))
+        .or(as_asn)
+        .or(org_asn);
+    let isp = field("isp").or(org_name).or(as_name);
+    Ok((isp, asn))
+}
+
+/// Look up the public IP, its reverse DNS name and optionally the ISP/ASN
+///
+/// Blocking; call from `spawn_blocking` in async code. A failed ISP lookup
+/// is logged and leaves those fields empty; without an ISP endpoint the
+/// local GeoIP ASN database is used when available.
+pub fn wan_info(settings: &WanLookupSettings) -> Result<WanInfo> {
+    settings.validate()?;
+
+    let body = fetch(&settings.ip_endpoint)?;
+    let ip = parse_public_ip(&body)
+        .with_context(|| format!("{} did not return an IP address", settings.ip_endpoint))?;
+
+    let reverse_dns = dns_lookup::lookup_addr(&ip)
+        .ok()
+        .filter(|name| *name != ip.to_string());
+
+    let mut info = WanInfo {
+        public_ip: ip.to_string(),
+        reverse_dns,
+        isp: None,
+        asn: None,
+    };
+
+    if let Some(endpoint) = &settings.isp_endpoint {
+        let url = endpoint.replace(WAN_IP_PLACEHOLDER, &info.public_ip);
+        match fetch(&url).and_then(|body| parse_isp_response(&body)) {
+            Ok((isp, asn)) => {
+                info.isp = isp;
+                info.asn = asn;
+            }
+            Err(e) => tracing::warn!("ISP lookup failed: {}", e),
+        }
+    }
+
+    // Fall back to the local ASN database, if one is configured
+    if info.asn.is_none() {
+        if let Some(geo) = geoip_lookup(ip) {
+            info.asn = geo.asn;
+            info.isp = info.isp.or(geo.as_org);
+        }
+    }
+
+    Ok(info)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wan_response_parsing() {
+        assert_eq!(
+            parse_public_ip("203.0.113.7\n"),
+            Some("203.0.113.7".parse().unwrap())
+        );
+        assert_eq!(
+            parse_public_ip(r#"{"ip":"2001:db8::1"}"#),
+            Some("2001:db8::1".parse().unwrap())
+        );
+        assert!(parse_public_ip("<html>").is_none());
+
+        // ipinfo.io
+        let (isp, asn) =
+            parse_isp_response(r#"{"ip":"8.8.8.8","org":"AS15169 Google LLC"}"#).unwrap();
+        assert_eq!(isp.as_deref(), Some("Google LLC"));
+        assert_eq!(asn.as_deref(), Some("AS15169"));
+
+        // ip-api.com
+        let (isp, asn) = parse_isp_response(
+            r#"{"isp":"Comcast Cable","as":"AS7922 Comcast Cable Communications, LLC"}"#,
+        )
+        .unwrap();
+        assert_eq!(isp.as_deref(), Some("Comcast Cable"));
+        assert_eq!(asn.as_deref(), Some("AS7922"));
+
+        let (isp, asn) = parse_isp_response(r#"{"asn":3320}"#).unwrap();
+        assert_eq!((isp, asn.as_deref()), (None, Some("AS3320")));
+        assert!(parse_isp_response("not json").is_err());
+
+        let invalid = WanLookupSettings {
+            isp_endpoint: Some("ftp://example.com".to_string()),
+            ..Default::default()
+        };
+        assert!(invalid.validate().is_err());
+    }
+}
@@ -0,0 +1,273 @@
+//! Chat notification channels
+//!
+//! Posts alerts to Slack, Discord or Microsoft Teams incoming webhooks,
+//! formatted the way each platform renders best: Slack blocks, a Discord
+//! embed, a Teams Adaptive Card. Like any channel, one can be named in an
+//! alert rule's `notify` list, e.g. to send "device offline" alerts to the
+//! ops channel only.
+
+use anyhow::{bail, Result};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use super::webhook::post_json;
+use super::{Notification, Notifier};
+use crate::alerts::AlertSeverity;
+use crate::config::{MAX_WEBHOOK_RETRIES, WEBHOOK_RETRIES};
+
+/// Chat service a channel posts to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChatPlatform {
+    Slack,
+    Discord,
+    Teams,
+}
+
+/// A chat incoming webhook alerts are posted to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChatSettings {
+    /// Channel name, as listed in alert rules
+    pub name: String,
+    pub platform: ChatPlatform,
+    /// Incoming webhook URL from the platform
+    pub url: String,
+    /// Attempts after a failed delivery
+    #[serde(default = "default_retries")]
+    pub retries: u32,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_retries() -> u32 {
+    WEBHOOK_RETRIES
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl ChatSettings {
+    pub fn validate(&self) -> Result<()> {
+        if self.name.trim().is_empty() {
+            bail!("Chat channel name cannot be empty");
+        }
+        if !self.url.starts_with("https://") {
+            bail!("Chat webhook URL must start with https://");
+        }
+        if self.retries > MAX_WEBHOOK_RETRIES {
+            bail!(
+                "Chat webhook retries must be at most {}",
+                MAX_WEBHOOK_RETRIES
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Emoji marking a severity in message titles
+fn severity_icon(severity: AlertSeverity) -> &'static str {
+    match severity {
+        AlertSeverity::Low => "\u{2139}\u{fe0f}",
+        AlertSeverity::Medium => "\u{26a0}\u{fe0f}",
+        AlertSeverity::High => "\u{1f7e0}",
+        AlertSeverity::Critical => "\u{1f534}",
+    }
+}
+
+/// Side bar color of a Discord embed
+fn severity_color(severity: AlertSeverity) -> u32 {
+    match severity {
+        AlertSeverity::Low => 0x95a5a6,
+        AlertSeverity::Medium => 0xf1c40f,
+        AlertSeverity::High => 0xe67e22,
+        AlertSeverity::Critical => 0xe74c3c,
+    }
+}
+
+/// Name-value details shown under the message
+fn facts(notification: &Notification) -> Vec<(&'static str, String)> {
+    let mut facts = vec![("Severity", notification.severity.as_str().to_string())];
+    if let Some(ip) = &notification.device_ip {
+        facts.push(("IP", ip.clone()));
+    }
+    if let Some(mac) = &notification.device_mac {
+        facts.push(("MAC", mac.clone()));
+    }
+    facts.push((
+        "Time",
+        notification
+            .created_at
+            .with_timezone(&Local)
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string(),
+    ));
+    facts
+}
+
+/// Posts alerts to one chat webhook
+#[derive(Debug, Clone)]
+pub struct ChatNotifier {
+    settings: ChatSettings,
+}
+
+impl ChatNotifier {
+    pub fn new(settings: ChatSettings) -> Self {
+        Self { settings }
+    }
+
+    /// Request body for an alert, in the platform's message format
+    pub fn payload(&self, notification: &Notification) -> Value {
+        let title = format!(
+            "{} {}",
+            severity_icon(notification.severity),
+            notification.alert_type
+        );
+        let facts = facts(notification);
+        let teams_color = if notification.severity >= AlertSeverity::High {
+            "Attention"
+        } else {
+            "Default"
+        };
+        match self.settings.platform {
+            ChatPlatform::Slack => json!({
+                "text": format!("{}: {}", notification.alert_type, notification.message),
+                "blocks": [
+                    {
+                        "type": "header",
+                        "text": { "type": "plain_text", "text": title }
+                    },
+                    {
+                        "type": "section",
+                        "text": { "type": "mrkdwn", "text": notification.message }
+                    },
+                    {
+                        "type": "context",
+                        "elements": facts
+                            .iter()
+                            .map(|(name, value)| json!({
+                                "type": "mrkdwn",
+                                "text": format!("*{}:* {}", name, value)
+                            }))
+                            .collect::<Vec<_>>()
+                    }
+                ]
+            }),
+            ChatPlatform::Discord => json!({
+                "embeds": [{
+                    "title": title,
+                    "description": notification.message,
+                    "color": severity_color(notification.severity),
+                    "fields": facts
+                        .iter()
+                        .map(|(name, value)| json!({
+                            "name": name,
+                            "value": value,
+                            "inline": true
+                        }))
+                        .collect::<Vec<_>>(),
+                    "timestamp": notification.created_at.to_rfc3339()
+                }]
+            }),
+            ChatPlatform::Teams => json!({
+                "type": "message",
+                "attachments": [{
+                    "contentType": "application/vnd.microsoft.card.adaptive",
+                    "content": {
+                        "$schema": "http://adaptivecards.io/schemas/adaptive-card.json",
+                        "type": "AdaptiveCard",
+                        "version": "1.4",
+                        "body": [
+                            {
+                                "type": "TextBlock",
+                                "text": title,
+                                "weight": "Bolder",
+                                "size": "Medium",
+                                "color": teams_color
+                            },
+                            {
+                                "type": "TextBlock",
+                                "text": notification.message,
+                                "wrap": true
+                            },
+                            {
+                                "type": "FactSet",
+                                "facts": facts
+                                    .iter()
+                                    .map(|(name, value)| json!({
+                                        "title": name,
+                                        "value": value
+                                    }))
+                                    .collect::<Vec<_>>()
+                            }
+                        ]
+                    }
+                }]
+            }),
+        }
+    }
+}
+
+impl Notifier for ChatNotifier {
+    fn name(&self) -> &str {
+        &self.settings.name
+    }
+
+    fn send(&self, notification: &Notification) -> Result<()> {
+        let body = self.payload(notification).to_string();
+        post_json(&self.settings.url, &body, self.settings.retries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    #[test]
+    fn test_chat_payloads() {
+        let notification = Notification {
+            alert_id: Some(3),
+            alert_type: "DeviceWentOffline".to_string(),
+            severity: AlertSeverity::High,
+            message: "NAS (192.168.1.10) went offline".to_string(),
+            device_mac: Some("AA:BB:CC:00:00:10".to_string()),
+            device_ip: Some("192.168.1.10".to_string()),
+            created_at: Utc::now(),
+            channels: vec!["ops".to_string()],
+        };
+        let mut settings = ChatSettings {
+            name: "ops".to_string(),
+            platform: ChatPlatform::Slack,
+            url: "https://hooks.slack.com/services/T0/B0/x".to_string(),
+            retries: 3,
+            enabled: true,
+        };
+        settings.validate().unwrap();
+
+        let slack = ChatNotifier::new(settings.clone()).payload(&notification);
+        assert_eq!(slack["blocks"][1]["text"]["text"], notification.message);
+        assert_eq!(
+            slack["blocks"][2]["elements"][1]["text"],
+            "*IP:* 192.168.1.10"
+        );
+
+        settings.platform = ChatPlatform::Discord;
+        let discord = ChatNotifier::new(settings.clone()).payload(&notification);
+        assert_eq!(discord["embeds"][0]["color"], 0xe67e22);
+        assert_eq!(
+            discord["embeds"][0]["fields"][2]["value"],
+            "AA:BB:CC:00:00:10"
+        );
+
+        settings.platform = ChatPlatform::Teams;
+        let teams = ChatNotifier::new(settings.clone()).payload(&notification);
+        let card = &teams["attachments"][0]["content"];
+        assert_eq!(card["type"], "AdaptiveCard");
+        assert_eq!(card["body"][0]["color"], "Attention");
+
+        settings.url = "http://hooks.slack.com/services/T0/B0/x".to_string();
+        assert!(settings.validate().is_err());
+    }
+}
@@ -0,0 +1,254 @@
+//! Desktop notifications
+//!
+//! Decides which monitor events pop up an OS notification and what it says.
+//! The desktop app shows the toast; clicking it brings the app forward on
+//! the device the event is about. Each severity is opted in separately, so
+//! users can keep, say, new devices and ARP anomalies while muting the
+//! medium-severity churn of devices going offline.
+
+use serde::{Deserialize, Serialize};
+
+use crate::alerts::AlertSeverity;
+use crate::monitor::NetworkEvent;
+
+/// Which events raise a desktop notification
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DesktopSettings {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Severities that notify
+    #[serde(default = "default_severities")]
+    pub severities: Vec<AlertSeverity>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_severities() -> Vec<AlertSeverity> {
+    vec![
+        AlertSeverity::Medium,
+        AlertSeverity::High,
+        AlertSeverity::Critical,
+    ]
+}
+
+impl Default for DesktopSettings {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            severities: default_severities(),
+        }
+    }
+}
+
+/// A desktop notification for a monitor event
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DesktopNotice {
+    pub title: String,
+    pub body: String,
+    pub severity: AlertSeverity,
+    /// Event kind, e.g. `NewDeviceDiscovered`
+    pub kind: String,
+    /// Device to show when the notification is clicked
+    pub device_mac: Option<String>,
+    pub device_ip: Option<String>,
+}
+
+fn device_name(ip: &str, hostname: &Option<String>) -> String {
+    match hostname {
+        Some(hostname) => format!("{} ({})", hostname, ip),
+        None => ip.to_string(),
+    }
+}
+
+impl DesktopSettings {
+    /// The notification for `event`, if its severity is opted in
+    ///
+    /// Lifecycle and progress events never notify.
+    pub fn notice(&self, event: &NetworkEvent) -> Option<DesktopNotice> {
+        let severity = event.severity();
+        if !self.enabled || !self.severities.contains(&severity) {
+            return None;
+        }
+
+        let (title, body, ip) = match event {
+            NetworkEvent::NewDeviceDiscovered {
+                ip,
+                hostname,
+                vendor,
+                device_type,
+                ..
+            } => (
+                "New device on the network".to_string(),
+                format!(
+                    "{}, {}",
+                    device_name(ip, hostname),
+                    vendor.as_deref().unwrap_or(device_type)
+                ),
+                Some(ip),
+            ),
+            NetworkEvent::DeviceWentOffline {
+                last_ip, hostname, ..
+            } => (
+                "Device went offline".to_string(),
+                device_name(last_ip, hostname),
+                Some(last_ip),
+            ),
+            NetworkEvent::DeviceCameOnline { ip, hostname, .. } => (
+                "Device came online".to_string(),
+                device_name(ip, hostname),
+                Some(ip),
+            ),
+            NetworkEvent::DeviceFlapping {
+                ip,
+                changes,
+                window_secs,
+                ..
+            } => (
+                "Device is flapping".to_string(),
+                format!(
+                    "{} changed state {} times in {} minutes",
+                    ip,
+                    changes,
+                    window_secs / 60
+                ),
+                Some(ip),
+            ),
+            NetworkEvent::DeviceIpChanged { old_ip, new_ip, .. } => (
+                "Device changed address".to_string(),
+                format!("{} is now {}", old_ip, new_ip),
+                Some(new_ip),
+            ),
+            NetworkEvent::ArpAddressTakeover {
+                ip,
+                previous_mac,
+                new_mac,
+                ..
+            } => (
+                "Possible ARP spoofing".to_string(),
+                format!("{} moved from {} to {}", ip, previous_mac, new_mac),
+                Some(ip),
+            ),
+            NetworkEvent::IpConflict { ip, macs } => (
+                "IP address conflict".to_string(),
+                format!("{} is claimed by {}", ip, macs.join(", ")),
+                Some(ip),
+            ),
+            NetworkEvent::SharedMacDetected { mac, ips } => (
+                "Possible MAC spoofing".to_string(),
+                format!("{} answers from {}", mac, ips.join(" and ")),
+                ips.first(),
+            ),
+            NetworkEvent::LatencyDegraded {
+                baseline_ms,
+                current_ms,
+                ..
+            } => (
+                "Device is slow".to_string(),
+                format!("Ping {} ms, usually {} ms", current_ms, baseline_ms),
+                None,
+            ),
+            NetworkEvent::ArpStormDetected {
+                ip,
+                request_count,
+                window_secs,
+                ..
+            } => (
+                "ARP storm".to_string(),
+                format!(
+                    "{} sent {} ARP requests in {} s",
+                    ip, request_count, window_secs
+                ),
+                Some(ip),
+            ),
+            NetworkEvent::InternetDegraded { reason, .. } => {
+                ("Internet degraded".to_string(), reason.clone(), None)
+            }
+            NetworkEvent::InternetDown { reason } => {
+                ("Internet down".to_string(), reason.clone(), None)
+            }
+            NetworkEvent::UnknownDevicePresent { ip, hostname, .. } => (
+                "Unknown device on the network".to_string(),
+                format!("{} is not in the baseline", device_name(ip, hostname)),
+                Some(ip),
+            ),
+            NetworkEvent::ExpectedDeviceMissing { ip, hostname, mac } => (
+                "Expected device missing".to_string(),
+                device_name(ip.as_deref().unwrap_or(mac), hostname),
+                ip.as_ref(),
+            ),
+            NetworkEvent::UnexpectedPortOpen { ip, port, .. } => (
+                "Unexpected open port".to_string(),
+                format!("{} has port {} open", ip, port),
+                Some(ip),
+            ),
+            NetworkEvent::MonitoringError { message } => {
+                ("Monitoring error".to_string(), message.clone(), None)
+            }
+            _ => return None,
+        };
+
+        let device_mac = match event {
+            NetworkEvent::ArpAddressTakeover { new_mac, .. } => Some(new_mac.clone()),
+            _ => event.device_macs().first().map(|mac| mac.to_string()),
+        };
+        Some(DesktopNotice {
+            title,
+            body,
+            severity,
+            kind: event.kind().to_string(),
+            device_mac,
+            device_ip: ip.cloned(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_desktop_notice() {
+        let joined = NetworkEvent::NewDeviceDiscovered {
+            ip: "192.168.1.50".to_string(),
+            mac: "AA:BB:CC:00:00:50".to_string(),
+            hostname: None,
+            device_type: "UNKNOWN".to_string(),
+            vendor: Some("Espressif".to_string()),
+            risk_score: 20,
+            network: None,
+        };
+        let settings = DesktopSettings::default();
+        let notice = settings.notice(&joined).unwrap();
+        assert_eq!(notice.title, "New device on the network");
+        assert_eq!(notice.body, "192.168.1.50, Espressif");
+        assert_eq!(notice.device_mac.as_deref(), Some("AA:BB:CC:00:00:50"));
+        assert_eq!(notice.device_ip.as_deref(), Some("192.168.1.50"));
+
+        // Lifecycle events never notify
+        assert!(settings.notice(&NetworkEvent::MonitoringStopped).is_none());
+
+        // Only opted-in severities
+        let high_only = DesktopSettings {
+            enabled: true,
+            severities: vec![AlertSeverity::High],
+        };
+        assert!(high_only.notice(&joined).is_none());
+        let takeover = NetworkEvent::ArpAddressTakeover {
+            ip: "192.168.1.1".to_string(),
+            previous_mac: "AA:BB:CC:00:00:01".to_string(),
+            new_mac: "AA:BB:CC:00:00:66".to_string(),
+            gratuitous: true,
+        };
+        assert_eq!(
+            high_only.notice(&takeover).unwrap().device_mac.as_deref(),
+            Some("AA:BB:CC:00:00:66")
+        );
+        let disabled = DesktopSettings {
+            enabled: false,
+            ..DesktopSettings::default()
+        };
+        assert!(disabled.notice(&takeover).is_none());
+    }
+}
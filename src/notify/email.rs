@@ -0,0 +1,359 @@
+//! Email notification channel
+//!
+//! Sends alerts through an SMTP server, upgraded with STARTTLS unless turned
+//! off for a local relay. Each channel picks which alerts are mailed right
+//! away by severity and type, and can also mail a daily digest of the last
+//! day's alerts. The SMTP password is encrypted with the machine key before
+//! it is saved, so it is never stored in plain text.
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Duration, Local, NaiveDate, Timelike, Utc};
+use lettre::message::header::ContentType;
+use lettre::message::{Mailbox, Message};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{SmtpTransport, Transport};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::{load_notification_settings, open_secret, seal_secret, Notification, Notifier};
+use crate::alerts::AlertSeverity;
+use crate::config::{DEFAULT_DIGEST_HOUR, DEFAULT_SMTP_PORT, SMTP_TIMEOUT};
+use crate::database::queries::{self, SETTING_DIGESTS_SENT};
+use crate::database::{AlertQuery, AlertRecord, AlertSeverity as StoredSeverity};
+
+/// An SMTP account alerts are mailed from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EmailSettings {
+    /// Channel name, as listed in alert rules
+    pub name: String,
+    pub host: String,
+    #[serde(default = "default_port")]
+    pub port: u16,
+    /// Upgrade the connection with STARTTLS (off only for a trusted relay)
+    #[serde(default = "default_starttls")]
+    pub starttls: bool,
+    #[serde(default)]
+    pub username: Option<String>,
+    /// New password as entered; encrypted on save and never serialized
+    #[serde(default, skip_serializing)]
+    pub password: Option<String>,
+    /// Saved password, encrypted with the machine key
+    #[serde(default)]
+    pub encrypted_password: Option<String>,
+    pub from: String,
+    pub to: Vec<String>,
+    /// Least severe alert mailed as it happens
+    #[serde(default = "default_min_severity")]
+    pub min_severity: AlertSeverity,
+    /// Alert types mailed (e.g. `HIGH_RISK`), compared case-insensitively;
+    /// empty for all
+    #[serde(default)]
+    pub alert_types: Vec<String>,
+    /// Also mail a summary of the last day's alerts once a day
+    #[serde(default)]
+    pub daily_digest: bool,
+    /// Local hour the digest is sent
+    #[serde(default = "default_digest_hour")]
+    pub digest_hour: u32,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_port() -> u16 {
+    DEFAULT_SMTP_PORT
+}
+
+fn default_starttls() -> bool {
+    true
+}
+
+fn default_min_severity() -> AlertSeverity {
+    AlertSeverity::Critical
+}
+
+fn default_digest_hour() -> u32 {
+    DEFAULT_DIGEST_HOUR
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl EmailSettings {
+    pub fn validate(&self) -> Result<()> {
+        if self.name.trim().is_empty() {
+            bail!("Email channel name cannot be empty");
+        }
+        if self.host.trim().is_empty() {
+            bail!("SMTP server cannot be empty");
+        }
+        self.from
+            .parse::<Mailbox>()
+            .with_context(|| format!("Invalid sender address: {}", self.from))?;
+        if self.to.is_empty() {
+            bail!("Email channel needs at least one recipient");
+        }
+        for to in &self.to {
+            to.parse::<Mailbox>()
+                .with_context(|| format!("Invalid recipient address: {}", to))?;
+        }
+        if self.digest_hour > 23 {
+            bail!("Digest hour must be between 0 and 23");
+        }
+        Ok(())
+    }
+
+    /// Replace a newly entered password with its encrypted form
+    ///
+    /// An empty password clears the saved one; none keeps it.
+    pub fn seal_password(&mut self) -> Result<()> {
+        seal_secret(&mut self.password, &mut self.encrypted_password)
+    }
+
+    /// Whether an alert of this type is mailed by this channel
+    fn wants_type(&self, alert_type: &str) -> bool {
+        self.alert_types.is_empty()
+            || self
+                .alert_types
+                .iter()
+                .any(|wanted| wanted.trim().eq_ignore_ascii_case(alert_type))
+    }
+}
+
+fn runtime_severity(severity: &StoredSeverity) -> AlertSeverity {
+    match severity {
+        StoredSeverity::Info => AlertSeverity::Low,
+        StoredSeverity::Warning => AlertSeverity::Medium,
+        StoredSeverity::Error => AlertSeverity::High,
+        StoredSeverity::Critical => AlertSeverity::Critical,
+    }
+}
+
+/// Mails alerts through one SMTP account
+#[derive(Debug, Clone)]
+pub struct EmailNotifier {
+    settings: EmailSettings,
+}
+
+impl EmailNotifier {
+    pub fn new(settings: EmailSettings) -> Self {
+        Self { settings }
+    }
+
+    fn transport(&self) -> Result<SmtpTransport> {
+        let host = self.settings.host.trim();
+        let builder = if self.settings.starttls {
+            SmtpTransport::starttls_relay(host)
+                .with_context(|| format!("Invalid SMTP server: {}", host))?
+        } else {
+            SmtpTransport::builder_dangerous(host)
+        };
+        let mut builder = builder.port(self.settings.port).timeout(Some(SMTP_TIMEOUT));
+        if let Some(username) = &self.settings.username {
+            let password = open_secret(&self.settings.password, &self.settings.encrypted_password)?;
+            builder = builder.credentials(Credentials::new(username.clone(), password));
+        }
+        Ok(builder.build())
+    }
+
+    /// Mail a plain-text message to every recipient
+    pub fn send_mail(&self, subject: &str, body: String) -> Result<()> {
+        let mut message = Message::builder()
+            .from(self.settings.from.parse::<Mailbox>()?)
+            .subject(subject)
+            .header(ContentType::TEXT_PLAIN);
+        for to in &self.settings.to {
+            message = message.to(to.parse::<Mailbox>()?);
+        }
+        let message = message.body(body).context("Failed to build email")?;
+        self.transport()?
+            .send(&message)
+            .with_context(|| format!("Failed to send email via {}", self.settings.host))?;
+        Ok(())
+    }
+
+    /// Mail a summary of `alerts`; nothing is sent if none are wanted
+    pub fn send_digest(&self, alerts: &[AlertRecord], day: NaiveDate) -> Result<bool> {
+        let alerts: Vec<&AlertRecord> = alerts
+            .iter()
+            .filter(|alert| self.settings.wants_type(&alert.alert_type_raw))
+            .collect();
+        if alerts.is_empty() {
+            return Ok(false);
+        }
+        self.send_mail(
+            &format!("Network alert digest for {}: {} alerts", day, alerts.len()),
+            digest_body(&alerts),
+        )?;
+        Ok(true)
+    }
+}
+
+fn alert_body(notification: &Notification) -> String {
+    let mut body = format!(
+        "{}\n\nType: {}\nSeverity: {}\n",
+        notification.message,
+        notification.alert_type,
+        notification.severity.as_str()
+    );
+    if let Some(ip) = &notification.device_ip {
+        body.push_str(&format!("Device IP: {}\n", ip));
+    }
+    if let Some(mac) = &notification.device_mac {
+        body.push_str(&format!("Device MAC: {}\n", mac));
+    }
+    body.push_str(&format!(
+        "Time: {}\n",
+        notification
+            .created_at
+            .with_timezone(&Local)
+            .format("%Y-%m-%d %H:%M:%S")
+    ));
+    body
+}
+
+fn digest_body(alerts: &[&AlertRecord]) -> String {
+    let mut body = String::new();
+    for alert in alerts {
+        let device = alert
+            .device_ip
+            .as_deref()
+            .or(alert.device_mac.as_deref())
+            .unwrap_or("-");
+        body.push_str(&format!(
+            "{}  {:<8}  {:<24}  {:<17}  {}",
+            alert.created_at.with_timezone(&Local).format("%m-%d %H:%M"),
+            runtime_severity(&alert.severity).as_str(),
+            alert.alert_type_raw,
+            device,
+            alert.message
+        ));
+        if alert.occurrences > 1 {
+            body.push_str(&format!(" (x{})", alert.occurrences));
+        }
+        body.push('\n');
+    }
+    body
+}
+
+impl Notifier for EmailNotifier {
+    fn name(&self) -> &str {
+        &self.settings.name
+    }
+
+    fn accepts(&self, notification: &Notification, _min_severity: AlertSeverity) -> bool {
+        notification.severity >= self.settings.min_severity
+            && self.settings.wants_type(&notification.alert_type)
+    }
+
+    fn send(&self, notification: &Notification) -> Result<()> {
+        self.send_mail(
+            &format!(
+                "[{}] {}: {}",
+                notification.severity.as_str(),
+                notification.alert_type,
+                notification.message
+            ),
+            alert_body(notification),
+        )
+    }
+}
+
+/// Mail the daily digest of every channel due at `now` in the background
+///
+/// A channel is due once its digest hour has passed on a day it has not yet
+/// been sent. Returns how many digests were started.
+pub fn send_due_digests(conn: &Connection, now: DateTime<Local>) -> Result<usize> {
+    let settings = load_notification_settings(conn)?;
+    let today = now.date_naive();
+    let mut sent: HashMap<String, NaiveDate> =
+        queries::get_json_setting(conn, SETTING_DIGESTS_SENT)?.unwrap_or_default();
+
+    let due: Vec<EmailSettings> = settings
+        .emails
+        .into_iter()
+        .filter(|email| email.enabled && email.daily_digest && now.hour() >= email.digest_hour)
+        .filter(|email| sent.get(&email.name) != Some(&today))
+        .collect();
+    if due.is_empty() {
+        return Ok(0);
+    }
+
+    let alerts = queries::query_alerts(
+        conn,
+        &AlertQuery {
+            since: Some(now.with_timezone(&Utc) - Duration::days(1)),
+            ..Default::default()
+        },
+    )?
+    .items;
+    for email in &due {
+        sent.insert(email.name.clone(), today);
+    }
+    queries::set_json_setting(conn, SETTING_DIGESTS_SENT, &sent)?;
+
+    let count = due.len();
+    std::thread::spawn(move || {
+        for email in due {
+            let name = email.name.clone();
+            if let Err(e) = EmailNotifier::new(email).send_digest(&alerts, today) {
+                tracing::warn!("[NOTIFY] Failed to send alert digest to {}: {:#}", name, e);
+            }
+        }
+    });
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alerts::{Alert, AlertType};
+    use crate::database::encryption::decrypt_secret;
+
+    #[test]
+    fn test_email_settings() {
+        let mut settings = EmailSettings {
+            name: "admin".to_string(),
+            host: "smtp.example.com".to_string(),
+            port: DEFAULT_SMTP_PORT,
+            starttls: true,
+            username: Some("alerts@example.com".to_string()),
+            password: Some("hunter2".to_string()),
+            encrypted_password: None,
+            from: "Network Monitor <alerts@example.com>".to_string(),
+            to: vec!["admin@example.com".to_string()],
+            min_severity: AlertSeverity::High,
+            alert_types: vec!["high_risk".to_string()],
+            daily_digest: true,
+            digest_hour: 8,
+            enabled: true,
+        };
+        settings.validate().unwrap();
+
+        // The password is never written out in plain text
+        let json = serde_json::to_string(&settings).unwrap();
+        assert!(!json.contains("hunter2"));
+        if settings.seal_password().is_ok() {
+            assert_eq!(settings.password, None);
+            let encrypted = settings.encrypted_password.clone().unwrap();
+            assert_eq!(decrypt_secret(&encrypted).unwrap(), "hunter2");
+        }
+
+        // Filtered by the channel's own severity and types
+        let notifier = EmailNotifier::new(settings.clone());
+        let risky =
+            Notification::from_alert(&Alert::new(AlertType::HighRiskDetected, "risk"), None);
+        let mut online =
+            Notification::from_alert(&Alert::new(AlertType::DeviceCameOnline, "on"), None);
+        online.severity = AlertSeverity::Critical;
+        assert!(notifier.accepts(&risky, AlertSeverity::Critical));
+        assert!(!notifier.accepts(&online, AlertSeverity::Low));
+
+        settings.to = vec!["not an address".to_string()];
+        assert!(settings.validate().is_err());
+        settings.to = vec!["admin@example.com".to_string()];
+        settings.digest_hour = 24;
+        assert!(settings.validate().is_err());
+    }
+}
@@ -0,0 +1,418 @@
+//! Alert notifications
+//!
+//! Sends newly stored alerts to channels outside the app, so they are seen
+//! even when the desktop UI is closed. Each kind of channel implements
+//! [`Notifier`]; the configured channels are saved in settings. Alerts at or
+//! above the minimum severity go to every enabled channel (email channels
+//! apply their own filter), while alerts raised by a rule that names
+//! channels go to those only. Delivery happens on a background thread, so a
+//! slow endpoint never holds up scanning. Desktop notifications for monitor
+//! events are configured here too, and shown by the desktop app.
+
+pub mod chat;
+pub mod desktop;
+pub mod email;
+pub mod mqtt;
+pub mod syslog;
+pub mod webhook;
+
+pub use chat::{ChatNotifier, ChatPlatform, ChatSettings};
+pub use desktop::{DesktopNotice, DesktopSettings};
+pub use email::{send_due_digests, EmailNotifier, EmailSettings};
+pub use mqtt::{MqttNotifier, MqttPublisher, MqttSettings};
+pub use syslog::{SyslogForwarder, SyslogNotifier, SyslogSettings, SyslogTransport};
+pub use webhook::{WebhookNotifier, WebhookSettings};
+
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Utc};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use crate::alerts::{Alert, AlertSeverity, RuleMatch};
+use crate::database::encryption::{decrypt_secret, encrypt_secret};
+use crate::database::queries::{self, SETTING_NOTIFICATIONS};
+
+/// A channel alerts can be sent to
+pub trait Notifier: Send + Sync {
+    /// Channel name, as listed in an alert rule's `notify`
+    fn name(&self) -> &str;
+
+    /// Whether an alert not routed by a rule is sent here
+    fn accepts(&self, notification: &Notification, min_severity: AlertSeverity) -> bool {
+        notification.severity >= min_severity
+    }
+
+    /// Deliver one alert
+    fn send(&self, notification: &Notification) -> Result<()>;
+}
+
+/// An alert as sent to notification channels
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Notification {
+    /// ID of the stored alert
+    pub alert_id: Option<i64>,
+    /// Alert type identifier, e.g. `HIGH_RISK`, or the event type a rule matched
+    pub alert_type: String,
+    pub severity: AlertSeverity,
+    pub message: String,
+    pub device_mac: Option<String>,
+    pub device_ip: Option<String>,
+    pub created_at: DateTime<Utc>,
+    /// Channels named by the rule that raised the alert; empty for all
+    #[serde(skip)]
+    pub channels: Vec<String>,
+}
+
+impl Notification {
+    /// Notification for an alert raised by a scan
+    pub fn from_alert(alert: &Alert, alert_id: Option<i64>) -> Self {
+        Self {
+            alert_id,
+            alert_type: alert.alert_type.as_str().to_string(),
+            severity: alert.severity,
+            message: alert.message.clone(),
+            device_mac: alert.device_mac.clone(),
+            device_ip: alert.device_ip.clone(),
+            created_at: Utc::now(),
+            channels: Vec::new(),
+        }
+    }
+
+    /// Notification for an alert raised by a user-defined rule
+    pub fn from_rule_match(rule_match: &RuleMatch) -> Self {
+        Self {
+            alert_id: rule_match.alert_id,
+            alert_type: rule_match.event_type.clone(),
+            severity: rule_match.severity,
+            message: rule_match.message.clone(),
+            device_mac: rule_match.device_mac.clone(),
+            device_ip: rule_match.device_ip.clone(),
+            created_at: Utc::now(),
+            channels: rule_match.notify.clone(),
+        }
+    }
+}
+
+/// Where alerts are sent
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NotificationSettings {
+    /// Least severe alert sent to every channel
+    #[serde(default = "default_min_severity")]
+    pub min_severity: AlertSeverity,
+    #[serde(default)]
+    pub webhooks: Vec<WebhookSettings>,
+    #[serde(default)]
+    pub emails: Vec<EmailSettings>,
+    /// Slack, Discord and Teams webhooks
+    #[serde(default)]
+    pub chats: Vec<ChatSettings>,
+    /// Broker for device state, alerts and Home Assistant discovery
+    #[serde(default)]
+    pub mqtt: Option<MqttSettings>,
+    /// Collector for alerts and monitor events
+    #[serde(default)]
+    pub syslog: Option<SyslogSettings>,
+    /// OS notifications for monitor events
+    #[serde(default)]
+    pub desktop: DesktopSettings,
+}
+
+fn default_min_severity() -> AlertSeverity {
+    AlertSeverity::High
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self {
+            min_severity: default_min_severity(),
+            webhooks: Vec::new(),
+            emails: Vec::new(),
+            chats: Vec::new(),
+            mqtt: None,
+            syslog: None,
+            desktop: DesktopSettings::default(),
+        }
+    }
+}
+
+impl NotificationSettings {
+    fn validate(&self) -> Result<()> {
+        let mut names = HashSet::new();
+        for webhook in &self.webhooks {
+            webhook.validate()?;
+            if !names.insert(webhook.name.trim().to_lowercase()) {
+                bail!("Duplicate notification channel name: {}", webhook.name);
+            }
+        }
+        for email in &self.emails {
+            email.validate()?;
+            if !names.insert(email.name.trim().to_lowercase()) {
+                bail!("Duplicate notification channel name: {}", email.name);
+            }
+        }
+        for chat in &self.chats {
+            chat.validate()?;
+            if !names.insert(chat.name.trim().to_lowercase()) {
+                bail!("Duplicate notification channel name: {}", chat.name);
+            }
+        }
+        if let Some(mqtt) = &self.mqtt {
+            mqtt.validate()?;
+            if !names.insert(mqtt.name.trim().to_lowercase()) {
+                bail!("Duplicate notification channel name: {}", mqtt.name);
+            }
+        }
+        if let Some(syslog) = &self.syslog {
+            syslog.validate()?;
+            if !names.insert(syslog.name.trim().to_lowercase()) {
+                bail!("Duplicate notification channel name: {}", syslog.name);
+            }
+        }
+        Ok(())
+    }
+
+    /// The enabled channels
+    pub fn notifiers(&self) -> Vec<Box<dyn Notifier>> {
+        let webhooks = self
+            .webhooks
+            .iter()
+            .filter(|webhook| webhook.enabled)
+            .map(|webhook| Box::new(WebhookNotifier::new(webhook.clone())) as Box<dyn Notifier>);
+        let emails = self
+            .emails
+            .iter()
+            .filter(|email| email.enabled)
+            .map(|email| Box::new(EmailNotifier::new(email.clone())) as Box<dyn Notifier>);
+        let chats = self
+            .chats
+            .iter()
+            .filter(|chat| chat.enabled)
+            .map(|chat| Box::new(ChatNotifier::new(chat.clone())) as Box<dyn Notifier>);
+        let mqtt = self
+            .mqtt
+            .iter()
+            .filter(|mqtt| mqtt.enabled)
+            .map(|mqtt| Box::new(MqttNotifier::new(mqtt.clone())) as Box<dyn Notifier>);
+        let syslog = self
+            .syslog
+            .iter()
+            .filter(|syslog| syslog.enabled)
+            .map(|syslog| Box::new(SyslogNotifier::new(syslog.clone())) as Box<dyn Notifier>);
+        webhooks
+            .chain(emails)
+            .chain(chats)
+            .chain(mqtt)
+            .chain(syslog)
+            .collect()
+    }
+}
+
+/// Load notification settings (no channels if none are saved)
+pub fn load_notification_settings(conn: &Connection) -> Result<NotificationSettings> {
+    Ok(queries::get_json_setting(conn, SETTING_NOTIFICATIONS)?.unwrap_or_default())
+}
+
+/// Validate and persist notification settings
+///
+/// Newly entered SMTP and MQTT passwords are encrypted before saving. A
+/// channel sent back without one keeps the password saved for it.
+pub fn save_notification_settings(
+    conn: &Connection,
+    settings: &NotificationSettings,
+) -> Result<()> {
+    settings.validate()?;
+    let saved = load_notification_settings(conn)?;
+    let mut settings = settings.clone();
+    for email in &mut settings.emails {
+        if email.password.is_none() && email.encrypted_password.is_none() {
+            email.encrypted_password = saved
+                .emails
+                .iter()
+                .find(|old| old.name == email.name)
+                .and_then(|old| old.encrypted_password.clone());
+        }
+        email.seal_password()?;
+    }
+    if let Some(mqtt) = &mut settings.mqtt {
+        if mqtt.password.is_none() && mqtt.encrypted_password.is_none() {
+            mqtt.encrypted_password = saved.mqtt.and_then(|old| old.encrypted_password);
+        }
+        mqtt.seal_password()?;
+    }
+    queries::set_json_setting(conn, SETTING_NOTIFICATIONS, &settings)
+}
+
+/// Replace a newly entered password with its encrypted form
+///
+/// An empty password clears the saved one; none keeps it.
+pub(crate) fn seal_secret(
+    password: &mut Option<String>,
+    encrypted: &mut Option<String>,
+) -> Result<()> {
+    match password.take() {
+        Some(password) if password.is_empty() => *encrypted = None,
+        Some(password) => {
+            let sealed = encrypt_secret(&password)
+                .map_err(|e| anyhow!("Failed to encrypt password: {}", e))?;
+            *encrypted = Some(sealed);
+        }
+        None => {}
+    }
+    Ok(())
+}
+
+/// The password to log in with: as entered, else the saved one decrypted
+pub(crate) fn open_secret(password: &Option<String>, encrypted: &Option<String>) -> Result<String> {
+    match (password, encrypted) {
+        (Some(password), _) => Ok(password.clone()),
+        (None, Some(encrypted)) => {
+            decrypt_secret(encrypted).map_err(|e| anyhow!("Failed to decrypt password: {}", e))
+        }
+        (None, None) => Ok(String::new()),
+    }
+}
+
+/// Whether `notifier` should receive `notification`
+fn wants(
+    notifier: &dyn Notifier,
+    notification: &Notification,
+    min_severity: AlertSeverity,
+) -> bool {
+    if notification.channels.is_empty() {
+        notifier.accepts(notification, min_severity)
+    } else {
+        notification
+            .channels
+            .iter()
+            .any(|channel| channel.trim().eq_ignore_ascii_case(notifier.name()))
+    }
+}
+
+/// Send each notification to the channels that want it, returning how many
+/// deliveries failed (failures are logged)
+pub fn send_notifications(
+    notifiers: &[Box<dyn Notifier>],
+    min_severity: AlertSeverity,
+    notifications: &[Notification],
+) -> usize {
+    let mut failed = 0;
+    for notification in notifications {
+        for notifier in notifiers {
+            if !wants(notifier.as_ref(), notification, min_severity) {
+                continue;
+            }
+            if let Err(e) = notifier.send(notification) {
+                failed += 1;
+                tracing::warn!(
+                    "[NOTIFY] Failed to send alert to {}: {:#}",
+                    notifier.name(),
+                    e
+                );
+            }
+        }
+    }
+    failed
+}
+
+/// Send newly stored alerts to the configured channels in the background
+pub fn notify_alerts(conn: &Connection, notifications: Vec<Notification>) -> Result<()> {
+    if notifications.is_empty() {
+        return Ok(());
+    }
+    let settings = load_notification_settings(conn)?;
+    let notifiers = settings.notifiers();
+    let min_severity = settings.min_severity;
+    let pending: Vec<Notification> = notifications
+        .into_iter()
+        .filter(|n| notifiers.iter().any(|c| wants(c.as_ref(), n, min_severity)))
+        .collect();
+    if pending.is_empty() {
+        return Ok(());
+    }
+
+    std::thread::spawn(move || send_notifications(&notifiers, min_severity, &pending));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alerts::AlertType;
+    use crate::database::Database;
+    use std::sync::{Arc, Mutex};
+
+    struct Recorder {
+        name: String,
+        sent: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Notifier for Recorder {
+        fn name(&self) -> &str {
+            &self.name
+        }
+
+        fn send(&self, notification: &Notification) -> Result<()> {
+            self.sent
+                .lock()
+                .unwrap()
+                .push(notification.alert_type.clone());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_notification_routing() {
+        let ops = Arc::new(Mutex::new(Vec::new()));
+        let home = Arc::new(Mutex::new(Vec::new()));
+        let recorder = |name: &str, sent: &Arc<Mutex<Vec<String>>>| {
+            Box::new(Recorder {
+                name: name.to_string(),
+                sent: Arc::clone(sent),
+            }) as Box<dyn Notifier>
+        };
+        let notifiers = vec![recorder("ops", &ops), recorder("home", &home)];
+
+        let risky =
+            Notification::from_alert(&Alert::new(AlertType::HighRiskDetected, "risk"), None);
+        let online = Notification::from_alert(&Alert::new(AlertType::DeviceCameOnline, "on"), None);
+        let mut rule = online.clone();
+        rule.alert_type = "DeviceCameOnline".to_string();
+        rule.channels = vec!["HOME".to_string()];
+        assert_eq!(
+            send_notifications(&notifiers, AlertSeverity::High, &[risky, online, rule]),
+            0
+        );
+
+        // High severity goes everywhere, a rule's alert only where it says
+        assert_eq!(*ops.lock().unwrap(), ["HIGH_RISK"]);
+        assert_eq!(*home.lock().unwrap(), ["HIGH_RISK", "DeviceCameOnline"]);
+
+        let db = Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
+        assert_eq!(
+            load_notification_settings(&conn).unwrap(),
+            NotificationSettings::default()
+        );
+        let webhook = WebhookSettings {
+            name: "ntfy".to_string(),
+            url: "https://ntfy.sh/alerts".to_string(),
+            template: None,
+            retries: 3,
+            enabled: true,
+        };
+        let mut settings = NotificationSettings {
+            min_severity: AlertSeverity::Critical,
+            webhooks: vec![webhook.clone()],
+            emails: Vec::new(),
+            chats: Vec::new(),
+            mqtt: None,
+            syslog: None,
+            desktop: DesktopSettings::default(),
+        };
+        save_notification_settings(&conn, &settings).unwrap();
+        assert_eq!(load_notification_settings(&conn).unwrap(), settings);
+        settings.webhooks.push(webhook);
+        assert!(save_notification_settings(&conn, &settings).is_err());
+    }
+}
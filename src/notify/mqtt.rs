@@ -0,0 +1,413 @@
+//! MQTT publishing
+//!
+//! Turns the monitor into a presence sensor for home automation. While
+//! monitoring, each device's state (`online`/`offline`) is published,
+//! retained, to `{prefix}/device/{id}/state` with its address and names in
+//! `{prefix}/device/{id}/attributes`, and new devices are announced on
+//! `{prefix}/event/new_device`. With Home Assistant discovery on, every
+//! device also gets a presence `binary_sensor` config under the discovery
+//! prefix, so it shows up in Home Assistant without any YAML. As a
+//! notification channel, alerts are published to `{prefix}/alert`.
+
+use anyhow::{bail, Context, Result};
+use rumqttc::{
+    Client, Connection, ConnectionError, Event, LastWill, MqttOptions, Outgoing, Packet, QoS,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+use super::{open_secret, seal_secret, Notification, Notifier};
+use crate::config::{DEFAULT_MQTT_PORT, MQTT_KEEP_ALIVE, MQTT_RECONNECT_DELAY, MQTT_TIMEOUT};
+use crate::monitor::NetworkEvent;
+
+/// An MQTT broker to publish to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MqttSettings {
+    /// Channel name, as listed in alert rules
+    #[serde(default = "default_name")]
+    pub name: String,
+    pub host: String,
+    #[serde(default = "default_port")]
+    pub port: u16,
+    #[serde(default = "default_client_id")]
+    pub client_id: String,
+    #[serde(default)]
+    pub username: Option<String>,
+    /// New password as entered; encrypted on save and never serialized
+    #[serde(default, skip_serializing)]
+    pub password: Option<String>,
+    /// Saved password, encrypted with the machine key
+    #[serde(default)]
+    pub encrypted_password: Option<String>,
+    /// Prefix of every topic published
+    #[serde(default = "default_topic_prefix")]
+    pub topic_prefix: String,
+    /// Publish device state while monitoring
+    #[serde(default = "default_true")]
+    pub publish_devices: bool,
+    /// Publish Home Assistant discovery configs for devices
+    #[serde(default)]
+    pub home_assistant: bool,
+    #[serde(default = "default_discovery_prefix")]
+    pub discovery_prefix: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+fn default_name() -> String {
+    "mqtt".to_string()
+}
+
+fn default_port() -> u16 {
+    DEFAULT_MQTT_PORT
+}
+
+fn default_client_id() -> String {
+    "network-topology-mapper".to_string()
+}
+
+fn default_topic_prefix() -> String {
+    "netmapper".to_string()
+}
+
+fn default_discovery_prefix() -> String {
+    "homeassistant".to_string()
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Whether a topic level is usable as a prefix (no wildcards)
+fn valid_topic(topic: &str) -> bool {
+    !topic.is_empty()
+        && !topic.starts_with('/')
+        && !topic.ends_with('/')
+        && !topic.contains(['+', '#'])
+}
+
+impl MqttSettings {
+    pub fn validate(&self) -> Result<()> {
+        if self.name.trim().is_empty() {
+            bail!("MQTT channel name cannot be empty");
+        }
+        if self.host.trim().is_empty() {
+            bail!("MQTT broker cannot be empty");
+        }
+        if self.client_id.trim().is_empty() {
+            bail!("MQTT client ID cannot be empty");
+        }
+        if !valid_topic(&self.topic_prefix) {
+            bail!("Invalid MQTT topic prefix: {}", self.topic_prefix);
+        }
+        if self.home_assistant && !valid_topic(&self.discovery_prefix) {
+            bail!("Invalid discovery prefix: {}", self.discovery_prefix);
+        }
+        Ok(())
+    }
+
+    /// Replace a newly entered password with its encrypted form
+    pub fn seal_password(&mut self) -> Result<()> {
+        seal_secret(&mut self.password, &mut self.encrypted_password)
+    }
+
+    /// Topic telling whether the app is connected (`online`/`offline`)
+    pub fn availability_topic(&self) -> String {
+        format!("{}/status", self.topic_prefix)
+    }
+
+    pub fn alert_topic(&self) -> String {
+        format!("{}/alert", self.topic_prefix)
+    }
+
+    /// Base topic of a device, keyed by its MAC without separators
+    pub fn device_topic(&self, mac: &str) -> String {
+        format!("{}/device/{}", self.topic_prefix, device_id(mac))
+    }
+
+    pub fn discovery_topic(&self, mac: &str) -> String {
+        format!(
+            "{}/binary_sensor/{}_{}/config",
+            self.discovery_prefix,
+            self.client_id,
+            device_id(mac)
+        )
+    }
+
+    /// Home Assistant discovery config for a device's presence sensor
+    pub fn discovery_payload(&self, mac: &str, name: &str, vendor: Option<&str>) -> Value {
+        let base = self.device_topic(mac);
+        json!({
+            "name": "Presence",
+            "unique_id": format!("{}_{}", self.client_id, device_id(mac)),
+            "device_class": "presence",
+            "state_topic": format!("{}/state", base),
+            "payload_on": "online",
+            "payload_off": "offline",
+            "json_attributes_topic": format!("{}/attributes", base),
+            "availability_topic": self.availability_topic(),
+            "device": {
+                "name": name,
+                "identifiers": [format!("{}_{}", self.client_id, device_id(mac))],
+                "connections": [["mac", mac.to_lowercase()]],
+                "manufacturer": vendor,
+            },
+        })
+    }
+
+    fn options(&self) -> Result<MqttOptions> {
+        let mut options = MqttOptions::new(&self.client_id, self.host.trim(), self.port);
+        options.set_keep_alive(MQTT_KEEP_ALIVE);
+        if let Some(username) = &self.username {
+            let password = open_secret(&self.password, &self.encrypted_password)?;
+            options.set_credentials(username, password);
+        }
+        Ok(options)
+    }
+}
+
+/// MAC as used in topics and IDs: lowercase hex digits only
+fn device_id(mac: &str) -> String {
+    mac.chars()
+        .filter(char::is_ascii_hexdigit)
+        .collect::<String>()
+        .to_lowercase()
+}
+
+/// Wait for the broker to acknowledge the publishes queued so far
+fn await_acks(connection: &mut Connection, mut pending: usize) -> Result<()> {
+    while pending > 0 {
+        match connection.recv_timeout(MQTT_TIMEOUT) {
+            Ok(Ok(Event::Incoming(Packet::PubAck(_)))) => pending -= 1,
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => return Err(e).context("MQTT connection failed"),
+            Err(_) => bail!("Timed out waiting for the MQTT broker"),
+        }
+    }
+    Ok(())
+}
+
+/// Publishes alerts to a broker, connecting for each delivery
+#[derive(Debug, Clone)]
+pub struct MqttNotifier {
+    settings: MqttSettings,
+}
+
+impl MqttNotifier {
+    pub fn new(settings: MqttSettings) -> Self {
+        Self { settings }
+    }
+}
+
+impl Notifier for MqttNotifier {
+    fn name(&self) -> &str {
+        &self.settings.name
+    }
+
+    fn send(&self, notification: &Notification) -> Result<()> {
+        let payload = serde_json::to_vec(notification).context("Failed to encode alert")?;
+        let (client, mut connection) = Client::new(self.settings.options()?, 10);
+        client
+            .publish(
+                self.settings.alert_topic(),
+                QoS::AtLeastOnce,
+                false,
+                payload,
+            )
+            .context("Failed to queue MQTT alert")?;
+        let result = await_acks(&mut connection, 1)
+            .with_context(|| format!("Failed to publish alert to {}", self.settings.host));
+        let _ = client.disconnect();
+        result
+    }
+}
+
+/// Keeps a connection open and publishes device state from monitor events
+///
+/// Reconnects on its own after the broker goes away. Dropping it
+/// disconnects, marking the app `offline` on the availability topic.
+pub struct MqttPublisher {
+    settings: MqttSettings,
+    client: Client,
+    /// Devices whose discovery config was published this session
+    announced: Mutex<HashSet<String>>,
+}
+
+impl MqttPublisher {
+    /// Connect in the background and mark the app `online`
+    pub fn connect(settings: MqttSettings) -> Result<Self> {
+        settings.validate()?;
+        let availability = settings.availability_topic();
+        let mut options = settings.options()?;
+        options.set_last_will(LastWill::new(
+            &availability,
+            "offline",
+            QoS::AtLeastOnce,
+            true,
+        ));
+        let (client, mut connection) = Client::new(options, 100);
+
+        let host = settings.host.clone();
+        std::thread::spawn(move || {
+            for event in connection.iter() {
+                match event {
+                    Ok(Event::Outgoing(Outgoing::Disconnect)) => break,
+                    Ok(_) => {}
+                    Err(ConnectionError::RequestsDone) => break,
+                    Err(e) => {
+                        tracing::warn!("[MQTT] Connection to {} failed: {}", host, e);
+                        std::thread::sleep(MQTT_RECONNECT_DELAY);
+                    }
+                }
+            }
+        });
+
+        client
+            .publish(&availability, QoS::AtLeastOnce, true, "online")
+            .context("Failed to queue MQTT status")?;
+        Ok(Self {
+            settings,
+            client,
+            announced: Mutex::new(HashSet::new()),
+        })
+    }
+
+    fn publish(&self, topic: String, retain: bool, payload: impl Into<Vec<u8>>) {
+        if let Err(e) = self
+            .client
+            .try_publish(&topic, QoS::AtLeastOnce, retain, payload)
+        {
+            tracing::warn!("[MQTT] Failed to publish to {}: {}", topic, e);
+        }
+    }
+
+    fn publish_state(
+        &self,
+        mac: &str,
+        online: bool,
+        ip: &str,
+        hostname: Option<&str>,
+        vendor: Option<&str>,
+    ) {
+        let name = hostname.or(vendor).unwrap_or(mac);
+        if self.settings.home_assistant {
+            let new = self
+                .announced
+                .lock()
+                .map(|mut announced| announced.insert(mac.to_uppercase()))
+                .unwrap_or(false);
+            if new {
+                let config = self.settings.discovery_payload(mac, name, vendor);
+                self.publish(self.settings.discovery_topic(mac), true, config.to_string());
+            }
+        }
+
+        let base = self.settings.device_topic(mac);
+        let attributes = json!({
+            "mac": mac.to_uppercase(),
+            "ip": ip,
+            "hostname": hostname,
+            "vendor": vendor,
+        });
+        self.publish(format!("{}/attributes", base), true, attributes.to_string());
+        let state = if online { "online" } else { "offline" };
+        self.publish(format!("{}/state", base), true, state);
+    }
+
+    /// Publish what a monitor event says about a device
+    pub fn publish_event(&self, event: &NetworkEvent) {
+        if !self.settings.publish_devices {
+            return;
+        }
+        match event {
+            NetworkEvent::NewDeviceDiscovered {
+                ip,
+                mac,
+                hostname,
+                vendor,
+                ..
+            } => {
+                self.publish_state(mac, true, ip, hostname.as_deref(), vendor.as_deref());
+                match serde_json::to_string(event) {
+                    Ok(payload) => self.publish(
+                        format!("{}/event/new_device", self.settings.topic_prefix),
+                        false,
+                        payload,
+                    ),
+                    Err(e) => tracing::warn!("[MQTT] Failed to encode event: {}", e),
+                }
+            }
+            NetworkEvent::DeviceCameOnline {
+                mac, ip, hostname, ..
+            } => self.publish_state(mac, true, ip, hostname.as_deref(), None),
+            NetworkEvent::DeviceWentOffline {
+                mac,
+                last_ip,
+                hostname,
+                ..
+            } => self.publish_state(mac, false, last_ip, hostname.as_deref(), None),
+            NetworkEvent::DeviceIpChanged { mac, new_ip, .. } => {
+                self.publish_state(mac, true, new_ip, None, None)
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Drop for MqttPublisher {
+    fn drop(&mut self) {
+        let _ = self.client.try_publish(
+            self.settings.availability_topic(),
+            QoS::AtLeastOnce,
+            true,
+            "offline",
+        );
+        let _ = self.client.try_disconnect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mqtt_topics() {
+        let mut settings = MqttSettings {
+            name: "mqtt".to_string(),
+            host: "broker.local".to_string(),
+            port: DEFAULT_MQTT_PORT,
+            client_id: "netmapper".to_string(),
+            username: None,
+            password: None,
+            encrypted_password: None,
+            topic_prefix: "home/network".to_string(),
+            publish_devices: true,
+            home_assistant: true,
+            discovery_prefix: "homeassistant".to_string(),
+            enabled: true,
+        };
+        settings.validate().unwrap();
+
+        let mac = "AA:BB:CC:00:00:01";
+        assert_eq!(
+            settings.device_topic(mac),
+            "home/network/device/aabbcc000001"
+        );
+        assert_eq!(
+            settings.discovery_topic(mac),
+            "homeassistant/binary_sensor/netmapper_aabbcc000001/config"
+        );
+        let config = settings.discovery_payload(mac, "phone", Some("Apple"));
+        assert_eq!(
+            config["state_topic"],
+            "home/network/device/aabbcc000001/state"
+        );
+        assert_eq!(config["device_class"], "presence");
+        assert_eq!(config["device"]["connections"][0][1], "aa:bb:cc:00:00:01");
+
+        settings.topic_prefix = "home/#".to_string();
+        assert!(settings.validate().is_err());
+    }
+}
@@ -0,0 +1,402 @@
+//! Syslog forwarding
+//!
+//! Sends alerts and monitor events to a syslog collector or SIEM as RFC 5424
+//! messages, over UDP, TCP or TLS (RFC 5425). Stream transports use
+//! octet-counting framing. Each message carries the alert or event fields as
+//! structured data, e.g.
+//! `<131>1 2026-03-02T08:00:00Z nas netmapper - HIGH_RISK [alert@32473 id="7" mac="AA:BB:..."] ...`,
+//! so they can be parsed without custom rules.
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, SecondsFormat, Utc};
+use native_tls::TlsConnector;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::net::{TcpStream, ToSocketAddrs, UdpSocket};
+use std::sync::mpsc::{self, Sender};
+
+use super::{Notification, Notifier};
+use crate::alerts::AlertSeverity;
+use crate::config::{DEFAULT_SYSLOG_FACILITY, SYSLOG_MAX_UDP_BYTES, SYSLOG_TIMEOUT};
+use crate::monitor::NetworkEvent;
+
+/// Private enterprise number used for structured data IDs
+const SD_ENTERPRISE: u32 = 32473;
+
+/// How messages reach the collector
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyslogTransport {
+    Udp,
+    Tcp,
+    Tls,
+}
+
+/// A syslog collector alerts and events are sent to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyslogSettings {
+    /// Channel name, as listed in alert rules
+    #[serde(default = "default_name")]
+    pub name: String,
+    pub host: String,
+    /// 514, or 6514 for TLS, if unset
+    #[serde(default)]
+    pub port: Option<u16>,
+    #[serde(default = "default_transport")]
+    pub transport: SyslogTransport,
+    /// Facility code, 16-23 for local0-local7
+    #[serde(default = "default_facility")]
+    pub facility: u8,
+    #[serde(default = "default_app_name")]
+    pub app_name: String,
+    /// Also forward monitor events while monitoring
+    #[serde(default = "default_true")]
+    pub forward_events: bool,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+fn default_name() -> String {
+    "syslog".to_string()
+}
+
+fn default_transport() -> SyslogTransport {
+    SyslogTransport::Udp
+}
+
+fn default_facility() -> u8 {
+    DEFAULT_SYSLOG_FACILITY
+}
+
+fn default_app_name() -> String {
+    "netmapper".to_string()
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl SyslogSettings {
+    pub fn validate(&self) -> Result<()> {
+        if self.name.trim().is_empty() {
+            bail!("Syslog channel name cannot be empty");
+        }
+        if self.host.trim().is_empty() {
+            bail!("Syslog server cannot be empty");
+        }
+        if self.facility > 23 {
+            bail!("Syslog facility must be between 0 and 23");
+        }
+        if self.app_name.is_empty()
+            || self.app_name.len() > 48
+            || !self.app_name.chars().all(|c| c.is_ascii_graphic())
+        {
+            bail!("Syslog app name must be 1-48 printable ASCII characters without spaces");
+        }
+        Ok(())
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port.unwrap_or(match self.transport {
+            SyslogTransport::Tls => 6514,
+            _ => 514,
+        })
+    }
+}
+
+/// Syslog severity code (0 emergency .. 7 debug)
+fn severity_code(severity: AlertSeverity) -> u8 {
+    match severity {
+        AlertSeverity::Low => 6,
+        AlertSeverity::Medium => 4,
+        AlertSeverity::High => 3,
+        AlertSeverity::Critical => 2,
+    }
+}
+
+/// Escape a structured data parameter value
+fn sd_escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace(']', "\\]")
+}
+
+/// A structured data element, e.g. `[alert@32473 id="7"]`
+fn sd_element(id: &str, params: &[(&str, Option<String>)]) -> String {
+    let mut element = format!("[{}@{}", id, SD_ENTERPRISE);
+    for (name, value) in params {
+        if let Some(value) = value {
+            element.push_str(&format!(" {}=\"{}\"", name, sd_escape(value)));
+        }
+    }
+    element.push(']');
+    element
+}
+
+fn local_hostname() -> String {
+    whoami::fallible::hostname()
+        .ok()
+        .filter(|name| !name.is_empty())
+        .map(|name| name.replace(' ', "-"))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+/// Format one RFC 5424 message
+///
+/// `msg_id` is truncated to the 32 characters allowed.
+pub fn format_syslog(
+    settings: &SyslogSettings,
+    severity: AlertSeverity,
+    timestamp: DateTime<Utc>,
+    msg_id: &str,
+    structured_data: &str,
+    message: &str,
+) -> String {
+    let priority = u16::from(settings.facility) * 8 + u16::from(severity_code(severity));
+    let msg_id: String = msg_id
+        .chars()
+        .filter(char::is_ascii_graphic)
+        .take(32)
+        .collect();
+    format!(
+        "<{}>1 {} {} {} {} {} {} {}",
+        priority,
+        timestamp.to_rfc3339_opts(SecondsFormat::Millis, true),
+        local_hostname(),
+        settings.app_name,
+        std::process::id(),
+        if msg_id.is_empty() {
+            "-"
+        } else {
+            msg_id.as_str()
+        },
+        if structured_data.is_empty() {
+            "-"
+        } else {
+            structured_data
+        },
+        message
+    )
+}
+
+/// Syslog message for an alert
+pub fn alert_message(settings: &SyslogSettings, notification: &Notification) -> String {
+    let data = sd_element(
+        "alert",
+        &[
+            ("id", notification.alert_id.map(|id| id.to_string())),
+            ("type", Some(notification.alert_type.clone())),
+            ("severity", Some(notification.severity.as_str().to_string())),
+            ("mac", notification.device_mac.clone()),
+            ("ip", notification.device_ip.clone()),
+        ],
+    );
+    format_syslog(
+        settings,
+        notification.severity,
+        notification.created_at,
+        &notification.alert_type,
+        &data,
+        &notification.message,
+    )
+}
+
+/// Syslog message for a monitor event; its fields become structured data
+pub fn event_message(settings: &SyslogSettings, event: &NetworkEvent) -> String {
+    let fields: Vec<(String, String)> = match serde_json::to_value(event) {
+        Ok(serde_json::Value::Object(mut map)) => match map.remove("data") {
+            Some(serde_json::Value::Object(data)) => data
+                .into_iter()
+                .filter(|(_, value)| !value.is_null())
+                .map(|(name, value)| match value {
+                    serde_json::Value::String(s) => (name, s),
+                    other => (name, other.to_string()),
+                })
+                .collect(),
+            _ => Vec::new(),
+        },
+        _ => Vec::new(),
+    };
+
+    let params: Vec<(&str, Option<String>)> = fields
+        .iter()
+        .map(|(name, value)| (name.as_str(), Some(value.clone())))
+        .collect();
+    let mut message = event.kind().to_string();
+    for (name, value) in &fields {
+        message.push_str(&format!(" {}={}", name, value));
+    }
+    format_syslog(
+        settings,
+        event.severity(),
+        Utc::now(),
+        event.kind(),
+        &sd_element("event", &params),
+        &message,
+    )
+}
+
+/// Send messages to the collector over one connection
+pub fn send_syslog(settings: &SyslogSettings, messages: &[String]) -> Result<()> {
+    let host = settings.host.trim();
+    let addr = (host, settings.port())
+        .to_socket_addrs()
+        .with_context(|| format!("Failed to resolve syslog server {}", host))?
+        .next()
+        .with_context(|| format!("No address for syslog server {}", host))?;
+
+    // Stream transports frame each message with its length (RFC 6587)
+    let framed = || {
+        messages
+            .iter()
+            .flat_map(|message| format!("{} {}", message.len(), message).into_bytes())
+            .collect::<Vec<u8>>()
+    };
+    match settings.transport {
+        SyslogTransport::Udp => {
+            let socket = UdpSocket::bind(if addr.is_ipv4() {
+                "0.0.0.0:0"
+            } else {
+                "[::]:0"
+            })?;
+            for message in messages {
+                let bytes = message.as_bytes();
+                socket.send_to(&bytes[..bytes.len().min(SYSLOG_MAX_UDP_BYTES)], addr)?;
+            }
+        }
+        SyslogTransport::Tcp => {
+            let mut stream = TcpStream::connect_timeout(&addr, SYSLOG_TIMEOUT)?;
+            stream.set_write_timeout(Some(SYSLOG_TIMEOUT))?;
+            stream.write_all(&framed())?;
+        }
+        SyslogTransport::Tls => {
+            let stream = TcpStream::connect_timeout(&addr, SYSLOG_TIMEOUT)?;
+            stream.set_read_timeout(Some(SYSLOG_TIMEOUT))?;
+            stream.set_write_timeout(Some(SYSLOG_TIMEOUT))?;
+            let mut stream = TlsConnector::new()?
+                .connect(host, stream)
+                .with_context(|| format!("TLS handshake with {} failed", host))?;
+            stream.write_all(&framed())?;
+            let _ = stream.shutdown();
+        }
+    }
+    Ok(())
+}
+
+/// Sends alerts to a syslog collector
+///
+/// Every alert is sent, whatever its severity: filtering is left to the SIEM.
+#[derive(Debug, Clone)]
+pub struct SyslogNotifier {
+    settings: SyslogSettings,
+}
+
+impl SyslogNotifier {
+    pub fn new(settings: SyslogSettings) -> Self {
+        Self { settings }
+    }
+}
+
+impl Notifier for SyslogNotifier {
+    fn name(&self) -> &str {
+        &self.settings.name
+    }
+
+    fn accepts(&self, _notification: &Notification, _min_severity: AlertSeverity) -> bool {
+        true
+    }
+
+    fn send(&self, notification: &Notification) -> Result<()> {
+        send_syslog(
+            &self.settings,
+            &[alert_message(&self.settings, notification)],
+        )
+        .with_context(|| format!("Failed to send alert to {}", self.settings.host))
+    }
+}
+
+/// Forwards monitor events from a background thread, so a slow collector
+/// never holds up the monitor. Stops when dropped.
+pub struct SyslogForwarder {
+    settings: SyslogSettings,
+    queue: Sender<String>,
+}
+
+impl SyslogForwarder {
+    pub fn start(settings: SyslogSettings) -> Result<Self> {
+        settings.validate()?;
+        let (queue, messages) = mpsc::channel::<String>();
+        let target = settings.clone();
+        std::thread::spawn(move || {
+            while let Ok(first) = messages.recv() {
+                // Send whatever piled up meanwhile over the same connection
+                let mut batch = vec![first];
+                batch.extend(messages.try_iter());
+                if let Err(e) = send_syslog(&target, &batch) {
+                    tracing::warn!(
+                        "[SYSLOG] Failed to send {} events to {}: {:#}",
+                        batch.len(),
+                        target.host,
+                        e
+                    );
+                }
+            }
+        });
+        Ok(Self { settings, queue })
+    }
+
+    pub fn forward_event(&self, event: &NetworkEvent) {
+        if matches!(event, NetworkEvent::ScanProgress { .. }) {
+            return;
+        }
+        let _ = self.queue.send(event_message(&self.settings, event));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_syslog_messages() {
+        let settings = SyslogSettings {
+            name: "siem".to_string(),
+            host: "siem.example.com".to_string(),
+            port: None,
+            transport: SyslogTransport::Tls,
+            facility: 16,
+            app_name: "netmapper".to_string(),
+            forward_events: true,
+            enabled: true,
+        };
+        settings.validate().unwrap();
+        assert_eq!(settings.port(), 6514);
+
+        let notification = Notification {
+            alert_id: Some(7),
+            alert_type: "HIGH_RISK".to_string(),
+            severity: AlertSeverity::High,
+            message: "Risky device".to_string(),
+            device_mac: Some("AA:BB:CC:00:00:07".to_string()),
+            device_ip: None,
+            created_at: Utc::now(),
+            channels: Vec::new(),
+        };
+        let message = alert_message(&settings, &notification);
+        // local0 (16) * 8 + err (3)
+        assert!(message.starts_with("<131>1 "));
+        assert!(message.contains(
+            r#" HIGH_RISK [alert@32473 id="7" type="HIGH_RISK" severity="HIGH" mac="AA:BB:CC:00:00:07"] Risky device"#
+        ));
+
+        let event = NetworkEvent::IpConflict {
+            ip: "192.168.1.9".to_string(),
+            macs: vec!["AA".to_string(), "BB".to_string()],
+        };
+        let message = event_message(&settings, &event);
+        assert!(message
+            .contains(r#" IpConflict [event@32473 ip="192.168.1.9" macs="[\"AA\",\"BB\"\]"]"#));
+        assert_eq!(sd_escape(r#"a"b\c]"#), r#"a\"b\\c\]"#);
+    }
+}
@@ -0,0 +1,216 @@
+//! Webhook notification channel
+//!
+//! POSTs each alert as JSON to a URL (ntfy, Gotify, an ELK ingest endpoint,
+//! ...). The body is the alert itself, or a template with `{{field}}`
+//! placeholders for services that expect their own shape, such as
+//! `{"title": "{{alert_type}}", "message": "{{message}}"}` for Gotify.
+//! Failed deliveries are retried with exponential backoff.
+
+use anyhow::{bail, Context, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use super::{Notification, Notifier};
+use crate::alerts::AlertSeverity;
+use crate::config::{MAX_WEBHOOK_RETRIES, WEBHOOK_RETRIES, WEBHOOK_RETRY_BACKOFF, WEBHOOK_TIMEOUT};
+
+/// Fields a payload template can reference as `{{field}}`
+pub const TEMPLATE_FIELDS: &[&str] = &[
+    "alert_id",
+    "alert_type",
+    "severity",
+    "message",
+    "device_mac",
+    "device_ip",
+    "created_at",
+];
+
+/// A webhook alerts are posted to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebhookSettings {
+    /// Channel name, as listed in alert rules
+    pub name: String,
+    pub url: String,
+    /// JSON body with `{{field}}` placeholders; the alert as JSON if unset
+    #[serde(default)]
+    pub template: Option<String>,
+    /// Attempts after a failed delivery
+    #[serde(default = "default_retries")]
+    pub retries: u32,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_retries() -> u32 {
+    WEBHOOK_RETRIES
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl WebhookSettings {
+    pub fn validate(&self) -> Result<()> {
+        if self.name.trim().is_empty() {
+            bail!("Webhook name cannot be empty");
+        }
+        if !(self.url.starts_with("https://") || self.url.starts_with("http://")) {
+            bail!("Webhook URL must start with http:// or https://");
+        }
+        if self.retries > MAX_WEBHOOK_RETRIES {
+            bail!("Webhook retries must be at most {}", MAX_WEBHOOK_RETRIES);
+        }
+        if let Some(template) = &self.template {
+            let sample = Notification {
+                alert_id: Some(1),
+                alert_type: "HIGH_RISK".to_string(),
+                severity: AlertSeverity::High,
+                message: "Sample \"alert\"".to_string(),
+                device_mac: None,
+                device_ip: None,
+                created_at: Utc::now(),
+                channels: Vec::new(),
+            };
+            serde_json::from_str::<serde_json::Value>(&render_template(template, &sample))
+                .context("Webhook template is not valid JSON")?;
+        }
+        Ok(())
+    }
+}
+
+/// Fill a payload template
+///
+/// Each placeholder becomes the field's text escaped for use inside a JSON
+/// string (empty if the alert has no such value), so templates put the
+/// placeholders between quotes.
+pub fn render_template(template: &str, notification: &Notification) -> String {
+    let value = |field: &str| match field {
+        "alert_id" => notification.alert_id.map(|id| id.to_string()),
+        "alert_type" => Some(notification.alert_type.clone()),
+        "severity" => Some(notification.severity.as_str().to_string()),
+        "message" => Some(notification.message.clone()),
+        "device_mac" => notification.device_mac.clone(),
+        "device_ip" => notification.device_ip.clone(),
+        "created_at" => Some(notification.created_at.to_rfc3339()),
+        _ => None,
+    };
+
+    let mut body = template.to_string();
+    for field in TEMPLATE_FIELDS {
+        let escaped = serde_json::to_string(&value(field).unwrap_or_default())
+            .map(|quoted| quoted[1..quoted.len() - 1].to_string())
+            .unwrap_or_default();
+        body = body.replace(&format!("{{{{{}}}}}", field), &escaped);
+    }
+    body
+}
+
+/// Whether a failed delivery is worth retrying
+fn retryable(error: &ureq::Error) -> bool {
+    match error {
+        ureq::Error::Status(code, _) => *code == 429 || *code >= 500,
+        ureq::Error::Transport(_) => true,
+    }
+}
+
+/// POST a JSON body, retrying up to `retries` times with exponential backoff
+pub(crate) fn post_json(url: &str, body: &str, retries: u32) -> Result<()> {
+    let mut attempt = 0;
+    loop {
+        let result = ureq::post(url)
+            .timeout(WEBHOOK_TIMEOUT)
+            .set("Content-Type", "application/json")
+            .send_string(body);
+        match result {
+            Ok(_) => return Ok(()),
+            Err(e) if attempt < retries && retryable(&e) => {
+                std::thread::sleep(WEBHOOK_RETRY_BACKOFF * 2u32.saturating_pow(attempt));
+                attempt += 1;
+            }
+            Err(e) => return Err(e).with_context(|| format!("Failed to post alert to {}", url)),
+        }
+    }
+}
+
+/// Posts alerts to one webhook
+#[derive(Debug, Clone)]
+pub struct WebhookNotifier {
+    settings: WebhookSettings,
+}
+
+impl WebhookNotifier {
+    pub fn new(settings: WebhookSettings) -> Self {
+        Self { settings }
+    }
+
+    /// Request body for an alert
+    pub fn payload(&self, notification: &Notification) -> Result<String> {
+        match &self.settings.template {
+            Some(template) => Ok(render_template(template, notification)),
+            None => serde_json::to_string(notification).context("Failed to encode alert"),
+        }
+    }
+}
+
+impl Notifier for WebhookNotifier {
+    fn name(&self) -> &str {
+        &self.settings.name
+    }
+
+    fn send(&self, notification: &Notification) -> Result<()> {
+        let body = self.payload(notification)?;
+        post_json(&self.settings.url, &body, self.settings.retries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_webhook_payload() {
+        let mut settings = WebhookSettings {
+            name: "gotify".to_string(),
+            url: "https://gotify.example/message".to_string(),
+            template: Some(
+                r#"{"title": "{{alert_type}} on {{device_ip}}", "message": "{{message}}"}"#
+                    .to_string(),
+            ),
+            retries: 3,
+            enabled: true,
+        };
+        settings.validate().unwrap();
+
+        let notification = Notification {
+            alert_id: Some(7),
+            alert_type: "MAC_CLONED".to_string(),
+            severity: AlertSeverity::High,
+            message: "MAC \"aa\" answers on\ntwo addresses".to_string(),
+            device_mac: Some("aa:00:00:00:00:07".to_string()),
+            device_ip: None,
+            created_at: Utc::now(),
+            channels: Vec::new(),
+        };
+        let payload = WebhookNotifier::new(settings.clone())
+            .payload(&notification)
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_str(&payload).unwrap();
+        assert_eq!(json["title"], "MAC_CLONED on ");
+        assert_eq!(json["message"], notification.message);
+
+        // Without a template the alert itself is posted
+        settings.template = None;
+        let payload = WebhookNotifier::new(settings.clone())
+            .payload(&notification)
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_str(&payload).unwrap();
+        assert_eq!(json["alert_id"], 7);
+        assert_eq!(json["severity"], "High");
+
+        settings.template = Some(r#"{"message": {{message}}}"#.to_string());
+        assert!(settings.validate().is_err());
+        settings.template = None;
+        settings.url = "ftp://example.com".to_string();
+        assert!(settings.validate().is_err());
+    }
+}
@@ -0,0 +1,432 @@
+//! Distributed scans between trusted peers
+//!
+//! Trusted instances scanning the same subnet split its addresses by
+//! instance ID. Each one scans its own slice and serves the signed result
+//! on its advertised port; the others fetch recent results instead of
+//! scanning that slice again. Slices without a fresh result from their
+//! owner are scanned locally, so a missing peer never leaves a gap.
+//!
+//! Requests and results are signed with each install's export key and
+//! checked against the public key pinned when the peer was trusted.
+//! Traffic is not encrypted.
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use super::presence::PeerInfo;
+use crate::config::{
+    PEER_REQUEST_MAX_SKEW_SECS, PEER_REQUEST_TIMEOUT, PEER_SHARE_MAX_AGE_SECS, PEER_SHARE_MAX_BYTES,
+};
+use crate::exports::{verify_export, ExportSignature, ExportSigner};
+use crate::models::HostInfo;
+
+/// An instance allowed to exchange scan results, pinned to its public key
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrustedPeer {
+    pub instance_id: String,
+    /// Export signing key of the peer (base64)
+    pub public_key: String,
+}
+
+/// One instance's scan of its slice of a subnet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanShare {
+    pub instance_id: String,
+    pub subnet: String,
+    /// Addresses the instance probed
+    pub targets: Vec<Ipv4Addr>,
+    /// Hosts found among `targets`
+    pub hosts: Vec<HostInfo>,
+    pub completed_at: DateTime<Utc>,
+}
+
+/// A share as sent over the wire: the JSON payload and its signature
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedShare {
+    pub payload: String,
+    pub signature: ExportSignature,
+}
+
+impl SignedShare {
+    pub fn sign(share: &ScanShare, signer: &ExportSigner) -> Result<Self> {
+        let payload = serde_json::to_string(share).context("Failed to serialize scan share")?;
+        let signature = signer.sign(payload.as_bytes());
+        Ok(Self { payload, signature })
+    }
+
+    /// Check the signature against a pinned key and decode the share
+    pub fn verify(&self, public_key: &str) -> Result<ScanShare> {
+        if !verify_export(self.payload.as_bytes(), &self.signature, public_key)? {
+            bail!("Scan share signature does not match the trusted key");
+        }
+        serde_json::from_str(&self.payload).context("Malformed scan share")
+    }
+}
+
+/// Signed request for a peer's latest share of a subnet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ShareRequest {
+    instance_id: String,
+    subnet: String,
+    timestamp: i64,
+    signature: ExportSignature,
+}
+
+impl ShareRequest {
+    fn message(instance_id: &str, subnet: &str, timestamp: i64) -> String {
+        format!("{}|{}|{}", instance_id, subnet, timestamp)
+    }
+
+    fn new(instance_id: &str, subnet: &str, signer: &ExportSigner) -> Self {
+        let timestamp = Utc::now().timestamp();
+        let message = Self::message(instance_id, subnet, timestamp);
+        Self {
+            instance_id: instance_id.to_string(),
+            subnet: subnet.to_string(),
+            timestamp,
+            signature: signer.sign(message.as_bytes()),
+        }
+    }
+
+    fn verify(&self, trusted: &[TrustedPeer]) -> Result<()> {
+        let peer = trusted
+            .iter()
+            .find(|peer| peer.instance_id == self.instance_id)
+            .ok_or_else(|| anyhow!("Instance {} is not trusted", self.instance_id))?;
+        if (Utc::now().timestamp() - self.timestamp).abs() > PEER_REQUEST_MAX_SKEW_SECS {
+            bail!("Share request timestamp is out of range");
+        }
+        let message = Self::message(&self.instance_id, &self.subnet, self.timestamp);
+        if !verify_export(message.as_bytes(), &self.signature, &peer.public_key)? {
+            bail!("Share request signature does not match the trusted key");
+        }
+        Ok(())
+    }
+}
+
+/// Addresses this instance scans when `participants` split `ips`
+///
+/// Addresses are dealt round-robin in instance ID order, so every
+/// participant computes the same split and slices stay spread over the range.
+pub fn scan_slice(ips: &[Ipv4Addr], participants: &[&str], own_id: &str) -> Vec<Ipv4Addr> {
+    let mut participants = participants.to_vec();
+    participants.sort_unstable();
+    participants.dedup();
+    let Some(rank) = participants.iter().position(|id| *id == own_id) else {
+        return ips.to_vec();
+    };
+
+    ips.iter()
+        .enumerate()
+        .filter(|(i, _)| i % participants.len() == rank)
+        .map(|(_, ip)| *ip)
+        .collect()
+}
+
+/// What to scan locally and which peer results to use
+#[derive(Debug, Clone, Default)]
+pub struct SharedScanPlan {
+    /// Addresses to probe locally (own slice plus slices without fresh results)
+    pub targets: Vec<Ipv4Addr>,
+    /// Hosts reported by peers for the remaining addresses
+    pub peer_hosts: Vec<HostInfo>,
+    /// Names of the peers whose results are used
+    pub contributors: Vec<String>,
+}
+
+impl SharedScanPlan {
+    /// Build a plan from the peers' verified shares
+    pub fn from_shares(
+        own_id: &str,
+        subnet: &str,
+        ips: &[Ipv4Addr],
+        peers: &[PeerInfo],
+        shares: Vec<(PeerInfo, ScanShare)>,
+    ) -> Self {
+        let mut participants: Vec<&str> = peers.iter().map(|p| p.instance_id.as_str()).collect();
+        participants.push(own_id);
+        let own_slice: HashSet<Ipv4Addr> =
+            scan_slice(ips, &participants, own_id).into_iter().collect();
+
+        let mut plan = Self::default();
+        let mut covered = HashSet::new();
+        for (peer, share) in shares {
+            let age = Utc::now() - share.completed_at;
+            if share.instance_id != peer.instance_id
+                || share.subnet != subnet
+                || age.num_seconds() > PEER_SHARE_MAX_AGE_SECS
+            {
+                tracing::debug!("Ignoring stale scan share from {}", peer.instance_name);
+                continue;
+            }
+            covered.extend(share.targets.iter().copied());
+            plan.peer_hosts.extend(share.hosts);
+            plan.contributors.push(peer.instance_name);
+        }
+
+        plan.targets = ips
+            .iter()
+            .filter(|ip| own_slice.contains(ip) || !covered.contains(ip))
+            .copied()
+            .collect();
+        plan
+    }
+
+    /// Add peer-reported hosts that were not probed locally
+    ///
+    /// A peer's own machine (reported as `LOCAL`) is marked `PEER`.
+    pub fn merge_into(self, hosts: &mut Vec<HostInfo>) {
+        let targets: HashSet<String> = self.targets.iter().map(|ip| ip.to_string()).collect();
+        for mut host in self.peer_hosts {
+            if targets.contains(&host.ip) || hosts.iter().any(|h| h.ip == host.ip) {
+                continue;
+            }
+            if host.discovery_method == "LOCAL" {
+                host.discovery_method = "PEER".to_string();
+            }
+            hosts.push(host);
+        }
+    }
+}
+
+/// Serves this instance's latest share to trusted peers
+pub(crate) struct ShareServer {
+    latest: Arc<RwLock<Option<SignedShare>>>,
+    stop: Arc<AtomicBool>,
+    local_addr: SocketAddr,
+}
+
+impl ShareServer {
+    pub(crate) fn start(bind: SocketAddr, trusted: Vec<TrustedPeer>) -> Result<Self> {
+        let listener =
+            TcpListener::bind(bind).with_context(|| format!("Failed to listen on {}", bind))?;
+        listener.set_nonblocking(true)?;
+        let local_addr = listener.local_addr()?;
+
+        let latest: Arc<RwLock<Option<SignedShare>>> = Arc::default();
+        let stop = Arc::new(AtomicBool::new(false));
+        let (shares, stopped) = (Arc::clone(&latest), Arc::clone(&stop));
+        std::thread::Builder::new()
+            .name("peer-shares".to_string())
+            .spawn(move || {
+                while !stopped.load(Ordering::SeqCst) {
+                    match listener.accept() {
+                        Ok((stream, addr)) => {
+                            if let Err(e) = serve_share(stream, &trusted, &shares) {
+                                tracing::debug!("Rejected share request from {}: {}", addr, e);
+                            }
+                        }
+                        Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                            std::thread::sleep(Duration::from_millis(200));
+                        }
+                        Err(e) => {
+                            tracing::warn!("Peer share listener error: {}", e);
+                            std::thread::sleep(Duration::from_secs(1));
+                        }
+                    }
+                }
+            })
+            .context("Failed to start peer share server")?;
+
+        Ok(Self {
+            latest,
+            stop,
+            local_addr,
+        })
+    }
+
+    pub(crate) fn publish(&self, share: SignedShare) {
+        if let Ok(mut latest) = self.latest.write() {
+            *latest = Some(share);
+        }
+    }
+
+    pub(crate) fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+}
+
+impl Drop for ShareServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+    }
+}
+
+fn serve_share(
+    stream: TcpStream,
+    trusted: &[TrustedPeer],
+    latest: &RwLock<Option<SignedShare>>,
+) -> Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(PEER_REQUEST_TIMEOUT))?;
+    stream.set_write_timeout(Some(PEER_REQUEST_TIMEOUT))?;
+
+    let mut line = String::new();
+    BufReader::new((&stream).take(8 * 1024)).read_line(&mut line)?;
+    let request: ShareRequest = serde_json::from_str(&line).context("Malformed share request")?;
+    request.verify(trusted)?;
+
+    let share = latest
+        .read()
+        .map_err(|_| anyhow!("Share lock poisoned"))?
+        .clone()
+        .filter(|share| {
+            serde_json::from_str::<ScanShare>(&share.payload)
+                .is_ok_and(|s| s.subnet == request.subnet)
+        });
+    let mut stream = stream;
+    serde_json::to_writer(&mut stream, &share)?;
+    stream.write_all(b"\n")?;
+    Ok(())
+}
+
+/// Ask a peer for its latest share of a subnet (`None` if it has none yet)
+pub(crate) async fn fetch_share(
+    peer: &PeerInfo,
+    own_id: &str,
+    subnet: &str,
+    signer: &ExportSigner,
+) -> Result<Option<SignedShare>> {
+    let request = ShareRequest::new(own_id, subnet, signer);
+    let mut line = serde_json::to_vec(&request)?;
+    line.push(b'\n');
+
+    let exchange = async {
+        let mut stream = tokio::net::TcpStream::connect((peer.ip.as_str(), peer.port)).await?;
+        stream.write_all(&line).await?;
+        let mut response = Vec::new();
+        (&mut stream)
+            .take(PEER_SHARE_MAX_BYTES as u64)
+            .read_to_end(&mut response)
+            .await?;
+        Ok::<_, std::io::Error>(response)
+    };
+    let response = tokio::time::timeout(PEER_REQUEST_TIMEOUT, exchange)
+        .await
+        .map_err(|_| anyhow!("Timed out contacting {}", peer.ip))?
+        .with_context(|| format!("Failed to contact {}", peer.ip))?;
+
+    serde_json::from_slice(&response).context("Malformed share response")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peer(id: &str, addr: SocketAddr) -> PeerInfo {
+        PeerInfo {
+            instance_id: id.to_string(),
+            instance_name: id.to_string(),
+            hostname: format!("{}.local", id),
+            ip: addr.ip().to_string(),
+            port: addr.port(),
+            version: String::new(),
+            public_key: None,
+            subnet: Some("10.0.0.0/24".to_string()),
+            monitoring: false,
+            last_seen: Utc::now(),
+        }
+    }
+
+    fn host(ip: &str, method: &str) -> HostInfo {
+        HostInfo::new(
+            ip.to_string(),
+            "AA:BB:CC:DD:EE:01".to_string(),
+            "PC".to_string(),
+            method.to_string(),
+        )
+    }
+
+    #[test]
+    fn test_scan_slices_partition_the_range() {
+        let ips: Vec<Ipv4Addr> = (1..=9).map(|i| Ipv4Addr::new(10, 0, 0, i)).collect();
+        let a = scan_slice(&ips, &["b", "a"], "a");
+        let b = scan_slice(&ips, &["a", "b"], "b");
+        assert_eq!(a.len() + b.len(), ips.len());
+        assert!(a.iter().all(|ip| !b.contains(ip)));
+        assert_eq!(
+            a[..2],
+            [Ipv4Addr::new(10, 0, 0, 1), Ipv4Addr::new(10, 0, 0, 3)]
+        );
+        // Not a participant: scan everything
+        assert_eq!(scan_slice(&ips, &["a", "b"], "c"), ips);
+    }
+
+    #[tokio::test]
+    async fn test_shares_are_served_to_trusted_peers_only() {
+        let (alice, bob) = (ExportSigner::generate(), ExportSigner::generate());
+        let trusted = vec![TrustedPeer {
+            instance_id: "bob".to_string(),
+            public_key: bob.public_key_base64(),
+        }];
+        let server = ShareServer::start("127.0.0.1:0".parse().unwrap(), trusted).unwrap();
+        let alice_peer = peer("alice", server.local_addr());
+        let subnet = "10.0.0.0/24";
+
+        // Nothing published yet
+        let empty = fetch_share(&alice_peer, "bob", subnet, &bob).await.unwrap();
+        assert!(empty.is_none());
+
+        let ips: Vec<Ipv4Addr> = (1..=4).map(|i| Ipv4Addr::new(10, 0, 0, i)).collect();
+        let alice_targets = scan_slice(&ips, &["alice", "bob"], "alice");
+        let share = ScanShare {
+            instance_id: "alice".to_string(),
+            subnet: subnet.to_string(),
+            targets: alice_targets.clone(),
+            hosts: vec![host("10.0.0.1", "LOCAL"), host("10.0.0.3", "ARP")],
+            completed_at: Utc::now(),
+        };
+        server.publish(SignedShare::sign(&share, &alice).unwrap());
+
+        let signed = fetch_share(&alice_peer, "bob", subnet, &bob)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(signed.verify(&bob.public_key_base64()).is_err());
+        let received = signed.verify(&alice.public_key_base64()).unwrap();
+        assert_eq!(received.targets, alice_targets);
+
+        // Untrusted instances and other subnets get nothing
+        assert!(fetch_share(&alice_peer, "mallory", subnet, &alice)
+            .await
+            .is_err());
+        let other = fetch_share(&alice_peer, "bob", "10.0.1.0/24", &bob).await;
+        assert!(other.unwrap().is_none());
+
+        let plan = SharedScanPlan::from_shares(
+            "bob",
+            subnet,
+            &ips,
+            std::slice::from_ref(&alice_peer),
+            vec![(alice_peer.clone(), received)],
+        );
+        assert_eq!(
+            plan.targets,
+            [Ipv4Addr::new(10, 0, 0, 2), Ipv4Addr::new(10, 0, 0, 4)]
+        );
+        assert_eq!(plan.contributors, ["alice"]);
+
+        let mut hosts = vec![host("10.0.0.2", "ARP")];
+        plan.merge_into(&mut hosts);
+        let methods: Vec<(&str, &str)> = hosts
+            .iter()
+            .map(|h| (h.ip.as_str(), h.discovery_method.as_str()))
+            .collect();
+        assert_eq!(
+            methods,
+            [
+                ("10.0.0.2", "ARP"),
+                ("10.0.0.1", "PEER"),
+                ("10.0.0.3", "ARP")
+            ]
+        );
+    }
+}
@@ -0,0 +1,14 @@
+//! Peer instances on the LAN
+//!
+//! Instances can announce themselves over mDNS so they find each other,
+//! which lets the app avoid monitoring a subnet that another instance is
+//! already watching and split scans of large subnets with trusted peers.
+
+mod distributed;
+mod presence;
+
+pub use distributed::{scan_slice, ScanShare, SharedScanPlan, SignedShare, TrustedPeer};
+pub use presence::{
+    instance_id, load_peer_settings, save_peer_settings, Announcement, PeerInfo, PeerPresence,
+    PeerSettings,
+};
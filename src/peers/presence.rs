@@ -0,0 +1,507 @@
+//! mDNS self-advertisement and peer browsing
+//!
+//! Each instance registers a `_netmapper._tcp` service whose TXT record
+//! carries its instance ID, version, the subnet it scans and whether it is
+//! monitoring. The same daemon browses for that service type and keeps a
+//! table of the other instances it has seen.
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::{Arc, Mutex, RwLock};
+
+use super::distributed::{
+    fetch_share, ScanShare, ShareServer, SharedScanPlan, SignedShare, TrustedPeer,
+};
+use crate::config::{PEER_DEFAULT_PORT, PEER_SERVICE_TYPE};
+use crate::database::queries::{self, SETTING_INSTANCE_ID, SETTING_PEER_SETTINGS};
+use crate::exports::ExportSigner;
+use crate::models::HostInfo;
+
+/// Peer lookup table keyed by mDNS full service name
+type PeerTable = Arc<RwLock<HashMap<String, PeerInfo>>>;
+
+/// Whether and how this instance announces itself
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PeerSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Name shown to other instances (defaults to the hostname)
+    #[serde(default)]
+    pub instance_name: Option<String>,
+    /// Port advertised for peer coordination
+    #[serde(default = "default_peer_port")]
+    pub port: u16,
+    /// Split scans of a shared subnet with trusted peers
+    #[serde(default)]
+    pub distributed_scans: bool,
+    /// Peers allowed to exchange scan results
+    #[serde(default)]
+    pub trusted_peers: Vec<TrustedPeer>,
+}
+
+fn default_peer_port() -> u16 {
+    PEER_DEFAULT_PORT
+}
+
+impl Default for PeerSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            instance_name: None,
+            port: PEER_DEFAULT_PORT,
+            distributed_scans: false,
+            trusted_peers: Vec::new(),
+        }
+    }
+}
+
+/// Saved peer settings, or the defaults (advertising off)
+pub fn load_peer_settings(conn: &Connection) -> Result<PeerSettings> {
+    Ok(queries::get_json_setting(conn, SETTING_PEER_SETTINGS)?.unwrap_or_default())
+}
+
+/// Validate and persist peer settings
+pub fn save_peer_settings(conn: &Connection, settings: &PeerSettings) -> Result<()> {
+    if settings.port == 0 {
+        bail!("Peer port cannot be 0");
+    }
+    if settings
+        .instance_name
+        .as_deref()
+        .is_some_and(|name| name.trim().is_empty() || name.contains('.'))
+    {
+        bail!("Instance name cannot be empty or contain '.'");
+    }
+    if settings
+        .trusted_peers
+        .iter()
+        .any(|peer| peer.instance_id.trim().is_empty() || peer.public_key.trim().is_empty())
+    {
+        bail!("Trusted peers need an instance ID and public key");
+    }
+    queries::set_json_setting(conn, SETTING_PEER_SETTINGS, settings)
+}
+
+/// This install's peer ID, generated and stored on first use
+pub fn instance_id(conn: &Connection) -> Result<String> {
+    if let Some(id) = queries::get_setting(conn, SETTING_INSTANCE_ID)? {
+        return Ok(id);
+    }
+
+    let mut bytes = [0u8; 16];
+    aes_gcm::aead::rand_core::RngCore::fill_bytes(&mut aes_gcm::aead::OsRng, &mut bytes);
+    let id = hex::encode(bytes);
+    queries::set_setting(conn, SETTING_INSTANCE_ID, &id)?;
+    Ok(id)
+}
+
+/// What this instance advertises about itself
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Announcement {
+    pub instance_id: String,
+    pub instance_name: String,
+    pub ip: Ipv4Addr,
+    pub port: u16,
+    /// Export signing key, so peers can pin it when trusting this instance
+    pub public_key: Option<String>,
+    /// Subnet this instance scans (e.g. `192.168.1.0/24`)
+    pub subnet: Option<String>,
+    pub monitoring: bool,
+}
+
+impl Announcement {
+    /// Announcement for this host from saved settings
+    pub fn new(
+        instance_id: String,
+        settings: &PeerSettings,
+        ip: Ipv4Addr,
+        subnet: Option<String>,
+    ) -> Self {
+        let instance_name = settings
+            .instance_name
+            .clone()
+            .unwrap_or_else(local_hostname);
+        Self {
+            instance_id,
+            instance_name,
+            ip,
+            port: settings.port,
+            public_key: None,
+            subnet,
+            monitoring: false,
+        }
+    }
+
+    fn service_info(&self) -> Result<ServiceInfo> {
+        let mut properties = HashMap::from([
+            ("id".to_string(), self.instance_id.clone()),
+            ("name".to_string(), self.instance_name.clone()),
+            ("version".to_string(), env!("CARGO_PKG_VERSION").to_string()),
+            (
+                "monitoring".to_string(),
+                if self.monitoring { "1" } else { "0" }.to_string(),
+            ),
+        ]);
+        if let Some(subnet) = &self.subnet {
+            properties.insert("subnet".to_string(), subnet.clone());
+        }
+        if let Some(key) = &self.public_key {
+            properties.insert("key".to_string(), key.clone());
+        }
+
+        // The short ID keeps service names unique when two hosts share a name
+        let short_id = &self.instance_id[..self.instance_id.len().min(8)];
+        ServiceInfo::new(
+            PEER_SERVICE_TYPE,
+            &format!("{}-{}", self.instance_name, short_id),
+            &format!("{}.local.", local_hostname()),
+            IpAddr::V4(self.ip),
+            self.port,
+            properties,
+        )
+        .context("Failed to build mDNS service record")
+    }
+}
+
+/// Another instance seen on the LAN
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PeerInfo {
+    pub instance_id: String,
+    pub instance_name: String,
+    pub hostname: String,
+    pub ip: String,
+    pub port: u16,
+    pub version: String,
+    pub public_key: Option<String>,
+    pub subnet: Option<String>,
+    pub monitoring: bool,
+    pub last_seen: DateTime<Utc>,
+}
+
+fn parse_peer(info: &ServiceInfo) -> Option<PeerInfo> {
+    let property = |key: &str| {
+        info.get_property_val_str(key)
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(str::to_string)
+    };
+
+    Some(PeerInfo {
+        instance_id: property("id")?,
+        instance_name: property("name").unwrap_or_default(),
+        hostname: info.get_hostname().trim_end_matches('.').to_string(),
+        ip: info.get_addresses_v4().into_iter().next()?.to_string(),
+        port: info.get_port(),
+        version: property("version").unwrap_or_default(),
+        public_key: property("key"),
+        subnet: property("subnet"),
+        monitoring: property("monitoring").as_deref() == Some("1"),
+        last_seen: Utc::now(),
+    })
+}
+
+fn local_hostname() -> String {
+    whoami::fallible::hostname()
+        .ok()
+        .map(|name| name.split('.').next().unwrap_or_default().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "netmapper".to_string())
+}
+
+/// Registered mDNS announcement plus a live table of peers
+///
+/// The announcement is withdrawn when this is dropped.
+pub struct PeerPresence {
+    daemon: ServiceDaemon,
+    fullname: String,
+    announcement: Mutex<Announcement>,
+    peers: PeerTable,
+    sharing: Option<Sharing>,
+}
+
+/// State for exchanging scan results with trusted peers
+struct Sharing {
+    server: ShareServer,
+    signer: ExportSigner,
+    trusted: Vec<TrustedPeer>,
+}
+
+impl PeerPresence {
+    /// Register the announcement and start browsing for peers
+    pub fn start(announcement: Announcement) -> Result<Self> {
+        let daemon = ServiceDaemon::new().context("Failed to start mDNS daemon")?;
+        let info = announcement.service_info()?;
+        let fullname = info.get_fullname().to_string();
+        daemon
+            .register(info)
+            .context("Failed to register mDNS service")?;
+
+        let receiver = daemon
+            .browse(PEER_SERVICE_TYPE)
+            .context("Failed to browse for peers")?;
+        let peers: PeerTable = Arc::default();
+        let table = Arc::clone(&peers);
+        let own_id = announcement.instance_id.clone();
+
+        // Ends when the daemon shuts down and the channel closes
+        std::thread::Builder::new()
+            .name("peer-browse".to_string())
+            .spawn(move || {
+                while let Ok(event) = receiver.recv() {
+                    match event {
+                        ServiceEvent::ServiceResolved(info) => {
+                            let Some(peer) = parse_peer(&info) else {
+                                continue;
+                            };
+                            if peer.instance_id == own_id {
+                                continue;
+                            }
+                            tracing::debug!(
+                                "Peer instance {} at {} (monitoring: {})",
+                                peer.instance_name,
+                                peer.ip,
+                                peer.monitoring
+                            );
+                            if let Ok(mut peers) = table.write() {
+                                peers.insert(info.get_fullname().to_string(), peer);
+                            }
+                        }
+                        ServiceEvent::ServiceRemoved(_, fullname) => {
+                            if let Ok(mut peers) = table.write() {
+                                peers.remove(&fullname);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            })
+            .context("Failed to start peer browser")?;
+
+        tracing::info!(
+            "Advertising instance '{}' via mDNS on {}",
+            announcement.instance_name,
+            announcement.ip
+        );
+        Ok(Self {
+            daemon,
+            fullname,
+            announcement: Mutex::new(announcement),
+            peers,
+            sharing: None,
+        })
+    }
+
+    /// Serve scan results to trusted peers on the advertised port
+    ///
+    /// `signer` should be the install's export signer, whose public key is
+    /// what peers pin.
+    pub fn with_sharing(mut self, signer: ExportSigner, trusted: Vec<TrustedPeer>) -> Result<Self> {
+        let port = self
+            .announcement()
+            .map(|a| a.port)
+            .unwrap_or(PEER_DEFAULT_PORT);
+        let server = ShareServer::start(
+            SocketAddr::from((Ipv4Addr::UNSPECIFIED, port)),
+            trusted.clone(),
+        )?;
+        tracing::info!(
+            "Serving scan shares to trusted peers on {}",
+            server.local_addr()
+        );
+        self.sharing = Some(Sharing {
+            server,
+            signer,
+            trusted,
+        });
+        Ok(self)
+    }
+
+    /// Trusted peers advertising the given subnet with their pinned key
+    pub fn trusted_peers_on(&self, subnet: &str) -> Vec<PeerInfo> {
+        let Some(sharing) = &self.sharing else {
+            return Vec::new();
+        };
+        self.peers()
+            .into_iter()
+            .filter(|peer| peer.subnet.as_deref() == Some(subnet))
+            .filter(|peer| {
+                sharing.trusted.iter().any(|trusted| {
+                    trusted.instance_id == peer.instance_id
+                        && peer.public_key.as_deref() == Some(trusted.public_key.as_str())
+                })
+            })
+            .collect()
+    }
+
+    /// Split a subnet with trusted peers and collect their recent results
+    ///
+    /// Returns `None` when sharing is off or no trusted peer scans the subnet.
+    pub async fn plan_shared_scan(&self, subnet: &str, ips: &[Ipv4Addr]) -> Option<SharedScanPlan> {
+        let sharing = self.sharing.as_ref()?;
+        let peers = self.trusted_peers_on(subnet);
+        if peers.is_empty() {
+            return None;
+        }
+        let own_id = self.announcement()?.instance_id;
+
+        let mut shares = Vec::new();
+        for peer in &peers {
+            let Some(trusted) = sharing
+                .trusted
+                .iter()
+                .find(|t| t.instance_id == peer.instance_id)
+            else {
+                continue;
+            };
+            match fetch_share(peer, &own_id, subnet, &sharing.signer).await {
+                Ok(Some(signed)) => match signed.verify(&trusted.public_key) {
+                    Ok(share) => shares.push((peer.clone(), share)),
+                    Err(e) => tracing::warn!("Rejected scan share from {}: {}", peer.ip, e),
+                },
+                Ok(None) => {}
+                Err(e) => tracing::debug!("No scan share from {}: {}", peer.ip, e),
+            }
+        }
+
+        let plan = SharedScanPlan::from_shares(&own_id, subnet, ips, &peers, shares);
+        tracing::info!(
+            "Distributed scan: probing {} of {} addresses, {} peer result(s) used",
+            plan.targets.len(),
+            ips.len(),
+            plan.contributors.len()
+        );
+        Some(plan)
+    }
+
+    /// Offer the hosts found among `targets` to trusted peers
+    pub fn publish_share(
+        &self,
+        subnet: &str,
+        targets: &[Ipv4Addr],
+        hosts: &[HostInfo],
+    ) -> Result<()> {
+        let (Some(sharing), Some(announcement)) = (&self.sharing, self.announcement()) else {
+            return Ok(());
+        };
+        let probed: std::collections::HashSet<String> =
+            targets.iter().map(|ip| ip.to_string()).collect();
+        let share = ScanShare {
+            instance_id: announcement.instance_id,
+            subnet: subnet.to_string(),
+            targets: targets.to_vec(),
+            hosts: hosts
+                .iter()
+                .filter(|h| probed.contains(&h.ip) || h.discovery_method == "LOCAL")
+                .cloned()
+                .collect(),
+            completed_at: Utc::now(),
+        };
+        sharing
+            .server
+            .publish(SignedShare::sign(&share, &sharing.signer)?);
+        Ok(())
+    }
+
+    /// Re-announce with a new subnet and monitoring state
+    pub fn update(&self, subnet: Option<String>, monitoring: bool) -> Result<()> {
+        let mut announcement = self
+            .announcement
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Peer announcement lock poisoned"))?;
+        announcement.subnet = subnet;
+        announcement.monitoring = monitoring;
+        self.daemon
+            .register(announcement.service_info()?)
+            .context("Failed to update mDNS service")
+    }
+
+    /// What this instance currently advertises
+    pub fn announcement(&self) -> Option<Announcement> {
+        self.announcement.lock().ok().map(|a| a.clone())
+    }
+
+    /// Peers seen so far, by name
+    pub fn peers(&self) -> Vec<PeerInfo> {
+        let mut peers: Vec<PeerInfo> = self
+            .peers
+            .read()
+            .map(|peers| peers.values().cloned().collect())
+            .unwrap_or_default();
+        peers.sort_by(|a, b| a.instance_name.cmp(&b.instance_name));
+        peers
+    }
+
+    /// Peers currently monitoring the given subnet
+    pub fn peers_monitoring(&self, subnet: &str) -> Vec<PeerInfo> {
+        self.peers()
+            .into_iter()
+            .filter(|peer| peer.monitoring && peer.subnet.as_deref() == Some(subnet))
+            .collect()
+    }
+}
+
+impl Drop for PeerPresence {
+    fn drop(&mut self) {
+        let _ = self.daemon.unregister(&self.fullname);
+        let _ = self.daemon.shutdown();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Database;
+
+    #[test]
+    fn test_peer_settings_and_announcement() {
+        let db = Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
+
+        let settings = load_peer_settings(&conn).unwrap();
+        assert!(!settings.enabled);
+        assert_eq!(settings.port, PEER_DEFAULT_PORT);
+
+        let id = instance_id(&conn).unwrap();
+        assert_eq!(id.len(), 32);
+        assert_eq!(instance_id(&conn).unwrap(), id);
+
+        let settings = PeerSettings {
+            enabled: true,
+            instance_name: Some("lab-box".to_string()),
+            port: 47_900,
+            ..Default::default()
+        };
+        save_peer_settings(&conn, &settings).unwrap();
+        assert_eq!(load_peer_settings(&conn).unwrap(), settings);
+        let invalid = PeerSettings {
+            instance_name: Some("lab.box".to_string()),
+            ..settings.clone()
+        };
+        assert!(save_peer_settings(&conn, &invalid).is_err());
+
+        let mut announcement = Announcement::new(
+            id.clone(),
+            &settings,
+            Ipv4Addr::new(192, 168, 1, 20),
+            Some("192.168.1.0/24".to_string()),
+        );
+        announcement.monitoring = true;
+        announcement.public_key = Some("a2V5".to_string());
+        let info = announcement.service_info().unwrap();
+        assert!(info.get_fullname().starts_with("lab-box-"));
+
+        let peer = parse_peer(&info).unwrap();
+        assert_eq!(peer.instance_id, id);
+        assert_eq!(peer.instance_name, "lab-box");
+        assert_eq!(peer.ip, "192.168.1.20");
+        assert_eq!(peer.port, 47_900);
+        assert_eq!(peer.subnet.as_deref(), Some("192.168.1.0/24"));
+        assert!(peer.monitoring);
+        assert_eq!(peer.public_key.as_deref(), Some("a2V5"));
+    }
+}
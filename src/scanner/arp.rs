@@ -1,4 +1,12 @@
 //! Active ARP scanning with adaptive timing
+//!
+//! Every MAC answering for an address is kept, so duplicate IPs (two devices
+//! configured with the same address) show up as conflicts instead of one of
+//! them silently disappearing.
+//!
+//! A diagnostics mode records every request sent and reply received with
+//! timestamps and summarizes reply latency, for tracking down switches that
+//! rate-limit broadcasts and make scans miss hosts intermittently.
 
 use anyhow::{anyhow, Result};
 use ipnetwork::Ipv4Network;
@@ -7,15 +15,18 @@ use pnet::packet::arp::{ArpHardwareTypes, ArpOperations, ArpPacket, MutableArpPa
 use pnet::packet::ethernet::{EtherTypes, EthernetPacket, MutableEthernetPacket};
 use pnet::packet::Packet;
 use pnet::util::MacAddr;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::net::Ipv4Addr;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
-use crate::config::{ARP_CHECK_INTERVAL_MS, ARP_IDLE_TIMEOUT_MS, ARP_MAX_WAIT_MS, ARP_ROUNDS};
+use crate::config::{
+    ARP_CHECK_INTERVAL_MS, ARP_IDLE_TIMEOUT_MS, ARP_MAX_WAIT_MS, ARP_ROUNDS, SUBNET_CHUNK_PREFIX,
+};
 use crate::models::InterfaceInfo;
-use crate::network::is_special_address;
+use crate::network::{is_special_address, subnet_chunks};
 
 /// Broadcast MAC address for ARP requests
 const BROADCAST_MAC: MacAddr = MacAddr(0xff, 0xff, 0xff, 0xff, 0xff, 0xff);
@@ -62,12 +73,68 @@ fn create_arp_request(
     Ok(buffer)
 }
 
+/// Packet log shared with the receiver thread in diagnostics mode
+type PacketLog = Arc<std::sync::Mutex<Vec<ArpPacketRecord>>>;
+
+/// Hosts found by an ARP scan
+#[derive(Debug, Clone, Default)]
+pub struct ArpScan {
+    /// First MAC that answered for each address
+    pub hosts: HashMap<Ipv4Addr, MacAddr>,
+    /// Further MACs that answered for an address in `hosts` (IP conflicts)
+    pub conflicts: HashMap<Ipv4Addr, Vec<MacAddr>>,
+    /// ARP requests put on the wire, over all rounds
+    pub requests_sent: usize,
+}
+
+impl ArpScan {
+    /// Record a reply, returning whether the address is new
+    fn record(&mut self, ip: Ipv4Addr, mac: MacAddr) -> bool {
+        match self.hosts.get(&ip) {
+            None => {
+                self.hosts.insert(ip, mac);
+                true
+            }
+            Some(first) if *first != mac => {
+                let others = self.conflicts.entry(ip).or_default();
+                if !others.contains(&mac) {
+                    others.push(mac);
+                }
+                false
+            }
+            Some(_) => false,
+        }
+    }
+
+    fn merge(&mut self, other: ArpScan) {
+        self.requests_sent += other.requests_sent;
+        for (ip, mac) in other.hosts {
+            self.record(ip, mac);
+        }
+        for (ip, macs) in other.conflicts {
+            for mac in macs {
+                self.record(ip, mac);
+            }
+        }
+    }
+}
+
 /// Performs Adaptive ARP scan with early termination
 pub fn active_arp_scan(
     interface: &InterfaceInfo,
     target_ips: &[Ipv4Addr],
     subnet: &Ipv4Network,
 ) -> Result<HashMap<Ipv4Addr, MacAddr>> {
+    Ok(arp_scan(interface, target_ips, subnet, None)?.hosts)
+}
+
+/// ARP scan, logging packets relative to `log.1` when `log` is set
+fn arp_scan(
+    interface: &InterfaceInfo,
+    target_ips: &[Ipv4Addr],
+    subnet: &Ipv4Network,
+    log: Option<(&PacketLog, Instant)>,
+) -> Result<ArpScan> {
     log_stderr!(
         "Phase 1: Active ARP scanning {} hosts (adaptive timing)...",
         target_ips.len()
@@ -99,8 +166,7 @@ pub fn active_arp_scan(
         }
     };
 
-    let discovered: Arc<std::sync::Mutex<HashMap<Ipv4Addr, MacAddr>>> =
-        Arc::new(std::sync::Mutex::new(HashMap::new()));
+    let discovered: Arc<std::sync::Mutex<ArpScan>> = Arc::default();
     let host_count = Arc::new(AtomicUsize::new(0));
     let scan_start = Instant::now();
 
@@ -110,6 +176,7 @@ pub fn active_arp_scan(
     let discovered_clone = Arc::clone(&discovered);
     let host_count_clone = Arc::clone(&host_count);
     let subnet_clone = *subnet;
+    let log_clone = log.map(|(packets, start)| (Arc::clone(packets), start));
 
     // Start receiver thread
     let receiver_handle = std::thread::spawn(move || {
@@ -125,11 +192,23 @@ pub fn active_arp_scan(
                                     let sender_ip = arp.get_sender_proto_addr();
                                     let sender_mac = arp.get_sender_hw_addr();
 
+                                    if let Some((packets, start)) = &log_clone {
+                                        if let Ok(mut packets) = packets.lock() {
+                                            packets.push(ArpPacketRecord {
+                                                offset_us: start.elapsed().as_micros() as u64,
+                                                kind: ArpPacketKind::Reply,
+                                                sender_ip,
+                                                sender_mac: sender_mac.to_string(),
+                                                target_ip: arp.get_target_proto_addr(),
+                                            });
+                                        }
+                                    }
+
                                     if subnet_clone.contains(sender_ip)
                                         && !is_special_address(sender_ip, &subnet_clone)
                                     {
-                                        let mut map = match discovered_clone.lock() {
-                                            Ok(map) => map,
+                                        let mut scan = match discovered_clone.lock() {
+                                            Ok(scan) => scan,
                                             Err(_) => {
                                                 log_stderr!(
                                                     "ARP receiver map lock poisoned; stopping receiver thread"
@@ -137,10 +216,7 @@ pub fn active_arp_scan(
                                                 break;
                                             }
                                         };
-                                        if let std::collections::hash_map::Entry::Vacant(e) =
-                                            map.entry(sender_ip)
-                                        {
-                                            e.insert(sender_mac);
+                                        if scan.record(sender_ip, sender_mac) {
                                             host_count_clone.fetch_add(1, Ordering::SeqCst);
                                         }
                                     }
@@ -160,18 +236,19 @@ pub fn active_arp_scan(
     std::thread::sleep(Duration::from_millis(10));
 
     // Adaptive ARP scan rounds
+    let mut requests_sent = 0;
     for round in 1..=ARP_ROUNDS {
         let round_start = Instant::now();
         let initial_count = host_count.load(Ordering::SeqCst);
 
         // Get remaining IPs to scan
         let remaining: Vec<Ipv4Addr> = {
-            let discovered_map = discovered
+            let scan = discovered
                 .lock()
                 .map_err(|_| anyhow!("ARP discovered-host map lock poisoned"))?;
             target_ips
                 .iter()
-                .filter(|ip| !discovered_map.contains_key(ip))
+                .filter(|ip| !scan.hosts.contains_key(ip))
                 .copied()
                 .collect()
         };
@@ -193,7 +270,21 @@ pub fn active_arp_scan(
         for target_ip in &remaining {
             match create_arp_request(interface.mac, interface.ip, *target_ip) {
                 Ok(packet) => {
-                    let _ = tx.send_to(&packet, None);
+                    let sent = tx.send_to(&packet, None);
+                    if let Some(Ok(())) = &sent {
+                        requests_sent += 1;
+                    }
+                    if let (Some((packets, start)), Some(Ok(()))) = (log, sent) {
+                        if let Ok(mut packets) = packets.lock() {
+                            packets.push(ArpPacketRecord {
+                                offset_us: start.elapsed().as_micros() as u64,
+                                kind: ArpPacketKind::Request,
+                                sender_ip: interface.ip,
+                                sender_mac: interface.mac.to_string(),
+                                target_ip: *target_ip,
+                            });
+                        }
+                    }
                 }
                 Err(e) => {
                     log_stderr!("Failed to create ARP request for {}: {}", target_ip, e);
@@ -244,18 +335,340 @@ pub fn active_arp_scan(
         return Err(anyhow!("ARP receiver thread panicked"));
     }
 
-    let map = discovered
+    let scan = discovered
         .lock()
         .map_err(|_| anyhow!("ARP discovered-host map lock poisoned"))?;
-    for (ip, mac) in map.iter() {
+    for (ip, mac) in scan.hosts.iter() {
         log_stderr!("[ARP] Found: {} -> {}", ip, mac);
     }
+    for (ip, macs) in scan.conflicts.iter() {
+        log_stderr!("[ARP] IP conflict: {} also claimed by {:?}", ip, macs);
+    }
 
     log_stderr!(
         "Phase 1 complete: {} hosts found in {:?}",
-        map.len(),
+        scan.hosts.len(),
         scan_start.elapsed()
     );
 
-    Ok(map.clone())
+    Ok(ArpScan {
+        requests_sent,
+        ..scan.clone()
+    })
+}
+
+/// Progress of a chunked ARP scan, reported after each chunk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArpChunkProgress {
+    /// Chunks completed so far (1-based)
+    pub chunk: usize,
+    pub chunks: usize,
+    /// Hosts found in all chunks so far
+    pub hosts_found: usize,
+}
+
+impl ArpChunkProgress {
+    /// Completed share of the scan, 0-100
+    pub fn percent(&self) -> u8 {
+        (self.chunk * 100 / self.chunks.max(1)) as u8
+    }
+}
+
+/// ARP scan of a large target list, one /24 chunk at a time
+///
+/// Avoids blasting tens of thousands of requests at once on /16 networks.
+/// Targets that fit in a single chunk are scanned exactly like
+/// `active_arp_scan`. `on_chunk` is called after every chunk.
+pub fn chunked_arp_scan(
+    interface: &InterfaceInfo,
+    target_ips: &[Ipv4Addr],
+    subnet: &Ipv4Network,
+    mut on_chunk: impl FnMut(ArpChunkProgress),
+) -> Result<ArpScan> {
+    let chunks = subnet_chunks(target_ips);
+    if chunks.len() > 1 {
+        log_stderr!(
+            "Scanning {} hosts in {} chunks of /{}",
+            target_ips.len(),
+            chunks.len(),
+            SUBNET_CHUNK_PREFIX
+        );
+    }
+
+    let mut discovered = ArpScan::default();
+    for (index, chunk) in chunks.iter().enumerate() {
+        // Late replies to an earlier chunk are still in the subnet and kept
+        discovered.merge(arp_scan(interface, chunk, subnet, None)?);
+        on_chunk(ArpChunkProgress {
+            chunk: index + 1,
+            chunks: chunks.len(),
+            hosts_found: discovered.hosts.len(),
+        });
+    }
+
+    Ok(discovered)
+}
+
+/// Direction of a logged ARP packet
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArpPacketKind {
+    /// Request sent by the scanner
+    Request,
+    /// Reply received from the network
+    Reply,
+}
+
+/// An ARP packet seen during a diagnostics run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArpPacketRecord {
+    /// Microseconds since the run started
+    pub offset_us: u64,
+    pub kind: ArpPacketKind,
+    pub sender_ip: Ipv4Addr,
+    pub sender_mac: String,
+    pub target_ip: Ipv4Addr,
+}
+
+/// Reply latency bucket (`upper_ms` is `None` for the open-ended last one)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LatencyBucket {
+    pub upper_ms: Option<u64>,
+    pub count: usize,
+}
+
+/// Distribution of request-to-reply latencies
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LatencySummary {
+    pub samples: usize,
+    pub min_ms: f64,
+    pub p50_ms: f64,
+    pub p90_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+    pub histogram: Vec<LatencyBucket>,
+}
+
+const LATENCY_BUCKETS_MS: [u64; 7] = [1, 5, 10, 50, 100, 500, 1000];
+
+impl LatencySummary {
+    fn from_samples(mut samples_us: Vec<u64>) -> Self {
+        if samples_us.is_empty() {
+            return Self::default();
+        }
+        samples_us.sort_unstable();
+
+        // Nearest-rank percentile
+        let percentile = |p: usize| {
+            let rank = (p * samples_us.len()).div_ceil(100).max(1);
+            samples_us[rank - 1] as f64 / 1000.0
+        };
+        let mut histogram: Vec<LatencyBucket> = LATENCY_BUCKETS_MS
+            .iter()
+            .map(|&upper| Some(upper))
+            .chain(std::iter::once(None))
+            .map(|upper_ms| LatencyBucket { upper_ms, count: 0 })
+            .collect();
+        for &sample in &samples_us {
+            let bucket = LATENCY_BUCKETS_MS
+                .iter()
+                .position(|&upper| sample < upper * 1000)
+                .unwrap_or(LATENCY_BUCKETS_MS.len());
+            histogram[bucket].count += 1;
+        }
+
+        Self {
+            samples: samples_us.len(),
+            min_ms: samples_us[0] as f64 / 1000.0,
+            p50_ms: percentile(50),
+            p90_ms: percentile(90),
+            p99_ms: percentile(99),
+            max_ms: samples_us[samples_us.len() - 1] as f64 / 1000.0,
+            histogram,
+        }
+    }
+}
+
+/// Packet log and summary of an ARP diagnostics run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArpDiagnostics {
+    pub duration_ms: u64,
+    pub targets: usize,
+    pub requests_sent: usize,
+    pub replies_received: usize,
+    pub hosts_found: usize,
+    /// Targets that were asked but never answered
+    pub unanswered: Vec<Ipv4Addr>,
+    /// Replies beyond the first from the same address
+    pub duplicate_replies: usize,
+    /// Replies from addresses no request was sent to
+    pub unsolicited_replies: usize,
+    /// Latency from the latest request to an address to its first reply
+    pub latency: LatencySummary,
+    pub packets: Vec<ArpPacketRecord>,
+}
+
+impl ArpDiagnostics {
+    /// Summarize a packet log for the given targets
+    pub fn from_packets(
+        mut packets: Vec<ArpPacketRecord>,
+        targets: &[Ipv4Addr],
+        duration: Duration,
+    ) -> Self {
+        packets.sort_by_key(|p| p.offset_us);
+
+        let mut last_request: HashMap<Ipv4Addr, u64> = HashMap::new();
+        let mut replies: HashMap<Ipv4Addr, usize> = HashMap::new();
+        let mut latencies = Vec::new();
+        let mut requests_sent = 0;
+        let mut unsolicited_replies = 0;
+
+        for packet in &packets {
+            match packet.kind {
+                ArpPacketKind::Request => {
+                    requests_sent += 1;
+                    last_request.insert(packet.target_ip, packet.offset_us);
+                }
+                ArpPacketKind::Reply => {
+                    let count = replies.entry(packet.sender_ip).or_default();
+                    *count += 1;
+                    match last_request.get(&packet.sender_ip) {
+                        Some(sent) if *count == 1 => {
+                            latencies.push(packet.offset_us.saturating_sub(*sent))
+                        }
+                        Some(_) => {}
+                        None => unsolicited_replies += 1,
+                    }
+                }
+            }
+        }
+
+        let answered: HashSet<&Ipv4Addr> = replies.keys().collect();
+        let unanswered = targets
+            .iter()
+            .filter(|ip| last_request.contains_key(ip) && !answered.contains(ip))
+            .copied()
+            .collect();
+        let hosts_found = targets.iter().filter(|ip| answered.contains(ip)).count();
+
+        Self {
+            duration_ms: duration.as_millis() as u64,
+            targets: targets.len(),
+            requests_sent,
+            replies_received: replies.values().sum(),
+            hosts_found,
+            unanswered,
+            duplicate_replies: replies.values().map(|count| count - 1).sum(),
+            unsolicited_replies,
+            latency: LatencySummary::from_samples(latencies),
+            packets,
+        }
+    }
+}
+
+/// ARP scan in diagnostics mode: logs every request and reply
+///
+/// Scans chunk by chunk like `chunked_arp_scan`, with all timestamps
+/// relative to the start of the run.
+pub fn arp_scan_diagnostics(
+    interface: &InterfaceInfo,
+    target_ips: &[Ipv4Addr],
+    subnet: &Ipv4Network,
+) -> Result<ArpDiagnostics> {
+    let log = PacketLog::default();
+    let start = Instant::now();
+    for chunk in subnet_chunks(target_ips) {
+        arp_scan(interface, chunk, subnet, Some((&log, start)))?;
+    }
+
+    let packets = std::mem::take(
+        &mut *log
+            .lock()
+            .map_err(|_| anyhow!("ARP packet log lock poisoned"))?,
+    );
+    Ok(ArpDiagnostics::from_packets(
+        packets,
+        target_ips,
+        start.elapsed(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn packet(offset_us: u64, kind: ArpPacketKind, ip: [u8; 4]) -> ArpPacketRecord {
+        let (sender_ip, target_ip) = match kind {
+            ArpPacketKind::Request => (Ipv4Addr::new(192, 168, 1, 2), Ipv4Addr::from(ip)),
+            ArpPacketKind::Reply => (Ipv4Addr::from(ip), Ipv4Addr::new(192, 168, 1, 2)),
+        };
+        ArpPacketRecord {
+            offset_us,
+            kind,
+            sender_ip,
+            sender_mac: "AA:BB:CC:DD:EE:FF".to_string(),
+            target_ip,
+        }
+    }
+
+    #[test]
+    fn test_arp_scan_keeps_conflicting_macs() {
+        let ip = Ipv4Addr::new(192, 168, 1, 50);
+        let first = MacAddr(0xaa, 0, 0, 0, 0, 1);
+        let second = MacAddr(0xbb, 0, 0, 0, 0, 2);
+
+        let mut scan = ArpScan::default();
+        assert!(scan.record(ip, first));
+        assert!(!scan.record(ip, first));
+        assert!(!scan.record(ip, second));
+        assert!(!scan.record(ip, second));
+        assert_eq!(scan.hosts.get(&ip), Some(&first));
+        assert_eq!(scan.conflicts.get(&ip), Some(&vec![second]));
+
+        // A later chunk's reply for the same address is a conflict too
+        let mut later = ArpScan::default();
+        later.record(ip, MacAddr(0xcc, 0, 0, 0, 0, 3));
+        scan.merge(later);
+        assert_eq!(scan.conflicts[&ip].len(), 2);
+        assert_eq!(scan.hosts.len(), 1);
+    }
+
+    #[test]
+    fn test_arp_diagnostics_summary() {
+        use ArpPacketKind::{Reply, Request};
+
+        let targets = [
+            Ipv4Addr::new(192, 168, 1, 10),
+            Ipv4Addr::new(192, 168, 1, 11),
+            Ipv4Addr::new(192, 168, 1, 12),
+        ];
+        let packets = vec![
+            packet(0, Request, [192, 168, 1, 10]),
+            packet(10, Request, [192, 168, 1, 11]),
+            packet(20, Request, [192, 168, 1, 12]),
+            packet(2_000, Reply, [192, 168, 1, 10]),
+            packet(2_500, Reply, [192, 168, 1, 10]),
+            packet(150_010, Reply, [192, 168, 1, 11]),
+            packet(3_000, Reply, [192, 168, 1, 99]),
+        ];
+
+        let report = ArpDiagnostics::from_packets(packets, &targets, Duration::from_millis(1200));
+        assert_eq!(report.requests_sent, 3);
+        assert_eq!(report.replies_received, 4);
+        assert_eq!(report.hosts_found, 2);
+        assert_eq!(report.unanswered, vec![Ipv4Addr::new(192, 168, 1, 12)]);
+        assert_eq!(report.duplicate_replies, 1);
+        assert_eq!(report.unsolicited_replies, 1);
+        assert!(report
+            .packets
+            .windows(2)
+            .all(|w| w[0].offset_us <= w[1].offset_us));
+
+        let latency = &report.latency;
+        assert_eq!(latency.samples, 2);
+        assert_eq!((latency.min_ms, latency.max_ms), (2.0, 150.0));
+        assert_eq!(latency.p50_ms, 2.0);
+        let counts: Vec<usize> = latency.histogram.iter().map(|b| b.count).collect();
+        assert_eq!(counts, vec![0, 1, 0, 0, 0, 1, 0, 0]);
+    }
 }
@@ -0,0 +1,155 @@
+//! TLS certificate inspection
+//!
+//! Fetches the certificate served on open TLS ports (see
+//! [`TLS_CERT_PORTS`]) so expiry dates of internal services can be tracked.
+//! Any certificate is accepted: self-signed ones are the norm on a LAN and
+//! the point is to read them, not to trust them.
+
+use std::net::{SocketAddr, TcpStream};
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use x509_parser::prelude::{FromDer, X509Certificate};
+
+use crate::config::{TLS_CERT_PORTS, TLS_CERT_TIMEOUT};
+use crate::{log_warn, HostInfo};
+
+/// Certificate served by a device on one port
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TlsCertificate {
+    pub mac: String,
+    pub ip: String,
+    pub port: u16,
+    pub subject: String,
+    pub issuer: String,
+    pub not_before: DateTime<Utc>,
+    pub not_after: DateTime<Utc>,
+    /// SHA-256 of the DER encoding, hex
+    pub fingerprint: String,
+}
+
+impl TlsCertificate {
+    pub fn is_self_signed(&self) -> bool {
+        self.subject == self.issuer
+    }
+}
+
+/// Read the fields of a DER-encoded certificate
+pub fn parse_certificate(mac: &str, ip: &str, port: u16, der: &[u8]) -> Result<TlsCertificate> {
+    let (_, cert) = X509Certificate::from_der(der)
+        .map_err(|e| anyhow!("Invalid certificate from {}:{}: {}", ip, port, e))?;
+    let time = |asn1: i64| {
+        DateTime::from_timestamp(asn1, 0)
+            .with_context(|| format!("Certificate date out of range from {}:{}", ip, port))
+    };
+    let validity = cert.validity();
+    Ok(TlsCertificate {
+        mac: mac.to_string(),
+        ip: ip.to_string(),
+        port,
+        subject: cert.subject().to_string(),
+        issuer: cert.issuer().to_string(),
+        not_before: time(validity.not_before.timestamp())?,
+        not_after: time(validity.not_after.timestamp())?,
+        fingerprint: hex::encode(Sha256::digest(der)),
+    })
+}
+
+/// Fetch the certificate served on `ip:port` (blocking)
+pub fn fetch_certificate(mac: &str, ip: &str, port: u16) -> Result<TlsCertificate> {
+    let addr: SocketAddr = format!("{}:{}", ip, port)
+        .parse()
+        .with_context(|| format!("Invalid address {}:{}", ip, port))?;
+    let stream = TcpStream::connect_timeout(&addr, TLS_CERT_TIMEOUT)
+        .with_context(|| format!("Failed to connect to {}", addr))?;
+    stream.set_read_timeout(Some(TLS_CERT_TIMEOUT))?;
+    stream.set_write_timeout(Some(TLS_CERT_TIMEOUT))?;
+
+    let connector = native_tls::TlsConnector::builder()
+        .danger_accept_invalid_certs(true)
+        .danger_accept_invalid_hostnames(true)
+        .use_sni(false)
+        .build()
+        .context("Failed to create TLS connector")?;
+    let tls = connector
+        .connect(ip, stream)
+        .map_err(|e| anyhow!("TLS handshake with {} failed: {}", addr, e))?;
+    let der = tls
+        .peer_certificate()
+        .context("Failed to read peer certificate")?
+        .with_context(|| format!("{} sent no certificate", addr))?
+        .to_der()
+        .context("Failed to encode peer certificate")?;
+    parse_certificate(mac, ip, port, &der)
+}
+
+/// Fetch the certificates of every host's open TLS ports
+///
+/// Ports that fail the handshake (not TLS after all, or gone) are skipped.
+pub async fn inspect_certificates(hosts: &[HostInfo]) -> Vec<TlsCertificate> {
+    let mut handles = Vec::new();
+    for host in hosts {
+        for &port in host
+            .open_ports
+            .iter()
+            .filter(|port| TLS_CERT_PORTS.contains(port))
+        {
+            let (mac, ip) = (host.mac.clone(), host.ip.clone());
+            handles.push(tokio::task::spawn_blocking(move || {
+                fetch_certificate(&mac, &ip, port)
+            }));
+        }
+    }
+
+    let mut certificates = Vec::new();
+    for handle in handles {
+        match handle.await {
+            Ok(Ok(certificate)) => certificates.push(certificate),
+            Ok(Err(e)) => {
+                log_warn!("Certificate inspection failed: {:#}", e);
+            }
+            Err(e) => {
+                log_warn!("Certificate inspection task failed: {}", e);
+            }
+        }
+    }
+    certificates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::Engine;
+
+    /// Self-signed EC certificate for CN=nas.local
+    const NAS_CERT: &str = "\
+        MIIBfTCCASOgAwIBAgIULRgFYqpYinh2MQzHVc0vv/Bo950wCgYIKoZIzj0EAwIwFDESMBAGA1UE\
+        AwwJbmFzLmxvY2FsMB4XDTI2MTAxNzAzMzMwOFoXDTM2MTAxNDAzMzMwOFowFDESMBAGA1UEAwwJ\
+        bmFzLmxvY2FsMFkwEwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAE0bBW5Zkm4yisyl2yizO03NpfFZeF\
+        DkU+qMmYvlI2lwsU10rv7NC2qE14xPqSq6AoZ6/nETPpg1a0YGYGT0+qwaNTMFEwHQYDVR0OBBYE\
+        FDIaa3NZdo/ltchkNF0donOnGc9aMB8GA1UdIwQYMBaAFDIaa3NZdo/ltchkNF0donOnGc9aMA8G\
+        A1UdEwEB/wQFMAMBAf8wCgYIKoZIzj0EAwIDSAAwRQIgMwH+eey4WrFD6tGtHbEwNPhce5vtew/q\
+        wWJKRTUcmYYCIQDy430aZC5ZMg3baiG3bMtTvVuLQm//mbVwUOiWqdn7FQ==";
+
+    #[test]
+    fn test_parse_certificate() {
+        let der = base64::engine::general_purpose::STANDARD
+            .decode(NAS_CERT)
+            .unwrap();
+        let cert = parse_certificate("AA:BB:CC:00:00:10", "192.168.1.10", 5001, &der).unwrap();
+        assert_eq!(cert.subject, "CN=nas.local");
+        assert!(cert.is_self_signed());
+        assert_eq!(
+            cert.not_after,
+            "2036-10-14T03:33:08Z".parse::<DateTime<Utc>>().unwrap()
+        );
+        assert_eq!(
+            cert.fingerprint,
+            "8b83fc355635a32968655f415e31f0a16c4551704b605a4a6f8ed809b6b249ea"
+        );
+
+        assert!(parse_certificate("AA:BB:CC:00:00:10", "192.168.1.10", 5001, b"junk").is_err());
+    }
+}
@@ -11,6 +11,7 @@ use surge_ping::{Client, Config, IcmpPacket, PingIdentifier, PingSequence};
 use tokio::sync::{Mutex, Semaphore};
 
 use crate::config::{MAX_CONCURRENT_PINGS, PING_RETRIES, PING_TIMEOUT};
+use crate::models::HostState;
 
 /// Logs a message to stderr
 macro_rules! log_stderr {
@@ -57,8 +58,39 @@ pub fn guess_os_from_ttl(ttl: u8) -> String {
     }
 }
 
+/// Outcome of probing a single host
+#[derive(Debug, Clone)]
+enum PingOutcome {
+    /// Echo reply received
+    Reply(IcmpResult),
+    /// An ICMP error came back instead of a reply
+    Error(HostState),
+    /// Nothing came back
+    Timeout,
+}
+
+/// ICMP type for Destination Unreachable
+const ICMP_DEST_UNREACHABLE: u8 = 3;
+/// ICMP type for Time Exceeded
+const ICMP_TIME_EXCEEDED: u8 = 11;
+
+/// Interpret an ICMP error received in response to an echo request
+///
+/// "Administratively prohibited" unreachables (codes 9, 10 and 13) mean a
+/// firewall dropped the probe; other unreachables and TTL-exceeded mean the
+/// host could not be reached at all. Returns `None` for non-error types.
+pub fn classify_icmp_error(icmp_type: u8, icmp_code: u8) -> Option<HostState> {
+    match (icmp_type, icmp_code) {
+        (ICMP_DEST_UNREACHABLE, 9 | 10 | 13) => Some(HostState::Filtered),
+        (ICMP_DEST_UNREACHABLE, _) | (ICMP_TIME_EXCEEDED, _) => Some(HostState::Down),
+        _ => None,
+    }
+}
+
 /// Pings a single IP address with retries, returns duration and TTL
-async fn ping_host_with_retries(client: &Client, ip: Ipv4Addr) -> Option<IcmpResult> {
+///
+/// ICMP errors are definitive and end the retries early.
+async fn ping_host_with_retries(client: &Client, ip: Ipv4Addr) -> PingOutcome {
     let payload = [0u8; 56];
 
     for attempt in 0..PING_RETRIES {
@@ -70,28 +102,51 @@ async fn ping_host_with_retries(client: &Client, ip: Ipv4Addr) -> Option<IcmpRes
             .ping(PingSequence(attempt as u16), &payload)
             .await
         {
-            Ok((packet, _rtt)) => {
-                let ttl = match packet {
-                    IcmpPacket::V4(p) => p.get_ttl(),
-                    IcmpPacket::V6(_) => None,
-                };
-                return Some(IcmpResult {
+            Ok((IcmpPacket::V4(packet), _rtt)) => {
+                let error = classify_icmp_error(packet.get_icmp_type().0, packet.get_icmp_code().0);
+                if let Some(state) = error {
+                    return PingOutcome::Error(state);
+                }
+                return PingOutcome::Reply(IcmpResult {
+                    duration: start.elapsed(),
+                    ttl: packet.get_ttl(),
+                });
+            }
+            Ok((IcmpPacket::V6(_), _rtt)) => {
+                return PingOutcome::Reply(IcmpResult {
                     duration: start.elapsed(),
-                    ttl,
+                    ttl: None,
                 });
             }
             Err(_) => continue,
         }
     }
-    None
+    PingOutcome::Timeout
+}
+
+/// Result of an ICMP probe scan
+#[derive(Debug, Clone, Default)]
+pub struct IcmpScan {
+    /// Hosts that answered with an echo reply
+    pub replies: HashMap<Ipv4Addr, IcmpResult>,
+    /// Reachability of every probed host
+    pub states: HashMap<Ipv4Addr, HostState>,
 }
 
 /// Performs ICMP scan on discovered hosts to get response times and TTL
 pub async fn icmp_scan(
     arp_hosts: &HashMap<Ipv4Addr, MacAddr>,
 ) -> Result<HashMap<Ipv4Addr, IcmpResult>> {
+    Ok(icmp_probe_scan(arp_hosts).await?.replies)
+}
+
+/// Performs ICMP scan and classifies hosts that did not reply
+///
+/// The hosts were found by ARP, so one that stays silent is on the LAN but
+/// dropping ICMP and is recorded as filtered rather than down.
+pub async fn icmp_probe_scan(arp_hosts: &HashMap<Ipv4Addr, MacAddr>) -> Result<IcmpScan> {
     if arp_hosts.is_empty() {
-        return Ok(HashMap::new());
+        return Ok(IcmpScan::default());
     }
 
     log_stderr!(
@@ -107,12 +162,12 @@ pub async fn icmp_scan(
                 "ICMP client unavailable ({}), skipping latency measurement",
                 e
             );
-            return Ok(HashMap::new());
+            return Ok(IcmpScan::default());
         }
     };
 
     let semaphore = Arc::new(Semaphore::new(MAX_CONCURRENT_PINGS));
-    let results = Arc::new(Mutex::new(HashMap::new()));
+    let results = Arc::new(Mutex::new(IcmpScan::default()));
 
     let mut handles = Vec::new();
 
@@ -130,9 +185,19 @@ pub async fn icmp_scan(
                 }
             };
 
-            if let Some(icmp_result) = ping_host_with_retries(&client, ip).await {
-                let mut res = results.lock().await;
-                res.insert(ip, icmp_result);
+            let outcome = ping_host_with_retries(&client, ip).await;
+            let mut res = results.lock().await;
+            match outcome {
+                PingOutcome::Reply(icmp_result) => {
+                    res.replies.insert(ip, icmp_result);
+                    res.states.insert(ip, HostState::Up);
+                }
+                PingOutcome::Error(state) => {
+                    res.states.insert(ip, state);
+                }
+                PingOutcome::Timeout => {
+                    res.states.insert(ip, HostState::Filtered);
+                }
             }
         });
 
@@ -146,7 +211,62 @@ pub async fn icmp_scan(
     }
 
     let res = results.lock().await;
-    log_stderr!("Phase 2 complete: {} hosts responded to ICMP", res.len());
+    let filtered = res
+        .states
+        .values()
+        .filter(|state| **state == HostState::Filtered)
+        .count();
+    log_stderr!(
+        "Phase 2 complete: {} hosts responded to ICMP, {} filtered",
+        res.replies.len(),
+        filtered
+    );
 
     Ok(res.clone())
 }
+
+/// Ping one address `count` times, one echo request each
+///
+/// Returns the round-trip time of each reply, `None` for requests that
+/// went unanswered (or drew an ICMP error), so loss and latency can both
+/// be read from the result.
+pub async fn ping_series(ip: Ipv4Addr, count: u32) -> Result<Vec<Option<Duration>>> {
+    let client = Client::new(&Config::default())?;
+    let payload = [0u8; 56];
+    let mut pinger = client
+        .pinger(IpAddr::V4(ip), PingIdentifier(rand_id()))
+        .await;
+    pinger.timeout(PING_TIMEOUT);
+
+    let mut samples = Vec::with_capacity(count as usize);
+    for seq in 0..count {
+        let sample = match pinger.ping(PingSequence(seq as u16), &payload).await {
+            Ok((IcmpPacket::V4(packet), rtt)) => {
+                classify_icmp_error(packet.get_icmp_type().0, packet.get_icmp_code().0)
+                    .is_none()
+                    .then_some(rtt)
+            }
+            Ok((IcmpPacket::V6(_), rtt)) => Some(rtt),
+            Err(_) => None,
+        };
+        samples.push(sample);
+    }
+    Ok(samples)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_icmp_error_classification() {
+        // Communication administratively prohibited
+        assert_eq!(classify_icmp_error(3, 13), Some(HostState::Filtered));
+        assert_eq!(classify_icmp_error(3, 10), Some(HostState::Filtered));
+        // Host unreachable, TTL exceeded in transit
+        assert_eq!(classify_icmp_error(3, 1), Some(HostState::Down));
+        assert_eq!(classify_icmp_error(11, 0), Some(HostState::Down));
+        // Echo reply is not an error
+        assert_eq!(classify_icmp_error(0, 0), None);
+    }
+}
@@ -0,0 +1,297 @@
+//! Active Directory / LDAP enrichment
+//!
+//! Matches device hostnames to AD computer objects and records the operating
+//! system, OU and last logon for each match. Devices with a computer object
+//! are treated as managed; the rest are unmanaged.
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use ldap3::{ldap_escape, LdapConnAsync, LdapConnSettings, Scope, SearchEntry};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::config::{LDAP_BATCH_SIZE, LDAP_TIMEOUT};
+use crate::database::{queries, Database, DirectoryComputer};
+
+/// Attributes read from each computer object
+const COMPUTER_ATTRIBUTES: &[&str] = &[
+    "cn",
+    "dNSHostName",
+    "operatingSystem",
+    "operatingSystemVersion",
+    "lastLogonTimestamp",
+    "userAccountControl",
+];
+
+/// `userAccountControl` flag for disabled accounts
+const UAC_ACCOUNTDISABLE: u32 = 0x0002;
+
+/// Seconds between the FILETIME epoch (1601-01-01) and the Unix epoch
+const FILETIME_UNIX_OFFSET_SECS: i64 = 11_644_473_600;
+
+/// Connection details for the directory server
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LdapConfig {
+    /// e.g. `ldap://dc01.corp.example:389` or `ldaps://dc01.corp.example`
+    pub url: String,
+    pub bind_dn: String,
+    pub bind_password: String,
+    /// Search base, e.g. `DC=corp,DC=example`
+    pub base_dn: String,
+    /// Upgrade plain `ldap://` connections with StartTLS
+    #[serde(default)]
+    pub starttls: bool,
+}
+
+impl LdapConfig {
+    fn validate(&self) -> Result<()> {
+        if !(self.url.starts_with("ldap://") || self.url.starts_with("ldaps://")) {
+            bail!("LDAP URL must start with ldap:// or ldaps://");
+        }
+        if self.base_dn.trim().is_empty() {
+            bail!("LDAP base DN cannot be empty");
+        }
+        Ok(())
+    }
+}
+
+/// Outcome of an LDAP enrichment run
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LdapEnrichmentSummary {
+    /// Devices with a hostname that were looked up
+    pub queried: usize,
+    /// Devices matched to a computer object (managed)
+    pub matched: usize,
+    /// Devices with a hostname but no computer object (unmanaged)
+    pub unmatched: usize,
+}
+
+/// Lowercased first label of a hostname (`PC-042.corp.example` -> `pc-042`)
+pub fn short_hostname(hostname: &str) -> String {
+    hostname
+        .trim()
+        .trim_end_matches('.')
+        .split('.')
+        .next()
+        .unwrap_or_default()
+        .to_lowercase()
+}
+
+/// Organizational unit path of a DN (`CN=PC1,OU=Laptops,OU=HQ,DC=corp` -> `HQ/Laptops`)
+pub fn ou_from_dn(dn: &str) -> Option<String> {
+    let mut ous: Vec<&str> = dn
+        .split(',')
+        .filter_map(|rdn| {
+            let (key, value) = rdn.trim().split_once('=')?;
+            key.eq_ignore_ascii_case("OU").then_some(value)
+        })
+        .collect();
+    if ous.is_empty() {
+        return None;
+    }
+    ous.reverse();
+    Some(ous.join("/"))
+}
+
+/// Convert an AD FILETIME (100ns intervals since 1601) to UTC
+fn filetime_to_datetime(value: &str) -> Option<DateTime<Utc>> {
+    let ticks: i64 = value.trim().parse().ok()?;
+    if ticks <= 0 {
+        return None;
+    }
+    DateTime::from_timestamp(
+        ticks / 10_000_000 - FILETIME_UNIX_OFFSET_SECS,
+        ((ticks % 10_000_000) * 100) as u32,
+    )
+}
+
+/// Search filter matching computer objects by name or DNS hostname
+fn computer_filter(names: &[String]) -> String {
+    let terms: String = names
+        .iter()
+        .map(|name| {
+            let name = ldap_escape(name.as_str());
+            format!("(cn={0})(dNSHostName={0}.*)", name)
+        })
+        .collect();
+    format!("(&(objectClass=computer)(|{}))", terms)
+}
+
+fn parse_computer(entry: SearchEntry) -> DirectoryComputer {
+    let first = |attr: &str| {
+        entry
+            .attrs
+            .get(attr)
+            .and_then(|values| values.first())
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+    };
+
+    let uac: u32 = first("userAccountControl")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0);
+
+    DirectoryComputer {
+        name: first("cn").unwrap_or_default(),
+        dns_hostname: first("dNSHostName"),
+        operating_system: first("operatingSystem"),
+        os_version: first("operatingSystemVersion"),
+        ou: ou_from_dn(&entry.dn),
+        last_logon: first("lastLogonTimestamp").and_then(|v| filetime_to_datetime(&v)),
+        enabled: uac & UAC_ACCOUNTDISABLE == 0,
+        distinguished_name: entry.dn,
+    }
+}
+
+/// Look up AD computer objects for a set of hostnames
+///
+/// Returns matches keyed by short hostname (see [`short_hostname`]).
+pub async fn ldap_lookup_computers(
+    config: &LdapConfig,
+    hostnames: &[String],
+) -> Result<HashMap<String, DirectoryComputer>> {
+    config.validate()?;
+
+    let mut names: Vec<String> = hostnames
+        .iter()
+        .map(|h| short_hostname(h))
+        .filter(|h| !h.is_empty())
+        .collect();
+    names.sort();
+    names.dedup();
+    if names.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let settings = LdapConnSettings::new()
+        .set_conn_timeout(LDAP_TIMEOUT)
+        .set_starttls(config.starttls);
+    let (conn, mut ldap) = LdapConnAsync::with_settings(settings, &config.url)
+        .await
+        .with_context(|| format!("Failed to connect to {}", config.url))?;
+    ldap3::drive!(conn);
+
+    ldap.with_timeout(LDAP_TIMEOUT)
+        .simple_bind(&config.bind_dn, &config.bind_password)
+        .await
+        .context("LDAP bind failed")?
+        .success()
+        .context("LDAP bind rejected")?;
+
+    let mut computers = HashMap::new();
+    for batch in names.chunks(LDAP_BATCH_SIZE) {
+        let (entries, _) = ldap
+            .with_timeout(LDAP_TIMEOUT)
+            .search(
+                &config.base_dn,
+                Scope::Subtree,
+                &computer_filter(batch),
+                COMPUTER_ATTRIBUTES,
+            )
+            .await
+            .context("LDAP search failed")?
+            .success()
+            .context("LDAP search rejected")?;
+
+        for entry in entries {
+            let computer = parse_computer(SearchEntry::construct(entry));
+            let key = short_hostname(computer.dns_hostname.as_deref().unwrap_or(&computer.name));
+            if batch.contains(&key) {
+                computers.insert(key, computer);
+            }
+        }
+    }
+
+    let _ = ldap.unbind().await;
+    Ok(computers)
+}
+
+/// Look up every stored device with a hostname and record directory matches
+///
+/// Matched devices also take the directory's operating system as `os_guess`;
+/// stale matches for devices no longer in the directory are removed.
+pub async fn ldap_enrich_devices(
+    db: &Database,
+    config: &LdapConfig,
+) -> Result<LdapEnrichmentSummary> {
+    let devices: Vec<(String, String)> = {
+        let conn = db.reader()?;
+        queries::get_all_devices(&conn)?
+            .into_iter()
+            .filter_map(|d| Some((d.mac, d.hostname?)))
+            .collect()
+    };
+
+    let hostnames: Vec<String> = devices.iter().map(|(_, h)| h.clone()).collect();
+    let computers = ldap_lookup_computers(config, &hostnames).await?;
+
+    let conn = db.writer()?;
+    let mut summary = LdapEnrichmentSummary {
+        queried: devices.len(),
+        ..Default::default()
+    };
+    for (mac, hostname) in &devices {
+        match computers.get(&short_hostname(hostname)) {
+            Some(computer) => {
+                queries::upsert_device_directory(&conn, mac, computer)?;
+                summary.matched += 1;
+            }
+            None => {
+                queries::delete_device_directory(&conn, mac)?;
+                summary.unmatched += 1;
+            }
+        }
+    }
+
+    tracing::info!(
+        "LDAP enrichment: {} of {} devices matched a computer object",
+        summary.matched,
+        summary.queried
+    );
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_directory_attribute_parsing() {
+        assert_eq!(short_hostname("PC-042.corp.example."), "pc-042");
+        assert_eq!(
+            ou_from_dn("CN=PC-042,OU=Laptops,OU=HQ,DC=corp,DC=example").as_deref(),
+            Some("HQ/Laptops")
+        );
+        assert_eq!(ou_from_dn("CN=PC-042,CN=Computers,DC=corp"), None);
+
+        // 2024-01-01T00:00:00Z
+        let logon = filetime_to_datetime("133485408000000000").unwrap();
+        assert_eq!(logon.to_rfc3339(), "2024-01-01T00:00:00+00:00");
+        assert!(filetime_to_datetime("0").is_none());
+
+        assert_eq!(
+            computer_filter(&["pc-1".to_string(), "a*b".to_string()]),
+            r"(&(objectClass=computer)(|(cn=pc-1)(dNSHostName=pc-1.*)(cn=a\2ab)(dNSHostName=a\2ab.*)))"
+        );
+
+        let mut attrs = HashMap::new();
+        attrs.insert("cn".to_string(), vec!["PC-042".to_string()]);
+        attrs.insert(
+            "operatingSystem".to_string(),
+            vec!["Windows 11 Enterprise".to_string()],
+        );
+        attrs.insert("userAccountControl".to_string(), vec!["4098".to_string()]);
+        let computer = parse_computer(SearchEntry {
+            dn: "CN=PC-042,OU=Laptops,DC=corp".to_string(),
+            attrs,
+            bin_attrs: HashMap::new(),
+        });
+        assert_eq!(computer.name, "PC-042");
+        assert_eq!(computer.ou.as_deref(), Some("Laptops"));
+        assert_eq!(
+            computer.operating_system.as_deref(),
+            Some("Windows 11 Enterprise")
+        );
+        assert!(!computer.enabled);
+    }
+}
@@ -1,13 +1,36 @@
-//! Scanner module - ARP, ICMP, TCP, and SNMP scanning
+//! Scanner module - ARP, ICMP, TCP, NDP, and SNMP scanning, plus LDAP enrichment
+//! TLS certificate inspection and SSH banner grabbing
 
 mod arp;
+mod certs;
 mod icmp;
+mod ldap;
+mod ndp;
 pub mod passive;
 mod snmp;
+mod ssh;
 mod tcp;
 
-pub use arp::active_arp_scan;
-pub use icmp::{guess_os_from_ttl, icmp_scan, IcmpResult};
-pub use passive::{ArpEvent, ArpMonitor, PassiveScanner};
+pub use arp::{
+    active_arp_scan, arp_scan_diagnostics, chunked_arp_scan, ArpChunkProgress, ArpDiagnostics, ArpScan,
+    ArpPacketKind, ArpPacketRecord, LatencyBucket, LatencySummary,
+};
+pub use certs::{fetch_certificate, inspect_certificates, parse_certificate, TlsCertificate};
+pub use icmp::{
+    classify_icmp_error, guess_os_from_ttl, icmp_probe_scan, icmp_scan, ping_series, IcmpResult,
+    IcmpScan,
+};
+pub use ldap::{
+    ldap_enrich_devices, ldap_lookup_computers, ou_from_dn, short_hostname, LdapConfig,
+    LdapEnrichmentSummary,
+};
+pub use ndp::{merge_ipv6_neighbors, ndp_discover, parse_ndp_cache, Ipv6Neighbor};
+pub use passive::{
+    ArpAnomaly, ArpEvent, ArpMonitor, ArpThresholds, ArpTracker, CaptureRingBuffer, PacketCapture,
+    PassiveScanner, SniCapture, SniEvent, TlsSniMonitor,
+};
 pub use snmp::{snmp_enrich, SnmpData, SnmpNeighbor};
-pub use tcp::tcp_probe_scan;
+pub use ssh::{
+    collect_ssh_host_keys, fetch_ssh_host_key, ssh_fingerprint, ssh_key_type, SshHostKey,
+};
+pub use tcp::{tcp_probe_scan, tcp_probe_scan_detailed, PortProbeResult};
@@ -0,0 +1,379 @@
+//! IPv6 link-local neighbor discovery
+//!
+//! Some devices (mostly IoT gear) only speak IPv6 and never answer ARP, so
+//! they are invisible to the IPv4 scan. An ICMPv6 echo to the all-nodes
+//! multicast address (ff02::1) draws a reply from every IPv6 host on the
+//! link, and the operating system's neighbor (NDP) cache adds hosts it has
+//! already seen. Neighbors are keyed by MAC and merged into the host list.
+
+use anyhow::{anyhow, Result};
+use pnet::datalink::{self, Channel};
+use pnet::ipnetwork::IpNetwork;
+use pnet::packet::ethernet::{EtherTypes, EthernetPacket, MutableEthernetPacket};
+use pnet::packet::icmpv6::{self, Icmpv6Packet, Icmpv6Types, MutableIcmpv6Packet};
+use pnet::packet::ip::IpNextHeaderProtocols;
+use pnet::packet::ipv6::{Ipv6Packet, MutableIpv6Packet};
+use pnet::packet::Packet;
+use pnet::util::MacAddr;
+use std::collections::BTreeMap;
+use std::net::Ipv6Addr;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use crate::config::NDP_LISTEN_MS;
+use crate::models::{HostInfo, HostState, InterfaceInfo};
+use crate::network::{calculate_risk_score, infer_device_type, lookup_vendor_info};
+
+/// Logs a message to stderr
+macro_rules! log_stderr {
+    ($($arg:tt)*) => {
+        eprintln!("[INFO] {}", format!($($arg)*));
+    };
+}
+
+/// Ethernet address IPv6 all-nodes multicast (ff02::1) maps to
+const ALL_NODES_MAC: MacAddr = MacAddr(0x33, 0x33, 0x00, 0x00, 0x00, 0x01);
+const ALL_NODES: Ipv6Addr = Ipv6Addr::new(0xff02, 0, 0, 0, 0, 0, 0, 1);
+
+const ETHERNET_HEADER_LEN: usize = 14;
+const IPV6_HEADER_LEN: usize = 40;
+/// Type, code, checksum, identifier and sequence number
+const ICMPV6_ECHO_LEN: usize = 8;
+
+/// An IPv6 address seen on the link with the MAC that answered for it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ipv6Neighbor {
+    pub ip: Ipv6Addr,
+    pub mac: MacAddr,
+}
+
+fn is_link_local(ip: &Ipv6Addr) -> bool {
+    ip.segments()[0] & 0xffc0 == 0xfe80
+}
+
+/// Creates an ICMPv6 echo request to the all-nodes multicast address
+fn create_multicast_echo(source_mac: MacAddr, source_ip: Ipv6Addr) -> Result<Vec<u8>> {
+    let mut buffer = vec![0u8; ETHERNET_HEADER_LEN + IPV6_HEADER_LEN + ICMPV6_ECHO_LEN];
+    let (ethernet_buf, rest) = buffer.split_at_mut(ETHERNET_HEADER_LEN);
+    let (ipv6_buf, icmp_buf) = rest.split_at_mut(IPV6_HEADER_LEN);
+
+    {
+        let mut ethernet = MutableEthernetPacket::new(ethernet_buf)
+            .ok_or_else(|| anyhow!("Failed to construct Ethernet packet buffer"))?;
+        ethernet.set_destination(ALL_NODES_MAC);
+        ethernet.set_source(source_mac);
+        ethernet.set_ethertype(EtherTypes::Ipv6);
+    }
+
+    {
+        let mut ipv6 = MutableIpv6Packet::new(ipv6_buf)
+            .ok_or_else(|| anyhow!("Failed to construct IPv6 packet buffer"))?;
+        ipv6.set_version(6);
+        ipv6.set_payload_length(ICMPV6_ECHO_LEN as u16);
+        ipv6.set_next_header(IpNextHeaderProtocols::Icmpv6);
+        ipv6.set_hop_limit(1);
+        ipv6.set_source(source_ip);
+        ipv6.set_destination(ALL_NODES);
+    }
+
+    {
+        let mut icmp = MutableIcmpv6Packet::new(icmp_buf)
+            .ok_or_else(|| anyhow!("Failed to construct ICMPv6 packet buffer"))?;
+        icmp.set_icmpv6_type(Icmpv6Types::EchoRequest);
+        // Identifier and sequence number
+        icmp.set_payload(&[0x4e, 0x58, 0x00, 0x01]);
+        let checksum = icmpv6::checksum(&icmp.to_immutable(), &source_ip, &ALL_NODES);
+        icmp.set_checksum(checksum);
+    }
+
+    Ok(buffer)
+}
+
+/// Neighbor announced by a received frame, if it is an ICMPv6 packet that
+/// proves a host owns its source address
+fn neighbor_from_frame(frame: &[u8]) -> Option<Ipv6Neighbor> {
+    let ethernet = EthernetPacket::new(frame)?;
+    if ethernet.get_ethertype() != EtherTypes::Ipv6 {
+        return None;
+    }
+    let ipv6 = Ipv6Packet::new(ethernet.payload())?;
+    if ipv6.get_next_header() != IpNextHeaderProtocols::Icmpv6 {
+        return None;
+    }
+    let icmp = Icmpv6Packet::new(ipv6.payload())?;
+    let announces = matches!(
+        icmp.get_icmpv6_type(),
+        Icmpv6Types::EchoReply
+            | Icmpv6Types::NeighborAdvert
+            | Icmpv6Types::NeighborSolicit
+            | Icmpv6Types::RouterAdvert
+    );
+    let source = ipv6.get_source();
+    (announces && !source.is_unspecified() && !source.is_multicast()).then_some(Ipv6Neighbor {
+        ip: source,
+        mac: ethernet.get_source(),
+    })
+}
+
+/// Discover IPv6 hosts on the interface's link
+///
+/// Pings ff02::1 from the interface's link-local address and listens for
+/// `NDP_LISTEN_MS`, then adds entries from the OS neighbor cache. Without
+/// a link-local address only the cache is read.
+pub fn ndp_discover(interface: &InterfaceInfo) -> Result<Vec<Ipv6Neighbor>> {
+    let link_local = interface
+        .pnet_interface
+        .ips
+        .iter()
+        .find_map(|network| match network {
+            IpNetwork::V6(net) if is_link_local(&net.ip()) => Some(net.ip()),
+            _ => None,
+        });
+
+    let mut neighbors = Vec::new();
+    if let Some(source_ip) = link_local {
+        log_stderr!("IPv6: pinging all-nodes multicast on {}...", interface.name);
+
+        let config = datalink::Config {
+            read_timeout: Some(Duration::from_millis(100)),
+            ..Default::default()
+        };
+        let (mut tx, mut rx) = match datalink::channel(&interface.pnet_interface, config) {
+            Ok(Channel::Ethernet(tx, rx)) => (tx, rx),
+            Ok(_) => return Err(anyhow!("Unsupported channel type")),
+            Err(e) => return Err(anyhow!("Failed to open datalink channel: {}", e)),
+        };
+
+        let packet = create_multicast_echo(interface.mac, source_ip)?;
+        if let Some(Err(e)) = tx.send_to(&packet, None) {
+            return Err(anyhow!("Failed to send ICMPv6 echo request: {}", e));
+        }
+
+        let deadline = Instant::now() + Duration::from_millis(NDP_LISTEN_MS);
+        while Instant::now() < deadline {
+            if let Ok(frame) = rx.next() {
+                if let Some(neighbor) = neighbor_from_frame(frame) {
+                    if neighbor.mac != interface.mac {
+                        neighbors.push(neighbor);
+                    }
+                }
+            }
+        }
+    } else {
+        log_stderr!(
+            "IPv6: no link-local address on {}, reading neighbor cache only",
+            interface.name
+        );
+    }
+
+    neighbors.extend(
+        read_ndp_cache(&interface.name)
+            .into_iter()
+            .filter(|n| n.mac != interface.mac),
+    );
+    neighbors.sort_by_key(|n| (n.mac.octets(), n.ip));
+    neighbors.dedup();
+
+    log_stderr!("IPv6 complete: {} neighbor addresses", neighbors.len());
+    Ok(neighbors)
+}
+
+/// Read the operating system's IPv6 neighbor cache for an interface
+fn read_ndp_cache(interface_name: &str) -> Vec<Ipv6Neighbor> {
+    #[cfg(target_os = "linux")]
+    let output = std::process::Command::new("ip")
+        .args(["-6", "neigh", "show", "dev", interface_name])
+        .output();
+    #[cfg(target_os = "macos")]
+    let output = std::process::Command::new("ndp")
+        .args(["-an", "-i", interface_name])
+        .output();
+    #[cfg(target_os = "windows")]
+    let output = std::process::Command::new("netsh")
+        .args(["interface", "ipv6", "show", "neighbors"])
+        .arg(format!("interface={}", interface_name))
+        .output();
+    #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+    let output: std::io::Result<std::process::Output> = {
+        let _ = interface_name;
+        Err(std::io::ErrorKind::Unsupported.into())
+    };
+
+    match output {
+        Ok(output) if output.status.success() => {
+            parse_ndp_cache(&String::from_utf8_lossy(&output.stdout))
+        }
+        Ok(output) => {
+            log_stderr!("IPv6 neighbor cache unavailable (exit {})", output.status);
+            Vec::new()
+        }
+        Err(e) => {
+            log_stderr!("IPv6 neighbor cache unavailable: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Parse neighbor cache listings (`ip -6 neigh`, `ndp -an` or
+/// `netsh interface ipv6 show neighbors`)
+///
+/// Each line is searched for an IPv6 address and a MAC. Multicast entries,
+/// incomplete entries and all-zero MACs are skipped.
+pub fn parse_ndp_cache(output: &str) -> Vec<Ipv6Neighbor> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut tokens = line.split_whitespace();
+            let ip = tokens.find_map(|token| {
+                let address = token.split('%').next().unwrap_or(token);
+                Ipv6Addr::from_str(address).ok()
+            })?;
+            let mac = tokens.find_map(|token| MacAddr::from_str(&token.replace('-', ":")).ok())?;
+            let usable = !ip.is_multicast()
+                && !ip.is_unspecified()
+                && mac != MacAddr::zero()
+                && mac.0 != 0x33;
+            usable.then_some(Ipv6Neighbor { ip, mac })
+        })
+        .collect()
+}
+
+/// Merge IPv6 neighbors into the scanned hosts by MAC
+///
+/// Addresses of hosts already found over IPv4 are listed on that host.
+/// MACs with no IPv4 host become IPv6-only hosts addressed by a global
+/// address when one was seen, otherwise link-local. Returns how many
+/// IPv6-only hosts were added.
+pub fn merge_ipv6_neighbors(hosts: &mut Vec<HostInfo>, neighbors: &[Ipv6Neighbor]) -> usize {
+    let mut by_mac: BTreeMap<MacAddr, Vec<Ipv6Addr>> = BTreeMap::new();
+    for neighbor in neighbors {
+        let addresses = by_mac.entry(neighbor.mac).or_default();
+        if !addresses.contains(&neighbor.ip) {
+            addresses.push(neighbor.ip);
+        }
+    }
+
+    let mut added = 0;
+    for (mac, mut addresses) in by_mac {
+        // Global addresses first
+        addresses.sort_by_key(|ip| (is_link_local(ip), *ip));
+        let mac_str = mac.to_string();
+        let addresses: Vec<String> = addresses.iter().map(Ipv6Addr::to_string).collect();
+
+        if let Some(host) = hosts
+            .iter_mut()
+            .find(|h| h.mac.eq_ignore_ascii_case(&mac_str))
+        {
+            for address in addresses {
+                if !host.ipv6_addresses.contains(&address) {
+                    host.ipv6_addresses.push(address);
+                }
+            }
+            continue;
+        }
+
+        let vendor_info = lookup_vendor_info(&mac_str);
+        let device_type = infer_device_type(vendor_info.vendor.as_deref(), None, &[], false);
+        let mut host = HostInfo::new(
+            addresses[0].clone(),
+            mac_str,
+            device_type.as_str().to_string(),
+            "NDP".to_string(),
+        );
+        host.vendor = vendor_info.vendor;
+        host.is_randomized = vendor_info.is_randomized;
+        host.risk_score = calculate_risk_score(device_type, &[], vendor_info.is_randomized);
+        host.host_state = Some(HostState::Up);
+        host.ipv6_addresses = addresses;
+        hosts.push(host);
+        added += 1;
+    }
+    added
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ndp_cache_formats() {
+        let linux = "fe80::1 lladdr aa:bb:cc:dd:ee:01 router REACHABLE\n\
+                     fe80::2 FAILED\n\
+                     2001:db8::5 lladdr aa:bb:cc:dd:ee:05 STALE\n";
+        let macos =
+            "Neighbor                        Linklayer Address  Netif Expire    St Flgs Prbs\n\
+                     fe80::3%en0                     aa:bb:cc:dd:ee:3 en0 23h59m58s S R\n";
+        let windows = "Internet Address                              Physical Address   Type\n\
+                       --------------------------------------------  -----------------  -----------\n\
+                       fe80::4                                       aa-bb-cc-dd-ee-04  Reachable\n\
+                       ff02::1                                       33-33-00-00-00-01  Permanent\n";
+
+        let neighbors: Vec<String> = [linux, macos, windows]
+            .iter()
+            .flat_map(|output| parse_ndp_cache(output))
+            .map(|n| format!("{} {}", n.ip, n.mac))
+            .collect();
+        assert_eq!(
+            neighbors,
+            vec![
+                "fe80::1 aa:bb:cc:dd:ee:01",
+                "2001:db8::5 aa:bb:cc:dd:ee:05",
+                "fe80::3 aa:bb:cc:dd:ee:03",
+                "fe80::4 aa:bb:cc:dd:ee:04",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_echo_reply_is_recognized() {
+        let source_mac = MacAddr(0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0x07);
+        let source_ip = Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 7);
+        let mut frame = create_multicast_echo(source_mac, source_ip).unwrap();
+
+        // Our own request is not an announcement; the same frame as a reply is
+        assert_eq!(neighbor_from_frame(&frame), None);
+        frame[ETHERNET_HEADER_LEN + IPV6_HEADER_LEN] = Icmpv6Types::EchoReply.0;
+        assert_eq!(
+            neighbor_from_frame(&frame),
+            Some(Ipv6Neighbor {
+                ip: source_ip,
+                mac: source_mac
+            })
+        );
+    }
+
+    #[test]
+    fn test_merge_ipv6_neighbors_by_mac() {
+        let mut hosts = vec![HostInfo::new(
+            "192.168.1.10".to_string(),
+            "aa:bb:cc:dd:ee:01".to_string(),
+            "PC".to_string(),
+            "ARP".to_string(),
+        )];
+        let mac = |last| MacAddr(0xaa, 0xbb, 0xcc, 0xdd, 0xee, last);
+        let neighbors = [
+            Ipv6Neighbor {
+                ip: "fe80::1".parse().unwrap(),
+                mac: mac(1),
+            },
+            Ipv6Neighbor {
+                ip: "fe80::9".parse().unwrap(),
+                mac: mac(9),
+            },
+            Ipv6Neighbor {
+                ip: "2001:db8::9".parse().unwrap(),
+                mac: mac(9),
+            },
+        ];
+
+        assert_eq!(merge_ipv6_neighbors(&mut hosts, &neighbors), 1);
+        assert_eq!(hosts[0].ip, "192.168.1.10");
+        assert_eq!(hosts[0].ipv6_addresses, vec!["fe80::1".to_string()]);
+        assert_eq!(hosts[1].ip, "2001:db8::9");
+        assert_eq!(hosts[1].discovery_method, "NDP");
+        assert_eq!(
+            hosts[1].ipv6_addresses,
+            vec!["2001:db8::9".to_string(), "fe80::9".to_string()]
+        );
+    }
+}
@@ -1,14 +1,25 @@
 //! ARP passive monitoring
 //!
 //! Listens to ARP broadcasts without sending packets
-//! Captures MAC addresses and IP assignments
+//! Captures MAC addresses and IP assignments, and optionally flags
+//! address takeover attempts (gratuitous ARP), IP conflicts (two MACs
+//! repeatedly claiming one address), shared MACs (one MAC repeatedly
+//! claiming two addresses) and ARP request storms
 
+use std::collections::{HashMap, VecDeque};
+
+use chrono::{DateTime, Duration, Utc};
 use pnet::datalink::{self, Channel, NetworkInterface};
 use pnet::packet::arp::{ArpOperations, ArpPacket};
 use pnet::packet::ethernet::{EtherTypes, EthernetPacket};
 use pnet::packet::Packet;
+use serde::{Deserialize, Serialize};
 use tokio::sync::mpsc;
 
+use crate::config::{
+    ARP_BINDING_TTL_SECS, ARP_MAX_WINDOW_SECS, ARP_STORM_REQUEST_THRESHOLD, ARP_STORM_WINDOW_SECS,
+};
+
 /// ARP event captured from network
 #[derive(Debug, Clone)]
 pub struct ArpEvent {
@@ -16,18 +27,268 @@ pub struct ArpEvent {
     pub sender_ip: String,
     pub target_ip: String,
     pub is_request: bool,
+    /// Announcement of the sender's own address (sender IP == target IP)
+    pub is_gratuitous: bool,
     pub timestamp: chrono::DateTime<chrono::Utc>,
 }
 
+/// Thresholds for ARP anomaly detection
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArpThresholds {
+    /// Requests from one MAC within the window that count as a storm
+    pub storm_request_threshold: u32,
+    /// Sliding window for request rate tracking
+    pub storm_window_secs: u64,
+    /// How long an IP → MAC binding is trusted when checking for takeovers
+    pub binding_ttl_secs: u64,
+    /// Report a different MAC claiming a recently bound IP
+    pub detect_takeover: bool,
+    /// Report a MAC alternating between two IPs (two hosts sharing it)
+    #[serde(default = "default_detect_shared_mac")]
+    pub detect_shared_mac: bool,
+}
+
+fn default_detect_shared_mac() -> bool {
+    true
+}
+
+impl Default for ArpThresholds {
+    fn default() -> Self {
+        Self {
+            storm_request_threshold: ARP_STORM_REQUEST_THRESHOLD,
+            storm_window_secs: ARP_STORM_WINDOW_SECS,
+            binding_ttl_secs: ARP_BINDING_TTL_SECS,
+            detect_takeover: true,
+            detect_shared_mac: default_detect_shared_mac(),
+        }
+    }
+}
+
+impl ArpThresholds {
+    /// Check both windows are between one second and [`ARP_MAX_WINDOW_SECS`]
+    pub fn validate(&self) -> anyhow::Result<()> {
+        for (name, secs) in [
+            ("Storm window", self.storm_window_secs),
+            ("Binding TTL", self.binding_ttl_secs),
+        ] {
+            if !(1..=ARP_MAX_WINDOW_SECS).contains(&secs) {
+                anyhow::bail!(
+                    "{} must be between 1 and {} seconds, got {}",
+                    name,
+                    ARP_MAX_WINDOW_SECS,
+                    secs
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Suspicious ARP activity
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+pub enum ArpAnomaly {
+    /// A different MAC claimed an IP that was recently bound to another device
+    AddressTakeover {
+        ip: String,
+        previous_mac: String,
+        new_mac: String,
+        gratuitous: bool,
+        timestamp: DateTime<Utc>,
+    },
+    /// Two MACs keep claiming the same IP (the displaced one claimed it back)
+    IpConflict {
+        ip: String,
+        macs: Vec<String>,
+        timestamp: DateTime<Utc>,
+    },
+    /// One MAC keeps claiming two IPs (it moved away and came back)
+    SharedMac {
+        mac: String,
+        ips: Vec<String>,
+        timestamp: DateTime<Utc>,
+    },
+    /// A device exceeded the ARP request rate threshold
+    ArpStorm {
+        mac: String,
+        ip: String,
+        request_count: u32,
+        window_secs: u64,
+        timestamp: DateTime<Utc>,
+    },
+}
+
+/// Tracks IP bindings and request rates across ARP events
+#[derive(Debug, Default)]
+pub struct ArpTracker {
+    thresholds: ArpThresholds,
+    /// IP -> (MAC, last seen)
+    bindings: HashMap<String, (String, DateTime<Utc>)>,
+    /// IP -> (MAC displaced by the current binding, when)
+    displaced: HashMap<String, (String, DateTime<Utc>)>,
+    /// IP -> time of the last conflict report (one report per binding TTL)
+    conflict_reported: HashMap<String, DateTime<Utc>>,
+    /// MAC -> (IP, last claimed)
+    claims: HashMap<String, (String, DateTime<Utc>)>,
+    /// MAC -> (IP it moved away from, when)
+    left: HashMap<String, (String, DateTime<Utc>)>,
+    /// MAC -> time of the last shared-MAC report (one report per binding TTL)
+    shared_reported: HashMap<String, DateTime<Utc>>,
+    /// MAC -> request timestamps within the storm window
+    requests: HashMap<String, VecDeque<DateTime<Utc>>>,
+    /// MAC -> time of the last storm report (one report per window)
+    storm_reported: HashMap<String, DateTime<Utc>>,
+}
+
+impl ArpTracker {
+    pub fn new(thresholds: ArpThresholds) -> Self {
+        Self {
+            thresholds,
+            ..Default::default()
+        }
+    }
+
+    pub fn thresholds(&self) -> &ArpThresholds {
+        &self.thresholds
+    }
+
+    /// Feed one ARP event and return any anomalies it triggers
+    pub fn observe(&mut self, event: &ArpEvent) -> Vec<ArpAnomaly> {
+        let mut anomalies = Vec::new();
+        let now = event.timestamp;
+
+        // 0.0.0.0 senders are address probes (RFC 5227) and claim nothing
+        if event.sender_ip != "0.0.0.0" {
+            let ttl = Duration::seconds(self.thresholds.binding_ttl_secs as i64);
+            if let Some((bound_mac, seen)) = self.bindings.get(&event.sender_ip).cloned() {
+                if bound_mac != event.sender_mac {
+                    // Claiming back an address it just lost is a conflict, not a takeover
+                    let contested = self
+                        .displaced
+                        .get(&event.sender_ip)
+                        .is_some_and(|(mac, at)| *mac == event.sender_mac && now - *at <= ttl);
+
+                    if self.thresholds.detect_takeover && now - seen <= ttl {
+                        if !contested {
+                            anomalies.push(ArpAnomaly::AddressTakeover {
+                                ip: event.sender_ip.clone(),
+                                previous_mac: bound_mac.clone(),
+                                new_mac: event.sender_mac.clone(),
+                                gratuitous: event.is_gratuitous,
+                                timestamp: now,
+                            });
+                        } else if self
+                            .conflict_reported
+                            .get(&event.sender_ip)
+                            .is_none_or(|t| now - *t > ttl)
+                        {
+                            self.conflict_reported.insert(event.sender_ip.clone(), now);
+                            let mut macs = vec![bound_mac.clone(), event.sender_mac.clone()];
+                            macs.sort();
+                            anomalies.push(ArpAnomaly::IpConflict {
+                                ip: event.sender_ip.clone(),
+                                macs,
+                                timestamp: now,
+                            });
+                        }
+                    }
+                    self.displaced
+                        .insert(event.sender_ip.clone(), (bound_mac, now));
+                }
+            }
+            self.bindings
+                .insert(event.sender_ip.clone(), (event.sender_mac.clone(), now));
+
+            if let Some((claimed_ip, seen)) = self.claims.get(&event.sender_mac).cloned() {
+                if claimed_ip != event.sender_ip {
+                    // Moving to a new address is a DHCP change; moving back is two hosts
+                    let returned = self
+                        .left
+                        .get(&event.sender_mac)
+                        .is_some_and(|(ip, at)| *ip == event.sender_ip && now - *at <= ttl);
+
+                    if self.thresholds.detect_shared_mac
+                        && returned
+                        && now - seen <= ttl
+                        && self
+                            .shared_reported
+                            .get(&event.sender_mac)
+                            .is_none_or(|t| now - *t > ttl)
+                    {
+                        self.shared_reported.insert(event.sender_mac.clone(), now);
+                        let mut ips = vec![claimed_ip.clone(), event.sender_ip.clone()];
+                        ips.sort();
+                        anomalies.push(ArpAnomaly::SharedMac {
+                            mac: event.sender_mac.clone(),
+                            ips,
+                            timestamp: now,
+                        });
+                    }
+                    self.left
+                        .insert(event.sender_mac.clone(), (claimed_ip, now));
+                }
+            }
+            self.claims
+                .insert(event.sender_mac.clone(), (event.sender_ip.clone(), now));
+        }
+
+        if event.is_request && self.thresholds.storm_request_threshold > 0 {
+            let window = Duration::seconds(self.thresholds.storm_window_secs as i64);
+            // Forget MACs quiet for a whole window, so a busy segment stays bounded
+            self.requests
+                .retain(|_, history| history.back().is_some_and(|t| now - *t <= window));
+            self.storm_reported.retain(|_, t| now - *t <= window);
+            let history = self.requests.entry(event.sender_mac.clone()).or_default();
+            history.push_back(now);
+            while history.front().is_some_and(|t| now - *t > window) {
+                history.pop_front();
+            }
+
+            let count = history.len() as u32;
+            let recently_reported = self
+                .storm_reported
+                .get(&event.sender_mac)
+                .is_some_and(|t| now - *t <= window);
+
+            if count >= self.thresholds.storm_request_threshold && !recently_reported {
+                self.storm_reported.insert(event.sender_mac.clone(), now);
+                anomalies.push(ArpAnomaly::ArpStorm {
+                    mac: event.sender_mac.clone(),
+                    ip: event.sender_ip.clone(),
+                    request_count: count,
+                    window_secs: self.thresholds.storm_window_secs,
+                    timestamp: now,
+                });
+            }
+        }
+
+        anomalies
+    }
+}
+
 /// ARP monitor for passive device discovery
 pub struct ArpMonitor {
     interface: NetworkInterface,
+    anomaly_detection: Option<(ArpThresholds, mpsc::Sender<ArpAnomaly>)>,
 }
 
 impl ArpMonitor {
     /// Create a new ARP monitor for the given interface
     pub fn new(interface: NetworkInterface) -> Self {
-        Self { interface }
+        Self {
+            interface,
+            anomaly_detection: None,
+        }
+    }
+
+    /// Also track gratuitous ARP and request rates, reporting anomalies on `tx`
+    pub fn with_anomaly_detection(
+        mut self,
+        thresholds: ArpThresholds,
+        tx: mpsc::Sender<ArpAnomaly>,
+    ) -> Self {
+        self.anomaly_detection = Some((thresholds, tx));
+        self
     }
 
     /// Start monitoring ARP traffic (passive listening)
@@ -47,48 +308,45 @@ impl ArpMonitor {
             _ => return Err("Unsupported channel type".into()),
         };
 
+        let mut tracker = self
+            .anomaly_detection
+            .as_ref()
+            .map(|(thresholds, anomaly_tx)| (ArpTracker::new(thresholds.clone()), anomaly_tx));
+
         tracing::info!("🎧 Started ARP monitoring on interface: {}", interface_name);
 
         // Listen for ARP packets
         loop {
             match rx.next() {
                 Ok(packet) => {
-                    // Parse Ethernet frame
-                    if let Some(ethernet) = EthernetPacket::new(packet) {
-                        // Check if it's an ARP packet
-                        if ethernet.get_ethertype() == EtherTypes::Arp {
-                            if let Some(arp) = ArpPacket::new(ethernet.payload()) {
-                                let event = ArpEvent {
-                                    sender_mac: format!(
-                                        "{:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}",
-                                        arp.get_sender_hw_addr().0,
-                                        arp.get_sender_hw_addr().1,
-                                        arp.get_sender_hw_addr().2,
-                                        arp.get_sender_hw_addr().3,
-                                        arp.get_sender_hw_addr().4,
-                                        arp.get_sender_hw_addr().5,
-                                    ),
-                                    sender_ip: arp.get_sender_proto_addr().to_string(),
-                                    target_ip: arp.get_target_proto_addr().to_string(),
-                                    is_request: arp.get_operation() == ArpOperations::Request,
-                                    timestamp: chrono::Utc::now(),
-                                };
-
-                                tracing::debug!(
-                                    "🎧 ARP: {} ({}) {} {}",
-                                    event.sender_ip,
-                                    event.sender_mac,
-                                    if event.is_request { "→" } else { "←" },
-                                    event.target_ip
-                                );
-
-                                // Send event
-                                if tx.send(event).await.is_err() {
-                                    tracing::warn!("ARP monitoring channel closed");
-                                    break;
+                    if let Some(event) = parse_arp_frame(packet) {
+                        tracing::debug!(
+                            "🎧 ARP: {} ({}) {} {}{}",
+                            event.sender_ip,
+                            event.sender_mac,
+                            if event.is_request { "→" } else { "←" },
+                            event.target_ip,
+                            if event.is_gratuitous {
+                                " [gratuitous]"
+                            } else {
+                                ""
+                            }
+                        );
+
+                        if let Some((tracker, anomaly_tx)) = tracker.as_mut() {
+                            for anomaly in tracker.observe(&event) {
+                                tracing::warn!("🚨 ARP anomaly: {:?}", anomaly);
+                                if anomaly_tx.try_send(anomaly).is_err() {
+                                    tracing::warn!("ARP anomaly channel full or closed");
                                 }
                             }
                         }
+
+                        // Send event
+                        if tx.send(event).await.is_err() {
+                            tracing::warn!("ARP monitoring channel closed");
+                            break;
+                        }
                     }
                 }
                 Err(e) => {
@@ -102,10 +360,46 @@ impl ArpMonitor {
     }
 }
 
+/// Parse an Ethernet frame into an ARP event
+pub fn parse_arp_frame(frame: &[u8]) -> Option<ArpEvent> {
+    let ethernet = EthernetPacket::new(frame)?;
+    if ethernet.get_ethertype() != EtherTypes::Arp {
+        return None;
+    }
+
+    let arp = ArpPacket::new(ethernet.payload())?;
+    let sender_hw = arp.get_sender_hw_addr();
+    let sender_ip = arp.get_sender_proto_addr();
+    let target_ip = arp.get_target_proto_addr();
+
+    Some(ArpEvent {
+        sender_mac: format!(
+            "{:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}",
+            sender_hw.0, sender_hw.1, sender_hw.2, sender_hw.3, sender_hw.4, sender_hw.5,
+        ),
+        sender_ip: sender_ip.to_string(),
+        target_ip: target_ip.to_string(),
+        is_request: arp.get_operation() == ArpOperations::Request,
+        is_gratuitous: !sender_ip.is_unspecified() && sender_ip == target_ip,
+        timestamp: chrono::Utc::now(),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn event(mac: &str, ip: &str, target: &str, is_request: bool, offset_ms: i64) -> ArpEvent {
+        ArpEvent {
+            sender_mac: mac.to_string(),
+            sender_ip: ip.to_string(),
+            target_ip: target.to_string(),
+            is_request,
+            is_gratuitous: ip == target,
+            timestamp: DateTime::<Utc>::UNIX_EPOCH + Duration::milliseconds(offset_ms),
+        }
+    }
+
     #[test]
     fn test_arp_event_creation() {
         let event = ArpEvent {
@@ -113,6 +407,7 @@ mod tests {
             sender_ip: "192.168.1.100".to_string(),
             target_ip: "192.168.1.1".to_string(),
             is_request: true,
+            is_gratuitous: false,
             timestamp: chrono::Utc::now(),
         };
 
@@ -120,4 +415,170 @@ mod tests {
         assert_eq!(event.sender_ip, "192.168.1.100");
         assert!(event.is_request);
     }
+
+    #[test]
+    fn test_gratuitous_takeover_detected() {
+        let mut tracker = ArpTracker::new(ArpThresholds::default());
+
+        let gateway = event("aa:00:00:00:00:01", "192.168.1.1", "192.168.1.50", false, 0);
+        assert!(tracker.observe(&gateway).is_empty());
+
+        let attacker = event(
+            "bb:00:00:00:00:02",
+            "192.168.1.1",
+            "192.168.1.1",
+            false,
+            1000,
+        );
+        let anomalies = tracker.observe(&attacker);
+        assert_eq!(anomalies.len(), 1);
+        assert!(matches!(
+            &anomalies[0],
+            ArpAnomaly::AddressTakeover { previous_mac, gratuitous: true, .. }
+                if previous_mac == "aa:00:00:00:00:01"
+        ));
+
+        // Probes from 0.0.0.0 never count as claims
+        let probe = event("cc:00:00:00:00:03", "0.0.0.0", "192.168.1.1", true, 2000);
+        assert!(tracker.observe(&probe).is_empty());
+    }
+
+    #[test]
+    fn test_ip_conflict_when_address_flaps() {
+        let mut tracker = ArpTracker::new(ArpThresholds::default());
+        let claim =
+            |mac: &str, offset_ms: i64| event(mac, "192.168.1.50", "192.168.1.1", false, offset_ms);
+
+        assert!(tracker.observe(&claim("aa:00:00:00:00:01", 0)).is_empty());
+        let anomalies = tracker.observe(&claim("bb:00:00:00:00:02", 1000));
+        assert!(matches!(
+            anomalies[..],
+            [ArpAnomaly::AddressTakeover { .. }]
+        ));
+
+        // The first device answers again: both are using the address
+        let anomalies = tracker.observe(&claim("aa:00:00:00:00:01", 2000));
+        assert!(matches!(
+            &anomalies[..],
+            [ArpAnomaly::IpConflict { macs, .. }]
+                if macs == &["aa:00:00:00:00:01", "bb:00:00:00:00:02"]
+        ));
+
+        // Further flapping is not reported again within the binding TTL
+        assert!(tracker
+            .observe(&claim("bb:00:00:00:00:02", 3000))
+            .is_empty());
+        assert!(tracker
+            .observe(&claim("aa:00:00:00:00:01", 4000))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_shared_mac_when_mac_alternates() {
+        let mut tracker = ArpTracker::new(ArpThresholds::default());
+        let claim = |ip: &str, offset_ms: i64| {
+            event("aa:00:00:00:00:07", ip, "192.168.1.1", false, offset_ms)
+        };
+
+        // Moving to a new address once is an ordinary DHCP change
+        assert!(tracker.observe(&claim("192.168.1.7", 0)).is_empty());
+        assert!(tracker.observe(&claim("192.168.1.70", 1000)).is_empty());
+
+        // Answering from the old address again: two hosts use the MAC
+        let anomalies = tracker.observe(&claim("192.168.1.7", 2000));
+        assert!(matches!(
+            &anomalies[..],
+            [ArpAnomaly::SharedMac { mac, ips, .. }]
+                if mac == "aa:00:00:00:00:07" && ips == &["192.168.1.7", "192.168.1.70"]
+        ));
+
+        // Reported once per binding TTL
+        assert!(tracker.observe(&claim("192.168.1.70", 3000)).is_empty());
+        assert!(tracker.observe(&claim("192.168.1.7", 4000)).is_empty());
+
+        let mut tracker = ArpTracker::new(ArpThresholds {
+            detect_shared_mac: false,
+            ..Default::default()
+        });
+        for (i, ip) in ["192.168.1.7", "192.168.1.70", "192.168.1.7"]
+            .into_iter()
+            .enumerate()
+        {
+            assert!(tracker.observe(&claim(ip, i as i64 * 1000)).is_empty());
+        }
+    }
+
+    #[test]
+    fn test_storm_reported_once_per_window() {
+        let thresholds = ArpThresholds {
+            storm_request_threshold: 5,
+            storm_window_secs: 10,
+            ..Default::default()
+        };
+        let mut tracker = ArpTracker::new(thresholds);
+
+        let storms = (0..12)
+            .flat_map(|i| {
+                tracker.observe(&event(
+                    "aa:00:00:00:00:09",
+                    "192.168.1.9",
+                    "192.168.1.200",
+                    true,
+                    i * 100,
+                ))
+            })
+            .filter(|a| matches!(a, ArpAnomaly::ArpStorm { .. }))
+            .count();
+        assert_eq!(storms, 1);
+
+        // Slow requests spread across windows never trigger
+        let mut tracker = ArpTracker::new(ArpThresholds {
+            storm_request_threshold: 5,
+            storm_window_secs: 1,
+            ..Default::default()
+        });
+        for i in 0..20 {
+            let e = event(
+                "aa:00:00:00:00:0a",
+                "192.168.1.10",
+                "192.168.1.1",
+                true,
+                i * 2000,
+            );
+            assert!(tracker.observe(&e).is_empty());
+        }
+
+        // Requests from other MACs drop the quiet ones
+        tracker.observe(&event(
+            "aa:00:00:00:00:0b",
+            "192.168.1.11",
+            "0.0.0.0",
+            true,
+            50_000,
+        ));
+        tracker.observe(&event(
+            "aa:00:00:00:00:0c",
+            "192.168.1.12",
+            "0.0.0.0",
+            true,
+            52_000,
+        ));
+        assert_eq!(tracker.requests.len(), 1);
+        assert!(tracker.requests.contains_key("aa:00:00:00:00:0c"));
+    }
+
+    #[test]
+    fn test_thresholds_validation() {
+        assert!(ArpThresholds::default().validate().is_ok());
+        let zero = ArpThresholds {
+            storm_window_secs: 0,
+            ..Default::default()
+        };
+        assert!(zero.validate().is_err());
+        let huge = ArpThresholds {
+            binding_ttl_secs: u64::MAX,
+            ..Default::default()
+        };
+        assert!(huge.validate().is_err());
+    }
 }
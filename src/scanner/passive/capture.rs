@@ -0,0 +1,278 @@
+//! Rolling packet capture
+//!
+//! Keeps the most recent frames seen on an interface in a size-bounded ring
+//! buffer so the last few minutes of traffic can be exported as a `.pcap`
+//! file (classic libpcap format, readable by Wireshark/tcpdump).
+
+use std::collections::VecDeque;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use pnet::datalink::{self, Channel, NetworkInterface};
+
+use crate::config::{CAPTURE_SNAPLEN, MAX_CAPTURE_BUFFER_BYTES, MIN_CAPTURE_BUFFER_BYTES};
+
+/// libpcap magic number (microsecond timestamps)
+const PCAP_MAGIC: u32 = 0xa1b2_c3d4;
+/// LINKTYPE_ETHERNET
+const PCAP_LINKTYPE_ETHERNET: u32 = 1;
+/// Per-packet bookkeeping overhead counted against the buffer capacity
+const PACKET_OVERHEAD_BYTES: usize = 16;
+
+/// A single captured frame
+#[derive(Debug, Clone)]
+pub struct CapturedPacket {
+    pub timestamp: DateTime<Utc>,
+    /// Frame bytes (truncated to the snap length)
+    pub data: Vec<u8>,
+    /// Original frame length on the wire
+    pub orig_len: u32,
+}
+
+/// Size-bounded FIFO of captured frames; oldest frames are evicted first
+#[derive(Debug)]
+pub struct CaptureRingBuffer {
+    capacity_bytes: usize,
+    used_bytes: usize,
+    packets: VecDeque<CapturedPacket>,
+}
+
+impl CaptureRingBuffer {
+    /// Create a buffer holding at most `capacity_bytes` of frame data
+    pub fn new(capacity_bytes: usize) -> Self {
+        Self {
+            capacity_bytes: capacity_bytes
+                .clamp(MIN_CAPTURE_BUFFER_BYTES, MAX_CAPTURE_BUFFER_BYTES),
+            used_bytes: 0,
+            packets: VecDeque::new(),
+        }
+    }
+
+    /// Append a frame, evicting the oldest frames as needed
+    pub fn push(&mut self, timestamp: DateTime<Utc>, frame: &[u8]) {
+        let data = frame[..frame.len().min(CAPTURE_SNAPLEN)].to_vec();
+        let cost = data.len() + PACKET_OVERHEAD_BYTES;
+
+        while self.used_bytes + cost > self.capacity_bytes {
+            match self.packets.pop_front() {
+                Some(old) => self.used_bytes -= old.data.len() + PACKET_OVERHEAD_BYTES,
+                None => break,
+            }
+        }
+
+        self.used_bytes += cost;
+        self.packets.push_back(CapturedPacket {
+            timestamp,
+            data,
+            orig_len: frame.len() as u32,
+        });
+    }
+
+    pub fn len(&self) -> usize {
+        self.packets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.packets.is_empty()
+    }
+
+    pub fn used_bytes(&self) -> usize {
+        self.used_bytes
+    }
+
+    pub fn capacity_bytes(&self) -> usize {
+        self.capacity_bytes
+    }
+
+    /// Frames captured at or after `cutoff` (all frames if `None`)
+    pub fn packets_since(
+        &self,
+        cutoff: Option<DateTime<Utc>>,
+    ) -> impl Iterator<Item = &CapturedPacket> {
+        self.packets
+            .iter()
+            .filter(move |p| cutoff.is_none_or(|c| p.timestamp >= c))
+    }
+
+    /// Write frames captured at or after `cutoff` as a pcap stream
+    ///
+    /// Returns the number of packets written.
+    pub fn write_pcap<W: Write>(
+        &self,
+        writer: &mut W,
+        cutoff: Option<DateTime<Utc>>,
+    ) -> io::Result<usize> {
+        writer.write_all(&PCAP_MAGIC.to_le_bytes())?;
+        writer.write_all(&2u16.to_le_bytes())?; // version major
+        writer.write_all(&4u16.to_le_bytes())?; // version minor
+        writer.write_all(&0i32.to_le_bytes())?; // thiszone (UTC)
+        writer.write_all(&0u32.to_le_bytes())?; // sigfigs
+        writer.write_all(&(CAPTURE_SNAPLEN as u32).to_le_bytes())?;
+        writer.write_all(&PCAP_LINKTYPE_ETHERNET.to_le_bytes())?;
+
+        let mut count = 0;
+        for packet in self.packets_since(cutoff) {
+            writer.write_all(&(packet.timestamp.timestamp() as u32).to_le_bytes())?;
+            writer.write_all(&packet.timestamp.timestamp_subsec_micros().to_le_bytes())?;
+            writer.write_all(&(packet.data.len() as u32).to_le_bytes())?;
+            writer.write_all(&packet.orig_len.to_le_bytes())?;
+            writer.write_all(&packet.data)?;
+            count += 1;
+        }
+
+        Ok(count)
+    }
+}
+
+/// Background capture feeding a [`CaptureRingBuffer`]
+///
+/// Capture runs on a dedicated thread until [`PacketCapture::stop`] is called
+/// or the handle is dropped.
+pub struct PacketCapture {
+    interface_name: String,
+    buffer: Arc<Mutex<CaptureRingBuffer>>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl PacketCapture {
+    /// Open the interface and start capturing into a buffer of `capacity_bytes`
+    pub fn start(
+        interface: &NetworkInterface,
+        capacity_bytes: usize,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let config = datalink::Config {
+            read_timeout: Some(Duration::from_millis(500)),
+            promiscuous: false,
+            ..Default::default()
+        };
+
+        let mut rx = match datalink::channel(interface, config)? {
+            Channel::Ethernet(_, rx) => rx,
+            _ => return Err("Unsupported channel type".into()),
+        };
+
+        let buffer = Arc::new(Mutex::new(CaptureRingBuffer::new(capacity_bytes)));
+        let stop = Arc::new(AtomicBool::new(false));
+        let interface_name = interface.name.clone();
+
+        let thread_buffer = Arc::clone(&buffer);
+        let thread_stop = Arc::clone(&stop);
+        let thread_name = interface_name.clone();
+        let thread = std::thread::Builder::new()
+            .name("pcap-ring".to_string())
+            .spawn(move || {
+                tracing::info!("🎧 Started packet capture on interface: {}", thread_name);
+
+                while !thread_stop.load(Ordering::SeqCst) {
+                    match rx.next() {
+                        Ok(frame) => match thread_buffer.lock() {
+                            Ok(mut buffer) => buffer.push(Utc::now(), frame),
+                            Err(_) => break,
+                        },
+                        Err(e) if e.kind() == io::ErrorKind::TimedOut => {}
+                        Err(e) => {
+                            tracing::error!("Packet capture error: {}", e);
+                            std::thread::sleep(Duration::from_millis(100));
+                        }
+                    }
+                }
+
+                tracing::info!("🎧 Stopped packet capture on interface: {}", thread_name);
+            })?;
+
+        Ok(Self {
+            interface_name,
+            buffer,
+            stop,
+            thread: Some(thread),
+        })
+    }
+
+    pub fn interface_name(&self) -> &str {
+        &self.interface_name
+    }
+
+    /// Shared handle to the underlying ring buffer
+    pub fn buffer(&self) -> Arc<Mutex<CaptureRingBuffer>> {
+        Arc::clone(&self.buffer)
+    }
+
+    /// Export the last `window` of traffic (everything buffered if `None`) as pcap bytes
+    pub fn export_pcap(&self, window: Option<Duration>) -> io::Result<Vec<u8>> {
+        let cutoff = window
+            .and_then(|w| chrono::Duration::from_std(w).ok())
+            .map(|w| Utc::now() - w);
+
+        let buffer = self
+            .buffer
+            .lock()
+            .map_err(|_| io::Error::other("Capture buffer lock poisoned"))?;
+
+        let mut bytes = Vec::with_capacity(buffer.used_bytes() + 24);
+        buffer.write_pcap(&mut bytes, cutoff)?;
+        Ok(bytes)
+    }
+
+    /// Stop capturing and wait for the capture thread to exit
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for PacketCapture {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ring_buffer_evicts_oldest() {
+        let mut buffer = CaptureRingBuffer::new(MIN_CAPTURE_BUFFER_BYTES);
+        let frame = vec![0xabu8; 1000];
+        let now = Utc::now();
+
+        for i in 0..(MIN_CAPTURE_BUFFER_BYTES / 1000 + 10) {
+            buffer.push(now + chrono::Duration::milliseconds(i as i64), &frame);
+        }
+
+        assert!(buffer.used_bytes() <= buffer.capacity_bytes());
+        let first = buffer.packets_since(None).next().unwrap();
+        assert!(first.timestamp > now);
+    }
+
+    #[test]
+    fn test_write_pcap_format() {
+        let mut buffer = CaptureRingBuffer::new(MIN_CAPTURE_BUFFER_BYTES);
+        let old = Utc::now() - chrono::Duration::minutes(10);
+        buffer.push(old, &[1, 2, 3]);
+        buffer.push(Utc::now(), &[4, 5, 6, 7]);
+
+        let mut out = Vec::new();
+        let cutoff = Utc::now() - chrono::Duration::minutes(5);
+        let written = buffer.write_pcap(&mut out, Some(cutoff)).unwrap();
+
+        assert_eq!(written, 1);
+        assert_eq!(&out[..4], &PCAP_MAGIC.to_le_bytes());
+        assert_eq!(&out[20..24], &PCAP_LINKTYPE_ETHERNET.to_le_bytes());
+        // 24-byte global header + 16-byte record header + 4 data bytes
+        assert_eq!(out.len(), 24 + 16 + 4);
+        assert_eq!(&out[24 + 8..24 + 12], &4u32.to_le_bytes());
+        assert_eq!(&out[40..], &[4, 5, 6, 7]);
+    }
+}
@@ -12,7 +12,11 @@ pub struct PassiveDevice {
     pub hostname: String,
     pub ip: String,
     pub mac: Option<String>,
+    /// Advertised service types without the domain (e.g. `_ipp._tcp`)
     pub services: Vec<String>,
+    /// Service instance name (e.g. "Office Printer")
+    pub instance_name: Option<String>,
+    pub port: Option<u16>,
     pub discovered_at: chrono::DateTime<chrono::Utc>,
     pub device_type_hint: Option<String>,
 }
@@ -100,7 +104,8 @@ impl PassiveScanner {
             .unwrap_or_else(|| "unknown".to_string());
 
         // Infer device type from service type
-        let service_type = info.get_type();
+        let service_type = normalize_service_type(info.get_type());
+        let service_type = service_type.as_str();
         let device_type_hint = match service_type {
             t if t.contains("_airplay") => Some("Apple TV / iOS Device".to_string()),
             t if t.contains("_raop") => Some("AirPlay Speaker".to_string()),
@@ -121,16 +126,47 @@ impl PassiveScanner {
             ip,
             mac: None, // Will be enriched by ARP monitoring
             services: vec![service_type.to_string()],
+            instance_name: instance_name(info.get_fullname(), info.get_type()),
+            port: Some(info.get_port()).filter(|p| *p != 0),
             discovered_at: chrono::Utc::now(),
             device_type_hint,
         }
     }
 }
 
+/// Strip the `.local.` domain from a service type (`_ipp._tcp.local.` -> `_ipp._tcp`)
+pub fn normalize_service_type(service_type: &str) -> String {
+    service_type
+        .trim_end_matches('.')
+        .trim_end_matches(".local")
+        .to_lowercase()
+}
+
+/// Extract the instance label from a full service name
+///
+/// `Office Printer._ipp._tcp.local.` with type `_ipp._tcp.local.` -> `Office Printer`
+fn instance_name(fullname: &str, service_type: &str) -> Option<String> {
+    fullname
+        .strip_suffix(service_type)
+        .map(|name| name.trim_end_matches('.'))
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_service_name_parsing() {
+        assert_eq!(normalize_service_type("_IPP._tcp.local."), "_ipp._tcp");
+        assert_eq!(
+            instance_name("Office Printer._ipp._tcp.local.", "_ipp._tcp.local."),
+            Some("Office Printer".to_string())
+        );
+        assert_eq!(instance_name("_ipp._tcp.local.", "_ipp._tcp.local."), None);
+    }
+
     #[tokio::test]
     async fn test_passive_scanner_creation() {
         let scanner = PassiveScanner::new();
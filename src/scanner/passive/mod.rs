@@ -4,9 +4,15 @@
 //! - mDNS/DNS-SD: Listen for service announcements
 //! - ARP monitoring: Observe ARP traffic
 //! - DHCP snooping: Capture DHCP requests
+//! - TLS SNI: Record hostnames from ClientHello messages
+//! - Packet capture: Rolling ring buffer exportable as pcap
 
 pub mod arp;
+pub mod capture;
 pub mod mdns;
+pub mod tls;
 
-pub use arp::{ArpEvent, ArpMonitor};
+pub use arp::{ArpAnomaly, ArpEvent, ArpMonitor, ArpThresholds, ArpTracker};
+pub use capture::{CaptureRingBuffer, CapturedPacket, PacketCapture};
 pub use mdns::PassiveScanner;
+pub use tls::{SniCapture, SniEvent, TlsSniMonitor};
@@ -0,0 +1,361 @@
+//! TLS SNI passive monitoring
+//!
+//! Reads the Server Name Indication from TLS ClientHello messages seen on the wire.
+//! Only the plaintext handshake header is inspected; no traffic is decrypted or modified.
+
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use pnet::datalink::{self, Channel, NetworkInterface};
+use pnet::packet::ethernet::{EtherTypes, EthernetPacket};
+use pnet::packet::ip::IpNextHeaderProtocols;
+use pnet::packet::ipv4::Ipv4Packet;
+use pnet::packet::tcp::TcpPacket;
+use pnet::packet::Packet;
+use tokio::sync::mpsc::{self, error::TrySendError};
+
+/// TLS record content type for handshake messages
+const TLS_CONTENT_HANDSHAKE: u8 = 0x16;
+/// Handshake message type for ClientHello
+const TLS_HANDSHAKE_CLIENT_HELLO: u8 = 0x01;
+/// Extension type for server_name (RFC 6066)
+const TLS_EXT_SERVER_NAME: u16 = 0x0000;
+/// SNI name type for DNS hostnames
+const SNI_NAME_TYPE_HOSTNAME: u8 = 0x00;
+
+/// SNI observation captured from a TLS ClientHello
+#[derive(Debug, Clone)]
+pub struct SniEvent {
+    pub client_mac: String,
+    pub client_ip: String,
+    pub server_ip: String,
+    pub server_port: u16,
+    pub server_name: String,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+}
+
+/// TLS SNI monitor for passive service inventory
+pub struct TlsSniMonitor {
+    interface: NetworkInterface,
+}
+
+impl TlsSniMonitor {
+    /// Create a new SNI monitor for the given interface
+    pub fn new(interface: NetworkInterface) -> Self {
+        Self { interface }
+    }
+
+    /// Start monitoring TLS handshakes (passive listening)
+    ///
+    /// Sends an event for every ClientHello carrying a hostname SNI; events
+    /// arriving while `tx` is full are dropped. Capture stops once the
+    /// returned handle is stopped or dropped, or the receiver goes away.
+    pub fn start_monitoring(
+        &self,
+        tx: mpsc::Sender<SniEvent>,
+    ) -> Result<SniCapture, Box<dyn std::error::Error>> {
+        let config = datalink::Config {
+            read_timeout: Some(Duration::from_millis(500)),
+            ..Default::default()
+        };
+
+        let mut rx = match datalink::channel(&self.interface, config)? {
+            Channel::Ethernet(_, rx) => rx,
+            _ => return Err("Unsupported channel type".into()),
+        };
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = Arc::clone(&stop);
+        let interface_name = self.interface.name.clone();
+        let thread = std::thread::Builder::new()
+            .name("tls-sni".to_string())
+            .spawn(move || {
+                tracing::info!(
+                    "🎧 Started TLS SNI monitoring on interface: {}",
+                    interface_name
+                );
+
+                while !thread_stop.load(Ordering::SeqCst) {
+                    match rx.next() {
+                        Ok(packet) => {
+                            let Some(event) = parse_sni_frame(packet) else {
+                                continue;
+                            };
+                            tracing::debug!(
+                                "🎧 SNI: {} ({}) → {} [{}]",
+                                event.client_ip,
+                                event.client_mac,
+                                event.server_name,
+                                event.server_ip
+                            );
+
+                            match tx.try_send(event) {
+                                Ok(()) => {}
+                                Err(TrySendError::Full(_)) => {
+                                    tracing::debug!("SNI channel full, dropping observation");
+                                }
+                                Err(TrySendError::Closed(_)) => {
+                                    tracing::warn!("SNI monitoring channel closed");
+                                    break;
+                                }
+                            }
+                        }
+                        Err(e) if e.kind() == io::ErrorKind::TimedOut => {}
+                        Err(e) => {
+                            tracing::error!("SNI monitoring error: {}", e);
+                            std::thread::sleep(Duration::from_millis(100));
+                        }
+                    }
+                }
+
+                tracing::info!(
+                    "🎧 Stopped TLS SNI monitoring on interface: {}",
+                    interface_name
+                );
+            })?;
+
+        Ok(SniCapture {
+            stop,
+            thread: Some(thread),
+        })
+    }
+}
+
+/// Running TLS SNI capture
+///
+/// Capture runs on a dedicated thread until [`SniCapture::stop`] is called
+/// or the handle is dropped.
+pub struct SniCapture {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl SniCapture {
+    /// Stop capturing and wait for the capture thread to exit
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for SniCapture {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// Parse an Ethernet frame and return an SNI event if it carries a ClientHello
+pub fn parse_sni_frame(frame: &[u8]) -> Option<SniEvent> {
+    let ethernet = EthernetPacket::new(frame)?;
+    if ethernet.get_ethertype() != EtherTypes::Ipv4 {
+        return None;
+    }
+
+    let ipv4 = Ipv4Packet::new(ethernet.payload())?;
+    if ipv4.get_next_level_protocol() != IpNextHeaderProtocols::Tcp {
+        return None;
+    }
+
+    let tcp = TcpPacket::new(ipv4.payload())?;
+    let server_name = parse_client_hello_sni(tcp.payload())?;
+
+    let mac = ethernet.get_source();
+    Some(SniEvent {
+        client_mac: format!(
+            "{:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}",
+            mac.0, mac.1, mac.2, mac.3, mac.4, mac.5
+        ),
+        client_ip: ipv4.get_source().to_string(),
+        server_ip: ipv4.get_destination().to_string(),
+        server_port: tcp.get_destination(),
+        server_name,
+        timestamp: chrono::Utc::now(),
+    })
+}
+
+/// Extract the SNI hostname from a TLS ClientHello record
+///
+/// Returns `None` for anything that is not a well-formed ClientHello with a
+/// hostname entry. Truncated records are tolerated as long as the SNI
+/// extension itself is fully contained in the segment.
+pub fn parse_client_hello_sni(payload: &[u8]) -> Option<String> {
+    let mut reader = ByteReader::new(payload);
+
+    // TLS record header
+    if reader.u8()? != TLS_CONTENT_HANDSHAKE {
+        return None;
+    }
+    let _record_version = reader.u16()?;
+    let _record_len = reader.u16()?;
+
+    // Handshake header
+    if reader.u8()? != TLS_HANDSHAKE_CLIENT_HELLO {
+        return None;
+    }
+    let _handshake_len = reader.u24()?;
+
+    // ClientHello body
+    let _client_version = reader.u16()?;
+    reader.skip(32)?; // random
+    let session_id_len = reader.u8()? as usize;
+    reader.skip(session_id_len)?;
+    let cipher_suites_len = reader.u16()? as usize;
+    reader.skip(cipher_suites_len)?;
+    let compression_len = reader.u8()? as usize;
+    reader.skip(compression_len)?;
+
+    let extensions_len = reader.u16()? as usize;
+    let mut extensions = ByteReader::new(reader.take(extensions_len.min(reader.remaining()))?);
+
+    while extensions.remaining() >= 4 {
+        let ext_type = extensions.u16()?;
+        let ext_len = extensions.u16()? as usize;
+        let ext_data = extensions.take(ext_len)?;
+
+        if ext_type == TLS_EXT_SERVER_NAME {
+            return parse_server_name_extension(ext_data);
+        }
+    }
+
+    None
+}
+
+/// Parse the server_name extension body and return the first hostname entry
+fn parse_server_name_extension(data: &[u8]) -> Option<String> {
+    let mut reader = ByteReader::new(data);
+    let list_len = reader.u16()? as usize;
+    let mut list = ByteReader::new(reader.take(list_len)?);
+
+    while list.remaining() >= 3 {
+        let name_type = list.u8()?;
+        let name_len = list.u16()? as usize;
+        let name = list.take(name_len)?;
+
+        if name_type == SNI_NAME_TYPE_HOSTNAME {
+            let hostname = std::str::from_utf8(name).ok()?.trim_end_matches('.');
+            if hostname.is_empty()
+                || !hostname
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.' || c == '_')
+            {
+                return None;
+            }
+            return Some(hostname.to_ascii_lowercase());
+        }
+    }
+
+    None
+}
+
+/// Minimal bounds-checked big-endian reader
+struct ByteReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn remaining(&self) -> usize {
+        self.data.len() - self.pos
+    }
+
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.remaining() < len {
+            return None;
+        }
+        let slice = &self.data[self.pos..self.pos + len];
+        self.pos += len;
+        Some(slice)
+    }
+
+    fn skip(&mut self, len: usize) -> Option<()> {
+        self.take(len).map(|_| ())
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.take(1).map(|b| b[0])
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        self.take(2).map(|b| u16::from_be_bytes([b[0], b[1]]))
+    }
+
+    fn u24(&mut self) -> Option<u32> {
+        self.take(3)
+            .map(|b| u32::from_be_bytes([0, b[0], b[1], b[2]]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build a minimal ClientHello record carrying the given SNI hostname
+    fn build_client_hello(hostname: &str) -> Vec<u8> {
+        let name = hostname.as_bytes();
+
+        let mut sni_ext = Vec::new();
+        sni_ext.extend_from_slice(&((name.len() + 3) as u16).to_be_bytes());
+        sni_ext.push(SNI_NAME_TYPE_HOSTNAME);
+        sni_ext.extend_from_slice(&(name.len() as u16).to_be_bytes());
+        sni_ext.extend_from_slice(name);
+
+        let mut extensions = Vec::new();
+        // Unrelated extension first (supported_groups) to exercise the walk
+        extensions.extend_from_slice(&[0x00, 0x0a, 0x00, 0x04, 0x00, 0x02, 0x00, 0x1d]);
+        extensions.extend_from_slice(&TLS_EXT_SERVER_NAME.to_be_bytes());
+        extensions.extend_from_slice(&(sni_ext.len() as u16).to_be_bytes());
+        extensions.extend_from_slice(&sni_ext);
+
+        let mut body = Vec::new();
+        body.extend_from_slice(&[0x03, 0x03]); // TLS 1.2
+        body.extend_from_slice(&[0u8; 32]); // random
+        body.push(0); // session id
+        body.extend_from_slice(&[0x00, 0x02, 0x13, 0x01]); // one cipher suite
+        body.extend_from_slice(&[0x01, 0x00]); // null compression
+        body.extend_from_slice(&(extensions.len() as u16).to_be_bytes());
+        body.extend_from_slice(&extensions);
+
+        let mut handshake = vec![TLS_HANDSHAKE_CLIENT_HELLO];
+        handshake.extend_from_slice(&(body.len() as u32).to_be_bytes()[1..]);
+        handshake.extend_from_slice(&body);
+
+        let mut record = vec![TLS_CONTENT_HANDSHAKE, 0x03, 0x01];
+        record.extend_from_slice(&(handshake.len() as u16).to_be_bytes());
+        record.extend_from_slice(&handshake);
+        record
+    }
+
+    #[test]
+    fn test_parse_client_hello_sni() {
+        let record = build_client_hello("Devices.Tuya.COM");
+        assert_eq!(
+            parse_client_hello_sni(&record),
+            Some("devices.tuya.com".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_non_handshake_and_truncated() {
+        assert_eq!(
+            parse_client_hello_sni(&[0x17, 0x03, 0x03, 0x00, 0x10]),
+            None
+        );
+
+        let record = build_client_hello("api.example.com");
+        assert_eq!(parse_client_hello_sni(&record[..40]), None);
+        assert_eq!(parse_client_hello_sni(&[]), None);
+    }
+}
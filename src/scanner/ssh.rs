@@ -0,0 +1,127 @@
+//! SSH banner grabbing
+//!
+//! Connects to open SSH ports (see [`SSH_PORTS`]), reads the server's
+//! identification banner and completes the key exchange just far enough to
+//! see its host key. No authentication is attempted.
+
+use std::net::{SocketAddr, TcpStream};
+
+use anyhow::{bail, Context, Result};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::config::{SSH_PORTS, SSH_PROBE_TIMEOUT};
+use crate::{log_warn, HostInfo};
+
+/// Host key and banner of an SSH server on one port
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SshHostKey {
+    pub mac: String,
+    pub ip: String,
+    pub port: u16,
+    /// Identification string, e.g. "SSH-2.0-OpenSSH_9.6"
+    pub banner: Option<String>,
+    /// Key algorithm, e.g. "ssh-ed25519"
+    pub key_type: String,
+    /// OpenSSH-style fingerprint, e.g. "SHA256:GsHT..."
+    pub fingerprint: String,
+}
+
+/// OpenSSH-style SHA-256 fingerprint of a public key blob
+pub fn ssh_fingerprint(blob: &[u8]) -> String {
+    format!(
+        "SHA256:{}",
+        base64::engine::general_purpose::STANDARD_NO_PAD.encode(Sha256::digest(blob))
+    )
+}
+
+/// Key algorithm named at the start of a public key blob
+pub fn ssh_key_type(blob: &[u8]) -> Result<String> {
+    let Some((len, rest)) = blob.split_first_chunk::<4>() else {
+        bail!("SSH host key too short");
+    };
+    let len = u32::from_be_bytes(*len) as usize;
+    let name = rest.get(..len).context("SSH host key type truncated")?;
+    Ok(String::from_utf8_lossy(name).into_owned())
+}
+
+/// Collect the banner and host key served on `ip:port` (blocking)
+pub fn fetch_ssh_host_key(mac: &str, ip: &str, port: u16) -> Result<SshHostKey> {
+    let addr: SocketAddr = format!("{}:{}", ip, port)
+        .parse()
+        .with_context(|| format!("Invalid address {}:{}", ip, port))?;
+    let stream = TcpStream::connect_timeout(&addr, SSH_PROBE_TIMEOUT)
+        .with_context(|| format!("Failed to connect to {}", addr))?;
+
+    let mut session = ssh2::Session::new().context("Failed to create SSH session")?;
+    session.set_timeout(SSH_PROBE_TIMEOUT.as_millis() as u32);
+    session.set_tcp_stream(stream);
+    session
+        .handshake()
+        .with_context(|| format!("SSH handshake with {} failed", addr))?;
+
+    let (blob, _) = session
+        .host_key()
+        .with_context(|| format!("{} sent no host key", addr))?;
+    Ok(SshHostKey {
+        mac: mac.to_string(),
+        ip: ip.to_string(),
+        port,
+        banner: session.banner().map(str::to_string),
+        key_type: ssh_key_type(blob)?,
+        fingerprint: ssh_fingerprint(blob),
+    })
+}
+
+/// Collect the host keys of every host's open SSH ports
+///
+/// Ports that fail the handshake (not SSH after all, or gone) are skipped.
+pub async fn collect_ssh_host_keys(hosts: &[HostInfo]) -> Vec<SshHostKey> {
+    let mut handles = Vec::new();
+    for host in hosts {
+        for &port in host
+            .open_ports
+            .iter()
+            .filter(|port| SSH_PORTS.contains(port))
+        {
+            let (mac, ip) = (host.mac.clone(), host.ip.clone());
+            handles.push(tokio::task::spawn_blocking(move || {
+                fetch_ssh_host_key(&mac, &ip, port)
+            }));
+        }
+    }
+
+    let mut keys = Vec::new();
+    for handle in handles {
+        match handle.await {
+            Ok(Ok(key)) => keys.push(key),
+            Ok(Err(e)) => {
+                log_warn!("SSH host key collection failed: {:#}", e);
+            }
+            Err(e) => {
+                log_warn!("SSH host key collection task failed: {}", e);
+            }
+        }
+    }
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ssh_fingerprint() {
+        let blob = base64::engine::general_purpose::STANDARD
+            .decode("AAAAC3NzaC1lZDI1NTE5AAAAIGxy+PPLE7c+8/0oLVNMF6csSf61Nf5rCFPPpP9ClUKA")
+            .unwrap();
+        // As printed by ssh-keygen -l
+        assert_eq!(
+            ssh_fingerprint(&blob),
+            "SHA256:GsHTEQNcU/74J34unUfP2iX3JKPWbyPvJ2RfRMfAt44"
+        );
+        assert_eq!(ssh_key_type(&blob).unwrap(), "ssh-ed25519");
+        assert!(ssh_key_type(&blob[..6]).is_err());
+    }
+}
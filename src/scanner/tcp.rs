@@ -23,27 +23,79 @@ macro_rules! log_warn {
     };
 }
 
-/// Probes a single host for open ports
-async fn probe_host_ports(ip: Ipv4Addr) -> Vec<u16> {
-    let mut open_ports = Vec::new();
+/// Open and filtered ports found on a host
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PortProbeResult {
+    pub open: Vec<u16>,
+    /// Ports whose connect timed out or was answered with an ICMP unreachable
+    pub filtered: Vec<u16>,
+}
+
+/// State of a probed port
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PortState {
+    Open,
+    Closed,
+    Filtered,
+}
+
+/// Classify the outcome of a connect attempt (`None` means it timed out)
+///
+/// A RST (connection refused) proves the host is there and the port is
+/// closed; silence or an unreachable means something dropped the SYN.
+fn classify_connect(result: Option<&std::io::Result<tokio::net::TcpStream>>) -> PortState {
+    use std::io::ErrorKind;
+
+    match result {
+        Some(Ok(_)) => PortState::Open,
+        None => PortState::Filtered,
+        Some(Err(e)) => match e.kind() {
+            ErrorKind::HostUnreachable | ErrorKind::NetworkUnreachable | ErrorKind::TimedOut => {
+                PortState::Filtered
+            }
+            _ => PortState::Closed,
+        },
+    }
+}
+
+/// Probes a single host for open and filtered ports
+async fn probe_host_ports(ip: Ipv4Addr) -> PortProbeResult {
+    let mut result = PortProbeResult::default();
 
     for &port in TCP_PROBE_PORTS {
         let addr = std::net::SocketAddr::new(std::net::IpAddr::V4(ip), port);
 
-        if let Ok(Ok(_)) =
-            tokio::time::timeout(TCP_PROBE_TIMEOUT, tokio::net::TcpStream::connect(addr)).await
-        {
-            open_ports.push(port);
+        let connect = tokio::time::timeout(TCP_PROBE_TIMEOUT, tokio::net::TcpStream::connect(addr))
+            .await
+            .ok();
+        match classify_connect(connect.as_ref()) {
+            PortState::Open => result.open.push(port),
+            PortState::Filtered => result.filtered.push(port),
+            PortState::Closed => {}
         }
     }
 
-    open_ports
+    result
 }
 
 /// Performs TCP probe scan on discovered hosts
 pub async fn tcp_probe_scan(
     hosts: &HashMap<Ipv4Addr, MacAddr>,
 ) -> Result<HashMap<Ipv4Addr, Vec<u16>>> {
+    Ok(tcp_probe_scan_detailed(hosts)
+        .await?
+        .into_iter()
+        .filter(|(_, ports)| !ports.open.is_empty())
+        .map(|(ip, ports)| (ip, ports.open))
+        .collect())
+}
+
+/// Performs TCP probe scan, also reporting filtered ports
+///
+/// Only hosts with at least one open or filtered port are included.
+pub async fn tcp_probe_scan_detailed(
+    hosts: &HashMap<Ipv4Addr, MacAddr>,
+) -> Result<HashMap<Ipv4Addr, PortProbeResult>> {
     log_stderr!(
         "Phase 3: TCP probing {} hosts ({} ports each)...",
         hosts.len(),
@@ -51,7 +103,7 @@ pub async fn tcp_probe_scan(
     );
 
     let semaphore = Arc::new(Semaphore::new(MAX_CONCURRENT_PINGS));
-    let port_results: Arc<Mutex<HashMap<Ipv4Addr, Vec<u16>>>> =
+    let port_results: Arc<Mutex<HashMap<Ipv4Addr, PortProbeResult>>> =
         Arc::new(Mutex::new(HashMap::new()));
 
     let mut handles = Vec::new();
@@ -69,10 +121,10 @@ pub async fn tcp_probe_scan(
                 }
             };
 
-            let open_ports = probe_host_ports(ip).await;
-            if !open_ports.is_empty() {
+            let ports = probe_host_ports(ip).await;
+            if !ports.open.is_empty() || !ports.filtered.is_empty() {
                 let mut results = port_results.lock().await;
-                results.insert(ip, open_ports);
+                results.insert(ip, ports);
             }
         });
 
@@ -86,14 +138,31 @@ pub async fn tcp_probe_scan(
     }
 
     let results = port_results.lock().await;
-    let hosts_with_ports = results.len();
-    let total_ports: usize = results.values().map(|v| v.len()).sum();
+    let hosts_with_ports = results.values().filter(|p| !p.open.is_empty()).count();
+    let total_ports: usize = results.values().map(|p| p.open.len()).sum();
+    let filtered_ports: usize = results.values().map(|p| p.filtered.len()).sum();
 
     log_stderr!(
-        "Phase 3 complete: {} hosts with open ports ({} ports total)",
+        "Phase 3 complete: {} hosts with open ports ({} ports total, {} filtered)",
         hosts_with_ports,
-        total_ports
+        total_ports,
+        filtered_ports
     );
 
     Ok(results.clone())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Error, ErrorKind};
+
+    #[test]
+    fn test_connect_classification() {
+        let refused = Err(Error::from(ErrorKind::ConnectionRefused));
+        let unreachable = Err(Error::from(ErrorKind::HostUnreachable));
+        assert_eq!(classify_connect(Some(&refused)), PortState::Closed);
+        assert_eq!(classify_connect(Some(&unreachable)), PortState::Filtered);
+        assert_eq!(classify_connect(None), PortState::Filtered);
+    }
+}
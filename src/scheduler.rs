@@ -0,0 +1,564 @@
+//! Scheduled full scans
+//!
+//! Cron-style schedules (e.g. `0 3 * * *` for nightly at 03:00) kept in the
+//! database. They run independently of the background monitor:
+//! [`run_scheduler`] wakes up periodically and hands every due schedule to a
+//! runner, which performs the scan, and records the outcome. A run missed
+//! while the app was closed is made up once, at the next start.
+
+use std::collections::BTreeSet;
+use std::future::Future;
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDateTime, TimeZone, Timelike, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::config::{CRON_SEARCH_DAYS, SCHEDULED_REPORT_DIR, SCHEDULER_POLL_INTERVAL};
+use crate::database::queries::parse_datetime_column;
+use crate::database::Database;
+use crate::notify::send_due_digests;
+
+/// A parsed five-field cron expression: minute, hour, day of month, month,
+/// day of week
+///
+/// Fields accept `*`, numbers, ranges (`1-5`), lists (`1,15`) and steps
+/// (`*/15`, `0-30/10`). Day of week runs from 0 (Sunday) to 7 (also Sunday).
+/// As in cron, when both day fields are restricted a day matching either
+/// one qualifies. `@hourly`, `@daily`, `@weekly` and `@monthly` are
+/// accepted as shorthands.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronExpr {
+    minutes: BTreeSet<u32>,
+    hours: BTreeSet<u32>,
+    days_of_month: BTreeSet<u32>,
+    months: BTreeSet<u32>,
+    days_of_week: BTreeSet<u32>,
+    any_day_of_month: bool,
+    any_day_of_week: bool,
+}
+
+impl CronExpr {
+    pub fn parse(source: &str) -> Result<Self> {
+        let source = match source.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            other => other,
+        };
+        let fields: Vec<&str> = source.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            bail!(
+                "Cron expression needs 5 fields (minute hour day month weekday), got {}",
+                fields.len()
+            );
+        };
+
+        let mut days_of_week = parse_field(weekday, "day of week", 0, 7)?;
+        if days_of_week.remove(&7) {
+            days_of_week.insert(0);
+        }
+        Ok(Self {
+            minutes: parse_field(minute, "minute", 0, 59)?,
+            hours: parse_field(hour, "hour", 0, 23)?,
+            days_of_month: parse_field(day, "day of month", 1, 31)?,
+            months: parse_field(month, "month", 1, 12)?,
+            days_of_week,
+            any_day_of_month: day.starts_with('*'),
+            any_day_of_week: weekday.starts_with('*'),
+        })
+    }
+
+    fn matches_day(&self, date: chrono::NaiveDate) -> bool {
+        if !self.months.contains(&date.month()) {
+            return false;
+        }
+        let by_month_day = self.days_of_month.contains(&date.day());
+        let by_weekday = self
+            .days_of_week
+            .contains(&date.weekday().num_days_from_sunday());
+        match (self.any_day_of_month, self.any_day_of_week) {
+            (true, true) => true,
+            (true, false) => by_weekday,
+            (false, true) => by_month_day,
+            (false, false) => by_month_day || by_weekday,
+        }
+    }
+
+    /// The first matching minute strictly after `after`
+    pub fn next_after(&self, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        let start = after.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let mut date = start.date();
+        for _ in 0..CRON_SEARCH_DAYS {
+            if self.matches_day(date) {
+                for &hour in &self.hours {
+                    for &minute in &self.minutes {
+                        let candidate = date.and_hms_opt(hour, minute, 0)?;
+                        if candidate >= start {
+                            return Some(candidate);
+                        }
+                    }
+                }
+            }
+            date = date.succ_opt()?;
+        }
+        None
+    }
+
+    /// The next run strictly after `after`, in local time
+    ///
+    /// Local times skipped by a daylight-saving change are passed over.
+    pub fn next_run(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let mut local = after.with_timezone(&Local).naive_local();
+        loop {
+            local = self.next_after(local)?;
+            if let Some(at) = Local.from_local_datetime(&local).earliest() {
+                return Some(at.with_timezone(&Utc));
+            }
+        }
+    }
+}
+
+fn parse_field(field: &str, name: &str, min: u32, max: u32) -> Result<BTreeSet<u32>> {
+    let mut values = BTreeSet::new();
+    for item in field.split(',') {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step
+                    .parse()
+                    .with_context(|| format!("Invalid {} step: {}", name, item))?;
+                if step == 0 {
+                    bail!("Invalid {} step: {}", name, item);
+                }
+                (range, step)
+            }
+            None => (item, 1),
+        };
+        let (first, last) = match range {
+            "*" => (min, max),
+            _ => {
+                let parse = |value: &str| -> Result<u32> {
+                    let value: u32 = value
+                        .parse()
+                        .with_context(|| format!("Invalid {}: {}", name, item))?;
+                    if !(min..=max).contains(&value) {
+                        bail!(
+                            "{} must be between {} and {}, got {}",
+                            name,
+                            min,
+                            max,
+                            value
+                        );
+                    }
+                    Ok(value)
+                };
+                match range.split_once('-') {
+                    Some((first, last)) => (parse(first)?, parse(last)?),
+                    // "5/10" means every 10th from 5 on
+                    None if step > 1 => (parse(range)?, max),
+                    None => {
+                        let value = parse(range)?;
+                        (value, value)
+                    }
+                }
+            }
+        };
+        if first > last {
+            bail!("Invalid {} range: {}", name, item);
+        }
+        values.extend((first..=last).step_by(step as usize));
+    }
+    Ok(values)
+}
+
+/// What a scheduled scan does, as created or edited by the user
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScanScheduleSpec {
+    pub name: String,
+    /// Five-field cron expression, evaluated in local time
+    pub cron: String,
+    /// Interface to scan; `None` picks the default one
+    #[serde(default)]
+    pub interface: Option<String>,
+    /// Write a PDF report after each run
+    #[serde(default)]
+    pub generate_report: bool,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl ScanScheduleSpec {
+    pub fn validate(&self) -> Result<()> {
+        if self.name.trim().is_empty() {
+            bail!("Schedule name cannot be empty");
+        }
+        let cron = CronExpr::parse(&self.cron)?;
+        if cron.next_run(Utc::now()).is_none() {
+            bail!("Cron expression never matches a date: {}", self.cron);
+        }
+        Ok(())
+    }
+}
+
+/// A stored scan schedule
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScanSchedule {
+    pub id: i64,
+    #[serde(flatten)]
+    pub spec: ScanScheduleSpec,
+    pub last_run: Option<DateTime<Utc>>,
+    /// Why the last run failed; `None` if it succeeded
+    pub last_error: Option<String>,
+    /// When the schedule next runs (`None` while disabled)
+    pub next_run: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl ScanSchedule {
+    /// When the first run after the last run (or the last edit) is due
+    fn due_at(&self) -> Option<DateTime<Utc>> {
+        let since = self
+            .last_run
+            .map_or(self.updated_at, |last| last.max(self.updated_at));
+        CronExpr::parse(&self.spec.cron).ok()?.next_run(since)
+    }
+
+    /// Where the report for a run at `at` is written
+    pub fn report_path(&self, at: DateTime<Utc>) -> PathBuf {
+        let slug: String = self
+            .spec
+            .name
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() {
+                    c.to_ascii_lowercase()
+                } else {
+                    '-'
+                }
+            })
+            .collect();
+        let file = format!(
+            "{}-{}.pdf",
+            slug.trim_matches('-'),
+            at.with_timezone(&Local).format("%Y%m%d-%H%M")
+        );
+        let mut dir = Database::default_path();
+        dir.set_file_name(SCHEDULED_REPORT_DIR);
+        dir.join(file)
+    }
+}
+
+const SCHEDULE_COLUMNS: &str = "id, name, cron, interface, generate_report, enabled, last_run, \
+                                last_error, created_at, updated_at";
+
+fn map_schedule_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<ScanSchedule> {
+    let mut schedule = ScanSchedule {
+        id: row.get(0)?,
+        spec: ScanScheduleSpec {
+            name: row.get(1)?,
+            cron: row.get(2)?,
+            interface: row.get(3)?,
+            generate_report: row.get(4)?,
+            enabled: row.get(5)?,
+        },
+        last_run: row
+            .get::<_, Option<String>>(6)?
+            .map(|s| parse_datetime_column(s, 6))
+            .transpose()?,
+        last_error: row.get(7)?,
+        next_run: None,
+        created_at: parse_datetime_column(row.get(8)?, 8)?,
+        updated_at: parse_datetime_column(row.get(9)?, 9)?,
+    };
+    if schedule.spec.enabled {
+        schedule.next_run = schedule.due_at();
+    }
+    Ok(schedule)
+}
+
+/// Create a scan schedule and return its ID
+pub fn create_scan_schedule(conn: &Connection, spec: &ScanScheduleSpec) -> Result<i64> {
+    spec.validate()?;
+    conn.execute(
+        r#"
+        INSERT INTO scan_schedules (name, cron, interface, generate_report, enabled)
+        VALUES (?1, ?2, ?3, ?4, ?5)
+        "#,
+        params![
+            spec.name.trim(),
+            spec.cron.trim(),
+            spec.interface,
+            spec.generate_report,
+            spec.enabled
+        ],
+    )
+    .context("Failed to create scan schedule")?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Replace a scan schedule's settings, returning whether it exists
+pub fn update_scan_schedule(conn: &Connection, id: i64, spec: &ScanScheduleSpec) -> Result<bool> {
+    spec.validate()?;
+    let affected = conn
+        .execute(
+            r#"
+            UPDATE scan_schedules
+            SET name = ?2, cron = ?3, interface = ?4, generate_report = ?5, enabled = ?6,
+                updated_at = datetime('now')
+            WHERE id = ?1
+            "#,
+            params![
+                id,
+                spec.name.trim(),
+                spec.cron.trim(),
+                spec.interface,
+                spec.generate_report,
+                spec.enabled
+            ],
+        )
+        .context("Failed to update scan schedule")?;
+    Ok(affected > 0)
+}
+
+/// Delete a scan schedule, returning whether it existed
+pub fn delete_scan_schedule(conn: &Connection, id: i64) -> Result<bool> {
+    let affected = conn
+        .execute("DELETE FROM scan_schedules WHERE id = ?1", params![id])
+        .context("Failed to delete scan schedule")?;
+    Ok(affected > 0)
+}
+
+/// Get a scan schedule by ID
+pub fn get_scan_schedule(conn: &Connection, id: i64) -> Result<Option<ScanSchedule>> {
+    conn.query_row(
+        &format!(
+            "SELECT {} FROM scan_schedules WHERE id = ?1",
+            SCHEDULE_COLUMNS
+        ),
+        params![id],
+        map_schedule_row,
+    )
+    .optional()
+    .context("Failed to get scan schedule")
+}
+
+/// Get all scan schedules, ordered by name
+pub fn get_scan_schedules(conn: &Connection) -> Result<Vec<ScanSchedule>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM scan_schedules ORDER BY name COLLATE NOCASE",
+        SCHEDULE_COLUMNS
+    ))?;
+    let schedules = stmt
+        .query_map([], map_schedule_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("Failed to get scan schedules")?;
+    Ok(schedules)
+}
+
+/// Enabled schedules whose next run is at or before `now`
+pub fn due_scan_schedules(conn: &Connection, now: DateTime<Utc>) -> Result<Vec<ScanSchedule>> {
+    Ok(get_scan_schedules(conn)?
+        .into_iter()
+        .filter(|schedule| schedule.next_run.is_some_and(|at| at <= now))
+        .collect())
+}
+
+/// Record that a schedule ran at `at`, with the error if it failed
+pub fn record_scan_schedule_run(
+    conn: &Connection,
+    id: i64,
+    at: DateTime<Utc>,
+    error: Option<&str>,
+) -> Result<()> {
+    conn.execute(
+        "UPDATE scan_schedules SET last_run = ?2, last_error = ?3 WHERE id = ?1",
+        params![id, at.format("%Y-%m-%d %H:%M:%S").to_string(), error],
+    )
+    .context("Failed to record scan schedule run")?;
+    Ok(())
+}
+
+/// Run due schedules forever, checking every [`SCHEDULER_POLL_INTERVAL`]
+///
+/// `run` performs one scheduled scan; its error is stored with the
+/// schedule. Schedules run one at a time, in name order. Alert digests that
+/// are due are mailed on the same ticks.
+pub async fn run_scheduler<F, Fut, E>(db: Database, mut run: F)
+where
+    F: FnMut(ScanSchedule) -> Fut,
+    Fut: Future<Output = std::result::Result<(), E>>,
+    E: std::fmt::Display,
+{
+    let mut ticks = tokio::time::interval(SCHEDULER_POLL_INTERVAL);
+    ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        ticks.tick().await;
+
+        if let Err(e) = db.write(|conn| send_due_digests(conn, Local::now())) {
+            tracing::warn!("[SCHEDULER] Failed to send alert digests: {:#}", e);
+        }
+
+        let due = match db.read(|conn| due_scan_schedules(conn, Utc::now())) {
+            Ok(due) => due,
+            Err(e) => {
+                tracing::warn!("[SCHEDULER] Failed to load scan schedules: {:#}", e);
+                continue;
+            }
+        };
+        for schedule in due {
+            let started = Utc::now();
+            tracing::info!(
+                "[SCHEDULER] Running scheduled scan '{}'",
+                schedule.spec.name
+            );
+            let error = run(schedule.clone())
+                .await
+                .err()
+                .map(|e| format!("{:#}", e));
+            if let Some(error) = &error {
+                tracing::warn!(
+                    "[SCHEDULER] Scheduled scan '{}' failed: {}",
+                    schedule.spec.name,
+                    error
+                );
+            }
+            if let Err(e) = db.write(|conn| {
+                record_scan_schedule_run(conn, schedule.id, started, error.as_deref())
+            }) {
+                tracing::warn!("[SCHEDULER] Failed to record scheduled scan: {:#}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(y: i32, mo: u32, d: u32, h: u32, mi: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(y, mo, d)
+            .unwrap()
+            .and_hms_opt(h, mi, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_cron_next_after() {
+        let nightly = CronExpr::parse("0 3 * * *").unwrap();
+        assert_eq!(
+            nightly.next_after(at(2024, 5, 1, 2, 59)),
+            Some(at(2024, 5, 1, 3, 0))
+        );
+        assert_eq!(
+            nightly.next_after(at(2024, 5, 1, 3, 0)),
+            Some(at(2024, 5, 2, 3, 0))
+        );
+
+        let quarter_hours = CronExpr::parse("*/15 9-17 * * 1-5").unwrap();
+        // Friday evening to Monday morning
+        assert_eq!(
+            quarter_hours.next_after(at(2024, 5, 3, 17, 50)),
+            Some(at(2024, 5, 6, 9, 0))
+        );
+        assert_eq!(
+            quarter_hours.next_after(at(2024, 5, 6, 9, 1)),
+            Some(at(2024, 5, 6, 9, 15))
+        );
+
+        // Either day field matches when both are restricted; 7 is Sunday
+        let either = CronExpr::parse("30 4 1 * 7").unwrap();
+        assert_eq!(
+            either.next_after(at(2024, 5, 1, 5, 0)),
+            Some(at(2024, 5, 5, 4, 30))
+        );
+
+        let leap_day = CronExpr::parse("0 0 29 2 *").unwrap();
+        assert_eq!(
+            leap_day.next_after(at(2024, 3, 1, 0, 0)),
+            Some(at(2028, 2, 29, 0, 0))
+        );
+        assert_eq!(
+            CronExpr::parse("0 0 31 2 *")
+                .unwrap()
+                .next_after(at(2024, 1, 1, 0, 0)),
+            None
+        );
+
+        assert_eq!(
+            CronExpr::parse("@daily").unwrap(),
+            CronExpr::parse("0 0 * * *").unwrap()
+        );
+        for invalid in [
+            "0 3 * *",
+            "60 * * * *",
+            "* * 0 * *",
+            "*/0 * * * *",
+            "5-1 * * * *",
+        ] {
+            assert!(
+                CronExpr::parse(invalid).is_err(),
+                "{} should be rejected",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn test_scan_schedule_crud_and_due() {
+        let db = Database::in_memory().unwrap();
+        let conn = db.writer().unwrap();
+
+        let mut spec = ScanScheduleSpec {
+            name: "Nightly".to_string(),
+            cron: "0 3 * * *".to_string(),
+            interface: None,
+            generate_report: true,
+            enabled: true,
+        };
+        let id = create_scan_schedule(&conn, &spec).unwrap();
+        assert!(create_scan_schedule(
+            &conn,
+            &ScanScheduleSpec {
+                cron: "0 0 31 2 *".to_string(),
+                ..spec.clone()
+            }
+        )
+        .is_err());
+
+        let schedule = get_scan_schedule(&conn, id).unwrap().unwrap();
+        let next = schedule.next_run.unwrap();
+        assert!(next > schedule.updated_at);
+        assert!(due_scan_schedules(&conn, Utc::now()).unwrap().is_empty());
+        assert_eq!(due_scan_schedules(&conn, next).unwrap().len(), 1);
+
+        // A run clears it until the following night
+        record_scan_schedule_run(&conn, id, next, Some("no interface")).unwrap();
+        let schedule = get_scan_schedule(&conn, id).unwrap().unwrap();
+        assert_eq!(schedule.last_error.as_deref(), Some("no interface"));
+        assert!(due_scan_schedules(&conn, next).unwrap().is_empty());
+        assert!(schedule.next_run.unwrap() > next);
+        assert!(schedule.report_path(next).ends_with(format!(
+            "reports/nightly-{}.pdf",
+            next.with_timezone(&Local).format("%Y%m%d-%H%M")
+        )));
+
+        spec.enabled = false;
+        assert!(update_scan_schedule(&conn, id, &spec).unwrap());
+        assert_eq!(get_scan_schedules(&conn).unwrap()[0].next_run, None);
+        assert!(due_scan_schedules(&conn, next + Duration::days(7))
+            .unwrap()
+            .is_empty());
+
+        assert!(delete_scan_schedule(&conn, id).unwrap());
+        assert!(!update_scan_schedule(&conn, id, &spec).unwrap());
+        assert!(get_scan_schedules(&conn).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,593 @@
+//! Terminal UI for `host-discovery monitor --tui`
+//!
+//! Renders the live output of a [`BackgroundMonitor`] in the terminal: a
+//! device table, a scrolling event log and the current scan progress. Meant
+//! for headless servers where the desktop app can't run.
+
+use std::collections::{HashMap, VecDeque};
+use std::io;
+use std::net::Ipv4Addr;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::time::Duration;
+
+use chrono::{DateTime, Local};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Cell, Gauge, List, ListItem, Paragraph, Row, Table};
+use ratatui::{DefaultTerminal, Frame};
+
+use host_discovery::{BackgroundMonitor, NetworkEvent};
+
+/// Number of event log lines kept in memory
+const EVENT_LOG_CAPACITY: usize = 500;
+/// Redraw / input poll period
+const TICK: Duration = Duration::from_millis(250);
+
+/// A row in the device table
+#[derive(Debug, Clone)]
+struct DeviceRow {
+    ip: String,
+    hostname: Option<String>,
+    device_type: String,
+    online: bool,
+    changed_at: DateTime<Local>,
+}
+
+/// Progress of the scan currently running
+#[derive(Debug, Clone, Default)]
+struct ScanProgress {
+    phase: String,
+    percent: u8,
+    message: String,
+}
+
+/// Everything the TUI displays, built up from monitor events
+#[derive(Debug, Default)]
+struct MonitorView {
+    interval_seconds: u64,
+    running: bool,
+    paused: bool,
+    scan_number: u32,
+    scanning: bool,
+    progress: ScanProgress,
+    last_scan: Option<(usize, u64)>,
+    devices: HashMap<String, DeviceRow>,
+    events: VecDeque<(DateTime<Local>, String)>,
+}
+
+impl MonitorView {
+    /// Fold a monitor event into the view
+    fn apply(&mut self, event: &NetworkEvent, at: DateTime<Local>) {
+        match event {
+            NetworkEvent::MonitoringStarted { interval_seconds } => {
+                self.running = true;
+                self.interval_seconds = *interval_seconds;
+            }
+            NetworkEvent::ProfileChanged {
+                interval_seconds, ..
+            } => {
+                self.interval_seconds = *interval_seconds;
+            }
+            NetworkEvent::MonitoringStopped => {
+                self.running = false;
+                self.paused = false;
+                self.scanning = false;
+            }
+            NetworkEvent::MonitoringPaused => self.paused = true,
+            NetworkEvent::MonitoringResumed => self.paused = false,
+            NetworkEvent::ScanStarted { scan_number, .. } => {
+                self.scan_number = *scan_number;
+                self.scanning = true;
+                self.progress = ScanProgress::default();
+            }
+            NetworkEvent::ScanProgress {
+                phase,
+                percent,
+                message,
+            } => {
+                self.progress = ScanProgress {
+                    phase: phase.clone(),
+                    percent: *percent,
+                    message: message.clone(),
+                };
+            }
+            NetworkEvent::ScanCompleted {
+                hosts_found,
+                duration_ms,
+                ..
+            } => {
+                self.scanning = false;
+                self.last_scan = Some((*hosts_found, *duration_ms));
+            }
+            NetworkEvent::NewDeviceDiscovered {
+                ip,
+                mac,
+                hostname,
+                device_type,
+                ..
+            } => {
+                self.devices.insert(
+                    mac.clone(),
+                    DeviceRow {
+                        ip: ip.clone(),
+                        hostname: hostname.clone(),
+                        device_type: device_type.clone(),
+                        online: true,
+                        changed_at: at,
+                    },
+                );
+            }
+            NetworkEvent::DeviceWentOffline { mac, .. } => {
+                if let Some(row) = self.devices.get_mut(mac) {
+                    row.online = false;
+                    row.changed_at = at;
+                }
+            }
+            NetworkEvent::DeviceCameOnline {
+                mac, ip, hostname, ..
+            } => {
+                let row = self
+                    .devices
+                    .entry(mac.clone())
+                    .or_insert_with(|| DeviceRow {
+                        ip: ip.clone(),
+                        hostname: None,
+                        device_type: "UNKNOWN".to_string(),
+                        online: true,
+                        changed_at: at,
+                    });
+                row.ip = ip.clone();
+                row.hostname = hostname.clone().or(row.hostname.take());
+                row.online = true;
+                row.changed_at = at;
+            }
+            NetworkEvent::DeviceIpChanged { mac, new_ip, .. } => {
+                if let Some(row) = self.devices.get_mut(mac) {
+                    row.ip = new_ip.clone();
+                    row.changed_at = at;
+                }
+            }
+            NetworkEvent::DeviceFlapping { .. }
+            | NetworkEvent::ArpAddressTakeover { .. }
+            | NetworkEvent::IpConflict { .. }
+            | NetworkEvent::SharedMacDetected { .. }
+            | NetworkEvent::ArpStormDetected { .. }
+            | NetworkEvent::LatencyDegraded { .. }
+            | NetworkEvent::InternetDegraded { .. }
+            | NetworkEvent::InternetDown { .. }
+            | NetworkEvent::QuietHoursChanged { .. }
+            | NetworkEvent::UnknownDevicePresent { .. }
+            | NetworkEvent::ExpectedDeviceMissing { .. }
+            | NetworkEvent::UnexpectedPortOpen { .. }
+            | NetworkEvent::MonitoringError { .. } => {}
+        }
+
+        if let Some(line) = describe_event(event) {
+            if self.events.len() == EVENT_LOG_CAPACITY {
+                self.events.pop_front();
+            }
+            self.events.push_back((at, line));
+        }
+    }
+
+    /// Devices sorted by IP address
+    fn sorted_devices(&self) -> Vec<(&String, &DeviceRow)> {
+        let mut devices: Vec<_> = self.devices.iter().collect();
+        devices.sort_by_key(|(mac, row)| {
+            (
+                row.ip.parse::<Ipv4Addr>().unwrap_or(Ipv4Addr::UNSPECIFIED),
+                (*mac).clone(),
+            )
+        });
+        devices
+    }
+
+    fn online_count(&self) -> usize {
+        self.devices.values().filter(|d| d.online).count()
+    }
+}
+
+/// One-line summary of an event for the log (`None` for progress noise)
+fn describe_event(event: &NetworkEvent) -> Option<String> {
+    let host = |hostname: &Option<String>| {
+        hostname
+            .as_deref()
+            .map(|h| format!(" [{}]", h))
+            .unwrap_or_default()
+    };
+
+    let line = match event {
+        NetworkEvent::MonitoringStarted { interval_seconds } => {
+            format!("Monitoring started (every {}s)", interval_seconds)
+        }
+        NetworkEvent::MonitoringStopped => "Monitoring stopped".to_string(),
+        NetworkEvent::MonitoringPaused => "Monitoring paused".to_string(),
+        NetworkEvent::MonitoringResumed => "Monitoring resumed".to_string(),
+        NetworkEvent::ScanStarted { scan_number, light } => format!(
+            "{} #{} started",
+            if *light { "Light scan" } else { "Scan" },
+            scan_number
+        ),
+        NetworkEvent::ScanProgress { .. } => return None,
+        NetworkEvent::ScanCompleted {
+            scan_number,
+            hosts_found,
+            duration_ms,
+        } => format!(
+            "Scan #{} complete: {} hosts in {:.1}s",
+            scan_number,
+            hosts_found,
+            *duration_ms as f64 / 1000.0
+        ),
+        NetworkEvent::NewDeviceDiscovered {
+            ip,
+            mac,
+            hostname,
+            device_type,
+            ..
+        } => format!(
+            "New device {} ({}) {}{}",
+            ip,
+            mac,
+            device_type,
+            host(hostname)
+        ),
+        NetworkEvent::DeviceWentOffline {
+            mac,
+            last_ip,
+            hostname,
+            ..
+        } => format!("Offline: {} ({}){}", last_ip, mac, host(hostname)),
+        NetworkEvent::DeviceCameOnline {
+            mac, ip, hostname, ..
+        } => {
+            format!("Back online: {} ({}){}", ip, mac, host(hostname))
+        }
+        NetworkEvent::DeviceIpChanged {
+            mac,
+            old_ip,
+            new_ip,
+            ..
+        } => format!("IP changed: {} -> {} ({})", old_ip, new_ip, mac),
+        NetworkEvent::DeviceFlapping {
+            mac,
+            ip,
+            changes,
+            window_secs,
+        } => format!(
+            "Device flapping: {} ({}), {} changes in {} min",
+            ip,
+            mac,
+            changes,
+            window_secs / 60
+        ),
+        NetworkEvent::ArpAddressTakeover {
+            ip,
+            previous_mac,
+            new_mac,
+            gratuitous,
+        } => format!(
+            "ARP takeover of {}: {} -> {}{}",
+            ip,
+            previous_mac,
+            new_mac,
+            if *gratuitous { " (gratuitous)" } else { "" }
+        ),
+        NetworkEvent::IpConflict { ip, macs } => {
+            format!("IP conflict on {}: {}", ip, macs.join(", "))
+        }
+        NetworkEvent::SharedMacDetected { mac, ips } => {
+            format!("Shared MAC {}: {}", mac, ips.join(", "))
+        }
+        NetworkEvent::ArpStormDetected {
+            mac,
+            ip,
+            request_count,
+            window_secs,
+        } => format!(
+            "ARP storm from {} ({}): {} requests in {}s",
+            ip, mac, request_count, window_secs
+        ),
+        NetworkEvent::LatencyDegraded {
+            mac,
+            baseline_ms,
+            current_ms,
+        } => format!(
+            "Latency of {} up: {} ms (usually {} ms)",
+            mac, current_ms, baseline_ms
+        ),
+        NetworkEvent::ProfileChanged {
+            profile,
+            interval_seconds,
+        } => format!(
+            "Switched to {} profile ({}s interval)",
+            profile, interval_seconds
+        ),
+        NetworkEvent::InternetDegraded { reason, .. } => format!("Internet degraded: {}", reason),
+        NetworkEvent::InternetDown { reason } => format!("Internet down: {}", reason),
+        NetworkEvent::QuietHoursChanged { active: true } => {
+            "Quiet hours: active scanning paused".to_string()
+        }
+        NetworkEvent::QuietHoursChanged { active: false } => "Quiet hours over".to_string(),
+        NetworkEvent::UnknownDevicePresent { mac, ip, .. } => {
+            format!("Not in baseline: {} ({})", ip, mac)
+        }
+        NetworkEvent::ExpectedDeviceMissing { mac, ip, .. } => format!(
+            "Baseline device missing: {} ({})",
+            ip.as_deref().unwrap_or("?"),
+            mac
+        ),
+        NetworkEvent::UnexpectedPortOpen { mac, ip, port } => {
+            format!("Port {} open on {} ({}), not in baseline", port, ip, mac)
+        }
+        NetworkEvent::MonitoringError { message } => format!("Error: {}", message),
+    };
+
+    Some(line)
+}
+
+/// Run the monitor with the terminal UI until the user quits
+pub async fn run(
+    interval: Option<u64>,
+    interface: Option<String>,
+    passive: bool,
+) -> anyhow::Result<()> {
+    let (tx, rx) = mpsc::channel();
+    let monitor = BackgroundMonitor::new();
+    monitor.set_interface(interface).await;
+    monitor.set_passive_discovery(passive);
+    monitor
+        .start(
+            move |event| {
+                let _ = tx.send(event);
+            },
+            interval,
+        )
+        .await
+        .map_err(anyhow::Error::msg)?;
+
+    let result = tokio::task::spawn_blocking(move || {
+        let mut terminal = ratatui::try_init()?;
+        let result = event_loop(&mut terminal, &rx);
+        ratatui::try_restore()?;
+        result
+    })
+    .await;
+
+    monitor.stop();
+    result??;
+    Ok(())
+}
+
+fn event_loop(terminal: &mut DefaultTerminal, rx: &Receiver<NetworkEvent>) -> io::Result<()> {
+    let mut view = MonitorView::default();
+
+    loop {
+        loop {
+            match rx.try_recv() {
+                Ok(event) => view.apply(&event, Local::now()),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => return Ok(()),
+            }
+        }
+
+        terminal.draw(|frame| draw(frame, &view))?;
+
+        if event::poll(TICK)? {
+            if let Event::Key(key) = event::read()? {
+                let ctrl_c =
+                    key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL);
+                if key.kind == KeyEventKind::Press
+                    && (matches!(key.code, KeyCode::Char('q') | KeyCode::Esc) || ctrl_c)
+                {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+fn draw(frame: &mut Frame, view: &MonitorView) {
+    let [progress_area, devices_area, log_area, help_area] = Layout::vertical([
+        Constraint::Length(3),
+        Constraint::Min(6),
+        Constraint::Length(10),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+
+    let status = if !view.running {
+        "stopped".to_string()
+    } else if view.paused && !view.scanning {
+        "paused".to_string()
+    } else if view.scanning {
+        format!("scan #{} - {}", view.scan_number, view.progress.phase)
+    } else {
+        match view.last_scan {
+            Some((hosts, ms)) => format!(
+                "idle - last scan: {} hosts in {:.1}s, every {}s",
+                hosts,
+                ms as f64 / 1000.0,
+                view.interval_seconds
+            ),
+            None => "starting".to_string(),
+        }
+    };
+    let percent = if view.scanning {
+        view.progress.percent.min(100)
+    } else {
+        100
+    };
+    let gauge = Gauge::default()
+        .block(Block::bordered().title(format!(" Host Discovery Monitor ({}) ", status)))
+        .gauge_style(Style::default().fg(Color::Cyan))
+        .percent(percent as u16)
+        .label(if view.scanning {
+            view.progress.message.clone()
+        } else {
+            format!(
+                "{}/{} devices online",
+                view.online_count(),
+                view.devices.len()
+            )
+        });
+    frame.render_widget(gauge, progress_area);
+
+    let rows: Vec<Row> = view
+        .sorted_devices()
+        .into_iter()
+        .map(|(mac, row)| {
+            let (state, color) = if row.online {
+                ("online", Color::Green)
+            } else {
+                ("offline", Color::Red)
+            };
+            Row::new(vec![
+                Cell::from(Span::styled(state, Style::default().fg(color))),
+                Cell::from(row.ip.clone()),
+                Cell::from(mac.clone()),
+                Cell::from(row.device_type.clone()),
+                Cell::from(row.hostname.clone().unwrap_or_default()),
+                Cell::from(row.changed_at.format("%H:%M:%S").to_string()),
+            ])
+        })
+        .collect();
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(8),
+            Constraint::Length(16),
+            Constraint::Length(18),
+            Constraint::Length(14),
+            Constraint::Min(10),
+            Constraint::Length(9),
+        ],
+    )
+    .header(
+        Row::new(["State", "IP", "MAC", "Type", "Hostname", "Changed"])
+            .style(Style::default().add_modifier(Modifier::BOLD)),
+    )
+    .block(Block::bordered().title(format!(" Devices ({}) ", view.devices.len())));
+    frame.render_widget(table, devices_area);
+
+    // Newest events at the bottom; only as many as fit
+    let visible = log_area.height.saturating_sub(2) as usize;
+    let items: Vec<ListItem> = view
+        .events
+        .iter()
+        .skip(view.events.len().saturating_sub(visible))
+        .map(|(at, line)| {
+            ListItem::new(Line::from(vec![
+                Span::styled(
+                    at.format("%H:%M:%S ").to_string(),
+                    Style::default().fg(Color::DarkGray),
+                ),
+                Span::raw(line.clone()),
+            ]))
+        })
+        .collect();
+    frame.render_widget(
+        List::new(items).block(Block::bordered().title(" Events ")),
+        log_area,
+    );
+
+    frame.render_widget(
+        Paragraph::new(" q / Esc: quit").style(Style::default().fg(Color::DarkGray)),
+        help_area,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_view_tracks_device_state() {
+        let mut view = MonitorView::default();
+        let now = Local::now();
+
+        view.apply(
+            &NetworkEvent::MonitoringStarted {
+                interval_seconds: 60,
+            },
+            now,
+        );
+        view.apply(
+            &NetworkEvent::ScanStarted {
+                scan_number: 1,
+                light: false,
+            },
+            now,
+        );
+        view.apply(
+            &NetworkEvent::ScanProgress {
+                phase: "ARP".to_string(),
+                percent: 20,
+                message: "ARP scanning 254 hosts...".to_string(),
+            },
+            now,
+        );
+        assert!(view.scanning);
+        assert_eq!(view.progress.percent, 20);
+
+        for (ip, mac) in [
+            ("192.168.1.20", "aa:00:00:00:00:02"),
+            ("192.168.1.3", "aa:00:00:00:00:01"),
+        ] {
+            view.apply(
+                &NetworkEvent::NewDeviceDiscovered {
+                    ip: ip.to_string(),
+                    mac: mac.to_string(),
+                    hostname: None,
+                    device_type: "PC".to_string(),
+                    vendor: None,
+                    risk_score: 0,
+                    network: None,
+                },
+                now,
+            );
+        }
+        view.apply(
+            &NetworkEvent::ScanCompleted {
+                scan_number: 1,
+                hosts_found: 2,
+                duration_ms: 1500,
+            },
+            now,
+        );
+        view.apply(
+            &NetworkEvent::DeviceWentOffline {
+                mac: "aa:00:00:00:00:02".to_string(),
+                last_ip: "192.168.1.20".to_string(),
+                hostname: None,
+                network: None,
+            },
+            now,
+        );
+        view.apply(
+            &NetworkEvent::DeviceIpChanged {
+                mac: "aa:00:00:00:00:01".to_string(),
+                old_ip: "192.168.1.3".to_string(),
+                new_ip: "192.168.1.30".to_string(),
+                network: None,
+            },
+            now,
+        );
+
+        assert!(!view.scanning);
+        assert_eq!(view.last_scan, Some((2, 1500)));
+        assert_eq!(view.online_count(), 1);
+
+        let order: Vec<&str> = view
+            .sorted_devices()
+            .iter()
+            .map(|(_, row)| row.ip.as_str())
+            .collect();
+        assert_eq!(order, vec!["192.168.1.20", "192.168.1.30"]);
+
+        // Progress updates are not logged
+        assert_eq!(view.events.len(), 7);
+    }
+}
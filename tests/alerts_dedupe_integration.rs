@@ -64,6 +64,7 @@ fn persist_alerts(conn: &rusqlite::Connection, alerts: &[RuntimeAlert]) -> usize
             dedupe_key: None,
             message: &alert.message,
             severity,
+            evidence: alert.evidence.as_ref(),
         };
         let result = queries::insert_alert_if_not_exists(
             conn,
@@ -106,14 +107,15 @@ fn build_scan(host: HostInfo, duration_ms: u64) -> ScanResult {
         total_hosts: 1,
         scan_duration_ms: duration_ms,
         active_hosts: vec![host],
+        wan: None,
+        ssid: None,
     }
 }
 
 #[test]
 fn test_alert_generation_and_dedupe_across_two_consecutive_scans() {
     let db = Database::in_memory().expect("in-memory db should initialize");
-    let conn = db.connection();
-    let conn = conn.lock().expect("connection lock should not be poisoned");
+    let conn = db.writer().expect("writer connection should be available");
 
     let scan1 = build_scan(build_host(), 1200);
     let known_first = queries::get_all_devices(&conn).expect("query should work");
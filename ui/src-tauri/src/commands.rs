@@ -2,74 +2,172 @@
 //!
 //! These commands are callable from the React frontend via `invoke()`.
 
-use std::sync::{Arc, Mutex};
 use std::time::Instant;
 use tokio::sync::Mutex as TokioMutex;
-use tauri::Emitter;
+use tauri::{Emitter, Manager};
 
 // Re-export types from the scanner library
 use host_discovery::{
-    ScanResult, HostInfo,
-    find_valid_interface, list_valid_interfaces, calculate_subnet_ips,
-    active_arp_scan, icmp_scan, tcp_probe_scan, dns_scan,
-    lookup_vendor_info, infer_device_type, calculate_risk_score,
-    guess_os_from_ttl, detect_alerts, detect_alerts_without_baseline, Alert as RuntimeAlert,
+    ScanResult, ScanDiff, HostInfo, HostSort, sort_hosts,
+    find_valid_interface, select_interface, list_interfaces, list_valid_interfaces,
+    InterfaceCandidate, calculate_subnet_ips,
+    chunked_arp_scan, arp_scan_diagnostics, ArpDiagnostics, ArpScan, merge_ip_conflicts, ndp_discover, merge_ipv6_neighbors, icmp_probe_scan, tcp_probe_scan_detailed, inspect_certificates, collect_ssh_host_keys, dns_scan_with,
+    lookup_vendor_info, infer_device_type, infer_device_type_with_services, calculate_risk_score,
+    load_vendor_source, load_vendor_overrides, load_device_type_rules, OuiUpdateSummary, VendorSourceSettings,
+    load_risk_profile, RiskProfile,
+    load_wan_settings, wan_info, wifi_ssid, WanInfo, WanLookupSettings,
+    load_geoip, geoip_lookup, GeoInfo, GeoIpSettings,
+    load_dns_sweep_settings, dns_forward_sweep, match_forward_records, DnsSweepResult, DnsSweepSettings,
+    load_dns_resolver, DnsResolver,
+    guess_os_from_ttl, detect_alerts_with_settings, detect_alerts_without_baseline_with_settings,
+    Alert as RuntimeAlert, AlertRule, AlertRuleSpec, apply_alert_rules, scan_rule_inputs, alerts,
     // Database
-    Database, DeviceRecord, ScanRecord, NetworkStats, AlertRecord,
+    AtRestEncryption, Database, ConnectionGuard, MaintenanceReport, QueryCache, DeviceRecord, DeviceSettings, ScanRecord, NetworkStats, AlertRecord,
+    DeviceDetails, DevicePurgePreview, DevicePurgeSummary, SmartGroupRecord, VendorOverrideRecord,
+    DeviceTypeRule, DeviceTypeRuleRecord,
+    LdapConfig, LdapEnrichmentSummary, PortExposureRecord, RiskAcceptance, RiskFinding,
+    DeviceMerge, DevicePortRecord, PortChangeRecord, TopologyEdgeRecord, ToolRunRecord, FindingHistoryRecord,
+    DeviceDailySummary, ScanDailySummary, PruneSummary, RetentionPolicy, TagCount, AttributeCount,
+    DeviceGroupRecord, LatencyPercentiles, LatencyPoint, LatencyResolution, AvailabilityWindow,
+    Page, DeviceQuery, AlertQuery, ScanQuery, NetworkProfile,
+    database::{dump, latency, networks, retention},
+    // Peers
+    peers::{self, Announcement, PeerInfo, PeerPresence, PeerSettings},
+    // Smart groups
+    DeviceFilter, groups, GroupPolicy, apply_group_policies,
     AlertSeverity as DbAlertSeverity, AlertType as DbAlertType,
     database::queries::{self, lookup_port_warnings},
     // Monitoring
-    BackgroundMonitor, MonitoringStatus, NetworkEvent,
+    BackgroundMonitor, MonitoringStats, MonitoringStatus, NetworkEvent, PacketCapture,
+    load_monitor_schedule, MonitorSchedule,
+    load_internet_check_settings, InternetCheckSettings, InternetHealth,
+    monitor::{event_log, internet, presence, EventFilter, PresenceSession, StoredEvent},
+    // Scheduled scans
+    scheduler::{self, ScanSchedule, ScanScheduleSpec},
+    // Notifications
+    load_notification_settings, notify_alerts, DesktopNotice, MqttPublisher, Notification,
+    NotificationSettings, SyslogForwarder,
+    notify::{ChatNotifier, EmailNotifier, MqttNotifier, Notifier, SyslogNotifier, WebhookNotifier},
+    scanner::ArpThresholds,
+    config::{DEFAULT_CAPTURE_BUFFER_BYTES, SENSITIVE_PORTS, STEALTH_MIN_SCANS},
     // Exports
-    export_devices_csv, export_hosts_csv, export_topology_json, 
+    export_devices_csv, export_hosts_csv, export_port_exposure_csv, export_tool_runs_csv, export_topology_json, 
     export_scan_result_json, generate_scan_report_pdf, generate_network_health_pdf,
+    generate_dashboard_html, refresh_public_dashboard, DashboardSettings, DashboardSnapshot,
+    ExportSignature, ExportSigner,
+    build_change_journal, export_journal_csv, export_journal_markdown,
     // Insights
-    SecurityReport,
-    insights::{calculate_security_grade, filter_vulnerabilities_by_context},
+    SecurityReport, CloudServiceInventory, PlannedChange, SimulationResult, simulate_changes,
+    insights::{calculate_security_grade, filter_vulnerabilities_by_context, find_stealth_hosts, StealthHost},
+    compliance_report, find_baseline_deviations, ComplianceReport, NetworkBaseline,
 };
 
 
 /// Application state holding database connection
 pub struct AppState {
-    pub db: Mutex<Database>,
+    /// Pooled connections; cloned freely into commands and background tasks
+    pub db: Database,
+    /// Device list and stats polled by the UI
+    pub cache: QueryCache,
+    /// Selected network profile; `None` shows every network
+    pub network: std::sync::RwLock<Option<i64>>,
 }
 
 impl AppState {
     pub fn new() -> Result<Self, String> {
         let db_path = Database::default_path();
-        let db = Database::new(db_path)
+        let db = Database::open(db_path, &AtRestEncryption::from_env())
             .map_err(|e| format!("Failed to initialize database: {}", e))?;
-        Ok(Self { db: Mutex::new(db) })
+
+        // Prefer the configured vendor source over the bundled table
+        if let Ok(conn) = db.writer() {
+            if let Err(e) = load_vendor_source(&conn) {
+                eprintln!("[WARN] Failed to load vendor source, using bundled table: {}", e);
+            }
+            if let Err(e) = load_vendor_overrides(&conn) {
+                eprintln!("[WARN] Failed to load vendor overrides: {}", e);
+            }
+            if let Err(e) = load_device_type_rules(&conn) {
+                eprintln!("[WARN] Failed to load device type rules: {}", e);
+            }
+            if let Err(e) = load_risk_profile(&conn) {
+                eprintln!("[WARN] Failed to load risk profile, using defaults: {}", e);
+            }
+            if let Err(e) = load_geoip(&conn) {
+                eprintln!("[WARN] Failed to load GeoIP databases: {}", e);
+            }
+            if let Err(e) = retention::load_retention_policy(&conn)
+                .and_then(|policy| retention::prune(&conn, &policy))
+            {
+                eprintln!("[WARN] Failed to prune old history: {:#}", e);
+            }
+        }
+
+        // Start on the network scanned last
+        let network = db
+            .read(networks::latest_network_id)
+            .unwrap_or_else(|e| {
+                eprintln!("[WARN] Failed to find the last scanned network: {}", e);
+                None
+            });
+
+        Ok(Self {
+            db,
+            cache: QueryCache::new(),
+            network: std::sync::RwLock::new(network),
+        })
+    }
+
+    /// Network profile listings are scoped to (`None` for all networks)
+    pub fn active_network(&self) -> Option<i64> {
+        *self.network.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn set_active_network(&self, network_id: Option<i64>) {
+        *self.network.write().unwrap_or_else(|e| e.into_inner()) = network_id;
     }
 }
 
 /// Monitoring state holding background monitor
 pub struct MonitorState {
     pub monitor: TokioMutex<BackgroundMonitor>,
+    pub sni_task: TokioMutex<Option<tokio::task::JoinHandle<()>>>,
+    pub mdns_task: TokioMutex<Option<tokio::task::JoinHandle<()>>>,
+    pub packet_capture: TokioMutex<Option<PacketCapture>>,
+    pub arp_watch: TokioMutex<Option<tokio::task::JoinHandle<()>>>,
+    /// mDNS self-advertisement, when enabled in settings
+    pub peer_presence: TokioMutex<Option<PeerPresence>>,
+    /// Monitor subscriptions forwarding events to MQTT and syslog
+    pub forwarders: TokioMutex<Vec<u64>>,
 }
 
 impl MonitorState {
     pub fn new() -> Self {
         Self {
             monitor: TokioMutex::new(BackgroundMonitor::new()),
+            sni_task: TokioMutex::new(None),
+            mdns_task: TokioMutex::new(None),
+            packet_capture: TokioMutex::new(None),
+            arp_watch: TokioMutex::new(None),
+            peer_presence: TokioMutex::new(None),
+            forwarders: TokioMutex::new(Vec::new()),
         }
     }
 }
 
 
-fn get_db_connection(state: &tauri::State<'_, AppState>) -> Result<Arc<Mutex<rusqlite::Connection>>, String> {
-    let db = state
-        .db
-        .lock()
-        .map_err(|_| "Database state lock poisoned".to_string())?;
-    Ok(db.connection())
+fn get_db_connection(state: &tauri::State<'_, AppState>) -> Result<Database, String> {
+    Ok(state.db.clone())
+}
+
+/// Lock the writer connection, for commands that modify the database
+fn lock_db_connection(db: &Database) -> Result<ConnectionGuard<'_>, String> {
+    db.writer().map_err(|e| e.to_string())
 }
 
-fn lock_db_connection(
-    conn: &Arc<Mutex<rusqlite::Connection>>,
-) -> Result<std::sync::MutexGuard<'_, rusqlite::Connection>, String> {
-    conn.lock()
-        .map_err(|_| "Database connection lock poisoned".to_string())
+/// Check out a pooled read-only connection, so reads don't wait on writes
+fn read_db_connection(db: &Database) -> Result<ConnectionGuard<'_>, String> {
+    db.reader().map_err(|e| e.to_string())
 }
 
 fn map_alert_type(alert: &RuntimeAlert) -> DbAlertType {
@@ -78,8 +176,20 @@ fn map_alert_type(alert: &RuntimeAlert) -> DbAlertType {
         "DEVICE_OFFLINE" => DbAlertType::DeviceOffline,
         "DEVICE_ONLINE" => DbAlertType::DeviceOnline,
         "HIGH_RISK" => DbAlertType::HighRisk,
-        "UNUSUAL_PORT" => DbAlertType::PortChange,
+        "UNUSUAL_PORT" | "PORT_DEVIATION" => DbAlertType::PortChange,
         "IP_CHANGED" => DbAlertType::IpChange,
+        "IP_CONFLICT" => DbAlertType::IpConflict,
+        "MAC_CLONED" => DbAlertType::MacCloned,
+        "GATEWAY_MAC_CHANGED" => DbAlertType::GatewayMacChanged,
+        "ROGUE_DHCP" => DbAlertType::RogueDhcp,
+        "PORT_OPENED" => DbAlertType::PortOpened,
+        "CERT_EXPIRING" => DbAlertType::CertificateExpiring,
+        "INTERNET_DOWN" => DbAlertType::InternetDown,
+        "FLAPPING" => DbAlertType::Flapping,
+        "UNTRUSTED_DEVICE" => DbAlertType::UntrustedDevice,
+        "BEHAVIOR_ANOMALY" => DbAlertType::BehaviorAnomaly,
+        "MAC_SPOOFING" => DbAlertType::MacSpoofing,
+        "SSH_HOST_KEY_CHANGED" => DbAlertType::SshHostKeyChanged,
         _ => DbAlertType::Custom,
     }
 }
@@ -94,39 +204,102 @@ fn map_alert_severity(alert: &RuntimeAlert) -> DbAlertSeverity {
     }
 }
 
-fn extract_port_from_alert_message(message: &str) -> Option<u16> {
-    message
-        .split(|c: char| !c.is_ascii_digit())
-        .find_map(|chunk| chunk.parse::<u16>().ok())
+fn load_device_settings(state: &tauri::State<'_, AppState>) -> Vec<DeviceSettings> {
+    let loaded = get_db_connection(state).and_then(|db_conn| {
+        let conn = read_db_connection(&db_conn)?;
+        queries::get_all_device_settings(&conn).map_err(|e| e.to_string())
+    });
+
+    loaded.unwrap_or_else(|e| {
+        eprintln!("[WARN] Failed to load device settings, using defaults: {}", e);
+        Vec::new()
+    })
 }
 
-fn build_alert_dedupe_key(alert: &RuntimeAlert) -> String {
-    let mac = alert.device_mac.as_deref().unwrap_or("unknown-mac");
-    let ip = alert.device_ip.as_deref().unwrap_or("unknown-ip");
+fn load_device_tags(
+    state: &tauri::State<'_, AppState>,
+) -> std::collections::HashMap<String, Vec<String>> {
+    let loaded = get_db_connection(state).and_then(|db_conn| {
+        let conn = read_db_connection(&db_conn)?;
+        queries::get_tags_by_mac(&conn).map_err(|e| e.to_string())
+    });
 
-    match alert.alert_type.as_str() {
-        "NEW_DEVICE" => format!("new-device:{mac}"),
-        "DEVICE_OFFLINE" => format!("device-offline:{mac}"),
-        "DEVICE_ONLINE" => format!("device-online:{mac}"),
-        "HIGH_RISK" => format!("high-risk:{mac}"),
-        "UNUSUAL_PORT" => {
-            let port = extract_port_from_alert_message(&alert.message)
-                .map(|p| p.to_string())
-                .unwrap_or_else(|| "unknown".to_string());
-            format!("unusual-port:{mac}:{port}")
-        }
-        "IP_CHANGED" => format!("ip-changed:{mac}:{ip}"),
-        _ => format!("custom:{mac}:{ip}"),
-    }
+    loaded.unwrap_or_else(|e| {
+        eprintln!("[WARN] Failed to load device tags, tag filters match nothing: {}", e);
+        Default::default()
+    })
+}
+
+fn load_untrusted_device_alerts(
+    state: &tauri::State<'_, AppState>,
+) -> alerts::UntrustedDeviceAlerts {
+    let loaded = get_db_connection(state).and_then(|db_conn| {
+        let conn = read_db_connection(&db_conn)?;
+        alerts::load_untrusted_device_alerts(&conn).map_err(|e| e.to_string())
+    });
+
+    loaded.unwrap_or_else(|e| {
+        eprintln!("[WARN] Failed to load untrusted device alert mode, leaving it off: {}", e);
+        alerts::UntrustedDeviceAlerts::default()
+    })
+}
+
+fn load_device_behavior(
+    state: &tauri::State<'_, AppState>,
+) -> std::collections::HashMap<String, alerts::DeviceBehavior> {
+    let loaded = get_db_connection(state).and_then(|db_conn| {
+        let conn = read_db_connection(&db_conn)?;
+        alerts::load_device_behavior(&conn).map_err(|e| e.to_string())
+    });
+
+    loaded.unwrap_or_else(|e| {
+        eprintln!("[WARN] Failed to load device history, skipping anomaly alerts: {}", e);
+        std::collections::HashMap::new()
+    })
+}
+
+fn load_ssh_host_keys(state: &tauri::State<'_, AppState>) -> Vec<alerts::StoredSshHostKey> {
+    let loaded = get_db_connection(state).and_then(|db_conn| {
+        let conn = read_db_connection(&db_conn)?;
+        alerts::load_ssh_host_keys(&conn).map_err(|e| e.to_string())
+    });
+
+    loaded.unwrap_or_else(|e| {
+        eprintln!("[WARN] Failed to load SSH host keys, skipping key change alerts: {}", e);
+        Vec::new()
+    })
 }
 
+fn load_device_groups(state: &tauri::State<'_, AppState>) -> Vec<DeviceGroupRecord> {
+    let loaded = get_db_connection(state).and_then(|db_conn| {
+        let conn = read_db_connection(&db_conn)?;
+        queries::get_device_groups(&conn).map_err(|e| e.to_string())
+    });
+
+    loaded.unwrap_or_else(|e| {
+        eprintln!("[WARN] Failed to load device groups, skipping group policies: {}", e);
+        Vec::new()
+    })
+}
 
 /// Perform a network scan and save to database
 /// 
-/// This calls the existing host-discovery scanner library.
+/// This calls the existing host-discovery scanner library. `interface` picks
+/// the adapter to scan; when omitted one is selected automatically. `sort`
+/// orders the hosts ("ip" (default), "risk", "last_seen" or "hostname").
 #[tauri::command]
-pub async fn scan_network(state: tauri::State<'_, AppState>) -> Result<ScanResult, String> {
+pub async fn scan_network(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    monitor_state: tauri::State<'_, MonitorState>,
+    interface: Option<String>,
+    sort: Option<String>,
+) -> Result<ScanResult, String> {
     let start = Instant::now();
+    let sort: HostSort = match sort.as_deref() {
+        Some(sort) => sort.parse()?,
+        None => HostSort::Ip,
+    };
 
     let known_devices = match get_db_connection(&state) {
         Ok(db_conn) => match lock_db_connection(&db_conn) {
@@ -147,23 +320,68 @@ pub async fn scan_network(state: tauri::State<'_, AppState>) -> Result<ScanResul
             None
         }
     };
+
+    // mDNS services seen per device (from passive discovery), used for type inference
+    let known_services = get_db_connection(&state)
+        .and_then(|db_conn| {
+            let conn = lock_db_connection(&db_conn)?;
+            queries::get_service_types_by_device(&conn).map_err(|e| e.to_string())
+        })
+        .unwrap_or_else(|e| {
+            eprintln!("[WARN] Failed to load advertised services for type inference: {}", e);
+            Default::default()
+        });
     
-    // Find a valid network interface
-    let interface = find_valid_interface()
+    // Public IP / ISP lookup runs alongside the scan
+    let wan_settings = get_db_connection(&state)
+        .and_then(|db_conn| {
+            let conn = lock_db_connection(&db_conn)?;
+            load_wan_settings(&conn).map_err(|e| e.to_string())
+        })
+        .unwrap_or_else(|e| {
+            eprintln!("[WARN] Failed to load WAN lookup settings: {}", e);
+            Default::default()
+        });
+    let wan_lookup = wan_settings
+        .enabled
+        .then(|| tokio::task::spawn_blocking(move || wan_info(&wan_settings)));
+
+    // Find the requested (or best) network interface
+    let interface = select_interface(interface.as_deref())
         .map_err(|e| format!("Failed to find network interface: {}", e))?;
 
     // Calculate subnet IPs
     let (subnet, ips) = calculate_subnet_ips(&interface)
         .map_err(|e| format!("Failed to calculate subnet: {}", e))?;
 
+    // Split the range with trusted peers scanning the same subnet
+    let presence = monitor_state.peer_presence.lock().await;
+    let shared_plan = match presence.as_ref() {
+        Some(presence) => presence.plan_shared_scan(&subnet.to_string(), &ips).await,
+        None => None,
+    };
+    let targets = shared_plan
+        .as_ref()
+        .map(|plan| plan.targets.clone())
+        .unwrap_or_else(|| ips.clone());
+
     // Run ARP scan (blocking, so we spawn it)
-    let arp_hosts = {
+    let ArpScan { hosts: arp_hosts, conflicts, .. } = {
         let interface_clone = interface.clone();
-        let ips_clone = ips.clone();
+        let ips_clone = targets.clone();
         let subnet_clone = subnet;
         
+        // Large subnets are scanned one /24 chunk at a time
         tokio::task::spawn_blocking(move || {
-            active_arp_scan(&interface_clone, &ips_clone, &subnet_clone)
+            chunked_arp_scan(&interface_clone, &ips_clone, &subnet_clone, |progress| {
+                let _ = app.emit("scan-progress", serde_json::json!({
+                    "phase": "arp",
+                    "progress": progress.percent() / 3,
+                    "chunk": progress.chunk,
+                    "chunks": progress.chunks,
+                    "hostsFound": progress.hosts_found
+                }));
+            })
         })
         .await
         .map_err(|e| format!("ARP scan task failed: {}", e))?
@@ -174,11 +392,12 @@ pub async fn scan_network(state: tauri::State<'_, AppState>) -> Result<ScanResul
 
     // Run ICMP and TCP scans in parallel
     let (response_times, port_results) = tokio::join!(
-        icmp_scan(&arp_hosts),
-        tcp_probe_scan(&arp_hosts)
+        icmp_probe_scan(&arp_hosts),
+        tcp_probe_scan_detailed(&arp_hosts)
     );
 
-    let response_times = response_times.map_err(|e| format!("ICMP scan failed: {}", e))?;
+    let icmp = response_times.map_err(|e| format!("ICMP scan failed: {}", e))?;
+    let (response_times, host_states) = (icmp.replies, icmp.states);
     let port_results = port_results.map_err(|e| format!("TCP scan failed: {}", e))?;
 
     let icmp_count = response_times.len();
@@ -190,7 +409,41 @@ pub async fn scan_network(state: tauri::State<'_, AppState>) -> Result<ScanResul
         .copied()
         .collect();
     
-    let dns_hostnames = dns_scan(&host_ips).await;
+    let dns_resolver = get_db_connection(&state)
+        .and_then(|db_conn| {
+            let conn = lock_db_connection(&db_conn)?;
+            load_dns_resolver(&conn).map_err(|e| e.to_string())
+        })
+        .unwrap_or_else(|e| {
+            eprintln!("[WARN] Failed to load DNS resolver setting: {}", e);
+            Default::default()
+        });
+    let mut dns_hostnames = dns_scan_with(&host_ips, &dns_resolver).await;
+
+    // Forward sweep of the local domain catches static entries without PTR records
+    let sweep_settings = get_db_connection(&state)
+        .and_then(|db_conn| {
+            let conn = lock_db_connection(&db_conn)?;
+            load_dns_sweep_settings(&conn).map_err(|e| e.to_string())
+        })
+        .unwrap_or_else(|e| {
+            eprintln!("[WARN] Failed to load DNS sweep settings: {}", e);
+            Default::default()
+        });
+    if sweep_settings.enabled {
+        let known: Vec<String> = dns_hostnames.values().cloned().collect();
+        let sweep =
+            tokio::task::spawn_blocking(move || dns_forward_sweep(&sweep_settings, &known)).await;
+        match sweep {
+            Ok(Ok(sweep)) => {
+                for (ip, name) in match_forward_records(&sweep.records, &host_ips) {
+                    dns_hostnames.entry(ip).or_insert(name);
+                }
+            }
+            Ok(Err(e)) => eprintln!("[WARN] Forward DNS sweep failed: {:#}", e),
+            Err(e) => eprintln!("[WARN] Forward DNS sweep task failed: {}", e),
+        }
+    }
 
     // Build host info list
     let mut active_hosts: Vec<HostInfo> = arp_hosts
@@ -201,16 +454,22 @@ pub async fn scan_network(state: tauri::State<'_, AppState>) -> Result<ScanResul
             let response_time = icmp_result.map(|r| r.duration.as_millis() as u64);
             let ttl = icmp_result.and_then(|r| r.ttl);
             let os_guess = ttl.map(guess_os_from_ttl);
-            let open_ports = port_results.get(ip).cloned().unwrap_or_default();
+            let ports = port_results.get(ip).cloned().unwrap_or_default();
+            let open_ports = ports.open;
             
             let mac_str = format!("{}", mac);
             let vendor_info = lookup_vendor_info(&mac_str);
             
             let is_gateway = ip.octets()[3] == 1 || open_ports.contains(&80);
-            let device_type = infer_device_type(
+            let services = known_services
+                .get(&mac_str)
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            let device_type = infer_device_type_with_services(
                 vendor_info.vendor.as_deref(),
                 dns_hostnames.get(ip).map(|s| s.as_str()),
                 &open_ports,
+                services,
                 is_gateway,
             );
             let risk_score = calculate_risk_score(
@@ -303,6 +562,12 @@ pub async fn scan_network(state: tauri::State<'_, AppState>) -> Result<ScanResul
                 device_type: device_type.as_str().to_string(),
                 risk_score,
                 open_ports,
+                filtered_ports: ports.filtered,
+                host_state: host_states.get(ip).copied(),
+                conflicting_macs: Vec::new(),
+                ipv6_addresses: Vec::new(),
+                device_id: None,
+                last_seen: None,
                 discovery_method: method,
                 hostname: dns_hostnames.get(ip).cloned(),
                 system_description: None,
@@ -367,6 +632,12 @@ pub async fn scan_network(state: tauri::State<'_, AppState>) -> Result<ScanResul
         vulnerabilities: local_vulnerabilities,
         port_warnings: Vec::new(),
         security_grade: String::new(),
+        filtered_ports: Vec::new(),
+        host_state: None,
+        conflicting_macs: Vec::new(),
+        ipv6_addresses: Vec::new(),
+        device_id: None,
+        last_seen: None,
     };
     
     // Calculate security grade for local machine
@@ -374,16 +645,55 @@ pub async fn scan_network(state: tauri::State<'_, AppState>) -> Result<ScanResul
     
     active_hosts.push(local_host);
 
-    // Sort by IP
-    active_hosts.sort_by(|a, b| {
-        let ip_a: std::net::Ipv4Addr = a.ip.parse().unwrap_or(std::net::Ipv4Addr::UNSPECIFIED);
-        let ip_b: std::net::Ipv4Addr = b.ip.parse().unwrap_or(std::net::Ipv4Addr::UNSPECIFIED);
-        ip_a.cmp(&ip_b)
-    });
+    if let Some(plan) = shared_plan {
+        if let Some(presence) = presence.as_ref() {
+            if let Err(e) = presence.publish_share(&subnet.to_string(), &targets, &active_hosts) {
+                eprintln!("[WARN] Failed to share scan results with peers: {}", e);
+            }
+        }
+        plan.merge_into(&mut active_hosts);
+    }
+    drop(presence);
+
+    // IPv6-only devices never answer ARP; find them on the link-local segment
+    let ndp_interface = interface.clone();
+    match tokio::task::spawn_blocking(move || ndp_discover(&ndp_interface)).await {
+        Ok(Ok(neighbors)) => {
+            merge_ipv6_neighbors(&mut active_hosts, &neighbors);
+        }
+        Ok(Err(e)) => eprintln!("[WARN] IPv6 neighbor discovery failed: {}", e),
+        Err(e) => eprintln!("[WARN] IPv6 neighbor discovery task failed: {}", e),
+    }
+
+    // Keep every device answering for a contested address
+    merge_ip_conflicts(&mut active_hosts, &conflicts);
+
+    // Sort by IP (IPv6-only devices last)
+    sort_hosts(&mut active_hosts, HostSort::Ip);
 
     let duration = start.elapsed().as_millis() as u64;
 
-    let scan_result = ScanResult {
+    let wan = match wan_lookup {
+        Some(lookup) => match lookup.await {
+            Ok(Ok(wan)) => Some(wan),
+            Ok(Err(e)) => {
+                eprintln!("[WARN] WAN lookup failed: {:#}", e);
+                None
+            }
+            Err(e) => {
+                eprintln!("[WARN] WAN lookup task failed: {}", e);
+                None
+            }
+        },
+        None => None,
+    };
+
+    let name = interface.name.clone();
+    let ssid = tokio::task::spawn_blocking(move || wifi_ssid(&name))
+        .await
+        .unwrap_or_default();
+
+    let mut scan_result = ScanResult {
         interface_name: interface.name,
         local_ip: interface.ip.to_string(),
         local_mac: format!("{}", interface.mac),
@@ -394,28 +704,87 @@ pub async fn scan_network(state: tauri::State<'_, AppState>) -> Result<ScanResul
         total_hosts: active_hosts.len(),
         scan_duration_ms: duration,
         active_hosts,
+        wan,
+        ssid,
     };
 
+    let device_settings = load_device_settings(&state);
     let detected_alerts = if let Some(known) = known_devices.as_ref() {
-        detect_alerts(known, &scan_result.active_hosts)
+        detect_alerts_with_settings(known, &scan_result.active_hosts, &device_settings)
     } else {
         eprintln!("[WARN] Known-device baseline unavailable; generating baseline-independent alerts only");
-        detect_alerts_without_baseline(&scan_result.active_hosts)
+        detect_alerts_without_baseline_with_settings(&scan_result.active_hosts, &device_settings)
     };
+    let untrusted_mode = load_untrusted_device_alerts(&state);
+    let mut detected_alerts = alerts::escalate_untrusted_devices(
+        detected_alerts,
+        &scan_result.active_hosts,
+        &device_settings,
+        &untrusted_mode,
+    );
+    detected_alerts.extend(alerts::detect_behavior_anomalies(
+        &scan_result.active_hosts,
+        &load_device_behavior(&state),
+        &device_settings,
+        chrono::Local::now(),
+    ));
+    let certificates = inspect_certificates(&scan_result.active_hosts).await;
+    detected_alerts.extend(alerts::detect_expiring_certificates(
+        &certificates,
+        &device_settings,
+        chrono::Utc::now(),
+    ));
+    let ssh_host_keys = collect_ssh_host_keys(&scan_result.active_hosts).await;
+    detected_alerts.extend(alerts::detect_ssh_host_key_changes(
+        &load_ssh_host_keys(&state),
+        &ssh_host_keys,
+        &device_settings,
+        chrono::Utc::now(),
+    ));
+    let detected_alerts = apply_group_policies(
+        detected_alerts,
+        &scan_result.active_hosts,
+        &load_device_groups(&state),
+        chrono::Local::now().naive_local(),
+    );
 
     // Save scan result to database
     {
         match get_db_connection(&state) {
             Ok(db_conn) => match lock_db_connection(&db_conn) {
                 Ok(conn) => {
-                    if let Err(e) = queries::insert_scan(&conn, &scan_result) {
-                        eprintln!("[WARN] Failed to save scan to database: {}", e);
+                    match queries::insert_scan(&conn, &scan_result)
+                        .and_then(|_| networks::latest_network_id(&conn))
+                    {
+                        // Follow the network just scanned
+                        Ok(network_id) => state.set_active_network(network_id),
+                        Err(e) => eprintln!("[WARN] Failed to save scan to database: {}", e),
+                    }
+                    if let Err(e) =
+                        queries::attach_device_ids(&conn, &mut scan_result.active_hosts)
+                    {
+                        eprintln!("[WARN] Failed to look up stored devices: {}", e);
+                    }
+                    if let Err(e) = latency::downsample_latency(&conn) {
+                        eprintln!("[WARN] Failed to downsample latency history: {}", e);
                     }
+                    if let Err(e) =
+                        alerts::record_certificates(&conn, &certificates, chrono::Utc::now())
+                    {
+                        eprintln!("[WARN] Failed to save certificates: {}", e);
+                    }
+                    if let Err(e) =
+                        alerts::record_ssh_host_keys(&conn, &ssh_host_keys, chrono::Utc::now())
+                    {
+                        eprintln!("[WARN] Failed to save SSH host keys: {}", e);
+                    }
+                    state.cache.invalidate();
 
+                    let mut notifications = Vec::new();
                     for alert in &detected_alerts {
                         let alert_type = map_alert_type(alert);
                         let severity = map_alert_severity(alert);
-                        let dedupe_key = build_alert_dedupe_key(alert);
+                        let dedupe_key = alert.dedupe_key();
                         let alert_insert = queries::AlertInsert {
                             alert_type,
                             device_id: None,
@@ -424,15 +793,50 @@ pub async fn scan_network(state: tauri::State<'_, AppState>) -> Result<ScanResul
                             dedupe_key: None,
                             message: &alert.message,
                             severity,
+                            evidence: alert.evidence.as_ref(),
                         };
-                        if let Err(e) = queries::insert_alert_if_not_exists(
+                        match queries::insert_alert_if_not_exists(
                             &conn,
                             &alert_insert,
                             &dedupe_key,
-                            30,
+                            alert.alert_type.cooldown_minutes(),
                         ) {
-                            eprintln!("[WARN] Failed to save alert to database: {}", e);
+                            Ok(Some(_))
+                                if alert.alert_type == alerts::AlertType::UntrustedDevice
+                                    && !untrusted_mode.notify => {}
+                            Ok(Some(id)) => {
+                                notifications.push(Notification::from_alert(alert, Some(id)))
+                            }
+                            Ok(None) => {}
+                            Err(e) => eprintln!("[WARN] Failed to save alert to database: {}", e),
+                        }
+                    }
+                    if let Err(e) = notify_alerts(&conn, notifications) {
+                        eprintln!("[WARN] Failed to send alert notifications: {}", e);
+                    }
+                    let rule_inputs = scan_rule_inputs(&scan_result.active_hosts, &detected_alerts);
+                    if let Err(e) = apply_alert_rules(&conn, &rule_inputs) {
+                        eprintln!("[WARN] Failed to apply alert rules: {}", e);
+                    }
+
+                    if let Err(e) = queries::alert_expired_risk_acceptances(&conn) {
+                        eprintln!("[WARN] Failed to check expired risk acceptances: {}", e);
+                    }
+
+                    if let Err(e) = refresh_public_dashboard(&conn) {
+                        eprintln!("[WARN] Failed to publish dashboard: {}", e);
+                    }
+
+                    match queries::get_network_baseline(&conn) {
+                        Ok(Some(baseline)) => {
+                            for deviation in
+                                find_baseline_deviations(&baseline, &scan_result.active_hosts)
+                            {
+                                let _ = app.emit("network-event", &NetworkEvent::from(deviation));
+                            }
                         }
+                        Ok(None) => {}
+                        Err(e) => eprintln!("[WARN] Failed to load network baseline: {}", e),
                     }
                 }
                 Err(e) => eprintln!(
@@ -447,9 +851,29 @@ pub async fn scan_network(state: tauri::State<'_, AppState>) -> Result<ScanResul
         }
     }
 
+    if sort != HostSort::Ip {
+        scan_result.sort_hosts(sort);
+    }
     Ok(scan_result)
 }
 
+/// Run only the ARP phase of a scan, logging every request and reply
+///
+/// For debugging intermittently missed hosts (e.g. switches rate-limiting
+/// broadcasts). Nothing is saved.
+#[tauri::command]
+pub async fn run_arp_diagnostics(interface: Option<String>) -> Result<ArpDiagnostics, String> {
+    let interface = select_interface(interface.as_deref())
+        .map_err(|e| format!("Failed to find network interface: {}", e))?;
+    let (subnet, ips) = calculate_subnet_ips(&interface)
+        .map_err(|e| format!("Failed to calculate subnet: {}", e))?;
+
+    tokio::task::spawn_blocking(move || arp_scan_diagnostics(&interface, &ips, &subnet))
+        .await
+        .map_err(|e| format!("ARP diagnostics task failed: {}", e))?
+        .map_err(|e| format!("ARP diagnostics failed: {}", e))
+}
+
 /// Get available network interfaces
 #[tauri::command]
 pub fn get_interfaces() -> Result<Vec<String>, String> {
@@ -460,193 +884,2570 @@ pub fn get_interfaces() -> Result<Vec<String>, String> {
     Ok(interfaces)
 }
 
+/// List every scannable interface address with its selection score
+#[tauri::command]
+pub fn list_network_interfaces() -> Result<Vec<InterfaceCandidate>, String> {
+    let interfaces = list_interfaces();
+    if interfaces.is_empty() {
+        return Err("No valid interfaces found".to_string());
+    }
+    Ok(interfaces)
+}
+
 // =====================================================
 // Database Commands
 // =====================================================
 
-/// Get recent scan history
+/// Get recent scan history, optionally only scans that found a device with `tag`
 #[tauri::command]
-pub fn get_scan_history(state: tauri::State<'_, AppState>, limit: Option<i32>) -> Result<Vec<ScanRecord>, String> {
+pub fn get_scan_history(
+    state: tauri::State<'_, AppState>,
+    limit: Option<i32>,
+    tag: Option<String>,
+) -> Result<Vec<ScanRecord>, String> {
     let conn = get_db_connection(&state)?;
-    let conn = lock_db_connection(&conn)?;
+    let conn = read_db_connection(&conn)?;
     
-    queries::get_recent_scans(&conn, limit.unwrap_or(20))
+    let limit = limit.unwrap_or(20);
+    queries::get_recent_scans_in_network(&conn, state.active_network(), tag.as_deref(), limit)
         .map_err(|e| format!("Failed to get scan history: {}", e))
 }
 
-/// Get all known devices
+/// Filtered page of scan history
 #[tauri::command]
-pub fn get_all_devices(state: tauri::State<'_, AppState>) -> Result<Vec<DeviceRecord>, String> {
+pub fn query_scans(
+    state: tauri::State<'_, AppState>,
+    query: ScanQuery,
+) -> Result<Page<ScanRecord>, String> {
     let conn = get_db_connection(&state)?;
-    let conn = lock_db_connection(&conn)?;
-    
-    queries::get_all_devices(&conn)
-        .map_err(|e| format!("Failed to get devices: {}", e))
+    let conn = read_db_connection(&conn)?;
+
+    let query = ScanQuery {
+        network_id: query.network_id.or(state.active_network()),
+        ..query
+    };
+    queries::query_scans(&conn, &query).map_err(|e| format!("Failed to query scans: {}", e))
 }
 
-/// Get device by MAC address
+/// Label a scan (e.g. "after router firmware update"); a blank label clears it
 #[tauri::command]
-pub fn get_device_by_mac(state: tauri::State<'_, AppState>, mac: String) -> Result<Option<DeviceRecord>, String> {
+pub fn set_scan_label(
+    state: tauri::State<'_, AppState>,
+    scan_id: i64,
+    label: Option<String>,
+) -> Result<(), String> {
     let conn = get_db_connection(&state)?;
     let conn = lock_db_connection(&conn)?;
-    
-    queries::get_device_by_mac(&conn, &mac)
-        .map_err(|e| format!("Failed to get device: {}", e))
+
+    match queries::set_scan_label(&conn, scan_id, label.as_deref()) {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(format!("Scan {} not found", scan_id)),
+        Err(e) => Err(format!("Failed to label scan: {}", e)),
+    }
 }
 
-/// Update device custom name
+/// Compare two scans: devices that appeared or disappeared, changed IPs and ports
 #[tauri::command]
-pub fn update_device_name(state: tauri::State<'_, AppState>, mac: String, name: String) -> Result<(), String> {
+pub fn diff_scans(
+    state: tauri::State<'_, AppState>,
+    scan_a: i64,
+    scan_b: i64,
+) -> Result<ScanDiff, String> {
     let conn = get_db_connection(&state)?;
-    let conn = lock_db_connection(&conn)?;
-    
-    queries::update_device_name(&conn, &mac, &name)
-        .map_err(|e| format!("Failed to update device name: {}", e))
+    let conn = read_db_connection(&conn)?;
+
+    queries::diff_scans(&conn, scan_a, scan_b)
+        .map_err(|e| format!("Failed to compare scans: {}", e))
 }
 
-/// Get network statistics
+/// Get whether back-to-back duplicate scans are hidden from history and stats
 #[tauri::command]
-pub fn get_network_stats(state: tauri::State<'_, AppState>) -> Result<NetworkStats, String> {
+pub fn get_collapse_duplicate_scans(state: tauri::State<'_, AppState>) -> Result<bool, String> {
     let conn = get_db_connection(&state)?;
-    let conn = lock_db_connection(&conn)?;
+    let conn = read_db_connection(&conn)?;
     
-    queries::get_network_stats(&conn)
-        .map_err(|e| format!("Failed to get network stats: {}", e))
+    queries::collapse_duplicate_scans(&conn)
+        .map_err(|e| format!("Failed to get duplicate scan setting: {}", e))
 }
 
-/// Get unread alerts
+/// Hide or show back-to-back duplicate scans in history and stats
 #[tauri::command]
-pub fn get_unread_alerts(state: tauri::State<'_, AppState>) -> Result<Vec<AlertRecord>, String> {
+pub fn set_collapse_duplicate_scans(state: tauri::State<'_, AppState>, enabled: bool) -> Result<(), String> {
     let conn = get_db_connection(&state)?;
     let conn = lock_db_connection(&conn)?;
     
-    queries::get_unread_alerts(&conn)
-        .map_err(|e| format!("Failed to get alerts: {}", e))
+    queries::set_collapse_duplicate_scans(&conn, enabled)
+        .map_err(|e| format!("Failed to save duplicate scan setting: {}", e))
 }
 
-/// Mark alert as read
+/// Get how much scan history and read alerts are kept
 #[tauri::command]
-pub fn mark_alert_read(state: tauri::State<'_, AppState>, alert_id: i64) -> Result<(), String> {
+pub fn get_retention_policy(state: tauri::State<'_, AppState>) -> Result<RetentionPolicy, String> {
     let conn = get_db_connection(&state)?;
-    let conn = lock_db_connection(&conn)?;
-    
-    queries::mark_alert_read(&conn, alert_id)
-        .map_err(|e| format!("Failed to mark alert read: {}", e))
+    let conn = read_db_connection(&conn)?;
+
+    retention::load_retention_policy(&conn)
+        .map_err(|e| format!("Failed to get retention policy: {}", e))
 }
 
-/// Mark all alerts as read
+/// Save the retention policy (applied on the next prune and at startup)
 #[tauri::command]
-pub fn mark_all_alerts_read(state: tauri::State<'_, AppState>) -> Result<(), String> {
+pub fn save_retention_policy(
+    state: tauri::State<'_, AppState>,
+    policy: RetentionPolicy,
+) -> Result<(), String> {
     let conn = get_db_connection(&state)?;
     let conn = lock_db_connection(&conn)?;
-    
-    queries::mark_all_alerts_read(&conn)
-        .map_err(|e| format!("Failed to mark alerts read: {}", e))
+
+    retention::save_retention_policy(&conn, &policy)
+        .map_err(|e| format!("Failed to save retention policy: {}", e))
 }
 
-/// Clear all alerts
+/// Remove scans, history and read alerts outside the retention policy now
 #[tauri::command]
-pub fn clear_all_alerts(state: tauri::State<'_, AppState>) -> Result<(), String> {
+pub fn prune_database(state: tauri::State<'_, AppState>) -> Result<PruneSummary, String> {
     let conn = get_db_connection(&state)?;
     let conn = lock_db_connection(&conn)?;
 
-    queries::clear_all_alerts(&conn)
-        .map_err(|e| format!("Failed to clear alerts: {}", e))
+    let summary = retention::load_retention_policy(&conn)
+        .and_then(|policy| retention::prune(&conn, &policy))
+        .map_err(|e| format!("Failed to prune database: {:#}", e))?;
+    state.cache.invalidate();
+    Ok(summary)
 }
 
-/// Get database path (for debugging)
+/// Copy the database to `path` while the app keeps running
 #[tauri::command]
-pub fn get_database_path(state: tauri::State<'_, AppState>) -> Result<String, String> {
-    let db = state
+pub fn backup_database(state: tauri::State<'_, AppState>, path: String) -> Result<(), String> {
+    state
         .db
-        .lock()
-        .map_err(|_| "Database state lock poisoned".to_string())?;
-    Ok(db.path().to_string_lossy().to_string())
+        .backup_to(std::path::Path::new(&path))
+        .map_err(|e| format!("Failed to back up database: {:#}", e))
 }
 
-// =====================================================
-// Monitoring Commands
-// =====================================================
+/// Replace the database with a backup (e.g. when moving to a new machine)
+#[tauri::command]
+pub fn restore_database(state: tauri::State<'_, AppState>, path: String) -> Result<(), String> {
+    state
+        .db
+        .restore_from(std::path::Path::new(&path))
+        .map_err(|e| format!("Failed to restore database: {:#}", e))?;
+    state.cache.invalidate();
+    Ok(())
+}
 
-/// Start background network monitoring
+/// Write devices, history, scans and alerts to a JSON file
 #[tauri::command]
-pub async fn start_monitoring(
-    monitor_state: tauri::State<'_, MonitorState>,
-    app: tauri::AppHandle,
-    interval_seconds: Option<u64>,
+pub fn export_database_to_json(
+    state: tauri::State<'_, AppState>,
+    path: String,
 ) -> Result<(), String> {
-    let monitor = monitor_state.monitor.lock().await;
-    
-    // Create callback that emits events to Tauri frontend
-    let app_handle = app.clone();
-    let callback = move |event: NetworkEvent| {
-        let _ = app_handle.emit("network-event", &event);
-    };
-    
-    monitor.start(callback, interval_seconds).await
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+
+    let json = dump::export_database_json(&conn)
+        .map_err(|e| format!("Failed to export database: {:#}", e))?;
+    std::fs::write(&path, json).map_err(|e| format!("Failed to write {}: {}", path, e))
 }
 
-/// Stop background network monitoring
+/// Load a JSON database export into this (empty) database
 #[tauri::command]
-pub async fn stop_monitoring(
-    monitor_state: tauri::State<'_, MonitorState>,
-) -> Result<(), String> {
-    let monitor = monitor_state.monitor.lock().await;
-    monitor.stop();
-    Ok(())
+pub fn import_database_from_json(
+    state: tauri::State<'_, AppState>,
+    path: String,
+) -> Result<dump::ImportSummary, String> {
+    let json = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let conn = get_db_connection(&state)?;
+    let conn = lock_db_connection(&conn)?;
+
+    let summary = dump::import_database_json(&conn, &json)
+        .map_err(|e| format!("Failed to import database: {:#}", e))?;
+    state.cache.invalidate();
+    Ok(summary)
 }
 
-/// Get current monitoring status
+/// Check database integrity, refresh statistics and compact the file
 #[tauri::command]
-pub async fn get_monitoring_status(
-    monitor_state: tauri::State<'_, MonitorState>,
-) -> Result<MonitoringStatus, String> {
-    let monitor = monitor_state.monitor.lock().await;
-    Ok(monitor.status().await)
+pub fn run_db_maintenance(state: tauri::State<'_, AppState>) -> Result<MaintenanceReport, String> {
+    state
+        .db
+        .maintenance()
+        .map_err(|e| format!("Database maintenance failed: {:#}", e))
 }
 
-// =====================================================
-// AI Insights Commands
-// =====================================================
+/// Per-day summaries of scans removed by pruning
+#[tauri::command]
+pub fn get_scan_daily_summaries(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<ScanDailySummary>, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
 
-// Note: DeviceDistribution, SecurityReport are available for future AI insights
+    retention::get_scan_daily_summaries(&conn)
+        .map_err(|e| format!("Failed to get scan summaries: {}", e))
+}
 
-/// Get network health score from current scan
+/// Get all known devices
 #[tauri::command]
-pub fn get_network_health(
+pub fn get_all_devices(state: tauri::State<'_, AppState>) -> Result<Vec<DeviceRecord>, String> {
+    state.cache.devices(&state.db, state.active_network())
+        .map_err(|e| format!("Failed to get devices: {}", e))
+}
+
+/// Filtered, sorted page of devices
+#[tauri::command]
+pub fn query_devices(
     state: tauri::State<'_, AppState>,
-) -> Result<serde_json::Value, String> {
+    query: DeviceQuery,
+) -> Result<Page<DeviceRecord>, String> {
     let conn = get_db_connection(&state)?;
-    let conn = lock_db_connection(&conn)?;
+    let conn = read_db_connection(&conn)?;
 
-    let hosts = queries::get_latest_scan_hosts(&conn)
-        .map_err(|e| format!("Failed to get latest scan host data: {}", e))?;
-    let health = host_discovery::NetworkHealth::calculate(&hosts);
-    
-    Ok(serde_json::json!({
-        "score": health.score,
-        "grade": health.grade.to_string(),
-        "status": health.status,
-        "breakdown": {
-            "security": health.breakdown.security,
-            "stability": health.breakdown.stability,
-            "compliance": health.breakdown.compliance
-        },
-        "insights": health.insights
-    }))
+    let query = DeviceQuery {
+        network_id: query.network_id.or(state.active_network()),
+        ..query
+    };
+    queries::query_devices(&conn, &query).map_err(|e| format!("Failed to query devices: {}", e))
 }
 
-/// Get device distribution stats
+/// Search devices by MAC, IP, hostname, vendor, name or notes
 #[tauri::command]
-pub fn get_device_distribution(
+pub fn search_devices(
     state: tauri::State<'_, AppState>,
-) -> Result<serde_json::Value, String> {
+    query: String,
+) -> Result<Vec<DeviceRecord>, String> {
     let conn = get_db_connection(&state)?;
-    let conn = lock_db_connection(&conn)?;
-    
-    let devices = queries::get_all_devices(&conn)
-        .map_err(|e| format!("Failed to get devices: {}", e))?;
-    
+    let conn = read_db_connection(&conn)?;
+
+    let mut devices = queries::search_devices(&conn, &query)
+        .map_err(|e| format!("Failed to search devices: {}", e))?;
+    if let Some(network_id) = state.active_network() {
+        devices.retain(|device| device.network_id == Some(network_id));
+    }
+    Ok(devices)
+}
+
+/// All network profiles, most recently scanned first
+#[tauri::command]
+pub fn get_networks(state: tauri::State<'_, AppState>) -> Result<Vec<NetworkProfile>, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+
+    networks::get_networks(&conn).map_err(|e| format!("Failed to get networks: {}", e))
+}
+
+/// Network profile devices, scans and alerts are shown for (`None` for all)
+#[tauri::command]
+pub fn get_active_network(state: tauri::State<'_, AppState>) -> Option<i64> {
+    state.active_network()
+}
+
+/// Show one network profile, or every network with `None`
+#[tauri::command]
+pub fn select_network(
+    state: tauri::State<'_, AppState>,
+    network_id: Option<i64>,
+) -> Result<(), String> {
+    if let Some(id) = network_id {
+        let conn = get_db_connection(&state)?;
+        let conn = read_db_connection(&conn)?;
+        networks::get_network(&conn, id)
+            .map_err(|e| format!("Failed to get network: {}", e))?
+            .ok_or_else(|| format!("Network {} not found", id))?;
+    }
+    state.set_active_network(network_id);
+    Ok(())
+}
+
+/// Rename a network profile, e.g. to "Home" or "Site A"
+#[tauri::command]
+pub fn rename_network(
+    state: tauri::State<'_, AppState>,
+    network_id: i64,
+    name: String,
+) -> Result<bool, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = lock_db_connection(&conn)?;
+
+    networks::rename_network(&conn, network_id, &name)
+        .map_err(|e| format!("Failed to rename network: {}", e))
+}
+
+/// Refresh the configured vendor source and use it for vendor lookups
+///
+/// Downloads the IEEE registry or re-reads the user's CSV file. If that
+/// fails the current vendor table stays in use.
+#[tauri::command]
+pub async fn update_oui_database(state: tauri::State<'_, AppState>) -> Result<OuiUpdateSummary, String> {
+    let settings = {
+        let conn = get_db_connection(&state)?;
+        let conn = lock_db_connection(&conn)?;
+        host_discovery::network::load_vendor_source_settings(&conn)
+            .map_err(|e| format!("Failed to load vendor source settings: {}", e))?
+    };
+
+    let source = tokio::task::spawn_blocking(move || host_discovery::network::fetch_vendor_source(&settings))
+        .await
+        .map_err(|e| format!("OUI update task failed: {}", e))?
+        .map_err(|e| format!("Failed to fetch vendor data: {}", e))?;
+
+    let conn = get_db_connection(&state)?;
+    let conn = lock_db_connection(&conn)?;
+    host_discovery::network::install_vendor_source(&conn, source)
+        .map_err(|e| format!("Failed to update OUI registry: {}", e))
+}
+
+/// Get the vendor data source (bundled table, IEEE download or CSV file)
+#[tauri::command]
+pub fn get_vendor_source_settings(state: tauri::State<'_, AppState>) -> Result<VendorSourceSettings, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+
+    host_discovery::network::load_vendor_source_settings(&conn)
+        .map_err(|e| format!("Failed to load vendor source settings: {}", e))
+}
+
+/// Select the vendor data source and reload it
+#[tauri::command]
+pub fn save_vendor_source_settings(
+    state: tauri::State<'_, AppState>,
+    settings: VendorSourceSettings,
+) -> Result<(), String> {
+    let conn = get_db_connection(&state)?;
+    let conn = lock_db_connection(&conn)?;
+
+    host_discovery::network::save_vendor_source_settings(&conn, &settings)
+        .map_err(|e| format!("Failed to save vendor source settings: {}", e))
+}
+
+/// Get user-defined vendor overrides
+#[tauri::command]
+pub fn get_vendor_overrides(state: tauri::State<'_, AppState>) -> Result<Vec<VendorOverrideRecord>, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+
+    queries::get_vendor_overrides(&conn)
+        .map_err(|e| format!("Failed to get vendor overrides: {}", e))
+}
+
+/// Set the vendor for a MAC prefix (6-11 hex digits) or a single MAC
+#[tauri::command]
+pub fn set_vendor_override(
+    state: tauri::State<'_, AppState>,
+    pattern: String,
+    vendor: String,
+) -> Result<Vec<VendorOverrideRecord>, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = lock_db_connection(&conn)?;
+
+    host_discovery::network::add_vendor_override(&conn, &pattern, &vendor)
+        .map_err(|e| format!("Failed to save vendor override: {}", e))?;
+    load_vendor_overrides(&conn)
+        .map_err(|e| format!("Failed to reload vendor overrides: {}", e))
+}
+
+/// Remove a vendor override
+#[tauri::command]
+pub fn delete_vendor_override(
+    state: tauri::State<'_, AppState>,
+    pattern: String,
+) -> Result<bool, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = lock_db_connection(&conn)?;
+
+    host_discovery::network::remove_vendor_override(&conn, &pattern)
+        .map_err(|e| format!("Failed to delete vendor override: {}", e))
+}
+
+/// Get user-defined device type rules
+#[tauri::command]
+pub fn get_device_type_rules(state: tauri::State<'_, AppState>) -> Result<Vec<DeviceTypeRuleRecord>, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+
+    queries::get_device_type_rules(&conn)
+        .map_err(|e| format!("Failed to get device type rules: {}", e))
+}
+
+/// Create a device type rule, or replace it when `id` is given
+#[tauri::command]
+pub fn save_device_type_rule(
+    state: tauri::State<'_, AppState>,
+    id: Option<i64>,
+    rule: DeviceTypeRule,
+) -> Result<i64, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = lock_db_connection(&conn)?;
+
+    match id {
+        Some(id) => host_discovery::network::update_device_type_rule(&conn, id, &rule)
+            .map_err(|e| format!("Failed to save device type rule: {}", e))?
+            .then_some(id)
+            .ok_or_else(|| format!("Device type rule {} not found", id)),
+        None => host_discovery::network::add_device_type_rule(&conn, &rule)
+            .map_err(|e| format!("Failed to save device type rule: {}", e)),
+    }
+}
+
+/// Delete a device type rule
+#[tauri::command]
+pub fn delete_device_type_rule(state: tauri::State<'_, AppState>, id: i64) -> Result<bool, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = lock_db_connection(&conn)?;
+
+    host_discovery::network::remove_device_type_rule(&conn, id)
+        .map_err(|e| format!("Failed to delete device type rule: {}", e))
+}
+
+/// Import device type rules from a JSON array, returning how many were added
+#[tauri::command]
+pub fn import_device_type_rules(state: tauri::State<'_, AppState>, json: String) -> Result<usize, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = lock_db_connection(&conn)?;
+
+    host_discovery::network::import_device_type_rules(&conn, &json)
+        .map_err(|e| format!("Failed to import device type rules: {}", e))
+}
+
+/// Get the risk scoring profile in use
+#[tauri::command]
+pub fn get_risk_profile() -> Result<RiskProfile, String> {
+    Ok(host_discovery::network::active_risk_profile())
+}
+
+/// Save and apply a risk scoring profile (affects scans from now on)
+#[tauri::command]
+pub fn save_risk_profile(state: tauri::State<'_, AppState>, profile: RiskProfile) -> Result<(), String> {
+    let conn = get_db_connection(&state)?;
+    let conn = lock_db_connection(&conn)?;
+
+    host_discovery::network::save_risk_profile(&conn, &profile)
+        .map_err(|e| format!("Failed to save risk profile: {}", e))
+}
+
+/// Replace the risk scoring profile with a built-in preset
+#[tauri::command]
+pub fn apply_risk_profile_preset(state: tauri::State<'_, AppState>, preset: String) -> Result<RiskProfile, String> {
+    let profile = RiskProfile::preset(&preset).ok_or_else(|| {
+        format!(
+            "Unknown risk profile preset '{}' (expected one of: {})",
+            preset,
+            host_discovery::network::RISK_PROFILE_PRESETS.join(", ")
+        )
+    })?;
+
+    let conn = get_db_connection(&state)?;
+    let conn = lock_db_connection(&conn)?;
+    host_discovery::network::save_risk_profile(&conn, &profile)
+        .map_err(|e| format!("Failed to save risk profile: {}", e))?;
+    Ok(profile)
+}
+
+/// Get device by MAC address (including advertised mDNS services)
+#[tauri::command]
+pub fn get_device_by_mac(state: tauri::State<'_, AppState>, mac: String) -> Result<Option<DeviceDetails>, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+    
+    queries::get_device_details(&conn, &mac)
+        .map_err(|e| format!("Failed to get device: {}", e))
+}
+
+/// Every port ever seen open on a device, with first/last sighting
+#[tauri::command]
+pub fn get_device_ports(
+    state: tauri::State<'_, AppState>,
+    mac: String,
+) -> Result<Vec<DevicePortRecord>, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+
+    queries::get_device_ports(&conn, &mac)
+        .map_err(|e| format!("Failed to get device ports: {}", e))
+}
+
+/// Devices a port has been seen open on (TCP unless `protocol` is given)
+#[tauri::command]
+pub fn get_devices_with_port(
+    state: tauri::State<'_, AppState>,
+    port: u16,
+    protocol: Option<String>,
+) -> Result<Vec<DevicePortRecord>, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+
+    queries::get_devices_with_port(&conn, port, protocol.as_deref().unwrap_or("tcp"))
+        .map_err(|e| format!("Failed to get devices with port {}: {}", port, e))
+}
+
+/// A device's port openings and closings, oldest first
+#[tauri::command]
+pub fn get_device_port_changes(
+    state: tauri::State<'_, AppState>,
+    mac: String,
+) -> Result<Vec<PortChangeRecord>, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+
+    queries::get_device_port_changes(&conn, &mac)
+        .map_err(|e| format!("Failed to get port changes: {}", e))
+}
+
+/// Ports first seen open on any device in the last `days` days
+#[tauri::command]
+pub fn get_new_ports(
+    state: tauri::State<'_, AppState>,
+    days: u32,
+) -> Result<Vec<DevicePortRecord>, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+
+    queries::get_new_ports(&conn, days)
+        .map_err(|e| format!("Failed to get new ports: {}", e))
+}
+
+/// How often a device was found over the last 24 hours, 7 days and 30 days
+#[tauri::command]
+pub fn get_device_availability(
+    state: tauri::State<'_, AppState>,
+    mac: String,
+) -> Result<Vec<AvailabilityWindow>, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+
+    queries::get_device_availability(&conn, &mac)
+        .map_err(|e| format!("Failed to get device availability: {}", e))
+}
+
+/// A device's latency history for charting
+///
+/// Picks raw, hourly or daily points from the range unless `resolution` is given.
+#[tauri::command]
+pub fn get_device_latency(
+    state: tauri::State<'_, AppState>,
+    mac: String,
+    since: Option<chrono::DateTime<chrono::Utc>>,
+    until: Option<chrono::DateTime<chrono::Utc>>,
+    resolution: Option<LatencyResolution>,
+) -> Result<Vec<LatencyPoint>, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+
+    let resolution = resolution.unwrap_or_else(|| LatencyResolution::for_range(since, until));
+    latency::get_latency_series(&conn, &mac, since, until, resolution)
+        .map_err(|e| format!("Failed to get latency history: {}", e))
+}
+
+/// Latency percentiles of a device over a range
+#[tauri::command]
+pub fn get_device_latency_percentiles(
+    state: tauri::State<'_, AppState>,
+    mac: String,
+    since: Option<chrono::DateTime<chrono::Utc>>,
+    until: Option<chrono::DateTime<chrono::Utc>>,
+) -> Result<Option<LatencyPercentiles>, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+
+    latency::get_latency_percentiles(&conn, &mac, since, until)
+        .map_err(|e| format!("Failed to get latency percentiles: {}", e))
+}
+
+/// Vulnerabilities and port warnings with when they appeared and were remediated
+#[tauri::command]
+pub fn get_finding_history(
+    state: tauri::State<'_, AppState>,
+    mac: Option<String>,
+) -> Result<Vec<FindingHistoryRecord>, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+
+    queries::get_finding_history(&conn, mac.as_deref())
+        .map_err(|e| format!("Failed to get finding history: {}", e))
+}
+
+/// LLDP links between devices, including ones not seen in the latest scan
+#[tauri::command]
+pub fn get_topology_edges(
+    state: tauri::State<'_, AppState>,
+    since: Option<chrono::DateTime<chrono::Utc>>,
+) -> Result<Vec<TopologyEdgeRecord>, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+
+    queries::get_topology_edges(&conn, since)
+        .map_err(|e| format!("Failed to get topology edges: {}", e))
+}
+
+/// Tag a device (e.g. "kids", "work", "IoT", "critical")
+#[tauri::command]
+pub async fn add_device_tag(
+    state: tauri::State<'_, AppState>,
+    monitor_state: tauri::State<'_, MonitorState>,
+    mac: String,
+    tag: String,
+) -> Result<(), String> {
+    let added = {
+        let conn = get_db_connection(&state)?;
+        let conn = lock_db_connection(&conn)?;
+        queries::add_tag(&conn, &mac, &tag)
+    };
+
+    match added {
+        Ok(true) => {
+            state.cache.invalidate();
+            let tags = load_device_tags(&state);
+            monitor_state.monitor.lock().await.set_device_tags(tags);
+            Ok(())
+        }
+        Ok(false) => Err(format!("Device {} not found", mac)),
+        Err(e) => Err(format!("Failed to tag device: {}", e)),
+    }
+}
+
+/// Remove a tag from a device
+#[tauri::command]
+pub async fn remove_device_tag(
+    state: tauri::State<'_, AppState>,
+    monitor_state: tauri::State<'_, MonitorState>,
+    mac: String,
+    tag: String,
+) -> Result<bool, String> {
+    let removed = {
+        let conn = get_db_connection(&state)?;
+        let conn = lock_db_connection(&conn)?;
+        queries::remove_tag(&conn, &mac, &tag)
+            .map_err(|e| format!("Failed to remove device tag: {}", e))?
+    };
+    state.cache.invalidate();
+    let tags = load_device_tags(&state);
+    monitor_state.monitor.lock().await.set_device_tags(tags);
+    Ok(removed)
+}
+
+/// Devices carrying a tag
+#[tauri::command]
+pub fn get_devices_by_tag(
+    state: tauri::State<'_, AppState>,
+    tag: String,
+) -> Result<Vec<DeviceRecord>, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+
+    queries::get_devices_by_tag(&conn, &tag)
+        .map_err(|e| format!("Failed to get devices tagged {}: {}", tag, e))
+}
+
+/// Every tag in use with its device count
+#[tauri::command]
+pub fn get_device_tags(state: tauri::State<'_, AppState>) -> Result<Vec<TagCount>, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+
+    queries::get_all_tags(&conn)
+        .map_err(|e| format!("Failed to get tags: {}", e))
+}
+
+/// Set a custom field on a device (asset tag, owner, location, ...);
+/// an empty value removes it
+#[tauri::command]
+pub fn set_device_attribute(
+    state: tauri::State<'_, AppState>,
+    mac: String,
+    name: String,
+    value: String,
+) -> Result<(), String> {
+    let conn = get_db_connection(&state)?;
+    let conn = lock_db_connection(&conn)?;
+
+    match queries::set_device_attribute(&conn, &mac, &name, &value) {
+        Ok(true) => {
+            state.cache.invalidate();
+            Ok(())
+        }
+        Ok(false) => Err(format!("Device {} not found", mac)),
+        Err(e) => Err(format!("Failed to set device field: {}", e)),
+    }
+}
+
+/// Remove a custom field from a device
+#[tauri::command]
+pub fn remove_device_attribute(
+    state: tauri::State<'_, AppState>,
+    mac: String,
+    name: String,
+) -> Result<bool, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = lock_db_connection(&conn)?;
+
+    let removed = queries::remove_device_attribute(&conn, &mac, &name)
+        .map_err(|e| format!("Failed to remove device field: {}", e))?;
+    state.cache.invalidate();
+    Ok(removed)
+}
+
+/// Every custom field name in use with its device count
+#[tauri::command]
+pub fn get_attribute_names(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<AttributeCount>, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+
+    queries::get_attribute_names(&conn)
+        .map_err(|e| format!("Failed to get device fields: {}", e))
+}
+
+/// Per-day summaries of a device's history removed by pruning
+#[tauri::command]
+pub fn get_device_daily_summaries(
+    state: tauri::State<'_, AppState>,
+    mac: String,
+) -> Result<Vec<DeviceDailySummary>, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+
+    retention::get_device_daily_summaries(&conn, &mac)
+        .map_err(|e| format!("Failed to get device summaries: {}", e))
+}
+
+/// MACs merged into a device (randomized MAC correlation and manual merges)
+#[tauri::command]
+pub fn get_device_merges(
+    state: tauri::State<'_, AppState>,
+    mac: String,
+) -> Result<Vec<DeviceMerge>, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+
+    queries::get_device_merges(&conn, &mac)
+        .map_err(|e| format!("Failed to get device merges: {}", e))
+}
+
+/// Merge a duplicate device record into a canonical one
+#[tauri::command]
+pub fn merge_devices(
+    state: tauri::State<'_, AppState>,
+    canonical_mac: String,
+    duplicate_mac: String,
+) -> Result<(), String> {
+    let conn = get_db_connection(&state)?;
+    let conn = lock_db_connection(&conn)?;
+
+    queries::merge_devices(&conn, &canonical_mac, &duplicate_mac)
+        .map_err(|e| format!("Failed to merge devices: {}", e))?;
+    state.cache.invalidate();
+    Ok(())
+}
+
+/// Update device custom name
+#[tauri::command]
+pub fn update_device_name(state: tauri::State<'_, AppState>, mac: String, name: String) -> Result<(), String> {
+    let conn = get_db_connection(&state)?;
+    let conn = lock_db_connection(&conn)?;
+    
+    queries::update_device_name(&conn, &mac, &name)
+        .map_err(|e| format!("Failed to update device name: {}", e))?;
+    state.cache.invalidate();
+    Ok(())
+}
+
+/// Get per-device alert threshold overrides (None if the device uses defaults)
+#[tauri::command]
+pub fn get_device_settings(
+    state: tauri::State<'_, AppState>,
+    mac: String,
+) -> Result<Option<DeviceSettings>, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+
+    queries::get_device_settings(&conn, &mac)
+        .map_err(|e| format!("Failed to get device settings: {}", e))
+}
+
+/// Apply a change to per-device settings, then hand the monitor the reloaded set
+async fn change_device_settings<T>(
+    state: &tauri::State<'_, AppState>,
+    monitor_state: &tauri::State<'_, MonitorState>,
+    change: impl FnOnce(&rusqlite::Connection) -> Result<T, String>,
+) -> Result<T, String> {
+    let (changed, all_settings) = {
+        let conn = get_db_connection(state)?;
+        let conn = lock_db_connection(&conn)?;
+
+        let changed = change(&conn)?;
+        let all_settings = queries::get_all_device_settings(&conn)
+            .map_err(|e| format!("Failed to reload device settings: {}", e))?;
+        (changed, all_settings)
+    };
+
+    let monitor = monitor_state.monitor.lock().await;
+    monitor.set_device_settings(all_settings).await;
+    Ok(changed)
+}
+
+/// Save per-device alert threshold overrides and apply them to the monitor
+#[tauri::command]
+pub async fn update_device_settings(
+    state: tauri::State<'_, AppState>,
+    monitor_state: tauri::State<'_, MonitorState>,
+    settings: DeviceSettings,
+) -> Result<(), String> {
+    change_device_settings(&state, &monitor_state, |conn| {
+        queries::upsert_device_settings(conn, &settings)
+            .map_err(|e| format!("Failed to save device settings: {}", e))
+    })
+    .await
+}
+
+/// Set (or clear with `None`) the expected open ports for a device
+#[tauri::command]
+pub async fn set_expected_ports(
+    state: tauri::State<'_, AppState>,
+    monitor_state: tauri::State<'_, MonitorState>,
+    mac: String,
+    ports: Option<Vec<u16>>,
+) -> Result<(), String> {
+    change_device_settings(&state, &monitor_state, |conn| {
+        queries::set_expected_ports(conn, &mac, ports.as_deref())
+            .map_err(|e| format!("Failed to save expected ports: {}", e))
+    })
+    .await
+}
+
+/// Add a device to or remove it from the monitor's watch list
+#[tauri::command]
+pub async fn set_device_watched(
+    state: tauri::State<'_, AppState>,
+    monitor_state: tauri::State<'_, MonitorState>,
+    mac: String,
+    watched: bool,
+) -> Result<(), String> {
+    change_device_settings(&state, &monitor_state, |conn| {
+        queries::set_device_watched(conn, &mac, watched)
+            .map_err(|e| format!("Failed to update watch list: {}", e))
+    })
+    .await
+}
+
+/// Add a device to or remove it from the known-device allowlist
+#[tauri::command]
+pub async fn set_device_trusted(
+    state: tauri::State<'_, AppState>,
+    monitor_state: tauri::State<'_, MonitorState>,
+    mac: String,
+    trusted: bool,
+) -> Result<(), String> {
+    change_device_settings(&state, &monitor_state, |conn| {
+        queries::set_device_trusted(conn, &mac, trusted)
+            .map_err(|e| format!("Failed to update allowlist: {}", e))
+    })
+    .await
+}
+
+/// Snooze a device's alerts until `until`, or lift the mute with `None`
+#[tauri::command]
+pub async fn mute_alerts_for_device(
+    state: tauri::State<'_, AppState>,
+    monitor_state: tauri::State<'_, MonitorState>,
+    mac: String,
+    until: Option<chrono::DateTime<chrono::Utc>>,
+) -> Result<(), String> {
+    change_device_settings(&state, &monitor_state, |conn| {
+        queries::mute_alerts_for_device(conn, &mac, until)
+            .map_err(|e| format!("Failed to mute device alerts: {}", e))
+    })
+    .await
+}
+
+/// Trust every known device, returning how many were added to the allowlist
+#[tauri::command]
+pub async fn trust_all_devices(
+    state: tauri::State<'_, AppState>,
+    monitor_state: tauri::State<'_, MonitorState>,
+) -> Result<usize, String> {
+    change_device_settings(&state, &monitor_state, |conn| {
+        queries::trust_all_devices(conn).map_err(|e| format!("Failed to trust devices: {}", e))
+    })
+    .await
+}
+
+/// Get how devices missing from the allowlist are alerted on
+#[tauri::command]
+pub fn get_untrusted_device_alerts(
+    state: tauri::State<'_, AppState>,
+) -> Result<alerts::UntrustedDeviceAlerts, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+
+    alerts::load_untrusted_device_alerts(&conn)
+        .map_err(|e| format!("Failed to load untrusted device alerts: {}", e))
+}
+
+/// Save how devices missing from the allowlist are alerted on
+#[tauri::command]
+pub fn save_untrusted_device_alerts(
+    state: tauri::State<'_, AppState>,
+    mode: alerts::UntrustedDeviceAlerts,
+) -> Result<(), String> {
+    let conn = get_db_connection(&state)?;
+    let conn = lock_db_connection(&conn)?;
+
+    alerts::save_untrusted_device_alerts(&conn, &mode)
+        .map_err(|e| format!("Failed to save untrusted device alerts: {}", e))
+}
+
+/// TLS certificates served by devices, soonest to expire first
+#[tauri::command]
+pub fn get_certificates(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<alerts::StoredCertificate>, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+
+    alerts::load_certificates(&conn).map_err(|e| format!("Failed to load certificates: {}", e))
+}
+
+/// SSH host keys presented by devices
+#[tauri::command]
+pub fn get_ssh_host_keys(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<alerts::StoredSshHostKey>, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+
+    alerts::load_ssh_host_keys(&conn).map_err(|e| format!("Failed to load SSH host keys: {}", e))
+}
+
+/// Record the device's currently open ports as its expected baseline
+#[tauri::command]
+pub async fn record_expected_ports(
+    state: tauri::State<'_, AppState>,
+    monitor_state: tauri::State<'_, MonitorState>,
+    mac: String,
+) -> Result<Vec<u16>, String> {
+    change_device_settings(&state, &monitor_state, |conn| {
+        queries::record_expected_ports_baseline(conn, &mac)
+            .map_err(|e| format!("Failed to record expected ports: {}", e))?
+            .ok_or_else(|| format!("Device {} not found", mac))
+    })
+    .await
+}
+
+/// Revert a device's alert thresholds to the defaults; trust, watch, mute
+/// and expected ports are kept
+#[tauri::command]
+pub async fn reset_device_settings(
+    state: tauri::State<'_, AppState>,
+    monitor_state: tauri::State<'_, MonitorState>,
+    mac: String,
+) -> Result<(), String> {
+    change_device_settings(&state, &monitor_state, |conn| {
+        queries::reset_device_thresholds(conn, &mac)
+            .map_err(|e| format!("Failed to reset device settings: {}", e))?;
+        Ok(())
+    })
+    .await
+}
+
+/// Show what purging a device would delete, with the token required to confirm
+#[tauri::command]
+pub fn preview_device_purge(
+    state: tauri::State<'_, AppState>,
+    mac: String,
+) -> Result<DevicePurgePreview, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+
+    queries::preview_device_purge(&conn, &mac)
+        .map_err(|e| format!("Failed to preview device purge: {}", e))?
+        .ok_or_else(|| format!("Device {} not found", mac))
+}
+
+/// Irreversibly delete a device and all its history, alerts and captured data
+#[tauri::command]
+pub async fn purge_device_data(
+    state: tauri::State<'_, AppState>,
+    monitor_state: tauri::State<'_, MonitorState>,
+    mac: String,
+    confirmation_token: String,
+) -> Result<DevicePurgeSummary, String> {
+    let summary = {
+        let conn = get_db_connection(&state)?;
+        let conn = lock_db_connection(&conn)?;
+
+        queries::purge_device_data(&conn, &mac, &confirmation_token)
+            .map_err(|e| format!("Failed to purge device data: {:#}", e))?
+    };
+
+    let monitor = monitor_state.monitor.lock().await;
+    monitor.forget_device(&mac).await;
+    Ok(summary)
+}
+
+// =====================================================
+// Smart Group Commands
+// =====================================================
+
+/// Get all saved smart groups
+#[tauri::command]
+pub fn get_smart_groups(state: tauri::State<'_, AppState>) -> Result<Vec<SmartGroupRecord>, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+
+    queries::get_smart_groups(&conn)
+        .map_err(|e| format!("Failed to get smart groups: {}", e))
+}
+
+/// Create a smart group from a filter expression (validated before saving)
+#[tauri::command]
+pub fn create_smart_group(
+    state: tauri::State<'_, AppState>,
+    name: String,
+    expression: String,
+    description: Option<String>,
+) -> Result<i64, String> {
+    let filter = DeviceFilter::parse(&expression)
+        .map_err(|e| format!("Invalid filter expression: {}", e))?;
+
+    let conn = get_db_connection(&state)?;
+    let conn = lock_db_connection(&conn)?;
+
+    queries::create_smart_group(&conn, name.trim(), filter.source(), description.as_deref())
+        .map_err(|e| format!("Failed to create smart group: {}", e))
+}
+
+/// Update a smart group's name, expression or description
+#[tauri::command]
+pub fn update_smart_group(
+    state: tauri::State<'_, AppState>,
+    id: i64,
+    name: String,
+    expression: String,
+    description: Option<String>,
+) -> Result<(), String> {
+    let filter = DeviceFilter::parse(&expression)
+        .map_err(|e| format!("Invalid filter expression: {}", e))?;
+
+    let conn = get_db_connection(&state)?;
+    let conn = lock_db_connection(&conn)?;
+
+    let updated = queries::update_smart_group(
+        &conn,
+        id,
+        name.trim(),
+        filter.source(),
+        description.as_deref(),
+    )
+    .map_err(|e| format!("Failed to update smart group: {}", e))?;
+
+    if updated {
+        Ok(())
+    } else {
+        Err(format!("Smart group {} not found", id))
+    }
+}
+
+/// Delete a smart group
+#[tauri::command]
+pub fn delete_smart_group(state: tauri::State<'_, AppState>, id: i64) -> Result<(), String> {
+    let conn = get_db_connection(&state)?;
+    let conn = lock_db_connection(&conn)?;
+
+    queries::delete_smart_group(&conn, id)
+        .map_err(|e| format!("Failed to delete smart group: {}", e))?;
+    Ok(())
+}
+
+/// Get devices matching a saved smart group
+#[tauri::command]
+pub fn get_devices_in_group(
+    state: tauri::State<'_, AppState>,
+    group_id: i64,
+) -> Result<Vec<DeviceRecord>, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+
+    groups::resolve_smart_group(&conn, group_id)
+        .map_err(|e| format!("Failed to resolve smart group: {}", e))
+}
+
+/// Evaluate an unsaved filter expression (live preview while editing a group)
+#[tauri::command]
+pub fn preview_device_filter(
+    state: tauri::State<'_, AppState>,
+    expression: String,
+) -> Result<Vec<DeviceRecord>, String> {
+    let filter = DeviceFilter::parse(&expression)
+        .map_err(|e| format!("Invalid filter expression: {}", e))?;
+
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+
+    groups::devices_matching(&conn, &filter)
+        .map_err(|e| format!("Failed to evaluate filter: {}", e))
+}
+
+// =====================================================
+// Device Group Commands
+// =====================================================
+
+/// Get all device groups with their policies and members
+#[tauri::command]
+pub fn get_device_groups(state: tauri::State<'_, AppState>) -> Result<Vec<DeviceGroupRecord>, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+
+    queries::get_device_groups(&conn)
+        .map_err(|e| format!("Failed to get device groups: {}", e))
+}
+
+/// Create a device group with a policy (validated before saving)
+#[tauri::command]
+pub fn create_device_group(
+    state: tauri::State<'_, AppState>,
+    name: String,
+    description: Option<String>,
+    policy: GroupPolicy,
+) -> Result<i64, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = lock_db_connection(&conn)?;
+
+    queries::create_device_group(&conn, &name, description.as_deref(), &policy)
+        .map_err(|e| format!("Failed to create device group: {}", e))
+}
+
+/// Update a device group's name, description or policy
+#[tauri::command]
+pub fn update_device_group(
+    state: tauri::State<'_, AppState>,
+    id: i64,
+    name: String,
+    description: Option<String>,
+    policy: GroupPolicy,
+) -> Result<(), String> {
+    let conn = get_db_connection(&state)?;
+    let conn = lock_db_connection(&conn)?;
+
+    let updated = queries::update_device_group(&conn, id, &name, description.as_deref(), &policy)
+        .map_err(|e| format!("Failed to update device group: {}", e))?;
+
+    if updated {
+        Ok(())
+    } else {
+        Err(format!("Device group {} not found", id))
+    }
+}
+
+/// Delete a device group (its members are kept)
+#[tauri::command]
+pub fn delete_device_group(state: tauri::State<'_, AppState>, id: i64) -> Result<(), String> {
+    let conn = get_db_connection(&state)?;
+    let conn = lock_db_connection(&conn)?;
+
+    queries::delete_device_group(&conn, id)
+        .map_err(|e| format!("Failed to delete device group: {}", e))?;
+    Ok(())
+}
+
+/// Add a device to a device group
+#[tauri::command]
+pub fn add_device_to_group(
+    state: tauri::State<'_, AppState>,
+    group_id: i64,
+    mac: String,
+) -> Result<(), String> {
+    let conn = get_db_connection(&state)?;
+    let conn = lock_db_connection(&conn)?;
+
+    let added = queries::add_device_to_group(&conn, group_id, &mac)
+        .map_err(|e| format!("Failed to add device to group: {}", e))?;
+
+    if added {
+        Ok(())
+    } else {
+        Err(format!("Device {} or group {} not found", mac, group_id))
+    }
+}
+
+/// Remove a device from a device group
+#[tauri::command]
+pub fn remove_device_from_group(
+    state: tauri::State<'_, AppState>,
+    group_id: i64,
+    mac: String,
+) -> Result<bool, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = lock_db_connection(&conn)?;
+
+    queries::remove_device_from_group(&conn, group_id, &mac)
+        .map_err(|e| format!("Failed to remove device from group: {}", e))
+}
+
+/// Get network statistics
+#[tauri::command]
+pub fn get_network_stats(state: tauri::State<'_, AppState>) -> Result<NetworkStats, String> {
+    state.cache.network_stats(&state.db, state.active_network())
+        .map_err(|e| format!("Failed to get network stats: {}", e))
+}
+
+/// Get unread alerts, optionally scoped to a smart group or a device tag
+#[tauri::command]
+pub fn get_unread_alerts(
+    state: tauri::State<'_, AppState>,
+    group_id: Option<i64>,
+    tag: Option<String>,
+) -> Result<Vec<AlertRecord>, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+    
+    let mut alerts = queries::get_unread_alerts(&conn)
+        .map_err(|e| format!("Failed to get alerts: {}", e))?;
+    if let Some(network_id) = state.active_network() {
+        alerts.retain(|alert| alert.network_id == Some(network_id));
+    }
+
+    match (group_id, tag) {
+        (Some(group_id), _) => {
+            let members = groups::resolve_smart_group(&conn, group_id)
+                .map_err(|e| format!("Failed to resolve smart group: {}", e))?;
+            Ok(groups::scope_alerts(alerts, &members))
+        }
+        (None, Some(tag)) => {
+            let members = queries::get_devices_by_tag(&conn, &tag)
+                .map_err(|e| format!("Failed to get devices tagged {}: {}", tag, e))?;
+            Ok(groups::scope_alerts(alerts, &members))
+        }
+        (None, None) => Ok(alerts),
+    }
+}
+
+/// Filtered, sorted page of alerts
+#[tauri::command]
+pub fn query_alerts(
+    state: tauri::State<'_, AppState>,
+    query: AlertQuery,
+) -> Result<Page<AlertRecord>, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+
+    let query = AlertQuery {
+        network_id: query.network_id.or(state.active_network()),
+        ..query
+    };
+    queries::query_alerts(&conn, &query).map_err(|e| format!("Failed to query alerts: {}", e))
+}
+
+/// Page of alert history, read and unread, for reconstructing incidents
+///
+/// Filters by `since`/`until`, severity and `device_mac`; read alerts are
+/// included unless `unread_only` is set.
+#[tauri::command]
+pub fn get_alert_history(
+    state: tauri::State<'_, AppState>,
+    query: AlertQuery,
+) -> Result<Page<AlertRecord>, String> {
+    query_alerts(state, query)
+}
+
+/// Mark alert as read
+#[tauri::command]
+pub fn mark_alert_read(state: tauri::State<'_, AppState>, alert_id: i64) -> Result<(), String> {
+    let conn = get_db_connection(&state)?;
+    let conn = lock_db_connection(&conn)?;
+    
+    queries::mark_alert_read(&conn, alert_id)
+        .map_err(|e| format!("Failed to mark alert read: {}", e))
+}
+
+/// Mark all alerts as read
+#[tauri::command]
+pub fn mark_all_alerts_read(state: tauri::State<'_, AppState>) -> Result<(), String> {
+    let conn = get_db_connection(&state)?;
+    let conn = lock_db_connection(&conn)?;
+    
+    queries::mark_all_alerts_read(&conn)
+        .map_err(|e| format!("Failed to mark alerts read: {}", e))
+}
+
+/// Clear all alerts
+#[tauri::command]
+pub fn clear_all_alerts(state: tauri::State<'_, AppState>) -> Result<(), String> {
+    let conn = get_db_connection(&state)?;
+    let conn = lock_db_connection(&conn)?;
+
+    queries::clear_all_alerts(&conn)
+        .map_err(|e| format!("Failed to clear alerts: {}", e))
+}
+
+/// Get all user-defined alert rules
+#[tauri::command]
+pub fn get_alert_rules(state: tauri::State<'_, AppState>) -> Result<Vec<AlertRule>, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+
+    alerts::get_alert_rules(&conn)
+        .map_err(|e| format!("Failed to get alert rules: {}", e))
+}
+
+/// Create an alert rule (the device filter is validated before saving)
+#[tauri::command]
+pub fn create_alert_rule(
+    state: tauri::State<'_, AppState>,
+    rule: AlertRuleSpec,
+) -> Result<AlertRule, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = lock_db_connection(&conn)?;
+
+    alerts::create_alert_rule(&conn, &rule)
+        .and_then(|id| alerts::get_alert_rule(&conn, id))
+        .map_err(|e| format!("Failed to create alert rule: {}", e))?
+        .ok_or_else(|| "Created alert rule not found".to_string())
+}
+
+/// Update an alert rule
+#[tauri::command]
+pub fn update_alert_rule(
+    state: tauri::State<'_, AppState>,
+    id: i64,
+    rule: AlertRuleSpec,
+) -> Result<AlertRule, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = lock_db_connection(&conn)?;
+
+    let updated = alerts::update_alert_rule(&conn, id, &rule)
+        .map_err(|e| format!("Failed to update alert rule: {}", e))?;
+    if !updated {
+        return Err(format!("Alert rule {} not found", id));
+    }
+    alerts::get_alert_rule(&conn, id)
+        .map_err(|e| format!("Failed to get alert rule: {}", e))?
+        .ok_or_else(|| format!("Alert rule {} not found", id))
+}
+
+/// Delete an alert rule (alerts it raised are kept)
+#[tauri::command]
+pub fn delete_alert_rule(state: tauri::State<'_, AppState>, id: i64) -> Result<(), String> {
+    let conn = get_db_connection(&state)?;
+    let conn = lock_db_connection(&conn)?;
+
+    alerts::delete_alert_rule(&conn, id)
+        .map_err(|e| format!("Failed to delete alert rule: {}", e))?;
+    Ok(())
+}
+
+/// Get the channels alerts are sent to
+#[tauri::command]
+pub fn get_notification_settings(
+    state: tauri::State<'_, AppState>,
+) -> Result<NotificationSettings, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+
+    load_notification_settings(&conn)
+        .map_err(|e| format!("Failed to load notification settings: {}", e))
+}
+
+/// Save the channels alerts are sent to (webhook URLs and templates are validated)
+#[tauri::command]
+pub fn save_notification_settings(
+    state: tauri::State<'_, AppState>,
+    settings: NotificationSettings,
+) -> Result<(), String> {
+    let conn = get_db_connection(&state)?;
+    let conn = lock_db_connection(&conn)?;
+
+    host_discovery::save_notification_settings(&conn, &settings)
+        .map_err(|e| format!("Failed to save notification settings: {}", e))
+}
+
+/// Send a sample alert to a webhook, retries included, to check its setup
+#[tauri::command]
+pub async fn test_webhook(webhook: host_discovery::WebhookSettings) -> Result<(), String> {
+    webhook.validate().map_err(|e| format!("Invalid webhook: {}", e))?;
+    let notification = Notification {
+        alert_id: None,
+        alert_type: "TEST".to_string(),
+        severity: alerts::AlertSeverity::Low,
+        message: "Test notification from Network Topology Mapper".to_string(),
+        device_mac: None,
+        device_ip: None,
+        created_at: chrono::Utc::now(),
+        channels: Vec::new(),
+    };
+
+    tokio::task::spawn_blocking(move || WebhookNotifier::new(webhook).send(&notification))
+        .await
+        .map_err(|e| format!("Webhook test task failed: {}", e))?
+        .map_err(|e| format!("Webhook test failed: {:#}", e))
+}
+
+/// Send a test alert by email
+#[tauri::command]
+pub async fn test_email(email: host_discovery::EmailSettings) -> Result<(), String> {
+    email.validate().map_err(|e| format!("Invalid email settings: {}", e))?;
+    let notification = Notification {
+        alert_id: None,
+        alert_type: "TEST".to_string(),
+        severity: alerts::AlertSeverity::Low,
+        message: "Test notification from Network Topology Mapper".to_string(),
+        device_mac: None,
+        device_ip: None,
+        created_at: chrono::Utc::now(),
+        channels: Vec::new(),
+    };
+
+    tokio::task::spawn_blocking(move || EmailNotifier::new(email).send(&notification))
+        .await
+        .map_err(|e| format!("Email test task failed: {}", e))?
+        .map_err(|e| format!("Email test failed: {:#}", e))
+}
+
+/// Post a test alert to a Slack, Discord or Teams webhook
+#[tauri::command]
+pub async fn test_chat_webhook(chat: host_discovery::ChatSettings) -> Result<(), String> {
+    chat.validate().map_err(|e| format!("Invalid chat webhook: {}", e))?;
+    let notification = Notification {
+        alert_id: None,
+        alert_type: "TEST".to_string(),
+        severity: alerts::AlertSeverity::Low,
+        message: "Test notification from Network Topology Mapper".to_string(),
+        device_mac: None,
+        device_ip: None,
+        created_at: chrono::Utc::now(),
+        channels: Vec::new(),
+    };
+
+    tokio::task::spawn_blocking(move || ChatNotifier::new(chat).send(&notification))
+        .await
+        .map_err(|e| format!("Chat test task failed: {}", e))?
+        .map_err(|e| format!("Chat test failed: {:#}", e))
+}
+
+/// Publish a test alert to the MQTT broker
+#[tauri::command]
+pub async fn test_mqtt(mqtt: host_discovery::MqttSettings) -> Result<(), String> {
+    mqtt.validate().map_err(|e| format!("Invalid MQTT settings: {}", e))?;
+    let notification = Notification {
+        alert_id: None,
+        alert_type: "TEST".to_string(),
+        severity: alerts::AlertSeverity::Low,
+        message: "Test notification from Network Topology Mapper".to_string(),
+        device_mac: None,
+        device_ip: None,
+        created_at: chrono::Utc::now(),
+        channels: Vec::new(),
+    };
+
+    tokio::task::spawn_blocking(move || MqttNotifier::new(mqtt).send(&notification))
+        .await
+        .map_err(|e| format!("MQTT test task failed: {}", e))?
+        .map_err(|e| format!("MQTT test failed: {:#}", e))
+}
+
+/// Send a test alert to the syslog server
+#[tauri::command]
+pub async fn test_syslog(syslog: host_discovery::SyslogSettings) -> Result<(), String> {
+    syslog.validate().map_err(|e| format!("Invalid syslog settings: {}", e))?;
+    let notification = Notification {
+        alert_id: None,
+        alert_type: "TEST".to_string(),
+        severity: alerts::AlertSeverity::Low,
+        message: "Test notification from Network Topology Mapper".to_string(),
+        device_mac: None,
+        device_ip: None,
+        created_at: chrono::Utc::now(),
+        channels: Vec::new(),
+    };
+
+    tokio::task::spawn_blocking(move || SyslogNotifier::new(syslog).send(&notification))
+        .await
+        .map_err(|e| format!("Syslog test task failed: {}", e))?
+        .map_err(|e| format!("Syslog test failed: {:#}", e))
+}
+
+/// Get database path (for debugging)
+#[tauri::command]
+pub fn get_database_path(state: tauri::State<'_, AppState>) -> Result<String, String> {
+    Ok(state.db.path().to_string_lossy().to_string())
+}
+
+// =====================================================
+// Monitoring Commands
+// =====================================================
+
+/// Start background network monitoring
+#[tauri::command]
+pub async fn start_monitoring(
+    state: tauri::State<'_, AppState>,
+    monitor_state: tauri::State<'_, MonitorState>,
+    app: tauri::AppHandle,
+    interval_seconds: Option<u64>,
+    interface: Option<String>,
+    passive: Option<bool>,
+    full_scan_every: Option<u32>,
+    targets: Option<Vec<String>>,
+) -> Result<(), String> {
+    let presence = monitor_state.peer_presence.lock().await;
+    let subnet = match presence.as_ref() {
+        Some(_) => monitored_subnet(interface.as_deref()),
+        None => None,
+    };
+    // Leave the subnet to a peer that is already watching it
+    if let (Some(presence), Some(subnet)) = (presence.as_ref(), subnet.as_deref()) {
+        if let Some(peer) = presence.peers_monitoring(subnet).first() {
+            return Err(format!(
+                "{} is already monitored by {} ({})",
+                subnet, peer.instance_name, peer.ip
+            ));
+        }
+    }
+
+    let device_settings = load_device_settings(&state);
+    let dns_resolver = get_db_connection(&state)
+        .and_then(|db_conn| {
+            let conn = lock_db_connection(&db_conn)?;
+            load_dns_resolver(&conn).map_err(|e| e.to_string())
+        })
+        .unwrap_or_else(|e| {
+            eprintln!("[WARN] Failed to load DNS resolver setting: {}", e);
+            Default::default()
+        });
+    let schedule = get_db_connection(&state)
+        .and_then(|db_conn| {
+            let conn = read_db_connection(&db_conn)?;
+            load_monitor_schedule(&conn).map_err(|e| e.to_string())
+        })
+        .unwrap_or_else(|e| {
+            eprintln!("[WARN] Failed to load monitoring schedule: {}", e);
+            Default::default()
+        });
+    let internet_check = get_db_connection(&state)
+        .and_then(|db_conn| {
+            let conn = read_db_connection(&db_conn)?;
+            load_internet_check_settings(&conn).map_err(|e| e.to_string())
+        })
+        .map_err(|e| eprintln!("[WARN] Failed to load internet check settings: {}", e))
+        .ok();
+    let monitor = monitor_state.monitor.lock().await;
+    monitor.set_device_settings(device_settings).await;
+    monitor.set_device_tags(load_device_tags(&state));
+    monitor.set_interface(interface).await;
+    monitor.set_dns_resolver(dns_resolver).await;
+    monitor.set_schedule(schedule).await;
+    monitor.set_internet_check(internet_check).await;
+    monitor.set_passive_discovery(passive.unwrap_or(false));
+    monitor.set_full_scan_every(full_scan_every.unwrap_or(1));
+    monitor.set_targets(targets.unwrap_or_default()).await;
+    monitor.set_event_store(Some(state.db.clone())).await;
+    
+    // Create callback that emits events to Tauri frontend
+    let app_handle = app.clone();
+    let db = state.db.clone();
+    let callback = move |event: NetworkEvent| {
+        let _ = app_handle.emit("network-event", &event);
+        // Low-severity events never notify; skip the settings lookup
+        if event.severity() > alerts::AlertSeverity::Low {
+            match db.read(load_notification_settings) {
+                Ok(settings) => {
+                    if let Some(notice) = settings.desktop.notice(&event) {
+                        show_desktop_notice(&app_handle, &notice);
+                    }
+                }
+                Err(e) => eprintln!("[WARN] Failed to load notification settings: {}", e),
+            }
+        }
+    };
+    
+    monitor.start(callback, interval_seconds).await?;
+
+    // Publish device state to MQTT and events to syslog while monitoring
+    let mut forwarders = monitor_state.forwarders.lock().await;
+    for id in forwarders.drain(..) {
+        monitor.unsubscribe(id);
+    }
+    let settings = state
+        .db
+        .read(load_notification_settings)
+        .unwrap_or_else(|e| {
+            eprintln!("[WARN] Failed to load notification settings: {}", e);
+            NotificationSettings::default()
+        });
+    if let Some(mqtt) = settings
+        .mqtt
+        .filter(|mqtt| mqtt.enabled && mqtt.publish_devices)
+    {
+        match MqttPublisher::connect(mqtt) {
+            Ok(publisher) => {
+                let filter = EventFilter::kinds([
+                    "NewDeviceDiscovered",
+                    "DeviceCameOnline",
+                    "DeviceWentOffline",
+                    "DeviceIpChanged",
+                ]);
+                forwarders
+                    .push(monitor.subscribe(filter, move |event| publisher.publish_event(&event)));
+            }
+            Err(e) => eprintln!("[WARN] Failed to connect to MQTT broker: {}", e),
+        }
+    }
+    if let Some(syslog) = settings
+        .syslog
+        .filter(|syslog| syslog.enabled && syslog.forward_events)
+    {
+        match SyslogForwarder::start(syslog) {
+            Ok(forwarder) => forwarders.push(monitor.subscribe(
+                EventFilter::default(),
+                move |event| forwarder.forward_event(&event),
+            )),
+            Err(e) => eprintln!("[WARN] Failed to start syslog forwarding: {}", e),
+        }
+    }
+
+    if let Some(presence) = presence.as_ref() {
+        if let Err(e) = presence.update(subnet, true) {
+            eprintln!("[WARN] Failed to announce monitoring to peers: {}", e);
+        }
+    }
+    Ok(())
+}
+
+/// Show an OS notification and tell the UI which device it is about, so
+/// clicking it (which brings the window forward) opens that device
+fn show_desktop_notice(app: &tauri::AppHandle, notice: &DesktopNotice) {
+    use tauri_plugin_notification::NotificationExt;
+
+    if let Err(e) = app
+        .notification()
+        .builder()
+        .title(&notice.title)
+        .body(&notice.body)
+        .show()
+    {
+        eprintln!("[WARN] Failed to show desktop notification: {}", e);
+        return;
+    }
+    let _ = app.emit("desktop-notification", notice);
+}
+
+/// Stop background network monitoring
+#[tauri::command]
+pub async fn stop_monitoring(
+    monitor_state: tauri::State<'_, MonitorState>,
+) -> Result<(), String> {
+    let monitor = monitor_state.monitor.lock().await;
+    monitor.stop();
+    for id in monitor_state.forwarders.lock().await.drain(..) {
+        monitor.unsubscribe(id);
+    }
+
+    if let Some(presence) = monitor_state.peer_presence.lock().await.as_ref() {
+        let subnet = presence.announcement().and_then(|a| a.subnet);
+        if let Err(e) = presence.update(subnet, false) {
+            eprintln!("[WARN] Failed to announce monitoring to peers: {}", e);
+        }
+    }
+    Ok(())
+}
+
+/// Pause background monitoring without losing its device state
+#[tauri::command]
+pub async fn pause_monitoring(
+    monitor_state: tauri::State<'_, MonitorState>,
+) -> Result<(), String> {
+    let monitor = monitor_state.monitor.lock().await;
+    if !monitor.is_running() {
+        return Err("Monitoring is not running".to_string());
+    }
+    monitor.pause();
+    Ok(())
+}
+
+/// Resume paused background monitoring
+#[tauri::command]
+pub async fn resume_monitoring(
+    monitor_state: tauri::State<'_, MonitorState>,
+) -> Result<(), String> {
+    let monitor = monitor_state.monitor.lock().await;
+    if !monitor.is_running() {
+        return Err("Monitoring is not running".to_string());
+    }
+    monitor.resume();
+    Ok(())
+}
+
+/// Change the monitoring interval without restarting; returns the interval applied
+#[tauri::command]
+pub async fn set_monitoring_interval(
+    monitor_state: tauri::State<'_, MonitorState>,
+    interval_seconds: u64,
+) -> Result<u64, String> {
+    let monitor = monitor_state.monitor.lock().await;
+    Ok(monitor.set_interval(interval_seconds).await)
+}
+
+/// Normalized subnet of the interface monitoring would use
+fn monitored_subnet(interface: Option<&str>) -> Option<String> {
+    let interface = select_interface(interface).ok()?;
+    calculate_subnet_ips(&interface)
+        .ok()
+        .map(|(subnet, _)| subnet.to_string())
+}
+
+/// Start mDNS self-advertisement if it is enabled in settings
+pub fn start_peer_presence(state: &AppState) -> Result<Option<PeerPresence>, String> {
+    let conn = lock_db_connection(&state.db)?;
+
+    let settings = peers::load_peer_settings(&conn)
+        .map_err(|e| format!("Failed to load peer settings: {}", e))?;
+    if !settings.enabled {
+        return Ok(None);
+    }
+    let instance_id = peers::instance_id(&conn)
+        .map_err(|e| format!("Failed to load instance ID: {}", e))?;
+    // Peers pin the export signing key when they trust this instance
+    let key_path = ExportSigner::key_path(state.db.path());
+    let signer = ExportSigner::load_or_create(&conn, &key_path)
+        .map_err(|e| format!("Failed to load signing key: {}", e))?;
+    drop(conn);
+
+    let interface = find_valid_interface()
+        .map_err(|e| format!("Failed to find network interface: {}", e))?;
+    let subnet = calculate_subnet_ips(&interface)
+        .ok()
+        .map(|(subnet, _)| subnet.to_string());
+
+    let mut announcement = Announcement::new(instance_id, &settings, interface.ip, subnet);
+    announcement.public_key = Some(signer.public_key_base64());
+    let presence = PeerPresence::start(announcement)
+        .map_err(|e| format!("Failed to start peer advertisement: {}", e))?;
+    if !settings.distributed_scans {
+        return Ok(Some(presence));
+    }
+    presence
+        .with_sharing(signer, settings.trusted_peers)
+        .map(Some)
+        .map_err(|e| format!("Failed to start scan sharing: {}", e))
+}
+
+/// Get mDNS self-advertisement settings
+#[tauri::command]
+pub fn get_peer_settings(state: tauri::State<'_, AppState>) -> Result<PeerSettings, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+
+    peers::load_peer_settings(&conn).map_err(|e| format!("Failed to load peer settings: {}", e))
+}
+
+/// Save mDNS self-advertisement settings and restart the announcement
+#[tauri::command]
+pub async fn save_peer_settings(
+    state: tauri::State<'_, AppState>,
+    monitor_state: tauri::State<'_, MonitorState>,
+    settings: PeerSettings,
+) -> Result<(), String> {
+    {
+        let conn = get_db_connection(&state)?;
+        let conn = lock_db_connection(&conn)?;
+        peers::save_peer_settings(&conn, &settings)
+            .map_err(|e| format!("Failed to save peer settings: {}", e))?;
+    }
+
+    let mut presence = monitor_state.peer_presence.lock().await;
+    // Withdraw the old announcement before registering a new one
+    *presence = None;
+    *presence = start_peer_presence(&state)?;
+
+    if let Some(presence) = presence.as_ref() {
+        if monitor_state.monitor.lock().await.is_running() {
+            let subnet = presence.announcement().and_then(|a| a.subnet);
+            presence
+                .update(subnet, true)
+                .map_err(|e| format!("Failed to announce monitoring to peers: {}", e))?;
+        }
+    }
+    Ok(())
+}
+
+/// List other instances seen on the LAN (empty unless advertising is enabled)
+#[tauri::command]
+pub async fn list_peers(
+    monitor_state: tauri::State<'_, MonitorState>,
+) -> Result<Vec<PeerInfo>, String> {
+    Ok(monitor_state
+        .peer_presence
+        .lock()
+        .await
+        .as_ref()
+        .map(|presence| presence.peers())
+        .unwrap_or_default())
+}
+
+/// Get current monitoring status
+#[tauri::command]
+pub async fn get_monitoring_status(
+    monitor_state: tauri::State<'_, MonitorState>,
+) -> Result<MonitoringStatus, String> {
+    let monitor = monitor_state.monitor.lock().await;
+    Ok(monitor.status().await)
+}
+
+/// Get monitor health metrics (uptime, scan durations, failures, event rates)
+#[tauri::command]
+pub async fn get_monitoring_stats(
+    monitor_state: tauri::State<'_, MonitorState>,
+) -> Result<MonitoringStats, String> {
+    let monitor = monitor_state.monitor.lock().await;
+    Ok(monitor.stats())
+}
+
+/// Stored monitor events, the latest `limit` (default 200) from `since` on, oldest first
+#[tauri::command]
+pub fn get_recent_events(
+    state: tauri::State<'_, AppState>,
+    limit: Option<usize>,
+    since: Option<chrono::DateTime<chrono::Utc>>,
+) -> Result<Vec<StoredEvent>, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+
+    event_log::get_recent_events(&conn, limit.unwrap_or(200), since)
+        .map_err(|e| format!("Failed to get monitor events: {}", e))
+}
+
+/// Replay stored monitor events after the one with id `after_id`, oldest first
+#[tauri::command]
+pub fn replay_events(
+    state: tauri::State<'_, AppState>,
+    after_id: i64,
+    limit: Option<usize>,
+) -> Result<Vec<StoredEvent>, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+
+    event_log::get_events_after(&conn, after_id, limit.unwrap_or(500))
+        .map_err(|e| format!("Failed to replay monitor events: {}", e))
+}
+
+/// Get the monitoring schedule (office-hours profiles and quiet hours)
+#[tauri::command]
+pub fn get_monitor_schedule(state: tauri::State<'_, AppState>) -> Result<MonitorSchedule, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+
+    load_monitor_schedule(&conn).map_err(|e| format!("Failed to load monitoring schedule: {}", e))
+}
+
+/// Save the monitoring schedule; a running monitor follows it from its next check
+#[tauri::command]
+pub async fn save_monitor_schedule(
+    state: tauri::State<'_, AppState>,
+    monitor_state: tauri::State<'_, MonitorState>,
+    schedule: MonitorSchedule,
+) -> Result<(), String> {
+    {
+        let conn = get_db_connection(&state)?;
+        let conn = lock_db_connection(&conn)?;
+        host_discovery::save_monitor_schedule(&conn, &schedule)
+            .map_err(|e| format!("Failed to save monitoring schedule: {}", e))?;
+    }
+
+    let monitor = monitor_state.monitor.lock().await;
+    monitor.set_schedule(schedule).await;
+    Ok(())
+}
+
+/// Get the settings of the monitor's internet health check
+#[tauri::command]
+pub fn get_internet_check_settings(
+    state: tauri::State<'_, AppState>,
+) -> Result<InternetCheckSettings, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+
+    load_internet_check_settings(&conn)
+        .map_err(|e| format!("Failed to load internet check settings: {}", e))
+}
+
+/// Save the internet health check settings; a running monitor uses them from its next cycle
+#[tauri::command]
+pub async fn save_internet_check_settings(
+    state: tauri::State<'_, AppState>,
+    monitor_state: tauri::State<'_, MonitorState>,
+    settings: InternetCheckSettings,
+) -> Result<(), String> {
+    {
+        let conn = get_db_connection(&state)?;
+        let conn = lock_db_connection(&conn)?;
+        host_discovery::save_internet_check_settings(&conn, &settings)
+            .map_err(|e| format!("Failed to save internet check settings: {}", e))?;
+    }
+
+    let monitor = monitor_state.monitor.lock().await;
+    monitor.set_internet_check(Some(settings)).await;
+    Ok(())
+}
+
+/// Stored internet health checks, the latest `limit` (default 500) from `since` on, oldest first
+#[tauri::command]
+pub fn get_internet_health(
+    state: tauri::State<'_, AppState>,
+    since: Option<chrono::DateTime<chrono::Utc>>,
+    limit: Option<usize>,
+) -> Result<Vec<InternetHealth>, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+
+    internet::get_internet_health(&conn, since, limit.unwrap_or(500))
+        .map_err(|e| format!("Failed to get internet health: {}", e))
+}
+
+/// Presence sessions overlapping `since`..`until`, optionally for one device, newest first
+#[tauri::command]
+pub fn get_presence_sessions(
+    state: tauri::State<'_, AppState>,
+    mac: Option<String>,
+    since: Option<chrono::DateTime<chrono::Utc>>,
+    until: Option<chrono::DateTime<chrono::Utc>>,
+) -> Result<Vec<PresenceSession>, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+
+    presence::get_presence_sessions(&conn, mac.as_deref(), since, until)
+        .map_err(|e| format!("Failed to get presence sessions: {}", e))
+}
+
+/// Start passive TLS SNI capture (stores contacted services per device)
+#[tauri::command]
+pub async fn start_sni_capture(
+    state: tauri::State<'_, AppState>,
+    monitor_state: tauri::State<'_, MonitorState>,
+) -> Result<(), String> {
+    let mut task = monitor_state.sni_task.lock().await;
+    if task.as_ref().is_some_and(|t| !t.is_finished()) {
+        return Err("SNI capture is already running".to_string());
+    }
+
+    let db = state.db.clone();
+
+    let handle = host_discovery::monitor::start_sni_inventory(db)
+        .map_err(|e| format!("Failed to start SNI capture: {}", e))?;
+    *task = Some(handle);
+    Ok(())
+}
+
+/// Stop passive TLS SNI capture
+#[tauri::command]
+pub async fn stop_sni_capture(
+    monitor_state: tauri::State<'_, MonitorState>,
+) -> Result<(), String> {
+    if let Some(handle) = monitor_state.sni_task.lock().await.take() {
+        handle.abort();
+    }
+    Ok(())
+}
+
+/// Start passive mDNS listening (stores advertised services per device)
+#[tauri::command]
+pub async fn start_mdns_catalog(
+    state: tauri::State<'_, AppState>,
+    monitor_state: tauri::State<'_, MonitorState>,
+) -> Result<(), String> {
+    let mut task = monitor_state.mdns_task.lock().await;
+    if task.as_ref().is_some_and(|t| !t.is_finished()) {
+        return Err("mDNS catalog is already running".to_string());
+    }
+
+    let db = state.db.clone();
+
+    let handle = host_discovery::monitor::start_mdns_catalog(db)
+        .map_err(|e| format!("Failed to start mDNS catalog: {}", e))?;
+    *task = Some(handle);
+    Ok(())
+}
+
+/// Stop passive mDNS listening
+#[tauri::command]
+pub async fn stop_mdns_catalog(
+    monitor_state: tauri::State<'_, MonitorState>,
+) -> Result<(), String> {
+    if let Some(handle) = monitor_state.mdns_task.lock().await.take() {
+        handle.abort();
+    }
+    Ok(())
+}
+
+/// Get public IP / ISP lookup settings
+#[tauri::command]
+pub fn get_wan_settings(state: tauri::State<'_, AppState>) -> Result<WanLookupSettings, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+
+    load_wan_settings(&conn).map_err(|e| format!("Failed to load WAN settings: {}", e))
+}
+
+/// Save public IP / ISP lookup settings
+#[tauri::command]
+pub fn save_wan_settings(
+    state: tauri::State<'_, AppState>,
+    settings: WanLookupSettings,
+) -> Result<(), String> {
+    let conn = get_db_connection(&state)?;
+    let conn = lock_db_connection(&conn)?;
+
+    host_discovery::save_wan_settings(&conn, &settings)
+        .map_err(|e| format!("Failed to save WAN settings: {}", e))
+}
+
+/// Look up the public IP and ISP now, without scanning
+#[tauri::command]
+pub async fn get_wan_info(state: tauri::State<'_, AppState>) -> Result<WanInfo, String> {
+    let settings = {
+        let conn = get_db_connection(&state)?;
+        let conn = read_db_connection(&conn)?;
+        load_wan_settings(&conn).map_err(|e| format!("Failed to load WAN settings: {}", e))?
+    };
+
+    tokio::task::spawn_blocking(move || wan_info(&settings))
+        .await
+        .map_err(|e| format!("WAN lookup task failed: {}", e))?
+        .map_err(|e| format!("WAN lookup failed: {:#}", e))
+}
+
+/// Get the backend used for reverse DNS lookups
+#[tauri::command]
+pub fn get_dns_resolver(state: tauri::State<'_, AppState>) -> Result<DnsResolver, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+
+    load_dns_resolver(&conn).map_err(|e| format!("Failed to load DNS resolver: {}", e))
+}
+
+/// Save the reverse DNS backend; the monitor picks it up on the next scan
+#[tauri::command]
+pub async fn save_dns_resolver(
+    state: tauri::State<'_, AppState>,
+    monitor_state: tauri::State<'_, MonitorState>,
+    resolver: DnsResolver,
+) -> Result<(), String> {
+    {
+        let conn = get_db_connection(&state)?;
+        let conn = lock_db_connection(&conn)?;
+        host_discovery::network::save_dns_resolver(&conn, &resolver)
+            .map_err(|e| format!("Failed to save DNS resolver: {}", e))?;
+    }
+
+    let monitor = monitor_state.monitor.lock().await;
+    monitor.set_dns_resolver(resolver).await;
+    Ok(())
+}
+
+/// Get forward DNS sweep settings
+#[tauri::command]
+pub fn get_dns_sweep_settings(
+    state: tauri::State<'_, AppState>,
+) -> Result<DnsSweepSettings, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+
+    load_dns_sweep_settings(&conn)
+        .map_err(|e| format!("Failed to load DNS sweep settings: {}", e))
+}
+
+/// Save forward DNS sweep settings
+#[tauri::command]
+pub fn save_dns_sweep_settings(
+    state: tauri::State<'_, AppState>,
+    settings: DnsSweepSettings,
+) -> Result<(), String> {
+    let conn = get_db_connection(&state)?;
+    let conn = lock_db_connection(&conn)?;
+
+    host_discovery::network::save_dns_sweep_settings(&conn, &settings)
+        .map_err(|e| format!("Failed to save DNS sweep settings: {}", e))
+}
+
+/// Sweep the local domain now and return every forward record found
+#[tauri::command]
+pub async fn run_dns_sweep(state: tauri::State<'_, AppState>) -> Result<DnsSweepResult, String> {
+    let (settings, known) = {
+        let conn = get_db_connection(&state)?;
+        let conn = read_db_connection(&conn)?;
+        let settings = load_dns_sweep_settings(&conn)
+            .map_err(|e| format!("Failed to load DNS sweep settings: {}", e))?;
+        let known: Vec<String> = queries::get_all_devices(&conn)
+            .map_err(|e| format!("Failed to load devices: {}", e))?
+            .into_iter()
+            .filter_map(|device| device.hostname)
+            .collect();
+        (settings, known)
+    };
+
+    tokio::task::spawn_blocking(move || dns_forward_sweep(&settings, &known))
+        .await
+        .map_err(|e| format!("DNS sweep task failed: {}", e))?
+        .map_err(|e| format!("DNS sweep failed: {:#}", e))
+}
+
+/// Get the local GeoIP database paths
+#[tauri::command]
+pub fn get_geoip_settings(state: tauri::State<'_, AppState>) -> Result<GeoIpSettings, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+
+    host_discovery::network::load_geoip_settings(&conn)
+        .map_err(|e| format!("Failed to load GeoIP settings: {}", e))
+}
+
+/// Save the local GeoIP database paths and reload the databases
+#[tauri::command]
+pub fn save_geoip_settings(
+    state: tauri::State<'_, AppState>,
+    settings: GeoIpSettings,
+) -> Result<(), String> {
+    let conn = get_db_connection(&state)?;
+    let conn = lock_db_connection(&conn)?;
+
+    host_discovery::network::save_geoip_settings(&conn, &settings)
+        .map_err(|e| format!("Failed to save GeoIP settings: {}", e))
+}
+
+/// Look up the country / ASN of an external address in the local databases
+#[tauri::command]
+pub fn lookup_geoip(ip: String) -> Result<Option<GeoInfo>, String> {
+    let ip: std::net::IpAddr = ip
+        .trim()
+        .parse()
+        .map_err(|_| format!("Invalid IP address: {}", ip))?;
+    Ok(geoip_lookup(ip))
+}
+
+/// Get the LDAP directory connection (password omitted), if configured
+#[tauri::command]
+pub fn get_ldap_config(state: tauri::State<'_, AppState>) -> Result<Option<LdapConfig>, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+
+    let config: Option<LdapConfig> = queries::get_json_setting(&conn, queries::SETTING_LDAP_CONFIG)
+        .map_err(|e| format!("Failed to load LDAP settings: {}", e))?;
+    Ok(config.map(|config| LdapConfig {
+        bind_password: String::new(),
+        ..config
+    }))
+}
+
+/// Save the LDAP directory connection (an empty password keeps the stored one)
+#[tauri::command]
+pub fn save_ldap_config(state: tauri::State<'_, AppState>, mut config: LdapConfig) -> Result<(), String> {
+    let conn = get_db_connection(&state)?;
+    let conn = lock_db_connection(&conn)?;
+
+    if config.bind_password.is_empty() {
+        let stored: Option<LdapConfig> = queries::get_json_setting(&conn, queries::SETTING_LDAP_CONFIG)
+            .map_err(|e| format!("Failed to load LDAP settings: {}", e))?;
+        if let Some(stored) = stored {
+            config.bind_password = stored.bind_password;
+        }
+    }
+
+    queries::set_json_setting(&conn, queries::SETTING_LDAP_CONFIG, &config)
+        .map_err(|e| format!("Failed to save LDAP settings: {}", e))
+}
+
+/// Match device hostnames against AD computer objects and store the results
+#[tauri::command]
+pub async fn run_ldap_enrichment(state: tauri::State<'_, AppState>) -> Result<LdapEnrichmentSummary, String> {
+    let db = state.db.clone();
+
+    let config: LdapConfig = {
+        let conn = read_db_connection(&db)?;
+        queries::get_json_setting(&conn, queries::SETTING_LDAP_CONFIG)
+            .map_err(|e| format!("Failed to load LDAP settings: {}", e))?
+            .ok_or_else(|| "LDAP is not configured".to_string())?
+    };
+
+    host_discovery::ldap_enrich_devices(&db, &config)
+        .await
+        .map_err(|e| format!("LDAP enrichment failed: {}", e))
+}
+
+/// Start the rolling packet capture buffer (size in MiB, default 16)
+#[tauri::command]
+pub async fn start_packet_capture(
+    monitor_state: tauri::State<'_, MonitorState>,
+    buffer_mb: Option<usize>,
+) -> Result<(), String> {
+    let mut capture = monitor_state.packet_capture.lock().await;
+    if capture.is_some() {
+        return Err("Packet capture is already running".to_string());
+    }
+
+    let interface = find_valid_interface()
+        .map_err(|e| format!("Failed to find network interface: {}", e))?;
+    let capacity = buffer_mb
+        .map(|mb| mb.saturating_mul(1024 * 1024))
+        .unwrap_or(DEFAULT_CAPTURE_BUFFER_BYTES);
+
+    let started = PacketCapture::start(&interface.pnet_interface, capacity)
+        .map_err(|e| format!("Failed to start packet capture: {}", e))?;
+    *capture = Some(started);
+    Ok(())
+}
+
+/// Stop the rolling packet capture and discard its buffer
+#[tauri::command]
+pub async fn stop_packet_capture(
+    monitor_state: tauri::State<'_, MonitorState>,
+) -> Result<(), String> {
+    if let Some(capture) = monitor_state.packet_capture.lock().await.take() {
+        capture.stop();
+    }
+    Ok(())
+}
+
+fn load_arp_thresholds(state: &tauri::State<'_, AppState>) -> Result<ArpThresholds, String> {
+    let conn = get_db_connection(state)?;
+    let conn = read_db_connection(&conn)?;
+
+    let thresholds: ArpThresholds = queries::get_json_setting(&conn, queries::SETTING_ARP_THRESHOLDS)
+        .map(Option::unwrap_or_default)
+        .map_err(|e| format!("Failed to load ARP thresholds: {}", e))?;
+    // Saved before ranges were checked; fall back rather than fail the watch
+    if let Err(e) = thresholds.validate() {
+        eprintln!("[WARN] Ignoring stored ARP thresholds: {}", e);
+        return Ok(ArpThresholds::default());
+    }
+    Ok(thresholds)
+}
+
+/// Save a MAC spoofing alert for a MAC seen on several addresses, and notify
+fn record_shared_mac(db: &Database, mac: &str, ips: &[String]) -> Result<(), String> {
+    let conn = lock_db_connection(db)?;
+    // Passive ARP reports lowercase MACs; scans may have stored them uppercase
+    let known = match queries::get_device_by_mac(&conn, mac).map_err(|e| e.to_string())? {
+        Some(device) => Some(device),
+        None => queries::get_device_by_mac(&conn, &mac.to_uppercase())
+            .map_err(|e| e.to_string())?,
+    };
+    let settings_mac = known.as_ref().map_or(mac, |d| d.mac.as_str());
+    if queries::get_device_settings(&conn, settings_mac)
+        .map_err(|e| e.to_string())?
+        .is_some_and(|s| s.is_muted(chrono::Utc::now()))
+    {
+        return Ok(());
+    }
+    let alert = alerts::shared_mac_alert(mac, ips, known.as_ref());
+    let alert_insert = queries::AlertInsert {
+        alert_type: map_alert_type(&alert),
+        device_id: known.as_ref().map(|d| d.id),
+        device_mac: alert.device_mac.as_deref(),
+        device_ip: alert.device_ip.as_deref(),
+        dedupe_key: None,
+        message: &alert.message,
+        severity: map_alert_severity(&alert),
+        evidence: alert.evidence.as_ref(),
+    };
+    let inserted = queries::insert_alert_if_not_exists(
+        &conn,
+        &alert_insert,
+        &alert.dedupe_key(),
+        alert.alert_type.cooldown_minutes(),
+    )
+    .map_err(|e| e.to_string())?;
+    if let Some(id) = inserted {
+        notify_alerts(&conn, vec![Notification::from_alert(&alert, Some(id))])
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+fn spawn_arp_watch(
+    app: &tauri::AppHandle,
+    db: Database,
+    thresholds: ArpThresholds,
+) -> Result<tokio::task::JoinHandle<()>, String> {
+    let app_handle = app.clone();
+    host_discovery::monitor::start_arp_anomaly_watch(thresholds, move |event: NetworkEvent| {
+        if let NetworkEvent::SharedMacDetected { mac, ips } = &event {
+            if let Err(e) = record_shared_mac(&db, mac, ips) {
+                eprintln!("[WARN] Failed to save MAC spoofing alert: {}", e);
+            }
+        }
+        let _ = app_handle.emit("network-event", &event);
+    })
+    .map_err(|e| format!("Failed to start ARP watch: {}", e))
+}
+
+/// Start ARP anomaly detection (address takeover and ARP storm events)
+#[tauri::command]
+pub async fn start_arp_watch(
+    state: tauri::State<'_, AppState>,
+    monitor_state: tauri::State<'_, MonitorState>,
+    app: tauri::AppHandle,
+) -> Result<(), String> {
+    let mut watch = monitor_state.arp_watch.lock().await;
+    if watch.as_ref().is_some_and(|t| !t.is_finished()) {
+        return Err("ARP watch is already running".to_string());
+    }
+
+    let thresholds = load_arp_thresholds(&state)?;
+    *watch = Some(spawn_arp_watch(&app, state.db.clone(), thresholds)?);
+    Ok(())
+}
+
+/// Stop ARP anomaly detection
+#[tauri::command]
+pub async fn stop_arp_watch(
+    monitor_state: tauri::State<'_, MonitorState>,
+) -> Result<(), String> {
+    if let Some(handle) = monitor_state.arp_watch.lock().await.take() {
+        handle.abort();
+    }
+    Ok(())
+}
+
+/// Get ARP anomaly alert thresholds
+#[tauri::command]
+pub fn get_arp_thresholds(state: tauri::State<'_, AppState>) -> Result<ArpThresholds, String> {
+    load_arp_thresholds(&state)
+}
+
+/// Save ARP anomaly alert thresholds (restarts the ARP watch if running)
+#[tauri::command]
+pub async fn update_arp_thresholds(
+    state: tauri::State<'_, AppState>,
+    monitor_state: tauri::State<'_, MonitorState>,
+    app: tauri::AppHandle,
+    thresholds: ArpThresholds,
+) -> Result<(), String> {
+    thresholds
+        .validate()
+        .map_err(|e| format!("Invalid ARP thresholds: {}", e))?;
+    {
+        let conn = get_db_connection(&state)?;
+        let conn = lock_db_connection(&conn)?;
+        queries::set_json_setting(&conn, queries::SETTING_ARP_THRESHOLDS, &thresholds)
+            .map_err(|e| format!("Failed to save ARP thresholds: {}", e))?;
+    }
+
+    let mut watch = monitor_state.arp_watch.lock().await;
+    if let Some(handle) = watch.take() {
+        handle.abort();
+        *watch = Some(spawn_arp_watch(&app, state.db.clone(), thresholds)?);
+    }
+    Ok(())
+}
+
+// =====================================================
+// Scheduled Scan Commands
+// =====================================================
+
+/// Start running scheduled scans in the background
+///
+/// Runs for the life of the app, whether or not monitoring is on. Each due
+/// schedule gets a full scan, saved like one started from the UI, and a PDF
+/// report when the schedule asks for one.
+pub fn start_scan_scheduler(app: &tauri::AppHandle) {
+    let app = app.clone();
+    let db = app.state::<AppState>().db.clone();
+    tauri::async_runtime::spawn(scheduler::run_scheduler(db, move |schedule| {
+        let app = app.clone();
+        async move { run_scheduled_scan(&app, &schedule).await }
+    }));
+}
+
+async fn run_scheduled_scan(app: &tauri::AppHandle, schedule: &ScanSchedule) -> Result<(), String> {
+    let scan = scan_network(
+        app.clone(),
+        app.state::<AppState>(),
+        app.state::<MonitorState>(),
+        schedule.spec.interface.clone(),
+        None,
+    )
+    .await?;
+    if !schedule.spec.generate_report {
+        return Ok(());
+    }
+
+    let pdf = build_scan_report(&app.state::<AppState>(), &scan, &scan.active_hosts)?;
+    let path = schedule.report_path(chrono::Utc::now());
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create report folder: {}", e))?;
+    }
+    std::fs::write(&path, pdf)
+        .map_err(|e| format!("Failed to write report to {}: {}", path.display(), e))
+}
+
+/// Get all scheduled scans with their next run times
+#[tauri::command]
+pub fn get_scan_schedules(state: tauri::State<'_, AppState>) -> Result<Vec<ScanSchedule>, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+
+    scheduler::get_scan_schedules(&conn)
+        .map_err(|e| format!("Failed to get scan schedules: {}", e))
+}
+
+/// Create a scheduled scan (the cron expression is validated before saving)
+#[tauri::command]
+pub fn create_scan_schedule(
+    state: tauri::State<'_, AppState>,
+    schedule: ScanScheduleSpec,
+) -> Result<ScanSchedule, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = lock_db_connection(&conn)?;
+
+    scheduler::create_scan_schedule(&conn, &schedule)
+        .and_then(|id| scheduler::get_scan_schedule(&conn, id))
+        .map_err(|e| format!("Failed to create scan schedule: {}", e))?
+        .ok_or_else(|| "Created scan schedule not found".to_string())
+}
+
+/// Update a scheduled scan
+#[tauri::command]
+pub fn update_scan_schedule(
+    state: tauri::State<'_, AppState>,
+    id: i64,
+    schedule: ScanScheduleSpec,
+) -> Result<ScanSchedule, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = lock_db_connection(&conn)?;
+
+    let updated = scheduler::update_scan_schedule(&conn, id, &schedule)
+        .map_err(|e| format!("Failed to update scan schedule: {}", e))?;
+    if !updated {
+        return Err(format!("Scan schedule {} not found", id));
+    }
+    scheduler::get_scan_schedule(&conn, id)
+        .map_err(|e| format!("Failed to get scan schedule: {}", e))?
+        .ok_or_else(|| format!("Scan schedule {} not found", id))
+}
+
+/// Delete a scheduled scan (reports already written are kept)
+#[tauri::command]
+pub fn delete_scan_schedule(state: tauri::State<'_, AppState>, id: i64) -> Result<(), String> {
+    let conn = get_db_connection(&state)?;
+    let conn = lock_db_connection(&conn)?;
+
+    scheduler::delete_scan_schedule(&conn, id)
+        .map_err(|e| format!("Failed to delete scan schedule: {}", e))?;
+    Ok(())
+}
+
+// =====================================================
+// AI Insights Commands
+// =====================================================
+
+// Note: DeviceDistribution, SecurityReport are available for future AI insights
+
+/// Accept the risk of a device finding until `expires_at`
+#[tauri::command]
+pub fn accept_device_risk(
+    state: tauri::State<'_, AppState>,
+    mac: String,
+    finding: RiskFinding,
+    reason: String,
+    accepted_by: Option<String>,
+    expires_at: chrono::DateTime<chrono::Utc>,
+) -> Result<i64, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = lock_db_connection(&conn)?;
+
+    queries::accept_risk(&conn, &mac, finding, &reason, accepted_by.as_deref(), expires_at)
+        .map_err(|e| format!("Failed to accept risk: {}", e))
+}
+
+/// Withdraw a risk acceptance
+#[tauri::command]
+pub fn revoke_risk_acceptance(state: tauri::State<'_, AppState>, id: i64) -> Result<bool, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = lock_db_connection(&conn)?;
+
+    queries::revoke_risk_acceptance(&conn, id)
+        .map_err(|e| format!("Failed to revoke risk acceptance: {}", e))
+}
+
+/// List risk acceptances, active and expired
+#[tauri::command]
+pub fn get_risk_acceptances(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<RiskAcceptance>, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+
+    queries::get_risk_acceptances(&conn)
+        .map_err(|e| format!("Failed to get risk acceptances: {}", e))
+}
+
+/// Get network health score from current scan
+#[tauri::command]
+pub fn get_network_health(
+    state: tauri::State<'_, AppState>,
+) -> Result<serde_json::Value, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = lock_db_connection(&conn)?;
+
+    let hosts = queries::get_latest_scan_hosts(&conn)
+        .map_err(|e| format!("Failed to get latest scan host data: {}", e))?;
+    if let Err(e) = queries::alert_expired_risk_acceptances(&conn) {
+        eprintln!("[WARN] Failed to check expired risk acceptances: {}", e);
+    }
+    let acceptances = queries::get_risk_acceptances(&conn)
+        .map_err(|e| format!("Failed to get risk acceptances: {}", e))?;
+    let device_groups = queries::get_device_groups(&conn)
+        .map_err(|e| format!("Failed to get device groups: {}", e))?;
+    let hosts = groups::without_ignored(&hosts, &device_groups);
+    let health = host_discovery::NetworkHealth::calculate_with_acceptances(&hosts, &acceptances);
+    
+    Ok(serde_json::json!({
+        "score": health.score,
+        "grade": health.grade.to_string(),
+        "status": health.status,
+        "breakdown": {
+            "security": health.breakdown.security,
+            "stability": health.breakdown.stability,
+            "compliance": health.breakdown.compliance
+        },
+        "insights": health.insights
+    }))
+}
+
+/// Preview how planned changes would affect network health and device risk
+///
+/// Runs against the latest scan; nothing on the network or in the database
+/// is changed.
+#[tauri::command]
+pub fn simulate_network_changes(
+    state: tauri::State<'_, AppState>,
+    changes: Vec<PlannedChange>,
+) -> Result<SimulationResult, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+
+    let hosts = queries::get_latest_scan_hosts(&conn)
+        .map_err(|e| format!("Failed to get latest scan host data: {}", e))?;
+    let acceptances = queries::get_risk_acceptances(&conn)
+        .map_err(|e| format!("Failed to get risk acceptances: {}", e))?;
+    let device_groups = queries::get_device_groups(&conn)
+        .map_err(|e| format!("Failed to get device groups: {}", e))?;
+    let hosts = groups::without_ignored(&hosts, &device_groups);
+
+    Ok(simulate_changes(&hosts, &changes, &acceptances))
+}
+
+/// Get device distribution stats
+#[tauri::command]
+pub fn get_device_distribution(
+    state: tauri::State<'_, AppState>,
+) -> Result<serde_json::Value, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+    
+    let devices = queries::get_all_devices(&conn)
+        .map_err(|e| format!("Failed to get devices: {}", e))?;
+    
     let mut by_type: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
     for device in &devices {
         let dtype = device.device_type.clone().unwrap_or_else(|| "UNKNOWN".to_string());
@@ -659,6 +3460,94 @@ pub fn get_device_distribution(
     }))
 }
 
+/// Get devices that answered only ARP over several consecutive scans
+#[tauri::command]
+pub fn get_stealth_hosts(
+    state: tauri::State<'_, AppState>,
+    min_scans: Option<u32>,
+) -> Result<Vec<StealthHost>, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+
+    let devices = queries::get_all_devices(&conn)
+        .map_err(|e| format!("Failed to get devices: {}", e))?;
+    let streaks = queries::get_arp_only_streaks(&conn)
+        .map_err(|e| format!("Failed to get scan history: {}", e))?;
+
+    Ok(find_stealth_hosts(
+        &devices,
+        &streaks,
+        min_scans.unwrap_or(STEALTH_MIN_SCANS),
+    ))
+}
+
+/// Freeze the devices and open ports of the latest scan as the baseline
+#[tauri::command]
+pub fn set_network_baseline(state: tauri::State<'_, AppState>) -> Result<NetworkBaseline, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = lock_db_connection(&conn)?;
+
+    queries::set_network_baseline(&conn)
+        .map_err(|e| format!("Failed to set network baseline: {}", e))
+}
+
+/// Get the network baseline, if one was set
+#[tauri::command]
+pub fn get_network_baseline(
+    state: tauri::State<'_, AppState>,
+) -> Result<Option<NetworkBaseline>, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+
+    queries::get_network_baseline(&conn)
+        .map_err(|e| format!("Failed to get network baseline: {}", e))
+}
+
+/// Remove the network baseline
+#[tauri::command]
+pub fn clear_network_baseline(state: tauri::State<'_, AppState>) -> Result<bool, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = lock_db_connection(&conn)?;
+
+    queries::clear_network_baseline(&conn)
+        .map_err(|e| format!("Failed to clear network baseline: {}", e))
+}
+
+/// Compare the latest scan with the baseline (None when no baseline is set)
+#[tauri::command]
+pub fn get_baseline_compliance(
+    state: tauri::State<'_, AppState>,
+) -> Result<Option<ComplianceReport>, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+
+    let Some(baseline) = queries::get_network_baseline(&conn)
+        .map_err(|e| format!("Failed to get network baseline: {}", e))?
+    else {
+        return Ok(None);
+    };
+    let hosts = queries::get_latest_scan_hosts(&conn)
+        .map_err(|e| format!("Failed to get latest scan: {}", e))?;
+
+    Ok(Some(compliance_report(&baseline, &hosts)))
+}
+
+/// Get cloud services contacted by each device (from passive SNI capture)
+#[tauri::command]
+pub fn get_cloud_service_inventory(
+    state: tauri::State<'_, AppState>,
+) -> Result<CloudServiceInventory, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+
+    let devices = queries::get_all_devices(&conn)
+        .map_err(|e| format!("Failed to get devices: {}", e))?;
+    let observations = queries::get_all_sni(&conn)
+        .map_err(|e| format!("Failed to get SNI observations: {}", e))?;
+
+    Ok(CloudServiceInventory::build(&devices, &observations))
+}
+
 /// Generate machine-readable schema for ScanResult contract.
 #[tauri::command]
 pub fn get_scan_result_schema() -> Result<serde_json::Value, String> {
@@ -681,16 +3570,24 @@ pub fn get_scan_result_schema() -> Result<serde_json::Value, String> {
 // Export Commands
 // =====================================================
 
-/// Export devices to CSV
+/// Export devices to CSV, optionally only a smart group or a device tag
 #[tauri::command]
 pub fn export_devices_to_csv(
     state: tauri::State<'_, AppState>,
+    group_id: Option<i64>,
+    tag: Option<String>,
 ) -> Result<String, String> {
     let conn = get_db_connection(&state)?;
-    let conn = lock_db_connection(&conn)?;
+    let conn = read_db_connection(&conn)?;
     
-    let devices = queries::get_all_devices(&conn)
-        .map_err(|e| format!("Failed to get devices: {}", e))?;
+    let devices = match (group_id, tag) {
+        (Some(group_id), _) => groups::resolve_smart_group(&conn, group_id)
+            .map_err(|e| format!("Failed to resolve smart group: {}", e))?,
+        (None, Some(tag)) => queries::get_devices_by_tag(&conn, &tag)
+            .map_err(|e| format!("Failed to get devices tagged {}: {}", tag, e))?,
+        (None, None) => queries::get_all_devices(&conn)
+            .map_err(|e| format!("Failed to get devices: {}", e))?,
+    };
     
     export_devices_csv(&devices)
         .map_err(|e| format!("Failed to export CSV: {}", e))
@@ -705,6 +3602,79 @@ pub fn export_scan_to_csv(
         .map_err(|e| format!("Failed to export CSV: {}", e))
 }
 
+/// Export the alert history matching `query` to CSV, every page, oldest first
+#[tauri::command]
+pub fn export_alerts_csv(
+    state: tauri::State<'_, AppState>,
+    query: AlertQuery,
+) -> Result<String, String> {
+    let query = AlertQuery {
+        limit: None,
+        offset: 0,
+        oldest_first: true,
+        ..query
+    };
+    let alerts = query_alerts(state, query)?.items;
+    host_discovery::export_alerts_csv(&alerts)
+        .map_err(|e| format!("Failed to export CSV: {}", e))
+}
+
+/// Sensitive ports observed open per network and device over a period
+///
+/// `ports` defaults to Telnet, SMB, RDP and VNC.
+#[tauri::command]
+pub fn get_port_exposure_report(
+    state: tauri::State<'_, AppState>,
+    subnet: Option<String>,
+    since: Option<chrono::DateTime<chrono::Utc>>,
+    until: Option<chrono::DateTime<chrono::Utc>>,
+    ports: Option<Vec<u16>>,
+) -> Result<Vec<PortExposureRecord>, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+
+    let ports = ports.unwrap_or_else(|| SENSITIVE_PORTS.to_vec());
+    queries::get_port_exposure(&conn, &ports, subnet.as_deref(), since, until)
+        .map_err(|e| format!("Failed to build port exposure report: {}", e))
+}
+
+/// Export the port exposure report to CSV
+#[tauri::command]
+pub fn export_port_exposure_to_csv(
+    state: tauri::State<'_, AppState>,
+    subnet: Option<String>,
+    since: Option<chrono::DateTime<chrono::Utc>>,
+    until: Option<chrono::DateTime<chrono::Utc>>,
+    ports: Option<Vec<u16>>,
+) -> Result<String, String> {
+    let records = get_port_exposure_report(state, subnet, since, until, ports)?;
+    export_port_exposure_csv(&records)
+        .map_err(|e| format!("Failed to export CSV: {}", e))
+}
+
+/// Export a chronological changelog of the network over a period
+///
+/// `format` is "markdown" (default) or "csv".
+#[tauri::command]
+pub fn export_change_journal(
+    state: tauri::State<'_, AppState>,
+    since: Option<chrono::DateTime<chrono::Utc>>,
+    until: Option<chrono::DateTime<chrono::Utc>>,
+    format: Option<String>,
+) -> Result<String, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+
+    let entries = build_change_journal(&conn, since, until)
+        .map_err(|e| format!("Failed to build change journal: {}", e))?;
+    match format.as_deref().unwrap_or("markdown") {
+        "markdown" => Ok(export_journal_markdown(&entries, since, until)),
+        "csv" => export_journal_csv(&entries)
+            .map_err(|e| format!("Failed to export CSV: {}", e)),
+        other => Err(format!("Unknown journal format: {}", other)),
+    }
+}
+
 /// Export topology data to JSON
 #[tauri::command]
 pub fn export_topology_to_json(
@@ -731,15 +3701,138 @@ pub fn export_scan_report(
     scan: ScanResult,
     hosts: Vec<HostInfo>,
 ) -> Result<Vec<u8>, String> {
-    let conn = get_db_connection(&state)?;
-    let conn = lock_db_connection(&conn)?;
+    build_scan_report(&state, &scan, &hosts)
+}
+
+fn build_scan_report(
+    state: &tauri::State<'_, AppState>,
+    scan: &ScanResult,
+    hosts: &[HostInfo],
+) -> Result<Vec<u8>, String> {
+    let conn = get_db_connection(state)?;
+    let conn = read_db_connection(&conn)?;
     
     let stats = queries::get_network_stats(&conn).ok();
-    
-    generate_scan_report_pdf(&scan, &hosts, stats.as_ref())
+
+    // Changes between the two most recent saved scans
+    let changes = match queries::get_recent_scans(&conn, 2).as_deref() {
+        Ok([newest, previous]) => queries::diff_scans(&conn, previous.id, newest.id)
+            .map_err(|e| eprintln!("[WARN] Failed to compare scans for report: {}", e))
+            .ok(),
+        _ => None,
+    };
+
+    let attributes = queries::get_attributes_by_mac(&conn).unwrap_or_else(|e| {
+        eprintln!("[WARN] Failed to load device fields for report: {}", e);
+        Default::default()
+    });
+
+    generate_scan_report_pdf(scan, hosts, stats.as_ref(), changes.as_ref(), &attributes)
         .map_err(|e| format!("Failed to generate PDF: {}", e))
 }
 
+/// Render the read-only HTML dashboard (health, topology, devices, recent alerts)
+#[tauri::command]
+pub fn export_public_dashboard(
+    state: tauri::State<'_, AppState>,
+) -> Result<String, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = lock_db_connection(&conn)?;
+
+    DashboardSnapshot::load(&conn)
+        .map(|snapshot| generate_dashboard_html(&snapshot))
+        .map_err(|e| format!("Failed to build dashboard: {}", e))
+}
+
+/// Get where the dashboard is published after each scan
+#[tauri::command]
+pub fn get_dashboard_settings(
+    state: tauri::State<'_, AppState>,
+) -> Result<DashboardSettings, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+
+    host_discovery::load_dashboard_settings(&conn)
+        .map_err(|e| format!("Failed to load dashboard settings: {}", e))
+}
+
+/// Save dashboard publishing settings and publish it right away if enabled
+#[tauri::command]
+pub fn save_dashboard_settings(
+    state: tauri::State<'_, AppState>,
+    settings: DashboardSettings,
+) -> Result<(), String> {
+    let conn = get_db_connection(&state)?;
+    let conn = lock_db_connection(&conn)?;
+
+    host_discovery::save_dashboard_settings(&conn, &settings)
+        .map_err(|e| format!("Failed to save dashboard settings: {}", e))?;
+    refresh_public_dashboard(&conn)
+        .map(|_| ())
+        .map_err(|e| format!("Failed to publish dashboard: {}", e))
+}
+
+/// Export the last N minutes of the packet capture buffer as a .pcap file
+#[tauri::command]
+pub async fn export_capture_pcap(
+    monitor_state: tauri::State<'_, MonitorState>,
+    minutes: Option<u64>,
+) -> Result<Vec<u8>, String> {
+    let capture = monitor_state.packet_capture.lock().await;
+    let capture = capture
+        .as_ref()
+        .ok_or_else(|| "Packet capture is not running".to_string())?;
+
+    let window = minutes.map(|m| std::time::Duration::from_secs(m * 60));
+    capture
+        .export_pcap(window)
+        .map_err(|e| format!("Failed to export capture: {}", e))
+}
+
+/// Get this install's export signing public key (PEM)
+#[tauri::command]
+pub fn get_export_public_key(state: tauri::State<'_, AppState>) -> Result<String, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = lock_db_connection(&conn)?;
+    let key_path = ExportSigner::key_path(state.db.path());
+
+    ExportSigner::load_or_create(&conn, &key_path)
+        .map(|signer| signer.public_key_pem())
+        .map_err(|e| format!("Failed to load signing key: {}", e))
+}
+
+/// Sign exported bytes (report, CSV or JSON) with this install's key
+#[tauri::command]
+pub fn sign_export(
+    state: tauri::State<'_, AppState>,
+    data: Vec<u8>,
+) -> Result<ExportSignature, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = lock_db_connection(&conn)?;
+    let key_path = ExportSigner::key_path(state.db.path());
+
+    let signer = ExportSigner::load_or_create(&conn, &key_path)
+        .map_err(|e| format!("Failed to load signing key: {}", e))?;
+    Ok(signer.sign(&data))
+}
+
+/// Verify a detached export signature (defaults to this install's public key)
+#[tauri::command]
+pub fn verify_export_signature(
+    state: tauri::State<'_, AppState>,
+    data: Vec<u8>,
+    signature: ExportSignature,
+    public_key: Option<String>,
+) -> Result<bool, String> {
+    let public_key = match public_key {
+        Some(key) => key,
+        None => get_export_public_key(state)?,
+    };
+
+    host_discovery::verify_export(&data, &signature, &public_key)
+        .map_err(|e| format!("Failed to verify signature: {}", e))
+}
+
 /// Generate and export network health/security report PDF
 #[tauri::command]
 pub fn export_security_report(
@@ -764,14 +3857,20 @@ pub struct PingResult {
     error: Option<String>,
 }
 
-/// Ping a single host
+/// Ping a single host, saving the run to tool history when `save` is set
 #[tauri::command]
-pub async fn ping_host(target: String, count: u32) -> Result<Vec<PingResult>, String> {
+pub async fn ping_host(
+    state: tauri::State<'_, AppState>,
+    target: String,
+    count: u32,
+    save: Option<bool>,
+) -> Result<Vec<PingResult>, String> {
     use std::net::{IpAddr, ToSocketAddrs};
     use std::time::Duration;
     use surge_ping::{Client, Config, PingIdentifier, PingSequence, IcmpPacket};
 
-    
+    let run_started = Instant::now();
+
     // Resolve hostname to IP
     let ip = if let Ok(addr) = target.parse::<IpAddr>() {
         addr
@@ -848,6 +3947,11 @@ pub async fn ping_host(target: String, count: u32) -> Result<Vec<PingResult>, St
             tokio::time::sleep(Duration::from_millis(500)).await;
         }
     }
+
+    if save.unwrap_or(false) {
+        let parameters = serde_json::json!({ "count": count });
+        save_tool_run(&state, "ping", &target, parameters, &results, run_started);
+    }
     
     Ok(results)
 }
@@ -860,12 +3964,19 @@ pub struct PortScanResult {
     service: Option<String>,
 }
 
-/// Scan ports on a target host
+/// Scan ports on a target host, saving the run to tool history when `save` is set
 #[tauri::command]
-pub async fn scan_ports(target: String, ports: Vec<u16>) -> Result<Vec<PortScanResult>, String> {
+pub async fn scan_ports(
+    state: tauri::State<'_, AppState>,
+    target: String,
+    ports: Vec<u16>,
+    save: Option<bool>,
+) -> Result<Vec<PortScanResult>, String> {
     use std::net::{IpAddr, ToSocketAddrs};
     use tokio::time::timeout;
     use std::time::Duration;
+
+    let run_started = Instant::now();
     
     // Resolve hostname to IP
     let ip = if let Ok(addr) = target.parse::<IpAddr>() {
@@ -886,7 +3997,7 @@ pub async fn scan_ports(target: String, ports: Vec<u16>) -> Result<Vec<PortScanR
     
     let mut results = Vec::new();
     
-    for port in ports {
+    for &port in &ports {
         let addr = std::net::SocketAddr::new(ip, port);
         
         let is_open = matches!(
@@ -906,10 +4017,83 @@ pub async fn scan_ports(target: String, ports: Vec<u16>) -> Result<Vec<PortScanR
             service,
         });
     }
+
+    if save.unwrap_or(false) {
+        let parameters = serde_json::json!({ "ports": ports });
+        save_tool_run(&state, "port_scan", &target, parameters, &results, run_started);
+    }
     
     Ok(results)
 }
 
+/// Store a tool run in history; a failed save only loses the history entry
+fn save_tool_run(
+    state: &AppState,
+    tool: &str,
+    target: &str,
+    parameters: serde_json::Value,
+    results: &impl serde::Serialize,
+    started: Instant,
+) {
+    let results = match serde_json::to_value(results) {
+        Ok(results) => results,
+        Err(e) => {
+            eprintln!("[WARN] Failed to encode {} run for {}: {}", tool, target, e);
+            return;
+        }
+    };
+    let saved = state.db.write(|conn| {
+        queries::insert_tool_run(
+            conn,
+            tool,
+            target,
+            &parameters,
+            &results,
+            Some(started.elapsed().as_millis() as u64),
+        )
+    });
+    if let Err(e) = saved {
+        eprintln!("[WARN] Failed to save {} run for {}: {}", tool, target, e);
+    }
+}
+
+/// Saved network tool runs, newest first
+#[tauri::command]
+pub fn get_tool_runs(
+    state: tauri::State<'_, AppState>,
+    tool: Option<String>,
+    target: Option<String>,
+    limit: Option<i32>,
+) -> Result<Vec<ToolRunRecord>, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = read_db_connection(&conn)?;
+
+    queries::get_tool_runs(&conn, tool.as_deref(), target.as_deref(), limit.unwrap_or(100))
+        .map_err(|e| format!("Failed to get tool runs: {}", e))
+}
+
+/// Delete a saved tool run
+#[tauri::command]
+pub fn delete_tool_run(state: tauri::State<'_, AppState>, id: i64) -> Result<bool, String> {
+    let conn = get_db_connection(&state)?;
+    let conn = lock_db_connection(&conn)?;
+
+    queries::delete_tool_run(&conn, id)
+        .map_err(|e| format!("Failed to delete tool run: {}", e))
+}
+
+/// Export saved tool runs to CSV
+#[tauri::command]
+pub fn export_tool_runs(
+    state: tauri::State<'_, AppState>,
+    tool: Option<String>,
+    target: Option<String>,
+) -> Result<String, String> {
+    let runs = get_tool_runs(state, tool, target, Some(i32::MAX))?;
+    export_tool_runs_csv(&runs)
+        .map_err(|e| format!("Failed to export CSV: {}", e))
+}
+
 /// Get common service name for a port
 fn get_service_name(port: u16) -> String {
     match port {
@@ -20,6 +20,8 @@ pub fn generate_demo_scan() -> ScanResult {
         total_hosts: hosts.len(),
         scan_duration_ms: 2500,
         active_hosts: hosts,
+        wan: None,
+        ssid: None,
     }
 }
 
@@ -46,6 +48,12 @@ fn generate_demo_hosts() -> Vec<HostInfo> {
             vulnerabilities: vec![],
             port_warnings: vec![],
             security_grade: "B".to_string(),
+            filtered_ports: Vec::new(),
+            host_state: None,
+            conflicting_macs: Vec::new(),
+            ipv6_addresses: Vec::new(),
+            device_id: None,
+            last_seen: None,
         },
         
         // 2. Windows PC - HIGH RISK
@@ -81,6 +89,12 @@ fn generate_demo_hosts() -> Vec<HostInfo> {
             ],
             port_warnings: vec![],
             security_grade: "F".to_string(),
+            filtered_ports: Vec::new(),
+            host_state: None,
+            conflicting_macs: Vec::new(),
+            ipv6_addresses: Vec::new(),
+            device_id: None,
+            last_seen: None,
         },
         
         // 3. Android Phone (Randomized MAC)
@@ -103,6 +117,12 @@ fn generate_demo_hosts() -> Vec<HostInfo> {
             vulnerabilities: vec![],
             port_warnings: vec![],
             security_grade: "A".to_string(),
+            filtered_ports: Vec::new(),
+            host_state: None,
+            conflicting_macs: Vec::new(),
+            ipv6_addresses: Vec::new(),
+            device_id: None,
+            last_seen: None,
         },
         
         // 4. Network Printer
@@ -125,6 +145,12 @@ fn generate_demo_hosts() -> Vec<HostInfo> {
             vulnerabilities: vec![],
             port_warnings: vec![],
             security_grade: "C".to_string(),
+            filtered_ports: Vec::new(),
+            host_state: None,
+            conflicting_macs: Vec::new(),
+            ipv6_addresses: Vec::new(),
+            device_id: None,
+            last_seen: None,
         },
         
         // 5. IP Camera - HIGH RISK
@@ -154,6 +180,12 @@ fn generate_demo_hosts() -> Vec<HostInfo> {
             ],
             port_warnings: vec![],
             security_grade: "D".to_string(),
+            filtered_ports: Vec::new(),
+            host_state: None,
+            conflicting_macs: Vec::new(),
+            ipv6_addresses: Vec::new(),
+            device_id: None,
+            last_seen: None,
         },
         
         // 6. Managed Switch (Cisco)
@@ -176,6 +208,12 @@ fn generate_demo_hosts() -> Vec<HostInfo> {
             vulnerabilities: vec![],
             port_warnings: vec![],
             security_grade: "B".to_string(),
+            filtered_ports: Vec::new(),
+            host_state: None,
+            conflicting_macs: Vec::new(),
+            ipv6_addresses: Vec::new(),
+            device_id: None,
+            last_seen: None,
         },
         
         // 7. MacBook Pro
@@ -198,6 +236,12 @@ fn generate_demo_hosts() -> Vec<HostInfo> {
             vulnerabilities: vec![],
             port_warnings: vec![],
             security_grade: "A".to_string(),
+            filtered_ports: Vec::new(),
+            host_state: None,
+            conflicting_macs: Vec::new(),
+            ipv6_addresses: Vec::new(),
+            device_id: None,
+            last_seen: None,
         },
         
         // 8. iPhone (Randomized MAC)
@@ -220,6 +264,12 @@ fn generate_demo_hosts() -> Vec<HostInfo> {
             vulnerabilities: vec![],
             port_warnings: vec![],
             security_grade: "A".to_string(),
+            filtered_ports: Vec::new(),
+            host_state: None,
+            conflicting_macs: Vec::new(),
+            ipv6_addresses: Vec::new(),
+            device_id: None,
+            last_seen: None,
         },
         
         // 9. Smart TV (Samsung)
@@ -242,6 +292,12 @@ fn generate_demo_hosts() -> Vec<HostInfo> {
             vulnerabilities: vec![],
             port_warnings: vec![],
             security_grade: "C".to_string(),
+            filtered_ports: Vec::new(),
+            host_state: None,
+            conflicting_macs: Vec::new(),
+            ipv6_addresses: Vec::new(),
+            device_id: None,
+            last_seen: None,
         },
         
         // 10. Raspberry Pi Server
@@ -271,6 +327,12 @@ fn generate_demo_hosts() -> Vec<HostInfo> {
             ],
             port_warnings: vec![],
             security_grade: "D".to_string(),
+            filtered_ports: Vec::new(),
+            host_state: None,
+            conflicting_macs: Vec::new(),
+            ipv6_addresses: Vec::new(),
+            device_id: None,
+            last_seen: None,
         },
         
         // 11. NAS Storage (Synology)
@@ -293,6 +355,12 @@ fn generate_demo_hosts() -> Vec<HostInfo> {
             vulnerabilities: vec![],
             port_warnings: vec![],
             security_grade: "B".to_string(),
+            filtered_ports: Vec::new(),
+            host_state: None,
+            conflicting_macs: Vec::new(),
+            ipv6_addresses: Vec::new(),
+            device_id: None,
+            last_seen: None,
         },
         
         // 12. Wireless Access Point
@@ -315,6 +383,12 @@ fn generate_demo_hosts() -> Vec<HostInfo> {
             vulnerabilities: vec![],
             port_warnings: vec![],
             security_grade: "A".to_string(),
+            filtered_ports: Vec::new(),
+            host_state: None,
+            conflicting_macs: Vec::new(),
+            ipv6_addresses: Vec::new(),
+            device_id: None,
+            last_seen: None,
         },
         
         // 13. Xbox Gaming Console
@@ -337,6 +411,12 @@ fn generate_demo_hosts() -> Vec<HostInfo> {
             vulnerabilities: vec![],
             port_warnings: vec![],
             security_grade: "A".to_string(),
+            filtered_ports: Vec::new(),
+            host_state: None,
+            conflicting_macs: Vec::new(),
+            ipv6_addresses: Vec::new(),
+            device_id: None,
+            last_seen: None,
         },
         
         // 14. Canon Printer
@@ -359,6 +439,12 @@ fn generate_demo_hosts() -> Vec<HostInfo> {
             vulnerabilities: vec![],
             port_warnings: vec![],
             security_grade: "C".to_string(),
+            filtered_ports: Vec::new(),
+            host_state: None,
+            conflicting_macs: Vec::new(),
+            ipv6_addresses: Vec::new(),
+            device_id: None,
+            last_seen: None,
         },
         
         // 15. Amazon Echo Dot
@@ -381,6 +467,12 @@ fn generate_demo_hosts() -> Vec<HostInfo> {
             vulnerabilities: vec![],
             port_warnings: vec![],
             security_grade: "B".to_string(),
+            filtered_ports: Vec::new(),
+            host_state: None,
+            conflicting_macs: Vec::new(),
+            ipv6_addresses: Vec::new(),
+            device_id: None,
+            last_seen: None,
         },
         
         // 16. Linux Workstation (HP)
@@ -403,6 +495,12 @@ fn generate_demo_hosts() -> Vec<HostInfo> {
             vulnerabilities: vec![],
             port_warnings: vec![],
             security_grade: "A".to_string(),
+            filtered_ports: Vec::new(),
+            host_state: None,
+            conflicting_macs: Vec::new(),
+            ipv6_addresses: Vec::new(),
+            device_id: None,
+            last_seen: None,
         },
     ]
 }
@@ -416,6 +514,7 @@ pub fn generate_demo_alerts() -> Vec<AlertRecord> {
             id: 1,
             created_at: now - chrono::Duration::hours(2),
             alert_type: AlertType::NewDevice,
+            alert_type_raw: AlertType::NewDevice.to_string(),
             device_id: Some(3),
             device_mac: Some("d2:81:c8:45:6b:71".to_string()),
             device_ip: Some("192.168.1.25".to_string()),
@@ -423,28 +522,42 @@ pub fn generate_demo_alerts() -> Vec<AlertRecord> {
             message: "New device detected: Galaxy-S23 (Private Device)".to_string(),
             severity: AlertSeverity::Info,
             is_read: false,
+            evidence: None,
+            network_id: None,
+            occurrences: 1,
+            last_seen_at: None,
         },
         AlertRecord {
             id: 2,
             created_at: now - chrono::Duration::hours(5),
             alert_type: AlertType::HighRisk,
+            alert_type_raw: AlertType::HighRisk.to_string(),
             device_id: Some(2),
             device_mac: Some("00:0c:29:5a:8f:1d".to_string()),
             device_ip: Some("192.168.1.10".to_string()),
             message: "High risk device: WIN-PC-001 has critical vulnerabilities (EternalBlue, BlueKeep)".to_string(),
             severity: AlertSeverity::Critical,
             is_read: false,
+            evidence: None,
+            network_id: None,
+            occurrences: 1,
+            last_seen_at: None,
         },
         AlertRecord {
             id: 3,
             created_at: now - chrono::Duration::days(1),
             alert_type: AlertType::PortChange,
+            alert_type_raw: AlertType::PortChange.to_string(),
             device_id: Some(5),
             device_mac: Some("44:19:b6:12:34:56".to_string()),
             device_ip: Some("192.168.1.70".to_string()),
             message: "New port detected on IP-Camera-Front: RTSP (554)".to_string(),
             severity: AlertSeverity::Warning,
             is_read: true,
+            evidence: None,
+            network_id: None,
+            occurrences: 1,
+            last_seen_at: None,
         },
     ]
 }
@@ -37,46 +37,220 @@ fn main() {
     
     tracing::info!("Monitoring state initialized");
 
+    // Announce this instance to peers on the LAN (if enabled)
+    match commands::start_peer_presence(&app_state) {
+        Ok(presence) => *monitor_state.peer_presence.blocking_lock() = presence,
+        Err(e) => eprintln!("[WARN] {}", e),
+    }
+
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
+        .plugin(tauri_plugin_notification::init())
         .manage(app_state)
         .manage(monitor_state)
+        .setup(|app| {
+            commands::start_scan_scheduler(app.handle());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             // Scanner commands
             commands::scan_network,
+            commands::run_arp_diagnostics,
             commands::get_interfaces,
+            commands::list_network_interfaces,
             // Database commands - History
             commands::get_scan_history,
+            commands::query_scans,
+            commands::set_scan_label,
+            commands::diff_scans,
+            commands::get_collapse_duplicate_scans,
+            commands::set_collapse_duplicate_scans,
+            commands::get_retention_policy,
+            commands::save_retention_policy,
+            commands::prune_database,
+            commands::backup_database,
+            commands::restore_database,
+            commands::run_db_maintenance,
+            commands::export_database_to_json,
+            commands::import_database_from_json,
+            commands::get_scan_daily_summaries,
             // Database commands - Devices
             commands::get_all_devices,
+            commands::query_devices,
+            commands::search_devices,
+            commands::get_networks,
+            commands::get_active_network,
+            commands::select_network,
+            commands::rename_network,
             commands::get_device_by_mac,
+            commands::update_oui_database,
+            commands::get_vendor_source_settings,
+            commands::save_vendor_source_settings,
+            commands::get_vendor_overrides,
+            commands::set_vendor_override,
+            commands::delete_vendor_override,
+            commands::get_device_type_rules,
+            commands::save_device_type_rule,
+            commands::delete_device_type_rule,
+            commands::import_device_type_rules,
+            commands::get_risk_profile,
+            commands::save_risk_profile,
+            commands::apply_risk_profile_preset,
             commands::update_device_name,
+            commands::get_device_ports,
+            commands::get_devices_with_port,
+            commands::get_device_port_changes,
+            commands::get_new_ports,
+            commands::get_topology_edges,
+            commands::get_finding_history,
+            commands::get_device_availability,
+            commands::get_device_latency,
+            commands::get_device_latency_percentiles,
+            commands::get_device_daily_summaries,
+            commands::add_device_tag,
+            commands::remove_device_tag,
+            commands::set_device_attribute,
+            commands::remove_device_attribute,
+            commands::get_attribute_names,
+            commands::get_devices_by_tag,
+            commands::get_device_tags,
+            commands::get_device_merges,
+            commands::merge_devices,
+            commands::get_device_settings,
+            commands::update_device_settings,
+            commands::reset_device_settings,
+            commands::set_expected_ports,
+            commands::set_device_watched,
+            commands::set_device_trusted,
+            commands::trust_all_devices,
+            commands::mute_alerts_for_device,
+            commands::get_untrusted_device_alerts,
+            commands::save_untrusted_device_alerts,
+            commands::get_certificates,
+            commands::get_ssh_host_keys,
+            commands::record_expected_ports,
+            commands::preview_device_purge,
+            commands::purge_device_data,
+            // Database commands - Smart groups
+            commands::get_smart_groups,
+            commands::create_smart_group,
+            commands::update_smart_group,
+            commands::delete_smart_group,
+            commands::get_devices_in_group,
+            commands::preview_device_filter,
+            commands::get_device_groups,
+            commands::create_device_group,
+            commands::update_device_group,
+            commands::delete_device_group,
+            commands::add_device_to_group,
+            commands::remove_device_from_group,
             // Database commands - Stats
             commands::get_network_stats,
             // Database commands - Alerts
             commands::get_unread_alerts,
+            commands::query_alerts,
+            commands::get_alert_history,
             commands::mark_alert_read,
             commands::mark_all_alerts_read,
             commands::clear_all_alerts,
+            commands::get_alert_rules,
+            commands::create_alert_rule,
+            commands::update_alert_rule,
+            commands::delete_alert_rule,
+            commands::get_notification_settings,
+            commands::save_notification_settings,
+            commands::test_webhook,
+            commands::test_email,
+            commands::test_chat_webhook,
+            commands::test_mqtt,
+            commands::test_syslog,
             // Monitoring commands
             commands::start_monitoring,
             commands::stop_monitoring,
+            commands::pause_monitoring,
+            commands::resume_monitoring,
+            commands::set_monitoring_interval,
+            commands::get_peer_settings,
+            commands::save_peer_settings,
+            commands::list_peers,
             commands::get_monitoring_status,
+            commands::get_monitoring_stats,
+            commands::get_recent_events,
+            commands::replay_events,
+            commands::get_monitor_schedule,
+            commands::save_monitor_schedule,
+            commands::get_internet_check_settings,
+            commands::save_internet_check_settings,
+            commands::get_internet_health,
+            commands::get_presence_sessions,
+            // Scheduled scan commands
+            commands::get_scan_schedules,
+            commands::create_scan_schedule,
+            commands::update_scan_schedule,
+            commands::delete_scan_schedule,
+            commands::start_sni_capture,
+            commands::stop_sni_capture,
+            commands::start_mdns_catalog,
+            commands::stop_mdns_catalog,
+            commands::get_ldap_config,
+            commands::save_ldap_config,
+            commands::run_ldap_enrichment,
+            commands::get_wan_settings,
+            commands::save_wan_settings,
+            commands::get_wan_info,
+            commands::get_dns_resolver,
+            commands::save_dns_resolver,
+            commands::get_dns_sweep_settings,
+            commands::save_dns_sweep_settings,
+            commands::run_dns_sweep,
+            commands::get_geoip_settings,
+            commands::save_geoip_settings,
+            commands::lookup_geoip,
+            commands::start_packet_capture,
+            commands::stop_packet_capture,
+            commands::start_arp_watch,
+            commands::stop_arp_watch,
+            commands::get_arp_thresholds,
+            commands::update_arp_thresholds,
             // AI Insights commands
+            commands::accept_device_risk,
+            commands::revoke_risk_acceptance,
+            commands::get_risk_acceptances,
             commands::get_network_health,
+            commands::simulate_network_changes,
             commands::get_device_distribution,
+            commands::get_cloud_service_inventory,
+            commands::get_stealth_hosts,
+            commands::set_network_baseline,
+            commands::get_network_baseline,
+            commands::clear_network_baseline,
+            commands::get_baseline_compliance,
             // Export commands
             commands::export_devices_to_csv,
             commands::export_scan_to_csv,
+            commands::get_port_exposure_report,
+            commands::export_port_exposure_to_csv,
+            commands::export_alerts_csv,
+            commands::export_change_journal,
             commands::export_topology_to_json,
             commands::export_scan_to_json,
             commands::export_scan_report,
+            commands::export_public_dashboard,
+            commands::get_dashboard_settings,
+            commands::save_dashboard_settings,
             commands::export_security_report,
+            commands::export_capture_pcap,
+            commands::get_export_public_key,
+            commands::sign_export,
+            commands::verify_export_signature,
             // Network Tools commands
             commands::ping_host,
             commands::scan_ports,
+            commands::get_tool_runs,
+            commands::delete_tool_run,
+            commands::export_tool_runs,
             commands::lookup_mac_vendor,
             // Demo Mode commands
             commands::mock_scan_network,
@@ -2,6 +2,7 @@ import { lazy, Suspense, useEffect, useMemo, useState, type ReactNode } from "re
 import { ThemeProvider, useTheme } from "./hooks/useTheme";
 import { ScanProvider, useScanContext, type HostInfo } from "./hooks/useScan";
 import { useKeyboardShortcuts, SHORTCUTS } from "./hooks/useKeyboardShortcuts";
+import { useDesktopNotifications } from "./hooks/useDesktopNotifications";
 import Sidebar from "./components/layout/Sidebar";
 import Titlebar from "./components/layout/Titlebar";
 import TopHeader from "./components/layout/TopHeader";
@@ -76,7 +77,7 @@ function AppContent() {
   );
   const [selectedDevice, setSelectedDevice] = useState<HostInfo | null>(null);
   const [unreadAlertsCount, setUnreadAlertsCount] = useState(0);
-  const { scan, stopScan, isScanning, scanStatus } = useScanContext();
+  const { scan, stopScan, isScanning, scanStatus, scanResult } = useScanContext();
   const { toggleTheme } = useTheme();
   const { shouldShow: showWelcome, markAsShown } = useWelcomeScreen();
 
@@ -112,6 +113,29 @@ function AppContent() {
     return () => window.removeEventListener("popstate", onPopState);
   }, []);
 
+  // Clicking a desktop notification opens its device
+  useDesktopNotifications(async (notice) => {
+    const mac = notice.device_mac;
+    if (!mac) return;
+    const scanned = scanResult?.active_hosts.find((host) => host.mac === mac);
+    if (scanned) {
+      setSelectedDevice(scanned);
+      return;
+    }
+    const stored = await tauriClient.getDeviceByMac(mac).catch(() => null);
+    setSelectedDevice({
+      ip: stored?.last_ip ?? notice.device_ip ?? "",
+      mac,
+      vendor: stored?.vendor,
+      hostname: stored?.hostname,
+      device_type: stored?.device_type ?? "UNKNOWN",
+      risk_score: 0,
+      open_ports: stored?.open_ports,
+      discovery_method: "monitor",
+      last_seen: stored?.last_seen,
+    });
+  });
+
   useKeyboardShortcuts([
     { ...SHORTCUTS.DASHBOARD, handler: () => handlePageChange("dashboard") },
     { ...SHORTCUTS.TOPOLOGY, handler: () => handlePageChange("topology") },
@@ -175,6 +199,7 @@ function AppContent() {
           currentPage={currentPage}
           isScanning={isScanning}
           scanStatus={scanStatus}
+          wan={scanResult?.wan}
           onStartScan={() => void scan()}
           onStopScan={stopScan}
           onNavigateToAlerts={() => handlePageChange("alerts")}
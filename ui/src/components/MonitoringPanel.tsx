@@ -168,6 +168,25 @@ export default function MonitoringPanel({ onScanComplete }: MonitoringPanelProps
         </div>
       </div>
 
+      {/* Interface and last cycle */}
+      {status.interface && (
+        <div className="mb-3 text-xs text-text-muted space-y-0.5">
+          <p>
+            {status.interface.name} · {status.interface.ip}
+            {status.interface.link_speed_mbps !== undefined &&
+              ` · ${status.interface.link_speed_mbps} Mbit/s`}
+            {status.interface.ssid && ` · ${status.interface.ssid}`}
+          </p>
+          {status.last_cycle_duration_ms !== undefined && (
+            <p>
+              Last cycle: {(status.last_cycle_duration_ms / 1000).toFixed(1)}s
+              {status.last_cycle_packets_sent !== undefined &&
+                `, ${status.last_cycle_packets_sent} packets sent`}
+            </p>
+          )}
+        </div>
+      )}
+
       {/* Events toggle */}
       <div className="flex items-center justify-between mb-2">
         <button
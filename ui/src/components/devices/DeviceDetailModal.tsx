@@ -234,6 +234,13 @@ export default function DeviceDetailModal({ device, onClose }: DeviceDetailModal
               {device.ttl && (
                 <InfoCard label="TTL" value={device.ttl.toString()} />
               )}
+              {persistedDevice?.availability?.last_7d != null && (
+                <InfoCard
+                  label="Availability (7d)"
+                  value={`${persistedDevice.availability.last_7d.toFixed(0)}%`}
+                  accent={persistedDevice.availability.last_7d < 90 ? '#F59E0B' : '#10B981'}
+                />
+              )}
               {device.os_guess && (
                 <InfoCard label="OS Detection" value={device.os_guess} span2 accent="#0EA5E9" />
               )}
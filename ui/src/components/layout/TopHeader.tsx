@@ -1,12 +1,14 @@
-import { Bell, Sun, Moon, Play, CircleStop, Loader2, Circle, CheckCircle } from 'lucide-react';
+import { Bell, Sun, Moon, Play, CircleStop, Loader2, Circle, CheckCircle, Globe } from 'lucide-react';
 import { motion } from 'framer-motion';
 import { ScanStatus } from '../../hooks/useScan';
 import { useTheme } from '../../hooks/useTheme';
+import type { WanInfo } from '../../lib/api/types';
 
 interface TopHeaderProps {
   currentPage?: string;
   isScanning?: boolean;
   scanStatus?: ScanStatus;
+  wan?: WanInfo;
   onStartScan?: () => void;
   onStopScan?: () => void;
   onNavigateToAlerts?: () => void;
@@ -69,10 +71,28 @@ function StatusPill({ scanStatus }: { scanStatus: ScanStatus }) {
   );
 }
 
+// External network the last scan was taken from
+function WanPill({ wan }: { wan: WanInfo }) {
+  const provider = [wan.isp, wan.asn].filter(Boolean).join(' · ');
+  const details = [wan.reverse_dns, provider].filter(Boolean).join('\n');
+
+  return (
+    <div
+      className="flex items-center gap-2 px-3 py-2 rounded-full bg-bg-hover text-text-secondary"
+      title={details || undefined}
+    >
+      <Globe className="w-4 h-4" />
+      <span className="text-sm font-mono">{wan.public_ip}</span>
+      {wan.isp && <span className="text-sm text-text-muted truncate max-w-[160px]">{wan.isp}</span>}
+    </div>
+  );
+}
+
 export default function TopHeader({
   currentPage = 'dashboard',
   isScanning = false,
   scanStatus = 'ready',
+  wan,
   onStartScan,
   onStopScan,
   onNavigateToAlerts,
@@ -146,6 +166,9 @@ export default function TopHeader({
 
       {/* Right: Status Pill + Actions */}
       <div className="flex items-center gap-3 shrink-0">
+        {/* Public IP of the scanned network */}
+        {wan && <WanPill wan={wan} />}
+
         {/* Status Indicator Pill */}
         <StatusPill scanStatus={scanStatus} />
 
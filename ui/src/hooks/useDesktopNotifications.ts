@@ -0,0 +1,50 @@
+/**
+ * useDesktopNotifications Hook
+ * Opens the device behind a desktop notification when the user clicks it
+ *
+ * Clicking an OS notification brings the window forward, so the device of
+ * the latest notification is opened if the window gains focus shortly after.
+ */
+
+import { useEffect, useRef } from 'react';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+import { isTauri } from '../lib/runtime/is-tauri';
+import type { DesktopNotice } from '../lib/api/types';
+
+/** How long after a notification a focus counts as clicking it */
+const CLICK_THROUGH_MS = 2 * 60 * 1000;
+
+export function useDesktopNotifications(onOpenDevice: (notice: DesktopNotice) => void) {
+  const pending = useRef<{ notice: DesktopNotice; at: number } | null>(null);
+  const onOpen = useRef(onOpenDevice);
+  onOpen.current = onOpenDevice;
+
+  useEffect(() => {
+    if (!isTauri()) {
+      return;
+    }
+
+    let unlisten: UnlistenFn | null = null;
+    listen<DesktopNotice>('desktop-notification', (event) => {
+      if (event.payload.device_mac) {
+        pending.current = { notice: event.payload, at: Date.now() };
+      }
+    }).then((fn) => {
+      unlisten = fn;
+    });
+
+    const onFocus = () => {
+      const latest = pending.current;
+      pending.current = null;
+      if (latest && Date.now() - latest.at < CLICK_THROUGH_MS) {
+        onOpen.current(latest.notice);
+      }
+    };
+    window.addEventListener('focus', onFocus);
+
+    return () => {
+      window.removeEventListener('focus', onFocus);
+      unlisten?.();
+    };
+  }, []);
+}
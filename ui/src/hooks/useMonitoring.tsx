@@ -191,6 +191,12 @@ export function useMonitoring(options: UseMonitoringOptions = {}) {
                 is_running: true,
                 interval_seconds: networkEvent.data.interval_seconds,
               };
+            } else if (networkEvent.type === "ProfileChanged") {
+              status = {
+                ...status,
+                active_profile: networkEvent.data.profile,
+                interval_seconds: networkEvent.data.interval_seconds,
+              };
             } else if (networkEvent.type === "MonitoringStopped") {
               status = { ...status, is_running: false };
             } else if (networkEvent.type === "ScanCompleted") {
@@ -272,6 +278,8 @@ export function getEventStyle(eventType: string): {
       return { icon: "📶", color: "text-green-500" };
     case "DeviceIpChanged":
       return { icon: "🔄", color: "text-orange-500" };
+    case "ProfileChanged":
+      return { icon: "🕘", color: "text-blue-500" };
     case "MonitoringError":
       return { icon: "❌", color: "text-red-500" };
     default:
@@ -302,6 +310,8 @@ export function formatEventMessage(event: NetworkEventType): string {
       return `Online: ${event.data.hostname || event.data.ip}`;
     case "DeviceIpChanged":
       return `IP changed: ${event.data.old_ip} → ${event.data.new_ip}`;
+    case "ProfileChanged":
+      return `Switched to ${event.data.profile} profile (${event.data.interval_seconds}s interval)`;
     case "MonitoringError":
       return `Error: ${event.data.message}`;
     default:
@@ -1,16 +1,100 @@
 import { invoke } from "@tauri-apps/api/core";
 import type {
   AlertRecord,
+  AlertRule,
+  AlertRuleSpec,
+  ChatSettings,
+  EmailSettings,
+  MqttSettings,
+  NotificationSettings,
+  SyslogSettings,
+  WebhookSettings,
+  ArpDiagnostics,
+  ArpThresholds,
+  CloudServiceInventory,
+  DeviceDetails,
+  DevicePurgePreview,
+  DevicePurgeSummary,
   DeviceRecord,
+  DeviceSettings,
+  ExportSignature,
   HostInfo,
   MonitoringStatus,
+  MonitoringStats,
+  StoredEvent,
+  MonitorSchedule,
+  InternetCheckSettings,
+  InternetHealth,
+  PresenceSession,
+  ScanSchedule,
+  ScanScheduleSpec,
   NetworkHealth,
+  PlannedChange,
+  SimulationResult,
   NetworkStats,
+  OuiUpdateSummary,
   PingResult,
   PortScanResult,
   ScanRecord,
+  HostSort,
+  Page,
+  DeviceQuery,
+  AlertQuery,
+  ScanQuery,
+  NetworkProfile,
+  ScanDiff,
   ScanResult,
+  SmartGroupRecord,
+  DeviceGroupRecord,
+  GroupPolicy,
   VendorLookupResult,
+  VendorOverrideRecord,
+  VendorSourceSettings,
+  DeviceTypeRule,
+  DeviceTypeRuleRecord,
+  RiskProfile,
+  RiskProfilePreset,
+  LdapConfig,
+  LdapEnrichmentSummary,
+  InterfaceCandidate,
+  PeerSettings,
+  PeerInfo,
+  UntrustedDeviceAlerts,
+  StoredCertificate,
+  StoredSshHostKey,
+  WanInfo,
+  WanLookupSettings,
+  StealthHost,
+  NetworkBaseline,
+  ComplianceReport,
+  GeoInfo,
+  GeoIpSettings,
+  DnsResolver,
+  DnsSweepSettings,
+  DnsSweepResult,
+  PortExposureQuery,
+  ChangeJournalQuery,
+  PortExposureRecord,
+  RiskAcceptance,
+  RiskFinding,
+  DashboardSettings,
+  DeviceMerge,
+  DevicePortRecord,
+  PortChangeRecord,
+  RetentionPolicy,
+  PruneSummary,
+  MaintenanceReport,
+  DeviceDailySummary,
+  ScanDailySummary,
+  TagCount,
+  AttributeCount,
+  TopologyEdgeRecord,
+  ToolRunRecord,
+  FindingHistoryRecord,
+  LatencyPoint,
+  LatencyPercentiles,
+  LatencyResolution,
+  AvailabilityWindow,
 } from "./types";
 import { isTauri } from "../runtime/is-tauri";
 
@@ -42,58 +126,425 @@ async function invokeCommand<T>(command: string, args?: InvokeArgs): Promise<T>
 
 export const tauriClient = {
   // Scanner
-  scanNetwork: () => invokeCommand<ScanResult>("scan_network"),
+  scanNetwork: (iface?: string, sort?: HostSort) =>
+    invokeCommand<ScanResult>("scan_network", { interface: iface, sort }),
   mockScanNetwork: () => invokeCommand<ScanResult>("mock_scan_network"),
+  runArpDiagnostics: (iface?: string) =>
+    invokeCommand<ArpDiagnostics>("run_arp_diagnostics", { interface: iface }),
   getInterfaces: () => invokeCommand<string[]>("get_interfaces"),
+  listNetworkInterfaces: () =>
+    invokeCommand<InterfaceCandidate[]>("list_network_interfaces"),
 
   // Database
-  getScanHistory: (limit = 20) =>
-    invokeCommand<ScanRecord[]>("get_scan_history", { limit }),
+  getScanHistory: (limit = 20, tag?: string) =>
+    invokeCommand<ScanRecord[]>("get_scan_history", { limit, tag }),
+  queryScans: (query: ScanQuery) =>
+    invokeCommand<Page<ScanRecord>>("query_scans", { query }),
+  setScanLabel: (scanId: number, label: string | null) =>
+    invokeCommand<void>("set_scan_label", { scanId, label }),
+  diffScans: (scanA: number, scanB: number) =>
+    invokeCommand<ScanDiff>("diff_scans", { scanA, scanB }),
+  getCollapseDuplicateScans: () =>
+    invokeCommand<boolean>("get_collapse_duplicate_scans"),
+  setCollapseDuplicateScans: (enabled: boolean) =>
+    invokeCommand<void>("set_collapse_duplicate_scans", { enabled }),
+  getRetentionPolicy: () => invokeCommand<RetentionPolicy>("get_retention_policy"),
+  saveRetentionPolicy: (policy: RetentionPolicy) =>
+    invokeCommand<void>("save_retention_policy", { policy }),
+  pruneDatabase: () => invokeCommand<PruneSummary>("prune_database"),
+  backupDatabase: (path: string) =>
+    invokeCommand<void>("backup_database", { path }),
+  restoreDatabase: (path: string) =>
+    invokeCommand<void>("restore_database", { path }),
+  runDbMaintenance: () => invokeCommand<MaintenanceReport>("run_db_maintenance"),
+  exportDatabaseToJson: (path: string) =>
+    invokeCommand<void>("export_database_to_json", { path }),
+  /** Rows imported per table */
+  importDatabaseFromJson: (path: string) =>
+    invokeCommand<Record<string, number>>("import_database_from_json", { path }),
+  getScanDailySummaries: () =>
+    invokeCommand<ScanDailySummary[]>("get_scan_daily_summaries"),
   getAllDevices: () => invokeCommand<DeviceRecord[]>("get_all_devices"),
+  queryDevices: (query: DeviceQuery) =>
+    invokeCommand<Page<DeviceRecord>>("query_devices", { query }),
+  searchDevices: (query: string) =>
+    invokeCommand<DeviceRecord[]>("search_devices", { query }),
+  getNetworks: () => invokeCommand<NetworkProfile[]>("get_networks"),
+  getActiveNetwork: () => invokeCommand<number | null>("get_active_network"),
+  /** Scope devices, scans and alerts to one network; null shows all */
+  selectNetwork: (networkId: number | null) =>
+    invokeCommand<void>("select_network", { networkId }),
+  renameNetwork: (networkId: number, name: string) =>
+    invokeCommand<boolean>("rename_network", { networkId, name }),
   getDeviceByMac: (mac: string) =>
-    invokeCommand<DeviceRecord | null>("get_device_by_mac", { mac }),
+    invokeCommand<DeviceDetails | null>("get_device_by_mac", { mac }),
+  updateOuiDatabase: () => invokeCommand<OuiUpdateSummary>("update_oui_database"),
+  getVendorSourceSettings: () =>
+    invokeCommand<VendorSourceSettings>("get_vendor_source_settings"),
+  saveVendorSourceSettings: (settings: VendorSourceSettings) =>
+    invokeCommand<void>("save_vendor_source_settings", { settings }),
+  getVendorOverrides: () =>
+    invokeCommand<VendorOverrideRecord[]>("get_vendor_overrides"),
+  setVendorOverride: (pattern: string, vendor: string) =>
+    invokeCommand<VendorOverrideRecord[]>("set_vendor_override", {
+      pattern,
+      vendor,
+    }),
+  deleteVendorOverride: (pattern: string) =>
+    invokeCommand<boolean>("delete_vendor_override", { pattern }),
+  getDeviceTypeRules: () =>
+    invokeCommand<DeviceTypeRuleRecord[]>("get_device_type_rules"),
+  saveDeviceTypeRule: (rule: DeviceTypeRule, id?: number) =>
+    invokeCommand<number>("save_device_type_rule", { id, rule }),
+  deleteDeviceTypeRule: (id: number) =>
+    invokeCommand<boolean>("delete_device_type_rule", { id }),
+  importDeviceTypeRules: (json: string) =>
+    invokeCommand<number>("import_device_type_rules", { json }),
+  getRiskProfile: () => invokeCommand<RiskProfile>("get_risk_profile"),
+  saveRiskProfile: (profile: RiskProfile) =>
+    invokeCommand<void>("save_risk_profile", { profile }),
+  applyRiskProfilePreset: (preset: RiskProfilePreset) =>
+    invokeCommand<RiskProfile>("apply_risk_profile_preset", { preset }),
   updateDeviceName: (mac: string, name: string) =>
     invokeCommand<void>("update_device_name", { mac, name }),
+  getDevicePorts: (mac: string) =>
+    invokeCommand<DevicePortRecord[]>("get_device_ports", { mac }),
+  getDevicesWithPort: (port: number, protocol?: string) =>
+    invokeCommand<DevicePortRecord[]>("get_devices_with_port", { port, protocol }),
+  getDevicePortChanges: (mac: string) =>
+    invokeCommand<PortChangeRecord[]>("get_device_port_changes", { mac }),
+  getNewPorts: (days: number) =>
+    invokeCommand<DevicePortRecord[]>("get_new_ports", { days }),
+  getDeviceAvailability: (mac: string) =>
+    invokeCommand<AvailabilityWindow[]>("get_device_availability", { mac }),
+  getDeviceLatency: (
+    mac: string,
+    since?: string,
+    until?: string,
+    resolution?: LatencyResolution,
+  ) =>
+    invokeCommand<LatencyPoint[]>("get_device_latency", {
+      mac,
+      since,
+      until,
+      resolution,
+    }),
+  getDeviceLatencyPercentiles: (mac: string, since?: string, until?: string) =>
+    invokeCommand<LatencyPercentiles | null>("get_device_latency_percentiles", {
+      mac,
+      since,
+      until,
+    }),
+  getFindingHistory: (mac?: string) =>
+    invokeCommand<FindingHistoryRecord[]>("get_finding_history", { mac }),
+  getTopologyEdges: (since?: string) =>
+    invokeCommand<TopologyEdgeRecord[]>("get_topology_edges", { since }),
+  getDeviceDailySummaries: (mac: string) =>
+    invokeCommand<DeviceDailySummary[]>("get_device_daily_summaries", { mac }),
+  addDeviceTag: (mac: string, tag: string) =>
+    invokeCommand<void>("add_device_tag", { mac, tag }),
+  removeDeviceTag: (mac: string, tag: string) =>
+    invokeCommand<boolean>("remove_device_tag", { mac, tag }),
+  getDevicesByTag: (tag: string) =>
+    invokeCommand<DeviceRecord[]>("get_devices_by_tag", { tag }),
+  getDeviceTags: () => invokeCommand<TagCount[]>("get_device_tags"),
+  setDeviceAttribute: (mac: string, name: string, value: string) =>
+    invokeCommand<void>("set_device_attribute", { mac, name, value }),
+  removeDeviceAttribute: (mac: string, name: string) =>
+    invokeCommand<boolean>("remove_device_attribute", { mac, name }),
+  getAttributeNames: () =>
+    invokeCommand<AttributeCount[]>("get_attribute_names"),
+  getDeviceMerges: (mac: string) =>
+    invokeCommand<DeviceMerge[]>("get_device_merges", { mac }),
+  mergeDevices: (canonicalMac: string, duplicateMac: string) =>
+    invokeCommand<void>("merge_devices", { canonicalMac, duplicateMac }),
+  getDeviceSettings: (mac: string) =>
+    invokeCommand<DeviceSettings | null>("get_device_settings", { mac }),
+  updateDeviceSettings: (settings: DeviceSettings) =>
+    invokeCommand<void>("update_device_settings", { settings }),
+  resetDeviceSettings: (mac: string) =>
+    invokeCommand<void>("reset_device_settings", { mac }),
+  setExpectedPorts: (mac: string, ports: number[] | null) =>
+    invokeCommand<void>("set_expected_ports", { mac, ports }),
+  setDeviceWatched: (mac: string, watched: boolean) =>
+    invokeCommand<void>("set_device_watched", { mac, watched }),
+  setDeviceTrusted: (mac: string, trusted: boolean) =>
+    invokeCommand<void>("set_device_trusted", { mac, trusted }),
+  trustAllDevices: () => invokeCommand<number>("trust_all_devices"),
+  /** `until` is an ISO 8601 time; null lifts the mute */
+  muteAlertsForDevice: (mac: string, until: string | null) =>
+    invokeCommand<void>("mute_alerts_for_device", { mac, until }),
+  getUntrustedDeviceAlerts: () =>
+    invokeCommand<UntrustedDeviceAlerts>("get_untrusted_device_alerts"),
+  saveUntrustedDeviceAlerts: (mode: UntrustedDeviceAlerts) =>
+    invokeCommand<void>("save_untrusted_device_alerts", { mode }),
+  getCertificates: () => invokeCommand<StoredCertificate[]>("get_certificates"),
+  getSshHostKeys: () => invokeCommand<StoredSshHostKey[]>("get_ssh_host_keys"),
+  recordExpectedPorts: (mac: string) =>
+    invokeCommand<number[]>("record_expected_ports", { mac }),
+  previewDevicePurge: (mac: string) =>
+    invokeCommand<DevicePurgePreview>("preview_device_purge", { mac }),
+  purgeDeviceData: (mac: string, confirmationToken: string) =>
+    invokeCommand<DevicePurgeSummary>("purge_device_data", {
+      mac,
+      confirmationToken,
+    }),
+  getSmartGroups: () => invokeCommand<SmartGroupRecord[]>("get_smart_groups"),
+  createSmartGroup: (name: string, expression: string, description?: string) =>
+    invokeCommand<number>("create_smart_group", { name, expression, description }),
+  updateSmartGroup: (
+    id: number,
+    name: string,
+    expression: string,
+    description?: string,
+  ) =>
+    invokeCommand<void>("update_smart_group", {
+      id,
+      name,
+      expression,
+      description,
+    }),
+  deleteSmartGroup: (id: number) =>
+    invokeCommand<void>("delete_smart_group", { id }),
+  getDevicesInGroup: (groupId: number) =>
+    invokeCommand<DeviceRecord[]>("get_devices_in_group", { groupId }),
+  previewDeviceFilter: (expression: string) =>
+    invokeCommand<DeviceRecord[]>("preview_device_filter", { expression }),
+  getDeviceGroups: () => invokeCommand<DeviceGroupRecord[]>("get_device_groups"),
+  createDeviceGroup: (name: string, policy: GroupPolicy, description?: string) =>
+    invokeCommand<number>("create_device_group", { name, description, policy }),
+  updateDeviceGroup: (
+    id: number,
+    name: string,
+    policy: GroupPolicy,
+    description?: string,
+  ) =>
+    invokeCommand<void>("update_device_group", {
+      id,
+      name,
+      description,
+      policy,
+    }),
+  deleteDeviceGroup: (id: number) =>
+    invokeCommand<void>("delete_device_group", { id }),
+  addDeviceToGroup: (groupId: number, mac: string) =>
+    invokeCommand<void>("add_device_to_group", { groupId, mac }),
+  removeDeviceFromGroup: (groupId: number, mac: string) =>
+    invokeCommand<boolean>("remove_device_from_group", { groupId, mac }),
   getNetworkStats: () => invokeCommand<NetworkStats>("get_network_stats"),
-  getUnreadAlerts: () => invokeCommand<AlertRecord[]>("get_unread_alerts"),
+  getUnreadAlerts: (groupId?: number, tag?: string) =>
+    invokeCommand<AlertRecord[]>("get_unread_alerts", { groupId, tag }),
+  queryAlerts: (query: AlertQuery) =>
+    invokeCommand<Page<AlertRecord>>("query_alerts", { query }),
+  getAlertHistory: (query: AlertQuery = {}) =>
+    invokeCommand<Page<AlertRecord>>("get_alert_history", { query }),
   markAlertRead: (alertId: number) =>
     invokeCommand<void>("mark_alert_read", { alertId }),
   markAllAlertsRead: () => invokeCommand<void>("mark_all_alerts_read"),
   clearAllAlerts: () => invokeCommand<void>("clear_all_alerts"),
+  getAlertRules: () => invokeCommand<AlertRule[]>("get_alert_rules"),
+  createAlertRule: (rule: AlertRuleSpec) =>
+    invokeCommand<AlertRule>("create_alert_rule", { rule }),
+  updateAlertRule: (id: number, rule: AlertRuleSpec) =>
+    invokeCommand<AlertRule>("update_alert_rule", { id, rule }),
+  deleteAlertRule: (id: number) =>
+    invokeCommand<void>("delete_alert_rule", { id }),
+  getNotificationSettings: () =>
+    invokeCommand<NotificationSettings>("get_notification_settings"),
+  saveNotificationSettings: (settings: NotificationSettings) =>
+    invokeCommand<void>("save_notification_settings", { settings }),
+  testWebhook: (webhook: WebhookSettings) =>
+    invokeCommand<void>("test_webhook", { webhook }),
+  testEmail: (email: EmailSettings) =>
+    invokeCommand<void>("test_email", { email }),
+  testChatWebhook: (chat: ChatSettings) =>
+    invokeCommand<void>("test_chat_webhook", { chat }),
+  testMqtt: (mqtt: MqttSettings) =>
+    invokeCommand<void>("test_mqtt", { mqtt }),
+  testSyslog: (syslog: SyslogSettings) =>
+    invokeCommand<void>("test_syslog", { syslog }),
   getDatabasePath: () => invokeCommand<string>("get_database_path"),
 
   // Monitoring
-  startMonitoring: (intervalSeconds?: number) =>
-    invokeCommand<void>("start_monitoring", { intervalSeconds }),
+  startMonitoring: (
+    intervalSeconds?: number,
+    iface?: string,
+    passive?: boolean,
+    fullScanEvery?: number,
+    targets?: string[],
+  ) =>
+    invokeCommand<void>("start_monitoring", {
+      intervalSeconds,
+      interface: iface,
+      passive,
+      fullScanEvery,
+      targets,
+    }),
   stopMonitoring: () => invokeCommand<void>("stop_monitoring"),
+  pauseMonitoring: () => invokeCommand<void>("pause_monitoring"),
+  resumeMonitoring: () => invokeCommand<void>("resume_monitoring"),
+  setMonitoringInterval: (intervalSeconds: number) =>
+    invokeCommand<number>("set_monitoring_interval", { intervalSeconds }),
+  getPeerSettings: () => invokeCommand<PeerSettings>("get_peer_settings"),
+  savePeerSettings: (settings: PeerSettings) =>
+    invokeCommand<void>("save_peer_settings", { settings }),
+  listPeers: () => invokeCommand<PeerInfo[]>("list_peers"),
   getMonitoringStatus: () =>
     invokeCommand<MonitoringStatus>("get_monitoring_status"),
+  getMonitoringStats: () =>
+    invokeCommand<MonitoringStats>("get_monitoring_stats"),
+  getRecentEvents: (limit?: number, since?: string) =>
+    invokeCommand<StoredEvent[]>("get_recent_events", { limit, since }),
+  replayEvents: (afterId: number, limit?: number) =>
+    invokeCommand<StoredEvent[]>("replay_events", { afterId, limit }),
+  getMonitorSchedule: () =>
+    invokeCommand<MonitorSchedule>("get_monitor_schedule"),
+  getInternetCheckSettings: () =>
+    invokeCommand<InternetCheckSettings>("get_internet_check_settings"),
+  saveInternetCheckSettings: (settings: InternetCheckSettings) =>
+    invokeCommand<void>("save_internet_check_settings", { settings }),
+  getInternetHealth: (since?: string, limit?: number) =>
+    invokeCommand<InternetHealth[]>("get_internet_health", { since, limit }),
+  getPresenceSessions: (mac?: string, since?: string, until?: string) =>
+    invokeCommand<PresenceSession[]>("get_presence_sessions", {
+      mac,
+      since,
+      until,
+    }),
+  saveMonitorSchedule: (schedule: MonitorSchedule) =>
+    invokeCommand<void>("save_monitor_schedule", { schedule }),
+  getScanSchedules: () => invokeCommand<ScanSchedule[]>("get_scan_schedules"),
+  createScanSchedule: (schedule: ScanScheduleSpec) =>
+    invokeCommand<ScanSchedule>("create_scan_schedule", { schedule }),
+  updateScanSchedule: (id: number, schedule: ScanScheduleSpec) =>
+    invokeCommand<ScanSchedule>("update_scan_schedule", { id, schedule }),
+  deleteScanSchedule: (id: number) =>
+    invokeCommand<void>("delete_scan_schedule", { id }),
+  startSniCapture: () => invokeCommand<void>("start_sni_capture"),
+  stopSniCapture: () => invokeCommand<void>("stop_sni_capture"),
+  startMdnsCatalog: () => invokeCommand<void>("start_mdns_catalog"),
+  stopMdnsCatalog: () => invokeCommand<void>("stop_mdns_catalog"),
+  getLdapConfig: () => invokeCommand<LdapConfig | null>("get_ldap_config"),
+  saveLdapConfig: (config: LdapConfig) =>
+    invokeCommand<void>("save_ldap_config", { config }),
+  runLdapEnrichment: () =>
+    invokeCommand<LdapEnrichmentSummary>("run_ldap_enrichment"),
+  getWanSettings: () => invokeCommand<WanLookupSettings>("get_wan_settings"),
+  saveWanSettings: (settings: WanLookupSettings) =>
+    invokeCommand<void>("save_wan_settings", { settings }),
+  getWanInfo: () => invokeCommand<WanInfo>("get_wan_info"),
+  getDnsResolver: () => invokeCommand<DnsResolver>("get_dns_resolver"),
+  saveDnsResolver: (resolver: DnsResolver) =>
+    invokeCommand<void>("save_dns_resolver", { resolver }),
+  getDnsSweepSettings: () =>
+    invokeCommand<DnsSweepSettings>("get_dns_sweep_settings"),
+  saveDnsSweepSettings: (settings: DnsSweepSettings) =>
+    invokeCommand<void>("save_dns_sweep_settings", { settings }),
+  runDnsSweep: () => invokeCommand<DnsSweepResult>("run_dns_sweep"),
+  getGeoIpSettings: () => invokeCommand<GeoIpSettings>("get_geoip_settings"),
+  saveGeoIpSettings: (settings: GeoIpSettings) =>
+    invokeCommand<void>("save_geoip_settings", { settings }),
+  lookupGeoIp: (ip: string) =>
+    invokeCommand<GeoInfo | null>("lookup_geoip", { ip }),
+  startPacketCapture: (bufferMb?: number) =>
+    invokeCommand<void>("start_packet_capture", { bufferMb }),
+  stopPacketCapture: () => invokeCommand<void>("stop_packet_capture"),
+  startArpWatch: () => invokeCommand<void>("start_arp_watch"),
+  stopArpWatch: () => invokeCommand<void>("stop_arp_watch"),
+  getArpThresholds: () => invokeCommand<ArpThresholds>("get_arp_thresholds"),
+  updateArpThresholds: (thresholds: ArpThresholds) =>
+    invokeCommand<void>("update_arp_thresholds", { thresholds }),
 
   // Insights
+  acceptDeviceRisk: (
+    mac: string,
+    finding: RiskFinding,
+    reason: string,
+    expiresAt: string,
+    acceptedBy?: string,
+  ) =>
+    invokeCommand<number>("accept_device_risk", {
+      mac,
+      finding,
+      reason,
+      acceptedBy,
+      expiresAt,
+    }),
+  revokeRiskAcceptance: (id: number) =>
+    invokeCommand<boolean>("revoke_risk_acceptance", { id }),
+  getRiskAcceptances: () =>
+    invokeCommand<RiskAcceptance[]>("get_risk_acceptances"),
   getNetworkHealth: () => invokeCommand<NetworkHealth>("get_network_health"),
+  simulateNetworkChanges: (changes: PlannedChange[]) =>
+    invokeCommand<SimulationResult>("simulate_network_changes", { changes }),
   getDeviceDistribution: () =>
     invokeCommand<Record<string, unknown>>("get_device_distribution"),
+  getCloudServiceInventory: () =>
+    invokeCommand<CloudServiceInventory>("get_cloud_service_inventory"),
+  getStealthHosts: (minScans?: number) =>
+    invokeCommand<StealthHost[]>("get_stealth_hosts", { minScans }),
+  setNetworkBaseline: () =>
+    invokeCommand<NetworkBaseline>("set_network_baseline"),
+  getNetworkBaseline: () =>
+    invokeCommand<NetworkBaseline | null>("get_network_baseline"),
+  clearNetworkBaseline: () => invokeCommand<boolean>("clear_network_baseline"),
+  getBaselineCompliance: () =>
+    invokeCommand<ComplianceReport | null>("get_baseline_compliance"),
   getScanResultSchema: () =>
     invokeCommand<Record<string, unknown>>("get_scan_result_schema"),
 
   // Exports
-  exportDevicesToCsv: () => invokeCommand<string>("export_devices_to_csv"),
+  exportDevicesToCsv: (groupId?: number, tag?: string) =>
+    invokeCommand<string>("export_devices_to_csv", { groupId, tag }),
   exportScanToCsv: (hosts: HostInfo[]) =>
     invokeCommand<string>("export_scan_to_csv", { hosts }),
+  getPortExposureReport: (options: PortExposureQuery = {}) =>
+    invokeCommand<PortExposureRecord[]>("get_port_exposure_report", { ...options }),
+  exportPortExposureToCsv: (options: PortExposureQuery = {}) =>
+    invokeCommand<string>("export_port_exposure_to_csv", { ...options }),
+  exportAlertsCsv: (query: AlertQuery = {}) =>
+    invokeCommand<string>("export_alerts_csv", { query }),
+  exportChangeJournal: (options: ChangeJournalQuery = {}) =>
+    invokeCommand<string>("export_change_journal", { ...options }),
   exportTopologyToJson: (hosts: HostInfo[], network: string) =>
     invokeCommand<string>("export_topology_to_json", { hosts, network }),
   exportScanToJson: (scan: ScanResult) =>
     invokeCommand<string>("export_scan_to_json", { scan }),
   exportScanReport: (scan: ScanResult, hosts: HostInfo[]) =>
     invokeCommand<number[]>("export_scan_report", { scan, hosts }),
+  exportPublicDashboard: () => invokeCommand<string>("export_public_dashboard"),
+  getDashboardSettings: () =>
+    invokeCommand<DashboardSettings>("get_dashboard_settings"),
+  saveDashboardSettings: (settings: DashboardSettings) =>
+    invokeCommand<void>("save_dashboard_settings", { settings }),
   exportSecurityReport: (hosts: HostInfo[]) =>
     invokeCommand<number[]>("export_security_report", { hosts }),
+  exportCapturePcap: (minutes?: number) =>
+    invokeCommand<number[]>("export_capture_pcap", { minutes }),
+  getExportPublicKey: () => invokeCommand<string>("get_export_public_key"),
+  signExport: (data: number[]) =>
+    invokeCommand<ExportSignature>("sign_export", { data }),
+  verifyExportSignature: (
+    data: number[],
+    signature: ExportSignature,
+    publicKey?: string,
+  ) =>
+    invokeCommand<boolean>("verify_export_signature", {
+      data,
+      signature,
+      publicKey,
+    }),
 
   // Tools
-  pingHost: (target: string, count: number) =>
-    invokeCommand<PingResult[]>("ping_host", { target, count }),
-  scanPorts: (target: string, ports: number[]) =>
-    invokeCommand<PortScanResult[]>("scan_ports", { target, ports }),
+  pingHost: (target: string, count: number, save?: boolean) =>
+    invokeCommand<PingResult[]>("ping_host", { target, count, save }),
+  scanPorts: (target: string, ports: number[], save?: boolean) =>
+    invokeCommand<PortScanResult[]>("scan_ports", { target, ports, save }),
+  getToolRuns: (tool?: string, target?: string, limit?: number) =>
+    invokeCommand<ToolRunRecord[]>("get_tool_runs", { tool, target, limit }),
+  deleteToolRun: (id: number) => invokeCommand<boolean>("delete_tool_run", { id }),
+  exportToolRuns: (tool?: string, target?: string) =>
+    invokeCommand<string>("export_tool_runs", { tool, target }),
   lookupMacVendor: (mac: string) =>
     invokeCommand<VendorLookupResult>("lookup_mac_vendor", { mac }),
 
@@ -13,6 +13,8 @@ export interface PortWarning {
   recommendation?: string;
 }
 
+export type HostState = "UP" | "DOWN" | "FILTERED";
+
 export interface HostInfo {
   ip: string;
   mac: string;
@@ -24,6 +26,15 @@ export interface HostInfo {
   device_type: string;
   risk_score: number;
   open_ports?: number[];
+  /** Ports that timed out or were rejected by a firewall */
+  filtered_ports?: number[];
+  host_state?: HostState;
+  /** Other MACs that answered for this IP in the same scan (address conflict) */
+  conflicting_macs?: string[];
+  /** IPv6 addresses seen for this MAC via NDP (link-local and global) */
+  ipv6_addresses?: string[];
+  /** Stored device this host belongs to (following MAC merges), once known */
+  device_id?: number;
   discovery_method: string;
   hostname?: string;
   system_description?: string;
@@ -34,6 +45,9 @@ export interface HostInfo {
   last_seen?: string;
 }
 
+/** Order for the hosts of a scan */
+export type HostSort = "ip" | "risk" | "last_seen" | "hostname";
+
 export interface ScanResult {
   interface_name: string;
   local_ip: string;
@@ -45,6 +59,222 @@ export interface ScanResult {
   total_hosts: number;
   scan_duration_ms: number;
   active_hosts: HostInfo[];
+  /** External network the scan was taken from */
+  wan?: WanInfo;
+  /** Wi-Fi network the scanning interface was associated with */
+  ssid?: string;
+}
+
+export interface ArpPacketRecord {
+  /** Microseconds since the diagnostics run started */
+  offset_us: number;
+  kind: "request" | "reply";
+  sender_ip: string;
+  sender_mac: string;
+  target_ip: string;
+}
+
+export interface LatencySummary {
+  samples: number;
+  min_ms: number;
+  p50_ms: number;
+  p90_ms: number;
+  p99_ms: number;
+  max_ms: number;
+  /** Buckets by upper bound; the last (null) is open-ended */
+  histogram: { upper_ms: number | null; count: number }[];
+}
+
+export interface ArpDiagnostics {
+  duration_ms: number;
+  targets: number;
+  requests_sent: number;
+  replies_received: number;
+  hosts_found: number;
+  unanswered: string[];
+  duplicate_replies: number;
+  unsolicited_replies: number;
+  latency: LatencySummary;
+  packets: ArpPacketRecord[];
+}
+
+export interface WanInfo {
+  public_ip: string;
+  reverse_dns?: string;
+  isp?: string;
+  asn?: string;
+}
+
+/** A sensitive port observed open on a device during a period */
+export interface PortExposureRecord {
+  subnet: string;
+  mac: string;
+  display_name: string | null;
+  vendor: string | null;
+  port: number;
+  last_ip: string;
+  first_observed: string;
+  last_observed: string;
+  /** Number of scans that found the port open */
+  scan_count: number;
+}
+
+export interface DevicePortRecord {
+  device_mac: string;
+  port: number;
+  /** "tcp" for ports found by the TCP probe */
+  protocol: string;
+  service?: string;
+  banner?: string;
+  first_seen: string;
+  last_seen: string;
+  /** Open in the device's most recent scan */
+  is_open: boolean;
+}
+
+/** A port opening or closing between two scans of a device */
+export interface PortChangeRecord {
+  device_mac: string;
+  port: number;
+  protocol: string;
+  /** false when the port closed */
+  opened: boolean;
+  /** Null once the scan is pruned */
+  scan_id: number | null;
+  changed_at: string;
+}
+
+/** Omitted/null limits keep that kind of data forever */
+export interface RetentionPolicy {
+  /** Days of scans and per-scan device history kept in full */
+  history_days?: number | null;
+  /** Most recent scans kept in full */
+  max_scans?: number | null;
+  /** Days read alerts are kept (unread alerts are never pruned) */
+  alert_days?: number | null;
+}
+
+export interface PruneSummary {
+  scans: number;
+  history_rows: number;
+  alerts: number;
+  summary_days: number;
+  /** Stored monitor events, kept as long as scan history */
+  events: number;
+  /** Internet health checks, kept as long as scan history */
+  internet_checks: number;
+  /** Ended presence sessions, kept as long as scan history */
+  presence_sessions: number;
+}
+
+export interface MaintenanceReport {
+  /** Problems found by the integrity check; empty when healthy */
+  integrity_errors: string[];
+  /** False when VACUUM was skipped because the integrity check failed */
+  vacuumed: boolean;
+  size_before_bytes: number;
+  size_after_bytes: number;
+  duration_ms: number;
+}
+
+export interface DeviceDailySummary {
+  device_mac: string;
+  /** YYYY-MM-DD (UTC) */
+  day: string;
+  sightings: number;
+  avg_response_ms: number | null;
+  min_response_ms: number | null;
+  max_response_ms: number | null;
+  max_risk_score: number;
+  last_ip: string | null;
+}
+
+export interface ScanDailySummary {
+  /** YYYY-MM-DD (UTC) */
+  day: string;
+  scans: number;
+  avg_hosts: number;
+  max_hosts: number;
+  avg_duration_ms: number;
+}
+
+export interface PortExposureQuery {
+  /** Limit to one network (all networks if omitted) */
+  subnet?: string;
+  /** RFC 3339 timestamps bounding the period */
+  since?: string;
+  until?: string;
+  /** Ports to report (Telnet, SMB, RDP and VNC if omitted) */
+  ports?: number[];
+}
+
+export interface ChangeJournalQuery {
+  /** RFC 3339 timestamps bounding the period */
+  since?: string;
+  until?: string;
+  /** Output format (Markdown if omitted) */
+  format?: "markdown" | "csv";
+}
+
+/** Where the read-only HTML dashboard is published after each scan */
+export interface DashboardSettings {
+  enabled: boolean;
+  /** Output file, e.g. in a web server's document root */
+  path: string | null;
+}
+
+/** Backend for reverse DNS lookups */
+export type DnsResolver =
+  | { kind: "system" }
+  | { kind: "server"; address: string }
+  | { kind: "doh"; url: string };
+
+export interface DnsSweepSettings {
+  /** Run the forward sweep as part of every scan */
+  enabled: boolean;
+  /** Domain to sweep (system search domain if unset) */
+  domain?: string | null;
+  /** DNS server to query (system nameserver if unset) */
+  server?: string | null;
+  /** Try a zone transfer (AXFR) before falling back to common names */
+  zone_transfer: boolean;
+  extra_names: string[];
+}
+
+export interface ForwardRecord {
+  name: string;
+  ip: string;
+}
+
+export interface DnsSweepResult {
+  domain: string;
+  server: string;
+  /** Whether the server allowed a zone transfer */
+  zone_transfer: boolean;
+  records: ForwardRecord[];
+}
+
+/** Country / ASN of an external address (from local GeoIP databases) */
+export interface GeoInfo {
+  country_code?: string;
+  country?: string;
+  city?: string;
+  asn?: string;
+  as_org?: string;
+}
+
+export interface GeoIpSettings {
+  /** Path to a MaxMind Country or City database (.mmdb) */
+  country_db?: string | null;
+  /** Path to a MaxMind ASN database (.mmdb) */
+  asn_db?: string | null;
+}
+
+export interface WanLookupSettings {
+  enabled: boolean;
+  ip_endpoint: string;
+  /** JSON ISP/ASN lookup; `{ip}` is replaced with the public IP */
+  isp_endpoint?: string | null;
 }
 
 export interface ScanRecord {
@@ -59,6 +289,107 @@ export interface ScanRecord {
   icmp_discovered: number;
   total_hosts: number;
   duration_ms: number;
+  duplicate_of?: number;
+  duplicate_count: number;
+  /** User note, e.g. "after router firmware update" */
+  label?: string | null;
+  /** Network profile the scan was taken on */
+  network_id?: number | null;
+}
+
+/** A network the app has scanned, told apart by subnet plus gateway MAC and/or SSID */
+export interface NetworkProfile {
+  id: number;
+  name: string;
+  subnet: string;
+  gateway_mac?: string | null;
+  ssid?: string | null;
+  created_at: string;
+  last_seen: string;
+}
+
+/** One page of a listing; `total` counts matches across all pages */
+export interface Page<T> {
+  items: T[];
+  total: number;
+}
+
+export type DeviceSort =
+  | "last_seen"
+  | "first_seen"
+  | "name"
+  | "vendor"
+  | "device_type";
+
+export interface DeviceQuery {
+  /** Page size; omitted returns all matches */
+  limit?: number;
+  offset?: number;
+  sort?: DeviceSort;
+  descending?: boolean;
+  device_type?: string;
+  /** Seen (or missing) in the latest scan */
+  online?: boolean;
+  seen_since?: string;
+  seen_until?: string;
+  /** Defaults to the selected network profile */
+  network_id?: number;
+}
+
+export interface AlertQuery {
+  limit?: number;
+  offset?: number;
+  oldest_first?: boolean;
+  severity?: "info" | "warning" | "error" | "critical";
+  unread_only?: boolean;
+  since?: string;
+  until?: string;
+  /** Defaults to the selected network profile */
+  network_id?: number;
+  /** Only alerts about this device (MAC, any case) */
+  device_mac?: string;
+}
+
+export interface ScanQuery {
+  limit?: number;
+  offset?: number;
+  oldest_first?: boolean;
+  since?: string;
+  until?: string;
+  /** Defaults to the selected network profile */
+  network_id?: number;
+}
+
+export interface DiffHost {
+  mac: string;
+  ip: string;
+  hostname?: string;
+}
+
+export interface IpChange {
+  mac: string;
+  old_ip: string;
+  new_ip: string;
+  hostname?: string;
+}
+
+export interface PortChange {
+  mac: string;
+  /** Address in the newer scan */
+  ip: string;
+  hostname?: string;
+  opened: number[];
+  closed: number[];
+}
+
+/** Changes between two scans, matched by MAC address */
+export interface ScanDiff {
+  /** In the newer scan only */
+  appeared: DiffHost[];
+  /** In the older scan only */
+  disappeared: DiffHost[];
+  ip_changes: IpChange[];
+  port_changes: PortChange[];
 }
 
 export interface DeviceRecord {
@@ -73,18 +404,442 @@ export interface DeviceRecord {
   os_guess?: string;
   custom_name?: string;
   notes?: string;
+  /** User tags such as "kids", "work" or "critical" */
+  tags: string[];
+  /** User-defined fields such as asset tag, owner or location, by name */
+  attributes?: Record<string, string>;
+  /** Percent of scans that found the device; null without scans in the window */
+  availability?: DeviceAvailability;
+  /** Open ports seen by the latest scan that found the device */
+  open_ports?: number[];
+  /** Network profile the device was last seen on */
+  network_id?: number | null;
+}
+
+export interface DeviceAvailability {
+  last_24h: number | null;
+  last_7d: number | null;
+  last_30d: number | null;
+}
+
+export interface AvailabilityWindow {
+  hours: number;
+  /** Scans of the device's networks since it was first seen */
+  scans: number;
+  seen: number;
+  percent: number | null;
+}
+
+export type LatencyResolution = "raw" | "hourly" | "daily";
+
+export interface LatencyPoint {
+  /** Bucket start, or scan time for raw points */
+  time: string;
+  samples: number;
+  avg_ms: number;
+  min_ms: number;
+  max_ms: number;
+  p50_ms: number;
+  p95_ms: number;
+}
+
+export interface LatencyPercentiles {
+  samples: number;
+  avg_ms: number;
+  min_ms: number;
+  p50_ms: number;
+  p90_ms: number;
+  p95_ms: number;
+  p99_ms: number;
+  max_ms: number;
+}
+
+export interface FindingHistoryRecord {
+  device_mac: string;
+  kind: "vulnerability" | "port_warning";
+  /** CVE id or port number */
+  key: string;
+  severity: string;
+  description: string;
+  first_seen: string;
+  last_seen: string;
+  scan_count: number;
+  /** First later scan without the finding; absent while still open */
+  remediated_at?: string;
+}
+
+export interface TopologyEdgeRecord {
+  local_device_mac: string;
+  local_port: string;
+  /** Neighbor's advertised system name */
+  remote_device: string;
+  remote_port: string;
+  remote_ip?: string;
+  /** Known device last seen at remote_ip */
+  remote_device_mac?: string;
+  /** "lldp" */
+  source: string;
+  first_seen: string;
+  last_seen: string;
+  /** Reported by the local device's most recent scan */
+  is_current: boolean;
+}
+
+export interface TagCount {
+  tag: string;
+  device_count: number;
+}
+
+export interface AttributeCount {
+  name: string;
+  device_count: number;
+}
+
+/** Evidence that linked a MAC address to a device */
+export type CorrelationSignal =
+  | "hostname"
+  | "mdns_identity"
+  | "dhcp_fingerprint"
+  | "port_profile"
+  | "manual";
+
+/** A MAC address merged into a canonical device record */
+export interface DeviceMerge {
+  id: number;
+  device_id: number;
+  mac: string;
+  /** Fingerprint score at merge time (0 for manual merges) */
+  score: number;
+  signals: CorrelationSignal[];
+  merged_at: string;
+}
+
+export interface DeviceServiceRecord {
+  id: number;
+  device_mac: string;
+  service_type: string;
+  instance_name: string;
+  port?: number;
+  first_seen: string;
+  last_seen: string;
+}
+
+export interface PortDeviation {
+  unexpected: number[];
+  missing: number[];
+}
+
+export interface DirectoryComputer {
+  name: string;
+  dns_hostname?: string;
+  operating_system?: string;
+  os_version?: string;
+  ou?: string;
+  last_logon?: string;
+  distinguished_name: string;
+  enabled: boolean;
+}
+
+export interface DeviceDetails extends DeviceRecord {
+  services: DeviceServiceRecord[];
+  open_ports: number[];
+  expected_ports: number[] | null;
+  port_deviation: PortDeviation | null;
+  /** Set for managed (Active Directory joined) devices */
+  directory: DirectoryComputer | null;
+}
+
+export interface OuiUpdateSummary {
+  entries: number;
+  source: string;
+  updated_at: string;
+}
+
+/** Where vendor data comes from; prefixes it lacks use the bundled table */
+export type VendorSourceSettings =
+  | { kind: "embedded" }
+  | { kind: "ieee"; url: string }
+  | { kind: "csv"; path: string };
+
+export interface ExportSignature {
+  algorithm: string;
+  public_key: string;
+  signature: string;
+  sha256: string;
+  signed_at: string;
+}
+
+export interface DevicePurgePreview {
+  mac: string;
+  history_rows: number;
+  alerts: number;
+  sni_observations: number;
+  services: number;
+  has_settings: boolean;
+  confirmation_token: string;
+}
+
+export interface DevicePurgeSummary {
+  mac: string;
+  history_rows: number;
+  alerts: number;
+  sni_observations: number;
+  services: number;
+  settings: number;
+}
+
+export interface VendorOverrideRecord {
+  pattern: string;
+  vendor: string;
+  is_exact_mac: boolean;
+  created_at: string;
+}
+
+export interface DeviceTypeRule {
+  name: string;
+  vendor_pattern?: string;
+  hostname_pattern?: string;
+  ports: number[];
+  service?: string;
+  device_type: string;
+  confidence: number;
+  enabled: boolean;
+}
+
+export interface DeviceTypeRuleRecord extends DeviceTypeRule {
+  id: number;
+  created_at: string;
+}
+
+export type RiskProfilePreset = "default" | "homelab" | "corporate";
+
+export interface RiskProfile {
+  name: string;
+  /** Keyed by device type, e.g. "IOT_DEVICE" */
+  device_type_scores: Record<string, number>;
+  /** Keyed by port number */
+  port_weights: Record<string, number>;
+  default_port_weight: number;
+  randomized_mac_penalty: number;
+  blacklisted_ports: number[];
+  blacklist_score: number;
+}
+
+export interface LdapConfig {
+  url: string;
+  bind_dn: string;
+  /** Empty when read back; leave empty on save to keep the stored password */
+  bind_password: string;
+  base_dn: string;
+  starttls: boolean;
+}
+
+export interface LdapEnrichmentSummary {
+  queried: number;
+  matched: number;
+  unmatched: number;
+}
+
+export interface InterfaceCandidate {
+  name: string;
+  ip: string;
+  mac: string;
+  prefix_len: number;
+  score: number;
+  is_default: boolean;
+}
+
+export interface TrustedPeer {
+  instance_id: string;
+  public_key: string;
+}
+
+export interface PeerSettings {
+  enabled: boolean;
+  instance_name?: string | null;
+  port: number;
+  distributed_scans: boolean;
+  trusted_peers: TrustedPeer[];
+}
+
+export interface PeerInfo {
+  instance_id: string;
+  instance_name: string;
+  hostname: string;
+  ip: string;
+  port: number;
+  version: string;
+  public_key?: string | null;
+  subnet?: string | null;
+  monitoring: boolean;
+  last_seen: string;
 }
 
 export interface AlertRecord {
   id: number;
   created_at: string;
   alert_type: string;
+  /** Type as stored; differs from `alert_type` ("custom") when this build does not know it */
+  alert_type_raw: string;
   device_id?: number;
   device_mac?: string;
   device_ip?: string;
   message: string;
   severity: string;
   is_read: boolean;
+  /** Supporting data recorded with the alert (e.g. the addresses of a cloned MAC) */
+  evidence?: Record<string, unknown>;
+  /** Network profile the alert was raised on */
+  network_id?: number | null;
+  /** Times the alert was raised while unread, counting the first */
+  occurrences: number;
+  /** When it was last raised again; null if it has not repeated */
+  last_seen_at: string | null;
+}
+
+/** A user-defined alert rule as created or edited */
+export interface AlertRuleSpec {
+  name: string;
+  /** Monitor event kinds, scan alert types ("HIGH_RISK") or "ScanHost"; empty matches all */
+  event_types?: string[];
+  /** Device filter expression, e.g. "tag = camera" */
+  device_filter?: string | null;
+  /** Minimum measure of the event (risk score, latency, packet loss, ...) */
+  threshold?: number | null;
+  severity: "Low" | "Medium" | "High" | "Critical";
+  /** Notification channels told about a match */
+  notify?: string[];
+  /** Minutes a repeat of an unread match is counted on it (60 when null) */
+  cooldown_minutes?: number | null;
+  enabled?: boolean;
+}
+
+/** A webhook alerts are posted to */
+export interface WebhookSettings {
+  /** Channel name, as listed in alert rules */
+  name: string;
+  url: string;
+  /** JSON body with {{field}} placeholders (alert_id, alert_type, severity, message, device_mac, device_ip, created_at); the alert as JSON if null */
+  template?: string | null;
+  /** Attempts after a failed delivery */
+  retries?: number;
+  enabled?: boolean;
+}
+
+/** An SMTP account alerts are mailed from */
+export interface EmailSettings {
+  /** Channel name, as listed in alert rules */
+  name: string;
+  host: string;
+  /** Defaults to 587 */
+  port?: number;
+  /** Upgrade the connection with STARTTLS (default true) */
+  starttls?: boolean;
+  username?: string | null;
+  /** New password to save; never returned */
+  password?: string | null;
+  /** Saved password, encrypted with the machine key */
+  encrypted_password?: string | null;
+  from: string;
+  to: string[];
+  /** Least severe alert mailed as it happens (default Critical) */
+  min_severity?: "Low" | "Medium" | "High" | "Critical";
+  /** Alert types mailed, e.g. HIGH_RISK; empty for all */
+  alert_types?: string[];
+  /** Also mail a summary of the last day's alerts */
+  daily_digest?: boolean;
+  /** Local hour the digest is sent (default 8) */
+  digest_hour?: number;
+  enabled?: boolean;
+}
+
+/** A Slack, Discord or Teams incoming webhook */
+export interface ChatSettings {
+  /** Channel name, as listed in alert rules */
+  name: string;
+  platform: "slack" | "discord" | "teams";
+  url: string;
+  /** Attempts after a failed delivery */
+  retries?: number;
+  enabled?: boolean;
+}
+
+/** An MQTT broker for device state, alerts and Home Assistant discovery */
+export interface MqttSettings {
+  /** Channel name, as listed in alert rules (default "mqtt") */
+  name?: string;
+  host: string;
+  /** Defaults to 1883 */
+  port?: number;
+  client_id?: string;
+  username?: string | null;
+  /** New password to save; never returned */
+  password?: string | null;
+  /** Saved password, encrypted with the machine key */
+  encrypted_password?: string | null;
+  /** Prefix of every topic published (default "netmapper") */
+  topic_prefix?: string;
+  /** Publish device online/offline state while monitoring (default true) */
+  publish_devices?: boolean;
+  /** Publish Home Assistant discovery configs */
+  home_assistant?: boolean;
+  /** Defaults to "homeassistant" */
+  discovery_prefix?: string;
+  enabled?: boolean;
+}
+
+/** A syslog collector (RFC 5424) for alerts and monitor events */
+export interface SyslogSettings {
+  /** Channel name, as listed in alert rules (default "syslog") */
+  name?: string;
+  host: string;
+  /** 514, or 6514 for TLS, if unset */
+  port?: number | null;
+  transport?: "udp" | "tcp" | "tls";
+  /** Facility code, 16-23 for local0-local7 (default 16) */
+  facility?: number;
+  app_name?: string;
+  /** Also forward monitor events while monitoring (default true) */
+  forward_events?: boolean;
+  enabled?: boolean;
+}
+
+/** Which monitor events raise an OS notification */
+export interface DesktopSettings {
+  enabled: boolean;
+  /** Severities that notify (default Medium, High and Critical) */
+  severities: Array<"Low" | "Medium" | "High" | "Critical">;
+}
+
+/** A desktop notification shown for a monitor event */
+export interface DesktopNotice {
+  title: string;
+  body: string;
+  severity: "Low" | "Medium" | "High" | "Critical";
+  /** Event kind, e.g. NewDeviceDiscovered */
+  kind: string;
+  /** Device opened when the notification is clicked */
+  device_mac: string | null;
+  device_ip: string | null;
+}
+
+/** Where alerts are sent */
+export interface NotificationSettings {
+  /** Least severe alert sent to every channel (rules naming channels go to those only) */
+  min_severity: "Low" | "Medium" | "High" | "Critical";
+  webhooks: WebhookSettings[];
+  emails?: EmailSettings[];
+  chats?: ChatSettings[];
+  mqtt?: MqttSettings | null;
+  syslog?: SyslogSettings | null;
+  desktop?: DesktopSettings;
+}
+
+/** A stored alert rule */
+export interface AlertRule extends Required<AlertRuleSpec> {
+  id: number;
+  created_at: string;
+  updated_at: string;
 }
 
 export interface NetworkStats {
@@ -94,9 +849,30 @@ export interface NetworkStats {
   new_devices_24h: number;
   high_risk_devices: number;
   total_scans: number;
+  duplicate_scans: number;
   last_scan_time?: string;
 }
 
+/** Health-score finding whose risk can be accepted for a device */
+export type RiskFinding =
+  | "risk_score"
+  | "randomized_mac"
+  | "unknown_type"
+  | "unresponsive";
+
+/** Accepted findings stop affecting the health score until they expire */
+export interface RiskAcceptance {
+  id: number;
+  device_mac: string;
+  finding: RiskFinding;
+  reason: string;
+  accepted_by: string | null;
+  accepted_at: string;
+  expires_at: string;
+  /** Whether the expiry review alert was raised */
+  review_alerted: boolean;
+}
+
 export interface NetworkHealth {
   score: number;
   grade: string;
@@ -109,19 +885,314 @@ export interface NetworkHealth {
   insights: string[];
 }
 
+export type PlannedChange =
+  | { kind: "block_port"; port: number }
+  | { kind: "block_device_port"; mac: string; port: number }
+  | { kind: "remove_device"; mac: string }
+  /** Move every device of a type (e.g. "IOT_DEVICE") onto an isolated segment */
+  | { kind: "segment_device_type"; device_type: string };
+
+export interface DeviceRiskChange {
+  mac: string;
+  ip: string;
+  before: number;
+  /** null if the device is removed */
+  after: number | null;
+}
+
+export interface SimulationResult {
+  before: NetworkHealth;
+  after: NetworkHealth;
+  /** Health score difference (positive is an improvement) */
+  score_change: number;
+  devices: DeviceRiskChange[];
+  /** Changes that affect no device */
+  unmatched: PlannedChange[];
+}
+
+export interface MonitorInterface {
+  name: string;
+  ip: string;
+  /** Negotiated link speed in Mbit/s, when the OS reports it */
+  link_speed_mbps?: number;
+  /** Wi-Fi network, when the interface is wireless */
+  ssid?: string;
+}
+
 export interface MonitoringStatus {
   is_running: boolean;
+  /** Running but not scanning until resumed */
+  is_paused: boolean;
   interval_seconds: number;
+  /** Scheduled profile in use, when the office-hours schedule is enabled */
+  active_profile?: string;
   scan_count: number;
   last_scan_time?: string;
   devices_online: number;
   devices_total: number;
+  /** Interface scanned by the last completed cycle */
+  interface?: MonitorInterface;
+  /** ARP requests and TCP connection attempts sent by the last completed cycle */
+  last_cycle_packets_sent?: number;
+  last_cycle_duration_ms?: number;
+  /** Devices currently considered online, by address */
+  devices?: DeviceSnapshot[];
+}
+
+/** Monitor health metrics of the current (or last) session */
+export interface MonitoringStats {
+  is_running: boolean;
+  /** When the session started; null if the monitor never ran */
+  started_at: string | null;
+  /** Time since the session started (or until it stopped) */
+  uptime_secs: number;
+  scans_completed: number;
+  scans_failed: number;
+  /** Failed scans since the last one that succeeded */
+  consecutive_failures: number;
+  avg_scan_duration_ms: number | null;
+  last_scan_duration_ms: number | null;
+  /** Times the watchdog restarted the scan loop */
+  restarts: number;
+  /** Events emitted, scan progress aside */
+  events_total: number;
+  /** Events per hour of uptime, by kind */
+  events_per_hour: Record<string, number>;
+}
+
+/** A device as the monitor last saw it */
+export interface DeviceSnapshot {
+  mac: string;
+  ip: string;
+  hostname?: string;
+  device_type: string;
+  vendor?: string;
+  /** 0-100, from the device type and open ports as in an on-demand scan */
+  risk_score: number;
+  is_online: boolean;
+  /** Ping round-trip time, when the device answered ICMP */
+  latency_ms?: number;
+  /** Open ports found by the last full scan */
+  open_ports: number[];
+  /** Subnet or range of the sweep that found the device */
+  network?: string;
+}
+
+export interface MonitorProfile {
+  name: string;
+  interval_seconds: number;
+  /** Full scan every this many cycles; the others only ping known devices */
+  full_scan_every?: number;
+}
+
+export type Weekday = "Mon" | "Tue" | "Wed" | "Thu" | "Fri" | "Sat" | "Sun";
+
+/** Weekly schedule switching between two monitoring profiles */
+/** Hours without active scanning; passive discovery keeps running */
+export interface QuietHours {
+  enabled: boolean;
+  /** Days on which quiet hours start */
+  days: Weekday[];
+  /** Local time, "HH:MM" */
+  start: string;
+  /** Local time, "HH:MM"; before `start` runs past midnight */
+  end: string;
+}
+
+export interface MonitorSchedule {
+  enabled: boolean;
+  /** Days on which office hours start */
+  days: Weekday[];
+  /** Local time, "HH:MM" */
+  start: string;
+  /** Local time, "HH:MM"; before `start` runs past midnight */
+  end: string;
+  office_hours: MonitorProfile;
+  off_hours: MonitorProfile;
+  /** Applies whether or not profile switching is enabled */
+  quiet_hours?: QuietHours;
+}
+
+export interface ArpThresholds {
+  storm_request_threshold: number;
+  storm_window_secs: number;
+  binding_ttl_secs: number;
+  detect_takeover: boolean;
+  /** Report a MAC alternating between two IPs (two hosts sharing it) */
+  detect_shared_mac?: boolean;
+}
+
+export interface SmartGroupRecord {
+  id: number;
+  name: string;
+  expression: string;
+  description: string | null;
+  created_at: string;
+  updated_at: string;
+}
+
+export type OnlineSchedule =
+  | { mode: "always" }
+  | {
+      mode: "hours";
+      /** ISO weekdays (1 = Monday); empty for every day */
+      days: number[];
+      /** Local hours; a window ending before it starts runs past midnight */
+      start_hour: number;
+      end_hour: number;
+    };
+
+export interface GroupPolicy {
+  /** Members raise no alerts and are left out of insights */
+  ignored: boolean;
+  expected_online: OnlineSchedule | null;
+  /** Ports members may have open */
+  allowed_ports: number[] | null;
+  /** Severity per alert type, e.g. { DEVICE_OFFLINE: "High" } */
+  severity_overrides: Record<string, "Low" | "Medium" | "High" | "Critical">;
+}
+
+export interface DeviceGroupRecord {
+  id: number;
+  name: string;
+  description: string | null;
+  policy: GroupPolicy;
+  /** MAC addresses of member devices */
+  members: string[];
+  created_at: string;
+  updated_at: string;
+}
+
+export interface DeviceSettings {
+  device_mac: string;
+  latency_threshold_ms: number | null;
+  offline_grace_secs: number | null;
+  offline_alerts_enabled: boolean;
+  risk_threshold: number | null;
+  risk_alerts_enabled: boolean;
+  expected_ports: number[] | null;
+  /** Checked every few seconds between full scans by the monitor */
+  watched?: boolean;
+  /** On the known-device allowlist */
+  trusted?: boolean;
+  /** No alerts for this device until then (ISO 8601) */
+  muted_until?: string | null;
+}
+
+/** Alerting on devices missing from the known-device allowlist */
+export interface UntrustedDeviceAlerts {
+  enabled: boolean;
+  severity: "High" | "Critical";
+  /** Send the alerts to notification channels too */
+  notify: boolean;
+}
+
+/** TLS certificate a device serves on one port */
+export interface StoredCertificate {
+  mac: string;
+  ip: string;
+  port: number;
+  subject: string;
+  issuer: string;
+  not_before: string;
+  not_after: string;
+  /** SHA-256 of the DER encoding, hex */
+  fingerprint: string;
+  /** When this certificate was first served on the port */
+  first_seen: string;
+  last_seen: string;
+}
+
+/** SSH host key a device presents on one port */
+export interface StoredSshHostKey {
+  mac: string;
+  ip: string;
+  port: number;
+  /** Identification string, e.g. "SSH-2.0-OpenSSH_9.6" */
+  banner: string | null;
+  key_type: string;
+  /** OpenSSH-style fingerprint, e.g. "SHA256:..." */
+  fingerprint: string;
+  /** When this key was first presented on the port */
+  first_seen: string;
+  last_seen: string;
+}
+
+export interface CloudService {
+  server_name: string;
+  provider: string | null;
+  hit_count: number;
+  last_seen: string;
+  /** Country / ASN of the last server address, if GeoIP databases are configured */
+  geo?: GeoInfo | null;
+}
+
+export interface DeviceCloudServices {
+  mac: string;
+  ip: string | null;
+  display_name: string | null;
+  device_type: string | null;
+  is_iot: boolean;
+  providers: string[];
+  services: CloudService[];
+}
+
+export interface CloudServiceInventory {
+  devices: DeviceCloudServices[];
+  provider_device_counts: Record<string, number>;
+}
+
+/** Device that answered only ARP over several consecutive scans */
+export interface StealthHost {
+  mac: string;
+  ip: string | null;
+  display_name: string | null;
+  vendor: string | null;
+  arp_only_scans: number;
+  last_seen: string;
+}
+
+/** Device and open ports frozen in the network baseline */
+export interface BaselineDevice {
+  mac: string;
+  ip: string | null;
+  hostname: string | null;
+  vendor: string | null;
+  device_type: string | null;
+  open_ports: number[];
+}
+
+export interface NetworkBaseline {
+  captured_at: string;
+  devices: BaselineDevice[];
+}
+
+/** One way the network differs from its baseline */
+export type BaselineDeviation =
+  | { kind: "UnknownDevice"; mac: string; ip: string; hostname: string | null }
+  | {
+      kind: "MissingDevice";
+      mac: string;
+      ip: string | null;
+      hostname: string | null;
+    }
+  | { kind: "UnexpectedPort"; mac: string; ip: string; port: number };
+
+export interface ComplianceReport {
+  baseline_taken_at: string;
+  baseline_devices: number;
+  /** Baseline devices present with no unexpected ports */
+  compliant_devices: number;
+  deviations: BaselineDeviation[];
+  compliance_percent: number;
 }
 
 export type NetworkEventType =
   | { type: "MonitoringStarted"; data: { interval_seconds: number } }
   | { type: "MonitoringStopped" }
-  | { type: "ScanStarted"; data: { scan_number: number } }
+  | { type: "MonitoringPaused" }
+  | { type: "MonitoringResumed" }
+  | { type: "ScanStarted"; data: { scan_number: number; light?: boolean } }
   | {
       type: "ScanProgress";
       data: { phase: string; percent: number; message: string };
@@ -132,22 +1203,185 @@ export type NetworkEventType =
     }
   | {
       type: "NewDeviceDiscovered";
-      data: { ip: string; mac: string; hostname?: string; device_type: string };
+      data: {
+        ip: string;
+        mac: string;
+        hostname?: string;
+        device_type: string;
+        vendor?: string;
+        /** 0-100, as in an on-demand scan */
+        risk_score?: number;
+        network?: string;
+      };
     }
   | {
       type: "DeviceWentOffline";
-      data: { mac: string; last_ip: string; hostname?: string };
+      data: {
+        mac: string;
+        last_ip: string;
+        hostname?: string;
+        network?: string;
+      };
     }
   | {
       type: "DeviceCameOnline";
-      data: { mac: string; ip: string; hostname?: string };
+      data: { mac: string; ip: string; hostname?: string; network?: string };
+    }
+  | {
+      type: "DeviceFlapping";
+      data: { mac: string; ip: string; changes: number; window_secs: number };
     }
   | {
       type: "DeviceIpChanged";
-      data: { mac: string; old_ip: string; new_ip: string };
+      data: { mac: string; old_ip: string; new_ip: string; network?: string };
+    }
+  | {
+      type: "ArpAddressTakeover";
+      data: {
+        ip: string;
+        previous_mac: string;
+        new_mac: string;
+        gratuitous: boolean;
+      };
+    }
+  | {
+      type: "IpConflict";
+      data: { ip: string; macs: string[] };
+    }
+  | {
+      type: "SharedMacDetected";
+      data: {
+        mac: string;
+        ips: string[];
+      };
+    }
+  | {
+      type: "ArpStormDetected";
+      data: {
+        mac: string;
+        ip: string;
+        request_count: number;
+        window_secs: number;
+      };
+    }
+  | {
+      type: "LatencyDegraded";
+      data: { mac: string; baseline_ms: number; current_ms: number };
+    }
+  | {
+      type: "ProfileChanged";
+      data: { profile: string; interval_seconds: number };
+    }
+  | {
+      type: "InternetDegraded";
+      data: {
+        loss_percent: number;
+        latency_ms: number | null;
+        dns_ms: number | null;
+        reason: string;
+      };
+    }
+  | { type: "InternetDown"; data: { reason: string } }
+  | { type: "QuietHoursChanged"; data: { active: boolean } }
+  | {
+      type: "UnknownDevicePresent";
+      data: { mac: string; ip: string; hostname: string | null };
+    }
+  | {
+      type: "ExpectedDeviceMissing";
+      data: { mac: string; ip: string | null; hostname: string | null };
+    }
+  | {
+      type: "UnexpectedPortOpen";
+      data: { mac: string; ip: string; port: number };
     }
   | { type: "MonitoringError"; data: { message: string } };
 
+/** What the monitor's internet health check pings and when it complains */
+export interface InternetCheckSettings {
+  enabled: boolean;
+  /** Gateway address; the router found by the last scan when unset */
+  gateway: string | null;
+  /** External IPv4 addresses to ping */
+  anchors: string[];
+  /** Name resolved to time DNS */
+  dns_name: string;
+  max_loss_percent: number;
+  max_latency_ms: number;
+  max_dns_ms: number;
+}
+
+export type InternetStatus = "up" | "degraded" | "down";
+
+/** Ping results for one address */
+export interface TargetStats {
+  target: string;
+  sent: number;
+  received: number;
+  avg_ms: number | null;
+}
+
+/** One internet health check */
+export interface InternetHealth {
+  checked_at: string;
+  status: InternetStatus;
+  /** What made the connection degraded or down */
+  reason: string | null;
+  gateway: TargetStats | null;
+  anchors: TargetStats[];
+  /** Packet loss across all anchors */
+  loss_percent: number;
+  latency_ms: number | null;
+  /** DNS resolution time, null if it failed */
+  dns_ms: number | null;
+}
+
+/** A stretch of time a device was on the network */
+export interface PresenceSession {
+  id: number;
+  mac: string;
+  /** Last address seen during the session */
+  ip: string;
+  arrived_at: string;
+  last_seen_at: string;
+  /** When the device was last seen before leaving; null while present */
+  left_at: string | null;
+  /** From arrival until the device left (or was last seen, while present) */
+  duration_secs: number;
+}
+
+/** A scheduled full scan as created or edited */
+export interface ScanScheduleSpec {
+  name: string;
+  /** Five-field cron expression in local time, e.g. "0 3 * * *" */
+  cron: string;
+  /** Interface to scan; the default one when unset */
+  interface?: string | null;
+  /** Write a PDF report to the reports folder after each run */
+  generate_report?: boolean;
+  enabled?: boolean;
+}
+
+/** A stored scheduled scan */
+export interface ScanSchedule extends Required<ScanScheduleSpec> {
+  id: number;
+  last_run: string | null;
+  /** Why the last run failed; null if it succeeded */
+  last_error: string | null;
+  /** Null while disabled */
+  next_run: string | null;
+  created_at: string;
+  updated_at: string;
+}
+
+/** A monitor event as stored, for the timeline and replay */
+export interface StoredEvent {
+  /** Increases with every event; the replay cursor */
+  id: number;
+  created_at: string;
+  event: NetworkEventType;
+}
+
 export interface PingResult {
   success: boolean;
   latency_ms: number | null;
@@ -162,6 +1396,17 @@ export interface PortScanResult {
   service: string | null;
 }
 
+export interface ToolRunRecord {
+  id: number;
+  /** "ping" or "port_scan" */
+  tool: string;
+  target: string;
+  parameters: Record<string, unknown>;
+  results: unknown;
+  duration_ms?: number;
+  created_at: string;
+}
+
 export interface VendorLookupResult {
   mac: string;
   vendor: string | null;
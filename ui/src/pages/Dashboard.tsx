@@ -171,6 +171,8 @@ function eventLabel(event: NetworkEventType): string {
       return `Device online ${event.data.hostname || event.data.ip}`;
     case "DeviceIpChanged":
       return `IP changed ${event.data.old_ip} -> ${event.data.new_ip}`;
+    case "ProfileChanged":
+      return `Switched to ${event.data.profile} profile`;
     case "MonitoringError":
       return `Error: ${event.data.message}`;
     default:
@@ -111,6 +111,7 @@ export default function Tools() {
 function PingTool() {
   const [target, setTarget] = useState('');
   const [count, setCount] = useState(4);
+  const [save, setSave] = useState(false);
   const [loading, setLoading] = useState(false);
   const [results, setResults] = useState<PingResult[]>([]);
   const [terminalOutput, setTerminalOutput] = useState<string[]>([]);
@@ -123,7 +124,7 @@ function PingTool() {
     setTerminalOutput(['$ ping ' + target.trim(), 'Sending ' + count + ' packets...', '']);
 
     try {
-      const pingResults = await tauriClient.pingHost(target.trim(), count);
+      const pingResults = await tauriClient.pingHost(target.trim(), count, save);
       setResults(pingResults);
       
       const output = pingResults.map((r) => 
@@ -179,6 +180,16 @@ function PingTool() {
             </select>
           </div>
 
+          <label className="flex items-center gap-2 text-xs text-text-secondary">
+            <input
+              type="checkbox"
+              checked={save}
+              onChange={(e) => setSave(e.target.checked)}
+              className="accent-accent-blue"
+            />
+            Save to history
+          </label>
+
           <button
             onClick={handlePing}
             disabled={loading || !target.trim()}
@@ -244,6 +255,7 @@ function PortScanTool() {
   const [target, setTarget] = useState('');
   const [startPort, setStartPort] = useState('1');
   const [endPort, setEndPort] = useState('1000');
+  const [save, setSave] = useState(false);
   const [loading, setLoading] = useState(false);
   const [results, setResults] = useState<PortScanResult[]>([]);
 
@@ -258,7 +270,7 @@ function PortScanTool() {
 
     try {
       const ports = Array.from({ length: Math.min(end - start + 1, 100) }, (_, i) => start + i);
-      const scanResults = await tauriClient.scanPorts(target.trim(), ports);
+      const scanResults = await tauriClient.scanPorts(target.trim(), ports, save);
       setResults(scanResults);
     } catch (error) {
       console.error('Scan failed:', error);
@@ -310,6 +322,16 @@ function PortScanTool() {
             </div>
           </div>
 
+          <label className="flex items-center gap-2 text-xs text-text-secondary">
+            <input
+              type="checkbox"
+              checked={save}
+              onChange={(e) => setSave(e.target.checked)}
+              className="accent-accent-blue"
+            />
+            Save to history
+          </label>
+
           <button
             onClick={handleScan}
             disabled={loading || !target.trim()}